@@ -0,0 +1,1481 @@
+use crate::{engine::EngineKind, hook::HookKind};
+
+/// A management subcommand given instead of running a rewrite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `profile add <profile> <name> <email>`: save an identity profile.
+    ProfileAdd {
+        /// The profile name.
+        profile: String,
+        /// The author name stored in the profile.
+        name: String,
+        /// The author email stored in the profile.
+        email: String,
+    },
+    /// `profile list`: print the saved profiles.
+    ProfileList,
+    /// `profile remove <profile>`: delete a saved profile.
+    ProfileRemove {
+        /// The profile name.
+        profile: String,
+    },
+    /// `verify [--name <name>] [--email <email>] [--revs <expr>]`: check that
+    /// every commit carries the expected identity.
+    Verify {
+        /// The expected name, if checked.
+        name: Option<String>,
+        /// The expected email, if checked.
+        email: Option<String>,
+        /// A `git rev-list` expression limiting the commits checked.
+        revs: Option<String>,
+    },
+    /// `check [--policy <file>] [--revs <expr> | --identity <ident>...]`:
+    /// check every commit, or the given identities, against an author policy
+    /// file.
+    Check {
+        /// The policy file; `.author-policy` at the top of the working tree
+        /// if not given.
+        policy: Option<String>,
+        /// A `git rev-list` expression limiting the commits checked.
+        revs: Option<String>,
+        /// Identities (`Name <email>`, as printed by `git var`) to check
+        /// instead of history.
+        identities: Vec<String>,
+    },
+    /// `install-hook [--hook pre-push|commit-msg] [--policy <file>] [--force]`:
+    /// write a hook that runs `check` on every push or commit.
+    InstallHook {
+        /// Which hook to write.
+        hook: HookKind,
+        /// The policy file the hook checks against, if not the default.
+        policy: Option<String>,
+        /// Replace an existing hook not written by this tool.
+        force: bool,
+    },
+    /// `selftest`: rewrite a scratch repository to check the environment.
+    Selftest,
+    /// `bench [--engine <name>]...`: time a rewrite of temporary clones with
+    /// each engine.
+    Bench {
+        /// The engines to compare; every engine if empty.
+        engines: Vec<EngineKind>,
+    },
+    /// `restore --from-bundle <path>`: point the refs saved in a
+    /// `--backup-bundle` file back at their original commits.
+    Restore {
+        /// The bundle file.
+        bundle: String,
+    },
+}
+
+/// Options parsed from the command line.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// `-h` / `--help`: print usage and exit.
+    pub help: bool,
+    /// `-V` / `--version`: print the version and exit.
+    pub version: bool,
+    /// `--manual`: let the user edit the rebase todo list.
+    pub manual: bool,
+    /// `--editor <cmd>`: the editor `--manual` opens the todo list in.
+    pub editor: Option<String>,
+    /// `--show-authors`: append each commit's author to its line of the `--manual` todo list.
+    pub show_authors: bool,
+    /// `--ask-each`: pick the new author of every marked commit from a menu.
+    pub ask_each: bool,
+    /// `--pick-branch`: choose the branch to rewrite from a menu of the local
+    /// branches instead of rewriting the checked-out one.
+    pub pick_branch: bool,
+    /// `--tui`: follow the rebase loop on a full-screen dashboard.
+    pub tui: bool,
+    /// `--show-commands`: print every `git` command line before running it.
+    pub show_commands: bool,
+    /// `-q` / `--quiet`: print nothing on success but errors, warnings and
+    /// prompts (no banner, checklist or per-commit progress).
+    pub quiet: bool,
+    /// `--force-pushed-history`: allow rewriting commits that exist on a remote.
+    pub force_pushed_history: bool,
+    /// `--allow-protected`: allow rewriting a protected branch (the remote's
+    /// default branch, or one matching `author-rewrite.protected`).
+    pub allow_protected: bool,
+    /// `--force`: skip the typed confirmation that large rewrites and
+    /// rewrites of pushed commits otherwise ask for.
+    pub force: bool,
+    /// `--revs <expr>`: a `git rev-list` expression selecting the commits to rewrite.
+    pub revs: Option<String>,
+    /// `--commits-from <file|->`: a file (or stdin) listing the commits to rewrite.
+    pub commits_from: Option<String>,
+    /// `--exclude <sha>`: leave this commit as is even when it is selected.
+    /// May be given more than once.
+    pub exclude: Vec<String>,
+    /// `--exclude-file <file|->`: a file (or stdin) listing commits to leave as is.
+    pub exclude_file: Option<String>,
+    /// `--sync-committer`: set each rewritten commit's committer to its author.
+    pub sync_committer: bool,
+    /// `--skip-merges`: never rewrite merge commits.
+    pub skip_merges: bool,
+    /// `--all-branches`: rewrite every local branch in one pass, so commits
+    /// they share get the same new SHAs on all of them.
+    pub all_branches: bool,
+    /// `--rewrite-domain <old=new>`: move emails from one domain to another.
+    pub rewrite_domain: Option<String>,
+    /// `--rules <file>`: map authors per commit with the rules in a file,
+    /// which may depend on the author date.
+    pub rules: Option<String>,
+    /// `--sha-map <csv>`: give the commits listed in a CSV file, by SHA, the
+    /// author named on their row.
+    pub sha_map: Option<String>,
+    /// `--name-only`: rewrite author names, keeping each commit's email.
+    pub name_only: bool,
+    /// `--email-only`: rewrite author emails, keeping each commit's name.
+    pub email_only: bool,
+    /// `--name <name>`: the new author name, instead of prompting for it.
+    pub name: Option<String>,
+    /// `--email <email>`: the new author email, instead of prompting for it.
+    pub email: Option<String>,
+    /// `-y` / `--yes`: answer confirmation prompts without asking.
+    pub yes: bool,
+    /// `--fsck`: run `git fsck` once the rewrite completes.
+    pub fsck: bool,
+    /// `--check-dco`: check that every rewritten commit is signed off by its author.
+    pub check_dco: bool,
+    /// `--check-github`: ask the GitHub API whether the new email belongs to
+    /// the token's account before rewriting.
+    pub check_github: bool,
+    /// `--cleanup`: expire reflogs and prune the pre-rewrite objects afterwards.
+    pub cleanup: bool,
+    /// `--backup-refs`: save the original branch tip under `refs/original/`.
+    pub backup_refs: bool,
+    /// `--drop-backup-refs`: delete every ref under `refs/original/` and exit.
+    pub drop_backup_refs: bool,
+    /// `--backup-bundle <path>`: save every ref to a bundle file before rewriting.
+    pub backup_bundle: Option<String>,
+    /// `--audit-log <path>`: append the audit record of the run to this file
+    /// instead of `.git/author-rewrite-audit.jsonl`.
+    pub audit_log: Option<String>,
+    /// `--report-html <path>`: write a standalone HTML report of the rewrite.
+    pub report_html: Option<String>,
+    /// `--replace-mode`: create `git replace` refs instead of rewriting history.
+    pub replace_mode: bool,
+    /// `--engine <name>`: the rewrite backend (see [`crate::engine::NAMES`]);
+    /// `--replace-mode` is short for `--engine replace`.
+    pub engine: Option<String>,
+    /// `--rewrite-tags`: also rewrite the tagger of matching annotated tags.
+    pub rewrite_tags: bool,
+    /// `--sign-tags`: re-sign the tags rewritten by `--rewrite-tags`.
+    pub sign_tags: bool,
+    /// `--repos-file <file|->`: run the rewrite in every repository listed in a file (or stdin).
+    pub repos_file: Option<String>,
+    /// `--discover <dir>`: find the repositories under a directory and pick which to rewrite.
+    pub discover: Option<String>,
+    /// `--include-vendored`: let `--discover` search `node_modules`, `target` and the like.
+    pub include_vendored: bool,
+    /// `--mirror <url>`: rewrite a fresh mirror clone of `url` and force-push it back.
+    pub mirror: Option<String>,
+    /// `-j` / `--jobs <n>`: rewrite up to `n` repositories of a batch at once.
+    pub jobs: Option<usize>,
+    /// `--message-replace <s/old/new/>`: sed-style substitutions applied to the
+    /// messages of rewritten commits, in order. May be given more than once.
+    pub message_replace: Vec<String>,
+    /// `--drop-author <email>`: drop the selected commits by this author instead
+    /// of rewriting them. May be given more than once.
+    pub drop_author: Vec<String>,
+    /// `--exec <cmd>`: run a shell command after each rewritten commit during
+    /// the rebase, overriding `author-rewrite.exec` in the git config. May be
+    /// given more than once.
+    pub exec: Vec<String>,
+    /// `--timeout <seconds>`: stop a git command (or the hook, credential
+    /// helper or pinentry it runs) that has not finished after this long.
+    pub timeout: Option<u64>,
+    /// `--skip-on-conflict`: drop commits that do not apply cleanly during the
+    /// rebase (`git rebase --skip`) and list them at the end.
+    pub skip_on_conflict: bool,
+    /// `--chunk-size <n>`: rebase at most `n` commits at a time, saving a
+    /// checkpoint after each chunk.
+    pub chunk_size: Option<usize>,
+    /// `--resume`: continue the chunked rewrite an earlier run left unfinished.
+    pub resume: bool,
+    /// `--annotate`: append provenance trailers to each rewritten commit's message.
+    pub annotate: bool,
+    /// `--record-original-sha`: append an `Original-Commit:` trailer with the
+    /// pre-rewrite SHA to each rewritten commit's message.
+    pub record_original_sha: bool,
+    /// `--no-verify`: skip the pre-commit and commit-msg hooks when amending.
+    pub no_verify: bool,
+    /// `--verify`: run the hooks for every amended commit, overriding
+    /// `author-rewrite.verify` in the git config.
+    pub verify: bool,
+    /// `--gpg-sign`: sign every rewritten commit, unlocking the key once up front.
+    pub gpg_sign: bool,
+    /// `--no-gpg-sign`: don't sign rewritten commits, whatever `commit.gpgsign` says.
+    pub no_gpg_sign: bool,
+    /// `--global`: also write the new identity to the global git config.
+    pub global: bool,
+    /// `--no-config-update`: leave every git config file untouched.
+    pub no_config_update: bool,
+    /// `--include-if <dir>`: save the identity for the repositories under
+    /// `dir` through an `includeIf` section instead of the local config.
+    pub include_if: Option<String>,
+    /// `--profile <name>`: rewrite to a saved identity profile instead of prompting.
+    pub profile: Option<String>,
+    /// `--spec <file|->`: a JSON job description filling in the other options.
+    pub spec: Option<String>,
+    /// A management subcommand such as `profile list`.
+    pub command: Option<Command>,
+}
+
+/// Returns the value for an option that requires one.
+///
+/// Accepts both `--flag value` and `--flag=value` forms. `inline` holds the
+/// part after `=` when present; otherwise the next argument is consumed.
+///
+/// # Parameters
+///
+/// * `name` – The option name, used in error messages.
+/// * `inline` – The value given after `=`, if any.
+/// * `rest` – Iterator over the remaining arguments.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the value.
+/// * `Err(String)` if no value was provided.
+fn take_value<'a, I>(name: &str, inline: Option<&str>, rest: &mut I) -> Result<String, String>
+where
+    I: Iterator<Item = &'a String>,
+{
+    match inline {
+        Some(v) => Ok(v.to_string()),
+        None => match rest.next() {
+            Some(v) => Ok(v.clone()),
+            None => Err(format!("option `{}` requires a value", name)),
+        },
+    }
+}
+
+/// Parses the arguments following `profile` into a [`Command`].
+///
+/// # Returns
+///
+/// * `Ok(Command)` on success.
+/// * `Err(String)` if the action is unknown or has the wrong number of arguments.
+fn parse_profile_command(args: &[&String]) -> Result<Command, String> {
+    let action = match args.first() {
+        Some(a) => a.as_str(),
+        None => {
+            return Err(String::from(
+                "`profile` requires an action: add, list or remove",
+            ))
+        }
+    };
+    let rest = &args[1..];
+
+    match (action, rest.len()) {
+        ("add", 3) => Ok(Command::ProfileAdd {
+            profile: rest[0].clone(),
+            name: rest[1].clone(),
+            email: rest[2].clone(),
+        }),
+        ("add", _) => Err(String::from("usage: profile add <PROFILE> <NAME> <EMAIL>")),
+        ("list", 0) => Ok(Command::ProfileList),
+        ("list", _) => Err(String::from("usage: profile list")),
+        ("remove", 1) => Ok(Command::ProfileRemove {
+            profile: rest[0].clone(),
+        }),
+        ("remove", _) => Err(String::from("usage: profile remove <PROFILE>")),
+        _ => Err(format!(
+            "unknown profile action `{}` (expected add, list or remove)",
+            action
+        )),
+    }
+}
+
+/// Parses the arguments following `verify` into a [`Command`].
+///
+/// # Returns
+///
+/// * `Ok(Command)` on success.
+/// * `Err(String)` for unknown options, or if neither `--name` nor `--email` is given.
+fn parse_verify_command(args: &[&String]) -> Result<Command, String> {
+    let mut name = None;
+    let mut email = None;
+    let mut revs = None;
+    let mut iter = args.iter().copied();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+        let slot = match flag {
+            "--name" => &mut name,
+            "--email" => &mut email,
+            "--revs" => &mut revs,
+            _ => return Err(format!("unknown verify argument `{}`", arg)),
+        };
+        match take_value(flag, inline, &mut iter) {
+            Ok(v) => {
+                *slot = Some(v);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if name.is_none() && email.is_none() {
+        return Err(String::from(
+            "usage: verify [--name <NAME>] [--email <EMAIL>] [--revs <EXPR>] (name or email required)",
+        ));
+    }
+
+    Ok(Command::Verify { name, email, revs })
+}
+
+/// Parses the arguments following `check` into a [`Command`].
+///
+/// # Returns
+///
+/// * `Ok(Command)` on success.
+/// * `Err(String)` for unknown arguments or missing values.
+fn parse_check_command(args: &[&String]) -> Result<Command, String> {
+    let usage = "usage: check [--policy <FILE>] [--revs <EXPR> | --identity <IDENT>...]";
+    let mut policy = None;
+    let mut revs = None;
+    let mut identities = Vec::new();
+    let mut iter = args.iter().copied();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+        if !matches!(flag, "--policy" | "--revs" | "--identity") {
+            return Err(format!("unknown check argument `{}` ({})", arg, usage));
+        }
+        let value = match take_value(flag, inline, &mut iter) {
+            Ok(v) => v,
+            Err(e) => return Err(e),
+        };
+        match flag {
+            "--policy" => {
+                policy = Some(value);
+            }
+            "--revs" => {
+                revs = Some(value);
+            }
+            _ => {
+                identities.push(value);
+            }
+        }
+    }
+
+    if revs.is_some() && !identities.is_empty() {
+        return Err(format!(
+            "check takes either --revs or --identity, not both ({})",
+            usage
+        ));
+    }
+    Ok(Command::Check {
+        policy,
+        revs,
+        identities,
+    })
+}
+
+/// Parses the arguments following `install-hook` into a [`Command`].
+///
+/// # Returns
+///
+/// * `Ok(Command)` on success.
+/// * `Err(String)` for unknown arguments or hooks.
+fn parse_install_hook_command(args: &[&String]) -> Result<Command, String> {
+    let usage = "usage: install-hook [--hook pre-push|commit-msg] [--policy <FILE>] [--force]";
+    let mut hook = HookKind::PrePush;
+    let mut policy = None;
+    let mut force = false;
+    let mut iter = args.iter().copied();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+        match flag {
+            "--force" => {
+                force = true;
+            }
+            "--hook" => match take_value(flag, inline, &mut iter) {
+                Ok(v) => match HookKind::parse(&v) {
+                    Ok(k) => {
+                        hook = k;
+                    }
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            },
+            "--policy" => match take_value(flag, inline, &mut iter) {
+                Ok(v) => {
+                    policy = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            _ => {
+                return Err(format!(
+                    "unknown install-hook argument `{}` ({})",
+                    arg, usage
+                ))
+            }
+        }
+    }
+
+    Ok(Command::InstallHook {
+        hook,
+        policy,
+        force,
+    })
+}
+
+/// Parses the arguments following `bench` into a [`Command`].
+///
+/// # Returns
+///
+/// * `Ok(Command)` on success.
+/// * `Err(String)` for unknown arguments or engines.
+fn parse_bench_command(args: &[&String]) -> Result<Command, String> {
+    let mut engines = Vec::new();
+    let mut iter = args.iter().copied();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+        if flag != "--engine" {
+            return Err(format!(
+                "unknown bench argument `{}` (usage: bench [--engine <NAME>]...)",
+                arg
+            ));
+        }
+        let name = match take_value(flag, inline, &mut iter) {
+            Ok(v) => v,
+            Err(e) => return Err(e),
+        };
+        match EngineKind::parse(&name) {
+            Ok(k) if !engines.contains(&k) => engines.push(k),
+            Ok(_) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(Command::Bench { engines })
+}
+
+/// Parses the arguments following `restore` into a [`Command`].
+///
+/// # Returns
+///
+/// * `Ok(Command)` on success.
+/// * `Err(String)` for unknown arguments, or if `--from-bundle` is missing.
+fn parse_restore_command(args: &[&String]) -> Result<Command, String> {
+    let usage = "usage: restore --from-bundle <PATH>";
+    let mut bundle = None;
+    let mut iter = args.iter().copied();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+        if flag != "--from-bundle" {
+            return Err(format!("unknown restore argument `{}` ({})", arg, usage));
+        }
+        match take_value(flag, inline, &mut iter) {
+            Ok(v) => {
+                bundle = Some(v);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    match bundle {
+        Some(bundle) => Ok(Command::Restore { bundle }),
+        None => Err(String::from(usage)),
+    }
+}
+
+/// Parses command-line arguments (excluding the program name) into [`Options`].
+///
+/// # Parameters
+///
+/// * `args` – The arguments following the program name.
+///
+/// # Returns
+///
+/// * `Ok(Options)` on success.
+/// * `Err(String)` describing an unknown option, a missing value, or an
+///   unexpected positional argument.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::args::parse;
+///
+/// let args = vec![String::from("--revs"), String::from("main..HEAD")];
+/// let opts = parse(&args).unwrap();
+/// assert_eq!(opts.revs.as_deref(), Some("main..HEAD"));
+/// ```
+pub fn parse(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options::default();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(v)),
+            _ => (arg.as_str(), None),
+        };
+
+        match name {
+            "-h" | "--help" => {
+                opts.help = true;
+            }
+            "-V" | "--version" => {
+                opts.version = true;
+            }
+            "--manual" => {
+                opts.manual = true;
+            }
+            "--force-pushed-history" => {
+                opts.force_pushed_history = true;
+            }
+            "--allow-protected" => {
+                opts.allow_protected = true;
+            }
+            "--force" => {
+                opts.force = true;
+            }
+            "--sync-committer" => {
+                opts.sync_committer = true;
+            }
+            "--skip-merges" => {
+                opts.skip_merges = true;
+            }
+            "--all-branches" => {
+                opts.all_branches = true;
+            }
+            "--name-only" => {
+                opts.name_only = true;
+            }
+            "--email-only" => {
+                opts.email_only = true;
+            }
+            "--global" => {
+                opts.global = true;
+            }
+            "--no-config-update" => {
+                opts.no_config_update = true;
+            }
+            "--fsck" => {
+                opts.fsck = true;
+            }
+            "--check-github" => {
+                opts.check_github = true;
+            }
+            "--check-dco" => {
+                opts.check_dco = true;
+            }
+            "--skip-on-conflict" => {
+                opts.skip_on_conflict = true;
+            }
+            "--resume" => {
+                opts.resume = true;
+            }
+            "--cleanup" => {
+                opts.cleanup = true;
+            }
+            "--backup-refs" => {
+                opts.backup_refs = true;
+            }
+            "--replace-mode" => {
+                opts.replace_mode = true;
+            }
+            "--rewrite-tags" => {
+                opts.rewrite_tags = true;
+            }
+            "--sign-tags" => {
+                opts.sign_tags = true;
+            }
+            "--annotate" => {
+                opts.annotate = true;
+            }
+            "--record-original-sha" => {
+                opts.record_original_sha = true;
+            }
+            "--no-verify" => {
+                opts.no_verify = true;
+            }
+            "--verify" => {
+                opts.verify = true;
+            }
+            "--gpg-sign" => {
+                opts.gpg_sign = true;
+            }
+            "--no-gpg-sign" => {
+                opts.no_gpg_sign = true;
+            }
+            "--show-authors" => {
+                opts.show_authors = true;
+            }
+            "--ask-each" => {
+                opts.ask_each = true;
+            }
+            "--pick-branch" => {
+                opts.pick_branch = true;
+            }
+            "--tui" => {
+                opts.tui = true;
+            }
+            "--show-commands" => {
+                opts.show_commands = true;
+            }
+            "-q" | "--quiet" => {
+                opts.quiet = true;
+            }
+            "--include-vendored" => {
+                opts.include_vendored = true;
+            }
+            "--drop-backup-refs" => {
+                opts.drop_backup_refs = true;
+            }
+            "--include-if" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.include_if = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--backup-bundle" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.backup_bundle = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--audit-log" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.audit_log = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--report-html" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.report_html = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "-y" | "--yes" => {
+                opts.yes = true;
+            }
+            "--name" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.name = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--email" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.email = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--revs" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.revs = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--commits-from" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.commits_from = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--exclude" => match take_value(name, inline, &mut iter) {
+                Ok(v) if v.trim().is_empty() => {
+                    return Err(String::from("option `--exclude` requires a commit"))
+                }
+                Ok(v) => {
+                    opts.exclude.push(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--exclude-file" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.exclude_file = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--rewrite-domain" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.rewrite_domain = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--rules" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.rules = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--sha-map" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.sha_map = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--spec" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.spec = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--drop-author" => match take_value(name, inline, &mut iter) {
+                Ok(v) if v.trim().is_empty() => {
+                    return Err(String::from("option `--drop-author` requires an email"))
+                }
+                Ok(v) => {
+                    opts.drop_author.push(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--engine" => match take_value(name, inline, &mut iter) {
+                Ok(v) => match EngineKind::parse(&v) {
+                    Ok(_) => {
+                        opts.engine = Some(v);
+                    }
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            },
+            "--exec" => match take_value(name, inline, &mut iter) {
+                Ok(v) if v.trim().is_empty() => {
+                    return Err(String::from("option `--exec` requires a command"))
+                }
+                Ok(v) => {
+                    opts.exec.push(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--editor" => match take_value(name, inline, &mut iter) {
+                Ok(v) if v.trim().is_empty() => {
+                    return Err(String::from("option `--editor` requires a command"))
+                }
+                Ok(v) => {
+                    opts.editor = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--mirror" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.mirror = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--discover" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.discover = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "-j" | "--jobs" => match take_value(name, inline, &mut iter) {
+                Ok(v) => match v.parse::<usize>() {
+                    Ok(n) if n > 0 => {
+                        opts.jobs = Some(n);
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{} expects a positive number of jobs, got `{}`",
+                            name, v
+                        ))
+                    }
+                },
+                Err(e) => return Err(e),
+            },
+            "--chunk-size" => match take_value(name, inline, &mut iter) {
+                Ok(v) => match v.parse::<usize>() {
+                    Ok(n) if n > 0 => {
+                        opts.chunk_size = Some(n);
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{} expects a positive number of commits, got `{}`",
+                            name, v
+                        ))
+                    }
+                },
+                Err(e) => return Err(e),
+            },
+            "--timeout" => match take_value(name, inline, &mut iter) {
+                Ok(v) => match v.parse::<u64>() {
+                    Ok(n) if n > 0 => {
+                        opts.timeout = Some(n);
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{} expects a positive number of seconds, got `{}`",
+                            name, v
+                        ))
+                    }
+                },
+                Err(e) => return Err(e),
+            },
+            "--message-replace" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.message_replace.push(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--repos-file" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.repos_file = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "--profile" => match take_value(name, inline, &mut iter) {
+                Ok(v) => {
+                    opts.profile = Some(v);
+                }
+                Err(e) => return Err(e),
+            },
+            "profile" => {
+                // Everything after the subcommand belongs to it.
+                let rest: Vec<&String> = iter.by_ref().collect();
+                match parse_profile_command(&rest) {
+                    Ok(c) => {
+                        opts.command = Some(c);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            "verify" => {
+                let rest: Vec<&String> = iter.by_ref().collect();
+                match parse_verify_command(&rest) {
+                    Ok(c) => {
+                        opts.command = Some(c);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            "check" => {
+                let rest: Vec<&String> = iter.by_ref().collect();
+                match parse_check_command(&rest) {
+                    Ok(c) => {
+                        opts.command = Some(c);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            "install-hook" => {
+                let rest: Vec<&String> = iter.by_ref().collect();
+                match parse_install_hook_command(&rest) {
+                    Ok(c) => {
+                        opts.command = Some(c);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            "selftest" => {
+                if let Some(extra) = iter.next() {
+                    return Err(format!("unexpected selftest argument `{}`", extra));
+                }
+                opts.command = Some(Command::Selftest);
+            }
+            "bench" => {
+                let rest: Vec<&String> = iter.by_ref().collect();
+                match parse_bench_command(&rest) {
+                    Ok(c) => {
+                        opts.command = Some(c);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            "restore" => {
+                let rest: Vec<&String> = iter.by_ref().collect();
+                match parse_restore_command(&rest) {
+                    Ok(c) => {
+                        opts.command = Some(c);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            _ => {
+                if name.starts_with('-') {
+                    return Err(format!("unknown option `{}`", name));
+                }
+                return Err(format!("unexpected argument `{}`", arg));
+            }
+        }
+    }
+
+    Ok(opts)
+}
+
+/// Parses a boolean environment variable value.
+///
+/// Accepts `1`/`true`/`yes`/`on` and `0`/`false`/`no`/`off` (any case); an
+/// empty value counts as false.
+fn parse_env_bool(var: &str, value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "" | "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!(
+            "invalid value `{}` for {} (expected 1/0, true/false, yes/no or on/off)",
+            value, var
+        )),
+    }
+}
+
+/// Fills in options from `GAR_*` environment variables.
+///
+/// | Variable                   | Option                   |
+/// |----------------------------|--------------------------|
+/// | `GAR_AUTHOR_NAME`          | `--name`                 |
+/// | `GAR_AUTHOR_EMAIL`         | `--email`                |
+/// | `GAR_PROFILE`              | `--profile`              |
+/// | `GAR_ASSUME_YES`           | `--yes`                  |
+/// | `GAR_GLOBAL`               | `--global`               |
+/// | `GAR_FORCE_PUSHED_HISTORY` | `--force-pushed-history` |
+/// | `GAR_FORCE`                | `--force`                |
+/// | `GAR_NO_CONFIG_UPDATE`     | `--no-config-update`     |
+///
+/// Values given on the command line take precedence: a variable only sets an
+/// option the command line left unset. Empty string variables are ignored.
+///
+/// # Parameters
+///
+/// * `opts` – Options already parsed from the command line.
+/// * `lookup` – Returns a variable's value, e.g. `|k| std::env::var(k).ok()`.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if a boolean variable has an unrecognized value.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::args::{apply_env, Options};
+///
+/// let mut opts = Options::default();
+/// apply_env(&mut opts, |k| (k == "GAR_ASSUME_YES").then(|| String::from("1"))).unwrap();
+/// assert!(opts.yes);
+/// ```
+pub fn apply_env<F>(opts: &mut Options, lookup: F) -> Result<(), String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let get = |var: &str| lookup(var).filter(|v| !v.is_empty());
+
+    for (var, slot) in [
+        ("GAR_AUTHOR_NAME", &mut opts.name),
+        ("GAR_AUTHOR_EMAIL", &mut opts.email),
+        ("GAR_PROFILE", &mut opts.profile),
+    ] {
+        if slot.is_none() {
+            *slot = get(var);
+        }
+    }
+
+    for (var, flag) in [
+        ("GAR_ASSUME_YES", &mut opts.yes),
+        ("GAR_GLOBAL", &mut opts.global),
+        ("GAR_FORCE_PUSHED_HISTORY", &mut opts.force_pushed_history),
+        ("GAR_FORCE", &mut opts.force),
+        ("GAR_NO_CONFIG_UPDATE", &mut opts.no_config_update),
+    ] {
+        if let Some(v) = get(var) {
+            match parse_env_bool(var, &v) {
+                Ok(b) => {
+                    *flag = *flag || b;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_env, parse, Command, Options};
+    use crate::{engine::EngineKind, hook::HookKind};
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_args_are_defaults() {
+        let opts = parse(&[]).expect("parse failed");
+        assert_eq!(opts, Options::default());
+    }
+
+    #[test]
+    fn parses_boolean_flags() {
+        let opts = parse(&strings(&[
+            "--manual",
+            "--force-pushed-history",
+            "--allow-protected",
+            "--pick-branch",
+            "--force",
+            "-h",
+            "--global",
+            "--rewrite-tags",
+            "--sign-tags",
+            "--include-vendored",
+            "--annotate",
+            "--record-original-sha",
+            "--no-verify",
+            "--no-gpg-sign",
+            "--no-config-update",
+            "--check-github",
+            "--check-dco",
+            "--skip-on-conflict",
+            "--resume",
+            "--skip-merges",
+            "--all-branches",
+            "--sync-committer",
+            "-q",
+        ]))
+        .expect("parse failed");
+        assert!(opts.manual);
+        assert!(opts.global);
+        assert!(opts.rewrite_tags);
+        assert!(opts.sign_tags);
+        assert!(opts.include_vendored);
+        assert!(opts.annotate);
+        assert!(opts.record_original_sha);
+        assert!(opts.no_verify);
+        assert!(!opts.verify);
+        assert!(opts.no_gpg_sign);
+        assert!(opts.skip_merges);
+        assert!(opts.all_branches);
+        assert!(opts.no_config_update);
+        assert!(opts.check_github);
+        assert!(opts.check_dco);
+        assert!(opts.skip_on_conflict);
+        assert!(opts.resume);
+        assert!(opts.sync_committer);
+        assert!(opts.quiet);
+        assert!(opts.force_pushed_history);
+        assert!(opts.allow_protected);
+        assert!(opts.pick_branch);
+        assert!(opts.force);
+        assert!(opts.help);
+        assert!(!opts.version);
+    }
+
+    #[test]
+    fn drop_author_is_repeatable() {
+        let opts = parse(&strings(&[
+            "--drop-author",
+            "bot@ci.example",
+            "--drop-author=old@x.com",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.drop_author, vec!["bot@ci.example", "old@x.com"]);
+    }
+
+    #[test]
+    fn engine_must_be_known() {
+        let opts = parse(&strings(&["--engine", "replace"])).expect("parse failed");
+        assert_eq!(opts.engine.as_deref(), Some("replace"));
+        let err = parse(&strings(&["--engine=libgit2"])).unwrap_err();
+        assert!(err.contains("unknown engine `libgit2`"), "{}", err);
+    }
+
+    #[test]
+    fn exec_is_repeatable_and_needs_a_command() {
+        let opts = parse(&strings(&[
+            "--exec",
+            "cargo fmt --check",
+            "--exec=make lint",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.exec, vec!["cargo fmt --check", "make lint"]);
+        assert!(parse(&strings(&["--exec", " "])).is_err());
+    }
+
+    #[test]
+    fn parses_editor_command() {
+        let opts = parse(&strings(&[
+            "--manual",
+            "--editor",
+            "code --wait",
+            "--show-authors",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.editor.as_deref(), Some("code --wait"));
+        assert!(opts.show_authors);
+        assert!(parse(&strings(&["--editor="])).is_err());
+    }
+
+    #[test]
+    fn parses_rebase_loop_modes() {
+        let opts = parse(&strings(&["--ask-each"])).expect("parse failed");
+        assert!(opts.ask_each && !opts.tui);
+        let opts = parse(&strings(&["--tui"])).expect("parse failed");
+        assert!(opts.tui && !opts.ask_each);
+        let opts = parse(&strings(&["--show-commands"])).expect("parse failed");
+        assert!(opts.show_commands && !opts.tui);
+    }
+
+    #[test]
+    fn parses_value_with_separate_argument() {
+        let opts = parse(&strings(&["--revs", "v1.0..HEAD --first-parent"])).expect("parse failed");
+        assert_eq!(opts.revs.as_deref(), Some("v1.0..HEAD --first-parent"));
+    }
+
+    #[test]
+    fn parses_value_with_equals() {
+        let opts = parse(&strings(&["--revs=main..feature"])).expect("parse failed");
+        assert_eq!(opts.revs.as_deref(), Some("main..feature"));
+        let opts = parse(&strings(&["--mirror=git@host:r.git"])).expect("parse failed");
+        assert_eq!(opts.mirror.as_deref(), Some("git@host:r.git"));
+        let opts = parse(&strings(&["--rules=authors.rules"])).expect("parse failed");
+        assert_eq!(opts.rules.as_deref(), Some("authors.rules"));
+        let opts = parse(&strings(&["--sha-map", "audit.csv"])).expect("parse failed");
+        assert_eq!(opts.sha_map.as_deref(), Some("audit.csv"));
+    }
+
+    #[test]
+    fn exclude_is_repeatable() {
+        let opts = parse(&strings(&[
+            "--exclude",
+            "abc123",
+            "--exclude=def456",
+            "--exclude-file",
+            "vendored.txt",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.exclude, vec!["abc123", "def456"]);
+        assert_eq!(opts.exclude_file.as_deref(), Some("vendored.txt"));
+        assert!(parse(&strings(&["--exclude="])).is_err());
+    }
+
+    #[test]
+    fn parses_stdin_markers() {
+        let opts = parse(&strings(&["--commits-from", "-"])).expect("parse failed");
+        assert_eq!(opts.commits_from.as_deref(), Some("-"));
+        let opts = parse(&strings(&["--spec=-"])).expect("parse failed");
+        assert_eq!(opts.spec.as_deref(), Some("-"));
+        let opts = parse(&strings(&["--repos-file", "-"])).expect("parse failed");
+        assert_eq!(opts.repos_file.as_deref(), Some("-"));
+    }
+
+    #[test]
+    fn parses_jobs() {
+        let opts = parse(&strings(&["-j", "4"])).expect("parse failed");
+        assert_eq!(opts.jobs, Some(4));
+        assert!(parse(&strings(&["--jobs=0"])).is_err());
+        assert!(parse(&strings(&["--jobs", "many"])).is_err());
+    }
+
+    #[test]
+    fn parses_timeout() {
+        let opts = parse(&strings(&["--timeout=90"])).expect("parse failed");
+        assert_eq!(opts.timeout, Some(90));
+        assert!(parse(&strings(&["--timeout", "0"])).is_err());
+        assert!(parse(&strings(&["--timeout", "1m"])).is_err());
+    }
+
+    #[test]
+    fn parses_chunk_size() {
+        let opts = parse(&strings(&["--chunk-size", "5000"])).expect("parse failed");
+        assert_eq!(opts.chunk_size, Some(5000));
+        assert!(parse(&strings(&["--chunk-size=0"])).is_err());
+        assert!(parse(&strings(&["--chunk-size", "5k"])).is_err());
+    }
+
+    #[test]
+    fn message_replace_accumulates() {
+        let opts = parse(&strings(&[
+            "--message-replace",
+            "s/a/b/",
+            "--message-replace=s/x=1/x=2/g",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.message_replace, vec!["s/a/b/", "s/x=1/x=2/g"]);
+    }
+
+    #[test]
+    fn missing_value_is_an_error() {
+        let err = parse(&strings(&["--revs"])).unwrap_err();
+        assert_eq!(err, "option `--revs` requires a value");
+    }
+
+    #[test]
+    fn unknown_option_is_an_error() {
+        let err = parse(&strings(&["--bogus"])).unwrap_err();
+        assert_eq!(err, "unknown option `--bogus`");
+    }
+
+    #[test]
+    fn positional_argument_is_an_error() {
+        let err = parse(&strings(&["stray"])).unwrap_err();
+        assert_eq!(err, "unexpected argument `stray`");
+    }
+
+    #[test]
+    fn parses_profile_flag() {
+        let opts = parse(&strings(&["--profile=work", "--name-only"])).expect("parse failed");
+        assert_eq!(opts.profile.as_deref(), Some("work"));
+        assert!(opts.name_only);
+    }
+
+    #[test]
+    fn parses_profile_subcommands() {
+        let opts = parse(&strings(&[
+            "profile",
+            "add",
+            "work",
+            "Jane Doe",
+            "jane@corp.com",
+        ]))
+        .expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::ProfileAdd {
+                profile: String::from("work"),
+                name: String::from("Jane Doe"),
+                email: String::from("jane@corp.com"),
+            })
+        );
+        let opts = parse(&strings(&["profile", "list"])).expect("parse failed");
+        assert_eq!(opts.command, Some(Command::ProfileList));
+    }
+
+    #[test]
+    fn profile_subcommand_checks_arguments() {
+        let err = parse(&strings(&["profile", "remove"])).unwrap_err();
+        assert_eq!(err, "usage: profile remove <PROFILE>");
+        assert!(parse(&strings(&["profile", "rename", "a"])).is_err());
+    }
+
+    #[test]
+    fn parses_identity_flags() {
+        let opts = parse(&strings(&[
+            "--name",
+            "Jane Doe",
+            "--email=jane@corp.com",
+            "-y",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(opts.email.as_deref(), Some("jane@corp.com"));
+        assert!(opts.yes);
+    }
+
+    #[test]
+    fn env_fills_unset_options_only() {
+        let mut opts = parse(&strings(&["--name", "Flag Name"])).expect("parse failed");
+        apply_env(&mut opts, |k| match k {
+            "GAR_AUTHOR_NAME" => Some(String::from("Env Name")),
+            "GAR_AUTHOR_EMAIL" => Some(String::from("env@x.com")),
+            "GAR_ASSUME_YES" => Some(String::from("true")),
+            "GAR_GLOBAL" => Some(String::from("0")),
+            "GAR_PROFILE" => Some(String::new()),
+            _ => None,
+        })
+        .expect("apply_env failed");
+        assert_eq!(opts.name.as_deref(), Some("Flag Name"));
+        assert_eq!(opts.email.as_deref(), Some("env@x.com"));
+        assert!(opts.yes);
+        assert!(!opts.global);
+        assert_eq!(opts.profile, None);
+    }
+
+    #[test]
+    fn env_rejects_bad_booleans() {
+        let mut opts = Options::default();
+        let err = apply_env(&mut opts, |k| {
+            (k == "GAR_ASSUME_YES").then(|| String::from("maybe"))
+        })
+        .unwrap_err();
+        assert!(err.contains("GAR_ASSUME_YES"));
+    }
+
+    #[test]
+    fn parses_verify_subcommand() {
+        let opts = parse(&strings(&[
+            "verify",
+            "--email",
+            "new@x.com",
+            "--revs=main..HEAD",
+        ]))
+        .expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::Verify {
+                name: None,
+                email: Some(String::from("new@x.com")),
+                revs: Some(String::from("main..HEAD")),
+            })
+        );
+        assert!(parse(&strings(&["verify"])).is_err());
+        assert!(parse(&strings(&["verify", "--email", "a@b", "--bogus"])).is_err());
+    }
+
+    #[test]
+    fn parses_check_subcommand() {
+        let opts = parse(&strings(&["check"])).expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::Check {
+                policy: None,
+                revs: None,
+                identities: Vec::new(),
+            })
+        );
+        let opts = parse(&strings(&[
+            "check",
+            "--policy",
+            "ci/authors",
+            "--revs=main..HEAD",
+        ]))
+        .expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::Check {
+                policy: Some(String::from("ci/authors")),
+                revs: Some(String::from("main..HEAD")),
+                identities: Vec::new(),
+            })
+        );
+        let opts = parse(&strings(&[
+            "check",
+            "--identity",
+            "A <a@x>",
+            "--identity=B <b@x>",
+        ]))
+        .expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::Check {
+                policy: None,
+                revs: None,
+                identities: vec![String::from("A <a@x>"), String::from("B <b@x>")],
+            })
+        );
+        assert!(parse(&strings(&["check", "--strict"])).is_err());
+        assert!(parse(&strings(&["check", "--policy"])).is_err());
+        assert!(parse(&strings(&[
+            "check",
+            "--revs",
+            "HEAD",
+            "--identity",
+            "A <a@x>"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn parses_install_hook_subcommand() {
+        let opts = parse(&strings(&["install-hook"])).expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::InstallHook {
+                hook: HookKind::PrePush,
+                policy: None,
+                force: false,
+            })
+        );
+        let opts = parse(&strings(&[
+            "install-hook",
+            "--hook=commit-msg",
+            "--policy",
+            "ci/authors",
+            "--force",
+        ]))
+        .expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::InstallHook {
+                hook: HookKind::CommitMsg,
+                policy: Some(String::from("ci/authors")),
+                force: true,
+            })
+        );
+        assert!(parse(&strings(&["install-hook", "--hook", "pre-commit"])).is_err());
+    }
+
+    #[test]
+    fn parses_selftest_subcommand() {
+        let opts = parse(&strings(&["selftest"])).expect("parse failed");
+        assert_eq!(opts.command, Some(Command::Selftest));
+        assert!(parse(&strings(&["selftest", "--quick"])).is_err());
+    }
+
+    #[test]
+    fn parses_bench_subcommand() {
+        let opts = parse(&strings(&["bench"])).expect("parse failed");
+        assert_eq!(opts.command, Some(Command::Bench { engines: vec![] }));
+        let opts = parse(&strings(&[
+            "bench",
+            "--engine=gix",
+            "--engine",
+            "rebase",
+            "--engine=gix",
+        ]))
+        .expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::Bench {
+                engines: vec![EngineKind::Gix, EngineKind::Rebase],
+            })
+        );
+        assert!(parse(&strings(&["bench", "--engine", "libgit2"])).is_err());
+        assert!(parse(&strings(&["bench", "--runs", "3"])).is_err());
+    }
+
+    #[test]
+    fn parses_restore_subcommand_and_backup_bundle() {
+        let opts =
+            parse(&strings(&["restore", "--from-bundle", "before.bundle"])).expect("parse failed");
+        assert_eq!(
+            opts.command,
+            Some(Command::Restore {
+                bundle: String::from("before.bundle"),
+            })
+        );
+        assert!(parse(&strings(&["restore"])).is_err());
+        assert!(parse(&strings(&["restore", "before.bundle"])).is_err());
+        let opts = parse(&strings(&["--backup-bundle=before.bundle"])).expect("parse failed");
+        assert_eq!(opts.backup_bundle.as_deref(), Some("before.bundle"));
+    }
+
+    #[test]
+    fn parses_audit_log_before_a_subcommand() {
+        let opts = parse(&strings(&[
+            "--audit-log",
+            "/srv/audit/rewrites.jsonl",
+            "restore",
+            "--from-bundle",
+            "before.bundle",
+        ]))
+        .expect("parse failed");
+        assert_eq!(opts.audit_log.as_deref(), Some("/srv/audit/rewrites.jsonl"));
+        assert!(matches!(opts.command, Some(Command::Restore { .. })));
+        assert!(parse(&strings(&["--audit-log"])).is_err());
+    }
+
+    #[test]
+    fn parses_report_html() {
+        let opts = parse(&strings(&["--report-html=CHG-1042.html"])).expect("parse failed");
+        assert_eq!(opts.report_html.as_deref(), Some("CHG-1042.html"));
+        assert!(parse(&strings(&["--report-html"])).is_err());
+    }
+
+    #[test]
+    fn parses_include_if() {
+        let opts = parse(&strings(&["--include-if", "~/work"])).expect("parse failed");
+        assert_eq!(opts.include_if.as_deref(), Some("~/work"));
+        assert!(parse(&strings(&["--include-if"])).is_err());
+    }
+}
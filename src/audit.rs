@@ -0,0 +1,341 @@
+use crate::{error::Error, git::Repo};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the audit log, inside the repository's git directory, unless
+/// `--audit-log` or [`PATH_KEY`] puts it elsewhere.
+pub const AUDIT_FILE: &str = "author-rewrite-audit.jsonl";
+
+/// Git config key naming the audit log, e.g. a file on a share the
+/// compliance team collects.
+pub const PATH_KEY: &str = "author-rewrite.auditLog";
+
+/// A ref a run created, moved or deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefMove {
+    /// The full ref name, e.g. `refs/heads/main`.
+    pub name: String,
+    /// Where it pointed before the run, or `None` if the run created it.
+    pub old: Option<String>,
+    /// Where it points after the run, or `None` if the run deleted it.
+    pub new: Option<String>,
+}
+
+/// One line of the audit log: one run of the tool in one repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// When the run started, in UTC (`2026-03-01T09:30:00Z`).
+    pub time: String,
+    /// The operating system account that ran the tool.
+    pub login: Option<String>,
+    /// The machine it ran on.
+    pub host: Option<String>,
+    /// The Git identity configured for the repository (`Name <email>`).
+    pub user: Option<String>,
+    /// The version of the tool.
+    pub version: String,
+    /// The repository's git directory.
+    pub repository: String,
+    /// The command line, without the program name.
+    pub args: Vec<String>,
+    /// The engine the rewrite used, if it got as far as choosing one.
+    pub engine: Option<String>,
+    /// The exit code, or `None` if the run failed.
+    pub exit: Option<i32>,
+    /// Why the run failed, if it did.
+    pub error: Option<String>,
+    /// Every ref that changed during the run, by name.
+    pub refs: Vec<RefMove>,
+}
+
+/// An audit record in the making: the log is opened and the refs are read
+/// when a run starts, and the record is written when it ends, whether it
+/// succeeded or not.
+pub struct Trail {
+    repo: Repo,
+    path: PathBuf,
+    file: File,
+    before: BTreeMap<String, String>,
+    record: Record,
+}
+
+impl Trail {
+    /// Starts the record of a run of `args` in `repo`, appending to the log
+    /// at `path`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Trail)` once the log is open; nothing should change before then.
+    /// * `Err(String)` if the log cannot be opened or the refs cannot be read.
+    pub fn start(repo: &Repo, path: &Path, args: &[String]) -> Result<Trail, String> {
+        let file = match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(f) => f,
+            Err(e) => {
+                return Err(format!(
+                    "cannot open the audit log {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+        let before = match snapshot(repo) {
+            Ok(refs) => refs,
+            Err(e) => return Err(e),
+        };
+        let name = repo.config_get("user.name").unwrap_or_default();
+        let email = repo.config_get("user.email").unwrap_or_default();
+        let user = if name.is_empty() && email.is_empty() {
+            None
+        } else {
+            Some(format!("{} <{}>", name, email))
+        };
+        let record = Record {
+            time: utc_now(),
+            login: env::var("USER").or_else(|_| env::var("USERNAME")).ok(),
+            host: hostname(),
+            user,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            repository: repo.git_dir().display().to_string(),
+            args: args.to_vec(),
+            engine: None,
+            exit: None,
+            error: None,
+            refs: Vec::new(),
+        };
+        Ok(Trail {
+            repo: repo.clone(),
+            path: path.to_path_buf(),
+            file,
+            before,
+            record,
+        })
+    }
+
+    /// The log the record goes to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records the engine the rewrite runs with.
+    pub fn set_engine(&mut self, name: &str) {
+        self.record.engine = Some(name.to_string());
+    }
+
+    /// Reads the refs again and appends the record, with the outcome of the
+    /// run, to the log as one line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Record)` with what was written.
+    /// * `Err(String)` if the refs could not be read or the line not written.
+    pub fn finish(mut self, outcome: &Result<i32, Error>) -> Result<Record, String> {
+        match outcome {
+            Ok(code) => self.record.exit = Some(*code),
+            Err(e) => self.record.error = Some(e.to_string()),
+        }
+        let after = match snapshot(&self.repo) {
+            Ok(refs) => refs,
+            Err(e) => return Err(e),
+        };
+        self.record.refs = moved(&self.before, &after);
+
+        let mut line = match serde_json::to_string(&self.record) {
+            Ok(l) => l,
+            Err(e) => return Err(format!("cannot encode the audit record: {}", e)),
+        };
+        line.push('\n');
+        // One write per record, so concurrent runs appending to a shared
+        // log do not interleave their lines.
+        match self.file.write_all(line.as_bytes()) {
+            Ok(()) => Ok(self.record),
+            Err(e) => Err(format!(
+                "cannot write the audit log {}: {}",
+                self.path.display(),
+                e
+            )),
+        }
+    }
+}
+
+/// Where the audit log of `repo` goes: `flag` (`--audit-log`) if given,
+/// else [`PATH_KEY`], else [`AUDIT_FILE`] in the git directory.
+pub fn log_path(repo: &Repo, flag: Option<&str>) -> PathBuf {
+    if let Some(path) = flag {
+        return PathBuf::from(path);
+    }
+    match repo.config_get(PATH_KEY) {
+        Ok(path) if !path.trim().is_empty() => PathBuf::from(path.trim()),
+        _ => repo.git_dir().join(AUDIT_FILE),
+    }
+}
+
+/// Reads every ref of `repo`, by name.
+fn snapshot(repo: &Repo) -> Result<BTreeMap<String, String>, String> {
+    match repo.for_each_ref("%(objectname) %(refname)", "refs") {
+        Ok(out) => Ok(out
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(sha, name)| (name.to_string(), sha.to_string()))
+            .collect()),
+        Err(e) => Err(format!("cannot read the refs for the audit log: {}", e)),
+    }
+}
+
+/// Lists the refs that differ between two snapshots, by name.
+pub(crate) fn moved(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<RefMove> {
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter(|name| before.get(*name) != after.get(*name))
+        .map(|name| RefMove {
+            name: name.clone(),
+            old: before.get(name).cloned(),
+            new: after.get(name).cloned(),
+        })
+        .collect()
+}
+
+/// The name of this machine, if the system tells.
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let name = String::from_utf8_lossy(&buf[..len]).into_owned();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// The name of this machine, if the system tells.
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
+/// The current time as an RFC 3339 UTC timestamp (see [`utc_timestamp`]).
+pub fn utc_now() -> String {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => utc_timestamp(d.as_secs()),
+        Err(_) => utc_timestamp(0),
+    }
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::audit::utc_timestamp;
+///
+/// assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+/// assert_eq!(utc_timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+/// ```
+pub fn utc_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let rest = seconds % 86_400;
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{moved, utc_timestamp, Record, RefMove};
+    use std::collections::BTreeMap;
+
+    fn refs(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, sha)| (name.to_string(), sha.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn moved_lists_created_moved_and_deleted_refs() {
+        let before = refs(&[
+            ("refs/heads/main", "a1"),
+            ("refs/heads/old", "b1"),
+            ("refs/tags/v1", "c1"),
+        ]);
+        let after = refs(&[
+            ("refs/heads/main", "a2"),
+            ("refs/original/refs/heads/main", "a1"),
+            ("refs/tags/v1", "c1"),
+        ]);
+        let change = |name: &str, old: Option<&str>, new: Option<&str>| RefMove {
+            name: name.to_string(),
+            old: old.map(String::from),
+            new: new.map(String::from),
+        };
+        assert_eq!(
+            moved(&before, &after),
+            vec![
+                change("refs/heads/main", Some("a1"), Some("a2")),
+                change("refs/heads/old", Some("b1"), None),
+                change("refs/original/refs/heads/main", None, Some("a1")),
+            ]
+        );
+        assert!(moved(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn utc_timestamp_handles_leap_days() {
+        assert_eq!(utc_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(utc_timestamp(1_709_251_199), "2024-02-29T23:59:59Z");
+        assert_eq!(utc_timestamp(1_735_689_600), "2025-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn record_is_one_json_line() {
+        let record = Record {
+            time: String::from("2026-03-01T09:30:00Z"),
+            login: Some(String::from("jane")),
+            host: None,
+            user: Some(String::from("Jane <jane@x.com>")),
+            version: String::from("1.0.0"),
+            repository: String::from("/work/app/.git"),
+            args: vec![String::from("--email"), String::from("new\nline@x.com")],
+            engine: Some(String::from("rebase")),
+            exit: Some(0),
+            error: None,
+            refs: Vec::new(),
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(serde_json::from_str::<Record>(&line).unwrap(), record);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::git::Repo;
+
+use std::path::{self, Path, PathBuf};
+
+/// Namespace for backups of rewritten refs, as used by `git filter-branch`.
+pub const BACKUP_PREFIX: &str = "refs/original/";
+
+/// Returns the backup ref for `head_ref`, e.g.
+/// `refs/heads/main` → `refs/original/refs/heads/main`.
+///
+/// A detached `HEAD` is backed up as `refs/original/HEAD`.
+pub fn backup_ref_name(head_ref: Option<&str>) -> String {
+    format!("{}{}", BACKUP_PREFIX, head_ref.unwrap_or("HEAD"))
+}
+
+/// Returns the backup ref for the current branch, failing if it already exists.
+///
+/// An existing backup is never overwritten: like `git filter-branch`, the
+/// rewrite is refused until the old backup has been dropped, so a second run
+/// cannot silently destroy the only pointer to the original history.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the name of the (unused) backup ref.
+/// * `Err(String)` if a backup already exists or the refs could not be listed.
+pub fn check_available(repo: &Repo) -> Result<String, String> {
+    let name = backup_ref_name(repo.symbolic_head().as_deref());
+
+    let existing = match repo.list_refs(&name) {
+        Ok(refs) => refs,
+        Err(e) => return Err(e),
+    };
+    if existing.contains(&name) {
+        return Err(format!(
+            "a previous backup already exists at {}; remove it with --drop-backup-refs first",
+            name
+        ));
+    }
+
+    Ok(name)
+}
+
+/// Records the current tip of the branch about to be rewritten under
+/// `refs/original/` (see [`check_available`]).
+///
+/// # Returns
+///
+/// * `Ok(String)` with the name of the backup ref.
+/// * `Err(String)` if a backup already exists or the ref could not be written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{backup, git::Repo};
+///
+/// let repo = Repo::discover().unwrap();
+/// let name = backup::create(&repo).unwrap();
+/// println!("Original history saved as {}", name);
+/// ```
+pub fn create(repo: &Repo) -> Result<String, String> {
+    let name = match check_available(repo) {
+        Ok(n) => n,
+        Err(e) => return Err(e),
+    };
+
+    let tip = match repo.rev_parse("HEAD") {
+        Ok(sha) => sha,
+        Err(e) => return Err(e),
+    };
+    match repo.update_ref(&name, &tip, "git-author-rewrite: backup before rewrite") {
+        Ok(_) => Ok(name),
+        Err(e) => Err(format!("cannot create backup {}: {}", name, e)),
+    }
+}
+
+/// Deletes every ref under `refs/original/`.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` with the deleted ref names, possibly empty.
+/// * `Err(String)` if the refs could not be listed or deleted.
+pub fn drop_all(repo: &Repo) -> Result<Vec<String>, String> {
+    let refs = match repo.list_refs(BACKUP_PREFIX) {
+        Ok(r) => r,
+        Err(e) => return Err(e),
+    };
+    for name in &refs {
+        if let Err(e) = repo.delete_ref(name) {
+            return Err(format!("cannot delete {}: {}", name, e));
+        }
+    }
+    Ok(refs)
+}
+
+/// A ref stored in a backup bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleRef {
+    /// The full ref name, e.g. `refs/heads/main`.
+    pub name: String,
+    /// The commit (or tag object) it pointed at when the bundle was made.
+    pub sha: String,
+}
+
+/// Makes `path` absolute against the current directory, since Git runs in
+/// the top of the working tree.
+fn absolute(path: &Path) -> Result<PathBuf, String> {
+    match path::absolute(path) {
+        Ok(p) => Ok(p),
+        Err(e) => Err(format!("invalid bundle path {}: {}", path.display(), e)),
+    }
+}
+
+/// Checks that no file exists at `path`, so a backup bundle can be written
+/// there without replacing an older one.
+///
+/// # Returns
+///
+/// * `Ok(())` if the path is free.
+/// * `Err(String)` if something already exists there.
+pub fn check_bundle_available(path: &Path) -> Result<(), String> {
+    if path.exists() {
+        return Err(format!(
+            "{} already exists; choose another --backup-bundle path or remove the old bundle first",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Writes every ref of `repo`, and everything they reach, to a bundle file
+/// at `path` (`--backup-bundle`).
+///
+/// Unlike a backup ref, the bundle lives outside the repository, so it
+/// survives `git gc` and a later `--cleanup`. Restore it with
+/// [`restore_bundle`].
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if a file already exists at `path` or Git failed.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{backup, git::Repo};
+/// use std::path::Path;
+///
+/// let repo = Repo::discover().unwrap();
+/// backup::create_bundle(&repo, Path::new("../before-rewrite.bundle")).unwrap();
+/// ```
+pub fn create_bundle(repo: &Repo, path: &Path) -> Result<(), String> {
+    if let Err(e) = check_bundle_available(path) {
+        return Err(e);
+    }
+    let path = match absolute(path) {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+    match repo.bundle_create(&path) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("cannot create bundle {}: {}", path.display(), e)),
+    }
+}
+
+/// Parses `git bundle list-heads` output, skipping the bundle's `HEAD`
+/// entry, which is not a ref that can be restored.
+pub(crate) fn parse_bundle_heads(out: &str) -> Vec<BundleRef> {
+    out.lines()
+        .filter_map(|l| l.trim().split_once(' '))
+        .filter(|(_, name)| name.starts_with("refs/"))
+        .map(|(sha, name)| BundleRef {
+            name: name.to_string(),
+            sha: sha.to_string(),
+        })
+        .collect()
+}
+
+/// Points every ref stored in the bundle at `path` back at its saved value
+/// (`restore --from-bundle`).
+///
+/// Refs created since the bundle was made are left alone. When the
+/// checked-out branch is restored, the working tree is reset to it, so
+/// tracked changes must be committed or stashed first.
+///
+/// # Returns
+///
+/// * `Ok(Vec<BundleRef>)` with the restored refs.
+/// * `Err(String)` if the bundle cannot be read, a rebase is in progress,
+///   the working tree has tracked changes, or Git failed.
+pub fn restore_bundle(repo: &Repo, path: &Path) -> Result<Vec<BundleRef>, String> {
+    let path = match absolute(path) {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+    let refs = match repo.bundle_list_heads(&path) {
+        Ok(out) => parse_bundle_heads(&out),
+        Err(e) => return Err(format!("cannot read bundle {}: {}", path.display(), e)),
+    };
+    if refs.is_empty() {
+        return Err(format!("bundle {} holds no refs", path.display()));
+    }
+
+    if repo.rebase_in_progress() {
+        return Err(String::from(
+            "a rebase is in progress; finish it or run `git rebase --abort` first",
+        ));
+    }
+    match repo.tracked_changes() {
+        Ok(changes) if changes.is_empty() => {}
+        Ok(_) => {
+            return Err(String::from(
+                "the working tree has uncommitted changes; commit or stash them first",
+            ))
+        }
+        Err(e) => return Err(e),
+    }
+
+    let refspecs: Vec<String> = refs
+        .iter()
+        .map(|r| format!("+{}:{}", r.name, r.name))
+        .collect();
+    if let Err(e) = repo.fetch_refs(&path.to_string_lossy(), &refspecs) {
+        return Err(format!("cannot restore from {}: {}", path.display(), e));
+    }
+
+    let head = repo.symbolic_head();
+    if refs.iter().any(|r| Some(&r.name) == head.as_ref()) {
+        if let Err(e) = repo.reset_hard() {
+            return Err(e);
+        }
+    }
+    Ok(refs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backup_ref_name, check_bundle_available, parse_bundle_heads, BundleRef};
+
+    #[test]
+    fn backup_ref_follows_filter_branch_layout() {
+        assert_eq!(
+            backup_ref_name(Some("refs/heads/main")),
+            "refs/original/refs/heads/main"
+        );
+    }
+
+    #[test]
+    fn detached_head_is_backed_up_as_head() {
+        assert_eq!(backup_ref_name(None), "refs/original/HEAD");
+    }
+
+    #[test]
+    fn bundle_heads_skip_head() {
+        let out = "1111 refs/heads/main\n2222 refs/tags/v1\n1111 HEAD\n";
+        assert_eq!(
+            parse_bundle_heads(out),
+            vec![
+                BundleRef {
+                    name: String::from("refs/heads/main"),
+                    sha: String::from("1111"),
+                },
+                BundleRef {
+                    name: String::from("refs/tags/v1"),
+                    sha: String::from("2222"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn existing_bundle_is_not_replaced() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("backup.bundle");
+        assert!(check_bundle_available(&path).is_ok());
+        std::fs::write(&path, "old").expect("failed to write bundle");
+        assert!(check_bundle_available(&path).is_err());
+    }
+}
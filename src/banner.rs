@@ -1,163 +1,535 @@
-use console::{measure_text_width, style};
-use std::iter;
+use crate::identity::Mapping;
 
-/// Prints a decorative, colorized banner describing the commit-rewrite process.
-///
-/// The banner is dynamically sized to fit the widest **visible** line of text,
-/// using [`console::measure_text_width`] to ignore ANSI color codes when
-/// calculating padding. It is framed with Unicode box-drawing characters
-/// (`╔═╗`, `║ ║`, `╚═╝`) and uses [`console::style`] for coloring and bolding.
-///
-/// Borders are styled independently from the inner text so that embedded color
-/// codes inside the content (e.g. yellow/cyan manual/auto mode text) do not
-/// affect the color of the box edges.
-///
-/// # Parameters
-///
-/// * `name` – The new author name to display in the banner.
-/// * `email` – The new author email to display in the banner.
-/// * `manual_mode` – When `true`, the banner shows manual rebase instructions
-///   (in a highlighted color). When `false`, it shows automatic mode
-///   instructions (also highlighted).
-///
-/// # Output
-///
-/// This function prints directly to standard output. It does not return any value.
-///
-/// # Notes
-///
-/// * Width calculation ignores ANSI codes, so padding stays correct even with
-///   inline colors.
-/// * Intended for interactive CLI display; not for structured logging.
-/// * If you require exact column widths for wide Unicode glyphs, ensure your
-///   terminal supports them and consider `unicode-width`.
-///
-/// # Examples
+use console::{measure_text_width, style, Color};
+
+/// Git config keys read by [`Theme::from_config`].
+pub const BORDER_KEY: &str = "author-rewrite.banner.border";
+/// See [`BORDER_KEY`].
+pub const LAYOUT_KEY: &str = "author-rewrite.banner.layout";
+/// See [`BORDER_KEY`].
+pub const BORDER_COLOR_KEY: &str = "author-rewrite.banner.borderColor";
+/// See [`BORDER_KEY`].
+pub const ACCENT_COLOR_KEY: &str = "author-rewrite.banner.accentColor";
+/// See [`BORDER_KEY`].
+pub const WARNING_COLOR_KEY: &str = "author-rewrite.banner.warningColor";
+
+/// The frame drawn around the banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Border {
+    /// `╔═╗ ║ ╚═╝`, the default.
+    Double,
+    /// `┌─┐ │ └─┘`.
+    Single,
+    /// `╭─╮ │ ╰─╯`.
+    Rounded,
+    /// `┏━┓ ┃ ┗━┛`.
+    Heavy,
+    /// `+-+ | +-+`, for terminals without box-drawing glyphs.
+    Ascii,
+    /// No frame: the lines are printed as they are.
+    None,
+}
+
+impl Border {
+    /// Looks up a border by its config name (`double`, `single`, `rounded`,
+    /// `heavy`, `ascii` or `none`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Border)` for a known name, ignoring case.
+    /// * `Err(String)` for anything else.
+    pub fn parse(name: &str) -> Result<Border, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "double" => Ok(Border::Double),
+            "single" => Ok(Border::Single),
+            "rounded" => Ok(Border::Rounded),
+            "heavy" => Ok(Border::Heavy),
+            "ascii" => Ok(Border::Ascii),
+            "none" => Ok(Border::None),
+            _ => Err(format!(
+                "unknown border `{}` (expected double, single, rounded, heavy, ascii or none)",
+                name
+            )),
+        }
+    }
+
+    /// The corner, edge and side glyphs: top-left, horizontal, top-right,
+    /// vertical, bottom-left, bottom-right. `None` for [`Border::None`].
+    fn glyphs(self) -> Option<[&'static str; 6]> {
+        match self {
+            Border::Double => Some(["╔", "═", "╗", "║", "╚", "╝"]),
+            Border::Single => Some(["┌", "─", "┐", "│", "└", "┘"]),
+            Border::Rounded => Some(["╭", "─", "╮", "│", "╰", "╯"]),
+            Border::Heavy => Some(["┏", "━", "┓", "┃", "┗", "┛"]),
+            Border::Ascii => Some(["+", "-", "+", "|", "+", "+"]),
+            Border::None => None,
+        }
+    }
+}
+
+/// How much of the banner is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Title, mode instructions, the mapping and what happens next.
+    Full,
+    /// A single line: the title, the mode and the mapping.
+    Minimal,
+}
+
+impl Layout {
+    /// Looks up a layout by its config name (`full` or `minimal`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Layout)` for a known name, ignoring case.
+    /// * `Err(String)` for anything else.
+    pub fn parse(name: &str) -> Result<Layout, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "full" => Ok(Layout::Full),
+            "minimal" => Ok(Layout::Minimal),
+            _ => Err(format!(
+                "unknown layout `{}` (expected full or minimal)",
+                name
+            )),
+        }
+    }
+}
+
+/// Parses a banner color: one of the eight terminal color names, an ANSI
+/// 256-color number, or `none` for the terminal's default color.
 ///
-/// ```no_run
-/// use git_author_rewrite::banner::print_banner;
+/// # Returns
 ///
-/// fn main() {
-///     print_banner("John Doe", "john@example.com", false);
-/// }
-/// ```
-pub fn print_banner(name: &str, email: &str, manual_mode: bool) {
-    let lines = banner_lines(name, email, manual_mode);
+/// * `Ok(Some(Color))` for a color, `Ok(None)` for `none`.
+/// * `Err(String)` for anything else.
+pub fn parse_color(name: &str) -> Result<Option<Color>, String> {
+    let color = match name.to_ascii_lowercase().as_str() {
+        "none" => return Ok(None),
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        other => match other.parse::<u8>() {
+            Ok(n) => Color::Color256(n),
+            Err(_) => {
+                return Err(format!(
+                    "unknown color `{}` (expected a color name, 0-255 or none)",
+                    name
+                ))
+            }
+        },
+    };
+    Ok(Some(color))
+}
 
+/// Colors and frame of the banner, read from the `author-rewrite.banner.*`
+/// git config keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The frame around the banner.
+    pub border: Border,
+    /// The full banner or a one-line summary.
+    pub layout: Layout,
+    /// Color of the frame (blue by default).
+    pub border_color: Option<Color>,
+    /// Color of the instructions of the rebase's auto mode and of
+    /// `--replace-mode` (cyan by default).
+    pub accent_color: Option<Color>,
+    /// Color of the instructions of `--manual` and of `--engine gix` (yellow
+    /// by default).
+    pub warning_color: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            border: Border::Double,
+            layout: Layout::Full,
+            border_color: Some(Color::Blue),
+            accent_color: Some(Color::Cyan),
+            warning_color: Some(Color::Yellow),
+        }
+    }
+}
+
+impl Theme {
+    /// Builds a theme from the git config, starting from the default and
+    /// replacing whatever is set.
+    ///
+    /// # Parameters
+    ///
+    /// * `get` – Looks up a config key, returning an empty string if it is
+    ///   not set (as [`Repo::config_get`](crate::git::Repo::config_get) does).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Theme)` on success.
+    /// * `Err(String)` naming the first key with an invalid value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::banner::{Border, Layout, Theme};
+    ///
+    /// let theme = Theme::from_config(|key| match key {
+    ///     "author-rewrite.banner.border" => String::from("ascii"),
+    ///     "author-rewrite.banner.layout" => String::from("minimal"),
+    ///     _ => String::new(),
+    /// })
+    /// .unwrap();
+    /// assert_eq!(theme.border, Border::Ascii);
+    /// assert_eq!(theme.layout, Layout::Minimal);
+    /// ```
+    pub fn from_config<F: FnMut(&str) -> String>(mut get: F) -> Result<Theme, String> {
+        let mut theme = Theme::default();
+        let mut value = |key: &str| {
+            let v = get(key);
+            let v = v.trim();
+            if v.is_empty() {
+                None
+            } else {
+                Some(v.to_string())
+            }
+        };
+        let invalid = |key: &str, e: String| format!("invalid {}: {}", key, e);
+
+        if let Some(v) = value(BORDER_KEY) {
+            match Border::parse(&v) {
+                Ok(b) => theme.border = b,
+                Err(e) => return Err(invalid(BORDER_KEY, e)),
+            }
+        }
+        if let Some(v) = value(LAYOUT_KEY) {
+            match Layout::parse(&v) {
+                Ok(l) => theme.layout = l,
+                Err(e) => return Err(invalid(LAYOUT_KEY, e)),
+            }
+        }
+        for (key, slot) in [
+            (BORDER_COLOR_KEY, &mut theme.border_color),
+            (ACCENT_COLOR_KEY, &mut theme.accent_color),
+            (WARNING_COLOR_KEY, &mut theme.warning_color),
+        ] {
+            if let Some(v) = value(key) {
+                match parse_color(&v) {
+                    Ok(c) => *slot = c,
+                    Err(e) => return Err(invalid(key, e)),
+                }
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// Styles `text` in `color` (if any), in bold if `bold`, for standard error,
+/// where the banner is printed.
+fn paint(text: &str, color: Option<Color>, bold: bool) -> String {
+    let mut styled = style(text).for_stderr();
+    if let Some(c) = color {
+        styled = styled.fg(c);
+    }
+    if bold {
+        styled = styled.bold();
+    }
+    styled.to_string()
+}
+
+/// Which theme color a banner's instructions are shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tone {
+    /// [`Theme::accent_color`].
+    Accent,
+    /// [`Theme::warning_color`].
+    Warning,
+}
+
+/// The text of a banner, before it is styled and framed.
+struct Banner {
+    /// First line.
+    title: &'static str,
+    /// Short name of the mode, for the one-line summary.
+    mode: &'static str,
+    /// Color of the instructions.
+    tone: Tone,
+    /// The two instruction lines, each with whether it is bold.
+    instructions: [(&'static str, bool); 2],
+    /// What happens to the identities, from [`Mapping::describe`].
+    mapping: String,
+    /// Closing lines.
+    notes: &'static [&'static str],
+}
+
+impl Banner {
+    /// The instructions' color in `theme`.
+    fn color(&self, theme: &Theme) -> Option<Color> {
+        match self.tone {
+            Tone::Accent => theme.accent_color,
+            Tone::Warning => theme.warning_color,
+        }
+    }
+
+    /// The lines of the banner, with ANSI styling, in the order they are
+    /// displayed; a single summary line with [`Layout::Minimal`].
+    fn lines(&self, theme: &Theme) -> Vec<String> {
+        let color = self.color(theme);
+        if theme.layout == Layout::Minimal {
+            return vec![format!(
+                "{} ({}): {}",
+                paint(self.title, None, true),
+                paint(self.mode, color, false),
+                self.mapping
+            )];
+        }
+
+        let mut lines = vec![self.title.to_string(), String::new()];
+        for (text, bold) in self.instructions {
+            lines.push(paint(text, color, bold));
+        }
+        lines.push(String::new());
+        lines.push(self.mapping.clone());
+        lines.extend(self.notes.iter().map(|n| n.to_string()));
+        lines
+    }
+}
+
+/// Frames `lines` with the theme's border, sized to the widest visible line
+/// (ANSI codes in the content are not counted). Returns the lines unchanged
+/// without a border, or for a one-line summary.
+fn framed(lines: Vec<String>, theme: &Theme) -> Vec<String> {
+    let glyphs = match theme.border.glyphs() {
+        Some(g) if theme.layout == Layout::Full => g,
+        _ => return lines,
+    };
+    let [top_left, edge, top_right, side, bottom_left, bottom_right] = glyphs;
     let max_width = lines
         .iter()
-        .map(|l| measure_text_width(l)) // ignore ANSI in content
+        .map(|l| measure_text_width(l))
         .max()
         .unwrap_or(0)
         + 2;
 
-    let border = "═".repeat(max_width);
-    let top = style(format!("╔{}╗", border)).blue().bold();
-    let bottom = style(format!("╚{}╝", border)).blue().bold();
-    let left = style("║ ").blue().bold().to_string();
-    let right = style("║").blue().bold().to_string();
+    // Borders are styled on their own so colors inside the content do not
+    // leak into the frame.
+    let frame = |s: &str| paint(s, theme.border_color, true);
+    let border = edge.repeat(max_width);
+    let left = frame(&format!("{} ", side));
+    let right = frame(side);
 
-    println!();
-    println!("{top}");
+    let mut out = vec![frame(&format!("{}{}{}", top_left, border, top_right))];
     for line in lines {
-        let visible = measure_text_width(&line);
-        let pad = max_width - visible; // includes the one space after left border
-        // build row: [blue left] + [colored line] + [padding spaces] + [blue right]
-        println!("{}{}{}{}", left, line, " ".repeat(pad - 1), right);
+        let pad = max_width - measure_text_width(&line) - 1;
+        out.push(format!("{}{}{}{}", left, line, " ".repeat(pad), right));
     }
-    println!("{bottom}");
-    println!();
+    out.push(frame(&format!("{}{}{}", bottom_left, border, bottom_right)));
+    out
 }
 
-/// Constructs the lines of text for the commit‑rewrite banner.
+/// Prints `banner` in `theme` to standard error, with a blank line before
+/// and after.
+fn print(banner: &Banner, theme: &Theme) {
+    eprintln!();
+    for line in framed(banner.lines(theme), theme) {
+        eprintln!("{}", line);
+    }
+    eprintln!();
+}
+
+/// Prints the banner describing the rebase: the mode, how identities will be
+/// rewritten and what the tool does next.
 ///
-/// Returns each banner line as a `String`, in the order they should be displayed:
-/// 1) title, 2) mode instructions (manual/auto), 3) author summary, 4) steps.
+/// By default the banner is framed with box-drawing characters (`╔═╗`,
+/// `║ ║`, `╚═╝`) in blue, with the auto mode instructions in cyan and the
+/// manual ones in yellow; `theme` changes the frame and colors, or shrinks
+/// the banner to one line.
 ///
-/// **Note:** This function **may include ANSI styling** in some lines:
-/// - In manual mode, the instruction lines are yellow + bold.
-/// - In auto mode, the instruction lines are cyan (first bold).
-/// Consumers that need accurate width calculations should measure **visible**
-/// width (e.g., with `console::measure_text_width`) rather than `str::len()`.
+/// The banner goes to standard error, like all of the tool's chatter.
 ///
 /// # Parameters
 ///
-/// * `name` – The new author name to embed in the banner text.
-/// * `email` – The new author email to embed in the banner text.
-/// * `manual_mode` – When `true`, includes highlighted manual instructions;
-///   when `false`, includes highlighted automatic instructions.
+/// * `mapping` – How commit identities will be rewritten, summarized in the banner.
+/// * `manual_mode` – When `true`, the banner shows manual rebase instructions;
+///   otherwise it shows the automatic mode instructions.
+/// * `theme` – Frame, layout and colors, usually from [`Theme::from_config`].
 ///
-/// # Returns
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::banner::{print_banner, Theme};
+/// use git_author_rewrite::identity::{Identity, Mapping};
 ///
-/// A vector of `String` values (some may contain ANSI escape codes for color).
+/// fn main() {
+///     let mapping = Mapping::Fixed(Identity::new("John Doe", "john@example.com"));
+///     print_banner(&mapping, false, &Theme::default());
+/// }
+/// ```
+pub fn print_banner(mapping: &Mapping, manual_mode: bool, theme: &Theme) {
+    print(&rebase_banner(mapping, manual_mode), theme);
+}
+
+/// Prints the banner shown by `--replace-mode` instead of [`print_banner`].
 ///
-/// # Usage
+/// It explains that history is left alone and the corrections live in
+/// `refs/replace/`.
 ///
-/// Intended for use by [`print_banner`](crate::banner::print_banner), which
-/// applies box borders and handles width/padding correctly for styled content.
+/// # Parameters
 ///
-/// # Examples
+/// * `mapping` – How commit identities will be rewritten, summarized in the banner.
+/// * `theme` – Frame, layout and colors.
+pub fn print_replace_banner(mapping: &Mapping, theme: &Theme) {
+    print(&replace_banner(mapping), theme);
+}
+
+/// Prints the banner shown by the metadata engine (the default) instead of
+/// [`print_banner`].
 ///
-/// ```ignore
-/// // Example only; function may return ANSI-styled strings.
-/// let lines = banner_lines("John Doe", "john@example.com", false);
-/// assert!(lines.iter().any(|l| l.contains("John Doe")));
-/// assert!(lines.iter().any(|l| l.contains("Auto mode")));
-/// ```
-fn banner_lines(name: &str, email: &str, manual_mode: bool) -> Vec<String> {
-    let top = ["Rewrite commit authors via interactive rebase", ""]
-        .into_iter()
-        .map(|s| s.to_string());
-
-    let mode = if manual_mode {
-        vec![
-            style("Manual mode: you'll edit the todo list yourself.")
-                .yellow()
-                .bold()
-                .to_string(),
-            style("Tip: mark commits you want to change as `edit`.")
-                .yellow()
-                .bold()
-                .to_string(),
-        ]
+/// # Parameters
+///
+/// * `mapping` – How commit identities will be rewritten, summarized in the banner.
+/// * `theme` – Frame, layout and colors.
+pub fn print_metadata_banner(mapping: &Mapping, theme: &Theme) {
+    print(&metadata_banner(mapping), theme);
+}
+
+/// Prints the banner shown by `--engine gix` instead of [`print_banner`].
+///
+/// # Parameters
+///
+/// * `mapping` – How commit identities will be rewritten, summarized in the banner.
+/// * `theme` – Frame, layout and colors.
+pub fn print_gix_banner(mapping: &Mapping, theme: &Theme) {
+    print(&gix_banner(mapping), theme);
+}
+
+/// The banner of the rebase, in manual or auto mode.
+fn rebase_banner(mapping: &Mapping, manual_mode: bool) -> Banner {
+    let (mode, tone, instructions) = if manual_mode {
+        (
+            "manual mode",
+            Tone::Warning,
+            [
+                ("Manual mode: you'll edit the todo list yourself.", true),
+                ("Tip: mark commits you want to change as `edit`.", true),
+            ],
+        )
     } else {
-        vec![
-            style("Auto mode: all `pick` lines will be changed to `edit`.")
-                .cyan()
-                .bold()
-                .to_string(),
-            style("(Use --manual to keep your editor and control which commits to edit.)")
-                .cyan()
-                .to_string(),
-        ]
-    }
-    .into_iter();
-
-    let bottom = iter::once(String::new())
-        .chain(iter::once(format!(
-            "New author will be set to: {} <{}>",
-            name, email
-        )))
-        .chain(
+        (
+            "auto mode",
+            Tone::Accent,
             [
-                "This tool will automatically:",
-                "  1) Amend each stop with the new author",
-                "  2) Run `git rebase --continue` until finished",
-            ]
-            .into_iter()
-            .map(|s| s.to_string()),
-        );
+                (
+                    "Auto mode: all `pick` lines will be changed to `edit`.",
+                    true,
+                ),
+                (
+                    "(Use --manual to keep your editor and control which commits to edit.)",
+                    false,
+                ),
+            ],
+        )
+    };
+    Banner {
+        title: "Rewrite commit authors via interactive rebase",
+        mode,
+        tone,
+        instructions,
+        mapping: mapping.describe(),
+        notes: &[
+            "This tool will automatically:",
+            "  1) Amend each stop with the new author",
+            "  2) Run `git rebase --continue` until finished",
+        ],
+    }
+}
 
-    top.chain(mode).chain(bottom).collect()
+/// The `--replace-mode` banner (see [`print_replace_banner`]).
+fn replace_banner(mapping: &Mapping) -> Banner {
+    Banner {
+        title: "Correct commit authors with `git replace`",
+        mode: "replace mode",
+        tone: Tone::Accent,
+        instructions: [
+            (
+                "Replace mode: history is not rewritten; nothing needs a force-push.",
+                true,
+            ),
+            (
+                "Git shows corrected copies of the commits via refs/replace/.",
+                false,
+            ),
+        ],
+        mapping: mapping.describe(),
+        notes: &[
+            "Undo with `git replace -d <commit>`; share with",
+            "`git push <remote> 'refs/replace/*'`.",
+        ],
+    }
+}
+
+/// The metadata engine's banner (see [`print_metadata_banner`]).
+fn metadata_banner(mapping: &Mapping) -> Banner {
+    Banner {
+        title: "Rewrite commit authors, keeping every tree",
+        mode: "metadata mode",
+        tone: Tone::Accent,
+        instructions: [
+            (
+                "Only authors, committers and messages change; files stay byte for byte the same.",
+                true,
+            ),
+            (
+                "Nothing can conflict; hooks do not run and commits are not signed.",
+                false,
+            ),
+        ],
+        mapping: mapping.describe(),
+        notes: &[
+            "The branch moves once every commit is written;",
+            "its reflog keeps the previous tip.",
+        ],
+    }
+}
+
+/// The `--engine gix` banner (see [`print_gix_banner`]).
+fn gix_banner(mapping: &Mapping) -> Banner {
+    Banner {
+        title: "Rewrite commit authors with gitoxide",
+        mode: "experimental",
+        tone: Tone::Warning,
+        instructions: [
+            (
+                "Experimental: commits are rewritten in-process, without a rebase.",
+                true,
+            ),
+            (
+                "Trees and dates are kept; hooks do not run and commits are not signed.",
+                false,
+            ),
+        ],
+        mapping: mapping.describe(),
+        notes: &[
+            "The branch moves once every commit is written;",
+            "its reflog keeps the previous tip.",
+        ],
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::banner_lines;
+    use super::{
+        framed, gix_banner, metadata_banner, parse_color, rebase_banner, replace_banner, Border,
+        Layout, Theme,
+    };
+    use crate::identity::{Identity, Mapping};
+    use console::Color;
 
     #[test]
     fn banner_auto_mode_lines_and_width_are_correct() {
-        let lines = banner_lines("John Doe", "john@doe.org", false);
+        let mapping = Mapping::Fixed(Identity::new("John Doe", "john@doe.org"));
+        let lines = rebase_banner(&mapping, false).lines(&Theme::default());
         let s = lines.join("\n");
 
         assert!(s.contains("Rewrite commit authors via interactive rebase"));
@@ -173,7 +545,8 @@ mod tests {
 
     #[test]
     fn banner_manual_mode_lines_and_width_are_correct() {
-        let lines = banner_lines("Jane", "jane@example.com", true);
+        let mapping = Mapping::Fixed(Identity::new("Jane", "jane@example.com"));
+        let lines = rebase_banner(&mapping, true).lines(&Theme::default());
         let s = lines.join("\n");
 
         assert!(s.contains("Manual mode: you'll edit the todo list yourself."));
@@ -184,4 +557,126 @@ mod tests {
 
         assert!(max_line >= "Rewrite commit authors via interactive rebase".len());
     }
+
+    #[test]
+    fn banner_describes_domain_swap() {
+        let mapping = Mapping::Domain {
+            from: String::from("old.com"),
+            to: String::from("new.com"),
+        };
+        let lines = rebase_banner(&mapping, false).lines(&Theme::default());
+        assert!(lines
+            .iter()
+            .any(|l| l == "Author/committer emails @old.com will become @new.com"));
+    }
+
+    #[test]
+    fn replace_banner_explains_history_is_kept() {
+        let mapping = Mapping::Fixed(Identity::new("Jane", "jane@example.com"));
+        let s = replace_banner(&mapping).lines(&Theme::default()).join("\n");
+        assert!(s.contains("history is not rewritten"));
+        assert!(s.contains("New author will be set to: Jane <jane@example.com>"));
+    }
+
+    #[test]
+    fn metadata_banner_promises_identical_trees() {
+        let mapping = Mapping::Fixed(Identity::new("Jane", "jane@example.com"));
+        let s = metadata_banner(&mapping)
+            .lines(&Theme::default())
+            .join("\n");
+        assert!(s.contains("files stay byte for byte the same"));
+        assert!(s.contains("Nothing can conflict"));
+        assert!(s.contains("New author will be set to: Jane <jane@example.com>"));
+    }
+
+    #[test]
+    fn gix_banner_warns_about_hooks_and_signing() {
+        let mapping = Mapping::Fixed(Identity::new("Jane", "jane@example.com"));
+        let s = gix_banner(&mapping).lines(&Theme::default()).join("\n");
+        assert!(s.contains("Experimental"));
+        assert!(s.contains("hooks do not run and commits are not signed"));
+        assert!(s.contains("New author will be set to: Jane <jane@example.com>"));
+    }
+
+    #[test]
+    fn theme_reads_the_config_keys() {
+        let theme = Theme::from_config(|key| {
+            match key {
+                "author-rewrite.banner.border" => "Rounded",
+                "author-rewrite.banner.borderColor" => "208",
+                "author-rewrite.banner.accentColor" => "none",
+                _ => "",
+            }
+            .to_string()
+        })
+        .unwrap();
+        assert_eq!(theme.border, Border::Rounded);
+        assert_eq!(theme.layout, Layout::Full);
+        assert_eq!(theme.border_color, Some(Color::Color256(208)));
+        assert_eq!(theme.accent_color, None);
+        assert_eq!(theme.warning_color, Some(Color::Yellow));
+
+        let err = Theme::from_config(|key| {
+            if key == "author-rewrite.banner.warningColor" {
+                String::from("chartreuse")
+            } else {
+                String::new()
+            }
+        })
+        .unwrap_err();
+        assert!(
+            err.starts_with("invalid author-rewrite.banner.warningColor: unknown color"),
+            "{}",
+            err
+        );
+        assert!(parse_color("256").is_err());
+        assert!(Border::parse("dotted").is_err());
+    }
+
+    #[test]
+    fn framed_uses_the_border_or_none() {
+        let theme = Theme {
+            border: Border::Ascii,
+            border_color: None,
+            ..Theme::default()
+        };
+        let lines = vec![String::from("Title"), String::from("Longer line")];
+        let plain = |lines: Vec<String>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|l| console::strip_ansi_codes(l).to_string())
+                .collect()
+        };
+        assert_eq!(
+            plain(framed(lines.clone(), &theme)),
+            vec![
+                "+-------------+",
+                "| Title       |",
+                "| Longer line |",
+                "+-------------+"
+            ]
+        );
+
+        let bare = Theme {
+            border: Border::None,
+            ..theme
+        };
+        assert_eq!(framed(lines, &bare), vec!["Title", "Longer line"]);
+    }
+
+    #[test]
+    fn minimal_layout_is_one_line() {
+        let mapping = Mapping::Fixed(Identity::new("Jane", "jane@example.com"));
+        let theme = Theme {
+            layout: Layout::Minimal,
+            ..Theme::default()
+        };
+        let lines = framed(rebase_banner(&mapping, true).lines(&theme), &theme);
+        assert_eq!(lines.len(), 1);
+        let line = console::strip_ansi_codes(&lines[0]).to_string();
+        assert_eq!(
+            line,
+            "Rewrite commit authors via interactive rebase (manual mode): New author will be set to: Jane <jane@example.com>"
+        );
+    }
 }
@@ -0,0 +1,463 @@
+use crate::{
+    args::Options,
+    exit::{EXIT_DCO_FAILED, EXIT_FSCK_FAILED, EXIT_NOTHING_TO_DO},
+};
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    path::{self, Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+/// How the rewrite of one repository in a batch ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The rewrite completed.
+    Rewritten,
+    /// The repository had no commits, or none were selected.
+    NothingToDo,
+    /// The rewrite failed; the reason is shown in the summary.
+    Failed(String),
+}
+
+/// The outcome of rewriting one repository of a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoOutcome {
+    /// The repository path, as listed.
+    pub path: PathBuf,
+    /// How its rewrite ended.
+    pub outcome: Outcome,
+}
+
+/// Parses a repository list, one path per line.
+///
+/// Blank lines and lines starting with `#` are ignored; surrounding
+/// whitespace is trimmed.
+pub(crate) fn parse_repo_list(body: &str) -> Vec<PathBuf> {
+    body.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Reads a repository list from `source`, which is a file path or `-` for stdin.
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` with the listed repositories.
+/// * `Err(String)` if the source could not be read.
+pub fn read_repo_list(source: &str) -> Result<Vec<PathBuf>, String> {
+    if source == "-" {
+        let mut body = String::new();
+        return match io::stdin().read_to_string(&mut body) {
+            Ok(_) => Ok(parse_repo_list(&body)),
+            Err(e) => Err(format!("cannot read repository list from stdin: {}", e)),
+        };
+    }
+
+    match fs::read_to_string(source) {
+        Ok(body) => Ok(parse_repo_list(&body)),
+        Err(e) => Err(format!("cannot read repository list {}: {}", source, e)),
+    }
+}
+
+/// Builds the command line each repository's rewrite is run with.
+///
+/// The options are passed on fully resolved (a `--spec` file or `GAR_*`
+/// variables already merged in), always with `--yes` since nobody can answer
+/// prompts for forty repositories.
+pub fn child_args(opts: &Options) -> Vec<String> {
+    let mut out = Vec::new();
+    // Children run inside each repository, so a relative rules, sha-map or
+    // audit log path would point somewhere else.
+    let absolute = |file: &Option<String>| {
+        file.as_ref().map(|r| match path::absolute(r) {
+            Ok(p) => p.display().to_string(),
+            Err(_) => r.clone(),
+        })
+    };
+    let rules = absolute(&opts.rules);
+    let sha_map = absolute(&opts.sha_map);
+    let audit_log = absolute(&opts.audit_log);
+    let values = [
+        ("--revs", &opts.revs),
+        ("--rewrite-domain", &opts.rewrite_domain),
+        ("--rules", &rules),
+        ("--sha-map", &sha_map),
+        ("--audit-log", &audit_log),
+        ("--name", &opts.name),
+        ("--email", &opts.email),
+        ("--profile", &opts.profile),
+    ];
+    for (flag, value) in values {
+        if let Some(v) = value {
+            out.push(flag.to_string());
+            out.push(v.clone());
+        }
+    }
+    for expr in &opts.message_replace {
+        out.push(String::from("--message-replace"));
+        out.push(expr.clone());
+    }
+    for email in &opts.drop_author {
+        out.push(String::from("--drop-author"));
+        out.push(email.clone());
+    }
+    if let Some(name) = &opts.engine {
+        out.push(String::from("--engine"));
+        out.push(name.clone());
+    }
+    for command in &opts.exec {
+        out.push(String::from("--exec"));
+        out.push(command.clone());
+    }
+    if let Some(size) = opts.chunk_size {
+        out.push(String::from("--chunk-size"));
+        out.push(size.to_string());
+    }
+    if let Some(secs) = opts.timeout {
+        out.push(String::from("--timeout"));
+        out.push(secs.to_string());
+    }
+
+    let flags = [
+        ("--name-only", opts.name_only),
+        ("--email-only", opts.email_only),
+        ("--force-pushed-history", opts.force_pushed_history),
+        ("--allow-protected", opts.allow_protected),
+        ("--force", opts.force),
+        ("--skip-merges", opts.skip_merges),
+        ("--all-branches", opts.all_branches),
+        ("--sync-committer", opts.sync_committer),
+        ("--global", opts.global),
+        ("--no-config-update", opts.no_config_update),
+        ("--fsck", opts.fsck),
+        ("--check-github", opts.check_github),
+        ("--check-dco", opts.check_dco),
+        ("--skip-on-conflict", opts.skip_on_conflict),
+        ("--cleanup", opts.cleanup),
+        ("--backup-refs", opts.backup_refs),
+        ("--replace-mode", opts.replace_mode),
+        ("--rewrite-tags", opts.rewrite_tags),
+        ("--sign-tags", opts.sign_tags),
+        ("--annotate", opts.annotate),
+        ("--record-original-sha", opts.record_original_sha),
+        ("--no-verify", opts.no_verify),
+        ("--verify", opts.verify),
+        ("--gpg-sign", opts.gpg_sign),
+        ("--no-gpg-sign", opts.no_gpg_sign),
+        ("--show-commands", opts.show_commands),
+        ("--quiet", opts.quiet),
+    ];
+    for (flag, set) in flags {
+        if set {
+            out.push(flag.to_string());
+        }
+    }
+
+    out.push(String::from("--yes"));
+    out
+}
+
+/// Maps a child's exit code onto an [`Outcome`].
+pub(crate) fn classify(code: Option<i32>) -> Outcome {
+    match code {
+        Some(0) => Outcome::Rewritten,
+        Some(EXIT_NOTHING_TO_DO) => Outcome::NothingToDo,
+        Some(EXIT_FSCK_FAILED) => Outcome::Failed(String::from("git fsck reported problems")),
+        Some(EXIT_DCO_FAILED) => {
+            Outcome::Failed(String::from("commits lack a sign-off by their author"))
+        }
+        Some(n) => Outcome::Failed(format!("exited with status {}", n)),
+        None => Outcome::Failed(String::from("terminated by a signal")),
+    }
+}
+
+/// Rewrites one repository by running `exe` with `args` inside it.
+///
+/// The child's output goes straight to the terminal; its stdin is closed so
+/// a forgotten prompt fails instead of hanging the batch.
+fn run_one(exe: &Path, repo: &Path, args: &[String]) -> Outcome {
+    if !repo.is_dir() {
+        return Outcome::Failed(String::from("not a directory"));
+    }
+
+    let mut cmd = Command::new(exe);
+    cmd.args(args).current_dir(repo).stdin(Stdio::null());
+    // Each child finds its own repository from its directory.
+    cmd.env_remove("GIT_DIR").env_remove("GIT_WORK_TREE");
+    match cmd.status() {
+        Ok(status) => classify(status.code()),
+        Err(e) => Outcome::Failed(format!("cannot start: {}", e)),
+    }
+}
+
+/// Prefixes one line of a child's output with its repository.
+pub(crate) fn prefixed(repo: &Path, line: &str) -> String {
+    format!("[{}] {}", repo.display(), line)
+}
+
+/// Like [`run_one`], but prints the child's output line by line, prefixed
+/// with the repository, so concurrent runs can be told apart.
+fn run_one_prefixed(exe: &Path, repo: &Path, args: &[String]) -> Outcome {
+    if !repo.is_dir() {
+        return Outcome::Failed(String::from("not a directory"));
+    }
+
+    let mut cmd = Command::new(exe);
+    cmd.args(args)
+        .current_dir(repo)
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => return Outcome::Failed(format!("cannot start: {}", e)),
+    };
+
+    // Whole lines are printed at once, so output interleaves only between lines.
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|scope| {
+        if let Some(out) = stdout {
+            scope.spawn(move || {
+                for line in BufReader::new(out).lines().map_while(Result::ok) {
+                    println!("{}", prefixed(repo, &line));
+                }
+            });
+        }
+        if let Some(err) = stderr {
+            scope.spawn(move || {
+                for line in BufReader::new(err).lines().map_while(Result::ok) {
+                    eprintln!("{}", prefixed(repo, &line));
+                }
+            });
+        }
+    });
+
+    match child.wait() {
+        Ok(status) => classify(status.code()),
+        Err(e) => Outcome::Failed(format!("{}", e)),
+    }
+}
+
+/// Runs the same rewrite in each of `repos`, one after another.
+///
+/// A failure in one repository does not stop the batch.
+///
+/// # Parameters
+///
+/// * `exe` – The `git-author-rewrite` binary to run in each repository.
+/// * `repos` – The repositories, in order.
+/// * `args` – The command line for each run, from [`child_args`].
+/// * `on_start` – Called before each repository, e.g. to print a header.
+///
+/// # Returns
+///
+/// One [`RepoOutcome`] per repository, in the order given.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it runs git-author-rewrite in real repositories.
+/// use git_author_rewrite::batch;
+/// use std::{env, path::PathBuf};
+///
+/// let exe = env::current_exe().unwrap();
+/// let repos = vec![PathBuf::from("../api"), PathBuf::from("../web")];
+/// let args = vec![String::from("--rewrite-domain"), String::from("old.com=new.com"), String::from("--yes")];
+/// let outcomes = batch::run_batch(&exe, &repos, &args, |repo| println!("==> {}", repo.display()));
+/// ```
+pub fn run_batch<F: Fn(&Path)>(
+    exe: &Path,
+    repos: &[PathBuf],
+    args: &[String],
+    on_start: F,
+) -> Vec<RepoOutcome> {
+    repos
+        .iter()
+        .map(|repo| {
+            on_start(repo);
+            RepoOutcome {
+                path: repo.clone(),
+                outcome: run_one(exe, repo, args),
+            }
+        })
+        .collect()
+}
+
+/// Runs the same rewrite in each of `repos`, up to `jobs` at a time.
+///
+/// Each repository's rebase is independent, so they run on a pool of worker
+/// threads; their output is prefixed with the repository (see
+/// [`prefixed`]). A failure in one repository does not stop the batch.
+///
+/// # Parameters
+///
+/// * `exe` – The `git-author-rewrite` binary to run in each repository.
+/// * `repos` – The repositories.
+/// * `args` – The command line for each run, from [`child_args`].
+/// * `jobs` – The most repositories rewritten at once (at least 1).
+///
+/// # Returns
+///
+/// One [`RepoOutcome`] per repository, in the order given (not the order
+/// they finished in).
+pub fn run_batch_parallel(
+    exe: &Path,
+    repos: &[PathBuf],
+    args: &[String],
+    jobs: usize,
+) -> Vec<RepoOutcome> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Outcome>>> = Mutex::new(vec![None; repos.len()]);
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, repos.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let repo = match repos.get(i) {
+                    Some(r) => r,
+                    None => break,
+                };
+                let outcome = run_one_prefixed(exe, repo, args);
+                if let Ok(mut slots) = results.lock() {
+                    slots[i] = Some(outcome);
+                }
+            });
+        }
+    });
+
+    let slots = match results.into_inner() {
+        Ok(s) => s,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    repos
+        .iter()
+        .zip(slots)
+        .map(|(repo, outcome)| RepoOutcome {
+            path: repo.clone(),
+            outcome: outcome
+                .unwrap_or_else(|| Outcome::Failed(String::from("worker thread panicked"))),
+        })
+        .collect()
+}
+
+/// Formats the end-of-batch summary, one line per repository, without styling.
+pub fn summary_lines(outcomes: &[RepoOutcome]) -> Vec<String> {
+    outcomes
+        .iter()
+        .map(|o| {
+            let status = match &o.outcome {
+                Outcome::Rewritten => String::from("rewritten"),
+                Outcome::NothingToDo => String::from("nothing to do"),
+                Outcome::Failed(reason) => format!("FAILED ({})", reason),
+            };
+            format!("{}: {}", o.path.display(), status)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        child_args, classify, parse_repo_list, prefixed, summary_lines, Outcome, RepoOutcome,
+    };
+    use crate::args::Options;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn parse_repo_list_skips_blanks_and_comments() {
+        let repos = parse_repo_list("# work\n  ../api  \n\n/src/web\n");
+        assert_eq!(
+            repos,
+            vec![PathBuf::from("../api"), PathBuf::from("/src/web")]
+        );
+    }
+
+    #[test]
+    fn child_args_pass_resolved_options_and_yes() {
+        let opts = Options {
+            rewrite_domain: Some(String::from("old.com=new.com")),
+            message_replace: vec![String::from("s/a/b/")],
+            fsck: true,
+            no_verify: true,
+            repos_file: Some(String::from("repos.txt")),
+            spec: Some(String::from("job.json")),
+            ..Options::default()
+        };
+        assert_eq!(
+            child_args(&opts),
+            vec![
+                "--rewrite-domain",
+                "old.com=new.com",
+                "--message-replace",
+                "s/a/b/",
+                "--fsck",
+                "--no-verify",
+                "--yes"
+            ]
+        );
+    }
+
+    #[test]
+    fn child_args_make_file_paths_absolute() {
+        let opts = Options {
+            rules: Some(String::from("authors.rules")),
+            audit_log: Some(String::from("audit.jsonl")),
+            ..Options::default()
+        };
+        let args = child_args(&opts);
+        assert_eq!(args[0], "--rules");
+        assert!(Path::new(&args[1]).is_absolute());
+        assert!(args[1].ends_with("authors.rules"));
+        assert_eq!(args[2], "--audit-log");
+        assert!(Path::new(&args[3]).is_absolute());
+    }
+
+    #[test]
+    fn classify_maps_exit_codes() {
+        assert_eq!(classify(Some(0)), Outcome::Rewritten);
+        assert_eq!(classify(Some(3)), Outcome::NothingToDo);
+        assert_eq!(
+            classify(Some(6)),
+            Outcome::Failed(String::from("commits lack a sign-off by their author"))
+        );
+        assert_eq!(
+            classify(Some(1)),
+            Outcome::Failed(String::from("exited with status 1"))
+        );
+    }
+
+    #[test]
+    fn prefixed_names_the_repository() {
+        assert_eq!(prefixed(Path::new("../api"), "✅ Done"), "[../api] ✅ Done");
+    }
+
+    #[test]
+    fn summary_lists_every_repository() {
+        let outcomes = vec![
+            RepoOutcome {
+                path: PathBuf::from("api"),
+                outcome: Outcome::Rewritten,
+            },
+            RepoOutcome {
+                path: PathBuf::from("gone"),
+                outcome: Outcome::Failed(String::from("not a directory")),
+            },
+        ];
+        assert_eq!(
+            summary_lines(&outcomes),
+            vec!["api: rewritten", "gone: FAILED (not a directory)"]
+        );
+    }
+}
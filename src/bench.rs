@@ -0,0 +1,347 @@
+use crate::{
+    batch::{self, Outcome},
+    engine::EngineKind,
+    git,
+};
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// The identity every benchmark run rewrites the history to.
+const BENCH_NAME: &str = "Bench Mark";
+const BENCH_EMAIL: &str = "bench@example.invalid";
+
+/// How one engine did on the benchmark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    /// The engine that ran.
+    pub engine: EngineKind,
+    /// How its rewrite ended.
+    pub outcome: Outcome,
+    /// Wall-clock time of the rewrite, without cloning.
+    pub elapsed: Duration,
+    /// The peak resident memory of the largest process of the run (the tool
+    /// or one of the `git` commands it started), in bytes; `None` where the
+    /// platform does not report it.
+    pub peak_memory: Option<u64>,
+}
+
+/// Builds the command line of a benchmark run with `engine`: every commit is
+/// rewritten to a fixed identity, without prompts, hooks or signing.
+pub(crate) fn child_args(engine: EngineKind) -> Vec<String> {
+    [
+        "--engine",
+        engine.name(),
+        "--name",
+        BENCH_NAME,
+        "--email",
+        BENCH_EMAIL,
+        "--force-pushed-history",
+        "--force",
+        "--no-verify",
+        "--no-gpg-sign",
+        "--yes",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Returns the temporary clone path for `engine` in this run.
+fn workdir(engine: EngineKind) -> PathBuf {
+    env::temp_dir().join(format!(
+        "git-author-rewrite-bench-{}-{}",
+        process::id(),
+        engine.name()
+    ))
+}
+
+/// Converts `ru_maxrss` to bytes; Apple platforms report bytes, the others
+/// kilobytes.
+#[cfg(unix)]
+fn max_rss_bytes(max_rss: libc::c_long) -> u64 {
+    let value = max_rss.max(0) as u64;
+    if cfg!(target_vendor = "apple") {
+        value
+    } else {
+        value * 1024
+    }
+}
+
+/// Waits for `child`, returning its exit code and peak memory.
+#[cfg(unix)]
+fn wait_measured(child: &mut Child) -> Result<(Option<i32>, Option<u64>), String> {
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    // SAFETY: `rusage` is plain data, valid when zeroed, and `pid` is a child
+    // of this process that has not been waited for.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        let waited = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if waited == pid {
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err.to_string());
+        }
+    }
+    let code = if libc::WIFEXITED(status) {
+        Some(libc::WEXITSTATUS(status))
+    } else {
+        None
+    };
+    Ok((code, Some(max_rss_bytes(usage.ru_maxrss))))
+}
+
+/// Waits for `child`, returning its exit code; the peak memory is unknown.
+#[cfg(not(unix))]
+fn wait_measured(child: &mut Child) -> Result<(Option<i32>, Option<u64>), String> {
+    match child.wait() {
+        Ok(status) => Ok((status.code(), None)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Returns the last non-empty line of the log at `path`, if any.
+fn last_log_line(path: &Path) -> Option<String> {
+    let log = match fs::read(path) {
+        Ok(b) => b,
+        Err(_) => return None,
+    };
+    String::from_utf8_lossy(&log)
+        .lines()
+        .map(|l| l.trim())
+        .rfind(|l| !l.is_empty())
+        .map(|l| l.to_string())
+}
+
+/// Rewrites a fresh clone of `source` with `engine`, measuring the run.
+fn run_engine(exe: &Path, source: &str, engine: EngineKind) -> Measurement {
+    let failed = |reason: String| Measurement {
+        engine,
+        outcome: Outcome::Failed(reason),
+        elapsed: Duration::ZERO,
+        peak_memory: None,
+    };
+
+    let dir = workdir(engine);
+    let log = dir.with_extension("log");
+    let _ = fs::remove_dir_all(&dir);
+    if let Err(e) = git::clone(source, &dir) {
+        return failed(format!("cannot clone: {}", e));
+    }
+    let stderr = match fs::File::create(&log) {
+        Ok(f) => f,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&dir);
+            return failed(format!("cannot create {}: {}", log.display(), e));
+        }
+    };
+
+    let mut cmd = Command::new(exe);
+    cmd.args(child_args(engine))
+        .current_dir(&dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(stderr);
+    let started = Instant::now();
+    let waited = match cmd.spawn() {
+        Ok(mut child) => wait_measured(&mut child),
+        Err(e) => Err(format!("cannot start: {}", e)),
+    };
+    let elapsed = started.elapsed();
+
+    let measurement = match waited {
+        Ok((code, peak_memory)) => Measurement {
+            engine,
+            outcome: match (batch::classify(code), last_log_line(&log)) {
+                (Outcome::Failed(reason), Some(line)) => {
+                    Outcome::Failed(format!("{}: {}", reason, line))
+                }
+                (outcome, _) => outcome,
+            },
+            elapsed,
+            peak_memory,
+        },
+        Err(e) => failed(e),
+    };
+    let _ = fs::remove_dir_all(&dir);
+    let _ = fs::remove_file(&log);
+    measurement
+}
+
+/// Rewrites a temporary clone of `source` with each of `engines` in turn,
+/// timing each run; the repository itself is left untouched.
+///
+/// # Parameters
+///
+/// * `exe` – The `git-author-rewrite` binary to run in each clone.
+/// * `source` – The repository to clone, e.g. its top-level directory.
+/// * `engines` – The engines to compare, in order.
+/// * `on_start` – Called before each engine, e.g. to print a header.
+///
+/// # Returns
+///
+/// One [`Measurement`] per engine, in the order given.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it clones and rewrites a real repository.
+/// use git_author_rewrite::{bench, engine::EngineKind};
+/// use std::env;
+///
+/// let exe = env::current_exe().unwrap();
+/// let results = bench::run(&exe, ".", &[EngineKind::Rebase, EngineKind::Gix], |_| {});
+/// for line in bench::table_lines(&results) {
+///     println!("{}", line);
+/// }
+/// ```
+pub fn run<F: Fn(EngineKind)>(
+    exe: &Path,
+    source: &str,
+    engines: &[EngineKind],
+    on_start: F,
+) -> Vec<Measurement> {
+    engines
+        .iter()
+        .map(|&engine| {
+            on_start(engine);
+            run_engine(exe, source, engine)
+        })
+        .collect()
+}
+
+/// Formats a byte count with a binary unit, e.g. `12.5 MiB`.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Formats the results as an aligned table, without styling.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::batch::Outcome;
+/// use git_author_rewrite::bench::{table_lines, Measurement};
+/// use git_author_rewrite::engine::EngineKind;
+/// use std::time::Duration;
+///
+/// let lines = table_lines(&[Measurement {
+///     engine: EngineKind::Gix,
+///     outcome: Outcome::Rewritten,
+///     elapsed: Duration::from_millis(1250),
+///     peak_memory: Some(20 * 1024 * 1024),
+/// }]);
+/// assert_eq!(lines[1], "gix       1.25 s     20.0 MiB  rewritten");
+/// ```
+pub fn table_lines(results: &[Measurement]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:<7}  {:>7}  {:>11}  {}",
+        "Engine", "Time", "Peak memory", "Result"
+    )];
+    for m in results {
+        let (time, memory) = match &m.outcome {
+            Outcome::Failed(_) => (String::from("-"), String::from("-")),
+            _ => (
+                format!("{:.2} s", m.elapsed.as_secs_f64()),
+                m.peak_memory
+                    .map(format_bytes)
+                    .unwrap_or_else(|| String::from("n/a")),
+            ),
+        };
+        let result = match &m.outcome {
+            Outcome::Rewritten => String::from("rewritten"),
+            Outcome::NothingToDo => String::from("nothing to do"),
+            Outcome::Failed(reason) => format!("FAILED ({})", reason),
+        };
+        lines.push(format!(
+            "{:<7}  {:>7}  {:>11}  {}",
+            m.engine.name(),
+            time,
+            memory,
+            result
+        ));
+    }
+    lines
+}
+
+/// Picks the engine that rewrote the history fastest.
+///
+/// # Returns
+///
+/// * `Some(EngineKind)` – The fastest engine that completed.
+/// * `None` if none did.
+pub fn fastest(results: &[Measurement]) -> Option<EngineKind> {
+    results
+        .iter()
+        .filter(|m| m.outcome == Outcome::Rewritten)
+        .min_by_key(|m| m.elapsed)
+        .map(|m| m.engine)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{child_args, fastest, format_bytes, table_lines, Measurement};
+    use crate::{batch::Outcome, engine::EngineKind};
+    use std::time::Duration;
+
+    fn measured(engine: EngineKind, outcome: Outcome, millis: u64) -> Measurement {
+        Measurement {
+            engine,
+            outcome,
+            elapsed: Duration::from_millis(millis),
+            peak_memory: Some(1536),
+        }
+    }
+
+    #[test]
+    fn child_args_rewrite_without_prompts() {
+        let args = child_args(EngineKind::Replace);
+        assert_eq!(&args[..2], ["--engine", "replace"]);
+        assert_eq!(args.last().map(String::as_str), Some("--yes"));
+        assert!(args.contains(&String::from("--force-pushed-history")));
+    }
+
+    #[test]
+    fn format_bytes_picks_a_readable_unit() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn table_and_fastest_skip_failed_runs() {
+        let results = vec![
+            measured(EngineKind::Rebase, Outcome::Rewritten, 3000),
+            measured(
+                EngineKind::Replace,
+                Outcome::Failed(String::from("boom")),
+                10,
+            ),
+            measured(EngineKind::Gix, Outcome::Rewritten, 200),
+        ];
+        let lines = table_lines(&results);
+        assert_eq!(lines[0], "Engine      Time  Peak memory  Result");
+        assert_eq!(lines[1], "rebase    3.00 s      1.5 KiB  rewritten");
+        assert_eq!(lines[2], "replace        -            -  FAILED (boom)");
+        assert_eq!(fastest(&results), Some(EngineKind::Gix));
+        assert_eq!(fastest(&results[1..2]), None);
+    }
+}
@@ -1,10 +1,35 @@
+use git_author_rewrite::error::Error;
+
+use console::style;
+
+/// Prints the error a run ended with to stderr, line by line from
+/// [`Error::lines`]: the error itself in bold red, what the tool was doing
+/// dimmed, then the hints.
+fn report(err: &Error) {
+    let context = err.chain().len();
+    for (i, line) in err.lines().into_iter().enumerate() {
+        let line = style(line).for_stderr();
+        if i == 0 {
+            eprintln!("{}", line.red().bold());
+        } else if i <= context {
+            eprintln!("{}", line.dim());
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+}
+
 /// Entry point for the `git-author-rewrite` binary.
 ///
 /// Delegates to the CLI entry function and exits the process with the
-/// returned exit code. If an error occurs, exits with status code 1.
+/// returned exit code. If an error occurs, prints it and exits with status
+/// code 1.
 fn main() {
     match git_author_rewrite::cli::entry() {
         Ok(code) => std::process::exit(code),
-        Err(_) => std::process::exit(1),
+        Err(err) => {
+            report(&err);
+            std::process::exit(1)
+        }
     }
 }
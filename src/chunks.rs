@@ -0,0 +1,184 @@
+use crate::selection::Selection;
+
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// The checkpoint of a chunked rewrite (`--chunk-size`), kept in the
+/// repository's `.git` directory until the branch has moved.
+pub const CHECKPOINT_FILE: &str = "author-rewrite-chunks.json";
+
+/// How far a chunked rewrite has come.
+///
+/// Each chunk is a rebase of its own, of the commits after the previous
+/// chunk's last commit up to and including `ends[i]`, onto the rewritten
+/// history so far. The branch itself stays at `tip` until every chunk is
+/// done, so an interrupted run only loses the chunk it was working on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The full name of the branch being rewritten, e.g. `refs/heads/main`.
+    pub branch: String,
+    /// The commit the branch pointed at when the rewrite started.
+    pub tip: String,
+    /// The last original commit of every chunk, oldest first; the last one is `tip`.
+    pub ends: Vec<String>,
+    /// How many chunks are rewritten.
+    pub done: usize,
+    /// The rewritten commit of the last chunk done, once there is one.
+    pub rewritten: Option<String>,
+    /// The selected commits, or `None` for all of them.
+    pub selection: Option<Vec<String>>,
+}
+
+impl Checkpoint {
+    /// Starts a checkpoint for rewriting `commits` (oldest first) of `branch`
+    /// in chunks of `size` commits.
+    pub fn new(branch: &str, commits: &[String], size: usize, selection: &Selection) -> Checkpoint {
+        let ends = plan(commits, size);
+        Checkpoint {
+            branch: branch.to_string(),
+            tip: ends.last().cloned().unwrap_or_default(),
+            ends,
+            done: 0,
+            rewritten: None,
+            selection: match selection {
+                Selection::All => None,
+                Selection::Commits(shas) => Some(shas.clone()),
+            },
+        }
+    }
+
+    /// The commits to rewrite, as the checkpoint recorded them.
+    pub fn selection(&self) -> Selection {
+        match &self.selection {
+            Some(shas) => Selection::Commits(shas.clone()),
+            None => Selection::All,
+        }
+    }
+
+    /// Reports whether every chunk is rewritten.
+    pub fn finished(&self) -> bool {
+        self.done >= self.ends.len()
+    }
+}
+
+/// Splits `commits` (oldest first) into chunks of at most `size` commits
+/// and returns the last commit of each. A `size` of 0 counts as 1.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::chunks::plan;
+///
+/// let commits: Vec<String> = ["a", "b", "c", "d", "e"].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(plan(&commits, 2), vec!["b", "d", "e"]);
+/// ```
+pub fn plan(commits: &[String], size: usize) -> Vec<String> {
+    commits
+        .chunks(size.max(1))
+        .filter_map(|chunk| chunk.last().cloned())
+        .collect()
+}
+
+/// Reads the checkpoint left in `git_dir`, if any.
+///
+/// # Returns
+///
+/// * `Ok(Some(Checkpoint))` if a chunked rewrite is unfinished.
+/// * `Ok(None)` if there is no checkpoint.
+/// * `Err(String)` if the file could not be read or parsed.
+pub fn load(git_dir: &Path) -> Result<Option<Checkpoint>, String> {
+    let path = git_dir.join(CHECKPOINT_FILE);
+    let body = match fs::read_to_string(&path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("cannot read {}: {}", path.display(), e)),
+    };
+    match serde_json::from_str(&body) {
+        Ok(c) => Ok(Some(c)),
+        Err(e) => Err(format!("cannot parse {}: {}", path.display(), e)),
+    }
+}
+
+/// Writes `checkpoint` to `git_dir`, through a temporary file renamed over
+/// the old one, so a crash leaves either the old checkpoint or the new one.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if the file could not be written.
+pub fn save(git_dir: &Path, checkpoint: &Checkpoint) -> Result<(), String> {
+    let path = git_dir.join(CHECKPOINT_FILE);
+    let temp = git_dir.join(format!("{}.tmp", CHECKPOINT_FILE));
+    let body = match serde_json::to_string_pretty(checkpoint) {
+        Ok(b) => b,
+        Err(e) => return Err(format!("cannot encode the checkpoint: {}", e)),
+    };
+    if let Err(e) = fs::write(&temp, body) {
+        return Err(format!("cannot write {}: {}", temp.display(), e));
+    }
+    match fs::rename(&temp, &path) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("cannot write {}: {}", path.display(), e)),
+    }
+}
+
+/// Removes the checkpoint from `git_dir` once the branch has moved.
+///
+/// Failures are ignored: the next run reports a leftover checkpoint, and
+/// `--resume` finds its branch already rewritten.
+pub fn remove(git_dir: &Path) {
+    let _ = fs::remove_file(git_dir.join(CHECKPOINT_FILE));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, plan, remove, save, Checkpoint, CHECKPOINT_FILE};
+    use crate::selection::Selection;
+
+    fn shas(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn plan_ends_every_chunk_and_the_history() {
+        let commits = shas(&["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(plan(&commits, 3), shas(&["c", "f"]));
+        assert_eq!(plan(&commits, 4), shas(&["d", "f"]));
+        assert_eq!(plan(&commits, 10), shas(&["f"]));
+        assert_eq!(plan(&commits, 0).len(), 6);
+        assert!(plan(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn checkpoint_survives_a_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let selection = Selection::Commits(shas(&["b", "e"]));
+        let mut checkpoint = Checkpoint::new(
+            "refs/heads/main",
+            &shas(&["a", "b", "c", "d", "e"]),
+            2,
+            &selection,
+        );
+        assert_eq!(checkpoint.tip, "e");
+        assert!(!checkpoint.finished());
+        checkpoint.done = 3;
+        checkpoint.rewritten = Some(String::from("f00"));
+
+        save(dir.path(), &checkpoint).unwrap();
+        let loaded = load(dir.path()).unwrap().expect("no checkpoint");
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(loaded.selection(), selection);
+        assert!(loaded.finished());
+
+        remove(dir.path());
+        assert_eq!(load(dir.path()), Ok(None));
+    }
+
+    #[test]
+    fn load_reports_a_corrupt_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(CHECKPOINT_FILE), "{\"branch\":").unwrap();
+        let err = load(dir.path()).unwrap_err();
+        assert!(err.starts_with("cannot parse"), "{}", err);
+    }
+}
@@ -1,61 +1,400 @@
-use crate::{banner::print_banner, git, prompt, sequence_editor};
+use crate::{
+    args, audit, backup,
+    banner::{self, print_banner, print_gix_banner, print_metadata_banner, print_replace_banner},
+    batch, bench,
+    chunks::{self, Checkpoint},
+    commit_object::CommitObject,
+    dco, diagnose, discover,
+    engine::{self, Engine, EngineKind, Job},
+    error::Error,
+    git::{self, Repo},
+    git_version,
+    github::{self, Verdict},
+    gix_backend,
+    history::{self, RefUpdate},
+    hook::{self, HookKind},
+    html_report,
+    identity::{self, CommitIdentities, Identity, Mapping},
+    include_if, lfs, lock,
+    message::{self, MessageEdits},
+    mirror, noreply, policy, preflight, preview,
+    profile::{self, Profile},
+    progress::{self, Progress, StallCheck},
+    prompt::{self, LeftoverRebase},
+    protected, replace, report, rules,
+    runner::{self, GitOutput},
+    scan::{self, HistoryScan, ScanOptions},
+    selection::{self, Selection},
+    selftest, sequence_editor, sha_map,
+    signing::{self, SigningPlan},
+    spec,
+    suggest::{self, Candidate},
+    tags::{self, AnnotatedTag},
+    tui,
+    verify::{self, Expectation, Rule},
+};
 
-use console::style;
-use std::{env, path::PathBuf};
+use console::StyledObject;
+use regex::Regex;
+use std::{
+    collections::HashSet,
+    env,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-/// Repository paths needed for the rewrite operation.
-struct RepoPaths {
-    root: PathBuf,
-    git_dir: PathBuf,
+/// Styles `val` for standard error, deciding on colors by whether standard
+/// error is a terminal.
+///
+/// Banners, checklists, progress, prompts, warnings and errors all go to
+/// standard error; standard output only carries what a command was asked to
+/// produce (the `verify`/`check` listings, `profile list`, the `bench` table,
+/// the batch summary, the final author report, help and version), so it can
+/// be piped or redirected on its own. Those results are styled with
+/// [`console::style`] directly.
+fn style<D>(val: D) -> StyledObject<D> {
+    console::style(val).for_stderr()
 }
 
-/// Verifies git is available and returns repository paths.
-fn verify_environment() -> Result<RepoPaths, ()> {
-    // Ensure `git` is available.
-    match which::which("git") {
-        Ok(_) => {}
-        Err(_) => {
-            eprintln!("{}", style("Error: `git` not found in PATH.").red().bold());
-            return Err(());
+pub use crate::exit::{EXIT_DCO_FAILED, EXIT_FSCK_FAILED, EXIT_NOTHING_TO_DO, EXIT_VERIFY_FAILED};
+
+/// Rewrites of more commits than this ask for the repository name to be
+/// typed, instead of a yes/no confirmation (unless `--force`); see
+/// [`LARGE_REWRITE_CONFIG_KEY`] to change it.
+const TYPED_CONFIRMATION_THRESHOLD: usize = 100;
+
+/// Git config key deciding how the confirmation before a rewrite starts is
+/// answered: `always-ask` or `assume-yes` (see [`StartConfirmation`]).
+const CONFIRM_START_CONFIG_KEY: &str = "author-rewrite.confirm.start";
+
+/// Git config key overriding [`TYPED_CONFIRMATION_THRESHOLD`].
+const LARGE_REWRITE_CONFIG_KEY: &str = "author-rewrite.confirm.largeRewriteThreshold";
+
+/// Git config key with a regular expression every prompted name must match.
+const NAME_PATTERN_CONFIG_KEY: &str = "author-rewrite.validate.name";
+
+/// Git config key with a regular expression every prompted email must match.
+const EMAIL_PATTERN_CONFIG_KEY: &str = "author-rewrite.validate.email";
+
+/// Most mismatches `verify` lists before summarizing the rest.
+const MAX_LISTED_VIOLATIONS: usize = 50;
+
+/// How many of the commits about to be rewritten already exist on remotes.
+struct PushedCommits {
+    /// Commits in the rewrite range.
+    total: usize,
+    /// Commits in the rewrite range reachable from any remote-tracking ref.
+    pushed: usize,
+    /// The current branch's upstream, if one is configured.
+    upstream: Option<String>,
+}
+
+/// Compares the selected commits reachable from `tip` (`HEAD`, or
+/// `--branches` for every local branch) against every remote-tracking ref.
+fn count_pushed_commits(
+    repo: &Repo,
+    tip: &str,
+    selection: &Selection,
+) -> Result<PushedCommits, String> {
+    let upstream = repo.upstream().ok();
+
+    match selection {
+        Selection::All => {
+            let total = match repo.rev_list_count(&[tip]) {
+                Ok(n) => n,
+                Err(e) => return Err(e),
+            };
+            let unpushed = match repo.rev_list_count(&[tip, "--not", "--remotes"]) {
+                Ok(n) => n,
+                Err(e) => return Err(e),
+            };
+
+            Ok(PushedCommits {
+                total,
+                pushed: total.saturating_sub(unpushed),
+                upstream,
+            })
+        }
+        Selection::Commits(shas) => {
+            let unpushed: HashSet<String> = match repo.rev_list(&[tip, "--not", "--remotes"]) {
+                Ok(v) => v.into_iter().collect(),
+                Err(e) => return Err(e),
+            };
+
+            Ok(PushedCommits {
+                total: shas.len(),
+                pushed: shas.iter().filter(|s| !unpushed.contains(*s)).count(),
+                upstream,
+            })
         }
     }
+}
+
+/// Builds the warning shown when already-pushed commits are about to be rewritten.
+///
+/// # Parameters
+///
+/// * `pushed` – Number of rewritten commits that exist on a remote.
+/// * `total` – Total number of commits that will be rewritten.
+/// * `upstream` – The current branch's upstream, mentioned when known.
+///
+/// # Returns
+///
+/// The warning lines, without styling.
+pub(crate) fn pushed_warning(pushed: usize, total: usize, upstream: Option<&str>) -> Vec<String> {
+    let location = match upstream {
+        Some(u) => format!("remotes (upstream: {})", u),
+        None => String::from("remotes"),
+    };
+
+    vec![
+        format!(
+            "⚠️  {} of {} commits to be rewritten already exist on {}.",
+            pushed, total, location
+        ),
+        String::from("Publishing the result requires a force-push, and every collaborator"),
+        String::from("will need to re-clone or rebase their work onto the rewritten history."),
+    ]
+}
+
+/// Warns about already-pushed commits reachable from `tip`, refusing to
+/// continue unless `force` is set.
+///
+/// # Returns
+///
+/// * `Ok(Some(PushedCommits))` with the counts, when the rewrite may go on.
+/// * `Ok(None)` if history could not be compared with the remotes.
+/// * `Err(Error)` if pushed commits would be rewritten without `force`.
+fn check_pushed_commits(
+    repo: &Repo,
+    tip: &str,
+    selection: &Selection,
+    force: bool,
+) -> Result<Option<PushedCommits>, Error> {
+    report_pushed_commits(count_pushed_commits(repo, tip, selection), force)
+}
 
-    // Resolve repository root.
-    let root = match git::rev_parse("--show-toplevel") {
-        Ok(s) => PathBuf::from(s),
+/// Warns about the already-pushed commits counted in `summary`, refusing to
+/// go on unless `force` is set (see [`check_pushed_commits`]).
+fn report_pushed_commits(
+    summary: Result<PushedCommits, String>,
+    force: bool,
+) -> Result<Option<PushedCommits>, Error> {
+    let summary = match summary {
+        Ok(s) => s,
         Err(e) => {
             eprintln!(
                 "{}",
-                style(format!("Error: not inside a git repo ({})", e))
-                    .red()
-                    .bold()
+                style(format!(
+                    "Warning: unable to compare history with remotes ({})",
+                    e
+                ))
+                .yellow()
             );
-            return Err(());
+            return Ok(None);
         }
     };
 
-    // Resolve .git directory.
-    let git_dir = match git::rev_parse("--git-dir") {
-        Ok(s) => {
-            let p = PathBuf::from(s);
-            if p.is_absolute() {
-                p
-            } else {
-                root.join(p)
-            }
-        }
+    if summary.pushed == 0 {
+        return Ok(Some(summary));
+    }
+
+    for line in pushed_warning(summary.pushed, summary.total, summary.upstream.as_deref()) {
+        eprintln!("{}", style(line).yellow().bold());
+    }
+
+    if !force {
+        return Err(Error::new(
+            "Refusing to rewrite pushed commits; re-run with --force-pushed-history to proceed.",
+        ));
+    }
+
+    Ok(Some(summary))
+}
+
+/// Refuses to rewrite `branch` (a full ref) if it is protected (the remote's
+/// default branch, or one matching `author-rewrite.protected`) unless
+/// `allow` (`--allow-protected`).
+///
+/// # Returns
+///
+/// * `Ok(())` if the branch is not protected, or `allow` is set.
+/// * `Err(Error)` if a protected branch would be rewritten, or the patterns
+///   could not be read.
+fn check_protected_branch(
+    repo: &Repo,
+    branch: &str,
+    allow: bool,
+    quiet: bool,
+) -> Result<(), Error> {
+    let reason = match protected::check(repo, branch) {
+        Ok(Some(r)) => r,
+        Ok(None) => return Ok(()),
         Err(e) => {
-            eprintln!(
-                "{}",
-                style(format!("Error: unable to locate .git dir ({})", e))
-                    .red()
-                    .bold()
-            );
-            return Err(());
+            return Err(Error::new(e));
         }
     };
+    let short = branch.trim_start_matches("refs/heads/");
+
+    if !allow {
+        return Err(Error::new(format!(
+            "Refusing to rewrite `{}`: {}. Re-run with --allow-protected to rewrite it anyway.",
+            short, reason
+        )));
+    }
+    if !quiet {
+        eprintln!(
+            "{}",
+            style(format!(
+                "⚠️  Rewriting the protected branch `{}` ({}).",
+                short, reason
+            ))
+            .yellow()
+            .bold()
+        );
+    }
+    Ok(())
+}
+
+/// How the yes/no confirmation before a rewrite starts is answered
+/// ([`CONFIRM_START_CONFIG_KEY`]). Typing the repository name for a risky
+/// rewrite is never skipped by it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StartConfirmation {
+    /// Not set: ask, unless `--yes` is given.
+    Default,
+    /// `always-ask`: ask even with `--yes`.
+    AlwaysAsk,
+    /// `assume-yes`: start without asking.
+    AssumeYes,
+}
+
+/// Parses a value of [`CONFIRM_START_CONFIG_KEY`]; empty means unset.
+fn parse_start_confirmation(value: &str) -> Result<StartConfirmation, String> {
+    match value.trim() {
+        "" => Ok(StartConfirmation::Default),
+        "always-ask" => Ok(StartConfirmation::AlwaysAsk),
+        "assume-yes" => Ok(StartConfirmation::AssumeYes),
+        other => Err(format!(
+            "invalid {}: `{}` (expected always-ask or assume-yes)",
+            CONFIRM_START_CONFIG_KEY, other
+        )),
+    }
+}
+
+/// Parses a value of [`LARGE_REWRITE_CONFIG_KEY`]; empty means unset.
+fn parse_large_rewrite_threshold(value: &str) -> Result<usize, String> {
+    if value.trim().is_empty() {
+        return Ok(TYPED_CONFIRMATION_THRESHOLD);
+    }
+    match value.trim().parse() {
+        Ok(n) => Ok(n),
+        Err(_) => Err(format!(
+            "invalid {}: `{}` (expected a number of commits)",
+            LARGE_REWRITE_CONFIG_KEY,
+            value.trim()
+        )),
+    }
+}
 
-    Ok(RepoPaths { root, git_dir })
+/// The team's confirmation settings from `repo`'s git config.
+#[derive(Debug, Clone, Copy)]
+struct ConfirmSettings {
+    start: StartConfirmation,
+    large_rewrite_threshold: usize,
+}
+
+impl ConfirmSettings {
+    /// Reads [`CONFIRM_START_CONFIG_KEY`] and [`LARGE_REWRITE_CONFIG_KEY`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ConfirmSettings)` – The settings, with defaults for unset keys.
+    /// * `Err(String)` if a value is invalid.
+    fn read(repo: &Repo) -> Result<ConfirmSettings, String> {
+        let start = match parse_start_confirmation(
+            &repo
+                .config_get(CONFIRM_START_CONFIG_KEY)
+                .unwrap_or_default(),
+        ) {
+            Ok(s) => s,
+            Err(e) => return Err(e),
+        };
+        let large_rewrite_threshold = match parse_large_rewrite_threshold(
+            &repo
+                .config_get(LARGE_REWRITE_CONFIG_KEY)
+                .unwrap_or_default(),
+        ) {
+            Ok(n) => n,
+            Err(e) => return Err(e),
+        };
+        Ok(ConfirmSettings {
+            start,
+            large_rewrite_threshold,
+        })
+    }
+
+    /// Answers the start confirmation without asking, if the settings and
+    /// `--yes` allow it.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(true))` to start without asking.
+    /// * `Ok(None)` to ask.
+    /// * `Err(String)` if the settings require asking and there is no
+    ///   terminal to ask on.
+    fn start_answer(self, yes: bool) -> Result<Option<bool>, String> {
+        match self.start {
+            StartConfirmation::AssumeYes => Ok(Some(true)),
+            StartConfirmation::Default if yes => Ok(Some(true)),
+            StartConfirmation::AlwaysAsk if !io::stdin().is_terminal() => Err(format!(
+                "{} is always-ask, so the rewrite must be confirmed on a terminal",
+                CONFIRM_START_CONFIG_KEY
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Explains why a rewrite needs the repository name typed to confirm it:
+/// it rewrites commits that exist on a remote, or more than `threshold`
+/// commits ([`TYPED_CONFIRMATION_THRESHOLD`] unless configured).
+///
+/// # Returns
+///
+/// The reason, or `None` if a yes/no confirmation is enough.
+fn typed_confirmation_reason(summary: &PushedCommits, threshold: usize) -> Option<String> {
+    if summary.pushed > 0 {
+        return Some(format!(
+            "This rewrites {} commits that already exist on a remote.",
+            summary.pushed
+        ));
+    }
+    if summary.total > threshold {
+        return Some(format!("This rewrites {} commits.", summary.total));
+    }
+    None
+}
+
+/// Asks for the repository name to be typed before a risky rewrite (see
+/// [`typed_confirmation_reason`]).
+///
+/// # Returns
+///
+/// * `Ok(bool)` – whether the name was typed correctly.
+/// * `Err(String)` if there is no terminal to type on, or the prompt failed.
+fn confirm_by_repo_name(repo_name: &str, reason: &str) -> Result<bool, String> {
+    if !io::stdin().is_terminal() {
+        return Err(format!(
+            "{} Re-run with --force to rewrite without typing the repository name.",
+            reason
+        ));
+    }
+    eprintln!();
+    eprintln!("{}", style(format!("⚠️  {}", reason)).yellow().bold());
+    let mut prompter = prompt::DialoguerStringPrompter;
+    prompt::confirm_typed(&mut prompter, repo_name)
 }
 
 /// Result of prompting for author input.
@@ -66,31 +405,161 @@ enum AuthorInput {
     NoChange,
 }
 
+/// Which parts of the author identity are being rewritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fields {
+    /// Name and email (the default).
+    Both,
+    /// Only the name (`--name-only`); each commit keeps its email.
+    NameOnly,
+    /// Only the email (`--email-only`); each commit keeps its name.
+    EmailOnly,
+}
+
+/// The checks a prompted `user.name` or `user.email` must pass: not empty,
+/// no `<` or `>`, a valid address for the email, and the pattern from
+/// [`NAME_PATTERN_CONFIG_KEY`] or [`EMAIL_PATTERN_CONFIG_KEY`] if set.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Check>)` – The checks, in the order they run.
+/// * `Err(Error)` if the configured pattern is not a valid regular expression.
+fn identity_checks(repo: &Repo, key: &str) -> Result<Vec<prompt::Check>, Error> {
+    let mut checks = vec![prompt::Check::NonEmpty];
+    let pattern_key = if key == "user.email" {
+        checks.push(prompt::Check::EmailSyntax);
+        EMAIL_PATTERN_CONFIG_KEY
+    } else {
+        NAME_PATTERN_CONFIG_KEY
+    };
+    checks.push(prompt::Check::NoAngleBrackets);
+    let pattern = repo.config_get(pattern_key).unwrap_or_default();
+    if !pattern.trim().is_empty() {
+        match Regex::new(pattern.trim()) {
+            Ok(pattern) => checks.push(prompt::Check::Matches {
+                pattern,
+                key: pattern_key.to_string(),
+            }),
+            Err(e) => {
+                return Err(Error::new(format!("invalid {}: {}", pattern_key, e)));
+            }
+        }
+    }
+    Ok(checks)
+}
+
 /// Prompts for author name and email, returning trimmed values or indicating no change.
-fn get_author_input(repo_name: &str) -> Result<AuthorInput, ()> {
-    let default_name = git::config_get("user.name").unwrap_or_default();
-    let default_email = git::config_get("user.email").unwrap_or_default();
+///
+/// Fields excluded by `fields` are not prompted for; their config defaults are
+/// returned unchanged. Values given with `--name` / `--email` (or their
+/// `GAR_*` variables) are used without prompting, and `--yes` accepts the
+/// config default for any value still missing.
+fn get_author_input(
+    repo: &Repo,
+    repo_name: &str,
+    fields: Fields,
+    opts: &args::Options,
+) -> Result<AuthorInput, Error> {
+    let default_name = repo.config_get("user.name").unwrap_or_default();
+    let default_email = repo.config_get("user.email").unwrap_or_default();
 
     let mut string_prompter = prompt::DialoguerStringPrompter;
 
-    let name = match prompt::ask(&mut string_prompter, "Author name", repo_name, &default_name) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("{}", style(format!("Prompt error: {}", e)).red().bold());
-            return Err(());
+    if default_name.is_empty() && default_email.is_empty() && !opts.yes && !opts.no_config_update {
+        eprintln!(
+            "{}",
+            style("No git identity is configured yet (user.name and user.email are unset). The identity you enter is also saved to this repository's config, so Git can record it as the committer of the rewritten commits.")
+                .yellow()
+        );
+    }
+
+    // Identities already known for the repository, offered in a menu and
+    // as completions.
+    let prompting = !opts.yes && (opts.name.is_none() || opts.email.is_none());
+    let known = if prompting {
+        suggest::candidates(repo)
+    } else {
+        Vec::new()
+    };
+    let picked = if prompting && opts.name.is_none() && opts.email.is_none() {
+        match pick_identity(repo_name, &known) {
+            Ok(p) => p,
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
         }
+    } else {
+        None
     };
 
-    let email = match prompt::ask(&mut string_prompter, "Author email", repo_name, &default_email) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("{}", style(format!("Prompt error: {}", e)).red().bold());
-            return Err(());
+    // Values given up front are taken as is; only prompted or defaulted
+    // values can mean "nothing to change".
+    let mut explicit = true;
+
+    let name = if fields == Fields::EmailOnly {
+        default_name.clone()
+    } else if let Some(v) = opts.name.as_deref() {
+        v.to_string()
+    } else if opts.yes {
+        explicit = false;
+        default_name.clone()
+    } else if let Some(id) = &picked {
+        explicit = false;
+        id.name.clone()
+    } else {
+        explicit = false;
+        match prompt::ask_suggesting(
+            &mut string_prompter,
+            "Author name",
+            repo_name,
+            &default_name,
+            &suggest::names(&known),
+            &identity_checks(repo, "user.name")?,
+        ) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
+        }
+    };
+
+    let email = if fields == Fields::NameOnly {
+        default_email.clone()
+    } else if let Some(v) = opts.email.as_deref() {
+        v.to_string()
+    } else if opts.yes {
+        explicit = false;
+        default_email.clone()
+    } else if let Some(id) = &picked {
+        explicit = false;
+        id.email.clone()
+    } else {
+        explicit = false;
+        match prompt::ask_suggesting(
+            &mut string_prompter,
+            "Author email",
+            repo_name,
+            &default_email,
+            &suggest::emails(&known, name.trim()),
+            &identity_checks(repo, "user.email")?,
+        ) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
         }
     };
 
+    // Offer to keep a typed-in or picked personal email out of history.
+    let email_prompted = fields != Fields::NameOnly && opts.email.is_none() && !opts.yes;
+    let email = if email_prompted {
+        offer_noreply(email)?
+    } else {
+        email
+    };
+
     // Check for no changes before trimming.
-    if should_exit_no_change(&name, &email, &default_name, &default_email) {
+    if !explicit && should_exit_no_change(&name, &email, &default_name, &default_email) {
         return Ok(AuthorInput::NoChange);
     }
 
@@ -100,297 +569,5067 @@ fn get_author_input(repo_name: &str) -> Result<AuthorInput, ()> {
     ))
 }
 
-/// Updates git config with the new author values.
-fn update_git_config(name: &str, email: &str) -> Result<(), ()> {
-    match git::config_set("user.name", name) {
-        Ok(_) => {}
+/// Offers the GitHub noreply form of `email` when it looks like a personal
+/// address (see [`noreply::is_personal`]), asking for the GitHub username
+/// and, for the `ID+username` form, the account ID.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the noreply address, or `email` unchanged if it is not
+///   personal, the offer is declined or the username is left blank.
+/// * `Err(Error)` if a prompt failed.
+fn offer_noreply(email: String) -> Result<String, Error> {
+    if !noreply::is_personal(&email) {
+        return Ok(email);
+    }
+    let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+    match prompt::confirm_noreply(&mut confirm_prompter, email.trim()) {
+        Ok(true) => {}
+        Ok(false) => return Ok(email),
         Err(e) => {
-            eprintln!(
-                "{}",
-                style(format!("Failed to set user.name: {}", e))
-                    .red()
-                    .bold()
-            );
-            return Err(());
+            return Err(Error::new(format!("prompt failed: {}", e)));
         }
     }
-    match git::config_set("user.email", email) {
-        Ok(_) => {}
+
+    let mut string_prompter = prompt::DialoguerStringPrompter;
+    let username = loop {
+        let value = match prompt::ask_github_username(&mut string_prompter) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
+        };
+        if value.is_empty() {
+            return Ok(email);
+        }
+        match noreply::validate_username(&value) {
+            Ok(()) => break value,
+            Err(e) => eprintln!("{}", style(e).red()),
+        }
+    };
+    let id = loop {
+        let value = match prompt::ask_github_id(&mut string_prompter) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
+        };
+        if value.is_empty() {
+            break None;
+        }
+        match value.parse::<u64>() {
+            Ok(id) => break Some(id),
+            Err(_) => eprintln!("{}", style("The account ID is a number.").red()),
+        }
+    };
+
+    let address = noreply::github_address(id, &username);
+    eprintln!("{}", style(format!("Using {}", address)).green());
+    Ok(address)
+}
+
+/// Offers the identities in `known` (see [`suggest::menu`]) in a menu,
+/// with a last choice to type the identity in.
+///
+/// # Returns
+///
+/// * `Ok(Some(Identity))` – The chosen identity.
+/// * `Ok(None)` if the user chose to type it in, or there was nothing to offer.
+/// * `Err(String)` if the prompt failed.
+fn pick_identity(repo_name: &str, known: &[Candidate]) -> Result<Option<Identity>, String> {
+    let choices = suggest::menu(known);
+    if choices.is_empty() {
+        return Ok(None);
+    }
+    let mut labels: Vec<String> = choices.iter().map(|c| c.to_string()).collect();
+    labels.push(String::from("Enter manually"));
+
+    let mut prompter = prompt::DialoguerSelectPrompter;
+    match prompt::select_identity(&mut prompter, repo_name, &labels) {
+        Ok(i) => Ok(choices.get(i).map(|c| c.identity.clone())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Builds the identity mapping from prompted values.
+///
+/// Values containing template placeholders become a [`Mapping::Template`];
+/// otherwise `fields` picks a fixed, name-only or email-only mapping.
+///
+/// # Returns
+///
+/// * `Ok(Mapping)` on success.
+/// * `Err(String)` if a template contains an unknown placeholder.
+fn build_mapping(name: String, email: String, fields: Fields) -> Result<Mapping, String> {
+    if !identity::is_template(&name) && !identity::is_template(&email) {
+        return Ok(match fields {
+            Fields::Both => Mapping::Fixed(Identity::new(&name, &email)),
+            Fields::NameOnly => Mapping::NameOnly(name),
+            Fields::EmailOnly => Mapping::EmailOnly(email),
+        });
+    }
+
+    for value in [&name, &email] {
+        if let Err(e) = identity::validate_template(value) {
+            return Err(e);
+        }
+    }
+
+    // A field that is not being rewritten keeps each commit's original value.
+    let name = match fields {
+        Fields::EmailOnly => String::from("{name}"),
+        _ => name,
+    };
+    let email = match fields {
+        Fields::NameOnly => String::from("{email}"),
+        _ => email,
+    };
+
+    Ok(Mapping::Template { name, email })
+}
+
+/// Returns the `user.name` / `user.email` values to store for `mapping`.
+///
+/// Only literal values are stored; templates are expanded per commit and
+/// mean nothing as a configured identity.
+fn config_values(mapping: &Mapping) -> (Option<&str>, Option<&str>) {
+    match mapping {
+        Mapping::Fixed(id) => (Some(&id.name), Some(&id.email)),
+        Mapping::NameOnly(name) => (Some(name), None),
+        Mapping::EmailOnly(email) => (None, Some(email)),
+        Mapping::Template { name, email } => {
+            let literal = |v: &str| !identity::is_template(v);
+            (
+                Some(name.as_str()).filter(|v| literal(v)),
+                Some(email.as_str()).filter(|v| literal(v)),
+            )
+        }
+        Mapping::SyncCommitter(Some(inner)) => config_values(inner),
+        Mapping::Domain { .. }
+        | Mapping::Rules(_)
+        | Mapping::Shas(_)
+        | Mapping::SyncCommitter(None) => (None, None),
+    }
+}
+
+/// Updates git config with the new author values.
+///
+/// Values passed as `None` are left untouched.
+fn update_git_config(repo: &Repo, name: Option<&str>, email: Option<&str>) -> Result<(), Error> {
+    for (key, value) in [("user.name", name), ("user.email", email)] {
+        let value = match value {
+            Some(v) => v,
+            None => continue,
+        };
+        match repo.config_set(key, value) {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(Error::new(format!("Failed to set {}: {}", key, e)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks with the GitHub API that the email of `mapping` will be attributed
+/// to the account of the token in `GITHUB_TOKEN` / `GH_TOKEN`
+/// (`--check-github`).
+///
+/// An email that is not a verified email of the account is reported; the user
+/// is asked whether to go on, and with `--yes` or without a terminal the run
+/// stops.
+fn check_github_email(mapping: &Mapping, opts: &args::Options) -> Result<(), Error> {
+    let email = match config_values(mapping).1 {
+        Some(e) => e,
+        None => {
+            return Err(Error::new(
+                "--check-github needs a fixed new email to check.",
+            ));
+        }
+    };
+    let token = match github::token() {
+        Some(t) => t,
+        None => {
+            return Err(Error::new(
+                "--check-github needs a GitHub token in GITHUB_TOKEN or GH_TOKEN.",
+            ));
+        }
+    };
+
+    let (account, verdict) = match github::check_email(email, &token) {
+        Ok(outcome) => outcome,
         Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    let problem = match verdict {
+        Verdict::Verified => {
+            if !opts.quiet {
+                eprintln!(
+                    "{}",
+                    style(format!(
+                        "✓ GitHub attributes {} to @{}.",
+                        email, account.login
+                    ))
+                    .green()
+                );
+            }
+            return Ok(());
+        }
+        Verdict::Unverified => "is not verified yet",
+        Verdict::NotOnAccount => "is not one of its emails",
+    };
+    eprintln!(
+        "{}",
+        style(format!(
+            "Warning: {} {} on GitHub account @{}, so GitHub will not attribute the rewritten commits to it. Add or verify it at https://github.com/settings/emails.",
+            email, problem, account.login
+        ))
+        .yellow()
+        .bold()
+    );
+
+    if opts.yes || !io::stdin().is_terminal() {
+        return Err(Error::new("Stopping before anything is rewritten."));
+    }
+    let mut prompter = prompt::DialoguerConfirmPrompter;
+    match prompt::confirm_unattributed_email(&mut prompter) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Error::new(
+            "stopped before anything was rewritten, as the new email would not be attributed on GitHub",
+        )),
+        Err(e) => Err(Error::new(format!("prompt failed: {}", e))),
+    }
+}
+
+/// Saves the identity of `mapping` for every repository under `dir`
+/// (`--include-if`).
+///
+/// Mappings without a literal name or email, such as `--rewrite-domain`,
+/// leave nothing to save.
+fn save_scoped_identity(dir: &str, mapping: &Mapping) -> Result<(), Error> {
+    let (name, email) = config_values(mapping);
+    if name.is_none() && email.is_none() {
+        eprintln!(
+            "{}",
+            style("Nothing to save for --include-if: the rewrite sets no fixed name or email.")
+                .yellow()
+        );
+        return Ok(());
+    }
+    match include_if::install(dir, name, email) {
+        Ok(scoped) => {
             eprintln!(
                 "{}",
-                style(format!("Failed to set user.email: {}", e))
-                    .red()
-                    .bold()
+                style(format!(
+                    "Saved the identity to {} for repositories matching {}.",
+                    scoped.file.display(),
+                    scoped.condition
+                ))
+                .green()
             );
-            return Err(());
+            Ok(())
+        }
+        Err(e) => Err(Error::new(e)),
+    }
+}
+
+/// Returns the `(key, value)` pairs whose global config value would change.
+///
+/// `current` holds the global `user.name` and `user.email`; fields that are
+/// `None` are not being written.
+fn global_changes<'a>(
+    name: Option<&'a str>,
+    email: Option<&'a str>,
+    current: (&str, &str),
+) -> Vec<(&'static str, &'a str)> {
+    let mut changes = Vec::new();
+    if let Some(n) = name {
+        if n != current.0 {
+            changes.push(("user.name", n));
+        }
+    }
+    if let Some(e) = email {
+        if e != current.1 {
+            changes.push(("user.email", e));
+        }
+    }
+    changes
+}
+
+/// Offers to copy the new author values into the global git config.
+///
+/// With `global` (`--global`) the values are written without asking. With
+/// `skip_prompt` (`--yes`) and no `--global`, the offer is declined, since
+/// the global identity affects every repository. Nothing happens when the
+/// global config already matches.
+fn update_global_config(
+    name: Option<&str>,
+    email: Option<&str>,
+    global: bool,
+    skip_prompt: bool,
+) -> Result<(), Error> {
+    if !global && skip_prompt {
+        return Ok(());
+    }
+
+    let current_name = git::config_get_global("user.name").unwrap_or_default();
+    let current_email = git::config_get_global("user.email").unwrap_or_default();
+    let changes = global_changes(name, email, (&current_name, &current_email));
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    if !global {
+        let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+        match prompt::confirm_global_update(&mut confirm_prompter) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
+        }
+    }
+
+    for (key, value) in changes {
+        match git::config_set_global(key, value) {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(Error::new(format!("Failed to set global {}: {}", key, e)));
+            }
         }
     }
+    eprintln!("{}", style("Updated global git config.").green());
     Ok(())
 }
 
-/// Runs the rebase loop, amending each commit with the new author.
-fn run_rebase_loop(git_dir: &PathBuf, author: &str) -> Result<(), ()> {
-    loop {
-        if !git::rebase_in_progress(git_dir) {
-            println!(
-                "{}",
-                style("✅ Successfully rewrote commit authors.")
-                    .green()
-                    .bold()
-            );
-            break;
+/// Lists the identity settings Git still lacks to record a committer:
+/// `user.name` and/or `user.email`, given their effective values.
+fn missing_identity_keys(name: &str, email: &str) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if name.trim().is_empty() {
+        missing.push("user.name");
+    }
+    if email.trim().is_empty() {
+        missing.push("user.email");
+    }
+    missing
+}
+
+/// Makes sure Git has an identity to record as the committer of the
+/// rewritten commits.
+///
+/// Mappings that do not set both `user.name` and `user.email` (partial and
+/// per-commit mappings, `--sync-committer`) leave the config as it was, so
+/// on a first run the rebase would stop at the first commit. The missing
+/// values are asked for, saved locally and, after asking, globally too.
+/// Without a terminal, or with `--yes`, the run stops with the commands to
+/// set them instead.
+fn ensure_committer_identity(
+    repo: &Repo,
+    repo_name: &str,
+    opts: &args::Options,
+) -> Result<(), Error> {
+    // GIT_COMMITTER_* from the environment are cleared for every command,
+    // so only the config counts.
+    let name = repo.config_get("user.name").unwrap_or_default();
+    let email = repo.config_get("user.email").unwrap_or_default();
+    let missing = missing_identity_keys(&name, &email);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let problem = format!(
+        "Git has no {} to record as the committer of the rewritten commits.",
+        missing.join(" or ")
+    );
+    if opts.yes || opts.no_config_update || !io::stdin().is_terminal() {
+        let mut err = Error::new(problem).hint("Set it and re-run:");
+        for key in missing {
+            err = err.hint(format!("  git config --global {} <VALUE>", key));
+        }
+        return Err(err);
+    }
+    eprintln!("{}", style(problem).yellow().bold());
+
+    let known = suggest::candidates(repo);
+    let mut prompter = prompt::DialoguerStringPrompter;
+    let mut values = Vec::new();
+    for key in &missing {
+        let (label, suggestions) = match *key {
+            "user.name" => ("Your name", suggest::names(&known)),
+            _ => ("Your email", suggest::emails(&known, name.trim())),
+        };
+        let checks = identity_checks(repo, key)?;
+        match prompt::ask_suggesting(&mut prompter, label, repo_name, "", &suggestions, &checks) {
+            Ok(v) => values.push(v.trim().to_string()),
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
+        }
+    }
+
+    let value_of = |key: &str| {
+        missing
+            .iter()
+            .position(|k| *k == key)
+            .map(|i| values[i].as_str())
+    };
+    let (new_name, new_email) = (value_of("user.name"), value_of("user.email"));
+    update_git_config(repo, new_name, new_email)?;
+    update_global_config(new_name, new_email, opts.global, false)
+}
+
+/// Resolves the commits selected on the command line.
+///
+/// Returns `Ok(None)` when the selection is empty, after telling the user.
+fn resolve_selection(repo: &Repo, opts: &args::Options) -> Result<Option<Selection>, Error> {
+    let resolved = if let Some(expr) = opts.revs.as_deref() {
+        selection::resolve_revs(repo, expr)
+    } else if let Some(source) = opts.commits_from.as_deref() {
+        match selection::read_sha_list(source) {
+            Ok(list) => selection::resolve_list(repo, &list),
+            Err(e) => Err(e),
+        }
+    } else {
+        Ok((Selection::All, 0))
+    };
+
+    let (sel, ignored) = match resolved {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(Error::new(e));
         }
+    };
+
+    let mut excluded = opts.exclude.clone();
+    if let Some(source) = opts.exclude_file.as_deref() {
+        match selection::read_sha_list(source) {
+            Ok(list) => excluded.extend(list),
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        }
+    }
+    let sel = match selection::exclude(repo, sel, &excluded) {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    let sel = if opts.skip_merges {
+        match selection::skip_merges(repo, sel) {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        }
+    } else {
+        sel
+    };
+
+    if ignored > 0 {
+        eprintln!(
+            "{}",
+            style(format!(
+                "Warning: ignoring {} selected commit(s) not reachable from HEAD.",
+                ignored
+            ))
+            .yellow()
+        );
+    }
+
+    if sel == Selection::Commits(Vec::new()) {
+        eprintln!(
+            "{}",
+            style("Nothing to rewrite: no commits matched the selection.")
+                .yellow()
+                .bold()
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(sel))
+}
+
+/// Builds the `--sequence-editor` arguments that mark the selected commits.
+///
+/// Returns `Ok(None)` in manual mode, where the user's own editor is used,
+/// unless `--show-authors` needs this executable to annotate the todo list
+/// before handing it to that editor.
+fn sequence_editor_args(
+    repo: &Repo,
+    selection: &Selection,
+    opts: &args::Options,
+) -> Result<Option<Vec<String>>, Error> {
+    if opts.manual {
+        if !opts.show_authors && opts.drop_author.is_empty() {
+            return Ok(None);
+        }
+        let editor = match &opts.editor {
+            Some(e) => e.clone(),
+            None => match repo.sequence_editor() {
+                Ok(e) => e,
+                Err(e) => {
+                    return Err(Error::new(format!("cannot determine your editor: {}", e)));
+                }
+            },
+        };
+        let mut args = drop_author_args(&opts.drop_author);
+        if opts.show_authors {
+            args.push(String::from("--show-authors"));
+        }
+        args.push(String::from("--open"));
+        args.push(editor);
+        return Ok(Some(args));
+    }
+
+    let mut args = drop_author_args(&opts.drop_author);
+    match exec_commands(repo, opts) {
+        Ok(commands) => {
+            for command in commands {
+                args.push(String::from("--exec"));
+                args.push(command);
+            }
+        }
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    }
+    match selection {
+        Selection::All => {}
+        Selection::Commits(shas) => match selection::write_selection_file(repo.git_dir(), shas) {
+            Ok(path) => {
+                args.push(String::from("--select"));
+                args.push(path.to_string_lossy().to_string());
+            }
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        },
+    }
+    Ok(Some(args))
+}
+
+/// Builds the `--drop-author <email>` sequence editor arguments.
+fn drop_author_args(emails: &[String]) -> Vec<String> {
+    emails
+        .iter()
+        .flat_map(|e| [String::from("--drop-author"), e.clone()])
+        .collect()
+}
+
+/// Reads the banner's theme from the `author-rewrite.banner.*` git config.
+///
+/// The banner is only decoration, so an invalid value is reported as a
+/// warning and the default theme is used instead.
+fn banner_theme(repo: &Repo) -> banner::Theme {
+    match banner::Theme::from_config(|key| repo.config_get(key).unwrap_or_default()) {
+        Ok(theme) => theme,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                style(format!("Warning: {}; using the default banner", e)).yellow()
+            );
+            banner::Theme::default()
+        }
+    }
+}
+
+/// Git config key holding the default `--exec` commands, one value each.
+const EXEC_CONFIG_KEY: &str = "author-rewrite.exec";
+
+/// Decides which commands run after each rewritten commit of the rebase.
+///
+/// `--exec` wins; otherwise every `author-rewrite.exec` value in the git
+/// config is used, in order.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` – The commands; empty for none.
+/// * `Err(String)` if the config could not be read.
+fn exec_commands(repo: &Repo, opts: &args::Options) -> Result<Vec<String>, String> {
+    if !opts.exec.is_empty() {
+        return Ok(opts.exec.clone());
+    }
+    match repo.config_get_all(EXEC_CONFIG_KEY) {
+        Ok(commands) => Ok(commands
+            .into_iter()
+            .filter(|c| !c.trim().is_empty())
+            .collect()),
+        Err(e) => Err(format!("invalid {}: {}", EXEC_CONFIG_KEY, e)),
+    }
+}
+
+/// Git config key holding the default for `--verify`/`--no-verify`.
+const VERIFY_CONFIG_KEY: &str = "author-rewrite.verify";
+
+/// Decides whether the commit hooks run for each amended commit.
+///
+/// `--no-verify` and `--verify` win; otherwise `author-rewrite.verify` in the
+/// git config decides, and hooks run if it is not set.
+///
+/// # Returns
+///
+/// * `Ok(bool)` – Whether to run the hooks.
+/// * `Err(String)` if the config value is not a boolean.
+fn commit_hooks_enabled(repo: &Repo, opts: &args::Options) -> Result<bool, String> {
+    if opts.no_verify {
+        return Ok(false);
+    }
+    if opts.verify {
+        return Ok(true);
+    }
+    match repo.config_get_bool(VERIFY_CONFIG_KEY) {
+        Ok(value) => Ok(value.unwrap_or(true)),
+        Err(e) => Err(format!("invalid {}: {}", VERIFY_CONFIG_KEY, e)),
+    }
+}
+
+/// Decides how `commit.gpgsign` is handled, asking the user if it is on
+/// and no flag decided already.
+///
+/// # Returns
+///
+/// * `Ok(SigningPlan)` – Never [`SigningPlan::Ask`].
+/// * `Err(String)` if the config is invalid or the prompt failed.
+fn commit_signing_plan(repo: &Repo, opts: &args::Options) -> Result<SigningPlan, String> {
+    let configured = match signing::configured(repo) {
+        Ok(b) => b,
+        Err(e) => return Err(e),
+    };
+    match signing::plan(configured, opts.gpg_sign, opts.no_gpg_sign, opts.yes) {
+        SigningPlan::Ask => {
+            let mut prompter = prompt::DialoguerConfirmPrompter;
+            match prompt::confirm_commit_signing(&mut prompter) {
+                Ok(true) => Ok(SigningPlan::Sign),
+                Ok(false) => Ok(SigningPlan::Off),
+                Err(e) => Err(e),
+            }
+        }
+        plan => Ok(plan),
+    }
+}
+
+/// Lists the identities `--ask-each` offers for a commit: what the mapping
+/// suggests, then each saved profile not already listed.
+///
+/// # Returns
+///
+/// Menu labels paired with their identities, without the skip choice.
+fn identity_choices(planned: Option<&Identity>, profiles: &[Profile]) -> Vec<(String, Identity)> {
+    let mut choices: Vec<(String, Identity)> = Vec::new();
+    if let Some(id) = planned {
+        choices.push((format!("{} (mapping)", id), id.clone()));
+    }
+    for p in profiles {
+        if choices.iter().all(|(_, id)| id != &p.identity) {
+            choices.push((
+                format!("{} (profile {})", p.identity, p.name),
+                p.identity.clone(),
+            ));
+        }
+    }
+    choices
+}
+
+/// Shows the commit the rebase stopped at and asks which author it gets
+/// (`--ask-each`).
+///
+/// # Returns
+///
+/// * `Ok(Some(Identity))` – The chosen author.
+/// * `Ok(None)` if the user skipped the commit.
+/// * `Err(String)` if the commit could not be read or the prompt failed.
+fn ask_commit_identity(
+    repo: &Repo,
+    original: &str,
+    ids: &CommitIdentities,
+    planned: Option<&Identity>,
+    profiles: &[Profile],
+) -> Result<Option<Identity>, String> {
+    let summary = match repo.log("%h %ad %s", &["-1", "--date=short", original]) {
+        Ok(s) => s,
+        Err(e) => return Err(e),
+    };
+    eprintln!();
+    eprintln!("{}", style(summary.trim_end()).bold());
+    eprintln!("  Author:    {}", ids.author);
+    eprintln!("  Committer: {}", ids.committer);
+
+    let choices = identity_choices(planned, profiles);
+    let mut labels: Vec<String> = choices.iter().map(|(label, _)| label.clone()).collect();
+    labels.push(format!("Skip (keep {})", ids.author));
+
+    let mut prompter = prompt::DialoguerSelectPrompter;
+    match prompt::select_commit_identity(&mut prompter, &labels) {
+        Ok(i) => Ok(choices.get(i).map(|(_, id)| id.clone())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Amends the commit the rebase is currently stopped at according to `mapping`.
+///
+/// The original commit's identities are read from the rebase's `done` list,
+/// so mappings that depend on the original author or committer see the
+/// values from before the rebase touched the commit. With `ask` (the saved
+/// profiles, for `--ask-each`), the user picks the author instead.
+///
+/// Returns what `git commit --amend` printed (see [`Repo::amend_author`]).
+fn amend_current_commit(
+    repo: &Repo,
+    mapping: &Mapping,
+    edits: &MessageEdits,
+    ask: Option<&[Profile]>,
+    options: git::CommitOptions,
+) -> Result<GitOutput, String> {
+    let (author, committer) = match (mapping, ask) {
+        (Mapping::Fixed(id), None) => (Some(id.clone()), None),
+        _ => {
+            let original = match repo.rebase_current_commit() {
+                Ok(sha) => sha,
+                Err(e) => return Err(e),
+            };
+            let ids = match identity::read_commit_identities(repo, &original) {
+                Ok(ids) => ids,
+                Err(e) => return Err(e),
+            };
+            let plan = mapping.plan(&ids);
+            let chosen = match ask {
+                Some(profiles) => {
+                    let planned = plan.as_ref().map(|(a, _)| a);
+                    match ask_commit_identity(repo, &original, &ids, planned, profiles) {
+                        Ok(Some(author)) => Some((author, plan.and_then(|(_, c)| c))),
+                        Ok(None) => None,
+                        Err(e) => return Err(e),
+                    }
+                }
+                None => plan,
+            };
+            let (author, committer) = match chosen {
+                Some((author, committer)) => (Some(author), committer),
+                // Unaffected, but re-created by the rebase: keep both identities.
+                // Git copies the author itself, byte for byte.
+                None => (None, Some(ids.committer)),
+            };
+            let mut written = author.iter().chain(committer.iter());
+            if let Some(lossy) = written.find(|id| id.is_lossy()) {
+                return Err(format!(
+                    "commit {} has an identity that is not valid UTF-8 ({}), which cannot be kept as is; leave the commit out of the rewrite (e.g. with --revs)",
+                    original, lossy
+                ));
+            }
+            (author, committer)
+        }
+    };
+
+    let message = if edits.is_empty() {
+        None
+    } else {
+        let original = match repo.rebase_current_commit() {
+            Ok(sha) => sha,
+            Err(e) => return Err(e),
+        };
+        match repo.cat_file("commit", "HEAD") {
+            Ok(raw) => match CommitObject::parse(&raw) {
+                Ok(obj) => edits.apply(&obj.message, &original),
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        }
+    };
+
+    let author = author.map(|a| a.to_string());
+    repo.amend_author(author.as_deref(), committer.as_ref(), message, options)
+}
+
+/// Turns a Git failure into an [`Error`], with the explanation and fix
+/// [`diagnose`] knows for it as hints.
+fn diagnosed(error: String) -> Error {
+    let diagnosis = diagnose::diagnose(&error);
+    let err = Error::new(error);
+    match diagnosis {
+        Some(d) => err.hint(d.problem).hint(format!("  Fix: {}", d.fix)),
+        None => err,
+    }
+}
+
+/// The error for a rebase step that failed at a commit (`doing` it, e.g.
+/// `amending`): the diagnosis if the failure is a known one, otherwise a
+/// reminder that a hook may have rejected the commit when hooks ran.
+fn rebase_step_error(
+    repo: &Repo,
+    error: String,
+    options: git::CommitOptions,
+    doing: &str,
+) -> Error {
+    let known = diagnose::diagnose(&error).is_some();
+    let err = diagnosed(error).context(stop_context(repo, doing));
+    if !known && options.run_hooks {
+        err.hint("If a pre-commit or commit-msg hook rejected the commit, re-run with --no-verify.")
+    } else {
+        err
+    }
+}
+
+/// Names the commit the rebase stopped at, for an error's context:
+/// `<doing> commit 1a2b3c4`, or `<doing> a commit` if it is not known.
+fn stop_context(repo: &Repo, doing: &str) -> String {
+    match repo.rebase_current_commit() {
+        Ok(sha) => format!("{} commit {}", doing, mirror::short(&sha)),
+        Err(_) => format!("{} a commit", doing),
+    }
+}
+
+/// Runs the rebase loop, amending each commit according to `mapping` (or the
+/// user's choice, with `ask`).
+///
+/// After each commit it prints the throughput and an estimate of the time
+/// left, except with `ask`, where the user's answers set the pace. With
+/// quiet `options` (`--quiet`) only failures are printed. With `skipped`
+/// (`--skip-on-conflict`), commits that conflict are skipped and added to it.
+fn run_rebase_loop(
+    repo: &Repo,
+    mapping: &Mapping,
+    edits: &MessageEdits,
+    ask: Option<&[Profile]>,
+    options: git::CommitOptions,
+    mut skipped: Option<&mut Vec<String>>,
+) -> Result<(), Error> {
+    let mut timing = Progress::new(progress::total_stops(repo.git_dir()));
+    let mut stall = StallCheck::new();
+    loop {
+        if !repo.rebase_in_progress() {
+            if !options.quiet {
+                eprintln!(
+                    "{}",
+                    style("✅ Successfully rewrote commit authors.")
+                        .green()
+                        .bold()
+                );
+            }
+            break;
+        }
+        let stop = progress::current_stop(repo.git_dir());
+        if stall.stalled(&stop) {
+            return Err(diagnosed(stall_error(&stop)));
+        }
+
+        let began = Instant::now();
+        match amend_current_commit(repo, mapping, edits, ask, options) {
+            Ok(_) if options.quiet => {}
+            Ok(_) => {
+                eprintln!("{}", style("Amended current commit author.").green());
+            }
+            Err(e) => {
+                let err = rebase_step_error(repo, e, options, "amending");
+                abort_if_hung(repo, err.message(), options);
+                return Err(err);
+            }
+        }
+
+        match repo.rebase_continue(options) {
+            Ok(_) => {}
+            Err(e) => {
+                let mut err = diagnosed(e)
+                    .context("running `git rebase --continue`")
+                    .context(stop_context(repo, "applying"));
+                if let Some(list) = skipped.as_deref_mut() {
+                    match skip_conflicts(repo, options, list) {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(e) => {
+                            err = err.hint(format!("The conflict could not be skipped: {}", e));
+                        }
+                    }
+                }
+                match resolve_with_mergetool(repo, options) {
+                    // Stopped at the resolved commit; amend it like any other.
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => {
+                        err = err.hint(format!("The merge tool failed: {}", e));
+                    }
+                }
+                abort_if_hung(repo, err.message(), options);
+                return Err(err);
+            }
+        }
+
+        timing.record(began.elapsed());
+        if ask.is_none() && !options.quiet {
+            eprintln!("{}", style(format!("Progress: {}", timing.summary())).dim());
+        }
+    }
+    Ok(())
+}
+
+/// Describes a rebase that stopped at `stop` twice in a row (see
+/// [`StallCheck`]), for [`diagnosed`] to explain.
+fn stall_error(stop: &progress::Stop) -> String {
+    format!(
+        "the rebase is not making progress: it stopped at step {} ({}) again after `git rebase --continue` succeeded",
+        stop.step, stop.line
+    )
+}
+
+/// Offers to abort the rebase when `error` says a command hung and was
+/// stopped (`--timeout`), so the run ends with the history as it was instead
+/// of a rebase left half done.
+///
+/// Nothing is asked with quiet `options` or without a terminal; the rebase
+/// is then left for `git rebase --abort` as usual.
+fn abort_if_hung(repo: &Repo, error: &str, options: git::CommitOptions) {
+    if !error.contains("appears hung") || options.quiet || !io::stdin().is_terminal() {
+        return;
+    }
+    let mut prompter = prompt::DialoguerConfirmPrompter;
+    match prompt::confirm_abort_hung_rebase(&mut prompter) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            eprintln!("{}", style(format!("Prompt failed: {}", e)).yellow());
+            return;
+        }
+    }
+    match repo.rebase_abort() {
+        Ok(()) => eprintln!(
+            "{}",
+            style("Rebase aborted; the history is unchanged.").green()
+        ),
+        Err(e) => eprintln!(
+            "{}",
+            style(format!("The rebase could not be aborted: {}", e)).yellow()
+        ),
+    }
+}
+
+/// Answers a conflict stop with `git rebase --skip` (`--skip-on-conflict`),
+/// again for as long as the next commit conflicts too, adding each skipped
+/// commit to `skipped`.
+///
+/// # Returns
+///
+/// * `Ok(true)` if commits were skipped and the rebase can go on.
+/// * `Ok(false)` if the rebase did not stop at a conflict.
+/// * `Err(String)` if Git failed.
+fn skip_conflicts(
+    repo: &Repo,
+    options: git::CommitOptions,
+    skipped: &mut Vec<String>,
+) -> Result<bool, String> {
+    loop {
+        match repo.unmerged_paths() {
+            Ok(paths) if paths.is_empty() => return Ok(false),
+            Ok(_) => {}
+            Err(e) => return Err(e),
+        }
+        let original = match repo.rebase_current_commit() {
+            Ok(sha) => sha,
+            Err(e) => return Err(e),
+        };
+        if !options.quiet {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Skipping {}: it does not apply cleanly.",
+                    mirror::short(&original)
+                ))
+                .yellow()
+            );
+        }
+        skipped.push(original);
+        match repo.rebase_skip(options) {
+            Ok(_) => return Ok(true),
+            // The next commit may conflict as well.
+            Err(e) => match repo.unmerged_paths() {
+                Ok(paths) if !paths.is_empty() => continue,
+                _ => return Err(e),
+            },
+        }
+    }
+}
+
+/// Offers to resolve the conflicts that stopped the rebase with
+/// `git mergetool`, asking again while files are left unresolved.
+///
+/// Once everything is resolved, the resolution is committed with the
+/// original commit's message and author, leaving the rebase stopped there as
+/// at an `edit` line. A resolution that leaves nothing to commit drops the
+/// commit instead, as `git rebase --continue` would.
+///
+/// # Returns
+///
+/// * `Ok(true)` if the rebase can go on.
+/// * `Ok(false)` if there was nothing to resolve, no terminal to ask on, or
+///   the user declined; the rebase stays stopped at the conflict.
+/// * `Err(String)` if Git failed.
+fn resolve_with_mergetool(repo: &Repo, options: git::CommitOptions) -> Result<bool, String> {
+    if options.quiet || !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    loop {
+        let conflicts = match repo.unmerged_paths() {
+            Ok(paths) => paths,
+            Err(e) => return Err(e),
+        };
+        if conflicts.is_empty() {
+            break;
+        }
+        for path in &conflicts {
+            eprintln!("  {}", path);
+        }
+        let mut prompter = prompt::DialoguerConfirmPrompter;
+        match prompt::confirm_mergetool(&mut prompter, conflicts.len()) {
+            Ok(true) => {}
+            Ok(false) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        if let Err(e) = repo.mergetool() {
+            eprintln!("{}", style(e).yellow());
+        }
+    }
+
+    let original = match repo.rebase_current_commit() {
+        Ok(sha) => sha,
+        Err(e) => return Err(e),
+    };
+    let staged = match repo.has_staged_changes() {
+        Ok(s) => s,
+        Err(e) => return Err(e),
+    };
+    let step = if staged {
+        repo.commit_resolution(&original, options)
+    } else {
+        eprintln!("The resolution leaves nothing to commit; dropping the commit.");
+        repo.rebase_continue(options)
+    };
+    match step {
+        Ok(_) => {
+            eprintln!(
+                "{}",
+                style("Conflict resolved; resuming the rewrite.").green()
+            );
+            Ok(true)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Picks the lines worth showing from what Git printed on stderr during a
+/// successful step: hints, warnings and errors, indented for the log pane.
+/// Progress and status chatter such as `Successfully rebased` is dropped.
+pub(crate) fn warning_lines(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| {
+            let lower = l.to_lowercase();
+            ["hint:", "warning:", "error:"]
+                .iter()
+                .any(|p| lower.starts_with(p))
+        })
+        .map(|l| format!("  {}", l))
+        .collect()
+}
+
+/// Runs the rebase loop behind the `--tui` dashboard.
+fn run_rebase_dashboard(
+    repo: &Repo,
+    mapping: &Mapping,
+    edits: &MessageEdits,
+    options: git::CommitOptions,
+    mut skipped: Option<&mut Vec<String>>,
+) -> Result<(), Error> {
+    let mut stall = StallCheck::new();
+    let step = || {
+        let stop = progress::current_stop(repo.git_dir());
+        if stall.stalled(&stop) {
+            return Err(stall_error(&stop));
+        }
+        let original = repo.rebase_current_commit().unwrap_or_default();
+        let short = &original[..original.len().min(7)];
+        let amended = match amend_current_commit(repo, mapping, edits, None, options) {
+            Ok(out) => out,
+            Err(e) => return Err(format!("Failed to amend commit {}: {}", short, e)),
+        };
+        let mut lines = vec![format!("Amended {}.", short)];
+        let continued = match repo.rebase_continue(options) {
+            Ok(out) => out,
+            Err(e) => {
+                let list = match skipped.as_deref_mut() {
+                    Some(list) => list,
+                    None => return Err(e),
+                };
+                let from = list.len();
+                return match skip_conflicts(repo, options, list) {
+                    Ok(true) => {
+                        for sha in &list[from..] {
+                            lines.push(format!(
+                                "Skipped {}: it does not apply cleanly.",
+                                mirror::short(sha)
+                            ));
+                        }
+                        Ok(lines.join("\n"))
+                    }
+                    Ok(false) => Err(e),
+                    Err(skip_error) => Err(skip_error),
+                };
+            }
+        };
+        // Git's output is captured under the dashboard; keep its warnings.
+        for out in [amended, continued] {
+            lines.extend(warning_lines(&out.stderr_text()));
+        }
+        Ok(lines.join("\n"))
+    };
+
+    match tui::run(repo, step) {
+        tui::Outcome::Finished { amended, elapsed } => {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "✅ Successfully rewrote commit authors ({} commits in {}).",
+                    amended,
+                    progress::format_duration(elapsed)
+                ))
+                .green()
+                .bold()
+            );
+            Ok(())
+        }
+        tui::Outcome::Failed(e) => Err(rebase_step_error(repo, e, options, "rewriting")),
+        tui::Outcome::Aborted => Err(Error::new("the rebase was aborted; history is unchanged")),
+    }
+}
+
+/// Determines whether the provided name and email are unchanged from the defaults.
+///
+/// Both values are compared after trimming leading and trailing whitespace.  
+/// Returns `true` only if **both** the `name` and `email` match their corresponding
+/// defaults exactly after trimming; otherwise returns `false`.
+///
+/// # Parameters
+///
+/// * `name` – The proposed author name.
+/// * `email` – The proposed author email.
+/// * `default_name` – The current default author name (e.g., from `git config`).
+/// * `default_email` – The current default author email.
+///
+/// # Returns
+///
+/// * `true` if both name and email match the defaults (ignoring surrounding whitespace).  
+/// * `false` otherwise.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Example (function is crate-private):
+/// // use git_author_rewrite::cli::should_exit_no_change;
+/// // assert!(should_exit_no_change("Alice ", "alice@example.com ", "Alice", "alice@example.com"));
+/// ```
+pub(crate) fn should_exit_no_change(
+    name: &str,
+    email: &str,
+    default_name: &str,
+    default_email: &str,
+) -> bool {
+    name.trim() == default_name.trim() && email.trim() == default_email.trim()
+}
+
+/// Runs `verify`, listing commits whose author or committer is unexpected.
+///
+/// Returns [`EXIT_VERIFY_FAILED`] if any commit does not match.
+fn run_verify(repo: &Repo, expect: &Expectation, revs: Option<&str>) -> Result<i32, Error> {
+    match verify::verify(repo, expect, revs) {
+        Ok(report) => Ok(print_violations(
+            &report,
+            false,
+            "match the expected identity",
+            "do not match the expected identity",
+        )),
+        Err(e) => Err(Error::new(e)),
+    }
+}
+
+/// Prints the outcome of `verify` or `check`: the violations (with their
+/// reasons if `reasons` is set) on stdout, then a summary saying the
+/// commits `passed` or `failed`.
+///
+/// Returns [`EXIT_VERIFY_FAILED`] if there are violations, else 0.
+fn print_violations(report: &verify::Report, reasons: bool, passed: &str, failed: &str) -> i32 {
+    if report.violations.is_empty() {
+        eprintln!(
+            "{}",
+            style(format!("✅ All {} commits {}.", report.checked, passed))
+                .green()
+                .bold()
+        );
+        return 0;
+    }
+
+    for v in report.violations.iter().take(MAX_LISTED_VIOLATIONS) {
+        let short = v.sha.get(..10).unwrap_or(&v.sha);
+        if reasons {
+            println!("{}  {:<9}  {}: {}", short, v.role, v.found, v.reason);
+        } else {
+            println!("{}  {:<9}  {}", short, v.role, v.found);
+        }
+    }
+    if report.violations.len() > MAX_LISTED_VIOLATIONS {
+        println!(
+            "… and {} more",
+            report.violations.len() - MAX_LISTED_VIOLATIONS
+        );
+    }
+
+    let mut commits: Vec<&str> = report.violations.iter().map(|v| v.sha.as_str()).collect();
+    commits.dedup();
+    eprintln!(
+        "{}",
+        style(format!(
+            "❌ {} of {} commits {}.",
+            commits.len(),
+            report.checked,
+            failed
+        ))
+        .red()
+        .bold()
+    );
+    EXIT_VERIFY_FAILED
+}
+
+/// Runs `check`: lists every commit whose author or committer breaks the
+/// policy in `policy_path` (default [`policy::DEFAULT_POLICY_FILE`] at the
+/// top of the working tree).
+///
+/// Returns [`EXIT_VERIFY_FAILED`] if any commit breaks the policy.
+fn run_check(
+    repo: &Repo,
+    policy_path: Option<&str>,
+    revs: Option<&str>,
+    identities: &[String],
+) -> Result<i32, Error> {
+    let path = match policy_path {
+        Some(p) => p.to_string(),
+        None => repo
+            .root()
+            .join(policy::DEFAULT_POLICY_FILE)
+            .to_string_lossy()
+            .into_owned(),
+    };
+    if !identities.is_empty() {
+        return check_identities(&path, identities);
+    }
+    match policy::read(&path).and_then(|p| verify::verify(repo, &p, revs)) {
+        Ok(report) => Ok(print_violations(
+            &report,
+            true,
+            "follow the author policy",
+            "break the author policy",
+        )),
+        Err(e) => Err(Error::new(e)),
+    }
+}
+
+/// Runs `check --identity`: checks each of `identities` against the policy
+/// at `path`, printing nothing when they all comply so the `commit-msg` hook
+/// stays quiet.
+fn check_identities(path: &str, identities: &[String]) -> Result<i32, Error> {
+    let parsed = policy::read(path).and_then(|p| {
+        identities
+            .iter()
+            .map(|v| policy::parse_identity(v))
+            .collect::<Result<Vec<Identity>, String>>()
+            .map(|ids| (p, ids))
+    });
+    let (rules, ids) = match parsed {
+        Ok(found) => found,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+
+    let mut broken = 0;
+    for id in &ids {
+        let reasons = rules.violations(id);
+        if !reasons.is_empty() {
+            broken += 1;
+        }
+        for reason in reasons {
+            eprintln!("{}: {}", id, reason);
+        }
+    }
+    if broken == 0 {
+        return Ok(0);
+    }
+    eprintln!(
+        "{}",
+        style(format!(
+            "❌ {} of {} identities break the author policy ({}).",
+            broken,
+            ids.len(),
+            path
+        ))
+        .red()
+        .bold()
+    );
+    Ok(EXIT_VERIFY_FAILED)
+}
+
+/// Runs `install-hook`: writes the `kind` hook, checking against `policy_path`
+/// or the policy file at the top of the working tree.
+///
+/// The policy is read first so a missing or broken file is reported now
+/// rather than on the next push.
+fn install_hook(
+    repo: &Repo,
+    kind: HookKind,
+    policy_path: Option<&str>,
+    force: bool,
+) -> Result<PathBuf, String> {
+    let policy_arg = match policy_path {
+        Some(p) => match std::path::absolute(p) {
+            Ok(abs) => Some(abs.to_string_lossy().into_owned()),
+            Err(e) => return Err(format!("cannot resolve {}: {}", p, e)),
+        },
+        None => None,
+    };
+    let check_path = match &policy_arg {
+        Some(p) => PathBuf::from(p),
+        None => repo.root().join(policy::DEFAULT_POLICY_FILE),
+    };
+    if policy_arg.is_none() && !check_path.exists() {
+        return Err(format!(
+            "no {} at the top of the working tree; write one (see `check` in --help) or pass --policy",
+            policy::DEFAULT_POLICY_FILE
+        ));
+    }
+    if let Err(e) = policy::read(&check_path.to_string_lossy()) {
+        return Err(e);
+    }
+
+    let exe = match env::current_exe() {
+        Ok(p) => p.to_string_lossy().into_owned(),
+        Err(e) => return Err(format!("cannot locate this executable: {}", e)),
+    };
+    hook::install(repo, kind, &exe, policy_arg.as_deref(), force)
+}
+
+/// Runs `bench`: rewrites a temporary clone of the repository with each of
+/// `engines` (every engine if empty) and compares time and peak memory.
+fn run_bench(engines: &[EngineKind]) -> Result<i32, Error> {
+    let repo = match Repo::discover() {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(Error::new(format!(
+                "bench must run inside a repository: {}",
+                e
+            )));
+        }
+    };
+    let commits = match repo.rev_list_count(&["HEAD"]) {
+        Ok(n) => n,
+        Err(_) => {
+            return Err(Error::new("the repository has no commits to rewrite"));
+        }
+    };
+    let exe = match env::current_exe() {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(Error::new(format!("cannot locate own executable: {}", e)));
+        }
+    };
+    let engines: Vec<EngineKind> = if engines.is_empty() {
+        engine::NAMES
+            .iter()
+            .filter_map(|n| EngineKind::parse(n).ok())
+            .collect()
+    } else {
+        engines.to_vec()
+    };
+
+    eprintln!(
+        "{}",
+        style(format!(
+            "Benchmarking {} engines on {} commits, each in a temporary clone (this repository is not changed)…",
+            engines.len(),
+            commits
+        ))
+        .bold()
+    );
+    let results = bench::run(&exe, &repo.root().to_string_lossy(), &engines, |engine| {
+        eprintln!("  {}…", engine.name());
+    });
+
+    println!();
+    for (i, line) in bench::table_lines(&results).iter().enumerate() {
+        if i == 0 {
+            println!("{}", console::style(line).bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+    println!();
+    match bench::fastest(&results) {
+        Some(engine) => {
+            println!(
+                "{}",
+                console::style(format!("Fastest: --engine {}", engine.name()))
+                    .green()
+                    .bold()
+            );
+            Ok(0)
+        }
+        None => Err(Error::new("no engine completed the rewrite")),
+    }
+}
+
+/// Runs `selftest`: rewrites a scratch repository end to end and prints
+/// which step, if any, failed.
+fn run_selftest() -> Result<i32, Error> {
+    let exe = match env::current_exe() {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(Error::new(format!("cannot locate own executable: {}", e)));
+        }
+    };
+    let dir = selftest::workdir();
+    eprintln!(
+        "{}",
+        style(format!(
+            "Rewriting a scratch repository in {}…",
+            dir.display()
+        ))
+        .bold()
+    );
+    let report = selftest::run(&exe, &dir);
+    eprintln!();
+    print_checklist(&report);
+
+    if !report.passed() {
+        return Err(Error::new(format!(
+            "Selftest failed; the scratch repository is kept at {}.",
+            dir.display()
+        )));
+    }
+    eprintln!(
+        "{}",
+        style("✅ Selftest passed: this environment can rewrite history.")
+            .green()
+            .bold()
+    );
+    Ok(0)
+}
+
+/// Runs a management subcommand such as `profile list` or `verify`.
+///
+/// Profiles live in the global git config, so these commands work outside a
+/// repository.
+fn run_command(command: &args::Command) -> Result<i32, Error> {
+    let result = match command {
+        args::Command::ProfileAdd {
+            profile,
+            name,
+            email,
+        } => {
+            let id = Identity::new(name.trim(), email.trim());
+            match profile::add(profile, &id) {
+                Ok(_) => {
+                    eprintln!("Saved profile `{}`: {}", profile, id);
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+        args::Command::ProfileList => match profile::list() {
+            Ok(profiles) => {
+                if profiles.is_empty() {
+                    eprintln!("No profiles saved. Add one with `git-author-rewrite profile add`.");
+                }
+                for p in profiles {
+                    println!("{:<12} {}", p.name, p.identity);
+                }
+                Ok(())
+            }
+            Err(e) => Err(e),
+        },
+        args::Command::ProfileRemove { profile } => match profile::remove(profile) {
+            Ok(_) => {
+                eprintln!("Removed profile `{}`.", profile);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        },
+        args::Command::Verify { name, email, revs } => {
+            let expect = Expectation {
+                name: name.clone(),
+                email: email.clone(),
+            };
+            return match Repo::discover() {
+                Ok(repo) => run_verify(&repo, &expect, revs.as_deref()),
+                Err(e) => Err(Error::new(e)),
+            };
+        }
+        args::Command::Check {
+            policy,
+            revs,
+            identities,
+        } => {
+            return match Repo::discover() {
+                Ok(repo) => run_check(&repo, policy.as_deref(), revs.as_deref(), identities),
+                Err(e) => Err(Error::new(e)),
+            };
+        }
+        args::Command::InstallHook {
+            hook,
+            policy,
+            force,
+        } => match Repo::discover() {
+            Ok(repo) => match install_hook(&repo, *hook, policy.as_deref(), *force) {
+                Ok(path) => {
+                    eprintln!("Installed {}", path.display());
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        },
+        args::Command::Selftest => return run_selftest(),
+        args::Command::Bench { engines } => return run_bench(engines),
+        args::Command::Restore { bundle } => match Repo::discover() {
+            Ok(repo) => match backup::restore_bundle(&repo, Path::new(bundle)) {
+                Ok(refs) => {
+                    for r in refs {
+                        eprintln!("Restored {} to {}", r.name, mirror::short(&r.sha));
+                    }
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        },
+    };
+
+    match result {
+        Ok(_) => Ok(0),
+        Err(e) => Err(Error::new(e)),
+    }
+}
+
+/// Lists the flags that must be given when there is no terminal to prompt on.
+///
+/// Returns an empty list when `opts` already answers every prompt. Without
+/// `--yes` the confirmation prompt cannot be answered; the identity values
+/// are listed alongside it so the user learns about them in one go.
+fn non_interactive_requirements(opts: &args::Options, fields: Fields) -> Vec<&'static str> {
+    if opts.yes {
+        return Vec::new();
+    }
+
+    let mut missing = missing_identity_values(opts, fields);
+    missing.push("--yes (or GAR_ASSUME_YES=1)");
+    missing
+}
+
+/// Lists the identity options that `opts` leaves to a prompt.
+fn missing_identity_values(opts: &args::Options, fields: Fields) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    let identity_given = opts.profile.is_some()
+        || opts.rewrite_domain.is_some()
+        || opts.rules.is_some()
+        || opts.sha_map.is_some()
+        || sync_only(opts);
+    if !identity_given && fields != Fields::EmailOnly && opts.name.is_none() {
+        missing.push("--name <NAME> (or GAR_AUTHOR_NAME)");
+    }
+    if !identity_given && fields != Fields::NameOnly && opts.email.is_none() {
+        missing.push("--email <EMAIL> (or GAR_AUTHOR_EMAIL)");
+    }
+    missing
+}
+
+/// Reports whether `--sync-committer` is the only identity change asked for,
+/// so authors are kept and nothing needs to be prompted for.
+fn sync_only(opts: &args::Options) -> bool {
+    opts.sync_committer
+        && opts.name.is_none()
+        && opts.email.is_none()
+        && opts.profile.is_none()
+        && opts.rewrite_domain.is_none()
+        && opts.rules.is_none()
+        && opts.sha_map.is_none()
+}
+
+/// Builds the identity mapping given on the command line: `--rewrite-domain`,
+/// `--rules` or `--sha-map`, or `--sync-committer` on its own.
+///
+/// # Returns
+///
+/// * `Ok(Some(Mapping))` with the mapping, wrapped for `--sync-committer`.
+/// * `Ok(None)` if the identity still has to come from a profile or prompts.
+/// * `Err(String)` if the mapping could not be read.
+fn flag_mapping(opts: &args::Options) -> Result<Option<Mapping>, String> {
+    let parsed = match (
+        opts.rewrite_domain.as_deref(),
+        opts.rules.as_deref(),
+        opts.sha_map.as_deref(),
+    ) {
+        (Some(spec), _, _) => Some(identity::parse_domain_swap(spec)),
+        (None, Some(path), _) => Some(rules::read(path).map(Mapping::Rules)),
+        (None, None, Some(path)) => Some(sha_map::read(path).map(Mapping::Shas)),
+        (None, None, None) => None,
+    };
+    match parsed {
+        Some(Ok(m)) if opts.sync_committer => Ok(Some(Mapping::SyncCommitter(Some(Box::new(m))))),
+        Some(Ok(m)) => Ok(Some(m)),
+        Some(Err(e)) => Err(e),
+        None if sync_only(opts) => Ok(Some(Mapping::SyncCommitter(None))),
+        None => Ok(None),
+    }
+}
+
+/// Builds the mapping to a single identity, taken from `profile_identity` or
+/// prompted for, and saves it to the git config unless told not to.
+///
+/// # Returns
+///
+/// * `Ok(Some(Mapping))` with the mapping, wrapped for `--sync-committer`.
+/// * `Ok(None)` if the prompted identity is the configured one, so there is
+///   nothing to change.
+/// * `Err(Error)` saying why the identity could not be had.
+fn identity_mapping(
+    repo: &Repo,
+    repo_name: &str,
+    opts: &args::Options,
+    fields: Fields,
+    profile_identity: Option<Identity>,
+) -> Result<Option<Mapping>, Error> {
+    let (name, email) = match profile_identity {
+        Some(id) => (id.name, id.email),
+        None => {
+            // Prompt for author input.
+            match get_author_input(repo, repo_name, fields, opts)? {
+                AuthorInput::Changed(n, e) => (n, e),
+                AuthorInput::NoChange => {
+                    eprintln!(
+                        "{}",
+                        style("No changes detected for name or email; exiting without modifying history.")
+                            .yellow()
+                            .bold()
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    let mapping = match build_mapping(name, email, fields) {
+        Ok(m) => m,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+
+    // Update local git config with the fields being rewritten.
+    // --include-if saves them for a directory instead.
+    if !opts.no_config_update && opts.include_if.is_none() {
+        let (config_name, config_email) = config_values(&mapping);
+        update_git_config(repo, config_name, config_email)?;
+        update_global_config(config_name, config_email, opts.global, opts.yes)?;
+    }
+
+    if opts.sync_committer {
+        Ok(Some(Mapping::SyncCommitter(Some(Box::new(mapping)))))
+    } else {
+        Ok(Some(mapping))
+    }
+}
+
+/// Describes `branch` for the `--pick-branch` menu: its name, whether it is
+/// checked out, and how it compares with its upstream.
+fn branch_label(branch: &git::Branch) -> String {
+    let mut label = branch.name.clone();
+    if branch.current {
+        label.push_str(" (current)");
+    }
+    let tracking = match (&branch.upstream, branch.upstream_gone) {
+        (None, _) => String::from("no upstream"),
+        (Some(up), true) => format!("{} is gone", up),
+        (Some(up), false) if branch.ahead == 0 && branch.behind == 0 => {
+            format!("up to date with {}", up)
+        }
+        (Some(up), false) => {
+            let mut counts = Vec::new();
+            if branch.ahead > 0 {
+                counts.push(format!("{} ahead", branch.ahead));
+            }
+            if branch.behind > 0 {
+                counts.push(format!("{} behind", branch.behind));
+            }
+            format!("{} {}", counts.join(", "), up)
+        }
+    };
+    format!("{}  ·  {}", label, tracking)
+}
+
+/// Lets the user choose the branch to rewrite (`--pick-branch`) and checks
+/// it out.
+///
+/// Nothing is asked when there is only one branch, with `--yes` (which keeps
+/// the checked-out branch), or without a terminal, where the usual check for
+/// missing answers reports the prompts it cannot show.
+fn pick_branch(repo: &Repo, opts: &args::Options) -> Result<(), Error> {
+    if opts.yes || !io::stdin().is_terminal() {
+        return Ok(());
+    }
+    let branches = match repo.local_branches() {
+        Ok(b) => b,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    if branches.len() < 2 {
+        return Ok(());
+    }
+
+    let labels: Vec<String> = branches.iter().map(branch_label).collect();
+    let current = branches.iter().position(|b| b.current).unwrap_or(0);
+    let mut select_prompter = prompt::DialoguerSelectPrompter;
+    let chosen = match prompt::select_branch(&mut select_prompter, &labels, current) {
+        Ok(i) => &branches[i],
+        Err(e) => {
+            return Err(Error::new(format!("prompt failed: {}", e)));
+        }
+    };
+    if chosen.current {
+        return Ok(());
+    }
+    if let Err(e) = repo.checkout_branch(&chosen.name) {
+        return Err(Error::new(e));
+    }
+    if !opts.quiet {
+        eprintln!("Switched to branch {}.", style(&chosen.name).bold());
+    }
+    Ok(())
+}
+
+/// Works out the mapping for a run that skips the commit selection: from the
+/// mapping flags, a `--profile`, or the identity prompts.
+///
+/// # Returns
+///
+/// * `Ok(Some(Mapping))` with the mapping.
+/// * `Ok(None)` if the prompted identity changes nothing.
+/// * `Err(Error)` saying why there is no mapping.
+fn resolve_mapping(
+    repo: &Repo,
+    repo_name: &str,
+    opts: &args::Options,
+    fields: Fields,
+) -> Result<Option<Mapping>, Error> {
+    match flag_mapping(opts) {
+        Ok(Some(m)) => return Ok(Some(m)),
+        Ok(None) => {}
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    }
+    let profile_identity = match opts.profile.as_deref() {
+        Some(name) => match profile::load(name) {
+            Ok(p) => Some(p.identity),
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        },
+        None => None,
+    };
+    identity_mapping(repo, repo_name, opts, fields, profile_identity)
+}
+
+/// Offers to resume or abort a rebase that is already in progress (left by
+/// a crashed run or started by hand), instead of letting pre-flight refuse
+/// to start.
+///
+/// Only a merge-backend rebase (`.git/rebase-merge`) can be resumed, and only
+/// when there is a terminal to ask on and `--yes` was not given; otherwise
+/// nothing is asked and pre-flight reports the rebase as usual.
+///
+/// # Returns
+///
+/// * `Ok(Some(LeftoverRebase))` with the choice; after `Abort` the rebase is
+///   gone.
+/// * `Ok(None)` if nothing was asked.
+/// * `Err(Error)` if the prompt or the abort failed.
+fn ask_leftover_rebase(repo: &Repo, opts: &args::Options) -> Result<Option<LeftoverRebase>, Error> {
+    if !repo.git_dir().join("rebase-merge").is_dir() || opts.yes || !io::stdin().is_terminal() {
+        return Ok(None);
+    }
+
+    let mut select_prompter = prompt::DialoguerSelectPrompter;
+    let choice = match prompt::select_leftover_rebase(&mut select_prompter) {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(Error::new(format!("prompt failed: {}", e)));
+        }
+    };
+    match choice {
+        LeftoverRebase::Exit => {
+            eprintln!(
+                "{}",
+                style("Left the rebase as it is; finish it with `git rebase --continue` or drop it with `git rebase --abort`.")
+                    .yellow()
+            );
+        }
+        LeftoverRebase::Abort => {
+            if let Err(e) = repo.rebase_abort() {
+                return Err(Error::new(e));
+            }
+            if !opts.quiet {
+                eprintln!("Aborted the rebase; the branch is back where it was.");
+            }
+        }
+        LeftoverRebase::Resume => {}
+    }
+    Ok(Some(choice))
+}
+
+/// Takes over a rebase found in progress at startup and amends its
+/// remaining stops with the mapping, then prints the usual final report.
+///
+/// The mapping comes from the flags, a profile or the prompts, as for a new
+/// rewrite. The selection, backups and pushed-history checks are skipped:
+/// the rebase already decided which commits it stops at.
+fn resume_rebase(
+    repo: &Repo,
+    opts: &args::Options,
+    fields: Fields,
+    edits: &MessageEdits,
+) -> Result<i32, Error> {
+    let repo_name = repo
+        .root()
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("current repository")
+        .to_string();
+
+    let mapping = match resolve_mapping(repo, &repo_name, opts, fields)? {
+        Some(m) => m,
+        None => return Ok(0),
+    };
+
+    let mut engine = RebaseEngine::new(opts, repo);
+    if !opts.quiet {
+        engine.print_banner(&mapping);
+    }
+    if let Err(e) = engine.prepare() {
+        return Err(Error::new(e));
+    }
+
+    let before = report::author_histogram(repo);
+    if let Err(e) = engine.resume(&mapping, edits) {
+        return Err(e.context("resuming the rebase"));
+    }
+    final_report(repo, before, None, engine.skipped(), &mapping, opts)
+}
+
+/// Continues the chunked rewrite (`--chunk-size`) an earlier run left
+/// unfinished, from the chunk after its last checkpoint, then prints the
+/// usual final report.
+///
+/// A rebase the interrupted run left behind is aborted first: its chunk is
+/// rewritten again from the start. The mapping comes from the flags, a
+/// profile or the prompts; the selection is the one the checkpoint recorded.
+fn resume_chunks(
+    checkpoint: Checkpoint,
+    opts: &args::Options,
+    fields: Fields,
+    edits: &MessageEdits,
+) -> Result<i32, Error> {
+    // The tree may be mid-rebase or on a detached HEAD, so only the basic
+    // checks apply.
+    let report = preflight::run(false);
+    let passed = report.passed();
+    if !opts.quiet || !passed {
+        print_preflight(&report);
+    }
+    let repo = match report.repo {
+        Some(r) if passed => skip_lfs_smudge(r, opts),
+        _ => return Err(Error::new("pre-flight checks failed")),
+    };
+
+    match repo.rev_parse(&checkpoint.branch) {
+        Ok(sha) if sha == checkpoint.tip => {}
+        _ => {
+            return Err(Error::new(format!(
+                "{} has moved since the chunked rewrite started; it cannot be resumed.",
+                checkpoint.branch
+            ))
+            .hint(format!(
+                "Delete .git/{} and start the rewrite again.",
+                chunks::CHECKPOINT_FILE
+            )));
+        }
+    }
+    if repo.rebase_in_progress() {
+        if let Err(e) = repo.rebase_abort() {
+            return Err(Error::new(e));
+        }
+    }
+
+    let repo_name = repo
+        .root()
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("current repository")
+        .to_string();
+    let mapping = match resolve_mapping(&repo, &repo_name, opts, fields)? {
+        Some(m) => m,
+        None => return Ok(0),
+    };
+
+    let mut engine = RebaseEngine::new(opts, &repo);
+    if !opts.quiet {
+        engine.print_banner(&mapping);
+        eprintln!(
+            "{}",
+            style(format!(
+                "Resuming after chunk {} of {}.",
+                checkpoint.done,
+                checkpoint.ends.len()
+            ))
+            .dim()
+        );
+    }
+    if let Err(e) = engine.prepare() {
+        return Err(Error::new(e));
+    }
+    if let Err(e) = engine.unlock_signing_key() {
+        return Err(Error::new(e));
+    }
+
+    let before = report::author_histogram_at(&repo, &checkpoint.tip);
+    let old_head = if opts.check_dco || opts.report_html.is_some() {
+        Some(checkpoint.tip.clone())
+    } else {
+        None
+    };
+    if let Err(e) = engine.run_chunks(checkpoint, &mapping, edits) {
+        return Err(e.context("resuming the chunked rewrite"));
+    }
+    final_report(
+        &repo,
+        before,
+        old_head.as_deref(),
+        engine.skipped(),
+        &mapping,
+        opts,
+    )
+}
+
+/// Collects the repositories for `--repos-file` or `--discover`.
+///
+/// Discovered repositories are offered for selection unless `--yes` was given.
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` with the repositories to rewrite, possibly empty.
+/// * `Err(Error)` if the list could not be read or the prompt failed.
+fn batch_repositories(opts: &args::Options) -> Result<Vec<PathBuf>, Error> {
+    let loaded = match (&opts.repos_file, &opts.discover) {
+        (Some(source), _) => batch::read_repo_list(source),
+        (None, Some(root)) => discover::discover(root, opts.include_vendored),
+        (None, None) => Ok(Vec::new()),
+    };
+    let repos = match loaded {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    let root = match &opts.discover {
+        Some(root) => root,
+        None => return Ok(repos),
+    };
+    eprintln!(
+        "{}",
+        style(format!(
+            "Found {} repositories under {}.",
+            repos.len(),
+            root
+        ))
+        .bold()
+    );
+    if opts.yes || repos.is_empty() {
+        for repo in &repos {
+            eprintln!("  {}", repo.display());
+        }
+        return Ok(repos);
+    }
+
+    if !io::stdin().is_terminal() {
+        return Err(Error::new(
+            "choosing discovered repositories needs a terminal; pass --yes to rewrite all of them.",
+        ));
+    }
+    let labels: Vec<String> = repos.iter().map(|r| r.display().to_string()).collect();
+    let mut select_prompter = prompt::DialoguerMultiSelectPrompter;
+    match prompt::select_repositories(&mut select_prompter, &labels) {
+        Ok(chosen) => Ok(chosen.into_iter().map(|i| repos[i].clone()).collect()),
+        Err(e) => Err(Error::new(format!("prompt failed: {}", e))),
+    }
+}
+
+/// Runs `--repos-file` / `--discover`: the same non-interactive rewrite in
+/// every chosen repository.
+///
+/// # Returns
+///
+/// * `Ok(0)` if every repository was rewritten or had nothing to do.
+/// * `Err(Error)` if the repositories could not be found or any of them failed.
+fn run_batch(opts: &args::Options, fields: Fields) -> Result<i32, Error> {
+    let missing = missing_identity_values(opts, fields);
+    if !missing.is_empty() {
+        let mut err = Error::new("batch rewrites run without prompts; also pass:");
+        for m in missing {
+            err = err.hint(format!("  {}", m));
+        }
+        return Err(err);
+    }
+
+    let repos = batch_repositories(opts)?;
+    if repos.is_empty() {
+        eprintln!(
+            "{}",
+            style("Nothing to rewrite: no repositories were chosen.")
+                .yellow()
+                .bold()
+        );
+        return Ok(EXIT_NOTHING_TO_DO);
+    }
+
+    let exe = match env::current_exe() {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(Error::new(format!("cannot locate own executable: {}", e)));
+        }
+    };
+
+    let child_args = batch::child_args(opts);
+    let outcomes = match opts.jobs {
+        Some(jobs) if jobs > 1 => batch::run_batch_parallel(&exe, &repos, &child_args, jobs),
+        _ => batch::run_batch(&exe, &repos, &child_args, |repo| {
+            eprintln!();
+            eprintln!("{}", style(format!("==> {}", repo.display())).cyan().bold());
+        }),
+    };
+
+    println!();
+    println!("{}", console::style("Batch summary:").bold());
+    for (line, o) in batch::summary_lines(&outcomes).iter().zip(&outcomes) {
+        match o.outcome {
+            batch::Outcome::Failed(_) => println!("  {}", console::style(line).red()),
+            _ => println!("  {}", line),
+        }
+    }
+
+    let failed = outcomes
+        .iter()
+        .filter(|o| matches!(o.outcome, batch::Outcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        return Err(Error::new(format!(
+            "{} of {} repositories failed.",
+            failed,
+            outcomes.len()
+        )));
+    }
+    Ok(0)
+}
+
+/// Deletes the pre-rewrite objects by expiring reflogs and pruning.
+///
+/// This is what actually removes an old email from the repository; until
+/// then the original commits stay reachable through the reflogs.
+fn cleanup_old_objects(repo: &Repo, quiet: bool) -> Result<(), Error> {
+    if !quiet {
+        eprintln!();
+        eprintln!(
+            "{}",
+            style("Expiring reflogs and pruning the pre-rewrite objects…").bold()
+        );
+    }
+
+    match repo.reflog_expire_all() {
+        Ok(_) => {}
+        Err(e) => {
+            return Err(Error::new(format!("`git reflog expire` failed: {}", e)));
+        }
+    }
+
+    match repo.gc_prune_now() {
+        Ok(_) => {}
+        Err(e) => {
+            return Err(Error::new(format!("`git gc --prune=now` failed: {}", e)));
+        }
+    }
+
+    if !quiet {
+        eprintln!(
+            "{}",
+            style("Cleanup complete: the original commits are gone from this repository.")
+                .green()
+                .bold()
+        );
+    }
+    Ok(())
+}
+
+/// Prints the pre-flight checklist lines of `report`, colored by outcome,
+/// followed by a blank line.
+fn print_checklist(report: &preflight::Report) {
+    for (check, line) in report.checks.iter().zip(preflight::checklist_lines(report)) {
+        let styled = match check.status {
+            preflight::Status::Passed => style(line).green(),
+            preflight::Status::Warning => style(line).yellow(),
+            preflight::Status::Failed => style(line).red().bold(),
+        };
+        eprintln!("  {}", styled);
+    }
+    eprintln!();
+}
+
+/// Prints the pre-flight checklist under its heading.
+fn print_preflight(report: &preflight::Report) {
+    eprintln!("{}", style("Pre-flight checks:").bold());
+    print_checklist(report);
+}
+
+/// Prints the pre-rewrite preview: selected commits whose signatures the
+/// rewrite will invalidate. Failures to inspect history are reported as
+/// warnings and do not abort the run.
+fn print_preview(scanned: &Result<HistoryScan, String>) {
+    match scanned {
+        Ok(found) => {
+            for line in preview::signed_commit_lines(&found.signed) {
+                eprintln!("{}", style(line).yellow());
+            }
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Warning: unable to inspect commit signatures ({})",
+                    e
+                ))
+                .yellow()
+            );
+        }
+    }
+}
+
+/// Prints the before/after author table, highlighting rows that changed.
+fn print_histogram(before: &report::Histogram, after: &report::Histogram) {
+    let rows = report::compare(before, after);
+    let lines = report::histogram_lines(&rows);
+
+    println!();
+    println!(
+        "{}",
+        console::style("Authors before and after the rewrite:").bold()
+    );
+    for (i, line) in lines.iter().enumerate() {
+        if i == 0 {
+            println!("  {}", console::style(line).dim());
+        } else if rows[i - 1].before != rows[i - 1].after {
+            println!("  {}", console::style(line).yellow());
+        } else {
+            println!("  {}", line);
+        }
+    }
+}
+
+/// Prints the outcome of the post-rewrite `git fsck`.
+///
+/// # Parameters
+///
+/// * `result` – The value returned by [`Repo::fsck`].
+///
+/// # Returns
+///
+/// `true` if the repository passed the check.
+fn print_fsck(result: &Result<String, String>) -> bool {
+    eprintln!();
+    match result {
+        Ok(warnings) => {
+            eprintln!("{}", style("git fsck: no problems found.").green().bold());
+            for line in warnings.lines() {
+                eprintln!("  {}", style(line).yellow());
+            }
+            true
+        }
+        Err(problems) => {
+            eprintln!(
+                "{}",
+                style(
+                    "git fsck reported problems; do not push this history until they are resolved:"
+                )
+                .red()
+                .bold()
+            );
+            for line in problems.lines() {
+                eprintln!("  {}", line);
+            }
+            false
+        }
+    }
+}
+
+/// Finishes a completed rewrite: author histogram and skipped commits, then
+/// `--fsck`, `--check-dco`, `--report-html` and `--cleanup`.
+///
+/// # Parameters
+///
+/// * `before` – The author histogram taken before the rewrite started.
+/// * `old_head` – The commit `HEAD` pointed at before the rewrite, for
+///   `--check-dco` and `--report-html`.
+/// * `skipped` – The commits `--skip-on-conflict` left out.
+/// * `mapping` – How identities were rewritten, for `--report-html`.
+/// * `opts` – The parsed options.
+///
+/// # Returns
+///
+/// * `Ok(0)`, `Ok(EXIT_FSCK_FAILED)` if `git fsck` found problems, or
+///   `Ok(EXIT_DCO_FAILED)` if rewritten commits lack a matching sign-off.
+/// * `Err(Error)` if the report could not be written or the cleanup failed.
+fn final_report(
+    repo: &Repo,
+    before: Result<report::Histogram, String>,
+    old_head: Option<&str>,
+    skipped: &[String],
+    mapping: &Mapping,
+    opts: &args::Options,
+) -> Result<i32, Error> {
+    let after = if !opts.quiet || opts.report_html.is_some() {
+        Some(report::author_histogram(repo))
+    } else {
+        None
+    };
+
+    // Show that exactly the intended commits changed hands.
+    if !opts.quiet {
+        if let (Ok(before), Some(Ok(after))) = (&before, &after) {
+            print_histogram(before, after);
+        }
+    }
+
+    // Their changes are missing from the new history; say so even when quiet.
+    if !skipped.is_empty() {
+        print_skipped(repo, skipped);
+    }
+
+    // What the terminal warned about, again for the HTML report.
+    let mut warnings = Vec::new();
+    if !skipped.is_empty() {
+        warnings.push(format!(
+            "{} commit(s) did not apply cleanly and were skipped; their changes are not in the rewritten history: {}",
+            skipped.len(),
+            skipped.join(", ")
+        ));
+    }
+    let mut code = 0;
+
+    // Catch corruption before the user force-pushes.
+    if opts.fsck {
+        let result = repo.fsck();
+        // Quiet runs only hear about problems.
+        let clean = match result {
+            Ok(_) if opts.quiet => true,
+            _ => print_fsck(&result),
+        };
+        if let Err(problems) = &result {
+            warnings.push(format!("git fsck reported problems: {}", problems.trim()));
+        }
+        if !clean {
+            // Keep the old objects around while the repository needs repair.
+            code = EXIT_FSCK_FAILED;
+        }
+    }
+
+    if let (Some(old), true, 0) = (old_head, opts.check_dco, code) {
+        let revs = ["HEAD", "--not", old];
+        let clean = match dco::check(repo, &revs) {
+            Ok(r) => {
+                for v in &r.violations {
+                    warnings.push(format!(
+                        "{} by {} is not signed off by its author (--check-dco).",
+                        v.sha, v.author
+                    ));
+                }
+                print_dco_report(&r, opts.quiet)
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    style(format!("Error: cannot check sign-offs: {}", e))
+                        .red()
+                        .bold()
+                );
+                warnings.push(format!("Sign-offs could not be checked: {}", e));
+                false
+            }
+        };
+        if !clean {
+            // Keep the old objects around so the rewrite can still be undone.
+            code = EXIT_DCO_FAILED;
+        }
+    }
+
+    if let Some(path) = &opts.report_html {
+        let authors = match (&before, &after) {
+            (Ok(before), Some(Ok(after))) => report::compare(before, after),
+            _ => {
+                warnings.push(String::from(
+                    "The author histograms could not be read, so they are left out.",
+                ));
+                Vec::new()
+            }
+        };
+        let commits = report_commits(repo, old_head, skipped, opts, &mut warnings);
+        let page = html_report::Report {
+            repository: repo.root().display().to_string(),
+            branch: repo
+                .symbolic_head()
+                .map(|b| b.trim_start_matches("refs/heads/").to_string()),
+            generated: audit::utc_now(),
+            command: env::args().skip(1).collect(),
+            mapping: mapping.describe(),
+            authors,
+            commits,
+            warnings,
+        };
+        if let Err(e) = html_report::write(Path::new(path), &page) {
+            return Err(Error::new(e).hint("The rewrite itself finished."));
+        }
+        if !opts.quiet {
+            eprintln!(
+                "{}",
+                style(format!("Wrote the HTML report to {}.", path)).dim()
+            );
+        }
+    }
+
+    if code != 0 {
+        return Ok(code);
+    }
+
+    if opts.cleanup {
+        cleanup_old_objects(repo, opts.quiet)?;
+    }
+
+    Ok(0)
+}
+
+/// Lists the commits the rewrite replaced for `--report-html`, or why they
+/// cannot be listed, and adds a warning when some of them were pushed.
+fn report_commits(
+    repo: &Repo,
+    old_head: Option<&str>,
+    skipped: &[String],
+    opts: &args::Options,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<html_report::CommitChange>, String> {
+    if opts.replace_mode {
+        return match html_report::replaced_commits(repo) {
+            Ok(c) => Ok(c),
+            Err(e) => Err(format!("The replace refs could not be read: {}", e)),
+        };
+    }
+    let old = match old_head {
+        Some(o) => o,
+        None => {
+            return Err(String::from(
+                "The commits before a resumed rebase are not known, so the SHA mapping is left out.",
+            ));
+        }
+    };
+
+    // Commits a remote has, minus those it has outside the rewritten range.
+    let replaced = repo.rev_list_count(&[old, "--not", "HEAD"]);
+    let unpushed = repo.rev_list_count(&[old, "--not", "HEAD", "--remotes"]);
+    if let (Ok(replaced), Ok(unpushed)) = (replaced, unpushed) {
+        if replaced > unpushed {
+            warnings.push(format!(
+                "{} of the {} replaced commits were already pushed; collaborators need to rebase onto, or re-clone, the rewritten history after the force-push.",
+                replaced - unpushed,
+                replaced
+            ));
+        }
+    }
+
+    match html_report::commit_changes(repo, old, skipped, &opts.drop_author) {
+        Ok(c) => c,
+        Err(e) => Err(format!("The rewritten commits could not be read: {}", e)),
+    }
+}
+
+/// Lists the commits `--skip-on-conflict` left out of the rewritten history,
+/// with their subjects when Git can still find them.
+fn print_skipped(repo: &Repo, skipped: &[String]) {
+    eprintln!(
+        "{}",
+        style(format!(
+            "⚠️  Skipped {} commit{} that did not apply cleanly; their changes are not in the rewritten history:",
+            skipped.len(),
+            if skipped.len() == 1 { "" } else { "s" }
+        ))
+        .yellow()
+        .bold()
+    );
+    let mut args = vec!["--no-walk=unsorted"];
+    args.extend(skipped.iter().map(String::as_str));
+    match repo.log("%H  %s", &args) {
+        Ok(log) => {
+            for line in log.lines() {
+                eprintln!("  {}", line);
+            }
+        }
+        Err(_) => {
+            for sha in skipped {
+                eprintln!("  {}", sha);
+            }
+        }
+    }
+}
+
+/// Prints the outcome of `--check-dco`, listing the commits that are not
+/// signed off by their author. Quiet runs only hear about problems.
+///
+/// # Returns
+///
+/// `true` if every checked commit passed.
+fn print_dco_report(report: &dco::Report, quiet: bool) -> bool {
+    if report.violations.is_empty() {
+        if !quiet {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "DCO: all {} rewritten commits are signed off by their author.",
+                    report.checked
+                ))
+                .green()
+                .bold()
+            );
+        }
+        return true;
+    }
+
+    for v in report.violations.iter().take(MAX_LISTED_VIOLATIONS) {
+        let short = v.sha.get(..10).unwrap_or(&v.sha);
+        let detail = match &v.problem {
+            dco::Problem::Missing => String::from("no Signed-off-by"),
+            dco::Problem::Mismatch(found) => {
+                let names: Vec<String> = found.iter().map(|id| id.to_string()).collect();
+                format!("signed off by {}", names.join(", "))
+            }
+        };
+        eprintln!("  {}  {}: {}", short, v.author, detail);
+    }
+    if report.violations.len() > MAX_LISTED_VIOLATIONS {
+        eprintln!(
+            "  … and {} more",
+            report.violations.len() - MAX_LISTED_VIOLATIONS
+        );
+    }
+    eprintln!(
+        "{}",
+        style(format!(
+            "{} of {} rewritten commits are not signed off by their author; a DCO-enforcing upstream will reject them. Amend the sign-offs (e.g. `git rebase --signoff`) before pushing.",
+            report.violations.len(),
+            report.checked
+        ))
+        .red()
+        .bold()
+    );
+    false
+}
+
+/// Lists the annotated tags `--rewrite-tags` should consider, or none without it.
+///
+/// Must run before the rewrite, while the selected commits are still the ones
+/// reachable from `HEAD`.
+fn tags_to_rewrite(
+    repo: &Repo,
+    selection: &Selection,
+    opts: &args::Options,
+) -> Result<Vec<AnnotatedTag>, Error> {
+    if !opts.rewrite_tags {
+        return Ok(Vec::new());
+    }
+    match tags::annotated_tags(repo, selection) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(Error::new(format!("cannot list tags: {}", e))),
+    }
+}
+
+/// Rewrites the taggers of `candidates` for `--rewrite-tags` and reports the result.
+fn rewrite_tag_identities(
+    repo: &Repo,
+    candidates: &[AnnotatedTag],
+    mapping: &Mapping,
+    opts: &args::Options,
+) -> Result<(), Error> {
+    if !opts.rewrite_tags {
+        return Ok(());
+    }
+    match tags::rewrite_tags(repo, candidates, mapping, opts.sign_tags, opts.replace_mode) {
+        Ok(_) if opts.quiet => Ok(()),
+        Ok(done) => {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "✅ Rewrote the tagger of {} annotated tags.",
+                    done.len()
+                ))
+                .green()
+                .bold()
+            );
+            for tag in &done {
+                eprintln!(
+                    "  {}",
+                    tag.refname
+                        .strip_prefix("refs/tags/")
+                        .unwrap_or(&tag.refname)
+                );
+            }
+            Ok(())
+        }
+        Err(e) => Err(Error::new(e)),
+    }
+}
+
+/// Builds the identity mapping from options alone, for runs that cannot prompt.
+///
+/// The caller has checked that the options name an identity (see
+/// [`missing_identity_values`]).
+fn noninteractive_mapping(opts: &args::Options, fields: Fields) -> Result<Mapping, String> {
+    if sync_only(opts) {
+        return Ok(Mapping::SyncCommitter(None));
+    }
+    match noninteractive_author_mapping(opts, fields) {
+        Ok(m) if opts.sync_committer => Ok(Mapping::SyncCommitter(Some(Box::new(m)))),
+        other => other,
+    }
+}
+
+/// Builds the author part of [`noninteractive_mapping`].
+fn noninteractive_author_mapping(opts: &args::Options, fields: Fields) -> Result<Mapping, String> {
+    if let Some(spec) = opts.rewrite_domain.as_deref() {
+        return identity::parse_domain_swap(spec);
+    }
+    if let Some(path) = opts.rules.as_deref() {
+        return rules::read(path).map(Mapping::Rules);
+    }
+    if let Some(path) = opts.sha_map.as_deref() {
+        return sha_map::read(path).map(Mapping::Shas);
+    }
+    let (name, email) = match opts.profile.as_deref() {
+        Some(profile_name) => match profile::load(profile_name) {
+            Ok(p) => (p.identity.name, p.identity.email),
+            Err(e) => return Err(e),
+        },
+        None => (
+            opts.name.clone().unwrap_or_default(),
+            opts.email.clone().unwrap_or_default(),
+        ),
+    };
+    build_mapping(name, email, fields)
+}
+
+/// Runs `--mirror`: clones `url` as a bare mirror, rewrites every branch and
+/// tag there, shows which refs move and force-pushes them after confirmation.
+///
+/// The temporary clone is deleted afterwards, unless something went wrong
+/// after it was rewritten, in which case it is kept for inspection.
+///
+/// # Returns
+///
+/// * `Ok(0)` once pushed (or canceled), `Ok(EXIT_NOTHING_TO_DO)` if nothing
+///   changes, or `Ok(EXIT_FSCK_FAILED)` if `--fsck` found problems.
+/// * `Err(Error)` if the clone, rewrite or push failed.
+fn run_mirror(
+    url: &str,
+    opts: &args::Options,
+    fields: Fields,
+    edits: &MessageEdits,
+) -> Result<i32, Error> {
+    let missing = missing_identity_values(opts, fields);
+    if !missing.is_empty() {
+        let mut err = Error::new("--mirror needs the identity up front; also pass:");
+        for m in missing {
+            err = err.hint(format!("  {}", m));
+        }
+        return Err(err);
+    }
+    let mapping = match noninteractive_mapping(opts, fields) {
+        Ok(m) => m,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    if !opts.yes && !io::stdin().is_terminal() {
+        return Err(Error::new(
+            "confirming the push needs a terminal; pass --yes to push without asking.",
+        ));
+    }
+
+    let cwd = match env::current_dir() {
+        Ok(d) => d,
+        Err(e) => {
+            return Err(Error::new(format!(
+                "cannot read the current directory: {}",
+                e
+            )));
+        }
+    };
+    let url = &mirror::absolute_url(url, &cwd);
+    let dir = mirror::workdir();
+    if let Err(e) = mirror::clone(url, &dir) {
+        return Err(Error::new(e));
+    }
+    let kept = format!("The mirror is kept at {} for inspection.", dir.display());
+    let repo = match Repo::open(&dir) {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(Error::new(format!("cannot open {}: {}", dir.display(), e)).hint(kept));
+        }
+    };
+
+    let current_user = Identity::new(
+        &repo.config_get("user.name").unwrap_or_default(),
+        &repo.config_get("user.email").unwrap_or_default(),
+    );
+    let tag_mapping = if opts.rewrite_tags {
+        Some(&mapping)
+    } else {
+        None
+    };
+    if !opts.quiet {
+        eprintln!("{}", style("Rewriting the mirror…").bold());
+    }
+    let rewrite = match history::rewrite_history(
+        &repo,
+        mirror::MIRROR_REFS,
+        &mapping,
+        &current_user,
+        edits,
+        tag_mapping,
+        opts.sign_tags,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(Error::new(e).context("rewriting the mirror").hint(kept));
+        }
+    };
+
+    if rewrite.refs.is_empty() {
+        eprintln!(
+            "{}",
+            style("Nothing to rewrite: no branch or tag changes.")
+                .yellow()
+                .bold()
+        );
+        mirror::discard(&dir);
+        return Ok(EXIT_NOTHING_TO_DO);
+    }
+
+    // The refs are shown whenever they are about to be confirmed.
+    if !opts.quiet || !opts.yes {
+        eprintln!(
+            "Rewrote {} commits; these refs will change on {}:",
+            rewrite.commits,
+            style(url).bold()
+        );
+        for line in mirror::ref_diff_lines(&rewrite.refs) {
+            eprintln!("  {}", line);
+        }
+    }
+
+    if opts.fsck {
+        // Validates the newly written objects before anything leaves this machine.
+        let result = repo.fsck();
+        // Quiet runs only hear about problems.
+        let clean = match result {
+            Ok(_) if opts.quiet => true,
+            _ => print_fsck(&result),
+        };
+        if !clean {
+            eprintln!("{}", style("Not pushing.").red().bold());
+            eprintln!("{}", kept);
+            return Ok(EXIT_FSCK_FAILED);
+        }
+    }
+
+    let confirmed = if opts.yes {
+        Ok(true)
+    } else {
+        let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+        prompt::confirm_mirror_push(&mut confirm_prompter, rewrite.refs.len(), url)
+    };
+    match confirmed {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!(
+                "{}",
+                style("Canceled by user. Nothing was pushed.")
+                    .yellow()
+                    .bold()
+            );
+            mirror::discard(&dir);
+            return Ok(0);
+        }
+        Err(e) => {
+            return Err(Error::new(format!("prompt failed: {}", e)).hint(kept));
+        }
+    }
+
+    if let Err(e) = mirror::push(&repo, url, &rewrite.refs) {
+        return Err(diagnosed(e)
+            .context(format!("pushing to {}", url))
+            .hint(kept));
+    }
+    mirror::discard(&dir);
+    if !opts.quiet {
+        eprintln!(
+            "{}",
+            style(format!(
+                "✅ Pushed the rewritten history ({} refs) to {}.",
+                rewrite.refs.len(),
+                url
+            ))
+            .green()
+            .bold()
+        );
+    }
+    Ok(0)
+}
+
+/// Runs `--all-branches`: rewrites the history of every local branch in one
+/// pass over the commit graph, so a commit shared by several branches gets
+/// one new SHA that all of them point at, then moves the branches together.
+///
+/// Commits are rewritten as objects, reusing their trees, so nothing can
+/// conflict and the working tree is left as it is. Tags move too with
+/// `--rewrite-tags`.
+///
+/// # Returns
+///
+/// * `Ok(0)` once the branches moved (or canceled), `Ok(EXIT_NOTHING_TO_DO)`
+///   if nothing changes, or the exit code of a failed final check.
+/// * `Err(Error)` if a check refused the rewrite or the rewrite failed.
+fn run_all_branches(
+    repo: &Repo,
+    opts: &args::Options,
+    fields: Fields,
+    edits: &MessageEdits,
+) -> Result<i32, Error> {
+    if repo.rebase_in_progress() {
+        return Err(Error::new("a rebase is in progress; finish it with `git rebase --continue` or drop it with `git rebase --abort` first."));
+    }
+    let repo_name = repo
+        .root()
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("current repository")
+        .to_string();
+
+    let pushed = check_pushed_commits(
+        repo,
+        "--branches",
+        &Selection::All,
+        opts.force_pushed_history,
+    )?;
+    let mapping = match resolve_mapping(repo, &repo_name, opts, fields)? {
+        Some(m) => m,
+        None => return Ok(0),
+    };
+
+    let current_user = Identity::new(
+        &repo.config_get("user.name").unwrap_or_default(),
+        &repo.config_get("user.email").unwrap_or_default(),
+    );
+    let (prefixes, tag_mapping): (&[&str], _) = if opts.rewrite_tags {
+        (&["refs/heads/", "refs/tags/"], Some(&mapping))
+    } else {
+        (&["refs/heads/"], None)
+    };
+    if !opts.quiet {
+        eprintln!("{}", style("Rewriting every local branch…").bold());
+    }
+    let rewrite = match history::rewrite_history(
+        repo,
+        prefixes,
+        &mapping,
+        &current_user,
+        edits,
+        tag_mapping,
+        opts.sign_tags,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    if rewrite.refs.is_empty() {
+        eprintln!(
+            "{}",
+            style("Nothing to rewrite: no branch or tag changes.")
+                .yellow()
+                .bold()
+        );
+        return Ok(EXIT_NOTHING_TO_DO);
+    }
+
+    for branch in rewrite
+        .refs
+        .iter()
+        .filter(|r| r.name.starts_with("refs/heads/"))
+    {
+        check_protected_branch(repo, &branch.name, opts.allow_protected, opts.quiet)?;
+    }
+
+    // The refs are shown whenever they are about to be confirmed.
+    if !opts.quiet || !opts.yes {
+        eprintln!("Rewrote {} commits; these refs will move:", rewrite.commits);
+        for line in mirror::ref_diff_lines(&rewrite.refs) {
+            eprintln!("  {}", line);
+        }
+    }
+
+    let settings = match ConfirmSettings::read(repo) {
+        Ok(s) => s,
+        Err(e) => return Err(Error::new(e)),
+    };
+    let typed_reason = match (&pushed, opts.force) {
+        (Some(summary), false) => {
+            typed_confirmation_reason(summary, settings.large_rewrite_threshold)
+        }
+        _ => None,
+    };
+    let confirmed = match typed_reason {
+        Some(reason) => confirm_by_repo_name(&repo_name, &reason),
+        None => match settings.start_answer(opts.yes) {
+            Ok(Some(answer)) => Ok(answer),
+            Ok(None) => {
+                let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+                prompt::confirm_move_refs(&mut confirm_prompter, rewrite.refs.len())
+            }
+            Err(e) => return Err(Error::new(e)),
+        },
+    };
+    match confirmed {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!(
+                "{}",
+                style("Canceled by user. No changes made.").yellow().bold()
+            );
+            return Ok(0);
+        }
+        Err(e) => {
+            return Err(Error::new(format!("prompt failed: {}", e)));
+        }
+    }
+
+    if let Some(path) = &opts.backup_bundle {
+        if let Err(e) = backup::create_bundle(repo, Path::new(path)) {
+            return Err(Error::new(e));
+        }
+    }
+
+    let before = report::author_histogram(repo);
+    if let Err(e) = history::move_refs(
+        repo,
+        &rewrite.refs,
+        "git-author-rewrite: rewrite every branch",
+    ) {
+        return Err(Error::new(e));
+    }
+    if !opts.quiet {
+        eprintln!(
+            "{}",
+            style(format!(
+                "✅ Rewrote {} commits; {} refs moved.",
+                rewrite.commits,
+                rewrite.refs.len()
+            ))
+            .green()
+            .bold()
+        );
+    }
+    final_report(repo, before, None, &[], &mapping, opts)
+}
+
+/// Sets `GIT_LFS_SKIP_SMUDGE=1` for the rebase when `repo` stores files in
+/// Git LFS: the rebase checks out every commit, and each checkout would
+/// download that commit's LFS content.
+fn skip_lfs_smudge(repo: Repo, opts: &args::Options) -> Repo {
+    if !lfs::detect(&repo) {
+        return repo;
+    }
+    if !opts.quiet {
+        eprintln!(
+            "{}",
+            style(format!(
+                "⚠️  Git LFS tracks files in this repository; the rebase checks out every commit, so LFS downloads are skipped for this run ({}=1).",
+                lfs::SKIP_SMUDGE_ENV
+            ))
+            .yellow()
+            .bold()
+        );
+        eprintln!(
+            "{}",
+            style("   Run `git lfs pull` afterwards if files are left as pointers. The metadata engine (--engine metadata) checks nothing out.").dim()
+        );
+    }
+    repo.with_env(lfs::SKIP_SMUDGE_ENV, "1")
+}
+
+/// Picks the OpenPGP key `--gpg-sign` signs with: the user chooses when
+/// more than one secret key can sign (the one `user.signingkey` names, or
+/// one for the new email, is highlighted), otherwise that key is taken.
+/// Warns when the key has no user ID with the new author email, since
+/// hosting sites then show the signatures as unverified.
+///
+/// Without a key list (another `gpg.format`, GnuPG missing, no keys), the
+/// repository is returned unchanged and signing goes by the configuration.
+fn choose_signing_key(repo: Repo, mapping: &Mapping, opts: &args::Options) -> Result<Repo, Error> {
+    let format = repo.config_get("gpg.format").unwrap_or_default();
+    if !opts.gpg_sign || (!format.is_empty() && format != "openpgp") {
+        return Ok(repo);
+    }
+    let keys = match signing::secret_keys(&repo) {
+        Ok(k) if !k.is_empty() => k,
+        _ => return Ok(repo),
+    };
+    let email = match config_values(mapping).1 {
+        Some(e) => e.to_string(),
+        None => repo.config_get("user.email").unwrap_or_default(),
+    };
+    let configured = repo.config_get("user.signingkey").unwrap_or_default();
+    let preferred = signing::preferred_key(
+        &keys,
+        Some(configured.as_str()).filter(|c| !c.is_empty()),
+        Some(email.as_str()).filter(|e| !e.is_empty()),
+    );
+
+    let index = if keys.len() > 1 && !opts.yes && io::stdin().is_terminal() {
+        let labels: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+        let mut select_prompter = prompt::DialoguerSelectPrompter;
+        match prompt::select_signing_key(&mut select_prompter, &labels, preferred) {
+            Ok(i) => i,
+            Err(e) => {
+                return Err(Error::new(format!("prompt failed: {}", e)));
+            }
+        }
+    } else {
+        preferred
+    };
+    let key = match keys.get(index) {
+        Some(k) => k,
+        None => return Ok(repo),
+    };
+
+    if !email.is_empty() && !key.has_email(&email) {
+        eprintln!(
+            "{}",
+            style(format!(
+                "⚠️  The signing key {} has no user ID with {}; hosting sites will show the rewritten commits as unverified.",
+                key.id, email
+            ))
+            .yellow()
+            .bold()
+        );
+    } else if !opts.quiet {
+        eprintln!("{}", style(format!("Signing with {}.", key)).dim());
+    }
+    Ok(repo.with_signing_key(&key.id))
+}
+
+/// Lists the options in use that only work in a rebase, so the rebase
+/// engine becomes the default when any is given.
+fn rebase_only_flags(opts: &args::Options) -> Vec<&'static str> {
+    let flags = [
+        ("--manual", opts.manual),
+        ("--ask-each", opts.ask_each),
+        ("--tui", opts.tui),
+        ("--exec", !opts.exec.is_empty()),
+        ("--drop-author", !opts.drop_author.is_empty()),
+        ("--skip-on-conflict", opts.skip_on_conflict),
+        ("--chunk-size", opts.chunk_size.is_some()),
+        ("--resume", opts.resume),
+        ("--verify", opts.verify),
+        ("--gpg-sign", opts.gpg_sign),
+    ];
+    flags
+        .iter()
+        .filter(|(_, given)| *given)
+        .map(|(flag, _)| *flag)
+        .collect()
+}
+
+/// The commit hooks a rebase runs for every amended commit.
+const COMMIT_HOOKS: [&str; 4] = [
+    "pre-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+];
+
+/// Explains why the default metadata engine should give way to the rebase
+/// engine in `repo`: commit hooks that would otherwise not run, or
+/// `commit.gpgsign`, since only a rebase signs commits.
+///
+/// # Returns
+///
+/// * `Ok(Some(reason))` if the rebase engine should be used.
+/// * `Ok(None)` if the metadata engine loses nothing.
+/// * `Err(String)` if the config could not be read.
+fn metadata_fallback(repo: &Repo, opts: &args::Options) -> Result<Option<&'static str>, String> {
+    let hooks_enabled = match commit_hooks_enabled(repo, opts) {
+        Ok(b) => b,
+        Err(e) => return Err(e),
+    };
+    if hooks_enabled {
+        if let Ok(dir) = repo.hooks_dir() {
+            if COMMIT_HOOKS.iter().any(|hook| dir.join(hook).is_file()) {
+                return Ok(Some(
+                    "the repository has commit hooks, which only run in a rebase (--no-verify skips them)",
+                ));
+            }
+        }
+    }
+    let signing = match signing::configured(repo) {
+        Ok(b) => b,
+        Err(e) => return Err(e),
+    };
+    if signing && !opts.no_gpg_sign {
+        return Ok(Some(
+            "commit.gpgsign is on, and only a rebase signs commits (--no-gpg-sign turns signing off)",
+        ));
+    }
+    Ok(None)
+}
+
+/// The metadata engine (the default): writes each commit anew with its
+/// original tree, then moves the branch once, so nothing can conflict.
+struct MetadataEngine<'a> {
+    opts: &'a args::Options,
+    repo: &'a Repo,
+}
+
+impl Engine for MetadataEngine<'_> {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Metadata
+    }
+
+    fn print_banner(&self, mapping: &Mapping) {
+        print_metadata_banner(mapping, &banner_theme(self.repo));
+    }
+
+    fn confirm(&self) -> Result<bool, String> {
+        let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+        prompt::confirm_metadata(&mut confirm_prompter)
+    }
+
+    fn rewrite(&mut self, job: &Job) -> Result<(), Error> {
+        let backup_ref = if self.opts.backup_refs {
+            match backup::create(self.repo) {
+                Ok(name) => Some(name),
+                Err(e) => return Err(Error::new(e)),
+            }
+        } else {
+            None
+        };
+
+        // Record the configured user where the mapping leaves the committer
+        // to Git, as a rebase would.
+        let current_user = Identity::new(
+            &self.repo.config_get("user.name").unwrap_or_default(),
+            &self.repo.config_get("user.email").unwrap_or_default(),
+        );
+        let rewrite = match history::rewrite_branch(
+            self.repo,
+            job.selection,
+            job.mapping,
+            &current_user,
+            job.edits,
+        ) {
+            Ok(r) => r,
+            Err(e) => return Err(Error::new(e)),
+        };
+        if let Err(e) = history::move_refs(self.repo, &rewrite.refs, METADATA_REFLOG_MESSAGE) {
+            return Err(diagnosed(e).context("moving the branch to the rewritten history"));
+        }
+
+        if !self.opts.quiet {
+            for update in &rewrite.refs {
+                eprintln!(
+                    "{}",
+                    style(format!(
+                        "✅ Rewrote {} commits; {} moved from {} to {}.",
+                        rewrite.commits,
+                        update.name,
+                        mirror::short(&update.old),
+                        mirror::short(&update.new)
+                    ))
+                    .green()
+                    .bold()
+                );
+            }
+        }
+        if let (Some(name), false) = (backup_ref, self.opts.quiet) {
+            eprintln!(
+                "Original history saved as {} (delete with --drop-backup-refs).",
+                style(name).bold()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The reflog message recorded when the metadata engine moves the branch.
+const METADATA_REFLOG_MESSAGE: &str = "git-author-rewrite: rewrite authors";
+
+/// The reflog message recorded when a chunked rewrite moves the branch.
+const CHUNKS_REFLOG_MESSAGE: &str = "git-author-rewrite: rewrite authors in chunks";
+
+/// The rebase engine: an interactive rebase that stops at every selected
+/// commit, amended by [`run_rebase_loop`] (or the `--tui` dashboard).
+struct RebaseEngine<'a> {
+    opts: &'a args::Options,
+    repo: &'a Repo,
+    commit_options: git::CommitOptions,
+    signing_plan: SigningPlan,
+    /// Saved profiles offered alongside the mapping at every stop (`--ask-each`).
+    ask_profiles: Option<Vec<Profile>>,
+    /// Commits skipped because they conflicted (`--skip-on-conflict`).
+    skipped: Vec<String>,
+}
+
+impl<'a> RebaseEngine<'a> {
+    fn new(opts: &'a args::Options, repo: &'a Repo) -> RebaseEngine<'a> {
+        RebaseEngine {
+            opts,
+            repo,
+            commit_options: git::CommitOptions::default(),
+            signing_plan: SigningPlan::Unsigned,
+            ask_profiles: None,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Unlocks the signing key, so signing asks for the passphrase once
+    /// instead of once per commit.
+    fn unlock_signing_key(&self) -> Result<(), String> {
+        if self.signing_plan != SigningPlan::Sign {
+            return Ok(());
+        }
+        if !self.opts.quiet {
+            eprintln!("Unlocking the commit signing key...");
+        }
+        match signing::unlock_key(self.repo) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!(
+                "{}; re-run with --no-gpg-sign to rewrite without signing.",
+                e
+            )),
+        }
+    }
+
+    /// Amends every stop of the rebase in progress until it finishes.
+    fn run_loop(&mut self, mapping: &Mapping, edits: &MessageEdits) -> Result<(), Error> {
+        let skipped = if self.opts.skip_on_conflict {
+            Some(&mut self.skipped)
+        } else {
+            None
+        };
+        let result = if self.opts.tui {
+            run_rebase_dashboard(self.repo, mapping, edits, self.commit_options, skipped)
+        } else {
+            run_rebase_loop(
+                self.repo,
+                mapping,
+                edits,
+                self.ask_profiles.as_deref(),
+                self.commit_options,
+                skipped,
+            )
+        };
+        selection::remove_selection_file(self.repo.git_dir());
+        result
+    }
+
+    /// Takes over a rebase that was already in progress when the tool
+    /// started, amending its remaining stops as if this run had started it.
+    fn resume(&mut self, mapping: &Mapping, edits: &MessageEdits) -> Result<(), Error> {
+        if let Err(e) = self.unlock_signing_key() {
+            return Err(Error::new(e));
+        }
+        self.run_loop(mapping, edits)
+    }
+
+    /// Starts the interactive rebase of `range`, marking the selected
+    /// commits (unless in manual mode).
+    fn start(&mut self, range: git::RebaseRange, selection: &Selection) -> Result<(), Error> {
+        let editor_args = match sequence_editor_args(self.repo, selection, self.opts) {
+            Ok(a) => a,
+            Err(e) => return Err(e),
+        };
+        let todo_editor = match (&editor_args, &self.opts.editor) {
+            (Some(extra), _) => git::TodoEditor::Auto(extra),
+            (None, Some(command)) => git::TodoEditor::Command(command),
+            (None, None) => git::TodoEditor::Default,
+        };
+        if let Err(e) = self
+            .repo
+            .rebase_interactive_range(range, todo_editor, self.commit_options)
+        {
+            // The first commits may already conflict, e.g. after --drop-author.
+            let resumed = self.opts.skip_on_conflict
+                && self.repo.rebase_in_progress()
+                && skip_conflicts(self.repo, self.commit_options, &mut self.skipped)
+                    .unwrap_or(false);
+            if !resumed {
+                return Err(diagnosed(e).context("starting the rebase"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits the branch into chunks of `size` commits (`--chunk-size`),
+    /// saves the checkpoint and rewrites them with [`RebaseEngine::run_chunks`].
+    ///
+    /// Chunks follow the commits in order, which only a linear history has.
+    fn start_chunks(&mut self, job: &Job, size: usize) -> Result<(), Error> {
+        let branch = match self.repo.symbolic_head() {
+            Some(b) => b,
+            None => {
+                return Err(Error::new(
+                    "--chunk-size rewrites a branch, but HEAD is detached.",
+                ));
+            }
+        };
+        match self.repo.rev_list_count(&["--merges", "HEAD"]) {
+            Ok(0) => {}
+            Ok(n) => {
+                return Err(Error::new(format!(
+                    "--chunk-size needs a linear history, but {} has {} merge commits.",
+                    branch.trim_start_matches("refs/heads/"),
+                    n
+                ))
+                .hint("Rewrite it without --chunk-size, or with the default metadata engine."));
+            }
+            Err(e) => return Err(Error::new(e)),
+        }
+        let commits = match self.repo.rev_list(&["--reverse", "HEAD"]) {
+            Ok(c) => c,
+            Err(e) => return Err(Error::new(e)),
+        };
+
+        let checkpoint = Checkpoint::new(&branch, &commits, size, job.selection);
+        if let Err(e) = chunks::save(self.repo.git_dir(), &checkpoint) {
+            return Err(Error::new(e));
+        }
+        if !self.opts.quiet {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Rewriting {} commits in {} chunks of up to {}; if the run stops, continue it with --resume.",
+                    commits.len(),
+                    checkpoint.ends.len(),
+                    size
+                ))
+                .dim()
+            );
+        }
+        self.run_chunks(checkpoint, job.mapping, job.edits)
+    }
+
+    /// Rewrites the chunks `checkpoint` has left, each as a rebase of its own
+    /// onto the previous one with `HEAD` detached, saving the checkpoint after
+    /// each. The branch moves once the last chunk is done.
+    fn run_chunks(
+        &mut self,
+        checkpoint: Checkpoint,
+        mapping: &Mapping,
+        edits: &MessageEdits,
+    ) -> Result<(), Error> {
+        let mut checkpoint = checkpoint;
+        let selection = checkpoint.selection();
+        let total = checkpoint.ends.len();
+        while !checkpoint.finished() {
+            let i = checkpoint.done;
+            let doing = format!("rewriting chunk {} of {}", i + 1, total);
+            if let Err(e) = self.repo.checkout_detached(&checkpoint.ends[i]) {
+                return Err(diagnosed(e).context(doing));
+            }
+            // Without a rewritten chunk to build on, start over from the root.
+            let range = match checkpoint.rewritten.as_deref() {
+                Some(onto) if i > 0 => git::RebaseRange::Onto {
+                    onto,
+                    upstream: &checkpoint.ends[i - 1],
+                },
+                _ => git::RebaseRange::Root,
+            };
+            let result = match self.start(range, &selection) {
+                Ok(()) => self.run_loop(mapping, edits),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                return Err(e.context(doing).hint(
+                    "The chunks before it are kept; re-run the same command with --resume to continue.",
+                ));
+            }
+
+            checkpoint.rewritten = match self.repo.rev_parse("HEAD") {
+                Ok(sha) => Some(sha),
+                Err(e) => return Err(Error::new(e).context(doing)),
+            };
+            checkpoint.done += 1;
+            if let Err(e) = chunks::save(self.repo.git_dir(), &checkpoint) {
+                return Err(Error::new(e).context(doing));
+            }
+            if !self.opts.quiet {
+                eprintln!(
+                    "{}",
+                    style(format!(
+                        "Chunk {} of {} rewritten; checkpoint saved.",
+                        i + 1,
+                        total
+                    ))
+                    .dim()
+                );
+            }
+        }
+
+        let new = checkpoint.rewritten.clone().unwrap_or_default();
+        let update = RefUpdate {
+            name: checkpoint.branch.clone(),
+            old: checkpoint.tip.clone(),
+            new,
+        };
+        if let Err(e) = history::move_refs(self.repo, &[update], CHUNKS_REFLOG_MESSAGE) {
+            return Err(diagnosed(e).context("moving the branch to the rewritten history"));
+        }
+        let short = checkpoint.branch.trim_start_matches("refs/heads/");
+        if let Err(e) = self.repo.checkout_branch(short) {
+            return Err(Error::new(e));
+        }
+        chunks::remove(self.repo.git_dir());
+        Ok(())
+    }
+}
+
+impl Engine for RebaseEngine<'_> {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Rebase
+    }
+
+    fn print_banner(&self, mapping: &Mapping) {
+        print_banner(mapping, self.opts.manual, &banner_theme(self.repo));
+    }
+
+    fn prepare(&mut self) -> Result<(), String> {
+        let run_hooks = match commit_hooks_enabled(self.repo, self.opts) {
+            Ok(b) => b,
+            Err(e) => return Err(e),
+        };
+        self.signing_plan = match commit_signing_plan(self.repo, self.opts) {
+            Ok(p) => p,
+            Err(e) => return Err(e),
+        };
+        self.commit_options = git::CommitOptions {
+            run_hooks,
+            sign: self.signing_plan.sign_override(),
+            quiet: self.opts.tui || self.opts.quiet,
+        };
+        if self.opts.ask_each {
+            self.ask_profiles = match profile::list() {
+                Ok(list) => Some(list),
+                Err(e) => return Err(e),
+            };
+        }
+        Ok(())
+    }
+
+    fn confirm(&self) -> Result<bool, String> {
+        let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+        prompt::confirm_start(&mut confirm_prompter)
+    }
+
+    fn rewrite(&mut self, job: &Job) -> Result<(), Error> {
+        if let Err(e) = self.unlock_signing_key() {
+            return Err(Error::new(e));
+        }
+
+        // Keep a filter-branch style pointer to the original history.
+        let backup_ref = if self.opts.backup_refs {
+            match backup::create(self.repo) {
+                Ok(name) => Some(name),
+                Err(e) => return Err(Error::new(e)),
+            }
+        } else {
+            None
+        };
+
+        let result = match self.opts.chunk_size {
+            Some(size) => self.start_chunks(job, size),
+            None => match self.start(git::RebaseRange::Root, job.selection) {
+                Ok(()) => self.run_loop(job.mapping, job.edits),
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e) = result {
+            return Err(e);
+        }
+
+        if let (Some(name), false) = (backup_ref, self.opts.quiet) {
+            eprintln!(
+                "Original history saved as {} (delete with --drop-backup-refs).",
+                style(name).bold()
+            );
+        }
+        Ok(())
+    }
+
+    fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+}
+
+/// The replace engine (`--replace-mode`): writes corrected commits and points
+/// `git replace` refs at them instead of rebasing.
+struct ReplaceEngine<'a> {
+    opts: &'a args::Options,
+    repo: &'a Repo,
+}
+
+impl Engine for ReplaceEngine<'_> {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Replace
+    }
+
+    fn print_banner(&self, mapping: &Mapping) {
+        print_replace_banner(mapping, &banner_theme(self.repo));
+    }
+
+    fn confirm(&self) -> Result<bool, String> {
+        let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+        prompt::confirm_replace(&mut confirm_prompter)
+    }
+
+    fn rewrite(&mut self, job: &Job) -> Result<(), Error> {
+        // Where the mapping leaves the committer to Git, record the configured
+        // user, as `git commit --amend` would during a rebase.
+        let current_user = Identity::new(
+            &self.repo.config_get("user.name").unwrap_or_default(),
+            &self.repo.config_get("user.email").unwrap_or_default(),
+        );
+        let replaced = match replace::create_replacements(
+            self.repo,
+            job.selection,
+            job.mapping,
+            &current_user,
+            job.edits,
+        ) {
+            Ok(r) => r,
+            Err(e) => return Err(Error::new(e)),
+        };
+
+        if !self.opts.quiet {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "✅ Created {} replacement commits under refs/replace/.",
+                    replaced.len()
+                ))
+                .green()
+                .bold()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The gitoxide engine (`--engine gix`, experimental): rewrites the branch's
+/// commit objects in-process and moves the branch, without a rebase.
+struct GixEngine<'a> {
+    opts: &'a args::Options,
+    repo: &'a Repo,
+}
+
+impl Engine for GixEngine<'_> {
+    fn kind(&self) -> EngineKind {
+        EngineKind::Gix
+    }
+
+    fn print_banner(&self, mapping: &Mapping) {
+        print_gix_banner(mapping, &banner_theme(self.repo));
+    }
+
+    fn confirm(&self) -> Result<bool, String> {
+        let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
+        prompt::confirm_gix(&mut confirm_prompter)
+    }
+
+    fn rewrite(&mut self, job: &Job) -> Result<(), Error> {
+        let backup_ref = if self.opts.backup_refs {
+            match backup::create(self.repo) {
+                Ok(name) => Some(name),
+                Err(e) => return Err(Error::new(e)),
+            }
+        } else {
+            None
+        };
+
+        // As in the replace engine, record the configured user where the
+        // mapping leaves the committer to Git.
+        let current_user = Identity::new(
+            &self.repo.config_get("user.name").unwrap_or_default(),
+            &self.repo.config_get("user.email").unwrap_or_default(),
+        );
+        let rewrite = match gix_backend::rewrite_branch(
+            self.repo,
+            job.selection,
+            job.mapping,
+            &current_user,
+            job.edits,
+        ) {
+            Ok(r) => r,
+            Err(e) => return Err(Error::new(e)),
+        };
+
+        if !self.opts.quiet {
+            for update in &rewrite.refs {
+                eprintln!(
+                    "{}",
+                    style(format!(
+                        "✅ Rewrote {} commits; {} moved from {} to {}.",
+                        rewrite.commits,
+                        update.name,
+                        mirror::short(&update.old),
+                        mirror::short(&update.new)
+                    ))
+                    .green()
+                    .bold()
+                );
+            }
+        }
+        if let (Some(name), false) = (backup_ref, self.opts.quiet) {
+            eprintln!(
+                "Original history saved as {} (delete with --drop-backup-refs).",
+                style(name).bold()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Prints usage information to stdout.
+fn print_help() {
+    println!(
+        "\
+git-author-rewrite {}
+
+Rewrite commit authors across an entire Git repository.
+
+USAGE:
+    git-author-rewrite [OPTIONS]
+    git-author-rewrite profile <add|list|remove> ...
+    git-author-rewrite verify [--name <NAME>] [--email <EMAIL>] [--revs <EXPR>]
+    git-author-rewrite check [--policy <FILE>] [--revs <EXPR> | --identity <IDENT>...]
+    git-author-rewrite install-hook [--hook pre-push|commit-msg] [--policy <FILE>] [--force]
+    git-author-rewrite selftest
+    git-author-rewrite bench [--engine <NAME>]...
+    git-author-rewrite restore --from-bundle <PATH>
+
+OPTIONS:
+    -h, --help       Print help information
+    -V, --version    Print version information
+    --manual         Edit the rebase todo list manually instead of auto-marking all commits
+    --editor <CMD>   With --manual, edit the todo list in CMD (e.g. 'code --wait')
+                     instead of GIT_SEQUENCE_EDITOR/core.editor
+    --show-authors   With --manual, add each commit's author to its todo line
+                     (as a `# Author: Name <email>` comment)
+    --ask-each       At every marked commit, show it and pick its author from a
+                     menu (the mapping's choice, saved profiles, or skip)
+    --pick-branch    Choose the branch to rewrite from a menu of the local
+                     branches (with how far each is ahead of or behind its
+                     upstream); the chosen branch is checked out first
+    --tui            Follow the rebase on a full-screen dashboard (commit list,
+                     progress, elapsed time/ETA, log); p pauses, a aborts
+    --show-commands  Print every git command line (with its environment
+                     overrides) to stderr before running it
+    -q, --quiet      Print nothing on success: no banner, checks or per-commit
+                     progress; errors, warnings and prompts still appear
+    --revs <EXPR>    Only rewrite the commits listed by `git rev-list <EXPR>`
+                     (e.g. 'main..feature' or 'v1.0..HEAD --first-parent')
+    --rewrite-domain <OLD=NEW>
+                     Move author/committer emails from domain OLD to NEW, keeping
+                     names and local parts (no identity prompts)
+    --rules <FILE>   Map authors per commit with the rules in FILE, one per line:
+                     'old@x.com before=2020-01-01 => Name <new@x.com>'
+                     (before=/after= compare the author date; first match wins)
+    --sha-map <CSV>  Give each commit listed in CSV the author on its row:
+                     'sha,new_name,new_email', with full SHAs; commits not
+                     listed are left alone
+    --name-only      Only rewrite author names; each commit keeps its email
+    --email-only     Only rewrite author emails; each commit keeps its name
+    --profile <NAME> Use the identity saved in profile NAME instead of prompting
+    --name <NAME>    Use NAME as the new author name instead of prompting
+    --email <EMAIL>  Use EMAIL as the new author email instead of prompting
+    -y, --yes        Don't ask for confirmation; missing values default to
+                     the current git config
+    --spec <FILE>    Read a JSON job description (identity, filters, engine,
+                     options) from FILE, or from stdin with '-'; flags override it
+    --fsck           Run `git fsck --no-dangling` after the rewrite and report
+                     any problems before you push
+    --check-dco      After the rewrite, check that every rewritten commit has a
+                     Signed-off-by line matching its new author, for upstreams
+                     that enforce the DCO
+    --report-html <PATH>
+                     After the rewrite, write a standalone HTML report to
+                     PATH: identity changes, authors before and after, every
+                     original and rewritten SHA, and the warnings
+    --check-github   Before rewriting, ask the GitHub API whether the new email
+                     is a verified email of the account owning the token in
+                     GITHUB_TOKEN or GH_TOKEN (needs the 'github' build feature)
+    --cleanup        After the rewrite, expire all reflogs and run
+                     `git gc --prune=now` so the original commits (and the
+                     old identities in them) are deleted; this removes the
+                     easy way to undo the rewrite
+    --backup-refs    Save the original branch tip under refs/original/ (as
+                     git filter-branch does) before rewriting
+    --drop-backup-refs
+                     Delete every ref under refs/original/ and exit
+    --include-if <DIR>
+                     Save the new name/email for every repository under DIR:
+                     they go to ~/.gitconfig-<DIR name>, included from the
+                     global config by an includeIf section, instead of to
+                     the local config
+    --backup-bundle <PATH>
+                     Save every ref to a git bundle at PATH before rewriting;
+                     unlike a backup ref it survives gc (see RESTORE)
+    --audit-log <PATH>
+                     Append the audit record of the run (who, when, options,
+                     refs moved) to PATH instead of
+                     .git/author-rewrite-audit.jsonl
+    --engine <NAME>  How the commits are rewritten: 'metadata' (the default:
+                     write each commit anew with its original tree, so nothing
+                     can conflict), 'rebase' (an interactive rebase amending
+                     each commit; chosen automatically for --manual, --ask-each,
+                     --tui, --exec, --drop-author, --skip-on-conflict,
+                     --chunk-size, --verify, --gpg-sign, commit hooks or
+                     commit.gpgsign), 'replace'
+                     (same as --replace-mode) or 'gix' (experimental: rewrite
+                     the commit objects in-process with gitoxide)
+    --replace-mode   Don't rewrite history; create `git replace` refs that show
+                     author-corrected copies of the commits instead (undo with
+                     `git replace -d`, share by pushing refs/replace/*)
+    --message-replace <s/OLD/NEW/FLAGS>
+                     Rewrite commit messages with a sed-style regex substitution
+                     in the same pass (flags: g = every match on a line,
+                     i = ignore case); may be given more than once
+    --drop-author <EMAIL>
+                     Drop the commits authored by EMAIL instead of rewriting
+                     them (e.g. a bot's commits); may be given more than once
+    --exec <CMD>     Run CMD through the shell after each rewritten commit during
+                     the rebase (e.g. a formatter or validator); a failure stops
+                     the rebase. May be given more than once (default:
+                     author-rewrite.exec in git config, one value per command)
+    --skip-on-conflict
+                     Skip commits that do not apply cleanly during the rebase
+                     (git rebase --skip) instead of stopping; they are left out
+                     of the rewritten history and listed at the end
+    --chunk-size <N> Rebase at most N commits at a time (for very large, linear
+                     histories), saving a checkpoint in .git after each chunk;
+                     the branch moves once the last chunk is done
+    --resume         Continue a --chunk-size rewrite that stopped (crash,
+                     reboot, conflict) from its last checkpoint
+    --timeout <SECONDS>
+                     Stop any git command (and the hook, credential helper or
+                     pinentry it runs) that has not finished after SECONDS and
+                     report it as hung (default: wait; a note appears after 30s)
+    --annotate       Append `Rewritten-By:` and `Original-Commit: <old sha>`
+                     trailers to every rewritten commit's message
+    --record-original-sha
+                     Append only an `Original-Commit: <old sha>` trailer to
+                     every rewritten commit's message
+    --no-verify      Skip the pre-commit and commit-msg hooks when amending
+                     each commit (default: author-rewrite.verify in git config)
+    --verify         Run the hooks for every amended commit, even if
+                     author-rewrite.verify is false
+    --gpg-sign       Sign every rewritten commit, asking for the key's
+                     passphrase once before the rebase starts; with several
+                     secret keys, asks which one to sign with
+    --no-gpg-sign    Don't sign rewritten commits, overriding commit.gpgsign
+                     for this run only (asked about when it is on)
+    --rewrite-tags   Also rewrite the tagger of annotated tags on the rewritten
+                     commits when it matches the old identity (tags keep their
+                     target; existing tag signatures are dropped)
+    --sign-tags      Re-sign the tags rewritten by --rewrite-tags with the new
+                     tagger's OpenPGP key (user.signingkey)
+    --global         Also write the new name/email to the global git config
+                     (otherwise you are asked when it differs)
+    --no-config-update
+                     Don't write the new name/email to any git config; the
+                     rewritten commits are committed by Git's configured identity
+    --commits-from <FILE>
+                     Only rewrite the commits listed in FILE (one SHA per line;
+                     use '-' to read the list from stdin)
+    --exclude <SHA>  Leave this commit as it is even when it is selected
+                     (repeatable)
+    --exclude-file <FILE>
+                     Leave the commits listed in FILE as they are (one SHA per
+                     line; use '-' to read the list from stdin)
+    --sync-committer Set each rewritten commit's committer to its (new) author;
+                     on its own, only committers change
+    --skip-merges    Never rewrite merge commits (the rebase engine flattens
+                     merges anyway; with --engine gix they are kept and only
+                     re-parented)
+    --all-branches   Rewrite every local branch in one pass, so commits they
+                     share get the same new SHAs on all of them; the branches
+                     move together once confirmed
+    --force-pushed-history
+                     Allow rewriting commits that already exist on a remote
+    --allow-protected
+                     Allow rewriting a protected branch: the remote's default
+                     branch (refs/remotes/origin/HEAD) or one matching an
+                     author-rewrite.protected pattern in git config
+    --force          Don't ask for the repository name to be typed before
+                     rewriting pushed commits or more than 100 commits (that
+                     confirmation is asked for even with --yes)
+    --repos-file <FILE>
+                     Run the same rewrite (without prompts) in every repository
+                     listed in FILE, one path per line ('-' reads stdin); failures
+                     don't stop the batch and a summary is printed at the end
+    --discover <DIR> Find every git repository under DIR, choose which ones to
+                     include (all of them with --yes), then rewrite them as a
+                     batch like --repos-file
+    --include-vendored
+                     Let --discover also search node_modules, vendor, target,
+                     build, dist and virtualenv directories
+    --mirror <URL>   Clone URL as a bare mirror into a temporary directory,
+                     rewrite every branch and tag there (without a working
+                     tree, so nothing can conflict), show which refs change and
+                     force-push them back after confirmation
+    -j, --jobs <N>   Rewrite up to N repositories of a batch at once; each
+                     output line is prefixed with its repository
+
+PROFILES:
+    profile add <PROFILE> <NAME> <EMAIL>
+                     Save an identity for use with --profile
+    profile list     List saved profiles
+    profile remove <PROFILE>
+                     Delete a saved profile
+
+    Profiles are stored in the global git config under author-rewrite.profile.*.
+
+VERIFY:
+    verify [--name <NAME>] [--email <EMAIL>] [--revs <EXPR>]
+                     List commits (default: all of HEAD) whose author or
+                     committer does not match NAME/EMAIL; exits with 4 if any
+
+CHECK:
+    check [--policy <FILE>] [--revs <EXPR> | --identity <IDENT>...]
+                     List commits (default: all of HEAD) whose author or
+                     committer breaks the policy in FILE (default:
+                     .author-policy at the top of the working tree); exits
+                     with 4 if any. Policy lines:
+                       allow-domain <DOMAIN>   emails must use a listed domain
+                                               ('*.corp.com' for subdomains)
+                       forbid <EMAIL>          this identity must not appear
+                                               ('Name <email>' also matches
+                                               the name)
+                       name-format <REGEX>     names must match REGEX
+                     With --identity, check the given 'Name <email>' values
+                     instead of history (repeatable).
+
+    install-hook [--hook pre-push|commit-msg] [--policy <FILE>] [--force]
+                     Write a hook running check: pre-push (default) blocks
+                     pushes of commits breaking the policy, commit-msg blocks
+                     commits whose author or committer breaks it. An existing
+                     hook of your own is only replaced with --force.
+
+SELFTEST:
+    selftest         Rewrite a scratch repository (a few commits by a fake
+                     identity) non-interactively and check the result, to
+                     confirm git, hooks and signing work here
+
+BENCH:
+    bench [--engine <NAME>]...
+                     Rewrite a temporary clone of the repository with each
+                     engine (default: all) and compare wall-clock time and
+                     peak memory; the repository itself is not changed
+
+RESTORE:
+    restore --from-bundle <PATH>
+                     Point every ref saved by --backup-bundle back at its
+                     original commit and reset the checked-out branch to it
+                     (needs a clean working tree; newer refs are kept)
+
+DESCRIPTION:
+    This tool prompts for a new author name and email, then rewrites all commits
+    in the repository to use the new author information. By default each
+    commit is written anew with its original tree and the branch is moved once,
+    so nothing can conflict; options that need to stop at each commit use an
+    interactive rebase instead.
+
+    In auto mode (default), all commits are automatically marked for editing.
+    In manual mode (--manual), you can choose which commits to edit.
+
+    The name and email you enter may be templates expanded per commit from
+    the original author: {{name}}, {{email}}, {{login}} (before the @) and {{domain}}.
+    For example, '{{login}}@newcorp.com' keeps everyone's individual address.
+
+    With --rewrite-domain, only commits whose author or committer email is at
+    the old domain are rewritten, and the git config is left untouched.
+    Likewise, --rules only rewrites the commits some rule matches.
+    With --revs or --commits-from, only the listed commits that are reachable
+    from HEAD are marked for editing. Commits named by --exclude or
+    --exclude-file, and merges with --skip-merges, are always left as 'pick'.
+
+    Commits that already exist on a remote are only rewritten when
+    --force-pushed-history is given, since collaborators will have to
+    re-clone or rebase afterwards. Such rewrites, and those of more than 100
+    commits, are confirmed by typing the repository name unless --force is given.
+
+    If a rebase is already in progress when the tool starts (left by a crashed
+    run or started by hand), it offers to resume it as an author rewrite, abort
+    it and start over, or exit. Without a terminal, or with --yes, pre-flight
+    fails instead.
+
+    Standard output only carries results: the author report after a rewrite,
+    the verify and check listings, profile list, the bench table and the batch
+    summary. The banner, checklists, progress, prompts, Git's own output,
+    warnings and errors go to standard error.
+
+BANNER:
+    The banner is configured in the git config: author-rewrite.banner.border
+    (double, single, rounded, heavy, ascii or none), author-rewrite.banner.layout
+    (full or minimal) and the borderColor, accentColor and warningColor keys of
+    the same section (a color name, 0-255 or none).
+
+ENVIRONMENT:
+    GAR_AUTHOR_NAME, GAR_AUTHOR_EMAIL, GAR_PROFILE
+                     Same as --name, --email and --profile
+    GAR_ASSUME_YES, GAR_GLOBAL, GAR_FORCE_PUSHED_HISTORY, GAR_FORCE,
+    GAR_NO_CONFIG_UPDATE
+                     Same as --yes, --global, --force-pushed-history, --force
+                     and --no-config-update when set to 1/true/yes/on
+    Command-line flags and --spec take precedence over these variables.
+    GIT_DIR, GIT_WORK_TREE
+                     Rewrite the repository they name, as git would (relative
+                     paths and core.worktree included); ignored by
+                     --repos-file and --discover
+    GIT_AUTHOR_*, GIT_COMMITTER_*, GIT_SEQUENCE_EDITOR, GIT_INDEX_FILE
+                     Not passed on to git, so an exported identity or editor
+                     cannot override the rewrite; GIT_SEQUENCE_EDITOR is still
+                     used for --manual
+
+EXIT CODES:
+    0    Success, or canceled without changes
+    1    An error occurred
+    3    Nothing to rewrite (no commits, or none matched the selection)
+    4    verify or check found commits that do not match the expected
+         identity or the author policy
+    5    The rewrite completed, but --fsck found problems
+    6    The rewrite completed, but --check-dco found commits not signed
+         off by their author",
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+/// Main CLI entry point for `git-author-rewrite`.
+///
+/// This function:
+/// 1. Handles special `--sequence-editor` invocation.
+/// 2. Parses CLI flags (see [`args::Options`]) and runs `profile` subcommands.
+/// 3. Verifies that `git` is installed and that the current directory is a git repository,
+///    and resolves the commits to rewrite (all of `HEAD`, `--revs`, or `--commits-from`).
+///    Commits that already exist on a remote are reported, and rewriting them
+///    requires `--force-pushed-history`.
+/// 4. Unless an identity mapping such as `--rewrite-domain` or a `--profile` was
+///    given, prompts for new author name and/or email (with defaults from
+///    `git config`; see `--name-only` / `--email-only`).
+/// 5. Exits early if neither name nor email has changed.
+/// 6. Updates local git config with new values (prompted identities only), and
+///    the global config too with `--global` or after confirmation.
+/// 7. Displays an informational banner and a preview of signed commits.
+/// 8. Rewrites the commit authors with the chosen engine (metadata by default,
+///    an interactive rebase when an option needs one).
+/// 9. Reports the author distribution before and after the rewrite, plus the
+///    result of `git fsck` with `--fsck`.
+/// 10. With `--cleanup`, expires reflogs and prunes the pre-rewrite objects.
+///
+/// Returns `Ok(exit_code)` on success, or the [`Error`] the run ended with.
+/// Nothing here prints that error: the caller reports it (the binary with
+/// [`Error::lines`]'s layout), with what the tool was doing, such as the
+/// commit being amended, and hints such as the fix for a known Git failure.
+///
+/// # Errors
+///
+/// Returns `Err(Error)` in the following cases:
+/// - The command line cannot be parsed.
+/// - `git` is not found in `PATH`.
+/// - The current directory is not a git repository.
+/// - Pushed commits would be rewritten without `--force-pushed-history`.
+/// - A protected branch would be rewritten without `--allow-protected`.
+/// - `--all-branches` is combined with an option it does not support, or
+///   the branches could not be moved.
+/// - Prompts fail.
+/// - Updating `git config` fails.
+/// - The rebase cannot be started or continued.
+///
+/// # Exit Codes
+///
+/// * `0` – Successful execution (including early exit when no changes detected).
+/// * [`EXIT_NOTHING_TO_DO`] – The repository has no commits, or none were selected.
+/// * Non-zero – Any failure along the way.
+pub fn entry() -> Result<i32, Error> {
+    let args: Vec<String> = env::args().collect();
+    let mut trail = None;
+    let result = run(&args, &mut trail);
+    let trail = match trail {
+        Some(t) => t,
+        None => return result,
+    };
+    match (trail.finish(&result), result) {
+        (Ok(_), result) => result,
+        (Err(e), Ok(_)) => {
+            Err(Error::new(e)
+                .hint("The run itself finished; record it by hand, or the log has a gap."))
+        }
+        (Err(e), Err(err)) => {
+            Err(err.hint(format!("The audit record was not written either: {}", e)))
+        }
+    }
+}
+
+/// Opens the audit log of `repo` for this run (see [`audit::Trail`]), before
+/// anything in the repository changes.
+fn start_audit(repo: &Repo, opts: &args::Options, args: &[String]) -> Result<audit::Trail, Error> {
+    let path = audit::log_path(repo, opts.audit_log.as_deref());
+    match audit::Trail::start(repo, &path, &args[1..]) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(Error::new(e).hint(format!(
+            "Nothing was changed. Point --audit-log (or {}) at a writable file.",
+            audit::PATH_KEY
+        ))),
+    }
+}
+
+/// Runs the tool with the command line `args`, leaving in `trail` the audit
+/// record of a run that got as far as a repository, for [`entry`] to finish.
+fn run(args: &[String], trail: &mut Option<audit::Trail>) -> Result<i32, Error> {
+    // Special case: act as `git sequence-editor` if invoked with that flag.
+    if args.len() >= 2 && args[1] == "--sequence-editor" {
+        match sequence_editor::run_args(&args[2..]) {
+            Ok(_) => {
+                return Ok(0);
+            }
+            Err(e) => {
+                return Err(Error::new(e).context("editing the rebase todo list"));
+            }
+        }
+    }
+
+    // Parse CLI flags.
+    let mut opts = match args::parse(&args[1..]) {
+        Ok(o) => o,
+        Err(e) => {
+            return Err(Error::new(e).hint("Run `git-author-rewrite --help` for usage."));
+        }
+    };
+
+    // A job spec fills in options not given as flags.
+    if let Some(source) = opts.spec.clone() {
+        let loaded = match spec::read(&source) {
+            Ok(sp) => spec::apply(sp, &mut opts),
+            Err(e) => Err(e),
+        };
+        match loaded {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        }
+        if source == "-" && opts.commits_from.as_deref() == Some("-") {
+            return Err(Error::new(
+                "--spec - and --commits-from - cannot both read stdin.",
+            ));
+        }
+        if source == "-" && opts.exclude_file.as_deref() == Some("-") {
+            return Err(Error::new(
+                "--spec - and --exclude-file - cannot both read stdin.",
+            ));
+        }
+    }
+
+    // GAR_* environment variables fill in options not given as flags (or spec).
+    match args::apply_env(&mut opts, |k| env::var(k).ok()) {
+        Ok(_) => {}
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    }
+
+    // Handle --help flag.
+    if opts.help {
+        print_help();
+        return Ok(0);
+    }
+
+    // Handle --version flag.
+    if opts.version {
+        println!("git-author-rewrite {}", env!("CARGO_PKG_VERSION"));
+        return Ok(0);
+    }
+
+    // --engine (or the spec's engine) picks the backend; --replace-mode is
+    // its shorthand, and the checks below go by it. Without either, options
+    // that need a rebase pick the rebase engine.
+    let rebase_only = rebase_only_flags(&opts);
+    let auto_engine = opts.engine.is_none() && !opts.replace_mode;
+    let mut engine_kind = match engine::resolve(
+        opts.engine.as_deref(),
+        opts.replace_mode,
+        !rebase_only.is_empty(),
+    ) {
+        Ok(k) => k,
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+    opts.replace_mode = engine_kind == EngineKind::Replace;
+    if engine_kind == EngineKind::Metadata && !rebase_only.is_empty() {
+        return Err(Error::new(format!(
+                "--engine metadata rewrites commit objects without a rebase; it cannot be combined with {}.",
+                rebase_only.join(", ")
+            )));
+    }
+
+    // Echo every git invocation from here on, and stop any that hang.
+    git::set_show_commands(opts.show_commands);
+    runner::set_timeout(opts.timeout.map(Duration::from_secs));
+
+    // Management subcommands do not rewrite anything.
+    if let Some(command) = &opts.command {
+        // Restoring from a bundle moves refs back, so it is recorded too.
+        if let (args::Command::Restore { .. }, Ok(repo)) = (command, Repo::discover()) {
+            *trail = Some(start_audit(&repo, &opts, args)?);
+        }
+        return run_command(command);
+    }
+
+    let manual_mode = opts.manual;
+    let selecting = opts.revs.is_some()
+        || opts.commits_from.is_some()
+        || !opts.exclude.is_empty()
+        || opts.exclude_file.is_some()
+        || opts.skip_merges;
+    if manual_mode && selecting {
+        return Err(Error::new(
+            "--revs, --commits-from, --exclude and --skip-merges cannot be combined with --manual.",
+        ));
+    }
+    if (opts.editor.is_some() || opts.show_authors) && !manual_mode {
+        return Err(Error::new(
+            "--editor and --show-authors only apply to --manual, where you edit the todo list.",
+        ));
+    }
+    if opts.revs.is_some() && opts.commits_from.is_some() {
+        return Err(Error::new("use either --revs or --commits-from, not both."));
+    }
+    if opts.commits_from.as_deref() == Some("-") && opts.exclude_file.as_deref() == Some("-") {
+        return Err(Error::new(
+            "--commits-from - and --exclude-file - cannot both read stdin.",
+        ));
+    }
+
+    if opts.replace_mode && (manual_mode || opts.backup_refs || opts.cleanup) {
+        return Err(Error::new(
+            "--replace-mode cannot be combined with --manual, --backup-refs or --cleanup.",
+        ));
+    }
+    if opts.check_dco && (opts.replace_mode || opts.mirror.is_some()) {
+        return Err(Error::new("--check-dco checks the commits a rewrite creates; it cannot be combined with --replace-mode or --mirror."));
+    }
+    if !opts.drop_author.is_empty() && (opts.replace_mode || opts.mirror.is_some()) {
+        return Err(Error::new("--drop-author removes commits with a rebase; it cannot be combined with --replace-mode or --mirror."));
+    }
+    if opts.skip_on_conflict
+        && (opts.replace_mode || opts.mirror.is_some() || engine_kind == EngineKind::Gix)
+    {
+        return Err(Error::new("--skip-on-conflict answers conflicts during a rebase; it cannot be combined with --replace-mode, --mirror or --engine gix."));
+    }
+    if (opts.chunk_size.is_some() || opts.resume)
+        && (manual_mode
+            || opts.replace_mode
+            || opts.mirror.is_some()
+            || opts.all_branches
+            || engine_kind == EngineKind::Gix)
+    {
+        return Err(Error::new("--chunk-size and --resume split a rebase into chunks; they cannot be combined with --manual, --replace-mode, --mirror, --all-branches or --engine gix."));
+    }
+    if opts.resume && (opts.repos_file.is_some() || opts.discover.is_some()) {
+        return Err(Error::new(
+            "--resume continues a chunked rewrite of this repository; it cannot be combined with --repos-file or --discover.",
+        ));
+    }
+    if !opts.exec.is_empty() && (manual_mode || opts.replace_mode || opts.mirror.is_some()) {
+        return Err(Error::new("--exec runs commands during the automatic rebase; it cannot be combined with --manual, --replace-mode or --mirror."));
+    }
+    if engine_kind == EngineKind::Gix
+        && (manual_mode
+            || opts.ask_each
+            || opts.tui
+            || !opts.exec.is_empty()
+            || !opts.drop_author.is_empty()
+            || opts.mirror.is_some())
+    {
+        return Err(Error::new("--engine gix rewrites commit objects without a rebase; it cannot be combined with --manual, --ask-each, --tui, --exec, --drop-author or --mirror."));
+    }
+    let unattended = opts.yes || opts.repos_file.is_some() || opts.discover.is_some();
+    if opts.ask_each && (unattended || opts.replace_mode || opts.mirror.is_some()) {
+        return Err(Error::new("--ask-each prompts at every commit of a rebase; it cannot be combined with --yes, --replace-mode, --mirror, --repos-file or --discover."));
+    }
+    if opts.tui
+        && (opts.ask_each
+            || opts.show_commands
+            || opts.quiet
+            || opts.replace_mode
+            || opts.mirror.is_some())
+    {
+        return Err(Error::new("--tui shows the rebase loop; it cannot be combined with --ask-each, --show-commands, --quiet, --replace-mode or --mirror."));
+    }
+    if opts.all_branches
+        && (manual_mode
+            || opts.revs.is_some()
+            || opts.commits_from.is_some()
+            || !opts.exclude.is_empty()
+            || opts.exclude_file.is_some()
+            || opts.skip_merges
+            || opts.replace_mode
+            || engine_kind == EngineKind::Gix
+            || opts.mirror.is_some()
+            || opts.ask_each
+            || opts.tui
+            || !opts.exec.is_empty()
+            || !opts.drop_author.is_empty()
+            || opts.skip_on_conflict
+            || opts.check_dco
+            || opts.backup_refs
+            || opts.pick_branch)
+    {
+        return Err(Error::new("--all-branches rewrites the history of every local branch at once; it cannot be combined with --manual, --revs, --commits-from, --exclude, --skip-merges, --replace-mode, --engine gix, --mirror, --ask-each, --tui, --exec, --drop-author, --skip-on-conflict, --check-dco, --backup-refs or --pick-branch."));
+    }
+    if opts.report_html.is_some()
+        && (opts.all_branches
+            || opts.mirror.is_some()
+            || opts.repos_file.is_some()
+            || opts.discover.is_some())
+    {
+        return Err(Error::new("--report-html reports on the rewrite of one branch; it cannot be combined with --all-branches, --mirror, --repos-file or --discover."));
+    }
+    if opts.sign_tags && !opts.rewrite_tags {
+        return Err(Error::new(
+            "--sign-tags only applies together with --rewrite-tags.",
+        ));
+    }
+    if opts.backup_refs && opts.cleanup {
+        return Err(Error::new("--backup-refs keeps the original commits, which --cleanup would delete; use one or the other."));
+    }
+
+    let fields = match (opts.name_only, opts.email_only) {
+        (true, true) => {
+            return Err(Error::new(
+                "use either --name-only or --email-only, not both.",
+            ));
+        }
+        (true, false) => Fields::NameOnly,
+        (false, true) => Fields::EmailOnly,
+        (false, false) => Fields::Both,
+    };
+    if fields != Fields::Both && opts.rewrite_domain.is_some() {
+        return Err(Error::new(
+            "--rewrite-domain cannot be combined with --name-only or --email-only.",
+        ));
+    }
+
+    let given_values = opts.name.is_some() || opts.email.is_some();
+    if given_values && (opts.profile.is_some() || opts.rewrite_domain.is_some()) {
+        return Err(Error::new(
+            "--name/--email cannot be combined with --profile or --rewrite-domain.",
+        ));
+    }
+    if (opts.name.is_some() && fields == Fields::EmailOnly)
+        || (opts.email.is_some() && fields == Fields::NameOnly)
+    {
+        return Err(Error::new(
+            "--name cannot be combined with --email-only, nor --email with --name-only.",
+        ));
+    }
+    if opts.profile.is_some() && opts.rewrite_domain.is_some() {
+        return Err(Error::new(
+            "use either --profile or --rewrite-domain, not both.",
+        ));
+    }
+    let other_identity = opts.name.is_some()
+        || opts.email.is_some()
+        || opts.profile.is_some()
+        || opts.rewrite_domain.is_some()
+        || fields != Fields::Both;
+    if opts.rules.is_some() && other_identity {
+        return Err(Error::new("--rules cannot be combined with --name, --email, --name-only, --email-only, --profile or --rewrite-domain."));
+    }
+    if opts.sha_map.is_some() && (other_identity || opts.rules.is_some()) {
+        return Err(Error::new("--sha-map cannot be combined with --name, --email, --name-only, --email-only, --profile, --rewrite-domain or --rules."));
+    }
+    if opts.verify && opts.no_verify {
+        return Err(Error::new("use either --verify or --no-verify, not both."));
+    }
+    if opts.gpg_sign && opts.no_gpg_sign {
+        return Err(Error::new(
+            "use either --gpg-sign or --no-gpg-sign, not both.",
+        ));
+    }
+    if opts.global && opts.no_config_update {
+        return Err(Error::new(
+            "use either --global or --no-config-update, not both.",
+        ));
+    }
+    if opts.include_if.is_some() && (opts.global || opts.no_config_update) {
+        return Err(Error::new("--include-if saves the identity for a directory; it cannot be combined with --global or --no-config-update."));
+    }
+
+    // Reject malformed substitutions before touching any repository.
+    let message_edits = match message::parse_all(&opts.message_replace) {
+        Ok(rewrites) => MessageEdits {
+            rewrites,
+            annotate: opts.annotate,
+            record_original_sha: opts.record_original_sha,
+        },
+        Err(e) => {
+            return Err(Error::new(e));
+        }
+    };
+
+    // A batch runs this same rewrite inside each listed or discovered repository.
+    if opts.repos_file.is_some() || opts.discover.is_some() {
+        if opts.repos_file.is_some() && opts.discover.is_some() {
+            return Err(Error::new(
+                "use either --repos-file or --discover, not both.",
+            ));
+        }
+        if opts.global && opts.jobs.unwrap_or(1) > 1 {
+            return Err(Error::new("--global cannot be combined with --jobs, since parallel runs would race to write the global config."));
+        }
+        if manual_mode
+            || opts.commits_from.is_some()
+            || !opts.exclude.is_empty()
+            || opts.exclude_file.is_some()
+            || opts.drop_backup_refs
+            || opts.backup_bundle.is_some()
+            || opts.include_if.is_some()
+        {
+            return Err(Error::new("--repos-file and --discover cannot be combined with --manual, --commits-from, --exclude, --drop-backup-refs, --backup-bundle or --include-if."));
+        }
+        if opts.repos_file.as_deref() == Some("-") && opts.spec.as_deref() == Some("-") {
+            return Err(Error::new(
+                "--spec - and --repos-file - cannot both read stdin.",
+            ));
+        }
+        return run_batch(&opts, fields);
+    }
 
-        match git::amend_author(author) {
-            Ok(_) => {
-                println!("{}", style("Amended current commit author.").green());
-            }
-            Err(e) => {
-                eprintln!(
-                    "{}",
-                    style(format!("❌ Failed to amend commit: {}", e))
-                        .red()
-                        .bold()
-                );
-                return Err(());
+    if opts.jobs.is_some() {
+        return Err(Error::new(
+            "--jobs only applies to --repos-file and --discover.",
+        ));
+    }
+
+    // Fail up front, rather than with an unknown-option error mid-rewrite, if
+    // this git is too old for the run. A missing git is reported by preflight.
+    if let Ok(version) = git_version::detect() {
+        let problems = git_version::unsupported(version, &git_version::required_features(&opts));
+        if let Some((first, rest)) = problems.split_first() {
+            let mut err = Error::new(first.clone());
+            for problem in rest {
+                err = err.hint(format!("Also: {}", problem));
             }
+            return Err(err);
         }
+    }
 
-        match git::rebase_continue() {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!(
-                    "{}",
-                    style(format!("❌ `git rebase --continue` failed: {}", e))
-                        .red()
-                        .bold()
-                );
-                return Err(());
+    // A mirror is rewritten in a temporary clone, away from this repository.
+    if let Some(url) = &opts.mirror {
+        let selecting = opts.revs.is_some()
+            || opts.commits_from.is_some()
+            || !opts.exclude.is_empty()
+            || opts.exclude_file.is_some()
+            || opts.skip_merges;
+        if manual_mode
+            || selecting
+            || opts.replace_mode
+            || opts.backup_refs
+            || opts.cleanup
+            || opts.drop_backup_refs
+            || opts.backup_bundle.is_some()
+        {
+            return Err(Error::new("--mirror rewrites every branch and tag; it cannot be combined with --manual, --revs, --commits-from, --exclude, --skip-merges, --replace-mode, --backup-refs, --backup-bundle, --cleanup or --drop-backup-refs."));
+        }
+        return run_mirror(url, &opts, fields, &message_edits);
+    }
+
+    // Prompts need a terminal; without one, fail at once with the flags to
+    // use instead of after the checks, or at the first prompt. Dropping
+    // backups and resuming a chunked rewrite ask nothing.
+    if !io::stdin().is_terminal() && !opts.drop_backup_refs && !opts.resume {
+        let missing = non_interactive_requirements(&opts, fields);
+        if !missing.is_empty() {
+            let mut err =
+                Error::new("stdin is not a terminal, so this run cannot prompt for input.")
+                    .hint("Provide the answers up front with:");
+            for flag in missing {
+                err = err.hint(format!("    {}", flag));
             }
+            return Err(err);
         }
     }
-    Ok(())
-}
 
-/// Determines whether the provided name and email are unchanged from the defaults.
-///
-/// Both values are compared after trimming leading and trailing whitespace.  
-/// Returns `true` only if **both** the `name` and `email` match their corresponding
-/// defaults exactly after trimming; otherwise returns `false`.
-///
-/// # Parameters
-///
-/// * `name` – The proposed author name.
-/// * `email` – The proposed author email.
-/// * `default_name` – The current default author name (e.g., from `git config`).
-/// * `default_email` – The current default author email.
-///
-/// # Returns
-///
-/// * `true` if both name and email match the defaults (ignoring surrounding whitespace).  
-/// * `false` otherwise.
-///
-/// # Examples
-///
-/// ```ignore
-/// // Example (function is crate-private):
-/// // use git_author_rewrite::cli::should_exit_no_change;
-/// // assert!(should_exit_no_change("Alice ", "alice@example.com ", "Alice", "alice@example.com"));
-/// ```
-pub(crate) fn should_exit_no_change(
-    name: &str,
-    email: &str,
-    default_name: &str,
-    default_email: &str,
-) -> bool {
-    name.trim() == default_name.trim() && email.trim() == default_email.trim()
-}
+    // One run per repository: a second rebase started on top of a running
+    // one corrupts both. Outside a repository, preflight says so below.
+    let discovered = Repo::discover().ok();
+    let _lock = match &discovered {
+        Some(r) => match lock::acquire(r.git_dir()) {
+            Ok(l) => Some(l),
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        },
+        None => None,
+    };
 
-/// Prints usage information to stdout.
-fn print_help() {
-    println!(
-        "\
-git-author-rewrite {}
+    // Every run that gets this far is recorded, however it ends.
+    if let Some(r) = &discovered {
+        *trail = Some(start_audit(r, &opts, args)?);
+    }
 
-Rewrite commit authors across an entire Git repository.
+    // Replace mode, --all-branches and dropping backups leave the working
+    // tree alone; everything else starts a rebase, which Git refuses while
+    // another one is in progress.
+    let rebasing = !opts.replace_mode && !opts.drop_backup_refs && !opts.all_branches;
 
-USAGE:
-    git-author-rewrite [OPTIONS]
+    // A chunked rewrite stopped halfway is continued, never started over.
+    let checkpoint = match &discovered {
+        Some(r) if rebasing => match chunks::load(r.git_dir()) {
+            Ok(c) => c,
+            Err(e) => {
+                return Err(Error::new(e));
+            }
+        },
+        _ => None,
+    };
+    match (checkpoint, opts.resume) {
+        (Some(c), true) => {
+            if let Some(t) = trail.as_mut() {
+                t.set_engine(EngineKind::Rebase.name());
+            }
+            return resume_chunks(c, &opts, fields, &message_edits);
+        }
+        (Some(c), false) => {
+            let short = c.branch.trim_start_matches("refs/heads/").to_string();
+            return Err(Error::new(format!(
+                "a chunked rewrite of {} stopped after {} of {} chunks.",
+                short,
+                c.done,
+                c.ends.len()
+            ))
+            .hint("Continue it by re-running the same command with --resume, or drop it with")
+            .hint(format!(
+                "`git rebase --abort; git checkout {} && rm .git/{}`.",
+                short,
+                chunks::CHECKPOINT_FILE
+            )));
+        }
+        (None, true) => {
+            return Err(Error::new(
+                "--resume found no unfinished chunked rewrite in this repository.",
+            ));
+        }
+        (None, false) => {}
+    }
+    let leftover = match &discovered {
+        Some(r) if rebasing => ask_leftover_rebase(r, &opts)?,
+        _ => None,
+    };
+    match leftover {
+        Some(LeftoverRebase::Exit) => return Ok(0),
+        Some(LeftoverRebase::Abort) | None => {}
+        Some(LeftoverRebase::Resume) => {
+            // The tree is mid-rebase, so only the basic checks apply.
+            let report = preflight::run(false);
+            let passed = report.passed();
+            if !opts.quiet || !passed {
+                print_preflight(&report);
+            }
+            if let Some(t) = trail.as_mut() {
+                t.set_engine(EngineKind::Rebase.name());
+            }
+            return match report.repo {
+                Some(r) if passed => {
+                    resume_rebase(&skip_lfs_smudge(r, &opts), &opts, fields, &message_edits)
+                }
+                _ => Err(Error::new("pre-flight checks failed")),
+            };
+        }
+    }
 
-OPTIONS:
-    -h, --help       Print help information
-    -V, --version    Print version information
-    --manual         Edit the rebase todo list manually instead of auto-marking all commits
+    // With --pick-branch, the chosen branch is checked out before the checks
+    // look at it.
+    if let (Some(r), true) = (&discovered, opts.pick_branch && !opts.drop_backup_refs) {
+        pick_branch(r, &opts)?;
+    }
 
-DESCRIPTION:
-    This tool prompts for a new author name and email, then rewrites all commits
-    in the repository to use the new author information via interactive rebase.
+    // Check the repository is in a state the rewrite can work with.
+    let report = preflight::run(rebasing);
+    let passed = report.passed();
+    if !opts.quiet || !passed {
+        print_preflight(&report);
+    }
+    let repo = match report.repo {
+        Some(r) if passed => r,
+        _ => {
+            return Err(Error::new(
+                "pre-flight checks failed; fix the problems above and re-run.",
+            ));
+        }
+    };
 
-    In auto mode (default), all commits are automatically marked for editing.
-    In manual mode (--manual), you can choose which commits to edit.",
-        env!("CARGO_PKG_VERSION")
-    );
-}
+    // Dropping old backups is a task of its own.
+    if opts.drop_backup_refs {
+        return match backup::drop_all(&repo) {
+            Ok(dropped) => {
+                if dropped.is_empty() {
+                    eprintln!("No backups under {}.", backup::BACKUP_PREFIX);
+                }
+                for name in dropped {
+                    eprintln!("Deleted {}", name);
+                }
+                Ok(0)
+            }
+            Err(e) => Err(Error::new(e)),
+        };
+    }
 
-/// Main CLI entry point for `git-author-rewrite`.
-///
-/// This function:
-/// 1. Handles special `--sequence-editor` invocation.
-/// 2. Parses CLI flags (currently only `--manual`).
-/// 3. Verifies that `git` is installed and that the current directory is a git repository.
-/// 4. Prompts for new author name and email (with defaults from `git config`).
-/// 5. Exits early if neither name nor email has changed.
-/// 6. Updates local git config with new values.
-/// 7. Displays an informational banner.
-/// 8. Optionally starts an interactive rebase to rewrite commit authors.
-///
-/// Returns `Ok(exit_code)` on success, or `Err(())` on error.
-///
-/// # Errors
-///
-/// Returns `Err(())` in the following cases:
-/// - `git` is not found in `PATH`.
-/// - The current directory is not a git repository.
-/// - Prompts fail.
-/// - Updating `git config` fails.
-/// - The rebase cannot be started or continued.
-///
-/// # Exit Codes
-///
-/// * `0` – Successful execution (including early exit when no changes detected).
-/// * Non-zero – Any failure along the way.
-pub fn entry() -> Result<i32, ()> {
-    // Parse command-line arguments.
-    let args: Vec<String> = env::args().collect();
+    // A freshly initialized repository has no history to rewrite.
+    if !repo.head_exists() {
+        eprintln!(
+            "{}",
+            style("Nothing to rewrite: this repository has no commits yet.")
+                .yellow()
+                .bold()
+        );
+        return Ok(EXIT_NOTHING_TO_DO);
+    }
 
-    // Handle --help flag.
-    if args.iter().any(|a| a == "--help" || a == "-h") {
-        print_help();
-        return Ok(0);
+    // Find out about a stale backup before asking anything.
+    if opts.backup_refs {
+        if let Err(e) = backup::check_available(&repo) {
+            return Err(Error::new(e));
+        }
+    }
+    if let Some(path) = &opts.backup_bundle {
+        if let Err(e) = backup::check_bundle_available(Path::new(path)) {
+            return Err(Error::new(e));
+        }
+    }
+    if let Some(path) = &opts.report_html {
+        if let Err(e) = html_report::check_path(Path::new(path)) {
+            return Err(Error::new(e));
+        }
     }
 
-    // Handle --version flag.
-    if args.iter().any(|a| a == "--version" || a == "-V") {
-        println!("git-author-rewrite {}", env!("CARGO_PKG_VERSION"));
-        return Ok(0);
+    // Every branch is rewritten at once, without a rebase.
+    if opts.all_branches {
+        return run_all_branches(&repo, &opts, fields, &message_edits);
     }
 
-    // Special case: act as `git sequence-editor` if invoked with that flag.
-    if args.len() >= 2 && args[1] == "--sequence-editor" {
-        let path = args.get(2).map(|s| s.as_str());
-        match sequence_editor::run(path) {
-            Ok(_) => {
-                return Ok(0);
-            }
+    // A saved profile replaces the identity prompts.
+    let profile_identity = match opts.profile.as_deref() {
+        Some(name) => match profile::load(name) {
+            Ok(p) => Some(p.identity),
             Err(e) => {
-                eprintln!(
-                    "{}",
-                    style(format!("Sequence editor error: {}", e)).red().bold()
-                );
-                return Err(());
+                return Err(Error::new(e));
             }
+        },
+        None => None,
+    };
+
+    // Identity mappings given on the command line replace the prompts.
+    let flag_mapping = match flag_mapping(&opts) {
+        Ok(m) => m,
+        Err(e) => {
+            return Err(Error::new(e));
         }
-    }
+    };
 
-    // Parse CLI flags.
-    let manual_mode = args.iter().any(|a| a == "--manual");
+    // Work out which commits will be rewritten.
+    let selection = match resolve_selection(&repo, &opts)? {
+        Some(sel) => sel,
+        None => return Ok(EXIT_NOTHING_TO_DO),
+    };
+
+    // Skip commits the mapping would leave untouched. Message substitutions
+    // may apply to any commit, --drop-author needs to see the commits it
+    // drops and --ask-each lets the user pick for any commit, so they keep
+    // the whole selection.
+    let keep_all =
+        !message_edits.rewrites.is_empty() || !opts.drop_author.is_empty() || opts.ask_each;
+    let restrict = match (&flag_mapping, keep_all) {
+        (Some(m), false) => Some(m),
+        _ => None,
+    };
+
+    // One pass over the history narrows the selection and feeds the
+    // pushed-commit check, the signature preview and the final report.
+    let scanned = scan::scan(
+        &repo,
+        ScanOptions {
+            selection: &selection,
+            restrict,
+            remotes: engine_kind.rewrites_history(),
+            signatures: !opts.quiet,
+        },
+    );
+    let selection = match (&scanned, restrict) {
+        (Ok(found), _) => found.selection.clone(),
+        (Err(e), Some(_)) => {
+            return Err(Error::new(e.clone()));
+        }
+        (Err(_), None) => selection,
+    };
+    if restrict.is_some() && selection == Selection::Commits(Vec::new()) {
+        eprintln!(
+            "{}",
+            style("Nothing to rewrite: no commits match the identity mapping.")
+                .yellow()
+                .bold()
+        );
+        return Ok(EXIT_NOTHING_TO_DO);
+    }
 
-    // Verify environment and get repository paths.
-    let paths = verify_environment()?;
+    // Refuse to silently rewrite history that collaborators already have.
+    // Replace mode leaves history alone, so there is nothing to refuse.
+    let pushed = if engine_kind.rewrites_history() {
+        if let Some(branch) = repo.symbolic_head() {
+            check_protected_branch(&repo, &branch, opts.allow_protected, opts.quiet)?;
+        }
+        let summary = match &scanned {
+            Ok(found) => Ok(PushedCommits {
+                total: found.total,
+                pushed: found.pushed.unwrap_or(0),
+                upstream: repo.upstream().ok(),
+            }),
+            Err(e) => Err(e.clone()),
+        };
+        report_pushed_commits(summary, opts.force_pushed_history)?
+    } else {
+        None
+    };
 
     // Get repository name for prompts.
-    let repo_name = paths
-        .root
+    let repo_name = repo
+        .root()
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or("current repository")
         .to_string();
 
-    // Prompt for author input.
-    let (name, email) = match get_author_input(&repo_name)? {
-        AuthorInput::Changed(n, e) => (n, e),
-        AuthorInput::NoChange => {
-            eprintln!(
-                "{}",
-                style("No changes detected for name or email; exiting without modifying history.")
-                    .yellow()
-                    .bold()
-            );
-            return Ok(0);
-        }
+    let mapping = match flag_mapping {
+        Some(m) => m,
+        None => match identity_mapping(&repo, &repo_name, &opts, fields, profile_identity)? {
+            Some(m) => m,
+            None => return Ok(0),
+        },
     };
 
-    // Update local git config.
-    update_git_config(&name, &email)?;
+    if opts.check_github {
+        check_github_email(&mapping, &opts)?;
+    }
+
+    if let Some(dir) = &opts.include_if {
+        save_scoped_identity(dir, &mapping)?;
+    }
 
-    // Show banner with instructions.
-    print_banner(&name, &email, manual_mode);
+    ensure_committer_identity(&repo, &repo_name, &opts)?;
 
-    // Confirm before starting rebase.
-    let mut confirm_prompter = prompt::DialoguerConfirmPrompter;
-    match prompt::confirm_start(&mut confirm_prompter) {
-        Ok(true) => {
-            // Start interactive rebase (auto-mark commits unless manual mode).
-            let auto_mark_all = !manual_mode;
-            match git::rebase_interactive(auto_mark_all) {
-                Ok(_) => {}
-                Err(e) => {
+    // The default engine cannot run hooks or sign commits; leave those
+    // repositories to a rebase.
+    if auto_engine && engine_kind == EngineKind::Metadata {
+        match metadata_fallback(&repo, &opts) {
+            Ok(Some(reason)) => {
+                if !opts.quiet {
                     eprintln!(
                         "{}",
-                        style(format!("❌ Rebase failed to start: {}", e))
-                            .red()
-                            .bold()
+                        style(format!("Using the rebase engine: {}.", reason)).dim()
                     );
-                    return Err(());
                 }
+                engine_kind = EngineKind::Rebase;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return Err(Error::new(e));
             }
+        }
+    }
+
+    let repo = if engine_kind == EngineKind::Rebase {
+        choose_signing_key(skip_lfs_smudge(repo, &opts), &mapping, &opts)?
+    } else {
+        repo
+    };
+
+    if let Some(t) = trail.as_mut() {
+        t.set_engine(engine_kind.name());
+    }
+    let mut engine: Box<dyn Engine + '_> = match engine_kind {
+        EngineKind::Metadata => Box::new(MetadataEngine {
+            opts: &opts,
+            repo: &repo,
+        }),
+        EngineKind::Rebase => Box::new(RebaseEngine::new(&opts, &repo)),
+        EngineKind::Replace => Box::new(ReplaceEngine {
+            opts: &opts,
+            repo: &repo,
+        }),
+        EngineKind::Gix => Box::new(GixEngine {
+            opts: &opts,
+            repo: &repo,
+        }),
+    };
+
+    if !opts.quiet {
+        // Show banner with instructions.
+        engine.print_banner(&mapping);
+
+        // Point out signatures the rewrite will invalidate.
+        print_preview(&scanned);
+    }
+
+    if let Err(e) = engine.prepare() {
+        return Err(Error::new(e));
+    }
 
-            // Run the rebase loop.
-            let author = format!("{} <{}>", name, email);
-            run_rebase_loop(&paths.git_dir, &author)?;
+    // Confirm before starting, unless --yes or the config already did. Risky
+    // rewrites need the repository name typed, which only --force skips.
+    let settings = match ConfirmSettings::read(&repo) {
+        Ok(s) => s,
+        Err(e) => return Err(Error::new(e)),
+    };
+    let typed_reason = match (&pushed, opts.force) {
+        (Some(summary), false) => {
+            typed_confirmation_reason(summary, settings.large_rewrite_threshold)
         }
+        _ => None,
+    };
+    let confirmed = match typed_reason {
+        Some(reason) => confirm_by_repo_name(&repo_name, &reason),
+        None => match settings.start_answer(opts.yes) {
+            Ok(Some(answer)) => Ok(answer),
+            Ok(None) => engine.confirm(),
+            Err(e) => return Err(Error::new(e)),
+        },
+    };
+    match confirmed {
+        Ok(true) => {}
         Ok(false) => {
-            println!(
+            eprintln!(
                 "{}",
                 style("Canceled by user. No changes made.").yellow().bold()
             );
             return Ok(0);
         }
         Err(e) => {
-            eprintln!("{}", style(format!("Prompt error: {}", e)).red().bold());
-            return Err(());
+            return Err(Error::new(format!("prompt failed: {}", e)));
         }
     }
 
-    Ok(0)
+    if let Some(path) = &opts.backup_bundle {
+        if let Err(e) = backup::create_bundle(&repo, Path::new(path)) {
+            return Err(Error::new(e));
+        }
+        if !opts.quiet {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Every ref saved to {} (undo with `git-author-rewrite restore --from-bundle {}`).",
+                    path, path
+                ))
+                .dim()
+            );
+        }
+    }
+
+    // Remember who authored what, for the final report, and where the
+    // branch was, so --check-dco can tell the new commits apart.
+    let before = match scanned {
+        Ok(found) => Ok(found.authors),
+        Err(e) => Err(e),
+    };
+    let old_head = if opts.check_dco || opts.report_html.is_some() {
+        repo.rev_parse("HEAD").ok()
+    } else {
+        None
+    };
+    let tag_candidates = tags_to_rewrite(&repo, &selection, &opts)?;
+
+    let job = Job {
+        selection: &selection,
+        mapping: &mapping,
+        edits: &message_edits,
+    };
+    if let Err(e) = engine.rewrite(&job) {
+        return Err(e.context(format!(
+            "rewriting with the {} engine",
+            engine.kind().name()
+        )));
+    }
+
+    rewrite_tag_identities(&repo, &tag_candidates, &mapping, &opts)?;
+
+    final_report(
+        &repo,
+        before,
+        old_head.as_deref(),
+        engine.skipped(),
+        &mapping,
+        &opts,
+    )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::should_exit_no_change;
+    use super::{
+        amend_current_commit, branch_label, build_mapping, config_values, global_changes,
+        identity_choices, missing_identity_keys, non_interactive_requirements,
+        parse_large_rewrite_threshold, parse_start_confirmation, pushed_warning,
+        should_exit_no_change, skip_conflicts, typed_confirmation_reason, warning_lines, Fields,
+        PushedCommits, StartConfirmation,
+    };
+    use crate::args::Options;
+    use crate::git::{CommitOptions, Repo};
+    use crate::identity::{Identity, Mapping};
+    use crate::profile::Profile;
+    use crate::test_util::ScriptedRunner;
+    use std::{fs, rc::Rc};
+
+    #[test]
+    fn warning_lines_keep_hints_and_warnings_only() {
+        let stderr = "Rebasing (2/3)\nhint: Waiting for your editor\n\
+                      warning: in the working copy of 'a.txt', LF will be replaced by CRLF\n\
+                      Successfully rebased and updated refs/heads/main.";
+        assert_eq!(
+            warning_lines(stderr),
+            vec![
+                "  hint: Waiting for your editor",
+                "  warning: in the working copy of 'a.txt', LF will be replaced by CRLF",
+            ]
+        );
+        assert!(warning_lines("").is_empty());
+    }
+
+    #[test]
+    fn branch_label_shows_the_tracking_state() {
+        let branch = |name: &str, upstream: Option<&str>, ahead, behind, gone| crate::git::Branch {
+            name: name.to_string(),
+            current: name == "main",
+            upstream: upstream.map(String::from),
+            ahead,
+            behind,
+            upstream_gone: gone,
+        };
+        assert_eq!(
+            branch_label(&branch("main", Some("origin/main"), 2, 1, false)),
+            "main (current)  ·  2 ahead, 1 behind origin/main"
+        );
+        assert_eq!(
+            branch_label(&branch("wip", Some("origin/wip"), 0, 0, false)),
+            "wip  ·  up to date with origin/wip"
+        );
+        assert_eq!(
+            branch_label(&branch("old", Some("origin/old"), 0, 0, true)),
+            "old  ·  origin/old is gone"
+        );
+        assert_eq!(
+            branch_label(&branch("topic", None, 0, 0, false)),
+            "topic  ·  no upstream"
+        );
+    }
+
+    #[test]
+    fn identity_choices_put_the_mapping_first_without_duplicates() {
+        let ann = Identity::new("Ann", "ann@x");
+        let profiles = vec![
+            Profile {
+                name: String::from("ann"),
+                identity: ann.clone(),
+            },
+            Profile {
+                name: String::from("work"),
+                identity: Identity::new("Bo", "bo@x"),
+            },
+        ];
+        let choices = identity_choices(Some(&ann), &profiles);
+        let labels: Vec<&str> = choices.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["Ann <ann@x> (mapping)", "Bo <bo@x> (profile work)"]
+        );
+        assert_eq!(identity_choices(None, &profiles).len(), 2);
+    }
 
     #[test]
     fn unchanged_both_returns_true() {
         let r = should_exit_no_change("A ", "b@example.com ", "A", "b@example.com");
-        assert_eq!(r, true);
+        assert!(r);
     }
 
     #[test]
     fn changed_name_only_returns_false() {
         let r = should_exit_no_change("New", "b@example.com", "Old", "b@example.com");
-        assert_eq!(r, false);
+        assert!(!r);
     }
 
     #[test]
     fn changed_email_only_returns_false() {
         let r = should_exit_no_change("A", "new@example.com", "A", "old@example.com");
-        assert_eq!(r, false);
+        assert!(!r);
     }
 
     #[test]
     fn both_changed_returns_false() {
         let r = should_exit_no_change("X", "y@z", "A", "b@c");
-        assert_eq!(r, false);
+        assert!(!r);
+    }
+
+    #[test]
+    fn pushed_warning_mentions_counts_and_upstream() {
+        let lines = pushed_warning(3, 10, Some("origin/main"));
+        assert!(lines[0].contains("3 of 10 commits"));
+        assert!(lines[0].contains("upstream: origin/main"));
+    }
+
+    #[test]
+    fn pushed_warning_without_upstream() {
+        let lines = pushed_warning(1, 1, None);
+        assert!(lines[0].ends_with("already exist on remotes."));
+        assert!(lines.iter().any(|l| l.contains("re-clone")));
+    }
+
+    #[test]
+    fn typed_confirmation_for_pushed_or_many_commits() {
+        let summary = |total, pushed| PushedCommits {
+            total,
+            pushed,
+            upstream: None,
+        };
+        assert_eq!(typed_confirmation_reason(&summary(100, 0), 100), None);
+        assert_eq!(
+            typed_confirmation_reason(&summary(101, 0), 100).as_deref(),
+            Some("This rewrites 101 commits.")
+        );
+        assert_eq!(typed_confirmation_reason(&summary(101, 0), 500), None);
+        assert_eq!(
+            typed_confirmation_reason(&summary(3, 2), 500).as_deref(),
+            Some("This rewrites 2 commits that already exist on a remote.")
+        );
+    }
+
+    #[test]
+    fn confirmation_settings_parse() {
+        assert_eq!(parse_start_confirmation(""), Ok(StartConfirmation::Default));
+        assert_eq!(
+            parse_start_confirmation("always-ask"),
+            Ok(StartConfirmation::AlwaysAsk)
+        );
+        assert_eq!(
+            parse_start_confirmation(" assume-yes\n"),
+            Ok(StartConfirmation::AssumeYes)
+        );
+        assert!(parse_start_confirmation("sometimes")
+            .unwrap_err()
+            .contains("expected always-ask or assume-yes"));
+        assert_eq!(parse_large_rewrite_threshold(""), Ok(100));
+        assert_eq!(parse_large_rewrite_threshold("500"), Ok(500));
+        assert!(parse_large_rewrite_threshold("lots").is_err());
+    }
+
+    #[test]
+    fn build_mapping_picks_fixed_or_partial() {
+        let m = build_mapping(String::from("A"), String::from("a@x"), Fields::Both).unwrap();
+        assert_eq!(m, Mapping::Fixed(Identity::new("A", "a@x")));
+        let m = build_mapping(String::from("A"), String::from("a@x"), Fields::EmailOnly).unwrap();
+        assert_eq!(m, Mapping::EmailOnly(String::from("a@x")));
+    }
+
+    #[test]
+    fn build_mapping_detects_templates() {
+        let m = build_mapping(
+            String::from("Old"),
+            String::from("{login}@new.com"),
+            Fields::EmailOnly,
+        )
+        .unwrap();
+        assert_eq!(
+            m,
+            Mapping::Template {
+                name: String::from("{name}"),
+                email: String::from("{login}@new.com"),
+            }
+        );
+        assert!(build_mapping(
+            String::from("{login} {nick}"),
+            String::from("a@x"),
+            Fields::Both
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn config_values_skip_templates() {
+        let m = Mapping::Template {
+            name: String::from("Jane"),
+            email: String::from("{login}@new.com"),
+        };
+        assert_eq!(config_values(&m), (Some("Jane"), None));
+    }
+
+    #[test]
+    fn missing_identity_keys_lists_blank_values() {
+        assert!(missing_identity_keys("Jane", "jane@x").is_empty());
+        assert_eq!(missing_identity_keys("Jane", " "), vec!["user.email"]);
+        assert_eq!(
+            missing_identity_keys("", ""),
+            vec!["user.name", "user.email"]
+        );
+    }
+
+    #[test]
+    fn global_changes_lists_only_differing_fields() {
+        assert_eq!(
+            global_changes(Some("Jane"), Some("j@x"), ("Jane", "old@x")),
+            vec![("user.email", "j@x")]
+        );
+        assert!(global_changes(Some("Jane"), None, ("Jane", "")).is_empty());
+    }
+
+    #[test]
+    fn non_interactive_requires_yes_and_identity() {
+        let missing = non_interactive_requirements(&Options::default(), Fields::NameOnly);
+        assert_eq!(
+            missing,
+            vec![
+                "--name <NAME> (or GAR_AUTHOR_NAME)",
+                "--yes (or GAR_ASSUME_YES=1)"
+            ]
+        );
+    }
+
+    #[test]
+    fn non_interactive_satisfied_by_yes() {
+        let opts = Options {
+            yes: true,
+            ..Options::default()
+        };
+        assert!(non_interactive_requirements(&opts, Fields::Both).is_empty());
+
+        let opts = Options {
+            profile: Some(String::from("work")),
+            ..Options::default()
+        };
+        assert_eq!(
+            non_interactive_requirements(&opts, Fields::Both),
+            vec!["--yes (or GAR_ASSUME_YES=1)"]
+        );
+    }
+
+    #[test]
+    fn amend_current_commit_maps_the_original_identities() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("rebase-merge")).unwrap();
+        fs::write(dir.path().join("rebase-merge/done"), "edit abc1234 first\n").unwrap();
+        let runner = Rc::new(ScriptedRunner::new(vec![
+            (
+                "log --format=%H%x00%an%x00%ae%x00%cn%x00%ce%x00%at -1 abc1234",
+                ScriptedRunner::ok(
+                    "abc1234def\x00Ann\x00ann@old.com\x00Bob\x00bob@old.com\x001600000000\n",
+                ),
+            ),
+            (
+                "commit --amend --author=Ann <ann@new.com> --no-edit",
+                ScriptedRunner::ok(""),
+            ),
+        ]));
+        let mapping = Mapping::Domain {
+            from: String::from("old.com"),
+            to: String::from("new.com"),
+        };
+
+        let repo =
+            Repo::new(dir.path().to_path_buf(), dir.path().to_path_buf()).using(runner.clone());
+        let result = amend_current_commit(
+            &repo,
+            &mapping,
+            &Default::default(),
+            None,
+            CommitOptions::default(),
+        );
+        assert!(result.is_ok());
+        assert!(runner.finished());
+        let amend = &runner.calls()[1];
+        assert!(amend
+            .envs
+            .contains(&("GIT_COMMITTER_EMAIL".into(), Some("bob@new.com".into()))));
+    }
+
+    #[test]
+    fn skip_conflicts_records_the_skipped_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("rebase-merge")).unwrap();
+        fs::write(dir.path().join("rebase-merge/done"), "edit abc1234 first\n").unwrap();
+        let runner = Rc::new(ScriptedRunner::new(vec![
+            (
+                "diff --name-only --diff-filter=U",
+                ScriptedRunner::ok("a.txt\n"),
+            ),
+            ("rebase --skip", ScriptedRunner::ok("")),
+            ("diff --name-only --diff-filter=U", ScriptedRunner::ok("")),
+        ]));
+        let options = CommitOptions {
+            quiet: true,
+            ..CommitOptions::default()
+        };
+        let repo =
+            Repo::new(dir.path().to_path_buf(), dir.path().to_path_buf()).using(runner.clone());
+
+        let mut skipped = Vec::new();
+        assert_eq!(skip_conflicts(&repo, options, &mut skipped), Ok(true));
+        assert_eq!(skipped, vec!["abc1234"]);
+        // A failure that is not a conflict is left to the caller.
+        assert_eq!(skip_conflicts(&repo, options, &mut skipped), Ok(false));
+        assert!(runner.finished());
     }
 }
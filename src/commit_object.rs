@@ -0,0 +1,354 @@
+use crate::identity::{CommitIdentities, Identity, Mapping};
+
+/// A raw commit (or annotated tag) object, split into its header lines and message.
+///
+/// Only the headers are interpreted; the message bytes are kept exactly as
+/// read, so rewriting a commit never touches its message or its encoding.
+/// Tag objects share the format, with a `tagger` header instead of
+/// `author`/`committer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitObject {
+    /// Header lines (`tree …`, `parent …`, `author …`, …), without newlines.
+    /// Continuation lines of multi-line headers start with a space.
+    pub headers: Vec<String>,
+    /// Everything after the blank line that ends the headers.
+    pub message: Vec<u8>,
+}
+
+/// Splits an identity header value (`Name <email> 1700000000 +0100`) into the
+/// identity and the timestamp part.
+pub(crate) fn parse_signature(value: &str) -> Option<(Identity, &str)> {
+    let open = match value.find('<') {
+        Some(i) => i,
+        None => return None,
+    };
+    let close = match value[open..].find('>') {
+        Some(i) => open + i,
+        None => return None,
+    };
+    let name = value[..open].trim_end();
+    let email = &value[open + 1..close];
+    let when = value[close + 1..].trim_start();
+    Some((Identity::new(name, email), when))
+}
+
+/// Reads the Unix seconds from the timestamp part of an identity header.
+fn signature_time(when: &str) -> Option<i64> {
+    when.split_whitespace().next().and_then(|t| t.parse().ok())
+}
+
+/// Formats an identity header value from an identity and a timestamp part.
+fn format_signature(id: &Identity, when: &str) -> String {
+    format!("{} <{}> {}", id.name, id.email, when)
+}
+
+impl CommitObject {
+    /// Parses the output of `git cat-file commit <sha>`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CommitObject)` on success.
+    /// * `Err(String)` if the headers are not valid UTF-8.
+    pub fn parse(raw: &[u8]) -> Result<CommitObject, String> {
+        let split = raw.windows(2).position(|w| w == b"\n\n");
+        let (head, message) = match split {
+            Some(i) => (&raw[..i], raw[i + 2..].to_vec()),
+            None => (raw, Vec::new()),
+        };
+        let head = match std::str::from_utf8(head) {
+            Ok(h) => h,
+            Err(_) => return Err(String::from("commit headers are not valid UTF-8")),
+        };
+
+        Ok(CommitObject {
+            headers: head
+                .split('\n')
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_string())
+                .collect(),
+            message,
+        })
+    }
+
+    /// Serializes the commit back into the raw object format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.headers.join("\n").into_bytes();
+        out.extend_from_slice(b"\n\n");
+        out.extend_from_slice(&self.message);
+        out
+    }
+
+    /// Returns the value of the first header called `key`.
+    fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find_map(|h| h.strip_prefix(key).and_then(|v| v.strip_prefix(' ')))
+    }
+
+    /// Reads the author and committer identities.
+    pub fn identities(&self) -> Option<CommitIdentities> {
+        let (author, author_time) = match self.header("author").and_then(parse_signature) {
+            Some((id, when)) => (id, signature_time(when)),
+            None => return None,
+        };
+        let committer = match self.header("committer").and_then(parse_signature) {
+            Some((id, _)) => id,
+            None => return None,
+        };
+        Some(CommitIdentities {
+            author,
+            committer,
+            author_time,
+            sha: None,
+        })
+    }
+
+    /// Replaces the value of the first header called `key`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the header exists and its value changed.
+    pub fn set_header(&mut self, key: &str, value: &str) -> bool {
+        let prefix = format!("{} ", key);
+        for h in self.headers.iter_mut() {
+            if let Some(old) = h.strip_prefix(&prefix) {
+                if old == value {
+                    return false;
+                }
+                *h = format!("{}{}", prefix, value);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Points `parent` headers at rewritten commits, using `rewritten` to map
+    /// an original SHA to its replacement.
+    ///
+    /// # Returns
+    ///
+    /// `true` if any parent changed.
+    pub fn map_parents<F: Fn(&str) -> Option<String>>(&mut self, rewritten: F) -> bool {
+        let mut changed = false;
+        for h in self.headers.iter_mut() {
+            let new = match h.strip_prefix("parent ").and_then(&rewritten) {
+                Some(n) => n,
+                None => continue,
+            };
+            *h = format!("parent {}", new);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Applies `mapping` to the author and committer of the commit `sha`
+    /// (this object's original SHA).
+    ///
+    /// Where the mapping leaves the committer to Git, `current_user` is
+    /// recorded instead, matching what a rebase would record.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if an identity changed.
+    /// * `Ok(false)` if the commit is unaffected.
+    /// * `Err(String)` if the commit has no author or committer.
+    pub fn apply_mapping(
+        &mut self,
+        sha: &str,
+        mapping: &Mapping,
+        current_user: &Identity,
+    ) -> Result<bool, String> {
+        let mut ids = match self.identities() {
+            Some(ids) => ids,
+            None => return Err(String::from("commit has no author or committer")),
+        };
+        ids.sha = Some(sha.to_string());
+
+        let (author, committer) = match mapping.plan(&ids) {
+            Some(plan) => plan,
+            None => return Ok(false),
+        };
+        let committer = committer.unwrap_or_else(|| current_user.clone());
+        if author == ids.author && committer == ids.committer {
+            return Ok(false);
+        }
+
+        self.set_identities(&author, Some(&committer));
+        Ok(true)
+    }
+
+    /// Reads the tag date of an annotated tag object, in Unix seconds.
+    pub fn tagger_time(&self) -> Option<i64> {
+        self.header("tagger")
+            .and_then(parse_signature)
+            .and_then(|(_, when)| signature_time(when))
+    }
+
+    /// Reads the tagger of an annotated tag object.
+    pub fn tagger(&self) -> Option<Identity> {
+        self.header("tagger")
+            .and_then(parse_signature)
+            .map(|(id, _)| id)
+    }
+
+    /// Replaces the tagger of an annotated tag object, keeping the date.
+    ///
+    /// An inline signature at the end of the message is removed, because it
+    /// would no longer verify.
+    pub fn set_tagger(&mut self, tagger: &Identity) {
+        for h in self.headers.iter_mut() {
+            let when = match h.strip_prefix("tagger ").and_then(parse_signature) {
+                Some((_, when)) => when.to_string(),
+                None => continue,
+            };
+            *h = format!("tagger {}", format_signature(tagger, &when));
+        }
+        self.strip_message_signature();
+    }
+
+    /// Removes an armored signature (PGP, SSH or X.509) from the end of the message.
+    pub(crate) fn strip_message_signature(&mut self) {
+        const MARKERS: [&[u8]; 3] = [
+            b"-----BEGIN PGP SIGNATURE-----",
+            b"-----BEGIN SSH SIGNATURE-----",
+            b"-----BEGIN SIGNED MESSAGE-----",
+        ];
+        let start = MARKERS
+            .iter()
+            .filter_map(|m| {
+                self.message
+                    .windows(m.len())
+                    .position(|w| w == *m)
+                    .filter(|&i| i == 0 || self.message[i - 1] == b'\n')
+            })
+            .min();
+        if let Some(i) = start {
+            self.message.truncate(i);
+        }
+    }
+
+    /// Drops the signature headers (`gpgsig`, `gpgsig-sha256`) and their
+    /// continuation lines, which no longer verify once anything changes.
+    pub fn drop_signature(&mut self) {
+        let mut in_signature = false;
+        self.headers.retain(|h| {
+            if h.starts_with(' ') && in_signature {
+                return false;
+            }
+            in_signature = h.starts_with("gpgsig ") || h.starts_with("gpgsig-sha256 ");
+            !in_signature
+        });
+    }
+
+    /// Replaces the author and (optionally) committer, keeping their dates.
+    ///
+    /// The signature is dropped too (see [`CommitObject::drop_signature`]).
+    pub fn set_identities(&mut self, author: &Identity, committer: Option<&Identity>) {
+        self.drop_signature();
+
+        for h in self.headers.iter_mut() {
+            let (key, value) = match h.split_once(' ') {
+                Some(kv) => kv,
+                None => continue,
+            };
+            let id = match key {
+                "author" => Some(author),
+                "committer" => committer,
+                _ => None,
+            };
+            if let (Some(id), Some((_, when))) = (id, parse_signature(value)) {
+                *h = format!("{} {}", key, format_signature(id, when));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_signature, CommitObject};
+    use crate::identity::Identity;
+
+    const RAW: &[u8] = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+parent 1111111111111111111111111111111111111111\n\
+author Old Name <old@x.com> 1700000000 +0100\n\
+committer Old Name <old@x.com> 1700000100 +0100\n\
+gpgsig -----BEGIN PGP SIGNATURE-----\n \n abc\n -----END PGP SIGNATURE-----\n\
+\n\
+Subject\n\nBody \xff\n";
+
+    #[test]
+    fn parse_signature_splits_identity_and_date() {
+        let (id, when) = parse_signature("Jane Doe <jane@x.com> 1700000000 -0500").unwrap();
+        assert_eq!(id, Identity::new("Jane Doe", "jane@x.com"));
+        assert_eq!(when, "1700000000 -0500");
+        assert!(parse_signature("no email here").is_none());
+    }
+
+    #[test]
+    fn round_trips_unchanged_objects() {
+        let obj = CommitObject::parse(RAW).unwrap();
+        assert_eq!(obj.to_bytes(), RAW);
+        let ids = obj.identities().unwrap();
+        assert_eq!(ids.author, Identity::new("Old Name", "old@x.com"));
+        assert_eq!(ids.author_time, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn set_identities_keeps_dates_and_drops_signature() {
+        let mut obj = CommitObject::parse(RAW).unwrap();
+        obj.set_identities(&Identity::new("New", "new@x.com"), None);
+        assert_eq!(
+            obj.headers,
+            vec![
+                "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904",
+                "parent 1111111111111111111111111111111111111111",
+                "author New <new@x.com> 1700000000 +0100",
+                "committer Old Name <old@x.com> 1700000100 +0100",
+            ]
+        );
+        assert_eq!(obj.message, b"Subject\n\nBody \xff\n");
+    }
+
+    #[test]
+    fn set_tagger_keeps_date_and_drops_signature() {
+        let raw = b"object 1111111111111111111111111111111111111111\n\
+type commit\n\
+tag v1.0\n\
+tagger Old <old@x.com> 1700000000 +0000\n\
+\n\
+Release 1.0\n\
+-----BEGIN PGP SIGNATURE-----\n\
+abc\n\
+-----END PGP SIGNATURE-----\n";
+        let mut obj = CommitObject::parse(raw).unwrap();
+        assert_eq!(obj.tagger(), Some(Identity::new("Old", "old@x.com")));
+        obj.set_tagger(&Identity::new("New", "new@x.com"));
+        assert_eq!(obj.headers[3], "tagger New <new@x.com> 1700000000 +0000");
+        assert_eq!(obj.message, b"Release 1.0\n");
+    }
+
+    #[test]
+    fn map_parents_and_set_header_report_changes() {
+        let mut obj = CommitObject::parse(RAW).unwrap();
+        assert!(!obj.map_parents(|_| None));
+        assert!(obj.map_parents(|p| {
+            assert_eq!(p, "1111111111111111111111111111111111111111");
+            Some(String::from("2222222222222222222222222222222222222222"))
+        }));
+        assert_eq!(
+            obj.headers[1],
+            "parent 2222222222222222222222222222222222222222"
+        );
+        assert!(obj.set_header("tree", "abc"));
+        assert!(!obj.set_header("tree", "abc"));
+        assert!(!obj.set_header("missing", "abc"));
+    }
+
+    #[test]
+    fn set_identities_can_replace_committer() {
+        let mut obj = CommitObject::parse(RAW).unwrap();
+        let new = Identity::new("New", "new@x.com");
+        obj.set_identities(&new, Some(&new));
+        assert_eq!(obj.headers[3], "committer New <new@x.com> 1700000100 +0100");
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{git::Repo, identity::Identity};
+
+/// The trailer a commit is signed off with under the Developer Certificate
+/// of Origin.
+const SIGN_OFF: &str = "Signed-off-by:";
+
+/// Why a commit fails the DCO check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The message has no `Signed-off-by` trailer at all.
+    Missing,
+    /// The message is signed off, but by none of these matches the author.
+    Mismatch(Vec<Identity>),
+}
+
+/// A commit whose sign-off does not match its author.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The full commit SHA.
+    pub sha: String,
+    /// The commit's author.
+    pub author: Identity,
+    /// What is wrong with the sign-off.
+    pub problem: Problem,
+}
+
+/// The outcome of checking a range of commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Number of commits checked.
+    pub checked: usize,
+    /// Every commit failing the check, newest first.
+    pub violations: Vec<Violation>,
+}
+
+/// Lists the identities of the `Signed-off-by` lines in `message`.
+///
+/// The key is matched case-insensitively, as DCO checkers do; lines whose
+/// value is not `Name <email>` are skipped.
+pub(crate) fn sign_offs(message: &str) -> Vec<Identity> {
+    let mut found = Vec::new();
+    for line in message.lines() {
+        let line = line.trim();
+        let value = match line.get(..SIGN_OFF.len()) {
+            Some(key) if key.eq_ignore_ascii_case(SIGN_OFF) => line[SIGN_OFF.len()..].trim(),
+            _ => continue,
+        };
+        let (name, email) = match value.split_once('<') {
+            Some((name, rest)) => match rest.strip_suffix('>') {
+                Some(email) => (name, email),
+                None => continue,
+            },
+            None => continue,
+        };
+        found.push(Identity::new(name.trim(), email.trim()));
+    }
+    found
+}
+
+/// Checks that `message` is signed off by `author`: same name, and the same
+/// email ignoring case.
+///
+/// # Returns
+///
+/// * `None` if a sign-off matches the author.
+/// * `Some(Problem)` otherwise.
+pub fn check_message(author: &Identity, message: &str) -> Option<Problem> {
+    let found = sign_offs(message);
+    if found.is_empty() {
+        return Some(Problem::Missing);
+    }
+    let matches = found
+        .iter()
+        .any(|s| s.name == author.name && s.email.eq_ignore_ascii_case(&author.email));
+    if matches {
+        None
+    } else {
+        Some(Problem::Mismatch(found))
+    }
+}
+
+/// Parses `git log` output in the format used by [`check`]: the SHA, author
+/// name, author email and raw message of each commit, separated by NUL, with
+/// commits separated by `\x1e`.
+fn check_log(log: &str) -> Report {
+    let mut checked = 0;
+    let mut violations = Vec::new();
+
+    for record in log.split('\x1e') {
+        let record = record.trim_start_matches('\n');
+        let mut fields = record.splitn(4, '\0');
+        let (sha, name, email, message) =
+            match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some(s), Some(n), Some(e), Some(m)) if !s.is_empty() => (s, n, e, m),
+                _ => continue,
+            };
+        checked += 1;
+        let author = Identity::new(name, email);
+        if let Some(problem) = check_message(&author, message) {
+            violations.push(Violation {
+                sha: sha.to_string(),
+                author,
+                problem,
+            });
+        }
+    }
+
+    Report {
+        checked,
+        violations,
+    }
+}
+
+/// Checks every commit selected by `revs` for a `Signed-off-by` trailer
+/// matching its author (`--check-dco`).
+///
+/// # Parameters
+///
+/// * `repo` – The repository to check.
+/// * `revs` – Revision arguments for `git log`, e.g. `["HEAD", "--not", old_head]`
+///   for the commits a rewrite created.
+///
+/// # Returns
+///
+/// * `Ok(Report)` with the commits checked and those failing.
+/// * `Err(String)` if the history could not be read.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{dco, git::Repo};
+///
+/// let repo = Repo::discover().unwrap();
+/// let report = dco::check(&repo, &["HEAD"]).unwrap();
+/// assert!(report.violations.is_empty());
+/// ```
+pub fn check(repo: &Repo, revs: &[&str]) -> Result<Report, String> {
+    match repo.log("%H%x00%an%x00%ae%x00%B%x1e", revs) {
+        Ok(log) => Ok(check_log(&log)),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_log, check_message, sign_offs, Problem};
+    use crate::identity::Identity;
+
+    #[test]
+    fn sign_offs_reads_every_trailer() {
+        let message = "Fix parser\n\nSigned-off-by: Jane Doe <jane@corp.com>\nsigned-off-by: Bot <bot@ci>\nSigned-off-by: nobody\n";
+        assert_eq!(
+            sign_offs(message),
+            vec![
+                Identity::new("Jane Doe", "jane@corp.com"),
+                Identity::new("Bot", "bot@ci"),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_message_wants_a_sign_off_by_the_author() {
+        let jane = Identity::new("Jane Doe", "jane@corp.com");
+        assert_eq!(
+            check_message(&jane, "Fix\n\nSigned-off-by: Jane Doe <Jane@Corp.com>"),
+            None
+        );
+        assert_eq!(check_message(&jane, "Fix"), Some(Problem::Missing));
+        assert_eq!(
+            check_message(&jane, "Fix\n\nSigned-off-by: Jane Doe <jane@old.com>"),
+            Some(Problem::Mismatch(vec![Identity::new(
+                "Jane Doe",
+                "jane@old.com"
+            )]))
+        );
+    }
+
+    #[test]
+    fn check_log_splits_commits() {
+        let log =
+            "aaa\0Jane\0j@x\0One\n\nSigned-off-by: Jane <j@x>\n\x1e\nbbb\0Jane\0j@x\0Two\n\x1e";
+        let report = check_log(log);
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].sha, "bbb");
+        assert_eq!(report.violations[0].problem, Problem::Missing);
+    }
+}
@@ -0,0 +1,159 @@
+/// An explanation of a Git failure, with the command that fixes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// What went wrong, in plain words.
+    pub problem: &'static str,
+    /// The command (or commands) to run before trying again.
+    pub fix: &'static str,
+}
+
+/// Known failures: any of the stderr fragments (matched case-insensitively)
+/// identifies the diagnosis. Checked in order, so more specific entries come
+/// first.
+const KNOWN: [(&[&str], Diagnosis); 9] = [
+    (
+        &["not making progress"],
+        Diagnosis {
+            problem: "`git rebase --continue` succeeded but left the rebase where it was; a hook (post-commit, post-rewrite) or an --exec command probably undoes each step.",
+            fix: "git rebase --abort   # then re-run with --no-verify, without --exec, or with the hook disabled; `git rebase --skip` drops just this commit",
+        },
+    ),
+    (
+        &["appears hung"],
+        Diagnosis {
+            problem: "A Git command stopped responding; a hook, credential helper or GPG pinentry was probably waiting for input that never came.",
+            fix: "git rebase --abort   # then re-run with --no-verify or --no-gpg-sign, or a longer --timeout",
+        },
+    ),
+    (
+        &["index.lock", "another git process seems to be running"],
+        Diagnosis {
+            problem: "Another Git process is running in this repository, or one crashed and left its lock file behind.",
+            fix: "rm -f \"$(git rev-parse --git-dir)/index.lock\"   # only once no other git command is running",
+        },
+    ),
+    (
+        &["you have unstaged changes", "your index contains uncommitted changes", "please commit or stash them", "would be overwritten by"],
+        Diagnosis {
+            problem: "The working tree has uncommitted changes, and a rebase needs a clean tree.",
+            fix: "git stash push --include-untracked   # then re-run, and `git stash pop` afterwards",
+        },
+    ),
+    (
+        &["you are not currently on a branch", "not on any branch", "head detached"],
+        Diagnosis {
+            problem: "HEAD is detached, so the rewritten commits would not end up on any branch.",
+            fix: "git switch <branch>   # or `git switch -c <new-branch>` to keep the detached commits",
+        },
+    ),
+    (
+        &["cannot rebase onto multiple branches"],
+        Diagnosis {
+            problem: "The branch has several upstreams configured (more than one branch.<name>.merge entry), so Git cannot tell which one to use.",
+            fix: "git branch --unset-upstream && git branch --set-upstream-to=<remote>/<branch>",
+        },
+    ),
+    (
+        &["execution failed:"],
+        Diagnosis {
+            problem: "An --exec command (or author-rewrite.exec) failed after a rewritten commit.",
+            fix: "git rebase --abort   # fix what the command reported, then re-run",
+        },
+    ),
+    (
+        &["pre-commit", "commit-msg", "hook"],
+        Diagnosis {
+            problem: "A pre-commit or commit-msg hook rejected the rewritten commit.",
+            fix: "git rebase --abort   # then re-run with --no-verify to skip the hooks",
+        },
+    ),
+    (
+        &["conflict", "could not apply"],
+        Diagnosis {
+            problem: "A commit did not apply cleanly while the history was re-created.",
+            fix: "git status   # resolve the conflicts, `git add` them and `git rebase --continue`, or give up with `git rebase --abort`",
+        },
+    ),
+];
+
+/// Matches a Git error message against well-known failures.
+///
+/// # Parameters
+///
+/// * `error` – The error text, typically including Git's stderr.
+///
+/// # Returns
+///
+/// * `Some(Diagnosis)` for a recognized failure.
+/// * `None` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::diagnose::diagnose;
+///
+/// let d = diagnose("error: cannot rebase: You have unstaged changes.").unwrap();
+/// assert!(d.fix.starts_with("git stash"));
+/// assert!(diagnose("fatal: something unusual").is_none());
+/// ```
+pub fn diagnose(error: &str) -> Option<Diagnosis> {
+    let lower = error.to_lowercase();
+    KNOWN
+        .iter()
+        .find(|(patterns, _)| patterns.iter().any(|p| lower.contains(p)))
+        .map(|(_, d)| d.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diagnose;
+
+    #[test]
+    fn recognizes_common_failures() {
+        let cases = [
+            (
+                "fatal: Unable to create '/r/.git/index.lock': File exists.",
+                "rm -f",
+            ),
+            (
+                "error: Your local changes to the following files would be overwritten by checkout",
+                "git stash",
+            ),
+            ("fatal: You are not currently on a branch.", "git switch"),
+            (
+                "fatal: Cannot rebase onto multiple branches.",
+                "git branch --unset-upstream",
+            ),
+            (
+                "`git commit --amend` returned non-zero:\nrunning pre-commit hook... failed",
+                "git rebase --abort",
+            ),
+            (
+                "CONFLICT (content): Merge conflict in a.txt\nerror: could not apply 1a2b3c4",
+                "git status",
+            ),
+            (
+                "`git rebase --continue` returned non-zero:\nwarning: execution failed: make lint",
+                "git rebase --abort",
+            ),
+            (
+                "`git commit --amend --no-edit` appears hung: it did not finish within 60s (--timeout), so it was stopped",
+                "git rebase --abort   # then re-run with --no-verify or --no-gpg-sign",
+            ),
+            (
+                "the rebase is not making progress: it stopped at step 4 (edit 1a2b3c4 Fix) again",
+                "git rebase --abort   # then re-run with --no-verify, without --exec",
+            ),
+        ];
+        for (error, fix) in cases {
+            let d = diagnose(error).unwrap_or_else(|| panic!("no diagnosis for {}", error));
+            assert!(d.fix.starts_with(fix), "{}: {}", error, d.fix);
+        }
+    }
+
+    #[test]
+    fn unknown_errors_are_left_alone() {
+        assert!(diagnose("`git rebase --continue` returned non-zero").is_none());
+        assert!(diagnose("").is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Directory names that hold vendored or generated code rather than projects
+/// of their own; repositories inside them are skipped unless asked for.
+pub const VENDORED_DIRS: &[&str] = &[
+    "node_modules",
+    "bower_components",
+    "vendor",
+    "target",
+    "build",
+    "dist",
+    ".venv",
+    "venv",
+    "Pods",
+];
+
+/// Reports whether the directory `name` is skipped while discovering.
+///
+/// The `.git` directory itself is never descended into.
+pub(crate) fn is_skipped(name: &str, include_vendored: bool) -> bool {
+    name == ".git" || (!include_vendored && VENDORED_DIRS.contains(&name))
+}
+
+/// Reports whether `dir` is the working tree of a Git repository.
+///
+/// `.git` may be a directory or, for worktrees and submodules, a file.
+fn is_repository(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+/// Recursively collects the repositories under `dir` into `found`.
+fn walk(dir: &Path, include_vendored: bool, found: &mut Vec<PathBuf>) {
+    if is_repository(dir) {
+        found.push(dir.to_path_buf());
+    }
+
+    // Unreadable directories are skipped rather than failing the search.
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        // Symlinks are not followed, so a link cycle cannot trap the walk.
+        let is_dir = match entry.file_type() {
+            Ok(t) => t.is_dir(),
+            Err(_) => false,
+        };
+        if !is_dir {
+            continue;
+        }
+        let name = entry.file_name();
+        if is_skipped(&name.to_string_lossy(), include_vendored) {
+            continue;
+        }
+        walk(&entry.path(), include_vendored, found);
+    }
+}
+
+/// Finds every Git repository at or below `root`.
+///
+/// Repositories nested inside other repositories are found too, except below
+/// the [`VENDORED_DIRS`] unless `include_vendored` is set. Symlinks are not
+/// followed.
+///
+/// # Parameters
+///
+/// * `root` – The directory to search.
+/// * `include_vendored` – Also search `node_modules`, `target` and the like.
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` with the repositories, sorted by path.
+/// * `Err(String)` if `root` is not a directory.
+///
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::discover::discover;
+///
+/// for repo in discover("/home/me/src", false).unwrap() {
+///     println!("{}", repo.display());
+/// }
+/// ```
+pub fn discover(root: &str, include_vendored: bool) -> Result<Vec<PathBuf>, String> {
+    let root = Path::new(root);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let mut found = Vec::new();
+    walk(root, include_vendored, &mut found);
+    found.sort();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discover, is_skipped};
+    use std::fs;
+
+    #[test]
+    fn is_skipped_covers_git_dir_and_vendored_dirs() {
+        assert!(is_skipped(".git", true));
+        assert!(is_skipped("node_modules", false));
+        assert!(!is_skipped("node_modules", true));
+        assert!(!is_skipped("src", false));
+    }
+
+    #[test]
+    fn discover_finds_nested_repositories_but_not_vendored_ones() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let root = dir.path();
+        for repo in ["api", "api/plugins/auth", "web", "web/node_modules/lib"] {
+            fs::create_dir_all(root.join(repo).join(".git")).expect("failed to create repo");
+        }
+        fs::create_dir_all(root.join("notes")).expect("failed to create dir");
+
+        let root_str = root.to_str().unwrap();
+        let found = discover(root_str, false).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                root.join("api"),
+                root.join("api/plugins/auth"),
+                root.join("web"),
+            ]
+        );
+        assert_eq!(discover(root_str, true).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn discover_rejects_missing_root() {
+        assert!(discover("/definitely/not/here", false).is_err());
+    }
+}
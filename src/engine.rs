@@ -0,0 +1,233 @@
+use crate::{error::Error, identity::Mapping, message::MessageEdits, selection::Selection};
+
+/// The rewrite engines this build has, by `--engine` name.
+pub const NAMES: [&str; 4] = ["metadata", "rebase", "replace", "gix"];
+
+/// Which engine rewrites the selected commits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EngineKind {
+    /// The branch's commit objects are rewritten with their original trees
+    /// and moved into place at once, so nothing can conflict.
+    #[default]
+    Metadata,
+    /// An interactive rebase that stops at every selected commit and amends it.
+    Rebase,
+    /// Corrected copies of the commits behind `git replace` refs; history
+    /// itself is left alone (`--replace-mode`).
+    Replace,
+    /// Experimental: the branch's commit objects are rewritten in-process with
+    /// gitoxide, without a rebase or a `git` binary for the rewrite itself.
+    Gix,
+}
+
+impl EngineKind {
+    /// Looks up an engine by its `--engine` name.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(EngineKind)` for a known name.
+    /// * `Err(String)` listing the available engines otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::engine::EngineKind;
+    ///
+    /// assert_eq!(EngineKind::parse("replace"), Ok(EngineKind::Replace));
+    /// assert!(EngineKind::parse("filter-branch").is_err());
+    /// ```
+    pub fn parse(name: &str) -> Result<EngineKind, String> {
+        match name {
+            "metadata" => Ok(EngineKind::Metadata),
+            "rebase" => Ok(EngineKind::Rebase),
+            "replace" => Ok(EngineKind::Replace),
+            "gix" => Ok(EngineKind::Gix),
+            other => Err(format!(
+                "unknown engine `{}` (available: {})",
+                other,
+                NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// The engine's `--engine` name.
+    pub fn name(self) -> &'static str {
+        match self {
+            EngineKind::Metadata => "metadata",
+            EngineKind::Rebase => "rebase",
+            EngineKind::Replace => "replace",
+            EngineKind::Gix => "gix",
+        }
+    }
+
+    /// Reports whether the engine replaces the branch's commits, so commits
+    /// that collaborators already have are affected.
+    pub fn rewrites_history(self) -> bool {
+        match self {
+            EngineKind::Metadata | EngineKind::Rebase | EngineKind::Gix => true,
+            EngineKind::Replace => false,
+        }
+    }
+}
+
+/// Decides the engine from `--engine` (or the spec's `engine`) and its
+/// shorthand `--replace-mode`.
+///
+/// # Parameters
+///
+/// * `name` – The engine asked for, if any.
+/// * `replace_mode` – Whether `--replace-mode` was given.
+/// * `needs_rebase` – Whether an option in use only works in a rebase
+///   (e.g. `--manual`), which makes the rebase engine the default.
+///
+/// # Returns
+///
+/// * `Ok(EngineKind)` – [`EngineKind::Metadata`] if neither is given, or
+///   [`EngineKind::Rebase`] with `needs_rebase`.
+/// * `Err(String)` for an unknown name, or `--replace-mode` with another engine.
+pub fn resolve(
+    name: Option<&str>,
+    replace_mode: bool,
+    needs_rebase: bool,
+) -> Result<EngineKind, String> {
+    let kind = match name {
+        Some(n) => match EngineKind::parse(n) {
+            Ok(k) => k,
+            Err(e) => return Err(e),
+        },
+        None if replace_mode => EngineKind::Replace,
+        None if needs_rebase => EngineKind::Rebase,
+        None => EngineKind::Metadata,
+    };
+    if replace_mode && kind != EngineKind::Replace {
+        return Err(format!(
+            "--replace-mode selects the replace engine; it cannot be combined with --engine {}",
+            kind.name()
+        ));
+    }
+    Ok(kind)
+}
+
+/// What an engine is asked to rewrite.
+#[derive(Debug, Clone, Copy)]
+pub struct Job<'a> {
+    /// The commits to rewrite.
+    pub selection: &'a Selection,
+    /// How their identities change.
+    pub mapping: &'a Mapping,
+    /// How their messages change.
+    pub edits: &'a MessageEdits,
+}
+
+/// A rewrite backend.
+///
+/// The flow around it is the same for every engine: the commits are
+/// selected, the banner and signature preview are shown, the user confirms,
+/// the engine rewrites, then tags are rewritten and the before/after report
+/// printed. Engines only differ in how the rewritten commits come about.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::engine::{Engine, EngineKind, Job};
+/// use git_author_rewrite::error::Error;
+/// use git_author_rewrite::identity::Mapping;
+///
+/// /// Rewrites nothing, e.g. for a dry run.
+/// struct Noop;
+///
+/// impl Engine for Noop {
+///     fn kind(&self) -> EngineKind {
+///         EngineKind::Rebase
+///     }
+///     fn print_banner(&self, mapping: &Mapping) {
+///         println!("Would rewrite to {}", mapping.describe());
+///     }
+///     fn confirm(&self) -> Result<bool, String> {
+///         Ok(true)
+///     }
+///     fn rewrite(&mut self, _job: &Job) -> Result<(), Error> {
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Engine {
+    /// Which engine this is.
+    fn kind(&self) -> EngineKind;
+
+    /// Prints the banner explaining what the run will do with `mapping`.
+    fn print_banner(&self, mapping: &Mapping);
+
+    /// Settles the engine's own settings before the user confirms, asking
+    /// about them if needed (e.g. whether to keep signing commits).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` when the engine is ready.
+    /// * `Err(String)` if a setting is invalid or a prompt failed.
+    fn prepare(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Asks the user whether to start.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` – The answer.
+    /// * `Err(String)` if the prompt failed.
+    fn confirm(&self) -> Result<bool, String>;
+
+    /// Rewrites the commits of `job`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once every commit is rewritten.
+    /// * `Err(Error)` on failure, with what the engine was doing.
+    fn rewrite(&mut self, job: &Job) -> Result<(), Error>;
+
+    /// The commits left out of the rewritten history because they did not
+    /// apply cleanly (`--skip-on-conflict`), oldest first.
+    fn skipped(&self) -> &[String] {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, EngineKind, NAMES};
+
+    #[test]
+    fn names_round_trip() {
+        for name in NAMES {
+            assert_eq!(EngineKind::parse(name).map(EngineKind::name), Ok(name));
+        }
+        let err = EngineKind::parse("libgit2").unwrap_err();
+        assert!(
+            err.contains("available: metadata, rebase, replace, gix"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn resolve_honors_the_replace_mode_shorthand() {
+        assert_eq!(resolve(None, true, false), Ok(EngineKind::Replace));
+        assert_eq!(
+            resolve(Some("replace"), true, false),
+            Ok(EngineKind::Replace)
+        );
+        assert!(resolve(Some("rebase"), true, false).is_err());
+        assert!(resolve(Some("gix"), true, false).is_err());
+        assert_eq!(resolve(Some("gix"), false, false), Ok(EngineKind::Gix));
+    }
+
+    #[test]
+    fn resolve_defaults_to_metadata_unless_a_rebase_is_needed() {
+        assert_eq!(resolve(None, false, false), Ok(EngineKind::Metadata));
+        assert_eq!(resolve(None, false, true), Ok(EngineKind::Rebase));
+        assert_eq!(
+            resolve(Some("metadata"), false, true),
+            Ok(EngineKind::Metadata)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+/// Why a run of the tool failed: what went wrong, what it was doing at the
+/// time (innermost first, e.g. the commit being amended, then the engine),
+/// and what the user can do about it.
+///
+/// Failures are returned up to `main`, which prints them once with
+/// [`Error::lines`]; nothing below it prints an error of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+    context: Vec<String>,
+    hints: Vec<String>,
+}
+
+impl Error {
+    /// Creates an error from what went wrong.
+    pub fn new(message: impl Into<String>) -> Error {
+        Error {
+            message: message.into(),
+            context: Vec::new(),
+            hints: Vec::new(),
+        }
+    }
+
+    /// Records what the tool was doing when the error happened, e.g.
+    /// `amending commit 1a2b3c4`. Each call adds an outer layer.
+    pub fn context(mut self, doing: impl Into<String>) -> Error {
+        self.context.push(doing.into());
+        self
+    }
+
+    /// Adds a line printed after the error, e.g. how to get usage or how to
+    /// fix a known Git failure. Hints are printed in the order they were added.
+    pub fn hint(mut self, hint: impl Into<String>) -> Error {
+        self.hints.push(hint.into());
+        self
+    }
+
+    /// What went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// What the tool was doing, innermost first.
+    pub fn chain(&self) -> &[String] {
+        &self.context
+    }
+
+    /// The hints, in the order they were added.
+    pub fn hints(&self) -> &[String] {
+        &self.hints
+    }
+
+    /// The report to print: `Error: <message>`, one `  while <context>`
+    /// line per layer from the innermost out, then the hints.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::error::Error;
+    ///
+    /// let err = Error::new("could not lock config file")
+    ///     .context("amending commit 1a2b3c4")
+    ///     .context("rewriting main with the rebase engine");
+    /// assert_eq!(
+    ///     err.lines(),
+    ///     vec![
+    ///         "Error: could not lock config file",
+    ///         "  while amending commit 1a2b3c4",
+    ///         "  while rewriting main with the rebase engine",
+    ///     ]
+    /// );
+    /// ```
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Error: {}", self.message)];
+        lines.extend(self.context.iter().map(|c| format!("  while {}", c)));
+        lines.extend(self.hints.iter().cloned());
+        lines
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = self.message.clone();
+        for c in &self.context {
+            text.push_str(&format!(" (while {})", c));
+        }
+        write!(f, "{}", text)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error {
+    fn from(message: String) -> Error {
+        Error::new(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Error {
+        Error::new(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn lines_list_the_context_from_the_innermost_out() {
+        let err = Error::new("unknown option `--bogus`")
+            .hint("Run `git-author-rewrite --help` for usage.");
+        assert_eq!(
+            err.lines(),
+            vec![
+                "Error: unknown option `--bogus`",
+                "Run `git-author-rewrite --help` for usage.",
+            ]
+        );
+        assert_eq!(err.message(), "unknown option `--bogus`");
+    }
+
+    #[test]
+    fn display_keeps_the_context_on_one_line() {
+        let err = Error::from(String::from("exit status 1"))
+            .context("running `git rebase --continue`")
+            .context("rewriting main");
+        assert_eq!(
+            err.to_string(),
+            "exit status 1 (while running `git rebase --continue`) (while rewriting main)"
+        );
+    }
+}
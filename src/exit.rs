@@ -0,0 +1,12 @@
+/// Exit code returned when the repository has nothing to rewrite.
+pub const EXIT_NOTHING_TO_DO: i32 = 3;
+
+/// Exit code returned when `verify` finds commits with an unexpected identity.
+pub const EXIT_VERIFY_FAILED: i32 = 4;
+
+/// Exit code returned when `--fsck` finds problems after a completed rewrite.
+pub const EXIT_FSCK_FAILED: i32 = 5;
+
+/// Exit code returned when `--check-dco` finds rewritten commits that are not
+/// signed off by their author.
+pub const EXIT_DCO_FAILED: i32 = 6;
@@ -1,49 +1,298 @@
-use std::path::Path;
-use std::process::{Command, Stdio};
+use crate::identity::Identity;
+use crate::runner::{GitCommand, GitOutput, GitRunner, GitStatus, Io, ProcessRunner};
+use crate::todo::{TodoCommand, TodoLine};
 
-/// Builds the value for the `GIT_SEQUENCE_EDITOR` environment variable.
+use std::cell::RefCell;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether `--show-commands` is on (see [`set_show_commands`]).
+static SHOW_COMMANDS: AtomicBool = AtomicBool::new(false);
+
+/// Turns `--show-commands` on or off: while on, every `git` command line is
+/// printed to stderr, with its environment overrides, right before it runs.
+pub fn set_show_commands(on: bool) {
+    SHOW_COMMANDS.store(on, Ordering::Relaxed);
+}
+
+thread_local! {
+    /// The runner set by [`with_runner`] on this thread, if any.
+    static RUNNER: RefCell<Option<Rc<dyn GitRunner>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous runner when [`with_runner`] returns or unwinds.
+struct RestoreRunner(Option<Rc<dyn GitRunner>>);
+
+impl Drop for RestoreRunner {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        RUNNER.with(|r| *r.borrow_mut() = previous);
+    }
+}
+
+/// Runs `f` with `runner` as the current thread's runner: every [`Repo`]
+/// opened inside `f`, and the free functions of this module, send their
+/// commands through it instead of a subprocess; e.g. a scripted runner in
+/// tests of `cli::entry`. A single [`Repo`] can also be given a runner with
+/// [`Repo::using`].
+///
+/// Calls can be nested; the previous runner is restored afterwards.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires the `test-util` feature.
+/// use git_author_rewrite::{git::{self, Repo}, test_util::ScriptedRunner};
+/// use std::{path::PathBuf, rc::Rc};
+///
+/// let runner = Rc::new(ScriptedRunner::new(vec![(
+///     "rev-parse --git-dir",
+///     ScriptedRunner::ok(".git"),
+/// )]));
+/// let repo = git::with_runner(runner.clone(), || Repo::new(PathBuf::from("."), PathBuf::from(".git")));
+/// let git_dir = repo.rev_parse("--git-dir");
+/// assert_eq!(git_dir.unwrap(), ".git");
+/// ```
+pub fn with_runner<T, F: FnOnce() -> T>(runner: Rc<dyn GitRunner>, f: F) -> T {
+    let previous = RUNNER.with(|r| r.replace(Some(runner)));
+    let _restore = RestoreRunner(previous);
+    f()
+}
+
+/// Returns the runner of the current thread: the one set by [`with_runner`],
+/// or [`ProcessRunner`].
+fn current_runner() -> Rc<dyn GitRunner> {
+    match RUNNER.with(|r| r.borrow().clone()) {
+        Some(runner) => runner,
+        None => Rc::new(ProcessRunner),
+    }
+}
+
+/// Calls `f` with the runner of the current thread (see [`current_runner`]).
+fn with_current_runner<T, F: FnOnce(&dyn GitRunner) -> T>(f: F) -> T {
+    f(current_runner().as_ref())
+}
+
+/// Variables from the user's environment that would silently change what a
+/// rewrite does: the identity and dates Git records, the todo list editor,
+/// and the repository and index it works on. Every command runs without them;
+/// the tool sets the ones it needs itself.
+const INHERITED_ENV: [&str; 10] = [
+    "GIT_AUTHOR_NAME",
+    "GIT_AUTHOR_EMAIL",
+    "GIT_AUTHOR_DATE",
+    "GIT_COMMITTER_NAME",
+    "GIT_COMMITTER_EMAIL",
+    "GIT_COMMITTER_DATE",
+    "GIT_SEQUENCE_EDITOR",
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_INDEX_FILE",
+];
+
+/// Removes from `cmd` each variable of [`INHERITED_ENV`] that `is_set`
+/// reports as set, so unset ones do not clutter `--show-commands`.
+fn clear_inherited_env(cmd: &mut GitCommand, is_set: impl Fn(&str) -> bool) {
+    for key in INHERITED_ENV {
+        if is_set(key) {
+            cmd.env_remove(key);
+        }
+    }
+}
+
+/// Starts a `git` command. Every Git invocation is built here, so that
+/// [`trace`] sees all of them, and none inherits [`INHERITED_ENV`].
+fn git_command() -> GitCommand {
+    let mut cmd = GitCommand::new();
+    clear_inherited_env(&mut cmd, |key| env::var_os(key).is_some());
+    cmd
+}
+
+/// Sets or removes the environment variables of `env` on `cmd`.
+fn apply_env(cmd: &mut GitCommand, env: &RepoEnv) {
+    for (key, value) in env {
+        match value {
+            Some(v) => {
+                cmd.env(key, v);
+            }
+            None => {
+                cmd.env_remove(key);
+            }
+        }
+    }
+}
+
+/// Formats `cmd` as a shell command line, preceded by the environment
+/// variables it sets (or `env -u` for the ones it removes).
+fn command_line(cmd: &GitCommand) -> String {
+    let mut words = Vec::new();
+    for (key, value) in &cmd.envs {
+        let key = key.to_string_lossy();
+        match value {
+            Some(v) => words.push(format!("{}={}", key, shell_quote(&v.to_string_lossy()))),
+            None => words.push(format!("env -u {}", key)),
+        }
+    }
+    words.push(shell_quote(&cmd.program.to_string_lossy()));
+    for arg in &cmd.args {
+        words.push(shell_quote(&arg.to_string_lossy()));
+    }
+    words.join(" ")
+}
+
+/// Prints `cmd` to stderr if `--show-commands` is on.
+fn trace(cmd: &GitCommand) {
+    if SHOW_COMMANDS.load(Ordering::Relaxed) {
+        eprintln!("+ {}", command_line(cmd));
+    }
+}
+
+/// Quotes a single word for the shell that Git uses to run editors.
 ///
-/// Wraps `exe_path` in quotes if it contains spaces or quotes, escaping any
+/// Wraps `word` in double quotes if it contains spaces or quotes, escaping any
 /// embedded double quotes with backslashes.
+fn shell_quote(word: &str) -> String {
+    let needs_quoting = word.contains(' ') || word.contains('"');
+
+    if needs_quoting {
+        let escaped = word.replace('"', "\\\"");
+        format!("\"{}\"", escaped)
+    } else {
+        word.to_string()
+    }
+}
+
+/// Builds the value for the `GIT_SEQUENCE_EDITOR` environment variable.
+///
+/// Wraps `exe_path` (and each of `extra_args`) in quotes if it contains spaces
+/// or quotes, escaping any embedded double quotes with backslashes. Git appends
+/// the todo file path after `extra_args` when it runs the editor.
 ///
 /// # Examples
 ///
 /// ```ignore
 /// let path = "/usr/local/bin/git-author-rewrite";
 /// assert_eq!(
-///     build_sequence_editor_env(path),
+///     build_sequence_editor_env(path, &[]),
 ///     "/usr/local/bin/git-author-rewrite --sequence-editor"
 /// );
 ///
 /// let path_with_space = "/path/with space/git-author-rewrite";
 /// assert_eq!(
-///     build_sequence_editor_env(path_with_space),
+///     build_sequence_editor_env(path_with_space, &[]),
 ///     "\"/path/with space/git-author-rewrite\" --sequence-editor"
 /// );
 /// ```
-pub(crate) fn build_sequence_editor_env(exe_path: &str) -> String {
-    let needs_quoting = exe_path.contains(' ') || exe_path.contains('"');
+pub(crate) fn build_sequence_editor_env(exe_path: &str, extra_args: &[String]) -> String {
+    let mut words = vec![shell_quote(exe_path), String::from("--sequence-editor")];
+    for arg in extra_args {
+        words.push(shell_quote(arg));
+    }
 
-    let quoted = if needs_quoting {
-        let escaped = exe_path.replace('"', "\\\"");
-        format!("\"{}\"", escaped)
-    } else {
-        exe_path.to_string()
-    };
+    words.join(" ")
+}
+
+/// Quotes a word for a POSIX shell with single quotes, which keep every
+/// character (including backslashes) literal.
+pub(crate) fn sh_single_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
 
-    format!("{quoted} --sequence-editor")
+/// Builds the shell script that runs this executable as the sequence editor.
+///
+/// Git for Windows runs `GIT_SEQUENCE_EDITOR` through its bundled `sh`, where
+/// a backslash path with spaces in the variable itself is easily mangled. A
+/// script holding the command line instead only has to be named by a simple
+/// path; inside it every word is single-quoted and backslashes become
+/// forward slashes, which the bundled shell understands. Git's todo file
+/// path is passed on as `"$@"`.
+pub(crate) fn sequence_editor_script(exe_path: &str, extra_args: &[String]) -> String {
+    let mut words = vec![
+        sh_single_quote(&exe_path.replace('\\', "/")),
+        String::from("--sequence-editor"),
+    ];
+    for arg in extra_args {
+        words.push(sh_single_quote(arg));
+    }
+    format!("#!/bin/sh\nexec {} \"$@\"\n", words.join(" "))
+}
+
+/// Writes the [`sequence_editor_script`] into `dir`, named after this process.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` with the path of the script.
+/// * `Err(String)` if it could not be written.
+fn write_sequence_editor_script(
+    dir: &Path,
+    exe_path: &str,
+    extra_args: &[String],
+) -> Result<PathBuf, String> {
+    let path = dir.join(format!(
+        "git-author-rewrite-{}-sequence-editor.sh",
+        process::id()
+    ));
+    if let Err(e) = fs::write(&path, sequence_editor_script(exe_path, extra_args)) {
+        return Err(format!("cannot write {}: {}", path.display(), e));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = fs::set_permissions(&path, fs::Permissions::from_mode(0o755)) {
+            return Err(format!("cannot make {} executable: {}", path.display(), e));
+        }
+    }
+    Ok(path)
+}
+
+/// Traces `cmd` and runs it with `runner`, its streams inherited unless set.
+fn exit_status(runner: &dyn GitRunner, cmd: &GitCommand) -> Result<GitStatus, String> {
+    trace(cmd);
+    runner.run_status(cmd)
+}
+
+/// Traces `cmd` and runs it with `runner`, its output captured unless set
+/// otherwise.
+fn captured(runner: &dyn GitRunner, cmd: &GitCommand) -> Result<GitOutput, String> {
+    trace(cmd);
+    runner.run_output(cmd)
+}
+
+/// Traces `cmd` and runs it with `runner`, passing each line of its stdout
+/// to `on_line` as it is read (see [`GitRunner::run_lines`]).
+///
+/// # Returns
+///
+/// * `Ok(())` if the command succeeded.
+/// * `Err(String)` with its stderr or the I/O error message otherwise.
+fn streamed(
+    runner: &dyn GitRunner,
+    cmd: &GitCommand,
+    on_line: &mut dyn FnMut(&[u8]),
+) -> Result<(), String> {
+    trace(cmd);
+    match runner.run_lines(cmd, on_line) {
+        Ok(out) if out.status.success() => Ok(()),
+        Ok(out) => Err(out.error_text()),
+        Err(e) => Err(e),
+    }
 }
 
 /// Runs a Git (or other) command and returns only its exit status.
 ///
-/// This function executes the provided [`std::process::Command`] and:
+/// This function executes the provided [`GitCommand`] and:
 /// - Returns `Ok(())` if the command exits successfully (status code `0`).
 /// - Returns `Err("non-zero exit")` if the command exits with a non-zero status.
 /// - Returns `Err` containing the I/O error message if the process fails to start.
 ///
 /// # Parameters
 ///
-/// * `cmd` — A fully configured [`std::process::Command`] to run.
+/// * `cmd` — A fully configured [`GitCommand`] to run.
 ///
 /// # Returns
 ///
@@ -53,18 +302,15 @@ pub(crate) fn build_sequence_editor_env(exe_path: &str) -> String {
 /// # Examples
 ///
 /// ```ignore
-/// use std::process::Command;
-///
-/// let cmd = Command::new("git").arg("status");
+/// let mut cmd = git_command();
+/// cmd.arg("status");
 /// match run_status(cmd) {
 ///     Ok(()) => println!("Git command succeeded"),
 ///     Err(e) => eprintln!("Git command failed: {}", e),
 /// }
 /// ```
-fn run_status(mut cmd: Command) -> Result<(), String> {
-    let status_res = cmd.status();
-
-    match status_res {
+fn run_status(runner: &dyn GitRunner, cmd: GitCommand) -> Result<(), String> {
+    match exit_status(runner, &cmd) {
         Ok(status) => {
             if status.success() {
                 Ok(())
@@ -72,136 +318,150 @@ fn run_status(mut cmd: Command) -> Result<(), String> {
                 Err(String::from("non-zero exit"))
             }
         }
-        Err(e) => Err(format!("{}", e)),
+        Err(e) => Err(e),
     }
 }
 
-/// Runs a command and returns its trimmed standard output on success,  
-/// or its standard error as an `Err` on failure.
-///
-/// This function executes the provided [`std::process::Command`] and:
-/// - If the command exits with a zero status, its `stdout` is captured,
-///   converted to UTF-8 (lossy), trimmed, and returned as `Ok(String)`.
-/// - If the command exits non-zero, its `stderr` is captured,
-///   converted to UTF-8 (lossy), trimmed, and returned as `Err(String)`.
-/// - If the process fails to spawn, the I/O error message is returned as `Err(String)`.
+/// Runs a command, capturing its output unless its streams are set otherwise.
 ///
 /// # Parameters
 ///
-/// * `cmd` — A fully configured [`std::process::Command`] ready to execute.
+/// * `cmd` — A fully configured [`GitCommand`] ready to execute.
 ///
 /// # Returns
 ///
-/// * `Ok(String)` containing trimmed `stdout` if the command succeeded.
-/// * `Err(String)` containing trimmed `stderr` or I/O error message otherwise.
+/// * `Ok(GitOutput)` with everything the command printed if it succeeded,
+///   including any warnings on stderr.
+/// * `Err(String)` with its stderr and stdout (see [`GitOutput::error_text`])
+///   or the I/O error message otherwise.
 ///
 /// # Examples
 ///
 /// ```ignore
 /// // This example is illustrative only; it won't run in doctests because
 /// // this function is crate-private and may depend on environment state.
-/// use std::process::Command;
-/// let cmd = Command::new("git").arg("rev-parse").arg("--show-toplevel");
-/// match run_output(cmd) {
-///     Ok(path) => println!("Repo root: {}", path),
+/// let mut cmd = git_command();
+/// cmd.arg("gc").arg("--auto");
+/// match run_output(runner, cmd) {
+///     Ok(out) => eprintln!("{}", out.stderr_text()),
 ///     Err(err) => eprintln!("Git error: {}", err),
 /// }
 /// ```
-fn run_output(mut cmd: Command) -> Result<String, String> {
-    let out_res = cmd.output();
-    match out_res {
+fn run_output(runner: &dyn GitRunner, cmd: GitCommand) -> Result<GitOutput, String> {
+    match captured(runner, &cmd) {
         Ok(out) => {
             if out.status.success() {
-                Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+                Ok(out)
             } else {
-                Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+                Err(out.error_text())
             }
         }
-        Err(e) => Err(format!("{}", e)),
+        Err(e) => Err(e),
     }
 }
 
-/// Runs `git rev-parse <flag>` and returns its output as a trimmed string.
-///
-/// This is a convenience wrapper around `git rev-parse` that captures `stdout`
-/// or returns `stderr` as an error. It is typically used to query repository
-/// metadata such as the repository root or `.git` directory path.
-///
-/// # Parameters
-///
-/// * `flag` — The argument to pass to `git rev-parse`, e.g. `--show-toplevel`
-///   or `--git-dir`.
-///
-/// # Returns
-///
-/// * `Ok(String)` containing the trimmed standard output if the command
-///   completed successfully.
-/// * `Err(String)` containing the trimmed standard error or an I/O error message
-///   if the command failed.
+/// Runs a command and returns its trimmed standard output on success,  
+/// or what it printed as an `Err` on failure (see [`run_output`]).
 ///
 /// # Examples
 ///
 /// ```ignore
-/// // This example is ignored because it depends on being inside a Git repository.
-/// use git_author_rewrite::git::rev_parse;
-///
-/// match rev_parse("--show-toplevel") {
-///     Ok(path) => println!("Repository root: {}", path),
+/// // This example is illustrative only; it won't run in doctests because
+/// // this function is crate-private and may depend on environment state.
+/// let mut cmd = git_command();
+/// cmd.arg("rev-parse").arg("--show-toplevel");
+/// match run_text(runner, cmd) {
+///     Ok(path) => println!("Repo root: {}", path),
 ///     Err(err) => eprintln!("Git error: {}", err),
 /// }
 /// ```
-pub fn rev_parse(flag: &str) -> Result<String, String> {
-    let mut cmd = Command::new("git");
-    cmd.arg("rev-parse").arg(flag);
-    cmd.stdout(Stdio::piped());
-    cmd.stderr(Stdio::piped());
-    run_output(cmd)
+fn run_text(runner: &dyn GitRunner, cmd: GitCommand) -> Result<String, String> {
+    match run_output(runner, cmd) {
+        Ok(out) => Ok(out.stdout_text()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Runs a command with the terminal as stdin and its stdout sent to our
+/// stderr, holding back its stderr: on success it is passed on unchanged
+/// (and returned, in case the caller wants to look at it), on failure it
+/// becomes the error (after `failure`), so the caller can explain it.
+fn run_keeping_stderr(
+    runner: &dyn GitRunner,
+    mut cmd: GitCommand,
+    failure: &str,
+) -> Result<GitOutput, String> {
+    cmd.stdin(Io::Inherit);
+    cmd.stdout(Io::Stderr);
+    cmd.stderr(Io::Piped);
+    match captured(runner, &cmd) {
+        Ok(out) if out.status.success() => {
+            eprint!("{}", String::from_utf8_lossy(&out.stderr));
+            Ok(out)
+        }
+        Ok(out) => {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            if stderr.trim().is_empty() {
+                Err(failure.to_string())
+            } else {
+                Err(format!("{}:\n{}", failure, stderr.trim_end()))
+            }
+        }
+        Err(e) => Err(format!("{}: {}", failure, e)),
+    }
 }
 
-/// Runs `git config --get <key>` and returns the result as a trimmed string.
+/// Runs a command with `input` on its stdin and returns its raw stdout.
 ///
-/// This function retrieves a Git configuration value for the specified key.
-/// If the key does not exist or the command fails, it returns an empty string
-/// instead of an error.
+/// # Returns
 ///
-/// # Parameters
+/// * `Ok(Vec<u8>)` with the untrimmed stdout if the command succeeded.
+/// * `Err(String)` with what it printed (see [`GitOutput::error_text`]) or
+///   the I/O error message otherwise.
+fn run_with_input(
+    runner: &dyn GitRunner,
+    mut cmd: GitCommand,
+    input: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    cmd.input(input);
+    cmd.stdout(Io::Piped);
+    cmd.stderr(Io::Piped);
+    match captured(runner, &cmd) {
+        Ok(out) if out.status.success() => Ok(out.stdout),
+        Ok(out) => Err(out.error_text()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Runs `git config --global --get <key>` and returns the trimmed value.
 ///
-/// * `key` — The Git configuration key to query (e.g. `"user.name"` or `"user.email"`).
+/// Like [`config_get`], a missing key yields an empty string rather than an
+/// error, but only the user's global configuration is consulted, so a
+/// repository-local override does not hide the global value.
 ///
-/// # Returns
-///
-/// * `Ok(String)` containing the trimmed config value, or an empty string if the key
-///   is missing or the command failed.
-/// * `Err(String)` is never returned — errors are converted into `Ok(String::new())`.
+/// # Parameters
 ///
-/// # Examples
+/// * `key` — The Git configuration key to query (e.g. `"user.name"`).
 ///
-/// ```ignore
-/// // Ignored because it requires a Git repository with a configured user.name.
-/// use git_author_rewrite::git::config_get;
+/// # Returns
 ///
-/// match config_get("user.name") {
-///     Ok(name) if !name.is_empty() => println!("User name: {}", name),
-///     Ok(_) => println!("No user name configured."),
-///     Err(_) => unreachable!(), // This function never returns Err
-/// }
-/// ```
-pub fn config_get(key: &str) -> Result<String, String> {
-    let mut cmd = Command::new("git");
-    cmd.arg("config").arg("--get").arg(key);
-    cmd.stdout(Stdio::piped());
-    cmd.stderr(Stdio::piped());
-    let res = run_output(cmd);
-    match res {
+/// * `Ok(String)` containing the trimmed value, or an empty string if unset.
+pub fn config_get_global(key: &str) -> Result<String, String> {
+    let mut cmd = git_command();
+    cmd.arg("config").arg("--global").arg("--get").arg(key);
+    cmd.stdout(Io::Piped);
+    cmd.stderr(Io::Piped);
+    match with_current_runner(|runner| run_text(runner, cmd)) {
         Ok(s) => Ok(s),
         Err(_) => Ok(String::new()),
     }
 }
 
-/// Sets a Git configuration key to the given value in the local repository.
+/// Sets a Git configuration key in the user's global configuration.
 ///
-/// This function runs `git config <key> <value>` without specifying `--global`,
-/// so the change applies only to the current repository.
+/// This runs `git config --global <key> <value>`, which writes to
+/// `~/.gitconfig` (or `$XDG_CONFIG_HOME/git/config`) and affects every
+/// repository of the current user.
 ///
 /// # Parameters
 ///
@@ -213,263 +473,2507 @@ pub fn config_get(key: &str) -> Result<String, String> {
 /// * `Ok(())` if the configuration was set successfully.
 /// * `Err(String)` containing an error message if the command failed.
 ///
-/// # Notes
-///
-/// This modifies the repository's **local** `.git/config` file.
-/// It does not affect global or system-level configuration.
-///
 /// # Examples
 ///
 /// ```ignore
-/// // Ignored because it requires a Git repository.
-/// use git_author_rewrite::git::config_set;
+/// // Ignored because it modifies the user's global Git configuration.
+/// use git_author_rewrite::git::config_set_global;
 ///
-/// if let Err(err) = config_set("user.name", "Jane Doe") {
-///     eprintln!("Failed to set Git config: {}", err);
-/// }
+/// config_set_global("user.name", "Jane Doe").unwrap();
 /// ```
-pub fn config_set(key: &str, value: &str) -> Result<(), String> {
-    let mut cmd = Command::new("git");
-    cmd.arg("config").arg(key).arg(value);
-    cmd.stdout(Stdio::null());
-    cmd.stderr(Stdio::piped());
-    run_status(cmd)
+pub fn config_set_global(key: &str, value: &str) -> Result<(), String> {
+    let mut cmd = git_command();
+    cmd.arg("config").arg("--global").arg(key).arg(value);
+    cmd.stdout(Io::Null);
+    cmd.stderr(Io::Piped);
+    with_current_runner(|runner| run_status(runner, cmd))
 }
 
-/// Runs an interactive rebase from the root commit, optionally auto-marking all commits for editing.
+/// Sets a Git configuration key in the config file at `path`.
 ///
-/// Internally, this executes:
-///
-/// ```text
-/// git rebase -i --root
-/// ```
-///
-/// If `auto_mark_all` is `true`, the `GIT_SEQUENCE_EDITOR` environment variable is set
-/// so that every `pick` line in the rebase todo list is replaced with `edit` automatically.
-/// This allows for non-interactive author rewriting across all commits.
+/// This runs `git config --file <path> <key> <value>`, creating the file if
+/// it does not exist yet.
 ///
 /// # Parameters
 ///
-/// * `auto_mark_all` – If `true`, configure `GIT_SEQUENCE_EDITOR` to mark all commits as `edit`.
-///   If `false`, the user will manually choose which commits to edit in their editor.
+/// * `path` — The config file to write.
+/// * `key` — The Git configuration key to set (e.g. `"user.name"`).
+/// * `value` — The value to assign to the configuration key.
 ///
 /// # Returns
 ///
-/// * `Ok(())` if the command ran successfully.
-/// * `Err(String)` if the executable could not be located or if `git rebase` exited with a non-zero status.
+/// * `Ok(())` if the configuration was set successfully.
+/// * `Err(String)` containing an error message if the command failed.
+pub fn config_set_file(path: &Path, key: &str, value: &str) -> Result<(), String> {
+    let mut cmd = git_command();
+    cmd.arg("config").arg("--file").arg(path).arg(key).arg(value);
+    cmd.stdout(Io::Null);
+    cmd.stderr(Io::Piped);
+    with_current_runner(|runner| run_status(runner, cmd))
+}
+
+/// Lists global configuration entries whose keys match `pattern`.
+///
+/// Runs `git config --global --null --get-regexp <pattern>`. Each entry is
+/// printed as the key, a newline, the value and a NUL byte, so values may
+/// contain any character.
+///
+/// # Parameters
+///
+/// * `pattern` — A regular expression matched against the full key names.
 ///
-/// # Notes
+/// # Returns
 ///
-/// * This command modifies commit history; it should be run only on branches
-///   where rewriting is safe.
-/// * The process inherits standard input/output/error so the user can interact with Git normally.
-/// * Requires the current working directory to be inside a Git repository.
+/// * `Ok(String)` with the raw output, empty if no key matches.
+/// * `Err(String)` if Git could not read the configuration.
 ///
 /// # Examples
 ///
 /// ```ignore
-/// // Ignored because it requires a Git repository.
-/// use git_author_rewrite::git::rebase_interactive;
+/// // Ignored because it reads the user's global Git configuration.
+/// use git_author_rewrite::git::config_get_regexp_global;
 ///
-/// // Automatically mark all commits for editing
-/// if let Err(err) = rebase_interactive(true) {
-///     eprintln!("Rebase failed: {}", err);
-/// }
+/// let raw = config_get_regexp_global("^user\\.").unwrap_or_default();
 /// ```
-pub fn rebase_interactive(auto_mark_all: bool) -> Result<(), String> {
-    let mut cmd = Command::new("git");
-    cmd.arg("rebase").arg("-i").arg("--root");
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-
-    if auto_mark_all {
-        let exe_res = std::env::current_exe();
-        match exe_res {
-            Ok(path) => {
-                // Quote the path if it contains spaces to avoid shell parsing issues.
-                let p = path.to_string_lossy();
-                let se = build_sequence_editor_env(&p);
-
-                cmd.env("GIT_SEQUENCE_EDITOR", se);
-            }
-            Err(e) => {
-                return Err(format!("cannot locate current executable: {}", e));
+pub fn config_get_regexp_global(pattern: &str) -> Result<String, String> {
+    let mut cmd = git_command();
+    cmd.arg("config")
+        .arg("--global")
+        .arg("--null")
+        .arg("--get-regexp")
+        .arg(pattern);
+    cmd.stdout(Io::Piped);
+    cmd.stderr(Io::Piped);
+    match with_current_runner(|runner| captured(runner, &cmd)) {
+        Ok(out) => {
+            if out.status.success() {
+                Ok(String::from_utf8_lossy(&out.stdout).to_string())
+            } else if out.status.code() == Some(1) {
+                // Exit status 1 means no key matched.
+                Ok(String::new())
+            } else {
+                Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
             }
         }
+        Err(e) => Err(e),
     }
-
-    run_status(cmd).map_err(|_| String::from("`git rebase -i --root` exited with non-zero status"))
 }
 
-/// Amends the current commit to set a new author without changing the commit message.
-///
-/// This runs:
-///
-/// ```text
-/// git commit --amend --author="<author>" --no-edit
-/// ```
-///
-/// The `--no-edit` flag ensures that the commit message remains unchanged.
-/// Standard input, output, and error are inherited so the command can prompt
-/// the user or show Git's output directly.
+/// Removes a section (e.g. `author-rewrite.profile.work`) from the global configuration.
 ///
 /// # Parameters
 ///
-/// * `author` – A full author string in the format `"Name <email@example.com>"`.
+/// * `section` — The section name, including any subsection.
 ///
 /// # Returns
 ///
-/// * `Ok(())` if the commit was successfully amended.
-/// * `Err(String)` if the Git command failed or exited with a non-zero status.
+/// * `Ok(())` if the section was removed.
+/// * `Err(String)` with Git's error message otherwise, including when the
+///   section does not exist.
+pub fn config_remove_section_global(section: &str) -> Result<(), String> {
+    let mut cmd = git_command();
+    cmd.arg("config")
+        .arg("--global")
+        .arg("--remove-section")
+        .arg(section);
+    cmd.stdout(Io::Null);
+    cmd.stderr(Io::Piped);
+    with_current_runner(|runner| run_status(runner, cmd))
+}
+
+/// Runs a commit or rebase step, passing its output through unless
+/// `options.quiet` asks for it to be captured; then only the returned
+/// [`GitOutput`] has Git's hints and warnings.
+fn run_step(
+    runner: &dyn GitRunner,
+    cmd: GitCommand,
+    options: CommitOptions,
+    failure: &str,
+) -> Result<GitOutput, String> {
+    if options.quiet {
+        return match run_output(runner, cmd) {
+            Ok(out) => Ok(out),
+            Err(e) if e.is_empty() => Err(failure.to_string()),
+            Err(e) => Err(format!("{}: {}", failure, e)),
+        };
+    }
+    run_keeping_stderr(runner, cmd, failure)
+}
+
+/// How the commits re-created during the rebase are made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitOptions {
+    /// Whether `git commit --amend` runs the pre-commit and commit-msg hooks.
+    pub run_hooks: bool,
+    /// Overrides `commit.gpgsign` for the run (`-c commit.gpgsign=<bool>`),
+    /// or `None` to follow the configuration.
+    pub sign: Option<bool>,
+    /// Capture Git's output instead of passing it through, so it cannot draw
+    /// over `--tui` or break `--quiet`. Failures then carry Git's error output.
+    pub quiet: bool,
+}
+
+impl Default for CommitOptions {
+    fn default() -> Self {
+        CommitOptions {
+            run_hooks: true,
+            sign: None,
+            quiet: false,
+        }
+    }
+}
+
+/// Who edits the rebase todo list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoEditor<'a> {
+    /// This executable in `--sequence-editor` mode, with extra arguments
+    /// (e.g. `["--select", "<file>"]` to mark only some commits).
+    Auto(&'a [String]),
+    /// The user, in the given editor command (e.g. `code --wait`).
+    Command(&'a str),
+    /// The user, in the editor Git would pick (`GIT_SEQUENCE_EDITOR`,
+    /// `sequence.editor`, `core.editor`, ...).
+    Default,
+}
+
+/// Which commits a rebase re-creates, and onto what.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseRange<'a> {
+    /// Every commit reachable from `HEAD`, from the root (`--root`).
+    Root,
+    /// The commits after `upstream` up to `HEAD`, replayed onto `onto`
+    /// (`--onto <onto> <upstream>`).
+    Onto {
+        onto: &'a str,
+        upstream: &'a str,
+    },
+}
+
+/// The `git log` format read by [`Commit::parse`]: one commit per line,
+/// fields separated by NUL bytes, the subject last.
+pub const COMMIT_FORMAT: &str = "%H%x00%an%x00%ae%x00%cn%x00%ce%x00%at%x00%ct%x00%s";
+
+/// One commit as listed by [`Repo::commits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    /// The full commit SHA.
+    pub sha: String,
+    pub author: Identity,
+    pub committer: Identity,
+    /// The author date in Unix seconds.
+    pub author_time: i64,
+    /// The committer date in Unix seconds.
+    pub commit_time: i64,
+    /// The first line of the commit message.
+    pub subject: String,
+}
+
+impl Commit {
+    /// Parses one line of [`COMMIT_FORMAT`] output.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the line has too few fields or a date is not a number.
+    pub fn parse(line: &str) -> Option<Commit> {
+        let fields: Vec<&str> = line.splitn(8, '\0').collect();
+        if fields.len() < 8 || fields[0].is_empty() {
+            return None;
+        }
+        let author_time = match fields[5].parse() {
+            Ok(t) => t,
+            Err(_) => return None,
+        };
+        let commit_time = match fields[6].parse() {
+            Ok(t) => t,
+            Err(_) => return None,
+        };
+
+        Some(Commit {
+            sha: fields[0].to_string(),
+            author: Identity::new(fields[1], fields[2]),
+            committer: Identity::new(fields[3], fields[4]),
+            author_time,
+            commit_time,
+            subject: fields[7].to_string(),
+        })
+    }
+}
+
+/// Parses `git log` output in [`COMMIT_FORMAT`], skipping lines that do not
+/// parse.
+pub fn parse_log(log: &str) -> Vec<Commit> {
+    log.lines().filter_map(Commit::parse).collect()
+}
+
+/// One working tree of a repository, as listed by [`Repo::worktrees`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    /// The top of the working tree (the repository itself if it is bare).
+    pub path: PathBuf,
+    /// The branch checked out (e.g. `refs/heads/main`), or `None` if `HEAD`
+    /// is detached or the repository is bare.
+    pub branch: Option<String>,
+}
+
+/// One local branch, as listed by [`Repo::local_branches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    /// The short name, e.g. `main`.
+    pub name: String,
+    /// Whether `HEAD` points at it.
+    pub current: bool,
+    /// The upstream it tracks, e.g. `origin/main`, if any.
+    pub upstream: Option<String>,
+    /// Commits on the branch that its upstream does not have.
+    pub ahead: usize,
+    /// Commits on the upstream that the branch does not have.
+    pub behind: usize,
+    /// Whether the upstream no longer exists (e.g. deleted on the remote).
+    pub upstream_gone: bool,
+}
+
+/// Reads the count after `word` in `%(upstream:track,nobracket)` output,
+/// e.g. 2 for `ahead` in `ahead 2, behind 1`.
+fn track_count(track: &str, word: &str) -> usize {
+    track
+        .split(", ")
+        .find_map(|part| part.strip_prefix(word))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Parses the output of [`Repo::local_branches`]' `git for-each-ref`: one
+/// line per branch with `%(HEAD)`, the short name, the upstream and its
+/// tracking state, separated by NUL bytes.
+pub(crate) fn parse_branches(out: &str) -> Vec<Branch> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let head = match fields.next() {
+                Some(f) => f,
+                None => return None,
+            };
+            let name = match fields.next() {
+                Some(f) => f,
+                None => return None,
+            };
+            let upstream = fields.next().unwrap_or_default();
+            let track = fields.next().unwrap_or_default();
+            Some(Branch {
+                name: name.to_string(),
+                current: head == "*",
+                upstream: if upstream.is_empty() {
+                    None
+                } else {
+                    Some(upstream.to_string())
+                },
+                ahead: track_count(track, "ahead "),
+                behind: track_count(track, "behind "),
+                upstream_gone: track == "gone",
+            })
+        })
+        .collect()
+}
+
+/// Parses `git worktree list --porcelain` output: one block per worktree,
+/// separated by blank lines, each starting with `worktree <path>`.
+pub(crate) fn parse_worktrees(out: &str) -> Vec<Worktree> {
+    let mut worktrees: Vec<Worktree> = Vec::new();
+    for line in out.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push(Worktree {
+                path: PathBuf::from(path),
+                branch: None,
+            });
+        } else if let (Some(branch), Some(last)) =
+            (line.strip_prefix("branch "), worktrees.last_mut())
+        {
+            last.branch = Some(branch.to_string());
+        }
+    }
+    worktrees
+}
+
+/// Environment variables that point Git at a repository instead of looking
+/// for one around the current directory.
+const REPO_ENV: [&str; 2] = ["GIT_DIR", "GIT_WORK_TREE"];
+
+/// Environment overrides for a repository's commands: a value to set, or
+/// `None` to remove the variable.
+type RepoEnv = Vec<(&'static str, Option<PathBuf>)>;
+
+/// A Git repository: its working tree, its git directory and the
+/// [`GitRunner`] its commands go through.
+///
+/// Every command runs in [`root`](Repo::root), so a `Repo` works the same
+/// whatever the process's current directory is; the files Git keeps during a
+/// rebase are read from [`git_dir`](Repo::git_dir), which is the worktree's
+/// own in a linked worktree.
+#[derive(Clone)]
+pub struct Repo {
+    root: PathBuf,
+    git_dir: PathBuf,
+    runner: Rc<dyn GitRunner>,
+    /// `GIT_DIR`/`GIT_WORK_TREE` as its commands get them: pinned to the
+    /// absolute paths when the repository was found through them, removed
+    /// when it was opened by path while they are set.
+    env: RepoEnv,
+    /// The OpenPGP key commits are signed with, overriding `user.signingkey`
+    /// for the commands that make commits (see [`Repo::with_signing_key`]).
+    signing_key: Option<String>,
+}
+
+impl fmt::Debug for Repo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Repo")
+            .field("root", &self.root)
+            .field("git_dir", &self.git_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Repo {
+    /// Repositories are equal when they are at the same paths, whatever
+    /// their runners.
+    fn eq(&self, other: &Repo) -> bool {
+        self.root == other.root && self.git_dir == other.git_dir
+    }
+}
+
+impl Eq for Repo {}
+
+impl Repo {
+    /// Finds the repository around the current directory (see [`Repo::open`]),
+    /// or the one `GIT_DIR` and `GIT_WORK_TREE` name, as Git would.
+    ///
+    /// When they are set (even as relative paths, or with `core.worktree` in
+    /// the repository's config), every command of the returned `Repo` gets
+    /// them as absolute paths, so running in [`root`](Repo::root) finds the
+    /// same repository.
+    pub fn discover() -> Result<Repo, String> {
+        let given: RepoEnv = REPO_ENV
+            .iter()
+            .filter_map(|key| match env::var_os(key) {
+                Some(v) if !v.is_empty() => Some((*key, Some(PathBuf::from(v)))),
+                _ => None,
+            })
+            .collect();
+        if given.is_empty() {
+            return Repo::open(Path::new("."));
+        }
+        match Repo::locate(Path::new("."), given) {
+            Ok(repo) => Ok(repo.pinned()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Finds the repository containing `dir`. Its commands go through the
+    /// current thread's runner (see [`with_runner`]). `GIT_DIR` and
+    /// `GIT_WORK_TREE` are ignored, since they name some other repository.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Repo)` – The repository; for a bare one, the root is the git
+    ///   directory.
+    /// * `Err(String)` if `dir` is not inside a repository.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    /// use std::path::Path;
+    ///
+    /// let repo = Repo::open(Path::new("../other-project")).unwrap();
+    /// println!("{}", repo.root().display());
+    /// ```
+    pub fn open(dir: &Path) -> Result<Repo, String> {
+        let cleared: RepoEnv = REPO_ENV
+            .iter()
+            .filter(|key| env::var_os(key).is_some())
+            .map(|key| (*key, None))
+            .collect();
+        Repo::locate(dir, cleared)
+    }
+
+    /// Finds the repository Git sees from `dir` with the environment
+    /// overrides `env`, which its commands keep.
+    fn locate(dir: &Path, env: RepoEnv) -> Result<Repo, String> {
+        let runner = current_runner();
+        let mut cmd = git_command();
+        apply_env(&mut cmd, &env);
+        cmd.current_dir(dir)
+            .arg("rev-parse")
+            .arg("--absolute-git-dir");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        let git_dir = match run_text(runner.as_ref(), cmd) {
+            Ok(s) => PathBuf::from(s),
+            Err(e) => return Err(format!("not inside a git repo ({})", e)),
+        };
+
+        let mut cmd = git_command();
+        apply_env(&mut cmd, &env);
+        cmd.current_dir(dir).arg("rev-parse").arg("--show-toplevel");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        let root = match run_text(runner.as_ref(), cmd) {
+            Ok(s) if !s.is_empty() => PathBuf::from(s),
+            // A bare repository has no working tree.
+            _ => git_dir.clone(),
+        };
+        Ok(Repo {
+            root,
+            git_dir,
+            runner,
+            env,
+            signing_key: None,
+        })
+    }
+
+    /// Returns the same repository with `GIT_DIR` (and, unless it is bare,
+    /// `GIT_WORK_TREE`) set to its absolute paths for every command.
+    fn pinned(self) -> Repo {
+        let mut env: RepoEnv = vec![("GIT_DIR", Some(self.git_dir.clone()))];
+        if self.root != self.git_dir {
+            env.push(("GIT_WORK_TREE", Some(self.root.clone())));
+        }
+        Repo { env, ..self }
+    }
+
+    /// Describes the repository at known paths without checking them, with
+    /// the current thread's runner; e.g. for tests.
+    pub fn new(root: PathBuf, git_dir: PathBuf) -> Repo {
+        Repo {
+            root,
+            git_dir,
+            runner: current_runner(),
+            env: Vec::new(),
+            signing_key: None,
+        }
+    }
+
+    /// Returns the same repository with its commands going through `runner`.
+    pub fn using(self, runner: Rc<dyn GitRunner>) -> Repo {
+        Repo { runner, ..self }
+    }
+
+    /// Returns the same repository with `key` set to `value` for every
+    /// command, e.g. `GIT_LFS_SKIP_SMUDGE=1` for the length of a rewrite.
+    pub fn with_env(self, key: &'static str, value: &str) -> Repo {
+        let mut env = self.env;
+        env.retain(|(k, _)| *k != key);
+        env.push((key, Some(PathBuf::from(value))));
+        Repo { env, ..self }
+    }
+
+    /// Returns the same repository signing the commits it makes with `key`
+    /// (`-c user.signingkey=<key>`) instead of the configured key.
+    pub fn with_signing_key(self, key: &str) -> Repo {
+        Repo {
+            signing_key: Some(key.to_string()),
+            ..self
+        }
+    }
+
+    /// The signing key set with [`Repo::with_signing_key`], if any.
+    pub fn signing_key(&self) -> Option<&str> {
+        self.signing_key.as_deref()
+    }
+
+    /// Returns the top of the working tree.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the git directory, as an absolute path.
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    fn runner(&self) -> &dyn GitRunner {
+        self.runner.as_ref()
+    }
+
+    /// Returns the directory Git runs this repository's hooks from:
+    /// `core.hooksPath` if set, `.git/hooks` otherwise.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` with the absolute path, which may not exist yet.
+    /// * `Err(String)` if Git could not resolve it.
+    pub fn hooks_dir(&self) -> Result<PathBuf, String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-parse").arg("--git-path").arg("hooks");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            // Relative paths are relative to the working tree, where Git ran.
+            Ok(path) => Ok(self.root.join(path)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Starts a `git` command in the working tree.
+    fn command(&self) -> GitCommand {
+        let mut cmd = git_command();
+        apply_env(&mut cmd, &self.env);
+        cmd.current_dir(&self.root);
+        cmd
+    }
+
+    /// Starts a `git` command with the `commit.gpgsign` override from `options`
+    /// and the signing key chosen for this repository, if any.
+    fn commit_command(&self, options: CommitOptions) -> GitCommand {
+        let mut cmd = self.command();
+        if let Some(sign) = options.sign {
+            cmd.arg("-c").arg(format!("commit.gpgsign={}", sign));
+        }
+        if let Some(key) = &self.signing_key {
+            cmd.arg("-c").arg(format!("user.signingkey={}", key));
+        }
+        cmd
+    }
+
+    /// Runs `git rev-parse <flag>` and returns its output as a trimmed string.
+    ///
+    /// This is a convenience wrapper around `git rev-parse` that captures `stdout`
+    /// or returns `stderr` as an error. It is typically used to query repository
+    /// metadata such as the repository root or `.git` directory path.
+    ///
+    /// # Parameters
+    ///
+    /// * `flag` — The argument to pass to `git rev-parse`, e.g. `--show-toplevel`
+    ///   or `--git-dir`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` containing the trimmed standard output if the command
+    ///   completed successfully.
+    /// * `Err(String)` containing the trimmed standard error or an I/O error message
+    ///   if the command failed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // This example is ignored because it depends on being inside a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// match repo.rev_parse("--show-toplevel") {
+    ///     Ok(path) => println!("Repository root: {}", path),
+    ///     Err(err) => eprintln!("Git error: {}", err),
+    /// }
+    /// ```
+    pub fn rev_parse(&self, flag: &str) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-parse").arg(flag);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Runs `git config --get <key>` and returns the result as a trimmed string.
+    ///
+    /// This function retrieves a Git configuration value for the specified key.
+    /// If the key does not exist or the command fails, it returns an empty string
+    /// instead of an error.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` — The Git configuration key to query (e.g. `"user.name"` or `"user.email"`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` containing the trimmed config value, or an empty string if the key
+    ///   is missing or the command failed.
+    /// * `Err(String)` is never returned — errors are converted into `Ok(String::new())`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository with a configured user.name.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// match repo.config_get("user.name") {
+    ///     Ok(name) if !name.is_empty() => println!("User name: {}", name),
+    ///     Ok(_) => println!("No user name configured."),
+    ///     Err(_) => unreachable!(), // This function never returns Err
+    /// }
+    /// ```
+    pub fn config_get(&self, key: &str) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("config").arg("--get").arg(key);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        let res = run_text(self.runner(), cmd);
+        match res {
+            Ok(s) => Ok(s),
+            Err(_) => Ok(String::new()),
+        }
+    }
+
+    /// Reads a Git configuration key from the repository's own config only
+    /// (`git config --local --get <key>`), ignoring the global and system files.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` containing the trimmed value, or an empty string if unset.
+    pub fn config_get_local(&self, key: &str) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("config").arg("--local").arg("--get").arg(key);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(s) => Ok(s),
+            Err(_) => Ok(String::new()),
+        }
+    }
+
+    /// Reads a boolean Git configuration key with `git config --bool --get <key>`.
+    ///
+    /// Git's spellings (`yes`/`no`, `on`/`off`, `1`/`0`, ...) are all accepted.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(bool))` with the value.
+    /// * `Ok(None)` if the key is not set.
+    /// * `Err(String)` if the value is not a boolean.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it depends on the repository's configuration.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let sign = repo.config_get_bool("commit.gpgsign").unwrap_or(None).unwrap_or(false);
+    /// ```
+    pub fn config_get_bool(&self, key: &str) -> Result<Option<bool>, String> {
+        let mut cmd = self.command();
+        cmd.arg("config").arg("--bool").arg("--get").arg(key);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(v) => Ok(Some(v == "true")),
+            // A missing key exits non-zero without saying anything.
+            Err(e) if e.is_empty() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads every value of a multi-valued Git configuration key with
+    /// `git config --get-all <key>`, in the order Git lists them.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` with the values; empty if the key is not set.
+    /// * `Err(String)` if the configuration could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it depends on the repository's configuration.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// for command in repo.config_get_all("author-rewrite.exec").unwrap_or_default() {
+    ///     println!("exec {}", command);
+    /// }
+    /// ```
+    pub fn config_get_all(&self, key: &str) -> Result<Vec<String>, String> {
+        let mut cmd = self.command();
+        cmd.arg("config").arg("--get-all").arg(key);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(v) => Ok(v.lines().map(String::from).collect()),
+            // A missing key exits non-zero without saying anything.
+            Err(e) if e.is_empty() => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets a Git configuration key to the given value in the local repository.
+    ///
+    /// This function runs `git config <key> <value>` without specifying `--global`,
+    /// so the change applies only to the current repository.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` — The Git configuration key to set (e.g. `"user.name"`).
+    /// * `value` — The value to assign to the configuration key.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the configuration was set successfully.
+    /// * `Err(String)` containing an error message if the command failed.
+    ///
+    /// # Notes
+    ///
+    /// This modifies the repository's **local** `.git/config` file.
+    /// It does not affect global or system-level configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if let Err(err) = repo.config_set("user.name", "Jane Doe") {
+    ///     eprintln!("Failed to set Git config: {}", err);
+    /// }
+    /// ```
+    pub fn config_set(&self, key: &str, value: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("config").arg(key).arg(value);
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        run_status(self.runner(), cmd)
+    }
+
+    /// Runs an interactive rebase from the root commit, optionally auto-marking commits for editing.
+    ///
+    /// Internally, this executes:
+    ///
+    /// ```text
+    /// git rebase -i --root
+    /// ```
+    ///
+    /// With [`TodoEditor::Auto`], the `GIT_SEQUENCE_EDITOR` environment variable is set
+    /// to this executable in `--sequence-editor` mode, so that `pick` lines in the rebase
+    /// todo list are replaced with `edit` automatically. This allows for non-interactive
+    /// author rewriting. On Windows it names a temporary wrapper script instead
+    /// (see [`sequence_editor_script`]), deleted once the rebase has started. With [`TodoEditor::Command`], it is set to the given command
+    /// for this rebase only.
+    ///
+    /// # Parameters
+    ///
+    /// * `editor` – Who edits the todo list.
+    /// * `options` – How the rebase re-creates commits (only `sign` applies).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the command ran successfully.
+    /// * `Err(String)` if the executable could not be located or if `git rebase` exited with a non-zero status.
+    ///
+    /// # Notes
+    ///
+    /// * This command modifies commit history; it should be run only on branches
+    ///   where rewriting is safe.
+    /// * The process inherits standard input/output/error so the user can interact with Git normally.
+    /// * Requires the current working directory to be inside a Git repository.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::{CommitOptions, Repo, TodoEditor};
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// // Automatically mark all commits for editing
+    /// if let Err(err) = repo.rebase_interactive(TodoEditor::Auto(&[]), CommitOptions::default()) {
+    ///     eprintln!("Rebase failed: {}", err);
+    /// }
+    /// ```
+    pub fn rebase_interactive(
+        &self,
+        editor: TodoEditor,
+        options: CommitOptions,
+    ) -> Result<(), String> {
+        self.rebase_interactive_range(RebaseRange::Root, editor, options)
+    }
+
+    /// Runs an interactive rebase of `range`, like [`Repo::rebase_interactive`]
+    /// does for the whole history.
+    ///
+    /// ```text
+    /// git rebase -i --onto <onto> <upstream>
+    /// ```
+    ///
+    /// A chunked rewrite (`--chunk-size`) rebases one chunk at a time this
+    /// way, with `HEAD` detached at the chunk's last commit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the command ran successfully.
+    /// * `Err(String)` if the executable could not be located or if `git rebase` exited with a non-zero status.
+    pub fn rebase_interactive_range(
+        &self,
+        range: RebaseRange,
+        editor: TodoEditor,
+        options: CommitOptions,
+    ) -> Result<(), String> {
+        let mut cmd = self.commit_command(options);
+        cmd.arg("rebase").arg("-i");
+        let failure = match range {
+            RebaseRange::Root => {
+                cmd.arg("--root");
+                String::from("`git rebase -i --root` exited with non-zero status")
+            }
+            RebaseRange::Onto { onto, upstream } => {
+                cmd.arg("--onto").arg(onto).arg(upstream);
+                format!(
+                    "`git rebase -i --onto {} {}` exited with non-zero status",
+                    onto, upstream
+                )
+            }
+        };
+        let failure = failure.as_str();
+        let mut script = None;
+        match editor {
+            TodoEditor::Auto(extra_args) => {
+                let exe_res = std::env::current_exe();
+                match exe_res {
+                    Ok(path) => {
+                        let p = path.to_string_lossy();
+                        let se = if cfg!(windows) {
+                            let temp = std::env::temp_dir();
+                            match write_sequence_editor_script(&temp, &p, extra_args) {
+                                Ok(s) => {
+                                    let named =
+                                        sh_single_quote(&s.to_string_lossy().replace('\\', "/"));
+                                    script = Some(s);
+                                    named
+                                }
+                                Err(e) => return Err(e),
+                            }
+                        } else {
+                            // Quote the path if it contains spaces to avoid shell parsing issues.
+                            build_sequence_editor_env(&p, extra_args)
+                        };
+
+                        cmd.env("GIT_SEQUENCE_EDITOR", se);
+                    }
+                    Err(e) => {
+                        return Err(format!("cannot locate current executable: {}", e));
+                    }
+                }
+            }
+            TodoEditor::Command(command) => {
+                cmd.env("GIT_SEQUENCE_EDITOR", command);
+            }
+            // The user's own editor, which git_command() cleared.
+            TodoEditor::Default => {
+                if let Some(editor) = env::var_os("GIT_SEQUENCE_EDITOR") {
+                    cmd.env("GIT_SEQUENCE_EDITOR", editor);
+                }
+            }
+        }
+
+        // A user's editor gets the terminal to itself.
+        if let TodoEditor::Auto(_) = editor {
+            let result = run_step(self.runner(), cmd, options, failure);
+            // The todo list is only edited as the rebase starts.
+            if let Some(s) = script {
+                let _ = fs::remove_file(s);
+            }
+            return result.map(|_| ());
+        }
+        cmd.stdin(Io::Inherit);
+        cmd.stdout(Io::Inherit);
+        cmd.stderr(Io::Inherit);
+        cmd.interactive();
+        run_status(self.runner(), cmd).map_err(|_| failure.to_string())
+    }
+
+    /// Amends the current commit to set a new author, optionally with a new message.
+    ///
+    /// This runs:
+    ///
+    /// ```text
+    /// git commit --amend --author="<author>" --no-edit
+    /// ```
+    ///
+    /// The `--no-edit` flag ensures that the commit message remains unchanged.
+    /// Standard input, output, and error are inherited so the command can prompt
+    /// the user or show Git's output directly. When `message` is given, it is
+    /// passed on stdin (`-F - --cleanup=verbatim`) and used exactly as is instead.
+    /// Unless `options.run_hooks` is set, `--no-verify` skips the pre-commit and
+    /// commit-msg hooks.
+    ///
+    /// # Parameters
+    ///
+    /// * `author` – A full author string in the format `"Name <email@example.com>"`,
+    ///   or `None` to keep the commit's author exactly as it is.
+    /// * `committer` – The committer to record, passed via `GIT_COMMITTER_NAME` and
+    ///   `GIT_COMMITTER_EMAIL`, or `None` to let Git use the configured user.
+    /// * `message` – The new commit message, or `None` to keep the current one.
+    /// * `options` – Whether hooks run and whether the commit is signed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitOutput)` if the commit was successfully amended; with
+    ///   `options.quiet` (or a new message), its stderr holds any hook output
+    ///   and warnings Git printed.
+    /// * `Err(String)` if the Git command failed or exited with a non-zero status.
+    ///
+    /// # Notes
+    ///
+    /// * Must be run inside a Git repository with a commit to amend.
+    /// * This rewrites history; only use on branches where rewriting is safe.
+    /// * The new author will replace the current commit's author.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::{CommitOptions, Repo};
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let options = CommitOptions::default();
+    /// if let Err(err) = repo.amend_author(Some("John Doe <john@example.com>"), None, None, options) {
+    ///     eprintln!("Failed to amend author: {}", err);
+    /// }
+    /// ```
+    pub fn amend_author(
+        &self,
+        author: Option<&str>,
+        committer: Option<&Identity>,
+        message: Option<Vec<u8>>,
+        options: CommitOptions,
+    ) -> Result<GitOutput, String> {
+        let mut cmd = self.commit_command(options);
+        cmd.arg("commit").arg("--amend");
+        if let Some(a) = author {
+            cmd.arg(format!("--author={}", a));
+        }
+        if !options.run_hooks {
+            cmd.arg("--no-verify");
+        }
+        if let Some(c) = committer {
+            cmd.env("GIT_COMMITTER_NAME", &c.name);
+            cmd.env("GIT_COMMITTER_EMAIL", &c.email);
+        }
+
+        if let Some(msg) = message {
+            cmd.arg("-F").arg("-").arg("--cleanup=verbatim");
+            cmd.input(msg);
+            return match run_output(self.runner(), cmd) {
+                Ok(out) => Ok(out),
+                Err(e) => Err(format!("`git commit --amend` failed: {}", e)),
+            };
+        }
+
+        cmd.arg("--no-edit");
+        run_step(
+            self.runner(),
+            cmd,
+            options,
+            "`git commit --amend` returned non-zero",
+        )
+    }
+
+    /// Continues an in-progress interactive rebase.
+    ///
+    /// This runs:
+    ///
+    /// ```text
+    /// git rebase --continue
+    /// ```
+    ///
+    /// Standard input, output, and error are inherited so that Git can prompt
+    /// the user or display its normal progress messages.
+    ///
+    /// # Parameters
+    ///
+    /// * `options` – How the rebase re-creates commits (only `sign` applies).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitOutput)` if the rebase continued successfully; with
+    ///   `options.quiet`, its stderr holds the hints and warnings Git printed.
+    /// * `Err(String)` if the command failed or exited with a non-zero status.
+    ///
+    /// # Notes
+    ///
+    /// * Must be run inside a Git repository with a rebase in progress.
+    /// * The rebase will continue from the current stop point, applying the next
+    ///   commit in sequence.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository and an active rebase.
+    /// use git_author_rewrite::git::{CommitOptions, Repo};
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if let Err(err) = repo.rebase_continue(CommitOptions::default()) {
+    ///     eprintln!("Failed to continue rebase: {}", err);
+    /// }
+    /// ```
+    pub fn rebase_continue(&self, options: CommitOptions) -> Result<GitOutput, String> {
+        let mut cmd = self.commit_command(options);
+
+        cmd.arg("rebase").arg("--continue");
+        run_step(
+            self.runner(),
+            cmd,
+            options,
+            "`git rebase --continue` returned non-zero",
+        )
+    }
+
+    /// Drops the commit the rebase stopped at and goes on with the next one
+    /// (`--skip-on-conflict`):
+    ///
+    /// ```text
+    /// git rebase --skip
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitOutput)` once the rebase stopped at the next `edit` line or
+    ///   finished.
+    /// * `Err(String)` if Git failed, e.g. because the next commit conflicts
+    ///   too.
+    pub fn rebase_skip(&self, options: CommitOptions) -> Result<GitOutput, String> {
+        let mut cmd = self.commit_command(options);
+        cmd.arg("rebase").arg("--skip");
+        run_step(
+            self.runner(),
+            cmd,
+            options,
+            "`git rebase --skip` returned non-zero",
+        )
+    }
+
+    /// Lists the files with unresolved merge conflicts, e.g. after a rebase
+    /// step failed to apply a commit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` with the paths, empty if there are no conflicts.
+    /// * `Err(String)` if Git could not read the index.
+    pub fn unmerged_paths(&self) -> Result<Vec<String>, String> {
+        let mut cmd = self.command();
+        cmd.arg("diff").arg("--name-only").arg("--diff-filter=U");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(out) => Ok(out.lines().map(str::to_string).collect()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs the configured merge tool on the conflicted files, with the
+    /// terminal attached:
+    ///
+    /// ```text
+    /// git mergetool
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the tool finished; some files may still be unresolved.
+    /// * `Err(String)` if it could not be run or was aborted.
+    pub fn mergetool(&self) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("mergetool");
+        cmd.stdin(Io::Inherit);
+        cmd.stdout(Io::Inherit);
+        cmd.stderr(Io::Inherit);
+        cmd.interactive();
+        match run_status(self.runner(), cmd) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(String::from("`git mergetool` returned non-zero")),
+        }
+    }
+
+    /// Reports whether the index differs from `HEAD`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if there are staged changes.
+    /// * `Err(String)` if Git failed.
+    pub fn has_staged_changes(&self) -> Result<bool, String> {
+        let mut cmd = self.command();
+        cmd.arg("diff").arg("--cached").arg("--name-only").arg("HEAD");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(out) => Ok(!out.is_empty()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Commits the staged resolution of a conflicted rebase step, reusing the
+    /// message, author and author date of `original`:
+    ///
+    /// ```text
+    /// git commit -C <original>
+    /// ```
+    ///
+    /// The rebase is then stopped at the new commit, as at an `edit` line, so
+    /// it can be amended before `git rebase --continue`.
+    ///
+    /// # Parameters
+    ///
+    /// * `original` – The commit that failed to apply.
+    /// * `options` – Whether hooks run and whether the commit is signed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitOutput)` once the commit is made.
+    /// * `Err(String)` if Git refused.
+    pub fn commit_resolution(
+        &self,
+        original: &str,
+        options: CommitOptions,
+    ) -> Result<GitOutput, String> {
+        let mut cmd = self.commit_command(options);
+        cmd.arg("commit").arg("-C").arg(original);
+        if !options.run_hooks {
+            cmd.arg("--no-verify");
+        }
+        run_step(self.runner(), cmd, options, "`git commit -C` returned non-zero")
+    }
+
+    /// Abandons the rebase in progress and restores the original branch.
+    ///
+    /// Internally, this executes:
+    ///
+    /// ```text
+    /// git rebase --abort
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the branch is back where it was.
+    /// * `Err(String)` with Git's error output otherwise.
+    pub fn rebase_abort(&self) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("rebase").arg("--abort");
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("`git rebase --abort` failed: {}", e)),
+        }
+    }
+
+    /// Reports whether `HEAD` points at a commit.
+    ///
+    /// This runs `git rev-parse --verify --quiet HEAD`, which fails in a freshly
+    /// initialized repository whose current branch is still unborn.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if `HEAD` resolves to a commit.
+    /// * `false` if the branch is unborn or the command could not be run.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if !repo.head_exists() {
+    ///     println!("No commits yet.");
+    /// }
+    /// ```
+    pub fn head_exists(&self) -> bool {
+        let mut cmd = self.command();
+        cmd.arg("rev-parse")
+            .arg("--verify")
+            .arg("--quiet")
+            .arg("HEAD");
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Null);
+        run_status(self.runner(), cmd).is_ok()
+    }
+
+    /// Returns the full name of the branch `HEAD` points at (e.g. `refs/heads/main`).
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` with the ref name.
+    /// * `None` if `HEAD` is detached or the command failed.
+    pub fn symbolic_head(&self) -> Option<String> {
+        let mut cmd = self.command();
+        cmd.arg("symbolic-ref").arg("--quiet").arg("HEAD");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Null);
+        run_text(self.runner(), cmd).ok().filter(|s| !s.is_empty())
+    }
+
+    /// Returns the default branch of `remote` as recorded by its
+    /// `refs/remotes/<remote>/HEAD` (e.g. `main`).
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` with the short branch name.
+    /// * `None` if the remote's `HEAD` is not known locally.
+    pub fn remote_head(&self, remote: &str) -> Option<String> {
+        let prefix = format!("refs/remotes/{}/", remote);
+        let mut cmd = self.command();
+        cmd.arg("symbolic-ref")
+            .arg("--quiet")
+            .arg(format!("{}HEAD", prefix));
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Null);
+        match run_text(self.runner(), cmd) {
+            Ok(target) => target.strip_prefix(&prefix).map(String::from),
+            Err(_) => None,
+        }
+    }
+
+    /// Lists the repository's worktrees, the main one first
+    /// (`git worktree list --porcelain`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Worktree>)` on success.
+    /// * `Err(String)` if the list could not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// for wt in repo.worktrees().unwrap() {
+    ///     println!("{} {:?}", wt.path.display(), wt.branch);
+    /// }
+    /// ```
+    pub fn worktrees(&self) -> Result<Vec<Worktree>, String> {
+        let mut cmd = self.command();
+        cmd.arg("worktree").arg("list").arg("--porcelain");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(out) => Ok(parse_worktrees(&out)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Lists the local branches with how far each is ahead of and behind
+    /// its upstream (`git for-each-ref refs/heads`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Branch>)` sorted by name.
+    /// * `Err(String)` if the branches could not be listed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// for b in repo.local_branches().unwrap() {
+    ///     println!("{} +{} -{}", b.name, b.ahead, b.behind);
+    /// }
+    /// ```
+    pub fn local_branches(&self) -> Result<Vec<Branch>, String> {
+        match self.for_each_ref(
+            "%(HEAD)%00%(refname:short)%00%(upstream:short)%00%(upstream:track,nobracket)",
+            "refs/heads",
+        ) {
+            Ok(out) => Ok(parse_branches(&out)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks out the local branch `name` (`git checkout <name> --`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise, e.g. when local
+    ///   changes would be overwritten.
+    pub fn checkout_branch(&self, name: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("checkout").arg("--quiet").arg(name).arg("--");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("cannot check out {}: {}", name, e)),
+        }
+    }
+
+    /// Detaches `HEAD` at the commit `sha` (`git checkout --detach <sha>`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn checkout_detached(&self, sha: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("checkout").arg("--quiet").arg("--detach").arg(sha);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("cannot check out {}: {}", sha, e)),
+        }
+    }
+
+    /// Points `name` at `value` with `git update-ref`, recording `message` in its reflog.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn update_ref(&self, name: &str, value: &str, message: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("update-ref")
+            .arg("-m")
+            .arg(message)
+            .arg(name)
+            .arg(value);
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs `git update-ref --stdin` with the commands in `input`, recording
+    /// `message` in the reflogs. Git applies them as one transaction, so
+    /// either every ref moves or none does.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise, e.g. when a ref
+    ///   no longer has the old value a command expects.
+    pub fn update_refs(&self, input: &str, message: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("update-ref").arg("-m").arg(message).arg("--stdin");
+        match run_with_input(self.runner(), cmd, input.as_bytes().to_vec()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deletes the ref `name` with `git update-ref -d`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn delete_ref(&self, name: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("update-ref").arg("-d").arg(name);
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Lists the full names of refs under `prefix` (e.g. `refs/original/`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` with the ref names, possibly empty.
+    /// * `Err(String)` if the command failed.
+    pub fn list_refs(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut cmd = self.command();
+        cmd.arg("for-each-ref")
+            .arg("--format=%(refname)")
+            .arg(prefix);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(out) => Ok(out.lines().map(|l| l.to_string()).collect()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs `git for-each-ref --format=<format> <prefix>` and returns its output.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with one formatted line per ref, possibly empty.
+    /// * `Err(String)` if the command failed.
+    pub fn for_each_ref(&self, format: &str, prefix: &str) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("for-each-ref")
+            .arg(format!("--format={}", format))
+            .arg(prefix);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Runs `git rev-list --count <args>` and returns the number of commits listed.
+    ///
+    /// The arguments are passed through verbatim, so any revision expression
+    /// understood by `git rev-list` can be used (e.g. `HEAD`, `HEAD --not --remotes`).
+    ///
+    /// # Parameters
+    ///
+    /// * `args` – Revision arguments appended after `git rev-list --count`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` with the number of commits reported by Git.
+    /// * `Err(String)` if the command failed or its output was not a number.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let unpushed = repo.rev_list_count(&["HEAD", "--not", "--remotes"]);
+    /// ```
+    pub fn rev_list_count(&self, args: &[&str]) -> Result<usize, String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-list").arg("--count").args(args);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+
+        match run_text(self.runner(), cmd) {
+            Ok(s) => match s.parse::<usize>() {
+                Ok(n) => Ok(n),
+                Err(e) => Err(format!(
+                    "unexpected `git rev-list --count` output {:?}: {}",
+                    s, e
+                )),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs `git rev-list <args>` and returns the listed commit SHAs.
+    ///
+    /// # Parameters
+    ///
+    /// * `args` – Revision arguments, passed through verbatim.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` with one full SHA per listed commit.
+    /// * `Err(String)` with Git's error message if the command failed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let shas = repo.rev_list(&["main..feature"]).unwrap_or_default();
+    /// ```
+    pub fn rev_list(&self, args: &[&str]) -> Result<Vec<String>, String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-list").args(args);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+
+        match run_text(self.runner(), cmd) {
+            Ok(s) => Ok(s.lines().map(|l| l.to_string()).collect()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Runs `git rev-list <args> --stdin`, feeding `input` on standard input.
+    ///
+    /// Useful for passing more revisions than fit on a command line, e.g. a long
+    /// list of SHAs with `--no-walk`.
+    ///
+    /// # Parameters
+    ///
+    /// * `args` – Revision arguments, passed through verbatim before `--stdin`.
+    /// * `input` – Revisions to feed on standard input, one per line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` with one full SHA per listed commit.
+    /// * `Err(String)` with Git's error message if the command failed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let shas = repo.rev_list_stdin(&["--no-walk"], "abc123\ndef456\n");
+    /// ```
+    pub fn rev_list_stdin(&self, args: &[&str], input: &str) -> Result<Vec<String>, String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-list").args(args).arg("--stdin");
+
+        match run_with_input(self.runner(), cmd, input.as_bytes().to_vec()) {
+            Ok(out) => Ok(String::from_utf8_lossy(&out)
+                .lines()
+                .map(|l| l.to_string())
+                .collect()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the short name of the current branch's upstream (e.g. `origin/main`).
+    ///
+    /// This runs:
+    ///
+    /// ```text
+    /// git rev-parse --abbrev-ref --symbolic-full-name @{upstream}
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the upstream name.
+    /// * `Err(String)` if the branch has no upstream or `HEAD` is detached.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if let Ok(name) = repo.upstream() {
+    ///     println!("Tracking {}", name);
+    /// }
+    /// ```
+    pub fn upstream(&self) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-parse")
+            .arg("--abbrev-ref")
+            .arg("--symbolic-full-name")
+            .arg("@{upstream}");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Lists uncommitted changes to tracked files, as printed by
+    /// `git status --porcelain --untracked-files=no`.
+    ///
+    /// Untracked files are left out, since a rebase does not touch them.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with one line per changed file; empty if the tree is clean.
+    /// * `Err(String)` with Git's error message if the command failed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if !repo.tracked_changes().unwrap_or_default().is_empty() {
+    ///     println!("Commit or stash your changes first.");
+    /// }
+    /// ```
+    pub fn tracked_changes(&self) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("status")
+            .arg("--porcelain")
+            .arg("--untracked-files=no");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Counts the commits `HEAD` and its upstream each have that the other lacks.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((ahead, behind))` on success.
+    /// * `Err(String)` if the branch has no upstream or the output was unexpected.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a branch with an upstream.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let (ahead, behind) = repo.ahead_behind().unwrap();
+    /// println!("{} ahead, {} behind", ahead, behind);
+    /// ```
+    pub fn ahead_behind(&self) -> Result<(usize, usize), String> {
+        let mut cmd = self.command();
+        cmd.arg("rev-list")
+            .arg("--left-right")
+            .arg("--count")
+            .arg("HEAD...@{upstream}");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        let out = match run_text(self.runner(), cmd) {
+            Ok(o) => o,
+            Err(e) => return Err(e),
+        };
+
+        let counts: Vec<&str> = out.split_whitespace().collect();
+        match counts.as_slice() {
+            [ahead, behind] => match (ahead.parse(), behind.parse()) {
+                (Ok(a), Ok(b)) => Ok((a, b)),
+                _ => Err(format!("unexpected rev-list output: {}", out)),
+            },
+            _ => Err(format!("unexpected rev-list output: {}", out)),
+        }
+    }
+
+    /// Runs `git log --format=<format> <args>` and returns the raw output.
+    ///
+    /// Callers choose a format with unambiguous separators (e.g. `%x00`) and parse
+    /// the result themselves; one commit is printed per line.
+    ///
+    /// # Parameters
+    ///
+    /// * `format` – A `git log` pretty format string, without the `--format=` prefix.
+    /// * `args` – Revision arguments, e.g. `["HEAD"]`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the trimmed output.
+    /// * `Err(String)` with Git's error message if the command failed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let out = repo.log("%H%x00%an", &["HEAD"]).unwrap_or_default();
+    /// ```
+    pub fn log(&self, format: &str, args: &[&str]) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("log")
+            .arg(format!("--format={}", format))
+            .args(args);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Lists the commits selected by `args`, newest first, as [`Commit`]s.
+    ///
+    /// # Parameters
+    ///
+    /// * `args` – Revision arguments for `git log`, e.g. `["--topo-order", "HEAD"]`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(impl Iterator<Item = Commit>)` over the listed commits.
+    /// * `Err(String)` with Git's error message if the command failed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// for commit in repo.commits(&["-5", "HEAD"]).unwrap() {
+    ///     println!("{} {} {}", &commit.sha[..7], commit.author, commit.subject);
+    /// }
+    /// ```
+    pub fn commits(&self, args: &[&str]) -> Result<impl Iterator<Item = Commit>, String> {
+        match self.log(COMMIT_FORMAT, args) {
+            Ok(out) => Ok(parse_log(&out).into_iter()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like [`Repo::commits`], but hands each commit to `f` as `git log`
+    /// prints it instead of reading the whole log first, so scanning a
+    /// history of any length only holds one commit in memory at a time.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once every commit was passed to `f`.
+    /// * `Err(String)` with Git's error message if the command failed, after
+    ///   `f` has seen whatever Git printed before failing.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let mut count = 0;
+    /// repo.each_commit(&["HEAD"], |_| count += 1).unwrap();
+    /// println!("{} commits", count);
+    /// ```
+    pub fn each_commit<F: FnMut(Commit)>(&self, args: &[&str], mut f: F) -> Result<(), String> {
+        self.each_log_line(COMMIT_FORMAT, args, |line| {
+            if let Some(commit) = Commit::parse(line) {
+                f(commit);
+            }
+        })
+    }
+
+    /// Runs `git log --format=<format> <args>` like [`Repo::log`], handing
+    /// each line to `f` as Git prints it instead of returning the whole
+    /// output (invalid UTF-8 is replaced).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once every line was passed to `f`.
+    /// * `Err(String)` with Git's error message if the command failed.
+    pub fn each_log_line<F: FnMut(&str)>(
+        &self,
+        format: &str,
+        args: &[&str],
+        mut f: F,
+    ) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("log")
+            .arg(format!("--format={}", format))
+            .args(args);
+        cmd.stderr(Io::Piped);
+        streamed(self.runner(), &cmd, &mut |line| f(&String::from_utf8_lossy(line)))
+    }
+
+    /// Runs `git fsck --no-dangling` to check the object database.
+    ///
+    /// Dangling objects are expected after a rewrite (the old commits), so they
+    /// are not reported. `git rebase --root` records a placeholder commit whose
+    /// empty tree is never written to the object database; the empty tree is
+    /// written first so fsck does not flag it as missing.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with any warnings Git printed, empty if there were none.
+    /// * `Err(String)` with Git's report if it found problems or could not run.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a Git repository.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if let Err(problems) = repo.fsck() {
+    ///     eprintln!("Repository is damaged:\n{}", problems);
+    /// }
+    /// ```
+    pub fn fsck(&self) -> Result<String, String> {
+        let mut empty_tree = self.command();
+        empty_tree
+            .arg("hash-object")
+            .arg("-w")
+            .arg("-t")
+            .arg("tree")
+            .arg("--stdin");
+        empty_tree.stdin(Io::Null);
+        empty_tree.stdout(Io::Null);
+        empty_tree.stderr(Io::Null);
+        let _ = run_status(self.runner(), empty_tree);
+
+        let mut cmd = self.command();
+        cmd.arg("fsck").arg("--no-dangling").arg("--no-progress");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match captured(self.runner(), &cmd) {
+            Ok(out) => {
+                let mut report = String::from_utf8_lossy(&out.stdout).trim().to_string();
+                let errors = String::from_utf8_lossy(&out.stderr).trim().to_string();
+                if !errors.is_empty() {
+                    if !report.is_empty() {
+                        report.push('\n');
+                    }
+                    report.push_str(&errors);
+                }
+                if out.status.success() {
+                    Ok(report)
+                } else {
+                    Err(report)
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Expires every reflog entry immediately (`git reflog expire --expire=now --all`).
+    ///
+    /// After a rewrite the reflogs are what keep the original commits reachable;
+    /// expiring them lets [`Repo::gc_prune_now`] delete those commits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` if the command failed.
+    pub fn reflog_expire_all(&self) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("reflog")
+            .arg("expire")
+            .arg("--expire=now")
+            .arg("--expire-unreachable=now")
+            .arg("--all");
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Inherit);
+        run_status(self.runner(), cmd)
+    }
+
+    /// Runs `git gc --prune=now`, deleting every unreachable object.
+    ///
+    /// Git's own progress output is shown to the user.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` if the command failed.
+    pub fn gc_prune_now(&self) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("gc").arg("--prune=now");
+        cmd.stdout(Io::Stderr);
+        cmd.stderr(Io::Inherit);
+        run_status(self.runner(), cmd)
+    }
+
+    /// Writes every ref and the objects they reach to the bundle file `path`
+    /// (`git bundle create <path> --all`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn bundle_create(&self, path: &Path) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("bundle")
+            .arg("create")
+            .arg("--quiet")
+            .arg(path)
+            .arg("--all");
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Lists the refs stored in the bundle file `path`, one `<sha> <refname>`
+    /// line each (`git bundle list-heads`).
+    ///
+    /// The bundle is verified first, so a truncated or foreign file is
+    /// reported before anything is read from it.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with Git's output.
+    /// * `Err(String)` if the file is not a usable bundle for this repository.
+    pub fn bundle_list_heads(&self, path: &Path) -> Result<String, String> {
+        let mut verify = self.command();
+        verify.arg("bundle").arg("verify").arg("--quiet").arg(path);
+        verify.stdout(Io::Null);
+        verify.stderr(Io::Piped);
+        if let Err(e) = run_text(self.runner(), verify) {
+            return Err(e);
+        }
+
+        let mut cmd = self.command();
+        cmd.arg("bundle").arg("list-heads").arg(path);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Fetches `refspecs` from `source` (a URL, path or bundle file), with
+    /// `--update-head-ok` so the checked-out branch may be updated too.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn fetch_refs(&self, source: &str, refspecs: &[String]) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("fetch")
+            .arg("--quiet")
+            .arg("--no-tags")
+            .arg("--update-head-ok")
+            .arg(source)
+            .args(refspecs);
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resets the index and working tree to `HEAD` (`git reset --hard`).
+    ///
+    /// Tracked changes are lost; callers check [`Repo::tracked_changes`] first.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn reset_hard(&self) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("reset").arg("--hard").arg("--quiet").arg("HEAD");
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Looks up the author of each of `shas`, in order.
+    ///
+    /// The commits are passed on stdin (`git log --no-walk=unsorted --stdin`),
+    /// so any number of them can be looked up at once.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Identity>)` with one author per commit.
+    /// * `Err(String)` if a commit does not exist.
+    pub fn commit_authors(&self, shas: &[&str]) -> Result<Vec<Identity>, String> {
+        if shas.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut cmd = self.command();
+        cmd.arg("log")
+            .arg("--no-walk=unsorted")
+            .arg("--stdin")
+            .arg("--format=%an%x00%ae");
+        let input = shas.iter().map(|s| format!("{}\n", s)).collect::<String>();
+        let out = match run_with_input(self.runner(), cmd, input.into_bytes()) {
+            Ok(o) => String::from_utf8_lossy(&o).to_string(),
+            Err(e) => return Err(e),
+        };
+        let authors: Vec<Identity> = out
+            .lines()
+            .map(|l| match l.split_once('\0') {
+                Some((name, email)) => Identity::new(name, email),
+                None => Identity::new(l, ""),
+            })
+            .collect();
+        if authors.len() != shas.len() {
+            return Err(format!(
+                "expected {} authors from `git log`, got {}",
+                shas.len(),
+                authors.len()
+            ));
+        }
+        Ok(authors)
+    }
+
+    /// Finds the editor Git would open the rebase todo list in.
+    ///
+    /// Checks `GIT_SEQUENCE_EDITOR`, then `sequence.editor`, then falls back to
+    /// `git var GIT_EDITOR` (`GIT_EDITOR`, `core.editor`, `VISUAL`, `EDITOR`, `vi`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the editor command, to be run by the shell.
+    /// * `Err(String)` if no editor could be determined.
+    pub fn sequence_editor(&self) -> Result<String, String> {
+        if let Ok(editor) = std::env::var("GIT_SEQUENCE_EDITOR") {
+            if !editor.is_empty() {
+                return Ok(editor);
+            }
+        }
+        if let Ok(editor) = self.config_get("sequence.editor") {
+            if !editor.is_empty() {
+                return Ok(editor);
+            }
+        }
+        let mut cmd = self.command();
+        cmd.arg("var").arg("GIT_EDITOR");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        run_text(self.runner(), cmd)
+    }
+
+    /// Returns the raw content of an object (`git cat-file <kind> <rev>`).
+    ///
+    /// Replace refs are ignored, so this always reads the object actually stored
+    /// under `rev`.
+    ///
+    /// # Parameters
+    ///
+    /// * `kind` – The expected object type, e.g. `"commit"` or `"tag"`.
+    /// * `rev` – The object to read.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` with the object exactly as stored.
+    /// * `Err(String)` if the object does not exist or has another type.
+    pub fn cat_file(&self, kind: &str, rev: &str) -> Result<Vec<u8>, String> {
+        let mut cmd = self.command();
+        cmd.arg("--no-replace-objects")
+            .arg("cat-file")
+            .arg(kind)
+            .arg(rev);
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match captured(self.runner(), &cmd) {
+            Ok(out) => {
+                if out.status.success() {
+                    Ok(out.stdout)
+                } else {
+                    Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes `content` to the object database as an object of type `kind`.
+    ///
+    /// Runs `git hash-object -w -t <kind> --stdin`; Git validates the object
+    /// before writing it.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the new object's SHA.
+    /// * `Err(String)` with Git's error message if the object was rejected.
+    pub fn hash_object(&self, kind: &str, content: Vec<u8>) -> Result<String, String> {
+        let mut cmd = self.command();
+        cmd.arg("hash-object")
+            .arg("-w")
+            .arg("-t")
+            .arg(kind)
+            .arg("--stdin");
+        match run_with_input(self.runner(), cmd, content) {
+            Ok(out) => Ok(String::from_utf8_lossy(&out).trim().to_string()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Makes Git show `replacement` wherever `original` is referenced (`git replace -f`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn replace(&self, original: &str, replacement: &str) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("replace").arg("-f").arg(original).arg(replacement);
+        cmd.stdout(Io::Null);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates a detached, armored OpenPGP signature of `payload`, as Git does
+    /// when signing a tag.
+    ///
+    /// The program configured in `gpg.program` (default `gpg`) is run as
+    /// `<program> --status-fd=2 -bsau <key>`. Other signature formats
+    /// (`gpg.format` of `ssh` or `x509`) are not supported.
+    ///
+    /// # Parameters
+    ///
+    /// * `payload` – The bytes to sign.
+    /// * `key` – The signing key, e.g. `user.signingkey` or `Name <email>`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` with the armored signature.
+    /// * `Err(String)` if signing failed or the format is unsupported.
+    pub fn sign_buffer(&self, payload: Vec<u8>, key: &str) -> Result<Vec<u8>, String> {
+        let format = self.config_get("gpg.format").unwrap_or_default();
+        if !format.is_empty() && format != "openpgp" {
+            return Err(format!(
+                "gpg.format {} is not supported; only OpenPGP signing is",
+                format
+            ));
+        }
+        let program = self.gpg_program();
+        let mut cmd = GitCommand::program(&program);
+        cmd.arg("--status-fd=2").arg("-bsau").arg(key);
+        match run_with_input(self.runner(), cmd, payload) {
+            Ok(sig) if !sig.is_empty() => Ok(sig),
+            Ok(_) => Err(format!("{} produced no signature", program)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The OpenPGP program Git signs with: `gpg.program`, or `gpg`.
+    fn gpg_program(&self) -> String {
+        match self.config_get("gpg.program") {
+            Ok(p) if !p.is_empty() => p,
+            _ => String::from("gpg"),
+        }
+    }
+
+    /// Lists the OpenPGP secret keys available for signing, in GnuPG's
+    /// machine-readable format.
+    ///
+    /// ```text
+    /// <gpg.program> --list-secret-keys --with-colons
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the `--with-colons` listing.
+    /// * `Err(String)` if the program could not be run or failed.
+    pub fn list_secret_keys(&self) -> Result<String, String> {
+        let program = self.gpg_program();
+        let mut cmd = GitCommand::program(&program);
+        cmd.arg("--list-secret-keys").arg("--with-colons");
+        cmd.stdout(Io::Piped);
+        cmd.stderr(Io::Piped);
+        match run_text(self.runner(), cmd) {
+            Ok(out) => Ok(out),
+            Err(e) => Err(format!("cannot list the secret keys of {}: {}", program, e)),
+        }
+    }
+
+    /// Force-pushes `refs` to `remote`, each guarded by `--force-with-lease`.
+    ///
+    /// Each entry is `(ref name, expected remote value, new value)`; a ref that
+    /// moved on the remote since it was fetched is rejected instead of overwritten.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every ref was updated.
+    /// * `Err(String)` if the push failed or any ref was rejected.
+    pub fn push_with_lease(&self, remote: &str, refs: &[(&str, &str, &str)]) -> Result<(), String> {
+        let mut cmd = self.command();
+        cmd.arg("push").arg("--atomic");
+        for (name, old, _) in refs {
+            cmd.arg(format!("--force-with-lease={}:{}", name, old));
+        }
+        cmd.arg(remote);
+        for (name, _, new) in refs {
+            cmd.arg(format!("{}:{}", new, name));
+        }
+        cmd.stdout(Io::Stderr);
+        cmd.stderr(Io::Inherit);
+        match exit_status(self.runner(), &cmd) {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("git push exited with {}", status)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Detects if a Git rebase is currently in progress.
+    ///
+    /// This checks for the presence of the `rebase-merge` or `rebase-apply`
+    /// directories inside the git directory, which are created by Git during an
+    /// interactive or apply-style rebase.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if either `rebase-merge` or `rebase-apply` exists.
+    /// * `false` otherwise.
+    ///
+    /// # Notes
+    ///
+    /// * This is a lightweight check that does not invoke Git directly.
+    /// * Both interactive (`rebase-merge`) and apply-style (`rebase-apply`) rebases
+    ///   are detected.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// if repo.rebase_in_progress() {
+    ///     println!("A rebase is currently in progress.");
+    /// }
+    /// ```
+    pub fn rebase_in_progress(&self) -> bool {
+        let merge = self.git_dir.join("rebase-merge");
+        let apply = self.git_dir.join("rebase-apply");
+
+        merge.exists() || apply.exists()
+    }
+
+    /// Returns the SHA of the original commit the rebase most recently applied.
+    ///
+    /// Reads the last entry of `rebase-merge/done`, which records each todo line
+    /// (with the full original SHA) as the rebase processes it. At an `edit` stop
+    /// this is the commit being edited, before any amendment.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the original commit SHA.
+    /// * `Err(String)` if no interactive rebase is in progress or the file is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Ignored because it requires a rebase stopped at an `edit` line.
+    /// use git_author_rewrite::git::Repo;
+    ///
+    /// let repo = Repo::discover().unwrap();
+    /// let sha = repo.rebase_current_commit();
+    /// ```
+    pub fn rebase_current_commit(&self) -> Result<String, String> {
+        let path = self.git_dir.join("rebase-merge").join("done");
+        // Subjects may be in a legacy encoding; only the SHA is needed.
+        let body = match fs::read(&path) {
+            Ok(b) => String::from_utf8_lossy(&b).into_owned(),
+            Err(e) => return Err(format!("cannot read {}: {}", path.display(), e)),
+        };
+
+        match last_done_commit(&body) {
+            Some(sha) => Ok(sha),
+            None => Err(String::from("the rebase has not applied any commit yet")),
+        }
+    }
+}
+
+/// Runs `git --version` and returns its output, e.g. `git version 2.43.0`.
 ///
-/// # Notes
+/// # Returns
 ///
-/// * Must be run inside a Git repository with a commit to amend.
-/// * This rewrites history; only use on branches where rewriting is safe.
-/// * The new author will replace the current commit's author.
+/// * `Ok(String)` with the trimmed version line.
+/// * `Err(String)` if `git` could not be run.
 ///
 /// # Examples
 ///
-/// ```ignore
-/// // Ignored because it requires a Git repository.
-/// use git_author_rewrite::git::amend_author;
+/// ```no_run
+/// use git_author_rewrite::git::version;
 ///
-/// if let Err(err) = amend_author("John Doe <john@example.com>") {
-///     eprintln!("Failed to amend author: {}", err);
-/// }
+/// println!("{}", version().unwrap_or_default());
 /// ```
-pub fn amend_author(author: &str) -> Result<(), String> {
-    let mut cmd = Command::new("git");
-    cmd.arg("commit")
-        .arg("--amend")
-        .arg(format!("--author={}", author))
-        .arg("--no-edit");
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-    run_status(cmd).map_err(|_| String::from("`git commit --amend` returned non-zero"))
+pub fn version() -> Result<String, String> {
+    let mut cmd = git_command();
+    cmd.arg("--version");
+    cmd.stdout(Io::Piped);
+    cmd.stderr(Io::Piped);
+    with_current_runner(|runner| run_text(runner, cmd))
 }
 
-/// Continues an in-progress interactive rebase.
-///
-/// This runs:
-///
-/// ```text
-/// git rebase --continue
-/// ```
-///
-/// Standard input, output, and error are inherited so that Git can prompt
-/// the user or display its normal progress messages.
+/// Runs `git <args>` inside `dir` with the extra environment `envs`, for
+/// repositories other than the current one (e.g. a scratch repository).
 ///
 /// # Returns
 ///
-/// * `Ok(())` if the rebase continued successfully.
-/// * `Err(String)` if the command failed or exited with a non-zero status.
-///
-/// # Notes
-///
-/// * Must be run inside a Git repository with a rebase in progress.
-/// * The rebase will continue from the current stop point, applying the next
-///   commit in sequence.
+/// * `Ok(String)` with the trimmed standard output.
+/// * `Err(String)` with Git's error message otherwise.
 ///
 /// # Examples
 ///
 /// ```ignore
-/// // Ignored because it requires a Git repository and an active rebase.
-/// use git_author_rewrite::git::rebase_continue;
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::git::run_in;
+/// use std::path::Path;
 ///
-/// if let Err(err) = rebase_continue() {
-///     eprintln!("Failed to continue rebase: {}", err);
-/// }
+/// let head = run_in(Path::new("/tmp/scratch"), &["rev-parse", "HEAD"], &[]).unwrap();
 /// ```
-pub fn rebase_continue() -> Result<(), String> {
-    let mut cmd = Command::new("git");
-
-    cmd.arg("rebase").arg("--continue");
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-    run_status(cmd).map_err(|_| String::from("`git rebase --continue` returned non-zero"))
+pub fn run_in(dir: &Path, args: &[&str], envs: &[(&str, &str)]) -> Result<String, String> {
+    let mut cmd = git_command();
+    cmd.current_dir(dir).args(args).envs(envs.iter().copied());
+    cmd.stdin(Io::Null);
+    cmd.stdout(Io::Piped);
+    cmd.stderr(Io::Piped);
+    with_current_runner(|runner| run_text(runner, cmd))
 }
 
-/// Detects if a Git rebase is currently in progress.
-///
-/// This checks for the presence of the `rebase-merge` or `rebase-apply`
-/// directories inside `.git/`, which are created by Git during an interactive
-/// or apply-style rebase.
-///
-/// # Parameters
-///
-/// * `git_dir` – Path to the `.git` directory of the repository.
+/// Clones `url` with a working tree into `dir`, quietly (`git clone --quiet`).
 ///
 /// # Returns
 ///
-/// * `true` if either `rebase-merge` or `rebase-apply` exists.
-/// * `false` otherwise.
-///
-/// # Notes
+/// * `Ok(())` on success.
+/// * `Err(String)` with Git's error message otherwise.
+pub fn clone(url: &str, dir: &Path) -> Result<(), String> {
+    let mut cmd = git_command();
+    cmd.arg("clone").arg("--quiet").arg(url).arg(dir);
+    cmd.stdout(Io::Piped);
+    cmd.stderr(Io::Piped);
+    match with_current_runner(|runner| run_text(runner, cmd)) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Clones `url` as a bare mirror into `dir` (`git clone --mirror`).
 ///
-/// * This is a lightweight check that does not invoke Git directly.
-/// * Both interactive (`rebase-merge`) and apply-style (`rebase-apply`) rebases
-///   are detected.
+/// Progress output is shown to the user.
 ///
-/// # Examples
-///
-/// ```ignore
-/// use std::path::Path;
-/// use git_author_rewrite::git::rebase_in_progress;
+/// # Returns
 ///
-/// let git_dir = Path::new(".git");
-/// if rebase_in_progress(git_dir) {
-///     println!("A rebase is currently in progress.");
-/// }
-/// ```
-pub fn rebase_in_progress(git_dir: &Path) -> bool {
-    let merge = git_dir.join("rebase-merge");
-    let apply = git_dir.join("rebase-apply");
-
-    merge.exists() || apply.exists()
+/// * `Ok(())` on success.
+/// * `Err(String)` with Git's error message otherwise.
+pub fn clone_mirror(url: &str, dir: &Path) -> Result<(), String> {
+    let mut cmd = git_command();
+    cmd.arg("clone").arg("--mirror").arg(url).arg(dir);
+    cmd.stdout(Io::Stderr);
+    cmd.stderr(Io::Inherit);
+    match with_current_runner(|runner| exit_status(runner, &cmd)) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("git clone --mirror exited with {}", status)),
+        Err(e) => Err(e),
+    }
 }
 
+/// Extracts the commit SHA from the last command line of a `done` file.
+fn last_done_commit(body: &str) -> Option<String> {
+    body.lines()
+        .rev()
+        .filter_map(TodoLine::parse)
+        .filter(|l| l.command != TodoCommand::Drop && l.command.takes_commit())
+        .find_map(|l| l.commit().map(|c| c.to_string()))
+}
 #[cfg(test)]
 mod tests {
     use super::build_sequence_editor_env;
-    use super::rebase_in_progress;
+    use super::{clear_inherited_env, command_line};
+    use super::last_done_commit;
+    use super::parse_branches;
+    use super::{parse_log, parse_worktrees, with_runner, Commit, CommitOptions, Repo};
+    use super::{sequence_editor_script, write_sequence_editor_script};
+    use crate::identity::Identity;
+    use crate::runner::{GitCommand, GitOutput, GitStatus};
+    use crate::test_util::ScriptedRunner;
     use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::rc::Rc;
+
+    #[test]
+    fn command_line_shows_env_overrides_and_quotes() {
+        let mut cmd = GitCommand::new();
+        cmd.env("GIT_COMMITTER_NAME", "Jane Doe")
+            .env_remove("GIT_EDITOR")
+            .arg("commit")
+            .arg("--author=Jane Doe <j@x>");
+        let line = command_line(&cmd);
+        assert!(line.contains("GIT_COMMITTER_NAME=\"Jane Doe\""), "{}", line);
+        assert!(line.contains("env -u GIT_EDITOR"), "{}", line);
+        assert!(
+            line.ends_with("git commit \"--author=Jane Doe <j@x>\""),
+            "{}",
+            line
+        );
+    }
+
+    #[test]
+    fn inherited_identity_and_editor_variables_are_cleared() {
+        let mut cmd = GitCommand::new();
+        clear_inherited_env(&mut cmd, |key| {
+            matches!(key, "GIT_AUTHOR_NAME" | "GIT_SEQUENCE_EDITOR")
+        });
+        assert_eq!(
+            cmd.envs,
+            vec![
+                ("GIT_AUTHOR_NAME".into(), None),
+                ("GIT_SEQUENCE_EDITOR".into(), None),
+            ]
+        );
+
+        // What the tool sets itself replaces the removal.
+        cmd.env("GIT_SEQUENCE_EDITOR", "gar --sequence-editor");
+        assert_eq!(
+            command_line(&cmd),
+            "env -u GIT_AUTHOR_NAME GIT_SEQUENCE_EDITOR=\"gar --sequence-editor\" git"
+        );
+    }
 
     #[test]
     fn sequence_editor_quotes_when_needed() {
-        let s = build_sequence_editor_env("/Users/me/My App/bin");
+        let s = build_sequence_editor_env("/Users/me/My App/bin", &[]);
         assert!(s.starts_with("\"/Users/me/My App/bin\" --sequence-editor"));
     }
 
     #[test]
     fn sequence_editor_no_quotes_when_no_space() {
-        let s = build_sequence_editor_env("/usr/local/bin/myapp");
+        let s = build_sequence_editor_env("/usr/local/bin/myapp", &[]);
         assert!(s.starts_with("/usr/local/bin/myapp --sequence-editor"));
     }
 
     #[test]
     fn sequence_editor_escapes_quotes() {
-        let s = build_sequence_editor_env("/path/with\"quote/bin");
+        let s = build_sequence_editor_env("/path/with\"quote/bin", &[]);
         assert_eq!(s, "\"/path/with\\\"quote/bin\" --sequence-editor");
     }
 
     #[test]
     fn sequence_editor_handles_space_and_quote() {
-        let s = build_sequence_editor_env("/path with \"quote\"/bin");
+        let s = build_sequence_editor_env("/path with \"quote\"/bin", &[]);
         assert_eq!(s, "\"/path with \\\"quote\\\"/bin\" --sequence-editor");
     }
 
+    #[test]
+    fn sequence_editor_appends_quoted_extra_args() {
+        let args = vec![String::from("--select"), String::from("/my repo/.git/sel")];
+        let s = build_sequence_editor_env("/usr/bin/gar", &args);
+        assert_eq!(
+            s,
+            "/usr/bin/gar --sequence-editor --select \"/my repo/.git/sel\""
+        );
+    }
+
+    #[test]
+    fn sequence_editor_script_single_quotes_windows_paths() {
+        let args = vec![
+            String::from("--select"),
+            String::from("C:\\Users\\Jo O'Neil\\repo\\.git\\sel"),
+        ];
+        let s = sequence_editor_script("C:\\Program Files\\gar\\gar.exe", &args);
+        assert_eq!(
+            s,
+            "#!/bin/sh\nexec 'C:/Program Files/gar/gar.exe' --sequence-editor '--select' \
+             'C:\\Users\\Jo O'\\''Neil\\repo\\.git\\sel' \"$@\"\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sequence_editor_script_passes_the_todo_file_on() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let args = vec![String::from("it's a b")];
+        let script = write_sequence_editor_script(dir.path(), "echo", &args).unwrap();
+        let out = Command::new(&script)
+            .arg("/tmp/my todo")
+            .output()
+            .expect("failed to run the script");
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            "--sequence-editor it's a b /tmp/my todo\n"
+        );
+    }
+
+    #[test]
+    fn repositories_found_through_git_dir_pin_it_for_every_command() {
+        let runner = Rc::new(ScriptedRunner::new(vec![
+            (
+                "rev-parse --absolute-git-dir",
+                ScriptedRunner::ok("/srv/app.git\n"),
+            ),
+            ("rev-parse --show-toplevel", ScriptedRunner::ok("/srv/app\n")),
+            ("rev-parse HEAD", ScriptedRunner::ok("abc1234\n")),
+        ]));
+        let given = vec![("GIT_DIR", Some(PathBuf::from("app.git")))];
+        let repo = with_runner(runner.clone(), || Repo::locate(Path::new("/srv"), given))
+            .unwrap()
+            .pinned();
+        assert_eq!(repo.root(), Path::new("/srv/app"));
+        assert_eq!(repo.git_dir(), Path::new("/srv/app.git"));
+        repo.rev_parse("HEAD").unwrap();
+
+        let calls = runner.calls();
+        assert!(calls[0]
+            .envs
+            .contains(&("GIT_DIR".into(), Some("app.git".into()))));
+        assert!(calls[2]
+            .envs
+            .contains(&("GIT_DIR".into(), Some("/srv/app.git".into()))));
+        assert!(calls[2]
+            .envs
+            .contains(&("GIT_WORK_TREE".into(), Some("/srv/app".into()))));
+    }
+
     #[test]
     fn rebase_progress_detection_rebase_merge() {
         let dir = tempfile::tempdir().expect("failed to create temp dir");
         let git_dir = dir.path().join(".git");
         fs::create_dir_all(&git_dir).expect("failed to create .git dir");
-        assert_eq!(rebase_in_progress(&git_dir), false);
+        let repo = Repo::new(dir.path().to_path_buf(), git_dir.clone());
+        assert!(!repo.rebase_in_progress());
         fs::create_dir_all(git_dir.join("rebase-merge")).expect("failed to create rebase-merge dir");
-        assert_eq!(rebase_in_progress(&git_dir), true);
+        assert!(repo.rebase_in_progress());
     }
 
     #[test]
@@ -477,8 +2981,175 @@ mod tests {
         let dir = tempfile::tempdir().expect("failed to create temp dir");
         let git_dir = dir.path().join(".git");
         fs::create_dir_all(&git_dir).expect("failed to create .git dir");
-        assert_eq!(rebase_in_progress(&git_dir), false);
+        let repo = Repo::new(dir.path().to_path_buf(), git_dir.clone());
+        assert!(!repo.rebase_in_progress());
         fs::create_dir_all(git_dir.join("rebase-apply")).expect("failed to create rebase-apply dir");
-        assert_eq!(rebase_in_progress(&git_dir), true);
+        assert!(repo.rebase_in_progress());
+    }
+
+    #[test]
+    fn last_done_commit_takes_final_command() {
+        let body = "pick aaaa111 first\nedit bbbb222 second\n# comment\n";
+        assert_eq!(last_done_commit(body), Some(String::from("bbbb222")));
+    }
+
+    #[test]
+    fn last_done_commit_skips_commands_without_commits() {
+        let body = "edit cccc333 third\nexec make test\n";
+        assert_eq!(last_done_commit(body), Some(String::from("cccc333")));
+        let body = "e cccc333 third\nf -C dddd444 fourth\n";
+        assert_eq!(last_done_commit(body), Some(String::from("dddd444")));
+        assert_eq!(last_done_commit(""), None);
+    }
+
+    #[test]
+    fn rebase_step_failures_carry_gits_error_output() {
+        let runner = Rc::new(ScriptedRunner::new(vec![(
+            "-c commit.gpgsign=false rebase --continue",
+            ScriptedRunner::failed(1, "error: could not apply abc1234\n"),
+        )]));
+        let options = CommitOptions {
+            sign: Some(false),
+            ..CommitOptions::default()
+        };
+        let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner);
+        let err = repo.rebase_continue(options).unwrap_err();
+        assert_eq!(
+            err,
+            "`git rebase --continue` returned non-zero:\nerror: could not apply abc1234"
+        );
+    }
+
+    #[test]
+    fn commit_resolution_reuses_the_original_commit() {
+        let runner = Rc::new(ScriptedRunner::new(vec![
+            (
+                "diff --name-only --diff-filter=U",
+                ScriptedRunner::ok("a.txt\nsrc/b.rs\n"),
+            ),
+            (
+                "commit -C abc1234 --no-verify",
+                ScriptedRunner::ok(""),
+            ),
+        ]));
+        let options = CommitOptions {
+            run_hooks: false,
+            quiet: true,
+            ..CommitOptions::default()
+        };
+        let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner);
+        assert_eq!(repo.unmerged_paths().unwrap(), vec!["a.txt", "src/b.rs"]);
+        assert!(repo.commit_resolution("abc1234", options).is_ok());
+    }
+
+    #[test]
+    fn quiet_steps_return_gits_warnings() {
+        let runner = Rc::new(ScriptedRunner::new(vec![(
+            "rebase --continue",
+            GitOutput {
+                status: GitStatus { code: Some(0) },
+                stdout: Vec::new(),
+                stderr: b"warning: skipped previously applied commit abc1234\n".to_vec(),
+            },
+        )]));
+        let options = CommitOptions {
+            quiet: true,
+            ..CommitOptions::default()
+        };
+        let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner);
+        let out = repo.rebase_continue(options).unwrap();
+        assert_eq!(
+            out.stderr_text(),
+            "warning: skipped previously applied commit abc1234"
+        );
+    }
+
+    #[test]
+    fn failures_include_what_git_printed_on_stdout() {
+        let runner = Rc::new(ScriptedRunner::new(vec![(
+            "commit --amend -F - --cleanup=verbatim",
+            GitOutput {
+                status: GitStatus { code: Some(1) },
+                stdout: b"nothing to commit, working tree clean\n".to_vec(),
+                stderr: Vec::new(),
+            },
+        )]));
+        let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner);
+        let err = repo
+            .amend_author(None, None, Some(b"msg".to_vec()), CommitOptions::default())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "`git commit --amend` failed: nothing to commit, working tree clean"
+        );
+    }
+
+    #[test]
+    fn commit_parse_reads_nul_separated_fields() {
+        let line = "abc123\x00Jane\x00jane@x.com\x00Bot\x00bot@ci\x00100\x00200\x00Fix a: b\x00c";
+        assert_eq!(
+            Commit::parse(line),
+            Some(Commit {
+                sha: String::from("abc123"),
+                author: Identity::new("Jane", "jane@x.com"),
+                committer: Identity::new("Bot", "bot@ci"),
+                author_time: 100,
+                commit_time: 200,
+                subject: String::from("Fix a: b\x00c"),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_log_skips_malformed_lines() {
+        let log = "a\x00A\x00a@x\x00A\x00a@x\x001\x002\x00first\n\
+                   b\x00B\x00b@x\n\
+                   c\x00C\x00c@x\x00C\x00c@x\x00soon\x002\x00third\n\
+                   d\x00D\x00d@x\x00D\x00d@x\x003\x004\x00";
+        let shas: Vec<String> = parse_log(log).into_iter().map(|c| c.sha).collect();
+        assert_eq!(shas, vec!["a", "d"]);
+    }
+
+    #[test]
+    fn parse_worktrees_reads_paths_and_branches() {
+        let out = "worktree /src/app\nHEAD 1111\nbranch refs/heads/main\n\n\
+                   worktree /src/app-fix\nHEAD 2222\ndetached\n\n\
+                   worktree /src/app-wip\nHEAD 3333\nbranch refs/heads/wip\nlocked\n";
+        let worktrees = parse_worktrees(out);
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[0].path, PathBuf::from("/src/app"));
+        assert_eq!(worktrees[0].branch.as_deref(), Some("refs/heads/main"));
+        assert_eq!(worktrees[1].branch, None);
+        assert_eq!(worktrees[2].branch.as_deref(), Some("refs/heads/wip"));
+    }
+
+    #[test]
+    fn parse_branches_reads_the_tracking_state() {
+        let out = "*\0main\0origin/main\0ahead 2, behind 1\n \0topic\0\0\n \0old\0origin/old\0gone\n \0wip\0origin/wip\0behind 3\n";
+        let branches = parse_branches(out);
+        assert_eq!(branches.len(), 4);
+        assert!(branches[0].current);
+        assert_eq!(branches[0].upstream.as_deref(), Some("origin/main"));
+        assert_eq!((branches[0].ahead, branches[0].behind), (2, 1));
+        assert!(!branches[1].current);
+        assert_eq!(branches[1].upstream, None);
+        assert!(branches[2].upstream_gone);
+        assert_eq!((branches[3].ahead, branches[3].behind), (0, 3));
+    }
+
+    #[test]
+    fn commits_run_git_log_with_the_commit_format() {
+        let runner = Rc::new(ScriptedRunner::new(vec![(
+            "log --format=%H%x00%an%x00%ae%x00%cn%x00%ce%x00%at%x00%ct%x00%s -2 HEAD",
+            ScriptedRunner::ok("b\x00B\x00b@x\x00B\x00b@x\x002\x002\x00second\na\x00A\x00a@x\x00A\x00a@x\x001\x001\x00first\n"),
+        )]));
+        let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner.clone());
+        let subjects: Vec<String> = repo
+            .commits(&["-2", "HEAD"])
+            .unwrap()
+            .map(|c| c.subject)
+            .collect();
+        assert_eq!(subjects, vec!["second", "first"]);
+        assert!(runner.finished());
     }
 }
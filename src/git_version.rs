@@ -0,0 +1,218 @@
+use crate::{args::Options, git};
+
+use std::fmt;
+
+/// A Git release, as reported by `git --version`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GitVersion {
+    /// The major version, e.g. `2` in `2.39.5`.
+    pub major: u32,
+    /// The minor version, e.g. `39` in `2.39.5`.
+    pub minor: u32,
+    /// The patch level, e.g. `5` in `2.39.5`.
+    pub patch: u32,
+}
+
+impl GitVersion {
+    /// Creates a version from its parts.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        GitVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses `git --version` output such as `git version 2.39.5`.
+    ///
+    /// Vendor suffixes (`2.39.5.windows.1`, `2.39.5 (Apple Git-143)`) are
+    /// ignored, and a missing patch level counts as `0`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(GitVersion)` if a version number was found.
+    /// * `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::git_version::GitVersion;
+    ///
+    /// let v = GitVersion::parse("git version 2.39.5.windows.1").unwrap();
+    /// assert_eq!(v, GitVersion::new(2, 39, 5));
+    /// ```
+    pub fn parse(out: &str) -> Option<Self> {
+        let number = match out.trim().strip_prefix("git version ") {
+            Some(rest) => rest.split_whitespace().next().unwrap_or(""),
+            None => return None,
+        };
+        let mut parts = number.split('.').map(|p| p.parse::<u32>());
+        let major = match parts.next() {
+            Some(Ok(n)) => n,
+            _ => return None,
+        };
+        let minor = match parts.next() {
+            Some(Ok(n)) => n,
+            _ => return None,
+        };
+        let patch = match parts.next() {
+            Some(Ok(n)) => n,
+            _ => 0,
+        };
+        Some(GitVersion::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for GitVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A Git capability the tool relies on, and the release that introduced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    /// The Git command or option, e.g. `` `git push --atomic` ``.
+    pub name: &'static str,
+    /// The first release that supports it.
+    pub since: GitVersion,
+}
+
+/// Interactive rebase of a branch's root commit, used by the rebase rewrite.
+pub const ROOT_REBASE: Feature = Feature {
+    name: "`git rebase -i --root`",
+    since: GitVersion::new(1, 7, 12),
+};
+
+/// All-or-nothing pushes, used to push a rewritten `--mirror` back.
+pub const ATOMIC_PUSH: Feature = Feature {
+    name: "`git push --atomic`",
+    since: GitVersion::new(2, 4, 0),
+};
+
+/// Lists the Git features the run described by `opts` will use, each with
+/// the option (or mode) that needs it.
+pub fn required_features(opts: &Options) -> Vec<(&'static str, Feature)> {
+    let mut out = Vec::new();
+    if opts.mirror.is_some() {
+        out.push(("--mirror", ATOMIC_PUSH));
+    } else if !opts.replace_mode && !opts.drop_backup_refs && opts.engine.as_deref() != Some("gix")
+    {
+        out.push(("rewriting by rebase", ROOT_REBASE));
+    }
+    out
+}
+
+/// Explains each of `required` that `version` does not support.
+///
+/// # Returns
+///
+/// One actionable message per unsupported feature; empty if all are supported.
+pub fn unsupported(version: GitVersion, required: &[(&'static str, Feature)]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|(_, feature)| version < feature.since)
+        .map(|(needed_by, feature)| {
+            format!(
+                "{} needs git {} or newer (for {}), but this is git {}; upgrade git to continue.",
+                needed_by, feature.since, feature.name, version
+            )
+        })
+        .collect()
+}
+
+/// Queries the installed Git's version.
+///
+/// # Returns
+///
+/// * `Ok(GitVersion)` on success.
+/// * `Err(String)` if `git` could not be run or its output was not understood.
+///
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::git_version;
+///
+/// match git_version::detect() {
+///     Ok(v) => println!("git {}", v),
+///     Err(e) => eprintln!("{}", e),
+/// }
+/// ```
+pub fn detect() -> Result<GitVersion, String> {
+    let out = match git::version() {
+        Ok(o) => o,
+        Err(e) => return Err(e),
+    };
+    match GitVersion::parse(&out) {
+        Some(v) => Ok(v),
+        None => Err(format!("unrecognized `git --version` output: {}", out)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{required_features, unsupported, GitVersion, ATOMIC_PUSH, ROOT_REBASE};
+    use crate::args::Options;
+
+    #[test]
+    fn parse_handles_vendor_suffixes() {
+        assert_eq!(
+            GitVersion::parse("git version 2.39.5"),
+            Some(GitVersion::new(2, 39, 5))
+        );
+        assert_eq!(
+            GitVersion::parse("git version 2.39.3 (Apple Git-146)"),
+            Some(GitVersion::new(2, 39, 3))
+        );
+        assert_eq!(
+            GitVersion::parse("git version 2.45.0.windows.1"),
+            Some(GitVersion::new(2, 45, 0))
+        );
+        assert_eq!(
+            GitVersion::parse("git version 3.0"),
+            Some(GitVersion::new(3, 0, 0))
+        );
+        assert_eq!(GitVersion::parse("hub version 2.14.2"), None);
+    }
+
+    #[test]
+    fn versions_order_numerically() {
+        assert!(GitVersion::new(2, 9, 0) < GitVersion::new(2, 18, 0));
+        assert!(GitVersion::new(1, 9, 5) < GitVersion::new(2, 0, 0));
+        assert_eq!(GitVersion::new(2, 4, 0).to_string(), "2.4.0");
+    }
+
+    #[test]
+    fn required_features_follow_the_mode() {
+        let mirror = Options {
+            mirror: Some(String::from("git@example.com:app.git")),
+            ..Options::default()
+        };
+        assert_eq!(required_features(&mirror), vec![("--mirror", ATOMIC_PUSH)]);
+
+        let replace = Options {
+            replace_mode: true,
+            ..Options::default()
+        };
+        assert!(required_features(&replace).is_empty());
+        let gix = Options {
+            engine: Some(String::from("gix")),
+            ..Options::default()
+        };
+        assert!(required_features(&gix).is_empty());
+        assert_eq!(
+            required_features(&Options::default()),
+            vec![("rewriting by rebase", ROOT_REBASE)]
+        );
+    }
+
+    #[test]
+    fn unsupported_explains_what_to_upgrade() {
+        let required = vec![("--mirror", ATOMIC_PUSH)];
+        assert!(unsupported(GitVersion::new(2, 4, 0), &required).is_empty());
+        assert_eq!(
+            unsupported(GitVersion::new(2, 3, 9), &required),
+            vec!["--mirror needs git 2.4.0 or newer (for `git push --atomic`), but this is git 2.3.9; upgrade git to continue."]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::noreply;
+
+use serde::Deserialize;
+use std::env;
+
+/// Environment variables read for the GitHub token, in order.
+pub const TOKEN_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// The GitHub account a token belongs to (`GET /user`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Account {
+    /// The username, e.g. `octocat`.
+    pub login: String,
+    /// The numeric account ID used in noreply addresses.
+    pub id: u64,
+}
+
+/// An email registered on the account (`GET /user/emails`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AccountEmail {
+    /// The address.
+    pub email: String,
+    /// Whether the owner confirmed the address.
+    pub verified: bool,
+}
+
+/// Whether GitHub will attribute commits with an email to the account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// A verified email of the account, or its noreply address.
+    Verified,
+    /// Registered on the account but not verified yet; commits are not
+    /// attributed until it is.
+    Unverified,
+    /// Not registered on the account at all.
+    NotOnAccount,
+}
+
+/// Reads the GitHub token from `GITHUB_TOKEN`, then `GH_TOKEN`.
+pub fn token() -> Option<String> {
+    TOKEN_VARS
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .map(|v| v.trim().to_string())
+        .find(|v| !v.is_empty())
+}
+
+/// Decides whether `email` belongs to `account`, given the emails registered
+/// on it.
+///
+/// A noreply address belongs to the account when its username (and ID, if
+/// present) match; any other email has to be listed in `emails`.
+pub fn verdict(email: &str, account: &Account, emails: &[AccountEmail]) -> Verdict {
+    if let Some((id, username)) = noreply::parse_github_address(email) {
+        let id_ok = match id {
+            Some(id) => id == account.id,
+            None => true,
+        };
+        if id_ok && username.eq_ignore_ascii_case(&account.login) {
+            return Verdict::Verified;
+        }
+        return Verdict::NotOnAccount;
+    }
+    match emails
+        .iter()
+        .find(|e| e.email.eq_ignore_ascii_case(email.trim()))
+    {
+        Some(e) if e.verified => Verdict::Verified,
+        Some(_) => Verdict::Unverified,
+        None => Verdict::NotOnAccount,
+    }
+}
+
+/// Asks the GitHub API whether `email` is a verified email of the account
+/// `token` belongs to (`--check-github`).
+///
+/// The token needs to read the account's email addresses: the `user:email`
+/// scope for a classic token, or the "Email addresses" permission for a
+/// fine-grained one.
+///
+/// # Returns
+///
+/// * `Ok((Account, Verdict))` with the token's account and the outcome.
+/// * `Err(String)` if the API could not be reached or refused the token.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it calls the GitHub API.
+/// use git_author_rewrite::github;
+///
+/// let token = github::token().unwrap();
+/// let (account, verdict) = github::check_email("jane@corp.com", &token).unwrap();
+/// println!("{}: {:?}", account.login, verdict);
+/// ```
+#[cfg(feature = "github")]
+pub fn check_email(email: &str, token: &str) -> Result<(Account, Verdict), String> {
+    let account: Account = match api_get("/user", token) {
+        Ok(body) => match serde_json::from_str(&body) {
+            Ok(a) => a,
+            Err(e) => return Err(format!("unexpected GitHub API response: {}", e)),
+        },
+        Err(e) => return Err(e),
+    };
+    let emails: Vec<AccountEmail> = match api_get("/user/emails", token) {
+        Ok(body) => match serde_json::from_str(&body) {
+            Ok(list) => list,
+            Err(e) => return Err(format!("unexpected GitHub API response: {}", e)),
+        },
+        Err(e) => return Err(e),
+    };
+    let outcome = verdict(email, &account, &emails);
+    Ok((account, outcome))
+}
+
+/// Without the `github` feature there is no HTTP client to ask with.
+#[cfg(not(feature = "github"))]
+pub fn check_email(_email: &str, _token: &str) -> Result<(Account, Verdict), String> {
+    Err(String::from(
+        "this build has no GitHub support; reinstall with `cargo install git-author-rewrite --features github`",
+    ))
+}
+
+/// Fetches `path` from the GitHub REST API and returns the response body.
+#[cfg(feature = "github")]
+fn api_get(path: &str, token: &str) -> Result<String, String> {
+    use std::time::Duration;
+
+    let config = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(20)))
+        .build();
+    let agent: ureq::Agent = config.into();
+    let url = format!("https://api.github.com{}", path);
+    let response = agent
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("User-Agent", "git-author-rewrite")
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .call();
+    match response {
+        Ok(mut r) => match r.body_mut().read_to_string() {
+            Ok(body) => Ok(body),
+            Err(e) => Err(format!("cannot read the GitHub API response: {}", e)),
+        },
+        Err(ureq::Error::StatusCode(401)) => Err(String::from(
+            "GitHub rejected the token (401); check GITHUB_TOKEN",
+        )),
+        Err(ureq::Error::StatusCode(code @ (403 | 404))) => Err(format!(
+            "GitHub refused {} ({}); the token needs the `user:email` scope (or the \"Email addresses\" permission)",
+            path, code
+        )),
+        Err(e) => Err(format!("cannot reach the GitHub API: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verdict, Account, AccountEmail, Verdict};
+
+    fn octocat() -> Account {
+        Account {
+            login: String::from("octocat"),
+            id: 583231,
+        }
+    }
+
+    fn email(address: &str, verified: bool) -> AccountEmail {
+        AccountEmail {
+            email: address.to_string(),
+            verified,
+        }
+    }
+
+    #[test]
+    fn verdict_checks_registered_emails() {
+        let emails = vec![email("octo@corp.com", true), email("octo@home.net", false)];
+        assert_eq!(
+            verdict("Octo@Corp.com", &octocat(), &emails),
+            Verdict::Verified
+        );
+        assert_eq!(
+            verdict("octo@home.net", &octocat(), &emails),
+            Verdict::Unverified
+        );
+        assert_eq!(
+            verdict("someone@else.com", &octocat(), &emails),
+            Verdict::NotOnAccount
+        );
+    }
+
+    #[test]
+    fn verdict_matches_noreply_addresses_against_the_account() {
+        let account = octocat();
+        assert_eq!(
+            verdict("583231+octocat@users.noreply.github.com", &account, &[]),
+            Verdict::Verified
+        );
+        assert_eq!(
+            verdict("octocat@users.noreply.github.com", &account, &[]),
+            Verdict::Verified
+        );
+        assert_eq!(
+            verdict("1+octocat@users.noreply.github.com", &account, &[]),
+            Verdict::NotOnAccount
+        );
+        assert_eq!(
+            verdict("583231+hubot@users.noreply.github.com", &account, &[]),
+            Verdict::NotOnAccount
+        );
+    }
+}
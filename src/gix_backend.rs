@@ -0,0 +1,251 @@
+use crate::{
+    git,
+    history::{self, HistoryRewrite, RefUpdate},
+    identity::{Identity, Mapping},
+    message::MessageEdits,
+    selection::Selection,
+};
+
+use gix::{objs::Write, refs::transaction::PreviousValue, ObjectId, Repository};
+use std::collections::{HashMap, HashSet};
+
+/// The reflog message recorded when the branch is moved.
+const REFLOG_MESSAGE: &str = "git-author-rewrite (gix): rewrite authors";
+
+/// Orders the commits reachable from `tip` so that every commit comes after
+/// all of its parents, visiting each commit once.
+///
+/// # Parameters
+///
+/// * `tip` – The commit to start from.
+/// * `parents` – Looks up the parents of a commit.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` – The commits, oldest first; `tip` is last.
+/// * `Err(String)` as soon as a lookup fails.
+pub(crate) fn parents_first<F>(tip: &str, mut parents: F) -> Result<Vec<String>, String>
+where
+    F: FnMut(&str) -> Result<Vec<String>, String>,
+{
+    // Depth-first, with each commit's parents still to visit; a commit is
+    // emitted once they are all done. Iterative, as histories can be deep.
+    let mut order = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack: Vec<(String, Vec<String>)> = Vec::new();
+
+    let mut visit = |sha: String, stack: &mut Vec<(String, Vec<String>)>| match parents(&sha) {
+        Ok(mut p) => {
+            // Popped from the end, so the first parent is walked first.
+            p.reverse();
+            stack.push((sha, p));
+            Ok(())
+        }
+        Err(e) => Err(e),
+    };
+
+    seen.insert(tip.to_string());
+    if let Err(e) = visit(tip.to_string(), &mut stack) {
+        return Err(e);
+    }
+    while let Some((_, pending)) = stack.last_mut() {
+        match pending.pop() {
+            Some(parent) => {
+                if seen.insert(parent.clone()) {
+                    if let Err(e) = visit(parent, &mut stack) {
+                        return Err(e);
+                    }
+                }
+            }
+            None => {
+                if let Some((sha, _)) = stack.pop() {
+                    order.push(sha);
+                }
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Parses a full hexadecimal object name.
+fn object_id(sha: &str) -> Result<ObjectId, String> {
+    match ObjectId::from_hex(sha.as_bytes()) {
+        Ok(id) => Ok(id),
+        Err(e) => Err(format!("invalid object name {}: {}", sha, e)),
+    }
+}
+
+/// Reads the raw object and the parents of the commit `sha`.
+fn read_commit(repo: &Repository, sha: &str) -> Result<(Vec<u8>, Vec<String>), String> {
+    let id = match object_id(sha) {
+        Ok(id) => id,
+        Err(e) => return Err(e),
+    };
+    let commit = match repo.find_commit(id) {
+        Ok(c) => c,
+        Err(e) => return Err(format!("cannot read commit {}: {}", sha, e)),
+    };
+    let parents = commit.parent_ids().map(|p| p.to_string()).collect();
+    Ok((commit.data.clone(), parents))
+}
+
+/// Rewrites the history of the current branch in-process with gitoxide
+/// (`--engine gix`).
+///
+/// Every commit reachable from `HEAD` is visited parents first. Selected
+/// commits get their identities changed by `mapping` and their messages by
+/// `edits`; the others are only re-created when a parent changed, keeping
+/// their author and committer. Trees and dates are kept, so nothing can
+/// conflict and the working tree stays as it is. Signatures are dropped,
+/// hooks do not run, and the branch is moved with a reflog entry once every
+/// commit is written.
+///
+/// # Parameters
+///
+/// * `repo` – The repository whose current branch is rewritten.
+/// * `selection` – The commits whose identities and messages change.
+/// * `mapping` – How their identities are rewritten.
+/// * `current_user` – The committer recorded where `mapping` leaves it to Git.
+/// * `edits` – Changes to their messages.
+///
+/// # Returns
+///
+/// * `Ok(HistoryRewrite)` – The number of new commits and the moved branch
+///   (no refs if nothing changed).
+/// * `Err(String)` if the repository could not be read or written; the
+///   branch is not moved then.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, gix_backend, identity::{Identity, Mapping}, selection::Selection};
+///
+/// let repo = Repo::discover().unwrap();
+/// let mapping = Mapping::Fixed(Identity::new("Jane Doe", "jane@corp.com"));
+/// let me = Identity::new("Jane Doe", "jane@corp.com");
+/// let done = gix_backend::rewrite_branch(&repo, &Selection::All, &mapping, &me, &Default::default()).unwrap();
+/// println!("{} commits rewritten", done.commits);
+/// ```
+pub fn rewrite_branch(
+    repo: &git::Repo,
+    selection: &Selection,
+    mapping: &Mapping,
+    current_user: &Identity,
+    edits: &MessageEdits,
+) -> Result<HistoryRewrite, String> {
+    let repo = match gix::discover(repo.root()) {
+        Ok(r) => r,
+        Err(e) => return Err(format!("cannot open the repository: {}", e)),
+    };
+    let branch = match repo.head_name() {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Err(String::from(
+                "HEAD is detached; switch to the branch to rewrite",
+            ))
+        }
+        Err(e) => return Err(format!("cannot read HEAD: {}", e)),
+    };
+    let tip = match repo.head_id() {
+        Ok(id) => id.detach(),
+        Err(e) => return Err(format!("cannot read HEAD: {}", e)),
+    };
+
+    let mut raw_commits = HashMap::new();
+    let commits = match parents_first(&tip.to_string(), |sha| match read_commit(&repo, sha) {
+        Ok((raw, parents)) => {
+            raw_commits.insert(sha.to_string(), raw);
+            Ok(parents)
+        }
+        Err(e) => Err(e),
+    }) {
+        Ok(c) => c,
+        Err(e) => return Err(e),
+    };
+
+    let unchanged = MessageEdits::default();
+    let mut rewritten = HashMap::new();
+    for sha in &commits {
+        let raw = match raw_commits.remove(sha) {
+            Some(r) => r,
+            None => continue,
+        };
+        let (mapping, edits) = if selection.contains(sha) {
+            (Some(mapping), edits)
+        } else {
+            (None, &unchanged)
+        };
+        let bytes =
+            match history::rewrite_commit(&raw, sha, mapping, current_user, edits, &rewritten) {
+                Ok(Some(b)) => b,
+                Ok(None) => continue,
+                Err(e) => return Err(format!("cannot rewrite commit {}: {}", sha, e)),
+            };
+        match repo.objects.write_buf(gix::object::Kind::Commit, &bytes) {
+            Ok(id) => {
+                rewritten.insert(sha.clone(), id.to_string());
+            }
+            Err(e) => return Err(format!("cannot write the rewrite of {}: {}", sha, e)),
+        }
+    }
+
+    let old = tip.to_string();
+    let new = match rewritten.get(&old) {
+        Some(n) => n.clone(),
+        None => return Ok(HistoryRewrite::default()),
+    };
+    let new_id = match object_id(&new) {
+        Ok(id) => id,
+        Err(e) => return Err(e),
+    };
+    let name = branch.as_bstr().to_string();
+    if let Err(e) = repo.reference(
+        branch,
+        new_id,
+        PreviousValue::MustExistAndMatch(tip.into()),
+        REFLOG_MESSAGE,
+    ) {
+        return Err(format!("cannot move {}: {}", name, e));
+    }
+
+    Ok(HistoryRewrite {
+        commits: rewritten.len(),
+        refs: vec![RefUpdate { name, old, new }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parents_first;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parents_first_orders_merges_and_visits_each_commit_once() {
+        // a - b - d (merge of b and c)
+        //  \- c -/
+        let graph: HashMap<&str, Vec<&str>> = [
+            ("a", vec![]),
+            ("b", vec!["a"]),
+            ("c", vec!["a"]),
+            ("d", vec!["b", "c"]),
+        ]
+        .into_iter()
+        .collect();
+        let order = parents_first("d", |sha| {
+            Ok(graph[sha].iter().map(|p| p.to_string()).collect())
+        })
+        .unwrap();
+        assert_eq!(order, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn parents_first_stops_at_a_failed_lookup() {
+        let err = parents_first("b", |sha| match sha {
+            "b" => Ok(vec![String::from("a")]),
+            other => Err(format!("cannot read commit {}", other)),
+        })
+        .unwrap_err();
+        assert_eq!(err, "cannot read commit a");
+    }
+}
@@ -0,0 +1,494 @@
+use crate::{
+    commit_object::CommitObject,
+    git::Repo,
+    identity::{Identity, Mapping},
+    message::MessageEdits,
+    selection::Selection,
+    tags,
+};
+
+use std::collections::HashMap;
+
+/// A ref that points somewhere else after the rewrite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefUpdate {
+    /// The full ref name, e.g. `refs/heads/main`.
+    pub name: String,
+    /// The object the ref points at now.
+    pub old: String,
+    /// The rewritten object it should point at.
+    pub new: String,
+}
+
+/// The outcome of rewriting the history behind a set of refs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryRewrite {
+    /// Number of commits that were written anew.
+    pub commits: usize,
+    /// The refs that have to move, sorted by name.
+    pub refs: Vec<RefUpdate>,
+}
+
+/// A ref as listed by `git for-each-ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RefTip {
+    pub name: String,
+    pub kind: String,
+    pub object: String,
+    /// The object an annotated tag peels to, and its type.
+    pub peeled: Option<(String, String)>,
+}
+
+/// The `git for-each-ref` format [`parse_ref_tips`] reads.
+const REF_FORMAT: &str = "%(refname) %(objecttype) %(objectname) %(*objecttype) %(*objectname)";
+
+/// Parses `git for-each-ref` output in [`REF_FORMAT`].
+///
+/// The peeled fields are empty for anything but annotated tags, and may be
+/// missing altogether on the last line once the output has been trimmed.
+pub(crate) fn parse_ref_tips(out: &str) -> Vec<RefTip> {
+    out.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields.as_slice() {
+                [name, kind, object, rest @ ..] => Some(RefTip {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                    object: object.to_string(),
+                    peeled: match rest {
+                        [peeled_kind, peeled] if !peeled.is_empty() => {
+                            Some((peeled_kind.to_string(), peeled.to_string()))
+                        }
+                        _ => None,
+                    },
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns the commit a ref leads to, if any.
+fn tip_commit(tip: &RefTip) -> Option<&str> {
+    match (tip.kind.as_str(), &tip.peeled) {
+        ("commit", _) => Some(&tip.object),
+        ("tag", Some((kind, sha))) if kind == "commit" => Some(sha),
+        _ => None,
+    }
+}
+
+/// Builds the rewritten version of the raw commit `raw`.
+///
+/// Parents are pointed at their rewritten versions (from `rewritten`),
+/// identities are changed by `mapping` (if given) and the message by
+/// `edits`; the tree and dates are kept.
+///
+/// # Returns
+///
+/// * `Ok(Some(bytes))` with the new commit object.
+/// * `Ok(None)` if neither a parent nor an identity changes.
+/// * `Err(String)` if the commit could not be parsed.
+pub(crate) fn rewrite_commit(
+    raw: &[u8],
+    sha: &str,
+    mapping: Option<&Mapping>,
+    current_user: &Identity,
+    edits: &MessageEdits,
+    rewritten: &HashMap<String, String>,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut obj = match CommitObject::parse(raw) {
+        Ok(o) => o,
+        Err(e) => return Err(e),
+    };
+    let parents_moved = obj.map_parents(|p| rewritten.get(p).cloned());
+    let identities_changed = match mapping.map(|m| obj.apply_mapping(sha, m, current_user)) {
+        Some(Ok(c)) => c,
+        Some(Err(e)) => return Err(e),
+        None => false,
+    };
+
+    let message_changed = match edits.substitute(&obj.message) {
+        Some(m) => {
+            obj.message = m;
+            true
+        }
+        None => false,
+    };
+
+    if !parents_moved && !identities_changed && !message_changed {
+        return Ok(None);
+    }
+    if let Some(m) = edits.annotate(&obj.message, sha) {
+        obj.message = m;
+    }
+    // The old signature covers the old parents.
+    obj.drop_signature();
+    Ok(Some(obj.to_bytes()))
+}
+
+/// Rewrites every commit reachable from the refs under `prefixes`, without
+/// a working tree.
+///
+/// Commits are written as new objects in topological order, reusing their
+/// trees, so the rewrite cannot conflict. No ref is moved; the returned
+/// [`RefUpdate`]s say where each ref should point. Annotated tags are
+/// re-created pointing at the rewritten commits, with their tagger rewritten
+/// too when `tag_mapping` is given. Tags of tags are left alone.
+///
+/// # Parameters
+///
+/// * `repo` – The repository to rewrite.
+/// * `prefixes` – Ref namespaces to rewrite, e.g. `refs/heads/`.
+/// * `mapping` – How commit identities are rewritten.
+/// * `current_user` – The committer recorded where `mapping` leaves it to Git.
+/// * `edits` – Changes to the messages of rewritten commits.
+/// * `tag_mapping` – How taggers are rewritten, if at all.
+/// * `sign_tags` – Re-sign rewritten tags.
+///
+/// # Returns
+///
+/// * `Ok(HistoryRewrite)` with the number of commits rewritten and the refs to move.
+/// * `Err(String)` if an object could not be read or written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, history, identity::{Identity, Mapping}};
+///
+/// let repo = Repo::discover().unwrap();
+/// let mapping = Mapping::Fixed(Identity::new("Jane Doe", "jane@corp.com"));
+/// let me = Identity::new("Jane Doe", "jane@corp.com");
+/// let done = history::rewrite_history(&repo, &["refs/heads/", "refs/tags/"], &mapping, &me, &Default::default(), None, false).unwrap();
+/// for r in &done.refs {
+///     println!("{}: {} -> {}", r.name, r.old, r.new);
+/// }
+/// ```
+pub fn rewrite_history(
+    repo: &Repo,
+    prefixes: &[&str],
+    mapping: &Mapping,
+    current_user: &Identity,
+    edits: &MessageEdits,
+    tag_mapping: Option<&Mapping>,
+    sign_tags: bool,
+) -> Result<HistoryRewrite, String> {
+    let mut tips = Vec::new();
+    for prefix in prefixes {
+        match repo.for_each_ref(REF_FORMAT, prefix) {
+            Ok(out) => tips.extend(parse_ref_tips(&out)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let starts: Vec<&str> = tips.iter().filter_map(tip_commit).collect();
+    if starts.is_empty() {
+        return Ok(HistoryRewrite::default());
+    }
+    let commits = match repo.rev_list_stdin(&["--topo-order", "--reverse"], &starts.join("\n")) {
+        Ok(c) => c,
+        Err(e) => return Err(e),
+    };
+
+    // Parents come first, so each commit's parents are already mapped.
+    let mut rewritten: HashMap<String, String> = HashMap::new();
+    for sha in commits {
+        let raw = match repo.cat_file("commit", &sha) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("cannot read commit {}: {}", sha, e)),
+        };
+        let bytes = match rewrite_commit(&raw, &sha, Some(mapping), current_user, edits, &rewritten)
+        {
+            Ok(Some(b)) => b,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("cannot rewrite commit {}: {}", sha, e)),
+        };
+        match repo.hash_object("commit", bytes) {
+            Ok(new) => {
+                rewritten.insert(sha, new);
+            }
+            Err(e) => return Err(format!("cannot write commit {}: {}", sha, e)),
+        }
+    }
+
+    let mut refs = Vec::new();
+    for tip in &tips {
+        let new = match tip.kind.as_str() {
+            "commit" => rewritten.get(&tip.object).cloned(),
+            "tag" => match retag(repo, tip, &rewritten, tag_mapping, sign_tags) {
+                Ok(n) => n,
+                Err(e) => return Err(e),
+            },
+            _ => None,
+        };
+        if let Some(new) = new {
+            refs.push(RefUpdate {
+                name: tip.name.clone(),
+                old: tip.object.clone(),
+                new,
+            });
+        }
+    }
+    refs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(HistoryRewrite {
+        commits: rewritten.len(),
+        refs,
+    })
+}
+
+/// Rewrites the history of the current branch without a working tree
+/// (`--engine metadata`).
+///
+/// Every commit reachable from `HEAD` is visited parents first. Selected
+/// commits get their identities changed by `mapping` and their messages by
+/// `edits`; the others are only re-created when a parent changed, keeping
+/// their author and committer. Each new commit reuses the original tree, so
+/// file contents stay byte for byte the same and nothing can conflict.
+/// Signatures are dropped. No ref is moved; see [`move_refs`].
+///
+/// # Parameters
+///
+/// * `repo` – The repository whose current branch is rewritten.
+/// * `selection` – The commits whose identities and messages change.
+/// * `mapping` – How their identities are rewritten.
+/// * `current_user` – The committer recorded where `mapping` leaves it to Git.
+/// * `edits` – Changes to their messages.
+///
+/// # Returns
+///
+/// * `Ok(HistoryRewrite)` with the number of new commits and the branch
+///   update (no refs if nothing changed).
+/// * `Err(String)` if `HEAD` is detached or an object could not be read or
+///   written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, history, identity::{Identity, Mapping}, selection::Selection};
+///
+/// let repo = Repo::discover().unwrap();
+/// let mapping = Mapping::Fixed(Identity::new("Jane Doe", "jane@corp.com"));
+/// let me = Identity::new("Jane Doe", "jane@corp.com");
+/// let done = history::rewrite_branch(&repo, &Selection::All, &mapping, &me, &Default::default()).unwrap();
+/// history::move_refs(&repo, &done.refs, "rewrite authors").unwrap();
+/// ```
+pub fn rewrite_branch(
+    repo: &Repo,
+    selection: &Selection,
+    mapping: &Mapping,
+    current_user: &Identity,
+    edits: &MessageEdits,
+) -> Result<HistoryRewrite, String> {
+    let branch = match repo.symbolic_head() {
+        Some(b) => b,
+        None => {
+            return Err(String::from(
+                "HEAD is detached; switch to the branch to rewrite",
+            ))
+        }
+    };
+    let tip = match repo.rev_parse("HEAD") {
+        Ok(t) => t,
+        Err(e) => return Err(format!("cannot read HEAD: {}", e)),
+    };
+    let commits = match repo.rev_list(&["--topo-order", "--reverse", &tip]) {
+        Ok(c) => c,
+        Err(e) => return Err(e),
+    };
+
+    // Parents come first, so each commit's parents are already mapped.
+    let unchanged = MessageEdits::default();
+    let mut rewritten: HashMap<String, String> = HashMap::new();
+    for sha in commits {
+        let raw = match repo.cat_file("commit", &sha) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("cannot read commit {}: {}", sha, e)),
+        };
+        let (mapping, edits) = if selection.contains(&sha) {
+            (Some(mapping), edits)
+        } else {
+            (None, &unchanged)
+        };
+        let bytes = match rewrite_commit(&raw, &sha, mapping, current_user, edits, &rewritten) {
+            Ok(Some(b)) => b,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("cannot rewrite commit {}: {}", sha, e)),
+        };
+        match repo.hash_object("commit", bytes) {
+            Ok(new) => {
+                rewritten.insert(sha, new);
+            }
+            Err(e) => return Err(format!("cannot write commit {}: {}", sha, e)),
+        }
+    }
+
+    let new = match rewritten.get(&tip) {
+        Some(n) => n.clone(),
+        None => return Ok(HistoryRewrite::default()),
+    };
+    Ok(HistoryRewrite {
+        commits: rewritten.len(),
+        refs: vec![RefUpdate {
+            name: branch,
+            old: tip,
+            new,
+        }],
+    })
+}
+
+/// Builds the `git update-ref --stdin` commands that move `refs`, each only
+/// if it still points at its old value.
+pub(crate) fn update_ref_commands(refs: &[RefUpdate]) -> String {
+    refs.iter()
+        .map(|r| format!("update {} {} {}\n", r.name, r.new, r.old))
+        .collect()
+}
+
+/// Moves `refs` to their rewritten values in one transaction, recording
+/// `message` in the reflogs.
+///
+/// A ref that moved since it was read (another commit made in the meantime)
+/// fails the whole update, so nothing is lost and no ref is left half done.
+///
+/// # Returns
+///
+/// * `Ok(())` once every ref points at its new value.
+/// * `Err(String)` if Git refused the update; no ref has moved then.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, history, identity::{Identity, Mapping}};
+///
+/// let repo = Repo::discover().unwrap();
+/// let mapping = Mapping::Fixed(Identity::new("Jane Doe", "jane@corp.com"));
+/// let me = Identity::new("Jane Doe", "jane@corp.com");
+/// let done = history::rewrite_history(&repo, &["refs/heads/"], &mapping, &me, &Default::default(), None, false).unwrap();
+/// history::move_refs(&repo, &done.refs, "rewrite authors").unwrap();
+/// ```
+pub fn move_refs(repo: &Repo, refs: &[RefUpdate], message: &str) -> Result<(), String> {
+    match repo.update_refs(&update_ref_commands(refs), message) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("cannot move the rewritten refs: {}", e)),
+    }
+}
+
+/// Re-creates the annotated tag `tip` for the rewritten history.
+///
+/// # Returns
+///
+/// * `Ok(Some(sha))` with the new tag object.
+/// * `Ok(None)` if the tag does not change.
+/// * `Err(String)` if the tag could not be read, signed or written.
+fn retag(
+    repo: &Repo,
+    tip: &RefTip,
+    rewritten: &HashMap<String, String>,
+    tag_mapping: Option<&Mapping>,
+    sign: bool,
+) -> Result<Option<String>, String> {
+    let raw = match repo.cat_file("tag", &tip.object) {
+        Ok(r) => r,
+        Err(e) => return Err(format!("cannot read tag {}: {}", tip.name, e)),
+    };
+    let target = tip.peeled.as_ref().and_then(|(_, sha)| rewritten.get(sha));
+
+    let bytes = match tags::rewritten_tag(repo, &raw, target.map(|t| t.as_str()), tag_mapping, sign)
+    {
+        Ok(Some(b)) => b,
+        Ok(None) => return Ok(None),
+        Err(e) => return Err(format!("cannot rewrite tag {}: {}", tip.name, e)),
+    };
+    match repo.hash_object("tag", bytes) {
+        Ok(sha) => Ok(Some(sha)),
+        Err(e) => Err(format!("cannot write tag {}: {}", tip.name, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ref_tips, rewrite_commit, tip_commit, update_ref_commands, RefUpdate};
+    use crate::{
+        identity::{parse_domain_swap, Identity},
+        message::MessageEdits,
+    };
+    use std::collections::HashMap;
+
+    const SHA: &str = "3333333333333333333333333333333333333333";
+
+    const RAW: &[u8] = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+parent 1111111111111111111111111111111111111111\n\
+author Keep <keep@else.com> 1700000000 +0000\n\
+committer Keep <keep@else.com> 1700000000 +0000\n\
+\n\
+msg\n";
+
+    #[test]
+    fn parse_ref_tips_reads_peeled_objects() {
+        let tips = parse_ref_tips(
+            "refs/heads/main commit aaa  \n\
+             refs/tags/v1 tag bbb commit ccc\n\
+             refs/tags/tree-tag tag ddd tree eee\n\
+             refs/heads/last commit fff",
+        );
+        assert_eq!(tips.len(), 4);
+        assert_eq!(tip_commit(&tips[0]), Some("aaa"));
+        assert_eq!(tip_commit(&tips[1]), Some("ccc"));
+        assert_eq!(tip_commit(&tips[2]), None);
+        assert_eq!(tip_commit(&tips[3]), Some("fff"));
+        assert_eq!(tips[3].peeled, None);
+    }
+
+    #[test]
+    fn update_ref_commands_expect_the_old_values() {
+        let refs = vec![
+            RefUpdate {
+                name: String::from("refs/heads/main"),
+                old: String::from("aaa"),
+                new: String::from("bbb"),
+            },
+            RefUpdate {
+                name: String::from("refs/heads/topic"),
+                old: String::from("ccc"),
+                new: String::from("ddd"),
+            },
+        ];
+        assert_eq!(
+            update_ref_commands(&refs),
+            "update refs/heads/main bbb aaa\nupdate refs/heads/topic ddd ccc\n"
+        );
+    }
+
+    #[test]
+    fn rewrite_commit_follows_rewritten_parents() {
+        let mapping = parse_domain_swap("old.com=new.com").unwrap();
+        let me = Identity::new("Me", "me@x.com");
+        let edits = MessageEdits {
+            annotate: true,
+            ..MessageEdits::default()
+        };
+        assert_eq!(
+            rewrite_commit(RAW, SHA, Some(&mapping), &me, &edits, &HashMap::new()).unwrap(),
+            None
+        );
+
+        let mut rewritten = HashMap::new();
+        rewritten.insert(
+            String::from("1111111111111111111111111111111111111111"),
+            String::from("2222222222222222222222222222222222222222"),
+        );
+        let out = rewrite_commit(RAW, SHA, Some(&mapping), &me, &edits, &rewritten)
+            .unwrap()
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("parent 2222222222222222222222222222222222222222\n"));
+        assert!(text.contains("author Keep <keep@else.com> 1700000000 +0000\n"));
+        assert!(text.ends_with(&format!(
+            "msg\n\nRewritten-By: git-author-rewrite\nOriginal-Commit: {}\n",
+            SHA
+        )));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::git::{sh_single_quote, Repo};
+
+use std::{fs, path::PathBuf};
+
+/// First comment line of every hook written by `install-hook`, so a later
+/// install can tell its own hooks from the user's.
+const MARKER: &str = "# Installed by git-author-rewrite install-hook.";
+
+/// Which Git hook `install-hook` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    /// `pre-push`: checks every commit about to be pushed.
+    PrePush,
+    /// `commit-msg`: checks the author and committer of each new commit.
+    CommitMsg,
+}
+
+impl HookKind {
+    /// Every hook kind, in the order listed in messages.
+    pub const ALL: [HookKind; 2] = [HookKind::PrePush, HookKind::CommitMsg];
+
+    /// The hook's file name, as Git knows it.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookKind::PrePush => "pre-push",
+            HookKind::CommitMsg => "commit-msg",
+        }
+    }
+
+    /// Looks up a hook kind by its file name.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HookKind)` for `pre-push` or `commit-msg`.
+    /// * `Err(String)` for anything else.
+    pub fn parse(name: &str) -> Result<HookKind, String> {
+        match HookKind::ALL.iter().find(|k| k.name() == name) {
+            Some(k) => Ok(*k),
+            None => Err(format!(
+                "unknown hook `{}` (expected pre-push or commit-msg)",
+                name
+            )),
+        }
+    }
+}
+
+/// Builds the hook script for `kind`.
+///
+/// The script runs `git-author-rewrite check` from `PATH`, falling back to
+/// `exe` (the executable that installed it), with `--policy policy` if given
+/// and the policy file at the top of the working tree otherwise.
+pub(crate) fn script(kind: HookKind, exe: &str, policy: Option<&str>) -> String {
+    let mut check = String::from("\"$gar\" check");
+    if let Some(p) = policy {
+        check.push_str(" --policy ");
+        check.push_str(&sh_single_quote(p));
+    }
+
+    let body = match kind {
+        HookKind::PrePush => format!(
+            "# Blocks pushes of commits whose author or committer breaks the author policy.\n\
+             status=0\n\
+             while read -r local_ref local_sha remote_ref remote_sha; do\n\
+             \x20   # Deleting a ref pushes no commits.\n\
+             \x20   case \"$local_sha\" in *[!0]*) ;; *) continue ;; esac\n\
+             \x20   if git cat-file -e \"$remote_sha^{{commit}}\" 2>/dev/null; then\n\
+             \x20       revs=\"$remote_sha..$local_sha\"\n\
+             \x20   else\n\
+             \x20       revs=\"$local_sha --not --remotes=$1\"\n\
+             \x20   fi\n\
+             \x20   {} --revs \"$revs\" || status=1\n\
+             done\n\
+             exit $status\n",
+            check
+        ),
+        HookKind::CommitMsg => format!(
+            "# Blocks commits whose author or committer breaks the author policy.\n\
+             exec {} --identity \"$(git var GIT_AUTHOR_IDENT)\" --identity \"$(git var GIT_COMMITTER_IDENT)\"\n",
+            check
+        ),
+    };
+
+    format!(
+        "#!/bin/sh\n\
+         {}\n\
+         gar=git-author-rewrite\n\
+         command -v \"$gar\" >/dev/null 2>&1 || gar={}\n\
+         {}",
+        MARKER,
+        sh_single_quote(&exe.replace('\\', "/")),
+        body
+    )
+}
+
+/// Writes the `kind` hook into `repo`'s hooks directory (`core.hooksPath` if
+/// set) and makes it executable.
+///
+/// A hook the user wrote is only replaced with `force`; one written by an
+/// earlier `install-hook` is always updated.
+///
+/// # Parameters
+///
+/// * `repo` – The repository to protect.
+/// * `kind` – Which hook to write.
+/// * `exe` – The path of this executable, used if it is not on `PATH`.
+/// * `policy` – The policy file the hook checks against, if not the default.
+/// * `force` – Replace an existing hook that was not written by this tool.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` with the path of the hook.
+/// * `Err(String)` if another hook is in the way or the file could not be
+///   written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, hook::{self, HookKind}};
+///
+/// let repo = Repo::discover().unwrap();
+/// let path = hook::install(&repo, HookKind::PrePush, "/usr/local/bin/git-author-rewrite", None, false).unwrap();
+/// println!("Installed {}", path.display());
+/// ```
+pub fn install(
+    repo: &Repo,
+    kind: HookKind,
+    exe: &str,
+    policy: Option<&str>,
+    force: bool,
+) -> Result<PathBuf, String> {
+    let dir = match repo.hooks_dir() {
+        Ok(d) => d,
+        Err(e) => return Err(e),
+    };
+    let path = dir.join(kind.name());
+
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !force && !existing.contains(MARKER) {
+            return Err(format!(
+                "{} already exists; re-run with --force to replace it",
+                path.display()
+            ));
+        }
+    }
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        return Err(format!("cannot create {}: {}", dir.display(), e));
+    }
+    if let Err(e) = fs::write(&path, script(kind, exe, policy)) {
+        return Err(format!("cannot write {}: {}", path.display(), e));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = fs::set_permissions(&path, fs::Permissions::from_mode(0o755)) {
+            return Err(format!("cannot make {} executable: {}", path.display(), e));
+        }
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{script, HookKind, MARKER};
+
+    #[test]
+    fn hook_kinds_round_trip() {
+        for kind in HookKind::ALL {
+            assert_eq!(HookKind::parse(kind.name()), Ok(kind));
+        }
+        assert!(HookKind::parse("pre-commit").is_err());
+    }
+
+    #[test]
+    fn pre_push_checks_the_pushed_range() {
+        let s = script(HookKind::PrePush, "/opt/gar", Some("/srv/it's.policy"));
+        assert!(s.starts_with("#!/bin/sh\n"));
+        assert!(s.contains(MARKER));
+        assert!(s.contains("|| gar='/opt/gar'\n"));
+        assert!(s.contains(
+            "\"$gar\" check --policy '/srv/it'\\''s.policy' --revs \"$revs\" || status=1"
+        ));
+        assert!(s.contains("revs=\"$remote_sha..$local_sha\""));
+        assert!(s.contains("git cat-file -e \"$remote_sha^{commit}\""));
+    }
+
+    #[test]
+    fn commit_msg_checks_both_identities() {
+        let s = script(HookKind::CommitMsg, "C:\\tools\\gar.exe", None);
+        assert!(s.contains("|| gar='C:/tools/gar.exe'\n"));
+        assert!(s.ends_with(
+            "exec \"$gar\" check --identity \"$(git var GIT_AUTHOR_IDENT)\" --identity \"$(git var GIT_COMMITTER_IDENT)\"\n"
+        ));
+    }
+}
@@ -0,0 +1,492 @@
+use crate::{
+    git::{Commit, Repo},
+    report::HistogramRow,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+/// A commit the rewrite replaced, and what replaced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitChange {
+    /// The original commit.
+    pub old: Commit,
+    /// The rewritten commit, or `None` if the rewrite left it out
+    /// (`--drop-author`, `--skip-on-conflict`).
+    pub new: Option<Commit>,
+}
+
+/// Everything `--report-html` shows about a rewrite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The repository's working tree.
+    pub repository: String,
+    /// The branch that was rewritten, if `HEAD` is on one.
+    pub branch: Option<String>,
+    /// When the report was written, in UTC.
+    pub generated: String,
+    /// The command line, without the program name.
+    pub command: Vec<String>,
+    /// How identities were rewritten (see [`crate::identity::Mapping::describe`]).
+    pub mapping: String,
+    /// Commits per author before and after the rewrite.
+    pub authors: Vec<HistogramRow>,
+    /// The replaced commits, oldest first, or why they cannot be listed.
+    pub commits: Result<Vec<CommitChange>, String>,
+    /// What needs attention before the history is pushed.
+    pub warnings: Vec<String>,
+}
+
+/// Checks, before the rewrite, that a report can be written to `path`.
+///
+/// # Returns
+///
+/// * `Ok(())` if the directory exists and `path` is not a directory.
+/// * `Err(String)` otherwise.
+pub fn check_path(path: &Path) -> Result<(), String> {
+    if path.is_dir() {
+        return Err(format!("--report-html: {} is a directory.", path.display()));
+    }
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() && !dir.is_dir() => Err(format!(
+            "--report-html: the directory {} does not exist.",
+            dir.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Lines up the commits only the old history has with those only the new
+/// one has, both oldest first.
+///
+/// A rewrite keeps the shape of the history and every author date, so the
+/// n-th old commit became the n-th new one, once the commits in `left_out`
+/// (dropped or skipped, with nothing in their place) are set aside.
+///
+/// # Returns
+///
+/// * `Some(changes)` in the order of `old`.
+/// * `None` if the two lists do not line up.
+pub(crate) fn pair(
+    old: Vec<Commit>,
+    new: Vec<Commit>,
+    left_out: &HashSet<String>,
+) -> Option<Vec<CommitChange>> {
+    let kept = old.iter().filter(|c| !left_out.contains(&c.sha)).count();
+    if kept != new.len() {
+        return None;
+    }
+    let mut new = new.into_iter();
+    let mut changes = Vec::with_capacity(old.len());
+    for commit in old {
+        if left_out.contains(&commit.sha) {
+            changes.push(CommitChange {
+                old: commit,
+                new: None,
+            });
+            continue;
+        }
+        match new.next() {
+            Some(n) if n.author_time == commit.author_time => changes.push(CommitChange {
+                old: commit,
+                new: Some(n),
+            }),
+            _ => return None,
+        }
+    }
+    Some(changes)
+}
+
+/// Lists the commits reachable from `old_head` that the rewrite replaced
+/// with the ones now reachable from `HEAD`.
+///
+/// # Parameters
+///
+/// * `old_head` – Where `HEAD` pointed before the rewrite.
+/// * `skipped` – The commits `--skip-on-conflict` left out.
+/// * `drop_authors` – The `--drop-author` emails.
+///
+/// # Returns
+///
+/// * `Ok(Ok(changes))` oldest first.
+/// * `Ok(Err(reason))` if the old and new commits cannot be matched.
+/// * `Err(String)` if the history could not be read.
+pub fn commit_changes(
+    repo: &Repo,
+    old_head: &str,
+    skipped: &[String],
+    drop_authors: &[String],
+) -> Result<Result<Vec<CommitChange>, String>, String> {
+    let mut old = Vec::new();
+    let args = ["--topo-order", "--reverse", old_head, "--not", "HEAD"];
+    if let Err(e) = repo.each_commit(&args, |c| old.push(c)) {
+        return Err(e);
+    }
+    let mut new = Vec::new();
+    let args = ["--topo-order", "--reverse", "HEAD", "--not", old_head];
+    if let Err(e) = repo.each_commit(&args, |c| new.push(c)) {
+        return Err(e);
+    }
+
+    let mut left_out: HashSet<String> = skipped.iter().cloned().collect();
+    for commit in &old {
+        if drop_authors
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(&commit.author.email))
+        {
+            left_out.insert(commit.sha.clone());
+        }
+    }
+    let (old_count, new_count) = (old.len(), new.len());
+    match pair(old, new, &left_out) {
+        Some(changes) => Ok(Ok(changes)),
+        None => Ok(Err(format!(
+            "The {} original and {} rewritten commits could not be matched one to one, so the SHA mapping is left out.",
+            old_count, new_count
+        ))),
+    }
+}
+
+/// Lists the commits that `git replace` refs stand in for (`--replace-mode`),
+/// with their replacements.
+///
+/// # Returns
+///
+/// * `Ok(changes)` in the order of the replace refs.
+/// * `Err(String)` if the refs or commits could not be read.
+pub fn replaced_commits(repo: &Repo) -> Result<Vec<CommitChange>, String> {
+    let out = match repo.for_each_ref("%(refname:lstrip=2) %(objectname)", "refs/replace") {
+        Ok(o) => o,
+        Err(e) => return Err(e),
+    };
+    let pairs: Vec<(&str, &str)> = out.lines().filter_map(|l| l.split_once(' ')).collect();
+    if pairs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Without this, Git shows the replacement under the original's name.
+    let plain = repo.clone().with_env("GIT_NO_REPLACE_OBJECTS", "1");
+    let mut commits: HashMap<String, Commit> = HashMap::new();
+    let mut args = vec!["--no-walk=unsorted"];
+    args.extend(pairs.iter().flat_map(|(old, new)| [*old, *new]));
+    if let Err(e) = plain.each_commit(&args, |c| {
+        commits.insert(c.sha.clone(), c);
+    }) {
+        return Err(e);
+    }
+    Ok(pairs
+        .iter()
+        .filter_map(|(old, new)| {
+            commits.get(*old).map(|o| CommitChange {
+                old: o.clone(),
+                new: commits.get(*new).cloned(),
+            })
+        })
+        .collect())
+}
+
+/// Counts the identity changes in `commits`: `(author before, author
+/// after)` and how many commits went from one to the other. Commits left
+/// out have `None` after.
+pub(crate) fn identity_changes(
+    commits: &[CommitChange],
+) -> BTreeMap<(String, Option<String>), usize> {
+    let mut changes = BTreeMap::new();
+    for change in commits {
+        let before = change.old.author.to_string();
+        let after = change.new.as_ref().map(|n| n.author.to_string());
+        if after.as_deref() == Some(before.as_str()) {
+            continue;
+        }
+        *changes.entry((before, after)).or_insert(0) += 1;
+    }
+    changes
+}
+
+/// Escapes `text` for HTML element content and attribute values.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::html_report::escape;
+///
+/// assert_eq!(escape("Jane <jane@x.com>"), "Jane &lt;jane@x.com&gt;");
+/// ```
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// The report's stylesheet, inlined so the page stands alone.
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+h1{font-size:1.5em}h2{font-size:1.2em;margin-top:2em;border-bottom:1px solid #ccc}\
+table{border-collapse:collapse;font-size:.9em}th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}\
+th{background:#f3f3f3}td.n{text-align:right}code{font-family:ui-monospace,monospace}\
+.up{color:#1a7f37}.down{color:#cf222e}.warn{color:#9a6700}dl{display:grid;grid-template-columns:max-content 1fr;gap:.2em 1em}\
+dt{font-weight:bold}";
+
+/// Renders `report` as a standalone HTML page.
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Author rewrite report: {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(&report.repository),
+        STYLE
+    ));
+    html.push_str("<h1>Author rewrite report</h1>\n<dl>\n");
+    let mut fact = |name: &str, value: &str| {
+        html.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", name, value));
+    };
+    fact(
+        "Repository",
+        &format!("<code>{}</code>", escape(&report.repository)),
+    );
+    if let Some(branch) = &report.branch {
+        fact("Branch", &format!("<code>{}</code>", escape(branch)));
+    }
+    fact("Generated", &escape(&report.generated));
+    fact(
+        "Tool",
+        &format!("git-author-rewrite {}", env!("CARGO_PKG_VERSION")),
+    );
+    fact(
+        "Command",
+        &format!(
+            "<code>git-author-rewrite {}</code>",
+            escape(&report.command.join(" "))
+        ),
+    );
+    fact("Identity mapping", &escape(&report.mapping));
+    if let Ok(commits) = &report.commits {
+        let dropped = commits.iter().filter(|c| c.new.is_none()).count();
+        fact(
+            "Commits replaced",
+            &format!("{} ({} left out)", commits.len(), dropped),
+        );
+    }
+    html.push_str("</dl>\n");
+
+    html.push_str("<h2>Warnings</h2>\n");
+    if report.warnings.is_empty() {
+        html.push_str("<p>None.</p>\n");
+    } else {
+        html.push_str("<ul>\n");
+        for warning in &report.warnings {
+            html.push_str(&format!("<li class=\"warn\">{}</li>\n", escape(warning)));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<h2>Identity changes</h2>\n");
+    match &report.commits {
+        Ok(commits) => {
+            let changes = identity_changes(commits);
+            if changes.is_empty() {
+                html.push_str("<p>No commit changed author.</p>\n");
+            } else {
+                html.push_str(
+                    "<table>\n<tr><th>Author before</th><th>Author after</th><th>Commits</th></tr>\n",
+                );
+                for ((before, after), count) in changes {
+                    let after = match after {
+                        Some(a) => escape(&a),
+                        None => String::from("<em>left out</em>"),
+                    };
+                    html.push_str(&format!(
+                        "<tr><td>{}</td><td>{}</td><td class=\"n\">{}</td></tr>\n",
+                        escape(&before),
+                        after,
+                        count
+                    ));
+                }
+                html.push_str("</table>\n");
+            }
+        }
+        Err(reason) => html.push_str(&format!("<p>{}</p>\n", escape(reason))),
+    }
+
+    html.push_str("<h2>Commits per author</h2>\n");
+    html.push_str(
+        "<table>\n<tr><th>Author</th><th>Before</th><th>After</th><th>Change</th></tr>\n",
+    );
+    for row in &report.authors {
+        let delta = row.after as i64 - row.before as i64;
+        let change = match delta {
+            0 => String::new(),
+            d if d > 0 => format!("<span class=\"up\">{:+}</span>", d),
+            d => format!("<span class=\"down\">{:+}</span>", d),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td></tr>\n",
+            escape(&row.identity),
+            row.before,
+            row.after,
+            change
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>SHA mapping</h2>\n");
+    match &report.commits {
+        Ok(commits) if commits.is_empty() => html.push_str("<p>No commit was replaced.</p>\n"),
+        Ok(commits) => {
+            html.push_str("<table>\n<tr><th>Original</th><th>Rewritten</th><th>Author before</th><th>Author after</th><th>Subject</th></tr>\n");
+            for change in commits {
+                let (new_sha, new_author) = match &change.new {
+                    Some(n) => (
+                        format!("<code>{}</code>", escape(&n.sha)),
+                        escape(&n.author.to_string()),
+                    ),
+                    None => (String::from("<em>left out</em>"), String::new()),
+                };
+                html.push_str(&format!(
+                    "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape(&change.old.sha),
+                    new_sha,
+                    escape(&change.old.author.to_string()),
+                    new_author,
+                    escape(&change.old.subject)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+        Err(reason) => html.push_str(&format!("<p>{}</p>\n", escape(reason))),
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Writes `report` to `path` as a standalone HTML page, replacing the file.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if the file could not be written.
+pub fn write(path: &Path, report: &Report) -> Result<(), String> {
+    match fs::write(path, render(report)) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("cannot write {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{identity_changes, pair, render, CommitChange, Report};
+    use crate::git::Commit;
+    use crate::identity::Identity;
+    use crate::report::HistogramRow;
+    use std::collections::HashSet;
+
+    fn commit(sha: &str, email: &str, time: i64) -> Commit {
+        let id = Identity::new("Dev", email);
+        Commit {
+            sha: sha.to_string(),
+            author: id.clone(),
+            committer: id,
+            author_time: time,
+            commit_time: time,
+            subject: format!("commit {}", sha),
+        }
+    }
+
+    #[test]
+    fn pair_matches_by_position_around_left_out_commits() {
+        let old = vec![
+            commit("a1", "old@x.com", 1),
+            commit("b1", "drop@x.com", 2),
+            commit("c1", "old@x.com", 3),
+        ];
+        let new = vec![commit("a2", "new@x.com", 1), commit("c2", "new@x.com", 3)];
+        let left_out = HashSet::from([String::from("b1")]);
+        let changes = pair(old.clone(), new.clone(), &left_out).expect("not paired");
+        let shas: Vec<(&str, Option<&str>)> = changes
+            .iter()
+            .map(|c| (c.old.sha.as_str(), c.new.as_ref().map(|n| n.sha.as_str())))
+            .collect();
+        assert_eq!(
+            shas,
+            vec![("a1", Some("a2")), ("b1", None), ("c1", Some("c2"))]
+        );
+
+        let counts = identity_changes(&changes);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(
+            counts[&(
+                String::from("Dev <old@x.com>"),
+                Some(String::from("Dev <new@x.com>"))
+            )],
+            2
+        );
+
+        // Without the left-out commit, or with dates that moved, nothing lines up.
+        assert!(pair(old.clone(), new.clone(), &HashSet::new()).is_none());
+        let shifted = vec![commit("a2", "new@x.com", 1), commit("c2", "new@x.com", 9)];
+        assert!(pair(old, shifted, &left_out).is_none());
+    }
+
+    #[test]
+    fn render_escapes_everything_from_the_repository() {
+        let mut old = commit("a1", "old@x.com", 1);
+        old.subject = String::from("Fix <script>alert(1)</script> & more");
+        let report = Report {
+            repository: String::from("/work/a&b"),
+            branch: Some(String::from("main")),
+            generated: String::from("2026-03-01T09:30:00Z"),
+            command: vec![String::from("--email"), String::from("new@x.com")],
+            mapping: String::from("all commits → Dev <new@x.com>"),
+            authors: vec![HistogramRow {
+                identity: String::from("Dev <old@x.com>"),
+                before: 1,
+                after: 0,
+            }],
+            commits: Ok(vec![CommitChange {
+                old,
+                new: Some(commit("a2", "new@x.com", 1)),
+            }]),
+            warnings: vec![String::from("1 commit was already pushed")],
+        };
+        let html = render(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Fix &lt;script&gt;alert(1)&lt;/script&gt; &amp; more"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("/work/a&amp;b"));
+        assert!(html.contains("Dev &lt;old@x.com&gt;"));
+        assert!(html.contains("<span class=\"down\">-1</span>"));
+        assert!(html.contains("<li class=\"warn\">1 commit was already pushed</li>"));
+    }
+
+    #[test]
+    fn render_explains_a_missing_sha_mapping() {
+        let report = Report {
+            repository: String::from("/work/app"),
+            branch: None,
+            generated: String::from("2026-03-01T09:30:00Z"),
+            command: Vec::new(),
+            mapping: String::from("all commits → Dev <new@x.com>"),
+            authors: Vec::new(),
+            commits: Err(String::from("The original commits are not known.")),
+            warnings: Vec::new(),
+        };
+        let html = render(&report);
+        assert_eq!(
+            html.matches("The original commits are not known.").count(),
+            2
+        );
+        assert!(html.contains("<p>None.</p>"));
+    }
+}
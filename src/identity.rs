@@ -0,0 +1,538 @@
+use crate::{
+    git::Repo,
+    rules::{self, Rule},
+};
+
+use std::{collections::HashMap, fmt};
+
+/// A Git identity: a name and an email address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+impl Identity {
+    /// Creates an identity from a name and an email.
+    pub fn new(name: &str, email: &str) -> Identity {
+        Identity {
+            name: name.to_string(),
+            email: email.to_string(),
+        }
+    }
+
+    /// Reports whether the identity holds U+FFFD, which is what bytes that
+    /// are not valid UTF-8 (a legacy encoding) turn into when Git's output
+    /// is decoded. Writing such an identity back would lose the original bytes.
+    pub fn is_lossy(&self) -> bool {
+        self.name.contains(char::REPLACEMENT_CHARACTER)
+            || self.email.contains(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+impl fmt::Display for Identity {
+    /// Formats the identity the way Git expects for `--author`: `Name <email>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+/// The author and committer of a single commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitIdentities {
+    pub author: Identity,
+    pub committer: Identity,
+    /// The author date in Unix seconds, when known.
+    pub author_time: Option<i64>,
+    /// The full SHA of the commit, when known; only [`Mapping::Shas`] needs it.
+    pub sha: Option<String>,
+}
+
+/// How the identities of rewritten commits are derived from the original ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mapping {
+    /// Every rewritten commit gets the same author; the committer comes from
+    /// the (updated) local git config.
+    Fixed(Identity),
+    /// Every rewritten commit's author gets this name, keeping its email.
+    NameOnly(String),
+    /// Every rewritten commit's author gets this email, keeping its name.
+    EmailOnly(String),
+    /// Every rewritten commit's author is built from templates such as
+    /// `{login}@newcorp.com`, expanded from the original author (see
+    /// [`expand_template`]).
+    Template { name: String, email: String },
+    /// Author and committer emails at domain `from` move to domain `to`,
+    /// keeping names and local parts.
+    Domain { from: String, to: String },
+    /// Each commit's author comes from the first matching rule of a
+    /// `--rules` file, which may depend on the author date.
+    Rules(Vec<Rule>),
+    /// The commits listed in a `--sha-map` file, by full lowercase SHA, get
+    /// the author given for them; every other commit is left alone.
+    Shas(HashMap<String, Identity>),
+    /// Each rewritten commit's committer becomes its author, after the
+    /// wrapped mapping (if any) has rewritten the author (`--sync-committer`).
+    SyncCommitter(Option<Box<Mapping>>),
+}
+
+impl Mapping {
+    /// Returns the new identity for `original`, or `None` if it is unaffected.
+    ///
+    /// # Parameters
+    ///
+    /// * `original` – The identity recorded in the original commit.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Identity)` with the replacement identity.
+    /// * `None` if this mapping leaves `original` unchanged.
+    pub fn apply(&self, original: &Identity) -> Option<Identity> {
+        self.apply_at(original, None)
+    }
+
+    /// Like [`Mapping::apply`], for an identity recorded at `time` (Unix
+    /// seconds). Only [`Mapping::Rules`] looks at the time; its date
+    /// conditions never match an unknown time.
+    ///
+    /// [`Mapping::Shas`] depends on the commit rather than the identity, so it
+    /// leaves every identity alone here; see [`Mapping::plan`].
+    pub fn apply_at(&self, original: &Identity, time: Option<i64>) -> Option<Identity> {
+        match self {
+            Mapping::Fixed(id) => Some(id.clone()),
+            Mapping::NameOnly(name) => {
+                if &original.name == name {
+                    return None;
+                }
+                Some(Identity::new(name, &original.email))
+            }
+            Mapping::EmailOnly(email) => {
+                if &original.email == email {
+                    return None;
+                }
+                Some(Identity::new(&original.name, email))
+            }
+            Mapping::Template { name, email } => {
+                let expanded = Identity {
+                    name: expand_template(name, original),
+                    email: expand_template(email, original),
+                };
+                if &expanded == original {
+                    return None;
+                }
+                Some(expanded)
+            }
+            Mapping::Domain { from, to } => match original.email.rsplit_once('@') {
+                Some((local, domain)) if domain.eq_ignore_ascii_case(from) => Some(Identity {
+                    name: original.name.clone(),
+                    email: format!("{}@{}", local, to),
+                }),
+                _ => None,
+            },
+            Mapping::Rules(list) => {
+                rules::resolve(list, original, time).filter(|n| n != original)
+            }
+            Mapping::Shas(_) => None,
+            Mapping::SyncCommitter(inner) => {
+                inner.as_ref().and_then(|m| m.apply_at(original, time))
+            }
+        }
+    }
+
+    /// Reports whether this mapping also rewrites committer identities.
+    ///
+    /// Mappings that set a new author identity leave the committer to Git,
+    /// which records the current user; the domain swap rewrites the original
+    /// committer as well.
+    pub fn maps_committer(&self) -> bool {
+        matches!(self, Mapping::Domain { .. } | Mapping::SyncCommitter(_))
+    }
+
+    /// Computes the author and committer to record for a rewritten commit.
+    ///
+    /// # Parameters
+    ///
+    /// * `original` – The identities recorded in the original commit.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((author, committer))` if anything changes; `committer` is `None`
+    ///   when Git should record the current user as usual.
+    /// * `None` if the commit is unaffected by this mapping.
+    pub fn plan(&self, original: &CommitIdentities) -> Option<(Identity, Option<Identity>)> {
+        if let Mapping::SyncCommitter(inner) = self {
+            let author = match inner.as_ref().and_then(|m| m.plan(original)) {
+                Some((author, _)) => author,
+                None => original.author.clone(),
+            };
+            if author == original.author && author == original.committer {
+                return None;
+            }
+            return Some((author.clone(), Some(author)));
+        }
+        if let Mapping::Shas(map) = self {
+            return original
+                .sha
+                .as_ref()
+                .and_then(|sha| map.get(sha))
+                .filter(|author| *author != &original.author)
+                .map(|author| (author.clone(), None));
+        }
+
+        let author = self.apply_at(&original.author, original.author_time);
+
+        if !self.maps_committer() {
+            return author.map(|a| (a, None));
+        }
+
+        let committer = self.apply(&original.committer);
+        if author.is_none() && committer.is_none() {
+            return None;
+        }
+
+        Some((
+            author.unwrap_or_else(|| original.author.clone()),
+            Some(committer.unwrap_or_else(|| original.committer.clone())),
+        ))
+    }
+
+    /// Describes the mapping for the banner, e.g. `New author will be set to: …`.
+    pub fn describe(&self) -> String {
+        match self {
+            Mapping::Fixed(id) => format!("New author will be set to: {}", id),
+            Mapping::NameOnly(name) => {
+                format!("Author names will be set to: {} (emails kept)", name)
+            }
+            Mapping::EmailOnly(email) => {
+                format!("Author emails will be set to: {} (names kept)", email)
+            }
+            Mapping::Template { name, email } => {
+                format!(
+                    "New author will be set to: {} <{}> (per commit)",
+                    name, email
+                )
+            }
+            Mapping::Domain { from, to } => {
+                format!("Author/committer emails @{} will become @{}", from, to)
+            }
+            Mapping::Rules(list) => format!(
+                "Authors will be set by {} mapping rule(s) (per commit)",
+                list.len()
+            ),
+            Mapping::Shas(map) => format!(
+                "Authors will be set for {} listed commit(s) (per commit)",
+                map.len()
+            ),
+            Mapping::SyncCommitter(None) => {
+                String::from("Committers will be set to each commit's author")
+            }
+            Mapping::SyncCommitter(Some(inner)) => {
+                format!("{}; committers will match authors", inner.describe())
+            }
+        }
+    }
+}
+
+/// Placeholders understood by [`expand_template`].
+pub const PLACEHOLDERS: [&str; 4] = ["{name}", "{email}", "{login}", "{domain}"];
+
+/// Reports whether `value` contains any template placeholder.
+pub fn is_template(value: &str) -> bool {
+    PLACEHOLDERS.iter().any(|p| value.contains(p))
+}
+
+/// Checks that every `{...}` in `template` is a known placeholder.
+///
+/// # Returns
+///
+/// * `Ok(())` if the template is valid.
+/// * `Err(String)` naming the first unknown or unterminated placeholder.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let tail = &rest[start..];
+        let end = match tail.find('}') {
+            Some(e) => e,
+            None => return Err(format!("unterminated placeholder in `{}`", template)),
+        };
+        let placeholder = &tail[..=end];
+        if !PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!(
+                "unknown placeholder `{}` (expected one of {})",
+                placeholder,
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+        rest = &tail[end + 1..];
+    }
+    Ok(())
+}
+
+/// Expands the placeholders in `template` from an original identity.
+///
+/// * `{name}` – the original name
+/// * `{email}` – the original email
+/// * `{login}` – the part of the original email before `@`
+/// * `{domain}` – the part of the original email after `@`
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::identity::{expand_template, Identity};
+///
+/// let original = Identity::new("Ann Lee", "ann.lee@oldcorp.com");
+/// assert_eq!(expand_template("{login}@newcorp.com", &original), "ann.lee@newcorp.com");
+/// ```
+pub fn expand_template(template: &str, original: &Identity) -> String {
+    let (login, domain) = match original.email.rsplit_once('@') {
+        Some((l, d)) => (l, d),
+        None => (original.email.as_str(), ""),
+    };
+
+    template
+        .replace("{name}", &original.name)
+        .replace("{email}", &original.email)
+        .replace("{login}", login)
+        .replace("{domain}", domain)
+}
+
+/// Parses a `--rewrite-domain` value of the form `old.com=new.com`.
+///
+/// A leading `@` on either side is accepted and stripped.
+///
+/// # Returns
+///
+/// * `Ok(Mapping::Domain)` on success.
+/// * `Err(String)` if the value is not two non-empty domains separated by `=`.
+pub fn parse_domain_swap(spec: &str) -> Result<Mapping, String> {
+    match spec.split_once('=') {
+        Some((from, to)) => {
+            let from = from.trim().trim_start_matches('@');
+            let to = to.trim().trim_start_matches('@');
+            if from.is_empty() || to.is_empty() || from.contains('@') || to.contains('@') {
+                return Err(format!(
+                    "invalid --rewrite-domain `{}` (expected old.com=new.com)",
+                    spec
+                ));
+            }
+            Ok(Mapping::Domain {
+                from: from.to_string(),
+                to: to.to_string(),
+            })
+        }
+        None => Err(format!(
+            "invalid --rewrite-domain `{}` (expected old.com=new.com)",
+            spec
+        )),
+    }
+}
+
+/// The `git log` format read by [`parse_commit_identities`].
+pub(crate) const IDENTITY_FORMAT: &str = "%an%x00%ae%x00%cn%x00%ce%x00%at";
+
+/// Parses [`IDENTITY_FORMAT`] output into commit identities. The trailing
+/// author date is optional.
+pub(crate) fn parse_commit_identities(line: &str) -> Option<CommitIdentities> {
+    let fields: Vec<&str> = line.split('\0').collect();
+    if fields.len() < 4 {
+        return None;
+    }
+
+    Some(CommitIdentities {
+        author: Identity::new(fields[0], fields[1]),
+        committer: Identity::new(fields[2], fields[3]),
+        author_time: fields.get(4).and_then(|t| t.trim().parse().ok()),
+        sha: None,
+    })
+}
+
+/// Reads the author, committer and full SHA of the commit named by `rev`.
+///
+/// # Returns
+///
+/// * `Ok(CommitIdentities)` on success.
+/// * `Err(String)` if `git log` failed or printed something unexpected.
+pub fn read_commit_identities(repo: &Repo, rev: &str) -> Result<CommitIdentities, String> {
+    let format = format!("%H%x00{}", IDENTITY_FORMAT);
+    let out = match repo.log(&format, &["-1", rev]) {
+        Ok(out) => out,
+        Err(e) => return Err(e),
+    };
+    let parsed = out.split_once('\0').and_then(|(sha, rest)| {
+        parse_commit_identities(rest).map(|mut ids| {
+            ids.sha = Some(sha.trim().to_string());
+            ids
+        })
+    });
+    match parsed {
+        Some(ids) => Ok(ids),
+        None => Err(format!("cannot read identities of {}", rev)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        expand_template, is_template, parse_commit_identities, parse_domain_swap,
+        validate_template, CommitIdentities, Identity, Mapping,
+    };
+
+    fn ids(author: (&str, &str), committer: (&str, &str)) -> CommitIdentities {
+        CommitIdentities {
+            author: Identity::new(author.0, author.1),
+            committer: Identity::new(committer.0, committer.1),
+            author_time: None,
+            sha: None,
+        }
+    }
+
+    #[test]
+    fn lossy_identities_are_detected() {
+        assert!(!Identity::new("Jürgen", "j@x.com").is_lossy());
+        let decoded = String::from_utf8_lossy(b"J\xfcrgen");
+        assert!(Identity::new(&decoded, "j@x.com").is_lossy());
+    }
+
+    #[test]
+    fn identity_displays_as_author_string() {
+        assert_eq!(
+            Identity::new("Jane", "j@x.org").to_string(),
+            "Jane <j@x.org>"
+        );
+    }
+
+    #[test]
+    fn domain_swap_keeps_name_and_local_part() {
+        let m = parse_domain_swap("oldcorp.com=newcorp.com").expect("parse failed");
+        let out = m.apply(&Identity::new("Ann", "ann.lee@OldCorp.com"));
+        assert_eq!(out, Some(Identity::new("Ann", "ann.lee@newcorp.com")));
+        assert_eq!(m.apply(&Identity::new("Bob", "bob@other.com")), None);
+        assert_eq!(m.apply(&Identity::new("Sub", "s@dev.oldcorp.com")), None);
+    }
+
+    #[test]
+    fn domain_swap_rejects_malformed_specs() {
+        assert!(parse_domain_swap("oldcorp.com").is_err());
+        assert!(parse_domain_swap("=new.com").is_err());
+        assert!(parse_domain_swap("a@old.com=new.com").is_err());
+        assert!(parse_domain_swap("@old.com=@new.com").is_ok());
+    }
+
+    #[test]
+    fn fixed_plan_leaves_committer_to_git() {
+        let m = Mapping::Fixed(Identity::new("New", "new@x"));
+        let plan = m.plan(&ids(("Old", "old@x"), ("Old", "old@x")));
+        assert_eq!(plan, Some((Identity::new("New", "new@x"), None)));
+    }
+
+    #[test]
+    fn domain_plan_maps_committer_independently() {
+        let m = parse_domain_swap("old.com=new.com").expect("parse failed");
+        let plan = m.plan(&ids(("A", "a@other.com"), ("Bot", "bot@old.com")));
+        assert_eq!(
+            plan,
+            Some((
+                Identity::new("A", "a@other.com"),
+                Some(Identity::new("Bot", "bot@new.com"))
+            ))
+        );
+        assert_eq!(m.plan(&ids(("A", "a@x.com"), ("B", "b@y.com"))), None);
+    }
+
+    #[test]
+    fn sync_committer_plan_copies_the_new_author() {
+        let m = Mapping::SyncCommitter(Some(Box::new(Mapping::NameOnly(String::from("Jane")))));
+        let plan = m.plan(&ids(("jdoe", "jane@x.org"), ("Bot", "bot@ci")));
+        let jane = Identity::new("Jane", "jane@x.org");
+        assert_eq!(plan, Some((jane.clone(), Some(jane))));
+
+        let m = Mapping::SyncCommitter(None);
+        let plan = m.plan(&ids(("Ann", "ann@x"), ("Bot", "bot@ci")));
+        let ann = Identity::new("Ann", "ann@x");
+        assert_eq!(plan, Some((ann.clone(), Some(ann))));
+        assert_eq!(m.plan(&ids(("Ann", "ann@x"), ("Ann", "ann@x"))), None);
+    }
+
+    #[test]
+    fn name_only_keeps_original_email() {
+        let m = Mapping::NameOnly(String::from("Jane Doe"));
+        let plan = m.plan(&ids(("jdoe", "jane@x.org"), ("jdoe", "jane@x.org")));
+        assert_eq!(plan, Some((Identity::new("Jane Doe", "jane@x.org"), None)));
+        assert_eq!(m.apply(&Identity::new("Jane Doe", "other@x.org")), None);
+    }
+
+    #[test]
+    fn email_only_keeps_original_name() {
+        let m = Mapping::EmailOnly(String::from("jane@new.org"));
+        let out = m.apply(&Identity::new("Jane", "jane@old.org"));
+        assert_eq!(out, Some(Identity::new("Jane", "jane@new.org")));
+    }
+
+    #[test]
+    fn template_expands_per_commit() {
+        let m = Mapping::Template {
+            name: String::from("{name}"),
+            email: String::from("{login}@newcorp.com"),
+        };
+        let out = m.apply(&Identity::new("Ann", "ann@oldcorp.com"));
+        assert_eq!(out, Some(Identity::new("Ann", "ann@newcorp.com")));
+        assert_eq!(m.apply(&Identity::new("Bob", "bob@newcorp.com")), None);
+    }
+
+    #[test]
+    fn template_expansion_without_at_sign() {
+        let original = Identity::new("Bot", "localbot");
+        assert_eq!(expand_template("{login}+{domain}", &original), "localbot+");
+    }
+
+    #[test]
+    fn template_validation() {
+        assert!(is_template("{login}@x.com"));
+        assert!(!is_template("plain@x.com"));
+        assert!(validate_template("{name} ({login})").is_ok());
+        assert!(validate_template("{nope}@x.com").is_err());
+        assert!(validate_template("{login@x.com").is_err());
+    }
+
+    #[test]
+    fn parses_commit_identities() {
+        let parsed = parse_commit_identities("A\0a@x\0C\0c@x").expect("parse failed");
+        assert_eq!(parsed, ids(("A", "a@x"), ("C", "c@x")));
+        assert!(parse_commit_identities("A\0a@x").is_none());
+        let timed =
+            parse_commit_identities("A\0a@x\0C\0c@x\x001700000000\n").expect("parse failed");
+        assert_eq!(timed.author_time, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn rules_plan_uses_the_author_date() {
+        let m = Mapping::Rules(
+            crate::rules::parse(
+                "old@x before=2020-01-01 => Ann <ann@x>\nold@x => Bo <bo@x>\nann@x => Ann <ann@x>\n",
+            )
+            .expect("parse failed"),
+        );
+        let mut old = ids(("Old", "old@x"), ("Old", "old@x"));
+        old.author_time = Some(1_500_000_000);
+        assert_eq!(m.plan(&old), Some((Identity::new("Ann", "ann@x"), None)));
+        old.author_time = Some(1_600_000_000);
+        assert_eq!(m.plan(&old), Some((Identity::new("Bo", "bo@x"), None)));
+        assert_eq!(m.plan(&ids(("Ann", "ann@x"), ("Ann", "ann@x"))), None);
+    }
+
+    #[test]
+    fn sha_map_plan_goes_by_the_commit() {
+        let jane = Identity::new("Jane", "jane@x");
+        let m = Mapping::Shas([(String::from("aaa"), jane.clone())].into_iter().collect());
+        let mut listed = ids(("Old", "old@x"), ("Old", "old@x"));
+        assert_eq!(m.plan(&listed), None);
+        listed.sha = Some(String::from("aaa"));
+        assert_eq!(m.plan(&listed), Some((jane.clone(), None)));
+        listed.sha = Some(String::from("bbb"));
+        assert_eq!(m.plan(&listed), None);
+
+        let synced = Mapping::SyncCommitter(Some(Box::new(m)));
+        listed.sha = Some(String::from("aaa"));
+        assert_eq!(synced.plan(&listed), Some((jane.clone(), Some(jane))));
+    }
+}
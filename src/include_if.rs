@@ -0,0 +1,194 @@
+use crate::git;
+
+use std::{
+    env,
+    path::{self, Path, PathBuf},
+};
+
+/// Where `--include-if` saved the identity for a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopedIdentity {
+    /// The `includeIf` condition added to the global config, e.g.
+    /// `gitdir:/home/jane/work/`.
+    pub condition: String,
+    /// The config file holding the identity, e.g. `~/.gitconfig-work`.
+    pub file: PathBuf,
+}
+
+/// Builds the `gitdir:` condition matching every repository under `dir`.
+///
+/// A leading `~/` is kept for Git to expand; other paths are made absolute
+/// against the current directory. The trailing `/` makes the pattern match
+/// the whole tree below `dir`.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the condition, e.g. `gitdir:~/work/`.
+/// * `Err(String)` if `dir` is empty or cannot be made absolute.
+pub fn gitdir_condition(dir: &str) -> Result<String, String> {
+    if dir.is_empty() {
+        return Err(String::from("--include-if needs a directory"));
+    }
+    let mut pattern = if dir == "~" || dir.starts_with("~/") {
+        dir.to_string()
+    } else {
+        match path::absolute(dir) {
+            Ok(p) => p.to_string_lossy().into_owned(),
+            Err(e) => return Err(format!("invalid directory {}: {}", dir, e)),
+        }
+    };
+    if !pattern.ends_with('/') {
+        pattern.push('/');
+    }
+    Ok(format!("gitdir:{}", pattern))
+}
+
+/// Names the identity file for `dir` after its last component:
+/// `~/work` → `.gitconfig-work`.
+///
+/// Characters other than ASCII letters, digits, `-` and `_` are replaced by
+/// `-`, so the name is safe on every platform.
+pub(crate) fn identity_file_name(dir: &str) -> String {
+    let last = dir
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or("");
+    let slug: String = last
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        String::from(".gitconfig-identity")
+    } else {
+        format!(".gitconfig-{}", slug)
+    }
+}
+
+/// The user's home directory, where the identity files are kept next to
+/// `~/.gitconfig`.
+fn home() -> Result<PathBuf, String> {
+    match env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        Some(h) if !h.is_empty() => Ok(PathBuf::from(h)),
+        _ => Err(String::from(
+            "cannot find the home directory (HOME is not set)",
+        )),
+    }
+}
+
+/// Saves the identity for every repository under `dir`, the pattern Git
+/// recommends for keeping work and personal identities apart.
+///
+/// The fields that are `Some` are written to a per-directory config file in
+/// the home directory (see [`identity_file_name`]), and an
+/// `[includeIf "gitdir:<dir>/"]` section pointing at it is added to the
+/// global config. Running again for the same directory updates both in
+/// place.
+///
+/// # Parameters
+///
+/// * `dir` – The directory whose repositories use the identity.
+/// * `name` – The `user.name` to save, if any.
+/// * `email` – The `user.email` to save, if any.
+///
+/// # Returns
+///
+/// * `Ok(ScopedIdentity)` describing what was written.
+/// * `Err(String)` if a config file could not be written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it modifies the user's global Git configuration.
+/// use git_author_rewrite::include_if;
+///
+/// let scoped = include_if::install("~/work", Some("Jane Doe"), Some("jane@corp.com")).unwrap();
+/// println!("{} → {}", scoped.condition, scoped.file.display());
+/// ```
+pub fn install(
+    dir: &str,
+    name: Option<&str>,
+    email: Option<&str>,
+) -> Result<ScopedIdentity, String> {
+    let condition = match gitdir_condition(dir) {
+        Ok(c) => c,
+        Err(e) => return Err(e),
+    };
+    let file = match home() {
+        Ok(h) => h.join(identity_file_name(dir)),
+        Err(e) => return Err(e),
+    };
+
+    for (key, value) in [("user.name", name), ("user.email", email)] {
+        if let Some(v) = value {
+            if let Err(e) = git::config_set_file(&file, key, v) {
+                return Err(format!("cannot write {}: {}", file.display(), e));
+            }
+        }
+    }
+    if let Err(e) = git::config_set_global(&include_key(&condition), &path_value(&file)) {
+        return Err(format!("cannot add the includeIf section: {}", e));
+    }
+
+    Ok(ScopedIdentity { condition, file })
+}
+
+/// The global config key of the include for `condition`.
+fn include_key(condition: &str) -> String {
+    format!("includeIf.{}.path", condition)
+}
+
+/// Formats `file` for `include.path`, which Git reads with forward slashes.
+fn path_value(file: &Path) -> String {
+    file.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gitdir_condition, identity_file_name, include_key};
+
+    #[test]
+    fn gitdir_condition_keeps_tilde_and_adds_a_trailing_slash() {
+        assert_eq!(gitdir_condition("~/work").unwrap(), "gitdir:~/work/");
+        assert_eq!(
+            gitdir_condition("/srv/repos/").unwrap(),
+            "gitdir:/srv/repos/"
+        );
+        assert!(gitdir_condition("").is_err());
+    }
+
+    #[test]
+    fn gitdir_condition_makes_relative_paths_absolute() {
+        let condition = gitdir_condition("work").unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            condition,
+            format!("gitdir:{}/", cwd.join("work").to_string_lossy())
+        );
+    }
+
+    #[test]
+    fn identity_file_name_uses_the_last_component() {
+        assert_eq!(identity_file_name("~/work/"), ".gitconfig-work");
+        assert_eq!(
+            identity_file_name("/srv/Client Projects"),
+            ".gitconfig-Client-Projects"
+        );
+        assert_eq!(identity_file_name("/"), ".gitconfig-identity");
+    }
+
+    #[test]
+    fn include_key_wraps_the_condition() {
+        assert_eq!(
+            include_key("gitdir:~/work/"),
+            "includeIf.gitdir:~/work/.path"
+        );
+    }
+}
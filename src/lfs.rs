@@ -0,0 +1,70 @@
+use crate::git::Repo;
+
+use std::fs;
+
+/// Environment variable that makes Git LFS leave pointer files in the
+/// working tree instead of downloading their content on checkout.
+pub const SKIP_SMUDGE_ENV: &str = "GIT_LFS_SKIP_SMUDGE";
+
+/// Reports whether a `.gitattributes` file sends any path through the LFS
+/// filter (`*.psd filter=lfs diff=lfs merge=lfs -text`).
+pub(crate) fn tracks_lfs(attributes: &str) -> bool {
+    attributes
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .any(|line| line.split_whitespace().skip(1).any(|a| a == "filter=lfs"))
+}
+
+/// Checks whether checking out commits of `repo` can trigger LFS downloads:
+/// Git LFS is installed (its `filter.lfs` smudge is configured) and the
+/// top-level `.gitattributes` at `HEAD`, or `info/attributes`, tracks files
+/// with it.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, lfs};
+///
+/// let repo = Repo::discover().unwrap();
+/// if lfs::detect(&repo) {
+///     println!("This repository stores files in Git LFS.");
+/// }
+/// ```
+pub fn detect(repo: &Repo) -> bool {
+    let installed = ["filter.lfs.process", "filter.lfs.smudge"]
+        .iter()
+        .any(|key| !repo.config_get(key).unwrap_or_default().is_empty());
+    if !installed {
+        return false;
+    }
+    let committed = match repo.cat_file("blob", "HEAD:.gitattributes") {
+        Ok(bytes) => tracks_lfs(&String::from_utf8_lossy(&bytes)),
+        Err(_) => false,
+    };
+    committed
+        || fs::read_to_string(repo.git_dir().join("info").join("attributes"))
+            .is_ok_and(|text| tracks_lfs(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tracks_lfs;
+
+    #[test]
+    fn tracks_lfs_finds_the_lfs_filter() {
+        assert!(tracks_lfs(
+            "*.txt text\n*.psd filter=lfs diff=lfs merge=lfs -text\n"
+        ));
+        assert!(tracks_lfs("  assets/**\tfilter=lfs -text"));
+    }
+
+    #[test]
+    fn tracks_lfs_ignores_comments_and_other_filters() {
+        assert!(!tracks_lfs("# *.psd filter=lfs\n"));
+        assert!(!tracks_lfs("*.c filter=indent\n*.bin -text\n"));
+        assert!(!tracks_lfs("filter=lfs\n"));
+        assert!(!tracks_lfs(""));
+    }
+}
@@ -4,9 +4,10 @@
 //!
 //! This crate provides functionality to:
 //! - Prompt for new author name and email
-//! - Start an interactive rebase from the root commit
-//! - Automatically mark all commits for editing
-//! - Amend each commit with the new author information
+//! - Rewrite every commit from the root with the new author information,
+//!   keeping its tree
+//! - Fall back to an interactive rebase that amends each commit when an
+//!   option needs one
 //!
 //! ## Usage
 //!
@@ -16,18 +17,132 @@
 //!
 //! # Manual mode: choose which commits to edit
 //! git-author-rewrite --manual
+//!
+//! # Company rename: move every @oldcorp.com email to @newcorp.com
+//! git-author-rewrite --rewrite-domain oldcorp.com=newcorp.com
+//!
+//! # Rewrite to a saved identity profile without prompting
+//! git-author-rewrite profile add work 'Jane Doe' jane@corp.com
+//! git-author-rewrite --profile work
+//!
+//! # Only rewrite the commits on a feature branch
+//! git-author-rewrite --revs 'main..feature'
 //! ```
 //!
 //! ## Modules
 //!
-//! - [`cli`] - Command-line interface and main entry point
+//! - [`cli`] - Command-line interface and main entry point (`cli` feature)
+//! - [`args`] - Command-line option parsing
 //! - [`git`] - Git command wrappers
-//! - [`prompt`] - User input abstractions
+//! - [`diagnose`] - Explanations and fixes for well-known Git failures
+//! - [`exit`] - Process exit codes of the binary
+//! - [`error`] - The error a run ends with: what failed, what the tool was doing, and hints
+//! - [`engine`] - The `Engine` trait shared by the rewrite backends (`--engine`)
+//! - [`runner`] - `GitRunner`: how the git layer runs commands (subprocesses, or a mock in tests)
+//! - [`git_version`] - Git version detection and the features each run needs
+//! - [`preflight`] - Repository checks run before anything is rewritten
+//! - [`protected`] - Protected branches: a remote's default branch and `author-rewrite.protected` patterns
+//! - [`lfs`] - Git LFS detection, so a rebase can skip smudge downloads
+//! - [`lock`] - Lock file keeping a second run out of a repository being rewritten
+//! - [`identity`] - Identities and how rewritten commits map onto them
+//! - [`rules`] - `--rules`: per-commit author mapping rules with date conditions
+//! - [`sha_map`] - `--sha-map`: per-commit authors from a CSV keyed by commit SHA
+//! - [`prompt`] - User input abstractions (`cli` feature)
+//! - [`suggest`] - Identities already known for a repository, for the identity picker and prompt completions
 //! - [`sequence_editor`] - Rebase todo file transformation
-//! - [`banner`] - Decorative CLI banner
+//! - [`todo`] - Rebase todo list grammar, including abbreviated commands
+//! - [`github`] - `--check-github`: asks the GitHub API whether the new email belongs to an account (`github` feature)
+//! - [`noreply`] - Personal email detection and GitHub noreply addresses
+//! - [`preview`] - Pre-rewrite summary of affected commits
+//! - [`signing`] - `commit.gpgsign` handling during the amend loop
+//! - [`profile`] - Named identity profiles stored in the global git config
+//! - [`include_if`] - `--include-if`: directory-scoped identities through `includeIf` sections
+//! - [`spec`] - JSON job descriptions for `--spec`
+//! - [`selection`] - Resolution of the commits chosen for rewriting
+//! - [`scan`] - One `git log` pass feeding the checks before a rewrite
+//! - [`message`] - `--message-replace`: sed-style substitutions in commit messages
+//! - [`commit_object`] - Raw commit objects and identity header rewriting
+//! - [`replace`] - `--replace-mode`: corrections as `git replace` refs
+//! - [`history`] - Object-level rewriting of all history behind a set of refs
+//! - [`gix_backend`] - `--engine gix`: in-process branch rewriting with gitoxide
+//! - [`mirror`] - `--mirror`: rewrite a mirror clone and force-push it back
+//! - [`tags`] - `--rewrite-tags`: tagger rewriting for annotated tags
+//! - [`report`] - Final report comparing authors before and after the rewrite
+//! - [`verify`] - Post-rewrite check that history carries the expected identity
+//! - [`policy`] - `check`: author policy files (allowed domains, forbidden identities, name format)
+//! - [`hook`] - `install-hook`: `pre-push` and `commit-msg` hooks that run `check`
+//! - [`dco`] - `--check-dco`: post-rewrite check that each commit is signed off by its author
+//! - [`backup`] - `refs/original/` backups of rewritten branches and backup bundles of every ref
+//! - [`batch`] - `--repos-file`: the same rewrite across many repositories
+//! - [`bench`] - `bench`: timing each engine on temporary clones of the repository
+//! - [`selftest`] - `selftest`: an end-to-end rewrite of a scratch repository
+//! - [`test_util`] - `TempRepo`, a temporary repository builder for tests (`test-util` feature)
+//! - [`discover`] - `--discover`: finding the repositories under a directory
+//! - [`progress`] - Throughput and time-left estimates for the rebase loop
+//! - [`chunks`] - `--chunk-size`: checkpoints of a rebase split into chunks, for `--resume`
+//! - [`html_report`] - `--report-html`: a standalone HTML report of the rewrite for change-management tickets
+//! - [`audit`] - Append-only JSONL log of every run: who, when, with which options, which refs moved
+//! - [`tui`] - `--tui`: full-screen dashboard for the rebase loop (`cli` feature)
+//! - [`banner`] - Decorative CLI banner (`cli` feature)
+
+// Error handling uses explicit `match` rather than `?` throughout the crate.
+#![allow(clippy::question_mark)]
 
+pub mod args;
+pub mod audit;
+pub mod backup;
+#[cfg(feature = "cli")]
 pub mod banner;
+pub mod batch;
+pub mod bench;
+pub mod chunks;
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod commit_object;
+pub mod dco;
+pub mod diagnose;
+pub mod discover;
+pub mod engine;
+pub mod error;
+pub mod exit;
 pub mod git;
+pub mod github;
+pub mod git_version;
+pub mod gix_backend;
+pub mod history;
+pub mod hook;
+pub mod html_report;
+pub mod identity;
+pub mod include_if;
+pub mod lfs;
+pub mod lock;
+pub mod message;
+pub mod mirror;
+pub mod policy;
+pub mod noreply;
+pub mod preflight;
+pub mod preview;
+pub mod profile;
+pub mod protected;
+pub mod progress;
+#[cfg(feature = "cli")]
 pub mod prompt;
+pub mod replace;
+pub mod report;
+pub mod rules;
+pub mod runner;
+pub mod scan;
+pub mod selection;
+pub mod selftest;
 pub mod sequence_editor;
+pub mod sha_map;
+pub mod signing;
+pub mod spec;
+pub mod suggest;
+pub mod tags;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod todo;
+#[cfg(feature = "cli")]
+pub mod tui;
+pub mod verify;
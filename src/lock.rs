@@ -0,0 +1,185 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Name of the lock file, inside the repository's git directory.
+pub const LOCK_FILE: &str = "author-rewrite.lock";
+
+/// Proof that this process is the only rewrite running in a repository.
+///
+/// The lock file is removed when the `Lock` is dropped. A run that is killed
+/// leaves it behind; the next run recognizes it as stale by its PID.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    /// The path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Reads the PID recorded in a lock file.
+fn holder(text: &str) -> Option<u32> {
+    text.trim().parse().ok()
+}
+
+/// Reports whether a process with `pid` is running.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(p) if p > 0 => p,
+        _ => return false,
+    };
+    // Signal 0 only checks that the process exists and may be signaled.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    // It exists, but belongs to another user.
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Reports whether a process with `pid` is running.
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    let filter = format!("PID eq {}", pid);
+    match process::Command::new("tasklist")
+        .args(["/FI", &filter, "/NH", "/FO", "CSV"])
+        .output()
+    {
+        Ok(out) => String::from_utf8_lossy(&out.stdout).contains(&format!("\"{}\"", pid)),
+        // Without an answer, assume the other run is still going.
+        Err(_) => true,
+    }
+}
+
+/// Reports whether a process with `pid` is running; without a way to tell,
+/// the other run is assumed to be going.
+#[cfg(not(any(unix, windows)))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Creates the lock file with this process's PID, failing if it exists.
+fn create(path: &Path) -> Result<(), std::io::Error> {
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(f) => f,
+        Err(e) => return Err(e),
+    };
+    writeln!(file, "{}", process::id())
+}
+
+/// Takes the lock on the repository whose git directory is `git_dir`, so a
+/// second run cannot start a rebase on top of this one's.
+///
+/// A lock left behind by a process that is no longer running is replaced. A
+/// lock without a readable PID is treated as held, since its owner may not
+/// have written it yet.
+///
+/// # Returns
+///
+/// * `Ok(Lock)` holding the lock until it is dropped.
+/// * `Err(String)` naming the running process, or if the file could not be
+///   written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, lock};
+///
+/// let repo = Repo::discover().unwrap();
+/// let _lock = lock::acquire(repo.git_dir()).unwrap();
+/// // ... rewrite ...
+/// ```
+pub fn acquire(git_dir: &Path) -> Result<Lock, String> {
+    let path = git_dir.join(LOCK_FILE);
+    let in_use = |detail: String| {
+        format!(
+            "another git-author-rewrite run {} in this repository; wait for it to finish (or delete {} if it is gone)",
+            detail,
+            path.display()
+        )
+    };
+
+    match create(&path) {
+        Ok(()) => return Ok(Lock { path }),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("cannot create {}: {}", path.display(), e)),
+    }
+
+    let text = fs::read_to_string(&path).unwrap_or_default();
+    match holder(&text) {
+        Some(pid) if process_alive(pid) => {
+            return Err(in_use(format!("(process {}) is active", pid)));
+        }
+        Some(_) => {}
+        None => return Err(in_use(String::from("is starting or active"))),
+    }
+
+    // Stale: its owner is gone. Only remove it if no other run replaced it
+    // in the meantime.
+    if fs::read_to_string(&path).unwrap_or_default() == text {
+        let _ = fs::remove_file(&path);
+    }
+    match create(&path) {
+        Ok(()) => Ok(Lock { path }),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            Err(in_use(String::from("started at the same time")))
+        }
+        Err(e) => Err(format!("cannot create {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{acquire, holder, LOCK_FILE};
+    use std::fs;
+
+    #[test]
+    fn holder_reads_the_pid() {
+        assert_eq!(holder("4242\n"), Some(4242));
+        assert_eq!(holder(""), None);
+        assert_eq!(holder("garbage"), None);
+    }
+
+    #[test]
+    fn a_second_run_is_refused_until_the_lock_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = acquire(dir.path()).expect("first lock");
+        let written = fs::read_to_string(lock.path()).unwrap();
+        assert_eq!(holder(&written), Some(std::process::id()));
+
+        let err = acquire(dir.path()).unwrap_err();
+        assert!(err.contains("is active"), "{}", err);
+
+        drop(lock);
+        assert!(!dir.path().join(LOCK_FILE).exists());
+        assert!(acquire(dir.path()).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_locks_are_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        // Above any real pid_max, so no such process exists.
+        fs::write(dir.path().join(LOCK_FILE), format!("{}\n", i32::MAX)).unwrap();
+        let lock = acquire(dir.path()).expect("stale lock not replaced");
+        let written = fs::read_to_string(lock.path()).unwrap();
+        assert_eq!(holder(&written), Some(std::process::id()));
+
+        fs::write(lock.path(), "").unwrap();
+        assert!(acquire(dir.path()).is_err());
+    }
+}
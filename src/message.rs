@@ -0,0 +1,487 @@
+use regex::bytes::{Regex, RegexBuilder};
+
+/// The trailer `--annotate` records the tool in.
+pub const REWRITTEN_BY_TRAILER: &str = "Rewritten-By";
+
+/// The trailer `--annotate` records the pre-rewrite SHA in.
+pub const ORIGINAL_COMMIT_TRAILER: &str = "Original-Commit";
+
+/// One `--message-replace` substitution, parsed from a sed-style
+/// `s/pattern/replacement/flags` expression.
+#[derive(Debug, Clone)]
+pub struct MessageRewrite {
+    /// The compiled pattern.
+    pattern: Regex,
+    /// The replacement, in `regex` syntax (`${1}` rather than sed's `\1`).
+    replacement: Vec<u8>,
+    /// The `g` flag: replace every match on a line, not just the first.
+    global: bool,
+}
+
+/// Splits `body` at unescaped occurrences of `delim`, keeping escapes other
+/// than `\<delim>` intact for the later stages.
+fn split_unescaped(body: &str, delim: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delim => {
+                    if let Some(last) = parts.last_mut() {
+                        last.push_str("\\\u{0}");
+                        last.push(next);
+                    }
+                }
+                Some(next) => {
+                    if let Some(last) = parts.last_mut() {
+                        last.push('\\');
+                        last.push(next);
+                    }
+                }
+                None => {
+                    if let Some(last) = parts.last_mut() {
+                        last.push('\\');
+                    }
+                }
+            }
+        } else if c == delim {
+            parts.push(String::new());
+        } else if let Some(last) = parts.last_mut() {
+            last.push(c);
+        }
+    }
+    parts
+}
+
+/// Turns an escaped delimiter (marked by [`split_unescaped`]) in a pattern
+/// into a literal match for it.
+fn pattern_source(part: &str) -> String {
+    let mut out = String::new();
+    let mut rest = part;
+    while let Some(i) = rest.find("\\\u{0}") {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 2..];
+        let mut chars = after.chars();
+        if let Some(delim) = chars.next() {
+            out.push_str(&regex::escape(&delim.to_string()));
+        }
+        rest = chars.as_str();
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Translates a sed replacement into `regex` replacement syntax: `\1`–`\9`
+/// and `&` refer to groups, `\n` is a newline and any other escaped
+/// character stands for itself.
+pub(crate) fn replacement_source(part: &str) -> String {
+    let mut out = String::new();
+    let mut chars = part.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\u{0}') => {
+                    if let Some(delim) = chars.next() {
+                        out.push(delim);
+                    }
+                }
+                Some(d) if d.is_ascii_digit() => {
+                    out.push_str(&format!("${{{}}}", d));
+                }
+                Some('n') => out.push('\n'),
+                Some('$') => out.push_str("$$"),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            '&' => out.push_str("${0}"),
+            '$' => out.push_str("$$"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Parses a sed-style substitution such as `s/JIRA-(\d+)/PROJ-\1/g`.
+///
+/// Any punctuation character may serve as the delimiter (`s|a/b|c|`), and is
+/// matched literally when escaped. The flags are `g` (every match on a line,
+/// not only the first) and `i` (case-insensitive).
+///
+/// # Returns
+///
+/// * `Ok(MessageRewrite)` on success.
+/// * `Err(String)` if the expression is malformed or the pattern invalid.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::message::parse_substitution;
+///
+/// let rewrite = parse_substitution("s/old.example.com/new.example.com/g").unwrap();
+/// let out = rewrite.apply(b"Deploy to old.example.com\n");
+/// assert_eq!(out, b"Deploy to new.example.com\n");
+/// ```
+pub fn parse_substitution(expr: &str) -> Result<MessageRewrite, String> {
+    let mut chars = expr.chars();
+    let delim = match (chars.next(), chars.next()) {
+        (Some('s'), Some(d)) if !d.is_alphanumeric() && !d.is_whitespace() && d != '\\' => d,
+        _ => {
+            return Err(format!(
+                "invalid substitution `{}`: expected s/pattern/replacement/",
+                expr
+            ))
+        }
+    };
+
+    let parts = split_unescaped(chars.as_str(), delim);
+    let (pattern, replacement, flags) = match parts.as_slice() {
+        [p, r, f] => (p, r, f),
+        [_, _] => {
+            return Err(format!(
+                "invalid substitution `{}`: missing the closing `{}`",
+                expr, delim
+            ))
+        }
+        _ => {
+            return Err(format!(
+                "invalid substitution `{}`: expected s{d}pattern{d}replacement{d}",
+                expr,
+                d = delim
+            ))
+        }
+    };
+    if pattern.is_empty() {
+        return Err(format!("invalid substitution `{}`: empty pattern", expr));
+    }
+
+    let mut global = false;
+    let mut case_insensitive = false;
+    for flag in flags.chars() {
+        match flag {
+            'g' => global = true,
+            'i' | 'I' => case_insensitive = true,
+            other => {
+                return Err(format!(
+                    "invalid substitution `{}`: unknown flag `{}` (expected g or i)",
+                    expr, other
+                ))
+            }
+        }
+    }
+
+    let compiled = match RegexBuilder::new(&pattern_source(pattern))
+        .case_insensitive(case_insensitive)
+        .build()
+    {
+        Ok(r) => r,
+        Err(e) => return Err(format!("invalid pattern in `{}`: {}", expr, e)),
+    };
+
+    Ok(MessageRewrite {
+        pattern: compiled,
+        replacement: replacement_source(replacement).into_bytes(),
+        global,
+    })
+}
+
+/// Parses every `--message-replace` expression.
+///
+/// # Returns
+///
+/// * `Ok(Vec<MessageRewrite>)` in the order given.
+/// * `Err(String)` for the first malformed expression.
+pub fn parse_all(exprs: &[String]) -> Result<Vec<MessageRewrite>, String> {
+    let mut out = Vec::new();
+    for expr in exprs {
+        match parse_substitution(expr) {
+            Ok(r) => out.push(r),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(out)
+}
+
+impl MessageRewrite {
+    /// Applies the substitution to `message`, line by line as sed does.
+    pub fn apply(&self, message: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(message.len());
+        for (i, line) in message.split(|b| *b == b'\n').enumerate() {
+            if i > 0 {
+                out.push(b'\n');
+            }
+            let replaced = if self.global {
+                self.pattern.replace_all(line, self.replacement.as_slice())
+            } else {
+                self.pattern.replace(line, self.replacement.as_slice())
+            };
+            out.extend_from_slice(&replaced);
+        }
+        out
+    }
+}
+
+/// Applies `rewrites` to `message` in order.
+///
+/// # Returns
+///
+/// * `Some(bytes)` with the new message if it changed.
+/// * `None` if no substitution matched.
+pub fn rewrite_message(rewrites: &[MessageRewrite], message: &[u8]) -> Option<Vec<u8>> {
+    if rewrites.is_empty() {
+        return None;
+    }
+    let mut current = message.to_vec();
+    for rewrite in rewrites {
+        current = rewrite.apply(&current);
+    }
+    if current == message {
+        None
+    } else {
+        Some(current)
+    }
+}
+
+/// Reports whether `line` looks like a `Key: value` trailer or the
+/// indented continuation of one.
+fn is_trailer_line(line: &[u8]) -> bool {
+    if line.first().is_some_and(|b| *b == b' ' || *b == b'\t') {
+        return true;
+    }
+    match line.iter().position(|b| *b == b':') {
+        Some(i) if i > 0 => line[..i]
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'-'),
+        _ => false,
+    }
+}
+
+/// Appends `trailers` (full `Key: value` lines) to `message`.
+///
+/// They join an existing trailer block (such as `Signed-off-by:` lines) when
+/// the message ends with one, and start a new paragraph otherwise. Trailers
+/// the message already contains are not repeated.
+pub(crate) fn append_trailers(message: &[u8], trailers: &[String]) -> Vec<u8> {
+    let end = message
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |i| i + 1);
+    let body = &message[..end];
+
+    let existing: Vec<&[u8]> = body.split(|b| *b == b'\n').collect();
+    let missing: Vec<&String> = trailers
+        .iter()
+        .filter(|t| !existing.contains(&t.as_bytes()))
+        .collect();
+    if missing.is_empty() {
+        return message.to_vec();
+    }
+
+    // A lone subject line such as `Fix: crash` is not a trailer block.
+    let joins_block = match body.windows(2).rposition(|w| w == b"\n\n") {
+        Some(i) => body[i + 2..].split(|b| *b == b'\n').all(is_trailer_line),
+        None => false,
+    };
+
+    let mut out = body.to_vec();
+    if !out.is_empty() {
+        out.extend_from_slice(if joins_block { b"\n" } else { b"\n\n" });
+    }
+    for (i, trailer) in missing.iter().enumerate() {
+        if i > 0 {
+            out.push(b'\n');
+        }
+        out.extend_from_slice(trailer.as_bytes());
+    }
+    out.push(b'\n');
+    out
+}
+
+/// Every change made to the messages of rewritten commits.
+#[derive(Debug, Clone, Default)]
+pub struct MessageEdits {
+    /// `--message-replace` substitutions, applied in order.
+    pub rewrites: Vec<MessageRewrite>,
+    /// `--annotate`: append provenance trailers naming the original commit.
+    pub annotate: bool,
+    /// `--record-original-sha`: append only the `Original-Commit:` trailer.
+    pub record_original_sha: bool,
+}
+
+impl MessageEdits {
+    /// Reports whether messages are left exactly as they are.
+    pub fn is_empty(&self) -> bool {
+        self.rewrites.is_empty() && !self.annotate && !self.record_original_sha
+    }
+
+    /// Applies the `--message-replace` substitutions to `message`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(bytes)` with the new message if it changed.
+    /// * `None` otherwise.
+    pub fn substitute(&self, message: &[u8]) -> Option<Vec<u8>> {
+        rewrite_message(&self.rewrites, message)
+    }
+
+    /// Adds the `--annotate` or `--record-original-sha` trailers for the
+    /// commit `original` to `message`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(bytes)` with the new message if it changed.
+    /// * `None` without either option, or if the trailers are already there.
+    pub fn annotate(&self, message: &[u8], original: &str) -> Option<Vec<u8>> {
+        let mut trailers = Vec::new();
+        if self.annotate {
+            trailers.push(format!("{}: git-author-rewrite", REWRITTEN_BY_TRAILER));
+        }
+        if self.annotate || self.record_original_sha {
+            trailers.push(format!("{}: {}", ORIGINAL_COMMIT_TRAILER, original));
+        } else {
+            return None;
+        }
+        let annotated = append_trailers(message, &trailers);
+        if annotated == message {
+            None
+        } else {
+            Some(annotated)
+        }
+    }
+
+    /// Applies every edit to the message of the commit `original`.
+    ///
+    /// # Parameters
+    ///
+    /// * `message` – The commit message.
+    /// * `original` – The SHA of the commit before the rewrite, for
+    ///   `--annotate` and `--record-original-sha`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(bytes)` with the new message if it changed.
+    /// * `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::message::MessageEdits;
+    ///
+    /// let edits = MessageEdits { annotate: true, ..MessageEdits::default() };
+    /// let out = edits.apply(b"Add parser\n", "1a2b3c").unwrap();
+    /// assert_eq!(
+    ///     out,
+    ///     b"Add parser\n\nRewritten-By: git-author-rewrite\nOriginal-Commit: 1a2b3c\n"
+    /// );
+    /// ```
+    pub fn apply(&self, message: &[u8], original: &str) -> Option<Vec<u8>> {
+        let substituted = self.substitute(message);
+        let current = substituted.as_deref().unwrap_or(message);
+        match self.annotate(current, original) {
+            Some(annotated) => Some(annotated),
+            None => substituted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        append_trailers, parse_all, parse_substitution, replacement_source, rewrite_message,
+        MessageEdits,
+    };
+
+    #[test]
+    fn replaces_first_match_per_line_unless_global() {
+        let once = parse_substitution("s/a/b/").unwrap();
+        assert_eq!(once.apply(b"aaa\naa\n"), b"baa\nba\n");
+        let all = parse_substitution("s/a/b/g").unwrap();
+        assert_eq!(all.apply(b"aaa\naa\n"), b"bbb\nbb\n");
+    }
+
+    #[test]
+    fn supports_groups_and_case_insensitivity() {
+        let r = parse_substitution(r"s/jira-(\d+)/PROJ-\1 (was &)/gi").unwrap();
+        assert_eq!(r.apply(b"Fix JIRA-12\n"), b"Fix PROJ-12 (was JIRA-12)\n");
+        assert_eq!(replacement_source(r"cost: $5 \& \\"), "cost: $$5 & \\");
+    }
+
+    #[test]
+    fn accepts_other_delimiters_and_escaped_ones() {
+        let r = parse_substitution("s|http://old/x|https://new/x|").unwrap();
+        assert_eq!(r.apply(b"see http://old/x"), b"see https://new/x");
+
+        let r = parse_substitution(r"s/a\/b/a\/c/").unwrap();
+        assert_eq!(r.apply(b"path a/b"), b"path a/c");
+
+        let r = parse_substitution(r"s|a\|b|x|").unwrap();
+        assert_eq!(r.apply(b"a|b ab"), b"x ab");
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for bad in ["x/a/b/", "s/a/b", "s//b/", "s/a/b/q", "s/(/b/", "sab"] {
+            assert!(parse_substitution(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rewrite_message_reports_only_changes() {
+        let rewrites = parse_all(&[
+            String::from("s/old@corp.com/new@corp.com/"),
+            String::from("s/Old Name/New Name/"),
+        ])
+        .unwrap();
+        assert_eq!(
+            rewrite_message(&rewrites, b"Signed-off-by: Old Name <old@corp.com>\n"),
+            Some(b"Signed-off-by: New Name <new@corp.com>\n".to_vec())
+        );
+        assert_eq!(rewrite_message(&rewrites, b"unrelated\n"), None);
+        assert_eq!(rewrite_message(&[], b"anything\n"), None);
+    }
+
+    #[test]
+    fn append_trailers_joins_an_existing_block() {
+        let trailers = vec![String::from("Original-Commit: abc")];
+        assert_eq!(
+            append_trailers(b"Fix\n\nBody.\n\nSigned-off-by: A <a@x>\n", &trailers),
+            b"Fix\n\nBody.\n\nSigned-off-by: A <a@x>\nOriginal-Commit: abc\n"
+        );
+        assert_eq!(
+            append_trailers(b"Fix: crash on start", &trailers),
+            b"Fix: crash on start\n\nOriginal-Commit: abc\n"
+        );
+    }
+
+    #[test]
+    fn annotate_is_idempotent_and_follows_substitutions() {
+        let edits = MessageEdits {
+            rewrites: parse_all(&[String::from("s/old/new/")]).unwrap(),
+            annotate: true,
+            ..MessageEdits::default()
+        };
+        let once = edits.apply(b"old subject\n", "abc").unwrap();
+        assert_eq!(
+            once,
+            b"new subject\n\nRewritten-By: git-author-rewrite\nOriginal-Commit: abc\n"
+        );
+        assert_eq!(edits.apply(&once, "abc"), None);
+    }
+
+    #[test]
+    fn record_original_sha_adds_only_the_original_commit() {
+        let edits = MessageEdits {
+            record_original_sha: true,
+            ..MessageEdits::default()
+        };
+        assert!(!edits.is_empty());
+        let out = edits
+            .apply(b"Fix\n\nSigned-off-by: A <a@x>\n", "abc")
+            .unwrap();
+        assert_eq!(
+            out,
+            b"Fix\n\nSigned-off-by: A <a@x>\nOriginal-Commit: abc\n"
+        );
+        assert_eq!(edits.apply(&out, "abc"), None);
+        assert_eq!(MessageEdits::default().annotate(b"Fix\n", "abc"), None);
+    }
+}
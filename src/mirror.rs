@@ -0,0 +1,161 @@
+use crate::{
+    git::{self, Repo},
+    history::RefUpdate,
+};
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Ref namespaces rewritten in a mirror. Other refs (such as a hosting
+/// provider's read-only `refs/pull/*`) are left alone.
+pub const MIRROR_REFS: &[&str] = &["refs/heads/", "refs/tags/"];
+
+/// Returns a fresh temporary directory path for the mirror of this run.
+pub fn workdir() -> PathBuf {
+    env::temp_dir().join(format!("git-author-rewrite-mirror-{}", process::id()))
+}
+
+/// Makes a relative local path in `url` absolute against `cwd`, so it still
+/// names the same repository when Git runs inside the mirror.
+/// Remote URLs are returned unchanged.
+pub fn absolute_url(url: &str, cwd: &Path) -> String {
+    let path = Path::new(url);
+    if path.is_relative() && cwd.join(path).exists() {
+        return cwd.join(path).to_string_lossy().into_owned();
+    }
+    url.to_string()
+}
+
+/// Clones `url` as a bare mirror into `dir`, replacing any leftover of an
+/// earlier run with the same path.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if the directory could not be cleared or the clone failed.
+pub fn clone(url: &str, dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            return Err(format!("cannot clear {}: {}", dir.display(), e));
+        }
+    }
+    match git::clone_mirror(url, dir) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("cannot clone {}: {}", url, e)),
+    }
+}
+
+/// Deletes the mirror at `dir`.
+pub fn discard(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+}
+
+/// Formats the refs that will be force-pushed as an aligned table, without
+/// styling: `refs/heads/main  1a2b3c4 → 5d6e7f8`.
+pub fn ref_diff_lines(refs: &[RefUpdate]) -> Vec<String> {
+    let width = refs.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    refs.iter()
+        .map(|r| {
+            format!(
+                "{:width$}  {} → {}",
+                r.name,
+                short(&r.old),
+                short(&r.new),
+                width = width
+            )
+        })
+        .collect()
+}
+
+/// Abbreviates a SHA for display.
+pub(crate) fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+/// Force-pushes the rewritten refs back to `url`, the repository the mirror
+/// was cloned from.
+///
+/// Each ref is only overwritten if the remote still has the value that was
+/// cloned, so commits pushed by someone else in the meantime are not lost.
+///
+/// # Returns
+///
+/// * `Ok(())` if every ref was updated.
+/// * `Err(String)` if the push failed or was rejected.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a mirror clone with a reachable remote.
+/// use git_author_rewrite::{git::Repo, history::RefUpdate, mirror};
+/// use std::path::Path;
+///
+/// let repo = Repo::open(Path::new("/tmp/mirror.git")).unwrap();
+/// let update = RefUpdate {
+///     name: String::from("refs/heads/main"),
+///     old: String::from("1a2b3c4d5e6f"),
+///     new: String::from("5d6e7f8a9b0c"),
+/// };
+/// mirror::push(&repo, "git@example.com:team/app.git", &[update]).unwrap();
+/// ```
+pub fn push(repo: &Repo, url: &str, refs: &[RefUpdate]) -> Result<(), String> {
+    let specs: Vec<(&str, &str, &str)> = refs
+        .iter()
+        .map(|r| (r.name.as_str(), r.old.as_str(), r.new.as_str()))
+        .collect();
+    // Pushing to the URL rather than the remote, since a mirror remote
+    // refuses explicit refspecs.
+    repo.push_with_lease(url, &specs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{absolute_url, ref_diff_lines};
+    use crate::history::RefUpdate;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn absolute_url_resolves_local_paths_only() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        fs::create_dir(dir.path().join("server.git")).expect("failed to create dir");
+
+        assert_eq!(
+            absolute_url("server.git", dir.path()),
+            dir.path().join("server.git").to_string_lossy()
+        );
+        assert_eq!(
+            absolute_url("git@example.com:team/app.git", dir.path()),
+            "git@example.com:team/app.git"
+        );
+        assert_eq!(
+            absolute_url("/srv/app.git", Path::new("/tmp")),
+            "/srv/app.git"
+        );
+    }
+
+    #[test]
+    fn ref_diff_lines_align_names_and_abbreviate() {
+        let refs = vec![
+            RefUpdate {
+                name: String::from("refs/heads/main"),
+                old: String::from("1111111aaaa"),
+                new: String::from("2222222bbbb"),
+            },
+            RefUpdate {
+                name: String::from("refs/tags/v1"),
+                old: String::from("3333333"),
+                new: String::from("4444444"),
+            },
+        ];
+        assert_eq!(
+            ref_diff_lines(&refs),
+            vec![
+                "refs/heads/main  1111111 → 2222222",
+                "refs/tags/v1     3333333 → 4444444",
+            ]
+        );
+    }
+}
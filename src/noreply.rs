@@ -0,0 +1,180 @@
+/// Domain of GitHub's private commit email addresses.
+pub const GITHUB_NOREPLY_DOMAIN: &str = "users.noreply.github.com";
+
+/// Free email providers whose addresses are usually someone's personal
+/// mailbox, as opposed to a company or project domain.
+const PERSONAL_DOMAINS: &[&str] = &[
+    "gmail.com",
+    "googlemail.com",
+    "outlook.com",
+    "hotmail.com",
+    "live.com",
+    "msn.com",
+    "yahoo.com",
+    "ymail.com",
+    "icloud.com",
+    "me.com",
+    "mac.com",
+    "aol.com",
+    "proton.me",
+    "protonmail.com",
+    "pm.me",
+    "gmx.com",
+    "gmx.de",
+    "gmx.net",
+    "web.de",
+    "mail.com",
+    "yandex.com",
+    "yandex.ru",
+    "fastmail.com",
+    "zoho.com",
+    "qq.com",
+    "163.com",
+];
+
+/// Providers that also hand out addresses under country domains, such as
+/// `yahoo.co.uk` or `hotmail.fr`.
+const REGIONAL_PROVIDERS: &[&str] = &["yahoo", "hotmail", "outlook", "live"];
+
+/// Reports whether `email` looks like a personal address: one at a free
+/// email provider, including regional variants such as `yahoo.co.uk`.
+pub fn is_personal(email: &str) -> bool {
+    let domain = match email.trim().rsplit_once('@') {
+        Some((_, d)) => d.to_ascii_lowercase(),
+        None => return false,
+    };
+    if PERSONAL_DOMAINS.contains(&domain.as_str()) {
+        return true;
+    }
+    REGIONAL_PROVIDERS.iter().any(|p| {
+        domain
+            .strip_prefix(p)
+            .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Checks that `username` is a valid GitHub username: up to 39 ASCII letters,
+/// digits and single hyphens, not starting or ending with a hyphen.
+///
+/// # Returns
+///
+/// * `Ok(())` if the username is valid.
+/// * `Err(String)` describing the problem otherwise.
+pub fn validate_username(username: &str) -> Result<(), String> {
+    let valid = !username.is_empty()
+        && username.len() <= 39
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !username.starts_with('-')
+        && !username.ends_with('-')
+        && !username.contains("--");
+    if !valid {
+        return Err(format!("`{}` is not a valid GitHub username", username));
+    }
+    Ok(())
+}
+
+/// Builds the GitHub noreply address for `username`.
+///
+/// Accounts created since July 2017 use `ID+username@users.noreply.github.com`,
+/// where `ID` is the numeric account ID shown at
+/// <https://github.com/settings/emails>; without an ID the older
+/// `username@users.noreply.github.com` form is returned.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::noreply::github_address;
+///
+/// assert_eq!(
+///     github_address(Some(1234567), "octocat"),
+///     "1234567+octocat@users.noreply.github.com"
+/// );
+/// assert_eq!(github_address(None, "octocat"), "octocat@users.noreply.github.com");
+/// ```
+pub fn github_address(id: Option<u64>, username: &str) -> String {
+    match id {
+        Some(id) => format!("{}+{}@{}", id, username, GITHUB_NOREPLY_DOMAIN),
+        None => format!("{}@{}", username, GITHUB_NOREPLY_DOMAIN),
+    }
+}
+
+/// Splits a GitHub noreply address into the account ID (if present) and the
+/// username: `42+jane@users.noreply.github.com` → `(Some(42), "jane")`.
+///
+/// # Returns
+///
+/// * `Some((id, username))` for a noreply address.
+/// * `None` for any other email.
+pub fn parse_github_address(email: &str) -> Option<(Option<u64>, String)> {
+    let (local, domain) = match email.trim().rsplit_once('@') {
+        Some(parts) => parts,
+        None => return None,
+    };
+    if !domain.eq_ignore_ascii_case(GITHUB_NOREPLY_DOMAIN) {
+        return None;
+    }
+    match local.split_once('+') {
+        Some((id, username)) => match id.parse::<u64>() {
+            Ok(id) => Some((Some(id), username.to_string())),
+            Err(_) => None,
+        },
+        None => Some((None, local.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{github_address, is_personal, parse_github_address, validate_username};
+
+    #[test]
+    fn is_personal_recognizes_free_providers() {
+        assert!(is_personal("jane.doe@gmail.com"));
+        assert!(is_personal("jane@GMail.com"));
+        assert!(is_personal("jane@yahoo.co.uk"));
+        assert!(!is_personal("jane@mail.corp.com"));
+        assert!(!is_personal("jane@corp.com"));
+        assert!(!is_personal("1234567+jane@users.noreply.github.com"));
+        assert!(!is_personal("not-an-email"));
+    }
+
+    #[test]
+    fn validate_username_follows_github_rules() {
+        assert!(validate_username("octo-cat42").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("-octocat").is_err());
+        assert!(validate_username("octo--cat").is_err());
+        assert!(validate_username("octo_cat").is_err());
+        assert!(validate_username(&"a".repeat(40)).is_err());
+    }
+
+    #[test]
+    fn parse_github_address_splits_id_and_username() {
+        assert_eq!(
+            parse_github_address("42+jane@users.noreply.github.com"),
+            Some((Some(42), String::from("jane")))
+        );
+        assert_eq!(
+            parse_github_address("jane@Users.NoReply.GitHub.com"),
+            Some((None, String::from("jane")))
+        );
+        assert_eq!(
+            parse_github_address("x+jane@users.noreply.github.com"),
+            None
+        );
+        assert_eq!(parse_github_address("jane@gmail.com"), None);
+    }
+
+    #[test]
+    fn github_address_uses_the_id_when_known() {
+        assert_eq!(
+            github_address(Some(42), "jane"),
+            "42+jane@users.noreply.github.com"
+        );
+        assert_eq!(
+            github_address(None, "jane"),
+            "jane@users.noreply.github.com"
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{commit_object::parse_signature, identity::Identity, verify::Rule};
+
+use regex::Regex;
+use std::fs;
+
+/// The policy file `check` reads when none is given, at the top of the
+/// working tree.
+pub const DEFAULT_POLICY_FILE: &str = ".author-policy";
+
+/// An identity no commit may carry: an email, optionally with the name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forbidden {
+    /// Only match this exact name, if set.
+    pub name: Option<String>,
+    /// The email to match (case-insensitive).
+    pub email: String,
+}
+
+/// The rules of an author policy file, read by the `check` subcommand.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// `allow-domain`: the email domains identities must use; any domain if
+    /// empty. `*.corp.com` allows every subdomain of `corp.com`.
+    pub allowed_domains: Vec<String>,
+    /// `forbid`: identities that must not appear.
+    pub forbidden: Vec<Forbidden>,
+    /// `name-format`: a regular expression every name must match.
+    pub name_format: Option<Regex>,
+}
+
+/// Checked against history with [`crate::verify::verify`].
+impl Rule for Policy {
+    /// Lists the policy rules `id` breaks, empty if it complies.
+    fn violations(&self, id: &Identity) -> Vec<String> {
+        let mut reasons = Vec::new();
+
+        let domain = match id.email.rsplit_once('@') {
+            Some((_, d)) => d.to_ascii_lowercase(),
+            None => String::new(),
+        };
+        if !self.allowed_domains.is_empty()
+            && !self
+                .allowed_domains
+                .iter()
+                .any(|a| domain_allowed(a, &domain))
+        {
+            reasons.push(format!("email domain `{}` is not allowed", domain));
+        }
+
+        let forbidden = self.forbidden.iter().any(|f| {
+            f.email.eq_ignore_ascii_case(&id.email) && f.name.as_ref().is_none_or(|n| *n == id.name)
+        });
+        if forbidden {
+            reasons.push(String::from("identity is forbidden"));
+        }
+
+        if let Some(format) = &self.name_format {
+            if !format.is_match(&id.name) {
+                reasons.push(format!("name does not match `{}`", format.as_str()));
+            }
+        }
+
+        reasons
+    }
+}
+
+/// Reports whether `domain` (lowercase) is allowed by the `allow-domain`
+/// value `allowed`.
+fn domain_allowed(allowed: &str, domain: &str) -> bool {
+    match allowed.strip_prefix("*.") {
+        Some(parent) => domain
+            .strip_suffix(parent)
+            .is_some_and(|sub| sub.ends_with('.')),
+        None => allowed == domain,
+    }
+}
+
+/// Parses one `forbid` value: `email` or `Name <email>`.
+fn parse_forbidden(value: &str) -> Result<Forbidden, String> {
+    if !value.contains('<') {
+        if !value.contains('@') || value.contains(char::is_whitespace) {
+            return Err(format!(
+                "invalid identity `{}` (expected an email or Name <email>)",
+                value
+            ));
+        }
+        return Ok(Forbidden {
+            name: None,
+            email: value.to_string(),
+        });
+    }
+    match parse_signature(value) {
+        Some((id, "")) if !id.email.is_empty() => Ok(Forbidden {
+            name: Some(id.name).filter(|n| !n.is_empty()),
+            email: id.email,
+        }),
+        _ => Err(format!(
+            "invalid identity `{}` (expected an email or Name <email>)",
+            value
+        )),
+    }
+}
+
+/// Parses an identity given to `check --identity`: `Name <email>`, optionally
+/// followed by a timestamp as printed by `git var GIT_AUTHOR_IDENT`.
+///
+/// # Returns
+///
+/// * `Ok(Identity)` on success.
+/// * `Err(String)` if `value` is not `Name <email>`.
+pub fn parse_identity(value: &str) -> Result<Identity, String> {
+    match parse_signature(value.trim()) {
+        Some((id, _)) if !id.email.is_empty() => Ok(id),
+        _ => Err(format!(
+            "invalid identity `{}` (expected Name <email>)",
+            value
+        )),
+    }
+}
+
+/// Parses a policy file: one `directive value` per line. Blank lines and
+/// lines starting with `#` are ignored.
+///
+/// * `allow-domain <domain>` – Emails must use one of the listed domains
+///   (`*.corp.com` for its subdomains).
+/// * `forbid <email>` or `forbid Name <email>` – An identity no commit may carry.
+/// * `name-format <regex>` – A regular expression every name must match.
+///
+/// # Returns
+///
+/// * `Ok(Policy)` on success.
+/// * `Err(String)` naming the first invalid line.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::{identity::Identity, policy::parse, verify::Rule};
+///
+/// let policy = parse(
+///     "allow-domain corp.com\n\
+///      forbid root@corp.com\n\
+///      name-format ^\\S+ \\S+\n",
+/// )
+/// .unwrap();
+/// assert!(policy.violations(&Identity::new("Jane Doe", "jane@corp.com")).is_empty());
+/// assert_eq!(policy.violations(&Identity::new("root", "root@corp.com")).len(), 2);
+/// ```
+pub fn parse(text: &str) -> Result<Policy, String> {
+    let mut policy = Policy::default();
+    let mut rules = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (directive, value) = match line.split_once(char::is_whitespace) {
+            Some((d, v)) => (d, v.trim()),
+            None => (line, ""),
+        };
+        if value.is_empty() {
+            return Err(format!(
+                "policy line {}: `{}` needs a value",
+                i + 1,
+                directive
+            ));
+        }
+        match directive {
+            "allow-domain" => {
+                policy.allowed_domains.push(value.to_ascii_lowercase());
+            }
+            "forbid" => match parse_forbidden(value) {
+                Ok(f) => policy.forbidden.push(f),
+                Err(e) => return Err(format!("policy line {}: {}", i + 1, e)),
+            },
+            "name-format" => match Regex::new(value) {
+                Ok(re) => {
+                    policy.name_format = Some(re);
+                }
+                Err(e) => return Err(format!("policy line {}: invalid name-format: {}", i + 1, e)),
+            },
+            _ => {
+                return Err(format!(
+                    "policy line {}: unknown directive `{}` (expected allow-domain, forbid or name-format)",
+                    i + 1,
+                    directive
+                ))
+            }
+        }
+        rules += 1;
+    }
+    if rules == 0 {
+        return Err(String::from("the policy file contains no rules"));
+    }
+    Ok(policy)
+}
+
+/// Reads and parses the policy file at `path`.
+///
+/// # Returns
+///
+/// * `Ok(Policy)` on success.
+/// * `Err(String)` if the file could not be read or parsed.
+pub fn read(path: &str) -> Result<Policy, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) => Err(format!("cannot read policy {}: {}", path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{domain_allowed, parse, parse_identity};
+    use crate::git::Commit;
+    use crate::identity::Identity;
+    use crate::verify::check_commits;
+
+    #[test]
+    fn parse_reads_every_directive() {
+        let policy = parse(
+            "# corp only\n\
+             allow-domain Corp.com\n\
+             allow-domain *.corp.com\n\
+             \n\
+             forbid root@localhost\n\
+             forbid Jane Doe <jane@gmail.com>\n\
+             name-format ^[A-Z]\n",
+        )
+        .expect("parse failed");
+        assert_eq!(policy.allowed_domains, vec!["corp.com", "*.corp.com"]);
+        assert_eq!(policy.forbidden.len(), 2);
+        assert_eq!(policy.forbidden[1].name.as_deref(), Some("Jane Doe"));
+        assert!(policy.name_format.is_some());
+    }
+
+    #[test]
+    fn parse_rejects_bad_lines() {
+        assert!(parse("").is_err());
+        assert!(parse("allow-domain").is_err());
+        assert!(parse("forbid not an email").is_err());
+        assert!(parse("name-format [").is_err());
+        assert!(parse("require-signoff yes").is_err());
+    }
+
+    #[test]
+    fn parse_identity_accepts_git_var_output() {
+        assert_eq!(
+            parse_identity("Jane Doe <jane@corp.com> 1700000000 +0100"),
+            Ok(Identity::new("Jane Doe", "jane@corp.com"))
+        );
+        assert_eq!(
+            parse_identity("Jane Doe <jane@corp.com>"),
+            Ok(Identity::new("Jane Doe", "jane@corp.com"))
+        );
+        assert!(parse_identity("jane@corp.com").is_err());
+    }
+
+    #[test]
+    fn domain_allowed_matches_exactly_or_by_subdomain() {
+        assert!(domain_allowed("corp.com", "corp.com"));
+        assert!(!domain_allowed("corp.com", "eu.corp.com"));
+        assert!(domain_allowed("*.corp.com", "eu.corp.com"));
+        assert!(!domain_allowed("*.corp.com", "corp.com"));
+        assert!(!domain_allowed("*.corp.com", "evilcorp.com"));
+    }
+
+    #[test]
+    fn check_commits_reports_each_broken_rule() {
+        let policy = parse(
+            "allow-domain corp.com\n\
+             forbid Bot <bot@corp.com>\n\
+             name-format ^\\S+ \\S+$\n",
+        )
+        .expect("parse failed");
+        let jane = Identity::new("Jane Doe", "jane@corp.com");
+        let commits = vec![
+            Commit {
+                sha: String::from("b"),
+                author: Identity::new("jane", "jane@gmail.com"),
+                committer: jane.clone(),
+                author_time: 0,
+                commit_time: 0,
+                subject: String::new(),
+            },
+            Commit {
+                sha: String::from("a"),
+                author: jane,
+                committer: Identity::new("Bot", "BOT@corp.com"),
+                author_time: 0,
+                commit_time: 0,
+                subject: String::new(),
+            },
+        ];
+        let report = check_commits(commits, &policy);
+        assert_eq!(report.checked, 2);
+        let found: Vec<(&str, &str, &str)> = report
+            .violations
+            .iter()
+            .map(|v| (v.sha.as_str(), v.role, v.reason.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("b", "author", "email domain `gmail.com` is not allowed"),
+                ("b", "author", "name does not match `^\\S+ \\S+$`"),
+                ("a", "committer", "identity is forbidden"),
+                ("a", "committer", "name does not match `^\\S+ \\S+$`"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use crate::git::{self, Repo, Worktree};
+
+use std::{fs, path::Path};
+
+/// How a single check came out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Nothing to worry about.
+    Passed,
+    /// Worth knowing, but the rewrite can go ahead.
+    Warning,
+    /// The rewrite cannot go ahead.
+    Failed,
+}
+
+/// One line of the pre-flight checklist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked, e.g. `clean working tree`.
+    pub name: &'static str,
+    /// How it came out.
+    pub status: Status,
+    /// What was found, or what to do about it.
+    pub detail: String,
+}
+
+impl Check {
+    pub(crate) fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Check {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// The outcome of all pre-flight checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The checks that ran, in order.
+    pub checks: Vec<Check>,
+    /// The repository, if one was found.
+    pub repo: Option<Repo>,
+}
+
+impl Report {
+    /// Reports whether no check failed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != Status::Failed)
+    }
+}
+
+/// Checks `git status --porcelain` output for uncommitted changes.
+pub(crate) fn clean_tree_check(changes: &str) -> Check {
+    let count = changes.lines().filter(|l| !l.trim().is_empty()).count();
+    match count {
+        0 => Check::new("clean working tree", Status::Passed, ""),
+        1 => Check::new(
+            "clean working tree",
+            Status::Failed,
+            "1 file has uncommitted changes; commit or stash it first",
+        ),
+        n => Check::new(
+            "clean working tree",
+            Status::Failed,
+            format!(
+                "{} files have uncommitted changes; commit or stash them first",
+                n
+            ),
+        ),
+    }
+}
+
+/// Checks `git rev-parse --is-shallow-repository` output.
+pub(crate) fn shallow_check(out: Result<String, String>) -> Check {
+    match out.as_deref() {
+        Ok("false") => Check::new("full history", Status::Passed, ""),
+        Ok("true") => Check::new(
+            "full history",
+            Status::Failed,
+            "this is a shallow clone; run `git fetch --unshallow` first",
+        ),
+        _ => Check::new(
+            "full history",
+            Status::Warning,
+            "could not tell whether this is a shallow clone",
+        ),
+    }
+}
+
+/// Checks how the current branch compares to its upstream.
+///
+/// Being behind is only a warning: the rewrite works, but the commits not
+/// pulled yet are left out of it.
+pub(crate) fn divergence_check(
+    upstream: Option<&str>,
+    counts: Result<(usize, usize), String>,
+) -> Check {
+    let upstream = match upstream {
+        Some(u) => u,
+        None => return Check::new("up to date with upstream", Status::Passed, "no upstream"),
+    };
+    match counts {
+        Ok((_, 0)) => Check::new("up to date with upstream", Status::Passed, upstream),
+        Ok((_, behind)) => Check::new(
+            "up to date with upstream",
+            Status::Warning,
+            format!(
+                "{} commit{} behind {}; pull first so the rewrite covers them",
+                behind,
+                if behind == 1 { "" } else { "s" },
+                upstream
+            ),
+        ),
+        Err(e) => Check::new(
+            "up to date with upstream",
+            Status::Warning,
+            format!("cannot compare with {} ({})", upstream, e),
+        ),
+    }
+}
+
+/// Checks that `branch`, about to be rewritten in the worktree at `root`, is
+/// not also checked out in another worktree, which would be left on the old
+/// commits (or make Git refuse to move the branch).
+///
+/// Git lists a main worktree whose git directory lives elsewhere
+/// (`--separate-git-dir`, `GIT_DIR`/`GIT_WORK_TREE`, `core.worktree`, a
+/// submodule) under its git directory, so an entry at `git_dir` is this
+/// worktree too.
+pub(crate) fn worktree_check(
+    root: &Path,
+    git_dir: &Path,
+    branch: &str,
+    worktrees: Result<Vec<Worktree>, String>,
+) -> Check {
+    let name = "branch not used elsewhere";
+    let worktrees = match worktrees {
+        Ok(w) => w,
+        Err(e) => {
+            return Check::new(
+                name,
+                Status::Warning,
+                format!("cannot list worktrees ({})", e),
+            )
+        }
+    };
+    let same = |a: &Path, b: &Path| {
+        a == b || matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(x), Ok(y)) if x == y)
+    };
+    let others: Vec<String> = worktrees
+        .iter()
+        .filter(|w| {
+            w.branch.as_deref() == Some(branch) && !same(&w.path, root) && !same(&w.path, git_dir)
+        })
+        .map(|w| w.path.display().to_string())
+        .collect();
+    if others.is_empty() {
+        return Check::new(name, Status::Passed, "");
+    }
+    Check::new(
+        name,
+        Status::Failed,
+        format!(
+            "`{}` is also checked out in {}; switch {} to another branch first",
+            branch.trim_start_matches("refs/heads/"),
+            others.join(", "),
+            if others.len() == 1 {
+                "that worktree"
+            } else {
+                "those worktrees"
+            }
+        ),
+    )
+}
+
+/// Runs every pre-flight check against the current directory.
+///
+/// Checks that need a repository are skipped when none is found. The checks
+/// about the working tree and the current branch only matter to a rebase,
+/// so they are skipped unless `rebasing` is set (e.g. `--replace-mode`
+/// leaves the branch alone).
+///
+/// # Parameters
+///
+/// * `rebasing` – Whether the run rewrites the current branch by rebasing.
+///
+/// # Returns
+///
+/// The [`Report`] with every check that ran.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it depends on the current repository.
+/// use git_author_rewrite::preflight;
+///
+/// let report = preflight::run(true);
+/// for line in preflight::checklist_lines(&report) {
+///     println!("{}", line);
+/// }
+/// if !report.passed() {
+///     std::process::exit(1);
+/// }
+/// ```
+pub fn run(rebasing: bool) -> Report {
+    let mut checks = Vec::new();
+
+    let git_check = match which::which("git") {
+        Err(_) => Check::new("git installed", Status::Failed, "`git` not found in PATH"),
+        Ok(_) => match git::version() {
+            Ok(v) => Check::new("git installed", Status::Passed, v),
+            Err(e) => Check::new("git installed", Status::Failed, e),
+        },
+    };
+    let have_git = git_check.status == Status::Passed;
+    checks.push(git_check);
+    if !have_git {
+        return Report { checks, repo: None };
+    }
+
+    let repo = match Repo::discover() {
+        Ok(r) => {
+            checks.push(Check::new(
+                "inside a repository",
+                Status::Passed,
+                r.root().display().to_string(),
+            ));
+            r
+        }
+        Err(e) => {
+            checks.push(Check::new("inside a repository", Status::Failed, e));
+            return Report { checks, repo: None };
+        }
+    };
+
+    checks.push(shallow_check(repo.rev_parse("--is-shallow-repository")));
+
+    if rebasing {
+        if repo.rebase_in_progress() {
+            checks.push(Check::new(
+                "no rebase in progress",
+                Status::Failed,
+                "finish it with `git rebase --continue` or drop it with `git rebase --abort`",
+            ));
+        } else {
+            checks.push(Check::new("no rebase in progress", Status::Passed, ""));
+            // A tree in the middle of a rebase is expected to be dirty.
+            checks.push(match repo.tracked_changes() {
+                Ok(changes) => clean_tree_check(&changes),
+                Err(e) => Check::new("clean working tree", Status::Failed, e),
+            });
+        }
+
+        match repo.symbolic_head() {
+            Some(branch) => {
+                checks.push(Check::new(
+                    "on a branch",
+                    Status::Passed,
+                    branch.trim_start_matches("refs/heads/").to_string(),
+                ));
+                checks.push(worktree_check(
+                    repo.root(),
+                    repo.git_dir(),
+                    &branch,
+                    repo.worktrees(),
+                ));
+            }
+            None => checks.push(Check::new(
+                "on a branch",
+                Status::Failed,
+                "HEAD is detached; check out the branch to rewrite first",
+            )),
+        }
+
+        let upstream = repo.upstream().ok();
+        let counts = match upstream {
+            Some(_) => repo.ahead_behind(),
+            None => Ok((0, 0)),
+        };
+        checks.push(divergence_check(upstream.as_deref(), counts));
+    }
+
+    Report {
+        checks,
+        repo: Some(repo),
+    }
+}
+
+/// Formats the checklist, one line per check, without styling.
+pub fn checklist_lines(report: &Report) -> Vec<String> {
+    let width = report
+        .checks
+        .iter()
+        .map(|c| c.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    report
+        .checks
+        .iter()
+        .map(|c| {
+            let mark = match c.status {
+                Status::Passed => "✔",
+                Status::Warning => "⚠",
+                Status::Failed => "✘",
+            };
+            let line = format!("{} {:width$}  {}", mark, c.name, c.detail, width = width);
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        checklist_lines, clean_tree_check, divergence_check, shallow_check, worktree_check, Check,
+        Report, Status,
+    };
+    use crate::git::Worktree;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn clean_tree_check_counts_changed_files() {
+        assert_eq!(clean_tree_check("").status, Status::Passed);
+        let dirty = clean_tree_check(" M src/main.rs\nA  new.rs\n");
+        assert_eq!(dirty.status, Status::Failed);
+        assert!(dirty.detail.starts_with("2 files have uncommitted changes"));
+    }
+
+    #[test]
+    fn shallow_check_fails_on_shallow_clones() {
+        assert_eq!(
+            shallow_check(Ok(String::from("false"))).status,
+            Status::Passed
+        );
+        assert_eq!(
+            shallow_check(Ok(String::from("true"))).status,
+            Status::Failed
+        );
+        assert_eq!(
+            shallow_check(Err(String::from("unknown option"))).status,
+            Status::Warning
+        );
+    }
+
+    #[test]
+    fn divergence_check_warns_when_behind() {
+        assert_eq!(divergence_check(None, Ok((0, 0))).status, Status::Passed);
+        assert_eq!(
+            divergence_check(Some("origin/main"), Ok((4, 0))).status,
+            Status::Passed
+        );
+        let behind = divergence_check(Some("origin/main"), Ok((1, 3)));
+        assert_eq!(behind.status, Status::Warning);
+        assert!(behind.detail.starts_with("3 commits behind origin/main"));
+    }
+
+    #[test]
+    fn report_fails_on_any_failed_check_and_lists_all() {
+        let report = Report {
+            checks: vec![
+                Check::new("git installed", Status::Passed, "git version 2.43.0"),
+                Check::new("full history", Status::Warning, "unknown"),
+                Check::new("on a branch", Status::Failed, "HEAD is detached"),
+            ],
+            repo: None,
+        };
+        assert!(!report.passed());
+        assert_eq!(
+            checklist_lines(&report),
+            vec![
+                "✔ git installed  git version 2.43.0",
+                "⚠ full history   unknown",
+                "✘ on a branch    HEAD is detached",
+            ]
+        );
+    }
+
+    #[test]
+    fn worktree_check_fails_when_another_worktree_has_the_branch() {
+        let wt = |path: &str, branch: Option<&str>| Worktree {
+            path: PathBuf::from(path),
+            branch: branch.map(String::from),
+        };
+        let worktrees = vec![
+            wt("/src/app", Some("refs/heads/main")),
+            wt("/src/app-fix", None),
+            wt("/src/app-main", Some("refs/heads/main")),
+        ];
+        let (root, git_dir) = (Path::new("/src/app"), Path::new("/src/app/.git"));
+
+        let check = worktree_check(root, git_dir, "refs/heads/main", Ok(worktrees.clone()));
+        assert_eq!(check.status, Status::Failed);
+        assert_eq!(
+            check.detail,
+            "`main` is also checked out in /src/app-main; switch that worktree to another branch first"
+        );
+        assert_eq!(
+            worktree_check(root, git_dir, "refs/heads/dev", Ok(worktrees)).status,
+            Status::Passed
+        );
+        assert_eq!(
+            worktree_check(root, git_dir, "refs/heads/main", Err(String::from("boom"))).status,
+            Status::Warning
+        );
+    }
+
+    #[test]
+    fn worktree_check_passes_in_a_separate_git_dir_checkout() {
+        // `git init --separate-git-dir /store/app.git /src/app` lists the
+        // main worktree under its git directory.
+        let worktrees = vec![
+            Worktree {
+                path: PathBuf::from("/store/app.git"),
+                branch: Some(String::from("refs/heads/main")),
+            },
+            Worktree {
+                path: PathBuf::from("/src/app-main"),
+                branch: Some(String::from("refs/heads/dev")),
+            },
+        ];
+        let (root, git_dir) = (Path::new("/src/app"), Path::new("/store/app.git"));
+
+        let check = worktree_check(root, git_dir, "refs/heads/main", Ok(worktrees.clone()));
+        assert_eq!(check.status, Status::Passed);
+        assert_eq!(
+            worktree_check(root, git_dir, "refs/heads/dev", Ok(worktrees)).status,
+            Status::Failed
+        );
+    }
+}
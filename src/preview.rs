@@ -0,0 +1,164 @@
+use crate::{git::Repo, selection::Selection};
+
+/// Maximum number of commits listed individually in a preview section.
+const MAX_LISTED: usize = 20;
+
+/// A commit carrying a GPG/SSH signature that the rewrite will invalidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedCommit {
+    /// Full commit SHA.
+    pub sha: String,
+    /// The `%G?` signature status letter reported by Git.
+    pub status: char,
+    /// Commit subject line.
+    pub subject: String,
+}
+
+impl SignedCommit {
+    /// Returns a short human-readable description of the signature status.
+    pub fn status_label(&self) -> &'static str {
+        match self.status {
+            'G' => "good",
+            'B' => "bad",
+            'U' => "good, unknown validity",
+            'X' => "expired signature",
+            'Y' => "expired key",
+            'R' => "revoked key",
+            'E' => "unverifiable",
+            _ => "signed",
+        }
+    }
+}
+
+/// Parses `git log --format=%H%x00%G?%x00%s` output into signed commits.
+///
+/// Lines whose status is `N` (no signature) are skipped, as are malformed lines.
+///
+/// # Parameters
+///
+/// * `output` – Raw `git log` output, one commit per line.
+///
+/// # Returns
+///
+/// The signed commits, in the order Git listed them.
+pub(crate) fn parse_signed_commits(output: &str) -> Vec<SignedCommit> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(3, '\0').collect();
+            if fields.len() < 2 {
+                return None;
+            }
+
+            let status = match fields[1].chars().next() {
+                Some(c) => c,
+                None => return None,
+            };
+            if status == 'N' {
+                return None;
+            }
+
+            Some(SignedCommit {
+                sha: fields[0].to_string(),
+                status,
+                subject: fields.get(2).copied().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Finds every signed commit among the selected commits.
+///
+/// # Parameters
+///
+/// * `repo` – The repository to inspect.
+/// * `selection` – The commits that will be rewritten.
+///
+/// # Returns
+///
+/// * `Ok(Vec<SignedCommit>)` – possibly empty.
+/// * `Err(String)` if `git log` failed.
+pub fn signed_commits(repo: &Repo, selection: &Selection) -> Result<Vec<SignedCommit>, String> {
+    match repo.log("%H%x00%G?%x00%s", &["HEAD"]) {
+        Ok(out) => Ok(parse_signed_commits(&out)
+            .into_iter()
+            .filter(|c| selection.contains(&c.sha))
+            .collect()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Builds the preview lines listing signed commits that will lose their signatures.
+///
+/// At most [`MAX_LISTED`] commits are listed individually; the remainder is
+/// summarized in a final line.
+///
+/// # Parameters
+///
+/// * `commits` – Signed commits in the rewrite range.
+///
+/// # Returns
+///
+/// The preview lines without styling, or an empty vector if `commits` is empty.
+pub fn signed_commit_lines(commits: &[SignedCommit]) -> Vec<String> {
+    if commits.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lines = vec![format!(
+        "🔏 {} signed commit(s) will lose their signatures and need re-signing:",
+        commits.len()
+    )];
+
+    for c in commits.iter().take(MAX_LISTED) {
+        let short = &c.sha[..c.sha.len().min(10)];
+        lines.push(format!("   {} {} ({})", short, c.subject, c.status_label()));
+    }
+
+    if commits.len() > MAX_LISTED {
+        lines.push(format!("   ... and {} more", commits.len() - MAX_LISTED));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_signed_commits, signed_commit_lines, SignedCommit};
+
+    #[test]
+    fn parse_skips_unsigned_commits() {
+        let out = "aaa\0N\0first\nbbb\0G\0second\nccc\0E\0third";
+        let commits = parse_signed_commits(out);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].sha, "bbb");
+        assert_eq!(commits[0].status, 'G');
+        assert_eq!(commits[1].subject, "third");
+    }
+
+    #[test]
+    fn parse_ignores_malformed_lines() {
+        let commits = parse_signed_commits("garbage\n\n");
+        assert!(commits.is_empty());
+    }
+
+    #[test]
+    fn lines_empty_when_nothing_signed() {
+        assert!(signed_commit_lines(&[]).is_empty());
+    }
+
+    #[test]
+    fn lines_truncate_long_lists() {
+        let commits: Vec<SignedCommit> = (0..25)
+            .map(|i| SignedCommit {
+                sha: format!("{:040}", i),
+                status: 'G',
+                subject: format!("commit {}", i),
+            })
+            .collect();
+        let lines = signed_commit_lines(&commits);
+        assert!(lines[0].contains("25 signed commit(s)"));
+        assert_eq!(lines.len(), 1 + 20 + 1);
+        assert!(lines.last().unwrap().contains("and 5 more"));
+    }
+}
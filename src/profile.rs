@@ -0,0 +1,225 @@
+use crate::{git, identity::Identity};
+
+/// Prefix of the global config keys holding identity profiles.
+///
+/// A profile named `work` is stored as
+/// `author-rewrite.profile.work.name` and `author-rewrite.profile.work.email`.
+const PROFILE_SECTION: &str = "author-rewrite.profile";
+
+/// A named identity saved for reuse with `--profile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// The profile name, e.g. `work`.
+    pub name: String,
+    /// The identity commits are rewritten to.
+    pub identity: Identity,
+}
+
+/// Checks that `name` is usable as a profile name.
+///
+/// Names are limited to ASCII letters, digits, `-` and `_` so they can be
+/// used unquoted in config keys and on the command line.
+///
+/// # Returns
+///
+/// * `Ok(())` if the name is valid.
+/// * `Err(String)` describing the problem otherwise.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err(String::from("profile name must not be empty"));
+    }
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "invalid profile name `{}` (use letters, digits, `-` and `_`)",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Parses the output of `git config --null --get-regexp` into profiles.
+///
+/// Entries are `key\nvalue\0`. Profiles missing either the name or the email
+/// are skipped. The result is sorted by profile name.
+pub(crate) fn parse_profiles(raw: &str) -> Vec<Profile> {
+    let prefix = format!("{}.", PROFILE_SECTION);
+    let mut profiles: Vec<(String, Option<String>, Option<String>)> = Vec::new();
+
+    for entry in raw.split('\0') {
+        let (key, value) = match entry.split_once('\n') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        let rest = match key.strip_prefix(&prefix) {
+            Some(r) => r,
+            None => continue,
+        };
+        let (profile, field) = match rest.rsplit_once('.') {
+            Some(pf) => pf,
+            None => continue,
+        };
+
+        let idx = match profiles.iter().position(|(p, _, _)| p == profile) {
+            Some(i) => i,
+            None => {
+                profiles.push((profile.to_string(), None, None));
+                profiles.len() - 1
+            }
+        };
+        match field {
+            "name" => {
+                profiles[idx].1 = Some(value.to_string());
+            }
+            "email" => {
+                profiles[idx].2 = Some(value.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    let mut complete: Vec<Profile> = profiles
+        .into_iter()
+        .filter_map(|(name, n, e)| match (n, e) {
+            (Some(n), Some(e)) => Some(Profile {
+                name,
+                identity: Identity::new(&n, &e),
+            }),
+            _ => None,
+        })
+        .collect();
+    complete.sort_by(|a, b| a.name.cmp(&b.name));
+    complete
+}
+
+/// Lists the profiles saved in the global git config.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Profile>)` sorted by name, possibly empty.
+/// * `Err(String)` if the config could not be read.
+pub fn list() -> Result<Vec<Profile>, String> {
+    // Git lowercases section names but keeps subsection (profile) names as is.
+    match git::config_get_regexp_global("^author-rewrite\\.profile\\.") {
+        Ok(raw) => Ok(parse_profiles(&raw)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Looks up the profile called `name`.
+///
+/// # Returns
+///
+/// * `Ok(Profile)` if the profile exists.
+/// * `Err(String)` if it does not, or the config could not be read.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it reads the user's global Git configuration.
+/// use git_author_rewrite::profile;
+///
+/// let work = profile::load("work").unwrap();
+/// println!("{}", work.identity);
+/// ```
+pub fn load(name: &str) -> Result<Profile, String> {
+    if let Err(e) = validate_name(name) {
+        return Err(e);
+    }
+    let profiles = match list() {
+        Ok(p) => p,
+        Err(e) => return Err(e),
+    };
+    match profiles.into_iter().find(|p| p.name == name) {
+        Some(p) => Ok(p),
+        None => Err(format!(
+            "no profile named `{}` (see `git-author-rewrite profile list`)",
+            name
+        )),
+    }
+}
+
+/// Saves `identity` as the profile `name`, replacing any existing profile.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if the name is invalid or the config could not be written.
+pub fn add(name: &str, identity: &Identity) -> Result<(), String> {
+    if let Err(e) = validate_name(name) {
+        return Err(e);
+    }
+    for (field, value) in [("name", &identity.name), ("email", &identity.email)] {
+        let key = format!("{}.{}.{}", PROFILE_SECTION, name, field);
+        if let Err(e) = git::config_set_global(&key, value) {
+            return Err(format!("cannot save profile `{}`: {}", name, e));
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the profile `name`.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if no such profile exists or the config could not be written.
+pub fn remove(name: &str) -> Result<(), String> {
+    if let Err(e) = load(name) {
+        return Err(e);
+    }
+    let section = format!("{}.{}", PROFILE_SECTION, name);
+    match git::config_remove_section_global(&section) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("cannot remove profile `{}`: {}", name, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_profiles, validate_name, Profile};
+    use crate::identity::Identity;
+
+    #[test]
+    fn validate_name_accepts_simple_names() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("oss_2-personal").is_ok());
+    }
+
+    #[test]
+    fn validate_name_rejects_bad_names() {
+        assert!(validate_name("").is_err());
+        assert!(validate_name("my work").is_err());
+        assert!(validate_name("a.b").is_err());
+    }
+
+    #[test]
+    fn parse_profiles_groups_and_sorts() {
+        let raw = "author-rewrite.profile.work.name\nJane Doe\0\
+                   author-rewrite.profile.oss.name\nJD\0\
+                   author-rewrite.profile.work.email\njane@corp.com\0\
+                   author-rewrite.profile.oss.email\njd@users.noreply.github.com\0";
+        assert_eq!(
+            parse_profiles(raw),
+            vec![
+                Profile {
+                    name: String::from("oss"),
+                    identity: Identity::new("JD", "jd@users.noreply.github.com"),
+                },
+                Profile {
+                    name: String::from("work"),
+                    identity: Identity::new("Jane Doe", "jane@corp.com"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_profiles_skips_incomplete_entries() {
+        let raw = "author-rewrite.profile.half.name\nOnly Name\0";
+        assert!(parse_profiles(raw).is_empty());
+        assert!(parse_profiles("").is_empty());
+    }
+}
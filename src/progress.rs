@@ -0,0 +1,248 @@
+use crate::todo::{TodoCommand, TodoLine};
+
+use std::{fs, path::Path, time::Duration};
+
+/// How many of the latest commits the rate is averaged over, so the estimate
+/// follows slow stretches (large commits, hooks) instead of the whole run.
+const WINDOW: usize = 20;
+
+/// Counts the `edit` stops in a rebase's `done` and `git-rebase-todo` lists.
+pub(crate) fn count_stops(done: &str, todo: &str) -> usize {
+    done.lines()
+        .chain(todo.lines())
+        .filter_map(TodoLine::parse)
+        .filter(|l| l.command == TodoCommand::Edit)
+        .count()
+}
+
+/// Reads a rebase progress file, whose subjects may not be valid UTF-8.
+pub(crate) fn read_todo(path: &Path) -> String {
+    match fs::read(path) {
+        Ok(b) => String::from_utf8_lossy(&b).into_owned(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Counts the commits the rebase in `git_dir` stops at for amending.
+///
+/// # Returns
+///
+/// The number of `edit` lines, done or still to do; `0` if no interactive
+/// rebase is in progress.
+///
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::progress;
+/// use std::path::Path;
+///
+/// println!("{} commits to amend", progress::total_stops(Path::new(".git")));
+/// ```
+pub fn total_stops(git_dir: &Path) -> usize {
+    let dir = git_dir.join("rebase-merge");
+    let done = read_todo(&dir.join("done"));
+    let todo = read_todo(&dir.join("git-rebase-todo"));
+    count_stops(&done, &todo)
+}
+
+/// Where a rebase is stopped: how many todo lines it has done, and the last
+/// of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stop {
+    /// The number of todo lines done, the one stopped at included.
+    pub step: usize,
+    /// The last line done, e.g. `edit 1a2b3c4 Fix parser`.
+    pub line: String,
+}
+
+/// Reads the [`Stop`] from a rebase's `done` list.
+pub(crate) fn stop_in(done: &str) -> Stop {
+    let lines: Vec<&str> = done
+        .lines()
+        .filter(|l| TodoLine::parse(l).is_some())
+        .collect();
+    Stop {
+        step: lines.len(),
+        line: lines.last().map_or(String::new(), |l| l.trim().to_string()),
+    }
+}
+
+/// Reads where the rebase in `git_dir` is stopped.
+pub fn current_stop(git_dir: &Path) -> Stop {
+    stop_in(&read_todo(&git_dir.join("rebase-merge").join("done")))
+}
+
+/// Notices a rebase loop that makes no progress: every successful
+/// `git rebase --continue` moves the rebase on by at least one todo line, so
+/// stopping at the same [`Stop`] twice means it is going round in circles
+/// (a hook or `exec` line undoing each step, for example).
+#[derive(Debug, Clone, Default)]
+pub struct StallCheck {
+    last: Option<Stop>,
+}
+
+impl StallCheck {
+    /// Starts with no stop seen.
+    pub fn new() -> StallCheck {
+        StallCheck::default()
+    }
+
+    /// Records `stop`, the place the rebase is stopped at before the next
+    /// amend.
+    ///
+    /// # Returns
+    ///
+    /// `true` if it is the same place as the previous call.
+    pub fn stalled(&mut self, stop: &Stop) -> bool {
+        let same = self.last.as_ref() == Some(stop);
+        self.last = Some(stop.clone());
+        same
+    }
+}
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` from an hour on.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::progress::format_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(format_duration(Duration::from_secs(75)), "1:15");
+/// ```
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Timing of the rebase loop: how long each commit took to amend, and what
+/// that means for the rest.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    total: usize,
+    times: Vec<Duration>,
+}
+
+impl Progress {
+    /// Starts tracking a loop over `total` commits.
+    pub fn new(total: usize) -> Progress {
+        Progress {
+            total,
+            times: Vec::new(),
+        }
+    }
+
+    /// Records that one more commit was handled, in `took`.
+    pub fn record(&mut self, took: Duration) {
+        self.times.push(took);
+    }
+
+    /// The number of commits handled so far.
+    pub fn completed(&self) -> usize {
+        self.times.len()
+    }
+
+    /// The number of commits the loop expects to handle.
+    pub fn total(&self) -> usize {
+        self.total.max(self.completed())
+    }
+
+    /// Commits per second over the latest [`WINDOW`] commits.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` once a commit has taken measurable time.
+    /// * `None` before that.
+    pub fn rate(&self) -> Option<f64> {
+        let recent = &self.times[self.times.len().saturating_sub(WINDOW)..];
+        let spent: Duration = recent.iter().sum();
+        if recent.is_empty() || spent.is_zero() {
+            return None;
+        }
+        Some(recent.len() as f64 / spent.as_secs_f64())
+    }
+
+    /// Estimates the time left at the current [`Progress::rate`].
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total() - self.completed();
+        self.rate()
+            .map(|r| Duration::from_secs_f64(remaining as f64 / r))
+    }
+
+    /// Summarizes the progress, e.g. `3/40 · 2.5 commits/s · ETA 0:15`.
+    pub fn summary(&self) -> String {
+        let (rate, eta) = match (self.rate(), self.eta()) {
+            (Some(r), Some(e)) => (format!("{:.1}", r), format_duration(e)),
+            _ => (String::from("-"), String::from("-:--")),
+        };
+        format!(
+            "{}/{} · {} commits/s · ETA {}",
+            self.completed(),
+            self.total(),
+            rate,
+            eta
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_stops, format_duration, stop_in, Progress, StallCheck, Stop};
+    use std::time::Duration;
+
+    #[test]
+    fn count_stops_only_counts_edits() {
+        let done = "pick aaa One\ne bbb Two\n";
+        let todo = "# comment\nedit ccc Three\nexec make\npick ddd Four\n";
+        assert_eq!(count_stops(done, todo), 2);
+    }
+
+    #[test]
+    fn repeated_stops_are_stalls() {
+        let first = stop_in("pick aaa One\nedit bbb Two\n");
+        assert_eq!(
+            first,
+            Stop {
+                step: 2,
+                line: String::from("edit bbb Two"),
+            }
+        );
+        let next = stop_in("pick aaa One\nedit bbb Two\nedit ccc Three\n");
+
+        let mut check = StallCheck::new();
+        assert!(!check.stalled(&first));
+        assert!(!check.stalled(&next));
+        assert!(check.stalled(&next));
+    }
+
+    #[test]
+    fn estimates_follow_the_recent_rate() {
+        let mut p = Progress::new(10);
+        assert_eq!(p.eta(), None);
+        assert_eq!(p.summary(), "0/10 · - commits/s · ETA -:--");
+        p.record(Duration::from_millis(500));
+        p.record(Duration::from_millis(500));
+        assert_eq!(p.rate(), Some(2.0));
+        assert_eq!(p.eta(), Some(Duration::from_secs(4)));
+        assert_eq!(p.summary(), "2/10 · 2.0 commits/s · ETA 0:04");
+    }
+
+    #[test]
+    fn total_grows_when_the_count_was_short() {
+        let mut p = Progress::new(1);
+        p.record(Duration::from_secs(1));
+        p.record(Duration::from_secs(1));
+        assert_eq!(p.total(), 2);
+        assert_eq!(p.eta(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn durations_format_as_clock_times() {
+        assert_eq!(format_duration(Duration::from_secs(75)), "1:15");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+    }
+}
@@ -1,4 +1,84 @@
-use dialoguer::{Confirm, Input, theme::ColorfulTheme};
+use crate::suggest;
+
+use dialoguer::{Completion, Confirm, Input, MultiSelect, Select, theme::ColorfulTheme};
+use regex::Regex;
+
+/// A rule an answer to a string prompt must follow, checked while the user
+/// is still at the prompt. Chain several with [`check_answer`].
+#[derive(Debug, Clone)]
+pub enum Check {
+    /// The answer is not blank.
+    NonEmpty,
+    /// The answer looks like an email address: `local@domain`, without spaces.
+    EmailSyntax,
+    /// The answer has no `<` or `>`, which would break the `Name <email>`
+    /// line Git writes.
+    NoAngleBrackets,
+    /// The answer matches `pattern`, configured under the git config key `key`.
+    Matches { pattern: Regex, key: String },
+}
+
+impl Check {
+    /// Checks `answer` (trimmed) against this rule.
+    ///
+    /// # Returns
+    /// `Ok(())` if it complies, or `Err(String)` with the message shown under the prompt.
+    pub fn check(&self, answer: &str) -> Result<(), String> {
+        let answer = answer.trim();
+        match self {
+            Check::NonEmpty if answer.is_empty() => Err(String::from("This cannot be empty.")),
+            Check::EmailSyntax => {
+                let valid = match answer.split_once('@') {
+                    Some((local, domain)) => {
+                        !local.is_empty()
+                            && !domain.is_empty()
+                            && !domain.contains('@')
+                            && !answer.contains(char::is_whitespace)
+                    }
+                    None => false,
+                };
+                if valid {
+                    Ok(())
+                } else {
+                    Err(format!("`{}` is not an email address (expected name@domain).", answer))
+                }
+            }
+            Check::NoAngleBrackets if answer.contains(['<', '>']) => {
+                Err(String::from("`<` and `>` are not allowed."))
+            }
+            Check::Matches { pattern, key } if !pattern.is_match(answer) => Err(format!(
+                "`{}` does not match `{}` ({}).",
+                answer,
+                pattern.as_str(),
+                key
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Runs `checks` on `answer` in order.
+///
+/// # Returns
+/// `Ok(())` if every check passes, or the message of the first that fails.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::prompt::{check_answer, Check};
+///
+/// let checks = [Check::NonEmpty, Check::EmailSyntax];
+/// assert!(check_answer(&checks, "jane@corp.com").is_ok());
+/// assert_eq!(check_answer(&checks, " ").unwrap_err(), "This cannot be empty.");
+/// ```
+pub fn check_answer(checks: &[Check], answer: &str) -> Result<(), String> {
+    for check in checks {
+        if let Err(e) = check.check(answer) {
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
 /// Abstraction over a string input prompt.
 ///
@@ -15,6 +95,56 @@ pub trait StringPrompter {
     /// # Returns
     /// `Ok(String)` if input is successfully collected, or an `Err(String)` describing the failure.
     fn prompt(&mut self, prompt: &str, default: &str) -> Result<String, String>;
+
+    /// Prompt the user for a string input, offering `suggestions` to complete it.
+    ///
+    /// The default implementation ignores the suggestions.
+    ///
+    /// # Parameters
+    /// - `prompt`: The message shown to the user.
+    /// - `default`: Default value if the user presses Enter without input.
+    /// - `suggestions`: Completion candidates, most likely first.
+    ///
+    /// # Returns
+    /// `Ok(String)` if input is successfully collected, or an `Err(String)` describing the failure.
+    fn prompt_suggesting(
+        &mut self,
+        prompt: &str,
+        default: &str,
+        _suggestions: &[String],
+    ) -> Result<String, String> {
+        self.prompt(prompt, default)
+    }
+
+    /// Prompt the user for a string input that must pass `checks`.
+    ///
+    /// Interactive implementations show a failed check under the prompt and
+    /// ask again; the default implementation prompts once and returns the
+    /// failure as an error.
+    ///
+    /// # Parameters
+    /// - `prompt`: The message shown to the user.
+    /// - `default`: Default value if the user presses Enter without input.
+    /// - `suggestions`: Completion candidates, most likely first.
+    /// - `checks`: The rules the answer must follow, see [`check_answer`].
+    ///
+    /// # Returns
+    /// `Ok(String)` with an answer that passes every check, or an `Err(String)` describing the failure.
+    fn prompt_checked(
+        &mut self,
+        prompt: &str,
+        default: &str,
+        suggestions: &[String],
+        checks: &[Check],
+    ) -> Result<String, String> {
+        match self.prompt_suggesting(prompt, default, suggestions) {
+            Ok(v) => match check_answer(checks, &v) {
+                Ok(()) => Ok(v),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
 }
 
 /// Abstraction over a boolean (yes/no) confirmation prompt.
@@ -33,6 +163,44 @@ pub trait ConfirmPrompter {
     fn confirm(&mut self, prompt: &str, default: bool) -> Result<bool, String>;
 }
 
+/// Abstraction over a prompt that picks any number of items from a list.
+pub trait MultiSelectPrompter {
+    /// Prompt the user to select items.
+    ///
+    /// # Parameters
+    /// - `prompt`: The message shown above the list.
+    /// - `items`: The choices, in display order.
+    /// - `defaults`: Whether each item starts out selected.
+    ///
+    /// # Returns
+    /// `Ok(Vec<usize>)` with the indices of the selected items, or `Err(String)` on input failure.
+    fn select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        defaults: &[bool],
+    ) -> Result<Vec<usize>, String>;
+}
+
+/// Abstraction over a prompt that picks exactly one item from a list.
+pub trait SelectPrompter {
+    /// Prompt the user to pick an item.
+    ///
+    /// # Parameters
+    /// - `prompt`: The message shown above the list.
+    /// - `items`: The choices, in display order.
+    /// - `default`: The index of the item highlighted initially.
+    ///
+    /// # Returns
+    /// `Ok(usize)` with the index of the chosen item, or `Err(String)` on input failure.
+    fn select_one(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        default: usize,
+    ) -> Result<usize, String>;
+}
+
 /// Default implementation of `StringPrompter` using `dialoguer::Input`.
 ///
 /// Uses the `ColorfulTheme` for user-friendly styling.
@@ -41,14 +209,61 @@ pub struct DialoguerStringPrompter;
 impl StringPrompter for DialoguerStringPrompter {
     fn prompt(&mut self, prompt: &str, default: &str) -> Result<String, String> {
         let theme = ColorfulTheme::default();
-        let input = Input::<String>::with_theme(&theme)
-            .with_prompt(prompt)
-            .default(default.to_string());
-        match input.interact_text() {
+        let input = Input::<String>::with_theme(&theme).with_prompt(prompt);
+        match with_default(input, default).interact_text() {
             Ok(v) => Ok(v),
             Err(e) => Err(e.to_string()),
         }
     }
+
+    fn prompt_suggesting(
+        &mut self,
+        prompt: &str,
+        default: &str,
+        suggestions: &[String],
+    ) -> Result<String, String> {
+        self.prompt_checked(prompt, default, suggestions, &[])
+    }
+
+    fn prompt_checked(
+        &mut self,
+        prompt: &str,
+        default: &str,
+        suggestions: &[String],
+        checks: &[Check],
+    ) -> Result<String, String> {
+        let theme = ColorfulTheme::default();
+        let completion = Suggestions(suggestions);
+        let input = Input::<String>::with_theme(&theme).with_prompt(prompt);
+        match with_default(input, default)
+            .completion_with(&completion)
+            .validate_with(|answer: &String| check_answer(checks, answer))
+            .interact_text()
+        {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Sets `default` as the input's default value; an empty default is not
+/// shown, and an empty answer is accepted instead.
+fn with_default<'a>(input: Input<'a, String>, default: &str) -> Input<'a, String> {
+    if default.is_empty() {
+        input.allow_empty(true)
+    } else {
+        input.default(default.to_string())
+    }
+}
+
+/// Tab (or the right arrow) completion over a list of suggestions, see
+/// [`suggest::complete`].
+struct Suggestions<'a>(&'a [String]);
+
+impl Completion for Suggestions<'_> {
+    fn get(&self, input: &str) -> Option<String> {
+        suggest::complete(self.0, input)
+    }
 }
 
 /// Default implementation of `ConfirmPrompter` using `dialoguer::Confirm`.
@@ -69,6 +284,54 @@ impl ConfirmPrompter for DialoguerConfirmPrompter {
     }
 }
 
+/// Default implementation of `MultiSelectPrompter` using `dialoguer::MultiSelect`.
+///
+/// Space toggles an item and Enter accepts the selection.
+pub struct DialoguerMultiSelectPrompter;
+
+impl MultiSelectPrompter for DialoguerMultiSelectPrompter {
+    fn select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        defaults: &[bool],
+    ) -> Result<Vec<usize>, String> {
+        let theme = ColorfulTheme::default();
+        let select = MultiSelect::with_theme(&theme)
+            .with_prompt(prompt)
+            .items(items)
+            .defaults(defaults);
+        match select.interact() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Default implementation of `SelectPrompter` using `dialoguer::Select`.
+///
+/// The arrow keys move between items and Enter picks one.
+pub struct DialoguerSelectPrompter;
+
+impl SelectPrompter for DialoguerSelectPrompter {
+    fn select_one(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        default: usize,
+    ) -> Result<usize, String> {
+        let theme = ColorfulTheme::default();
+        let select = Select::with_theme(&theme)
+            .with_prompt(prompt)
+            .items(items)
+            .default(default);
+        match select.interact() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
 /// Prompt the user for an input string, including context from a repository name.
 ///
 /// Wraps the `StringPrompter` trait and constructs a prompt of the form:
@@ -93,6 +356,37 @@ pub fn ask<P: StringPrompter>(
     prompter.prompt(&prompt, default_value)
 }
 
+/// Like [`ask`], offering `suggestions` (e.g. identities already used in the
+/// repository) for Tab completion and only accepting an answer that passes
+/// `checks`.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `StringPrompter` implementation.
+/// - `label`: A short description of what is being requested (e.g., `"Author email"`).
+/// - `repo_name`: The name of the current repository, shown for context.
+/// - `default_value`: A fallback if the user presses Enter without typing.
+/// - `suggestions`: Completion candidates, most likely first.
+/// - `checks`: The rules the answer must follow, see [`check_answer`].
+///
+/// # Returns
+/// - `Ok(String)` containing user input or the default.
+/// - `Err(String)` if the input could not be collected or failed a check.
+pub fn ask_suggesting<P: StringPrompter>(
+    prompter: &mut P,
+    label: &str,
+    repo_name: &str,
+    default_value: &str,
+    suggestions: &[String],
+    checks: &[Check],
+) -> Result<String, String> {
+    let prompt = if suggestions.is_empty() {
+        format!("{} for {}", label, repo_name)
+    } else {
+        format!("{} for {} (Tab completes)", label, repo_name)
+    };
+    prompter.prompt_checked(&prompt, default_value, suggestions, checks)
+}
+
 /// Ask the user to confirm whether to begin rewriting commit history.
 ///
 /// Wraps the `ConfirmPrompter` trait with a specific prompt about rewriting commits.
@@ -109,6 +403,391 @@ pub fn confirm_start<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, Strin
     prompter.confirm(prompt, true)
 }
 
+/// Ask the user to confirm a risky rewrite by typing the repository's name,
+/// instead of answering yes/no.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `StringPrompter` implementation.
+/// - `repo_name`: The name that has to be typed.
+///
+/// # Returns
+/// - `Ok(true)` if the user typed `repo_name` (surrounding spaces are ignored).
+/// - `Ok(false)` if they typed anything else or nothing.
+/// - `Err(String)` if input failed.
+pub fn confirm_typed<P: StringPrompter>(prompter: &mut P, repo_name: &str) -> Result<bool, String> {
+    let prompt = format!("Type the repository name ({}) to start", repo_name);
+    match prompter.prompt(&prompt, "") {
+        Ok(v) => Ok(v.trim() == repo_name),
+        Err(e) => Err(e),
+    }
+}
+
+/// Ask the user to confirm creating `git replace` refs (`--replace-mode`).
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` if the user confirmed.
+/// - `Ok(false)` if the user declined.
+/// - `Err(String)` if input failed.
+pub fn confirm_replace<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "Create replacement commits now? (history itself is left unchanged)";
+    prompter.confirm(prompt, true)
+}
+
+/// Ask the user to confirm rewriting the branch's commit objects (the
+/// metadata engine).
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` if the user confirmed.
+/// - `Ok(false)` if the user declined.
+/// - `Err(String)` if input failed.
+pub fn confirm_metadata<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "Rewrite the branch now? (every commit keeps its tree, so nothing can conflict)";
+    prompter.confirm(prompt, true)
+}
+
+/// Ask the user to confirm rewriting the branch in-process (`--engine gix`).
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` if the user confirmed.
+/// - `Ok(false)` if the user declined.
+/// - `Err(String)` if input failed.
+pub fn confirm_gix<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "Rewrite the branch now? (commit objects are rewritten without a rebase)";
+    prompter.confirm(prompt, true)
+}
+
+/// Ask the user to confirm force-pushing a rewritten mirror (`--mirror`).
+///
+/// Defaults to "no", since the push replaces history on the server.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+/// - `count`: The number of refs that will be force-pushed.
+/// - `url`: The remote being pushed to.
+///
+/// # Returns
+/// - `Ok(true)` if the user confirmed.
+/// - `Ok(false)` if the user declined.
+/// - `Err(String)` if input failed.
+pub fn confirm_mirror_push<P: ConfirmPrompter>(
+    prompter: &mut P,
+    count: usize,
+    url: &str,
+) -> Result<bool, String> {
+    let prompt = format!("Force-push these {} refs to {}?", count, url);
+    prompter.confirm(&prompt, false)
+}
+
+/// Ask the user to confirm moving every rewritten branch (`--all-branches`).
+///
+/// Defaults to "yes", like starting any other rewrite.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+/// - `count`: The number of refs that will move.
+///
+/// # Returns
+/// - `Ok(true)` if the user confirmed.
+/// - `Ok(false)` if the user declined.
+/// - `Err(String)` if input failed.
+pub fn confirm_move_refs<P: ConfirmPrompter>(prompter: &mut P, count: usize) -> Result<bool, String> {
+    let prompt = format!("Move these {} refs to the rewritten history?", count);
+    prompter.confirm(&prompt, true)
+}
+
+/// Ask the user whether the global git config should get the new identity too.
+///
+/// Defaults to "no", since the global identity applies to every repository.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` if the user wants the global config updated.
+/// - `Ok(false)` otherwise.
+/// - `Err(String)` if input failed.
+pub fn confirm_global_update<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "Also update your global git config so other repositories use this identity?";
+    prompter.confirm(prompt, false)
+}
+
+/// Ask the user whether to replace a personal email with their GitHub noreply
+/// address, which keeps the personal one out of public history.
+///
+/// Defaults to "no", keeping the email as entered.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+/// - `email`: The personal email that was entered.
+///
+/// # Returns
+/// - `Ok(true)` if the user wants the noreply address instead.
+/// - `Ok(false)` otherwise.
+/// - `Err(String)` if input failed.
+pub fn confirm_noreply<P: ConfirmPrompter>(prompter: &mut P, email: &str) -> Result<bool, String> {
+    let prompt = format!(
+        "{} looks like a personal address. Use your GitHub noreply address (ID+username@users.noreply.github.com) instead?",
+        email
+    );
+    prompter.confirm(&prompt, false)
+}
+
+/// Ask for the GitHub username of a noreply address.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `StringPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(String)` with the trimmed input, empty to keep the original email.
+/// - `Err(String)` if input failed.
+pub fn ask_github_username<P: StringPrompter>(prompter: &mut P) -> Result<String, String> {
+    match prompter.prompt("GitHub username (blank keeps the email)", "") {
+        Ok(v) => Ok(v.trim().to_string()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Ask for the numeric GitHub account ID of a noreply address.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `StringPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(String)` with the trimmed input, empty for the username-only form.
+/// - `Err(String)` if input failed.
+pub fn ask_github_id<P: StringPrompter>(prompter: &mut P) -> Result<String, String> {
+    let prompt = "GitHub account ID, shown at https://github.com/settings/emails (blank for the older username-only form)";
+    match prompter.prompt(prompt, "") {
+        Ok(v) => Ok(v.trim().to_string()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Ask the user whether to rewrite to an email GitHub will not attribute to
+/// their account (`--check-github`).
+///
+/// Defaults to "no", since every rewritten commit would lose its attribution.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` to rewrite anyway.
+/// - `Ok(false)` to stop.
+/// - `Err(String)` if input failed.
+pub fn confirm_unattributed_email<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "Rewrite to this email anyway?";
+    prompter.confirm(prompt, false)
+}
+
+/// Ask the user whether to resolve the conflicts that stopped the rebase
+/// with `git mergetool`.
+///
+/// Defaults to "yes"; declining leaves the rebase stopped at the conflict.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+/// - `count`: The number of files with conflicts.
+///
+/// # Returns
+/// - `Ok(true)` to launch the merge tool.
+/// - `Ok(false)` to stop.
+/// - `Err(String)` if input failed.
+pub fn confirm_mergetool<P: ConfirmPrompter>(
+    prompter: &mut P,
+    count: usize,
+) -> Result<bool, String> {
+    let files = if count == 1 { "file has" } else { "files have" };
+    let prompt = format!(
+        "{} {} conflicts. Launch `git mergetool` to resolve them?",
+        count, files
+    );
+    prompter.confirm(&prompt, true)
+}
+
+/// Ask the user whether to abort the rebase after a Git command hung and was
+/// stopped (`--timeout`).
+///
+/// Defaults to "yes", which puts the branch back as it was before the run.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` to run `git rebase --abort`.
+/// - `Ok(false)` to leave the rebase stopped where it is.
+/// - `Err(String)` if input failed.
+pub fn confirm_abort_hung_rebase<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "Abort the rebase and leave the history as it was?";
+    prompter.confirm(prompt, true)
+}
+
+/// Ask the user whether to keep signing commits while `commit.gpgsign` is on.
+///
+/// Defaults to "yes", which keeps the configured behavior.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `ConfirmPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(true)` to sign every rewritten commit.
+/// - `Ok(false)` to turn signing off for this run.
+/// - `Err(String)` if input failed.
+pub fn confirm_commit_signing<P: ConfirmPrompter>(prompter: &mut P) -> Result<bool, String> {
+    let prompt = "commit.gpgsign is on. Sign every rewritten commit? (the key is unlocked once up front; no = don't sign during this run)";
+    prompter.confirm(prompt, true)
+}
+
+/// Ask the user which of the discovered repositories to rewrite (`--discover`).
+///
+/// Every repository starts out selected.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `MultiSelectPrompter` implementation.
+/// - `repos`: The repositories, as shown to the user.
+///
+/// # Returns
+/// - `Ok(Vec<usize>)` with the indices of the chosen repositories.
+/// - `Err(String)` if input failed.
+pub fn select_repositories<P: MultiSelectPrompter>(
+    prompter: &mut P,
+    repos: &[String],
+) -> Result<Vec<usize>, String> {
+    let prompt = "Rewrite which repositories? (space toggles, enter confirms)";
+    prompter.select(prompt, repos, &vec![true; repos.len()])
+}
+
+/// Ask the user which identity to rewrite the history to.
+///
+/// The first choice is highlighted, so Enter accepts the most likely identity.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `SelectPrompter` implementation.
+/// - `repo_name`: The name of the current repository, shown for context.
+/// - `choices`: The identities on offer, as shown to the user; the last one
+///   should be the option to type the identity in.
+///
+/// # Returns
+/// - `Ok(usize)` with the index of the chosen item.
+/// - `Err(String)` if input failed.
+pub fn select_identity<P: SelectPrompter>(
+    prompter: &mut P,
+    repo_name: &str,
+    choices: &[String],
+) -> Result<usize, String> {
+    let prompt = format!("New author for {}", repo_name);
+    prompter.select_one(&prompt, choices, 0)
+}
+
+/// Ask the user which identity the current commit should get (`--ask-each`).
+///
+/// The first choice is highlighted, so Enter accepts what the mapping suggests.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `SelectPrompter` implementation.
+/// - `choices`: The identities on offer, as shown to the user; the last one
+///   should be the option to skip the commit.
+///
+/// # Returns
+/// - `Ok(usize)` with the index of the chosen item.
+/// - `Err(String)` if input failed.
+pub fn select_commit_identity<P: SelectPrompter>(
+    prompter: &mut P,
+    choices: &[String],
+) -> Result<usize, String> {
+    let prompt = "Author for this commit";
+    prompter.select_one(prompt, choices, 0)
+}
+
+/// Ask the user which local branch to rewrite (`--pick-branch`).
+///
+/// The checked-out branch is highlighted, so Enter keeps it.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `SelectPrompter` implementation.
+/// - `choices`: The branches, as shown to the user.
+/// - `current`: The index of the checked-out branch.
+///
+/// # Returns
+/// - `Ok(usize)` with the index of the chosen branch.
+/// - `Err(String)` if input failed.
+pub fn select_branch<P: SelectPrompter>(
+    prompter: &mut P,
+    choices: &[String],
+    current: usize,
+) -> Result<usize, String> {
+    let prompt = "Rewrite which branch?";
+    prompter.select_one(prompt, choices, current)
+}
+
+/// Ask the user which OpenPGP key `--gpg-sign` signs the rewritten commits
+/// with, when more than one secret key can sign.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `SelectPrompter` implementation.
+/// - `keys`: The keys, as shown to the user (key ID and user IDs).
+/// - `preferred`: The index of the key highlighted initially.
+///
+/// # Returns
+/// - `Ok(usize)` with the index of the chosen key.
+/// - `Err(String)` if input failed.
+pub fn select_signing_key<P: SelectPrompter>(
+    prompter: &mut P,
+    keys: &[String],
+    preferred: usize,
+) -> Result<usize, String> {
+    let prompt = "Sign the rewritten commits with which key?";
+    prompter.select_one(prompt, keys, preferred)
+}
+
+/// What to do about a rebase found in progress when the tool starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeftoverRebase {
+    /// Carry on with it, amending each remaining stop like an author rewrite.
+    Resume,
+    /// Run `git rebase --abort`, then start the rewrite as usual.
+    Abort,
+    /// Leave it alone and exit.
+    Exit,
+}
+
+/// Ask the user what to do about a rebase that is already in progress, left
+/// behind by a crashed run or started by hand.
+///
+/// Defaults to exiting, which leaves the rebase untouched.
+///
+/// # Parameters
+/// - `prompter`: A mutable reference to a `SelectPrompter` implementation.
+///
+/// # Returns
+/// - `Ok(LeftoverRebase)` with the user's choice.
+/// - `Err(String)` if input failed.
+pub fn select_leftover_rebase<P: SelectPrompter>(prompter: &mut P) -> Result<LeftoverRebase, String> {
+    let prompt = "A rebase is already in progress in this repository. What should happen to it?";
+    let choices = [
+        (LeftoverRebase::Resume, "Resume it as an author rewrite"),
+        (LeftoverRebase::Abort, "Abort it (git rebase --abort) and start a new rewrite"),
+        (LeftoverRebase::Exit, "Exit and leave it as it is"),
+    ];
+    let items: Vec<String> = choices.iter().map(|(_, label)| label.to_string()).collect();
+    match prompter.select_one(prompt, &items, 2) {
+        Ok(i) => match choices.get(i) {
+            Some((choice, _)) => Ok(*choice),
+            None => Err(format!("invalid choice {}", i)),
+        },
+        Err(e) => Err(e),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +820,159 @@ mod tests {
         }
     }
 
+    struct MockMultiSelectPrompter {
+        pub response: Vec<usize>,
+    }
+
+    impl MultiSelectPrompter for MockMultiSelectPrompter {
+        fn select(
+            &mut self,
+            _prompt: &str,
+            items: &[String],
+            defaults: &[bool],
+        ) -> Result<Vec<usize>, String> {
+            assert_eq!(items.len(), defaults.len());
+            assert!(defaults.iter().all(|d| *d));
+            Ok(self.response.clone())
+        }
+    }
+
+    struct MockSelectPrompter {
+        pub response: usize,
+        pub expected_prompt: &'static str,
+        pub expected_default: usize,
+    }
+
+    impl SelectPrompter for MockSelectPrompter {
+        fn select_one(
+            &mut self,
+            prompt: &str,
+            items: &[String],
+            default: usize,
+        ) -> Result<usize, String> {
+            assert_eq!(prompt, self.expected_prompt);
+            assert!(default < items.len());
+            assert_eq!(default, self.expected_default);
+            Ok(self.response)
+        }
+    }
+
+    #[test]
+    fn test_select_commit_identity_highlights_the_first_choice() {
+        let mut prompter = MockSelectPrompter {
+            response: 1,
+            expected_prompt: "Author for this commit",
+            expected_default: 0,
+        };
+        let choices = vec![String::from("A <a@x>"), String::from("Skip")];
+        assert_eq!(select_commit_identity(&mut prompter, &choices).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_select_identity_names_the_repository() {
+        let mut prompter = MockSelectPrompter {
+            response: 0,
+            expected_prompt: "New author for my-repo",
+            expected_default: 0,
+        };
+        let choices = vec![
+            String::from("A <a@x> (local config)"),
+            String::from("Enter manually"),
+        ];
+        assert_eq!(
+            select_identity(&mut prompter, "my-repo", &choices).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_select_branch_highlights_the_current_branch() {
+        let mut prompter = MockSelectPrompter {
+            response: 0,
+            expected_prompt: "Rewrite which branch?",
+            expected_default: 1,
+        };
+        let choices = vec![String::from("feature"), String::from("main (current)")];
+        assert_eq!(select_branch(&mut prompter, &choices, 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_select_signing_key_highlights_the_preferred_key() {
+        let mut prompter = MockSelectPrompter {
+            response: 0,
+            expected_prompt: "Sign the rewritten commits with which key?",
+            expected_default: 1,
+        };
+        let keys = vec![
+            String::from("1111AAAA2222BBBB  Jane <jane@home.org>"),
+            String::from("5555EEEE6666FFFF  Jane <jane@corp.com>"),
+        ];
+        assert_eq!(select_signing_key(&mut prompter, &keys, 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_select_leftover_rebase_defaults_to_exit() {
+        let prompt = "A rebase is already in progress in this repository. What should happen to it?";
+        let mut prompter = MockSelectPrompter {
+            response: 0,
+            expected_prompt: prompt,
+            expected_default: 2,
+        };
+        assert_eq!(select_leftover_rebase(&mut prompter).unwrap(), LeftoverRebase::Resume);
+        prompter.response = 1;
+        assert_eq!(select_leftover_rebase(&mut prompter).unwrap(), LeftoverRebase::Abort);
+        prompter.response = 3;
+        assert!(select_leftover_rebase(&mut prompter).is_err());
+    }
+
+    #[test]
+    fn test_select_repositories_preselects_everything() {
+        let mut prompter = MockMultiSelectPrompter { response: vec![1] };
+        let repos = vec![String::from("a"), String::from("b")];
+        assert_eq!(select_repositories(&mut prompter, &repos).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_confirm_noreply_defaults_to_no() {
+        let mut prompter = MockConfirmPrompter {
+            response: Ok(false),
+            expected_prompt: "jane@gmail.com looks like a personal address. Use your GitHub noreply address (ID+username@users.noreply.github.com) instead?".to_string(),
+            expected_default: false,
+        };
+        assert!(!confirm_noreply(&mut prompter, "jane@gmail.com").unwrap());
+    }
+
+    #[test]
+    fn test_confirm_mergetool_counts_the_files() {
+        let mut prompter = MockConfirmPrompter {
+            response: Ok(true),
+            expected_prompt: "1 file has conflicts. Launch `git mergetool` to resolve them?"
+                .to_string(),
+            expected_default: true,
+        };
+        assert!(confirm_mergetool(&mut prompter, 1).unwrap());
+    }
+
+    #[test]
+    fn test_confirm_abort_hung_rebase_defaults_to_yes() {
+        let mut prompter = MockConfirmPrompter {
+            response: Ok(false),
+            expected_prompt: "Abort the rebase and leave the history as it was?".to_string(),
+            expected_default: true,
+        };
+        assert!(!confirm_abort_hung_rebase(&mut prompter).unwrap());
+    }
+
+    #[test]
+    fn test_confirm_mirror_push_defaults_to_no() {
+        let mut prompter = MockConfirmPrompter {
+            response: Ok(true),
+            expected_prompt: "Force-push these 3 refs to git@host:repo.git?".to_string(),
+            expected_default: false,
+        };
+        assert!(confirm_mirror_push(&mut prompter, 3, "git@host:repo.git").unwrap());
+    }
+
     #[test]
     fn test_ask_returns_user_input() {
         let mut prompter = MockStringPrompter {
@@ -152,6 +984,57 @@ mod tests {
         assert_eq!(result.unwrap(), "Alice");
     }
 
+    #[test]
+    fn test_ask_suggesting_mentions_completion_only_with_suggestions() {
+        let mut prompter = MockStringPrompter {
+            response: Ok("jane@corp.com".to_string()),
+            expected_prompt: "Author email for my-repo (Tab completes)".to_string(),
+            expected_default: "".to_string(),
+        };
+        let suggestions = vec!["jane@corp.com".to_string()];
+        let result = ask_suggesting(&mut prompter, "Author email", "my-repo", "", &suggestions, &[]);
+        assert_eq!(result.unwrap(), "jane@corp.com");
+
+        prompter.expected_prompt = "Author email for my-repo".to_string();
+        assert!(ask_suggesting(&mut prompter, "Author email", "my-repo", "", &[], &[]).is_ok());
+    }
+
+    #[test]
+    fn test_ask_suggesting_rejects_an_answer_failing_a_check() {
+        let mut prompter = MockStringPrompter {
+            response: Ok("Jane <jane@corp.com>".to_string()),
+            expected_prompt: "Author email for my-repo".to_string(),
+            expected_default: "".to_string(),
+        };
+        let checks = [Check::NonEmpty, Check::EmailSyntax, Check::NoAngleBrackets];
+        let result = ask_suggesting(&mut prompter, "Author email", "my-repo", "", &[], &checks);
+        assert_eq!(
+            result.unwrap_err(),
+            "`Jane <jane@corp.com>` is not an email address (expected name@domain)."
+        );
+    }
+
+    #[test]
+    fn test_check_answer_runs_the_checks_in_order() {
+        let corp = Check::Matches {
+            pattern: Regex::new("@corp\\.com$").unwrap(),
+            key: "author-rewrite.validate.email".to_string(),
+        };
+        let checks = [Check::NonEmpty, Check::EmailSyntax, Check::NoAngleBrackets, corp];
+        assert!(check_answer(&checks, " jane@corp.com ").is_ok());
+        assert_eq!(check_answer(&checks, "").unwrap_err(), "This cannot be empty.");
+        assert!(check_answer(&checks, "jane@@corp.com").is_err());
+        assert!(check_answer(&checks, "ja ne@corp.com").is_err());
+        assert_eq!(
+            check_answer(&checks, "jane@gmail.com").unwrap_err(),
+            "`jane@gmail.com` does not match `@corp\\.com$` (author-rewrite.validate.email)."
+        );
+        assert_eq!(
+            check_answer(&[Check::NoAngleBrackets], "Jane <Doe>").unwrap_err(),
+            "`<` and `>` are not allowed."
+        );
+    }
+
     #[test]
     fn test_ask_returns_default_on_empty_input() {
         let mut prompter = MockStringPrompter {
@@ -174,6 +1057,19 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_confirm_typed_needs_the_exact_name() {
+        let mut prompter = MockStringPrompter {
+            response: Ok(" my-repo ".to_string()),
+            expected_prompt: "Type the repository name (my-repo) to start".to_string(),
+            expected_default: "".to_string(),
+        };
+        assert!(confirm_typed(&mut prompter, "my-repo").unwrap());
+
+        prompter.response = Ok("y".to_string());
+        assert!(!confirm_typed(&mut prompter, "my-repo").unwrap());
+    }
+
     #[test]
     fn test_confirm_start_true() {
         let mut prompter = MockConfirmPrompter {
@@ -183,7 +1079,7 @@ mod tests {
             expected_default: true,
         };
         let result = confirm_start(&mut prompter);
-        assert_eq!(result.unwrap(), true);
+        assert!(result.unwrap());
     }
 
     #[test]
@@ -195,7 +1091,7 @@ mod tests {
             expected_default: true,
         };
         let result = confirm_start(&mut prompter);
-        assert_eq!(result.unwrap(), false);
+        assert!(!result.unwrap());
     }
 
     #[test]
@@ -209,4 +1105,27 @@ mod tests {
         let result = confirm_start(&mut prompter);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_confirm_global_update_defaults_to_no() {
+        let mut prompter = MockConfirmPrompter {
+            response: Ok(false),
+            expected_prompt:
+                "Also update your global git config so other repositories use this identity?"
+                    .to_string(),
+            expected_default: false,
+        };
+        let result = confirm_global_update(&mut prompter);
+        assert!(!result.unwrap());
+    }
+
+    #[test]
+    fn test_confirm_commit_signing_defaults_to_yes() {
+        let mut prompter = MockConfirmPrompter {
+            response: Ok(true),
+            expected_prompt: "commit.gpgsign is on. Sign every rewritten commit? (the key is unlocked once up front; no = don't sign during this run)".to_string(),
+            expected_default: true,
+        };
+        assert!(confirm_commit_signing(&mut prompter).unwrap());
+    }
 }
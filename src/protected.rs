@@ -0,0 +1,169 @@
+use crate::git::Repo;
+
+use std::fmt;
+
+/// Git config key listing extra protected branch patterns, one per value
+/// (e.g. `main` or `release/*`).
+pub const CONFIG_KEY: &str = "author-rewrite.protected";
+
+/// Why a branch counts as protected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// It is the default branch of this remote (its `refs/remotes/<remote>/HEAD`).
+    RemoteDefault(String),
+    /// It matches this pattern from [`CONFIG_KEY`].
+    Pattern(String),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::RemoteDefault(remote) => write!(f, "it is the default branch of {}", remote),
+            Reason::Pattern(pattern) => write!(
+                f,
+                "it matches the protected pattern `{}` ({})",
+                pattern, CONFIG_KEY
+            ),
+        }
+    }
+}
+
+/// Matches `name` against a glob `pattern`, where `*` stands for any run of
+/// characters (including `/`) and `?` for exactly one.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // Where the last `*` was, and how much of `name` it has swallowed.
+    let mut star: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Decides whether `branch` is protected, given the default branches of the
+/// remotes and the configured patterns. A remote default wins over a pattern.
+///
+/// # Parameters
+///
+/// * `branch` – The short branch name, e.g. `main`.
+/// * `defaults` – Each remote with its default branch, e.g. `("origin", "main")`.
+/// * `patterns` – The values of [`CONFIG_KEY`].
+pub(crate) fn reason(
+    branch: &str,
+    defaults: &[(String, String)],
+    patterns: &[String],
+) -> Option<Reason> {
+    if let Some((remote, _)) = defaults.iter().find(|(_, b)| b == branch) {
+        return Some(Reason::RemoteDefault(remote.clone()));
+    }
+    patterns
+        .iter()
+        .map(|p| p.trim())
+        .find(|p| !p.is_empty() && glob_match(p, branch))
+        .map(|p| Reason::Pattern(p.to_string()))
+}
+
+/// Checks whether the branch `branch` (a full ref such as
+/// `refs/heads/main`) is protected: the default branch of its upstream's
+/// remote or of `origin`, or matched by a pattern in [`CONFIG_KEY`].
+///
+/// A remote without `refs/remotes/<remote>/HEAD` (e.g. one added with
+/// `git remote add` and never queried with `git remote set-head`) has no
+/// known default branch.
+///
+/// # Returns
+///
+/// * `Ok(Some(Reason))` if the branch is protected.
+/// * `Ok(None)` otherwise.
+/// * `Err(String)` if the configured patterns could not be read.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, protected};
+///
+/// let repo = Repo::discover().unwrap();
+/// if let Some(reason) = protected::check(&repo, "refs/heads/main").unwrap() {
+///     println!("main is protected: {}", reason);
+/// }
+/// ```
+pub fn check(repo: &Repo, branch: &str) -> Result<Option<Reason>, String> {
+    let short = branch.trim_start_matches("refs/heads/");
+    let mut remotes = vec![String::from("origin")];
+    let upstream_remote = repo
+        .config_get(&format!("branch.{}.remote", short))
+        .unwrap_or_default();
+    // `.` means the upstream is a local branch.
+    if !upstream_remote.is_empty() && upstream_remote != "." && upstream_remote != "origin" {
+        remotes.insert(0, upstream_remote);
+    }
+    let defaults: Vec<(String, String)> = remotes
+        .into_iter()
+        .filter_map(|remote| {
+            let default = match repo.remote_head(&remote) {
+                Some(d) => d,
+                None => return None,
+            };
+            Some((remote, default))
+        })
+        .collect();
+
+    let patterns = match repo.config_get_all(CONFIG_KEY) {
+        Ok(p) => p,
+        Err(e) => return Err(format!("invalid {}: {}", CONFIG_KEY, e)),
+    };
+    Ok(reason(short, &defaults, &patterns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, reason, Reason};
+
+    #[test]
+    fn glob_match_handles_stars_and_question_marks() {
+        assert!(glob_match("main", "main"));
+        assert!(!glob_match("main", "maint"));
+        assert!(glob_match("release/*", "release/1.2"));
+        assert!(glob_match("release/*", "release/1.x/hotfix"));
+        assert!(!glob_match("release/*", "releases"));
+        assert!(glob_match("v?", "v2"));
+        assert!(!glob_match("v?", "v10"));
+        assert!(glob_match("*-stable", "6.1-stable"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn reason_prefers_the_remote_default() {
+        let defaults = vec![(String::from("origin"), String::from("main"))];
+        let patterns = vec![String::from("main"), String::from("release/*")];
+        assert_eq!(
+            reason("main", &defaults, &patterns),
+            Some(Reason::RemoteDefault(String::from("origin")))
+        );
+        assert_eq!(
+            reason("release/2.0", &defaults, &patterns),
+            Some(Reason::Pattern(String::from("release/*")))
+        );
+        assert_eq!(reason("feature/x", &defaults, &patterns), None);
+        assert_eq!(
+            Reason::Pattern(String::from("release/*")).to_string(),
+            "it matches the protected pattern `release/*` (author-rewrite.protected)"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    commit_object::CommitObject,
+    git::Repo,
+    identity::{Identity, Mapping},
+    message::MessageEdits,
+    selection::Selection,
+};
+
+/// A commit that now has a replacement object with corrected identities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// The SHA of the original commit, which is left untouched.
+    pub original: String,
+    /// The SHA of the corrected object Git shows in its place.
+    pub replacement: String,
+}
+
+/// Builds the corrected version of the raw commit `raw`.
+///
+/// Trees, parents and dates are kept; the identities change, and so does the
+/// message where `edits` say so. Any signature is dropped, since it would no
+/// longer verify.
+///
+/// # Parameters
+///
+/// * `raw` – The commit as printed by `git cat-file commit`.
+/// * `sha` – The commit's SHA, recorded by `--annotate`.
+/// * `mapping` – How identities are rewritten.
+/// * `current_user` – The committer to record where the mapping leaves the
+///   committer to Git, matching what a rebase would record.
+/// * `edits` – Changes to the message; trailers are only added to commits
+///   that change anyway.
+///
+/// # Returns
+///
+/// * `Ok(Some(bytes))` with the new commit object.
+/// * `Ok(None)` if the commit would not change.
+/// * `Err(String)` if the commit could not be parsed.
+pub(crate) fn corrected_object(
+    raw: &[u8],
+    sha: &str,
+    mapping: &Mapping,
+    current_user: &Identity,
+    edits: &MessageEdits,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut obj = match CommitObject::parse(raw) {
+        Ok(o) => o,
+        Err(e) => return Err(e),
+    };
+    let identities_changed = match obj.apply_mapping(sha, mapping, current_user) {
+        Ok(c) => c,
+        Err(e) => return Err(e),
+    };
+    let message_changed = match edits.substitute(&obj.message) {
+        Some(m) => {
+            obj.message = m;
+            true
+        }
+        None => false,
+    };
+
+    if !identities_changed && !message_changed {
+        return Ok(None);
+    }
+    if let Some(m) = edits.annotate(&obj.message, sha) {
+        obj.message = m;
+    }
+    obj.drop_signature();
+    Ok(Some(obj.to_bytes()))
+}
+
+/// Creates `git replace` refs giving every selected commit corrected identities
+/// (and messages, for `--message-replace` and `--annotate`).
+///
+/// History itself is not rewritten: branches keep pointing at the original
+/// commits, and Git shows the replacements wherever those are referenced.
+/// Delete the refs with `git replace -d` to undo.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Replacement>)` with the commits that got a replacement.
+/// * `Err(String)` if a commit could not be read or a replacement written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, identity::{Identity, Mapping}, replace, selection::Selection};
+///
+/// let repo = Repo::discover().unwrap();
+/// let mapping = Mapping::Fixed(Identity::new("Jane Doe", "jane@corp.com"));
+/// let me = Identity::new("Jane Doe", "jane@corp.com");
+/// let done = replace::create_replacements(&repo, &Selection::All, &mapping, &me, &Default::default()).unwrap();
+/// println!("{} commits replaced", done.len());
+/// ```
+pub fn create_replacements(
+    repo: &Repo,
+    selection: &Selection,
+    mapping: &Mapping,
+    current_user: &Identity,
+    edits: &MessageEdits,
+) -> Result<Vec<Replacement>, String> {
+    let commits = match selection {
+        Selection::All => match repo.rev_list(&["HEAD"]) {
+            Ok(v) => v,
+            Err(e) => return Err(e),
+        },
+        Selection::Commits(shas) => shas.clone(),
+    };
+
+    let mut done = Vec::new();
+    for sha in commits {
+        let raw = match repo.cat_file("commit", &sha) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("cannot read commit {}: {}", sha, e)),
+        };
+        let corrected = match corrected_object(&raw, &sha, mapping, current_user, edits) {
+            Ok(Some(c)) => c,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("cannot rewrite commit {}: {}", sha, e)),
+        };
+        let replacement = match repo.hash_object("commit", corrected) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("cannot write replacement for {}: {}", sha, e)),
+        };
+        if let Err(e) = repo.replace(&sha, &replacement) {
+            return Err(format!("cannot replace {}: {}", sha, e));
+        }
+        done.push(Replacement {
+            original: sha,
+            replacement,
+        });
+    }
+
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::corrected_object;
+    use crate::{
+        identity::{parse_domain_swap, Identity, Mapping},
+        message::{parse_all, MessageEdits},
+    };
+
+    const SHA: &str = "1111111111111111111111111111111111111111";
+
+    const RAW: &[u8] = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+author Old <old@old.com> 1700000000 +0000\n\
+committer Old <old@old.com> 1700000000 +0000\n\
+\n\
+msg\n";
+
+    #[test]
+    fn fixed_mapping_sets_author_and_current_committer() {
+        let jane = Identity::new("Jane", "jane@new.com");
+        let out = corrected_object(
+            RAW,
+            SHA,
+            &Mapping::Fixed(jane.clone()),
+            &jane,
+            &MessageEdits::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+             author Jane <jane@new.com> 1700000000 +0000\n\
+             committer Jane <jane@new.com> 1700000000 +0000\n\
+             \n\
+             msg\n"
+        );
+    }
+
+    #[test]
+    fn unaffected_commits_are_skipped() {
+        let mapping = parse_domain_swap("elsewhere.com=new.com").unwrap();
+        let me = Identity::new("Me", "me@x.com");
+        let annotate = MessageEdits {
+            annotate: true,
+            ..MessageEdits::default()
+        };
+        assert_eq!(
+            corrected_object(RAW, SHA, &mapping, &me, &annotate).unwrap(),
+            None
+        );
+
+        let old = Identity::new("Old", "old@old.com");
+        assert_eq!(
+            corrected_object(RAW, SHA, &Mapping::Fixed(old.clone()), &old, &annotate).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn message_substitutions_change_otherwise_unaffected_commits() {
+        let old = Identity::new("Old", "old@old.com");
+        let edits = MessageEdits {
+            rewrites: parse_all(&[String::from("s/msg/message/")]).unwrap(),
+            annotate: true,
+            ..MessageEdits::default()
+        };
+        let out = corrected_object(RAW, SHA, &Mapping::Fixed(old.clone()), &old, &edits)
+            .unwrap()
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with(&format!(
+            "\n\nmessage\n\nRewritten-By: git-author-rewrite\nOriginal-Commit: {}\n",
+            SHA
+        )));
+    }
+}
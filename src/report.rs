@@ -0,0 +1,187 @@
+use crate::git::{Commit, Repo};
+
+use std::collections::{BTreeMap, BTreeSet};
+use unicode_width::UnicodeWidthStr;
+
+/// Commit counts per author, keyed by `Name <email>`.
+pub type Histogram = BTreeMap<String, usize>;
+
+/// One author's commit count before and after the rewrite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramRow {
+    /// The author, formatted as `Name <email>`.
+    pub identity: String,
+    /// Commits by this author before the rewrite.
+    pub before: usize,
+    /// Commits by this author after the rewrite.
+    pub after: usize,
+}
+
+/// Counts `commits` per author.
+pub fn count_authors<I: IntoIterator<Item = Commit>>(commits: I) -> Histogram {
+    let mut histogram = Histogram::new();
+    for commit in commits {
+        *histogram.entry(commit.author.to_string()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Counts the commits reachable from `HEAD` per author.
+///
+/// The log is streamed (see [`Repo::each_commit`]), so memory grows with
+/// the number of distinct authors, not with the length of the history.
+///
+/// # Returns
+///
+/// * `Ok(Histogram)` on success.
+/// * `Err(String)` if the history could not be read.
+pub fn author_histogram(repo: &Repo) -> Result<Histogram, String> {
+    author_histogram_at(repo, "HEAD")
+}
+
+/// Counts the commits reachable from `rev` per author, like
+/// [`author_histogram`] does for `HEAD`.
+pub fn author_histogram_at(repo: &Repo, rev: &str) -> Result<Histogram, String> {
+    let mut histogram = Histogram::new();
+    match repo.each_commit(&[rev], |commit| {
+        *histogram.entry(commit.author.to_string()).or_insert(0) += 1;
+    }) {
+        Ok(()) => Ok(histogram),
+        Err(e) => Err(e),
+    }
+}
+
+/// Joins two histograms into rows, busiest authors (before the rewrite) first.
+///
+/// Authors only present after the rewrite follow, ordered by their new count.
+pub fn compare(before: &Histogram, after: &Histogram) -> Vec<HistogramRow> {
+    let mut rows: Vec<HistogramRow> = before
+        .keys()
+        .chain(after.keys())
+        .collect::<BTreeSet<&String>>()
+        .into_iter()
+        .map(|identity| HistogramRow {
+            identity: identity.clone(),
+            before: before.get(identity).copied().unwrap_or(0),
+            after: after.get(identity).copied().unwrap_or(0),
+        })
+        .collect();
+
+    rows.sort_by(|a, b| {
+        b.before
+            .cmp(&a.before)
+            .then(b.after.cmp(&a.after))
+            .then(a.identity.cmp(&b.identity))
+    });
+    rows
+}
+
+/// Formats `rows` as an aligned table, without styling.
+///
+/// The last column shows the change in commit count, blank when unchanged.
+pub fn histogram_lines(rows: &[HistogramRow]) -> Vec<String> {
+    let header = "Author";
+    let width = rows
+        .iter()
+        .map(|r| r.identity.width())
+        .chain(std::iter::once(header.len()))
+        .max()
+        .unwrap_or(0);
+    let pad = |s: &str| format!("{}{}", s, " ".repeat(width - s.width()));
+
+    let mut lines = vec![format!("{}  {:>6}  {:>6}", pad(header), "Before", "After")];
+    for r in rows {
+        let delta = if r.after == r.before {
+            String::new()
+        } else {
+            format!("  {:+}", r.after as i64 - r.before as i64)
+        };
+        lines.push(format!(
+            "{}  {:>6}  {:>6}{}",
+            pad(&r.identity),
+            r.before,
+            r.after,
+            delta
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, count_authors, histogram_lines, Histogram, HistogramRow};
+    use crate::git::Commit;
+    use crate::identity::Identity;
+
+    /// Counts one commit per `(name, email)` author.
+    fn histogram(authors: &[(&str, &str)]) -> Histogram {
+        count_authors(authors.iter().map(|(name, email)| {
+            let id = Identity::new(name, email);
+            Commit {
+                sha: String::from("0000000"),
+                author: id.clone(),
+                committer: id,
+                author_time: 0,
+                commit_time: 0,
+                subject: String::new(),
+            }
+        }))
+    }
+
+    #[test]
+    fn count_authors_counts_each_identity() {
+        let h = histogram(&[("A", "a@x"), ("B", "b@x"), ("A", "a@x")]);
+        assert_eq!(h.get("A <a@x>"), Some(&2));
+        assert_eq!(h.get("B <b@x>"), Some(&1));
+    }
+
+    #[test]
+    fn compare_includes_vanished_and_new_authors() {
+        let before = histogram(&[("Old", "o@x"), ("Old", "o@x"), ("Keep", "k@x")]);
+        let after = histogram(&[("New", "n@x"), ("New", "n@x"), ("Keep", "k@x")]);
+        assert_eq!(
+            compare(&before, &after),
+            vec![
+                HistogramRow {
+                    identity: String::from("Old <o@x>"),
+                    before: 2,
+                    after: 0,
+                },
+                HistogramRow {
+                    identity: String::from("Keep <k@x>"),
+                    before: 1,
+                    after: 1,
+                },
+                HistogramRow {
+                    identity: String::from("New <n@x>"),
+                    before: 0,
+                    after: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn histogram_lines_align_and_show_deltas() {
+        let rows = vec![
+            HistogramRow {
+                identity: String::from("Old <o@x>"),
+                before: 2,
+                after: 0,
+            },
+            HistogramRow {
+                identity: String::from("K <k@x>"),
+                before: 1,
+                after: 1,
+            },
+        ];
+        assert_eq!(
+            histogram_lines(&rows),
+            vec![
+                "Author     Before   After",
+                "Old <o@x>       2       0  -2",
+                "K <k@x>         1       1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{commit_object::parse_signature, identity::Identity};
+
+use std::fs;
+
+/// One line of a `--rules` file: commits by `email`, optionally limited to a
+/// date range, get the author `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// The original author email to match (case-insensitive).
+    pub email: String,
+    /// Only match commits authored before this time (Unix seconds).
+    pub before: Option<i64>,
+    /// Only match commits authored at or after this time (Unix seconds).
+    pub after: Option<i64>,
+    /// The new author.
+    pub to: Identity,
+}
+
+impl Rule {
+    /// Reports whether the rule applies to `original`, authored at `time`.
+    ///
+    /// A rule with a date condition never matches when the time is unknown.
+    pub fn matches(&self, original: &Identity, time: Option<i64>) -> bool {
+        if !original.email.eq_ignore_ascii_case(&self.email) {
+            return false;
+        }
+        if self.before.is_none() && self.after.is_none() {
+            return true;
+        }
+        match time {
+            Some(t) => self.before.is_none_or(|b| t < b) && self.after.is_none_or(|a| t >= a),
+            None => false,
+        }
+    }
+}
+
+/// Returns the new author for `original` from the first matching rule.
+///
+/// # Returns
+///
+/// * `Some(Identity)` if a rule matches.
+/// * `None` if none does.
+pub fn resolve(rules: &[Rule], original: &Identity, time: Option<i64>) -> Option<Identity> {
+    rules
+        .iter()
+        .find(|r| r.matches(original, time))
+        .map(|r| r.to.clone())
+}
+
+/// Converts a `YYYY-MM-DD` date to Unix seconds at midnight UTC.
+pub(crate) fn parse_date(date: &str) -> Option<i64> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let (y, m, d) = match parts.as_slice() {
+        [y, m, d] if y.len() == 4 && m.len() == 2 && d.len() == 2 => {
+            match (y.parse::<i64>(), m.parse::<i64>(), d.parse::<i64>()) {
+                (Ok(y), Ok(m), Ok(d)) => (y, m, d),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+
+    // Days since 1970-01-01 in the proleptic Gregorian calendar.
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days * 86_400)
+}
+
+/// Parses one rule, e.g. `old@x.com before=2020-01-01 => Jane Doe <jane@x.com>`.
+fn parse_rule(line: &str) -> Result<Rule, String> {
+    let (lhs, rhs) = match line.split_once("=>") {
+        Some(parts) => parts,
+        None => {
+            return Err(String::from(
+                "expected `<email> [conditions] => Name <email>`",
+            ))
+        }
+    };
+    let to = match parse_signature(rhs.trim()) {
+        Some((id, "")) if !id.name.is_empty() && !id.email.is_empty() => id,
+        _ => {
+            return Err(format!(
+                "invalid identity `{}` (expected Name <email>)",
+                rhs.trim()
+            ))
+        }
+    };
+
+    let mut words = lhs.split_whitespace();
+    let email = match words.next() {
+        Some(e) => e.to_string(),
+        None => return Err(String::from("missing the email to match")),
+    };
+    let mut rule = Rule {
+        email,
+        before: None,
+        after: None,
+        to,
+    };
+    for condition in words {
+        let (slot, date) = match condition.split_once('=') {
+            Some(("before", d)) => (&mut rule.before, d),
+            Some(("after", d)) => (&mut rule.after, d),
+            _ => {
+                return Err(format!(
+                    "unknown condition `{}` (expected before=YYYY-MM-DD or after=YYYY-MM-DD)",
+                    condition
+                ))
+            }
+        };
+        match parse_date(date) {
+            Some(t) => *slot = Some(t),
+            None => return Err(format!("invalid date `{}` (expected YYYY-MM-DD)", date)),
+        }
+    }
+    Ok(rule)
+}
+
+/// Parses a rules file: one rule per line, first match wins. Blank lines and
+/// lines starting with `#` are ignored.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Rule>)` on success.
+/// * `Err(String)` naming the first invalid line.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::rules::parse;
+///
+/// let rules = parse(
+///     "shared@corp.com before=2020-01-01 => Ann Lee <ann@corp.com>\n\
+///      shared@corp.com => Bo Chen <bo@corp.com>\n",
+/// )
+/// .unwrap();
+/// assert_eq!(rules.len(), 2);
+/// ```
+pub fn parse(text: &str) -> Result<Vec<Rule>, String> {
+    let mut rules = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_rule(line) {
+            Ok(r) => rules.push(r),
+            Err(e) => return Err(format!("rules line {}: {}", i + 1, e)),
+        }
+    }
+    if rules.is_empty() {
+        return Err(String::from("the rules file contains no rules"));
+    }
+    Ok(rules)
+}
+
+/// Reads and parses the rules file at `path`.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Rule>)` on success.
+/// * `Err(String)` if the file could not be read or parsed.
+pub fn read(path: &str) -> Result<Vec<Rule>, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) => Err(format!("cannot read rules {}: {}", path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_date, resolve};
+    use crate::identity::Identity;
+
+    #[test]
+    fn parse_date_counts_from_the_epoch() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2020-01-01"), Some(1_577_836_800));
+        assert_eq!(parse_date("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(parse_date("2020-13-01"), None);
+        assert_eq!(parse_date("20-01-01"), None);
+    }
+
+    #[test]
+    fn rules_split_an_alias_by_date() {
+        let rules = parse(
+            "# shared alias\n\
+             old@alias.com before=2020-01-01 => Ann <ann@x.com>\n\
+             \n\
+             OLD@alias.com => Bo <bo@x.com>\n",
+        )
+        .expect("parse failed");
+        let alias = Identity::new("Alias", "old@alias.com");
+        let ann = Some(Identity::new("Ann", "ann@x.com"));
+        let bo = Some(Identity::new("Bo", "bo@x.com"));
+
+        assert_eq!(resolve(&rules, &alias, Some(1_500_000_000)), ann);
+        assert_eq!(resolve(&rules, &alias, Some(1_577_836_800)), bo);
+        assert_eq!(resolve(&rules, &alias, None), bo);
+        assert_eq!(
+            resolve(&rules, &Identity::new("X", "x@y.com"), Some(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_reports_the_offending_line() {
+        let err =
+            parse("a@x.com => A <a@x.com>\nb@x.com since=2020-01-01 => B <b@x.com>\n").unwrap_err();
+        assert!(
+            err.starts_with("rules line 2: unknown condition"),
+            "{}",
+            err
+        );
+        assert!(parse("a@x.com => just a name").is_err());
+        assert!(parse("a@x.com before=2020-1-1 => A <a@x.com>").is_err());
+        assert!(parse("# nothing\n").is_err());
+    }
+}
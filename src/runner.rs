@@ -0,0 +1,729 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The `--timeout` in seconds, 0 for none (see [`set_timeout`]).
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(0);
+
+/// How long a command may run before a note says it may be waiting for input.
+const HANG_NOTICE: Duration = Duration::from_secs(30);
+
+/// The first pause between checks on a command running under `--timeout`;
+/// it doubles up to [`POLL_INTERVAL`], so quick commands are not held up.
+const FIRST_POLL: Duration = Duration::from_millis(1);
+
+/// The longest pause between checks on a command running under `--timeout`.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Sets `--timeout`: from then on, [`ProcessRunner`] stops a command that has
+/// not exited after `limit` and fails it as hung. `None` waits for ever.
+///
+/// Commands marked [`GitCommand::interactive`] (an editor, `git mergetool`)
+/// are never stopped, since they wait for the user on purpose.
+pub fn set_timeout(limit: Option<Duration>) {
+    let secs = limit.map_or(0, |d| d.as_secs().max(1));
+    TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+}
+
+/// Returns the `--timeout` set with [`set_timeout`].
+fn timeout() -> Option<Duration> {
+    match TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// How one standard stream of a command is connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Io {
+    /// Shared with this process (the user's terminal).
+    Inherit,
+    /// Captured by the runner.
+    Piped,
+    /// Connected to the null device.
+    Null,
+    /// Written to this process's standard error, so Git's chatter stays out
+    /// of standard output, which is kept for results.
+    Stderr,
+}
+
+impl Io {
+    fn stdio(self) -> Stdio {
+        match self {
+            Io::Inherit => Stdio::inherit(),
+            Io::Piped => Stdio::piped(),
+            Io::Null => Stdio::null(),
+            Io::Stderr => Stdio::from(std::io::stderr()),
+        }
+    }
+}
+
+/// A command to run: its arguments, working directory, environment overrides
+/// and how its streams are connected.
+///
+/// The methods mirror [`std::process::Command`], so a command is built the
+/// same way; unlike it, the description can be inspected (by a
+/// [`GitRunner`] other than [`ProcessRunner`], or by `--show-commands`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitCommand {
+    /// The program, `git` unless [`GitCommand::program`] changed it.
+    pub program: OsString,
+    /// The arguments, in order.
+    pub args: Vec<OsString>,
+    /// The working directory, or `None` for the current one.
+    pub cwd: Option<PathBuf>,
+    /// Environment variables to set (`Some`) or remove (`None`).
+    pub envs: Vec<(OsString, Option<OsString>)>,
+    /// Where each stream goes; `None` keeps the runner's default.
+    pub stdin: Option<Io>,
+    pub stdout: Option<Io>,
+    pub stderr: Option<Io>,
+    /// Bytes fed to the command's stdin, if any.
+    pub input: Option<Vec<u8>>,
+    /// Whether the command waits for the user (an editor, `git mergetool`),
+    /// so `--timeout` never stops it.
+    pub interactive: bool,
+}
+
+impl Default for GitCommand {
+    fn default() -> Self {
+        GitCommand::new()
+    }
+}
+
+impl GitCommand {
+    /// Starts a `git` command without arguments.
+    pub fn new() -> GitCommand {
+        GitCommand::program("git")
+    }
+
+    /// Starts a command running `program` instead of `git` (e.g. `gpg`).
+    pub fn program<S: AsRef<OsStr>>(program: S) -> GitCommand {
+        GitCommand {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            cwd: None,
+            envs: Vec::new(),
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            input: None,
+            interactive: false,
+        }
+    }
+
+    /// Adds an argument.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds several arguments.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    /// Sets an environment variable for the command, replacing an earlier
+    /// setting or removal of it.
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) -> &mut Self {
+        self.envs.retain(|(k, _)| k != key.as_ref());
+        self.envs.push((
+            key.as_ref().to_os_string(),
+            Some(value.as_ref().to_os_string()),
+        ));
+        self
+    }
+
+    /// Sets several environment variables.
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (key, value) in vars {
+            self.env(key, value);
+        }
+        self
+    }
+
+    /// Removes an environment variable for the command, replacing an earlier
+    /// setting of it.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.envs.retain(|(k, _)| k != key.as_ref());
+        self.envs.push((key.as_ref().to_os_string(), None));
+        self
+    }
+
+    /// Runs the command in `dir`.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.cwd = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Connects stdin.
+    pub fn stdin(&mut self, io: Io) -> &mut Self {
+        self.stdin = Some(io);
+        self
+    }
+
+    /// Connects stdout.
+    pub fn stdout(&mut self, io: Io) -> &mut Self {
+        self.stdout = Some(io);
+        self
+    }
+
+    /// Connects stderr.
+    pub fn stderr(&mut self, io: Io) -> &mut Self {
+        self.stderr = Some(io);
+        self
+    }
+
+    /// Feeds `input` to the command's stdin.
+    pub fn input(&mut self, input: Vec<u8>) -> &mut Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Marks the command as waiting for the user, so it is never timed out.
+    pub fn interactive(&mut self) -> &mut Self {
+        self.interactive = true;
+        self
+    }
+
+    /// Returns the arguments joined by spaces, unquoted, e.g. `rev-parse HEAD`;
+    /// meant for matching commands, not for running them.
+    pub fn arg_line(&self) -> String {
+        self.args
+            .iter()
+            .map(|a| a.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Builds the [`std::process::Command`] this describes, without input.
+    fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        if let Some(dir) = &self.cwd {
+            cmd.current_dir(dir);
+        }
+        for (key, value) in &self.envs {
+            match value {
+                Some(v) => {
+                    cmd.env(key, v);
+                }
+                None => {
+                    cmd.env_remove(key);
+                }
+            }
+        }
+        if let Some(io) = self.stdin {
+            cmd.stdin(io.stdio());
+        }
+        if let Some(io) = self.stdout {
+            cmd.stdout(io.stdio());
+        }
+        if let Some(io) = self.stderr {
+            cmd.stderr(io.stdio());
+        }
+        cmd
+    }
+}
+
+/// How a command exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GitStatus {
+    /// The exit code, or `None` if the command was terminated by a signal.
+    pub code: Option<i32>,
+}
+
+impl GitStatus {
+    /// Whether the command exited with status 0.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Returns the exit code, if there is one.
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+}
+
+impl fmt::Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "exit status: {}", code),
+            None => write!(f, "a signal"),
+        }
+    }
+}
+
+/// What a finished command printed, and how it exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitOutput {
+    pub status: GitStatus,
+    /// The captured stdout; empty unless it was piped.
+    pub stdout: Vec<u8>,
+    /// The captured stderr; empty unless it was piped.
+    pub stderr: Vec<u8>,
+}
+
+impl GitOutput {
+    /// Returns stdout as text (invalid UTF-8 replaced), trimmed.
+    pub fn stdout_text(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).trim().to_string()
+    }
+
+    /// Returns stderr as text (invalid UTF-8 replaced), trimmed; e.g. the
+    /// hints and warnings Git prints even when a command succeeds.
+    pub fn stderr_text(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).trim().to_string()
+    }
+
+    /// Describes a failure from everything the command printed: stderr, then
+    /// stdout (where some commands, like `git commit`, explain themselves).
+    /// Empty if the command printed nothing.
+    pub fn error_text(&self) -> String {
+        let parts: Vec<String> = [self.stderr_text(), self.stdout_text()]
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .collect();
+        parts.join("\n")
+    }
+}
+
+/// Runs the commands of the git layer.
+///
+/// [`ProcessRunner`] starts real processes; tests can substitute a scripted
+/// runner with [`git::with_runner`](crate::git::with_runner).
+pub trait GitRunner {
+    /// Runs `cmd` and waits for it. Streams left unset are inherited.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitStatus)` once the command exited, successfully or not.
+    /// * `Err(String)` if it could not be started.
+    fn run_status(&self, cmd: &GitCommand) -> Result<GitStatus, String>;
+
+    /// Runs `cmd`, feeding it `cmd.input`, and waits for it. Unset stdout and
+    /// stderr are captured; unset stdin is the null device.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitOutput)` once the command exited, successfully or not.
+    /// * `Err(String)` if it could not be started.
+    fn run_output(&self, cmd: &GitCommand) -> Result<GitOutput, String>;
+
+    /// Runs `cmd` and passes each line of its stdout, without the line
+    /// ending, to `on_line` as it is read, so output of any size is never
+    /// held in memory at once. Unset stdin is the null device; unset stderr
+    /// is captured.
+    ///
+    /// The default implementation runs [`GitRunner::run_output`] and splits
+    /// what it captured.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GitOutput)` once the command exited, successfully or not, with
+    ///   an empty `stdout`.
+    /// * `Err(String)` if it could not be started or its stdout not read.
+    fn run_lines(
+        &self,
+        cmd: &GitCommand,
+        on_line: &mut dyn FnMut(&[u8]),
+    ) -> Result<GitOutput, String> {
+        match self.run_output(cmd) {
+            Ok(out) => {
+                for line in out.stdout.split_inclusive(|b| *b == b'\n') {
+                    on_line(trim_line_ending(line));
+                }
+                Ok(GitOutput {
+                    status: out.status,
+                    stdout: Vec::new(),
+                    stderr: out.stderr,
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Strips the `\n` or `\r\n` a line read from a command ends with, like
+/// [`str::lines`].
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// The note printed when `cmd` is still running after [`HANG_NOTICE`].
+fn hang_notice(cmd: &GitCommand) -> String {
+    format!(
+        "Still waiting for `{} {}` after {}s; a hook, credential helper or GPG pinentry may be waiting for input.",
+        cmd.program.to_string_lossy(),
+        cmd.arg_line(),
+        HANG_NOTICE.as_secs()
+    )
+}
+
+/// Waits for `child`, started from `cmd`, to exit.
+///
+/// After [`HANG_NOTICE`] a note on stderr says what it may be waiting for;
+/// after `limit` it is killed and reported as hung. Without a limit the
+/// wait blocks until the command exits, and a watchdog thread prints the
+/// note.
+///
+/// # Returns
+///
+/// * `Ok(ExitStatus)` once the command exited.
+/// * `Err(String)` if waiting failed or the command was stopped.
+fn wait_limited(
+    child: &mut Child,
+    cmd: &GitCommand,
+    limit: Option<Duration>,
+) -> Result<ExitStatus, String> {
+    let limit = match limit {
+        Some(l) => l,
+        None => return wait_unlimited(child, cmd),
+    };
+    let started = Instant::now();
+    let mut noticed = false;
+    let mut interval = FIRST_POLL;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) => {}
+            Err(e) => return Err(format!("{}", e)),
+        }
+        let waited = started.elapsed();
+        if waited >= limit {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!(
+                "`{} {}` appears hung: it did not finish within {}s (--timeout), so it was stopped",
+                cmd.program.to_string_lossy(),
+                cmd.arg_line(),
+                limit.as_secs()
+            ));
+        }
+        if !noticed && waited >= HANG_NOTICE {
+            noticed = true;
+            eprintln!("{}", hang_notice(cmd));
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(POLL_INTERVAL);
+    }
+}
+
+/// Blocks until `child` exits, while a watchdog thread prints the
+/// [`HANG_NOTICE`] note if that takes long.
+fn wait_unlimited(child: &mut Child, cmd: &GitCommand) -> Result<ExitStatus, String> {
+    let (exited, watch) = mpsc::channel::<()>();
+    let notice = hang_notice(cmd);
+    thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = watch.recv_timeout(HANG_NOTICE) {
+            eprintln!("{}", notice);
+        }
+    });
+    let status = child.wait();
+    // Dropping the sender wakes the watchdog, which then exits quietly.
+    drop(exited);
+    match status {
+        Ok(s) => Ok(s),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+/// Reads a captured stream to the end on its own thread, so a command
+/// filling one pipe cannot block while the other is being waited on.
+fn drain<R: Read + Send + 'static>(stream: Option<R>) -> Option<thread::JoinHandle<Vec<u8>>> {
+    stream.map(|mut s| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = s.read_to_end(&mut buf);
+            buf
+        })
+    })
+}
+
+/// The [`GitRunner`] that runs commands as subprocesses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessRunner;
+
+impl ProcessRunner {
+    /// The limit for `cmd`: the `--timeout`, unless it waits for the user.
+    fn limit_for(cmd: &GitCommand) -> Option<Duration> {
+        if cmd.interactive {
+            None
+        } else {
+            timeout()
+        }
+    }
+}
+
+impl GitRunner for ProcessRunner {
+    fn run_status(&self, cmd: &GitCommand) -> Result<GitStatus, String> {
+        let mut child = match cmd.to_command().spawn() {
+            Ok(c) => c,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        match wait_limited(&mut child, cmd, ProcessRunner::limit_for(cmd)) {
+            Ok(status) => Ok(GitStatus {
+                code: status.code(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn run_output(&self, cmd: &GitCommand) -> Result<GitOutput, String> {
+        let mut command = cmd.to_command();
+        if cmd.input.is_some() {
+            command.stdin(Stdio::piped());
+        } else if cmd.stdin.is_none() {
+            command.stdin(Stdio::null());
+        }
+        if cmd.stdout.is_none() {
+            command.stdout(Stdio::piped());
+        }
+        if cmd.stderr.is_none() {
+            command.stderr(Stdio::piped());
+        }
+        let mut child = match command.spawn() {
+            Ok(c) => c,
+            Err(e) => return Err(format!("{}", e)),
+        };
+
+        // Stdin is fed from a separate thread so a large input cannot
+        // deadlock against the command filling its stdout pipe.
+        let writer = match (child.stdin.take(), cmd.input.clone()) {
+            (Some(mut stdin), Some(input)) => Some(thread::spawn(move || {
+                let _ = stdin.write_all(&input);
+            })),
+            _ => None,
+        };
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        // A stopped command's own children (a hung hook) may keep the pipes
+        // open, so its readers are left behind rather than joined.
+        let status = match wait_limited(&mut child, cmd, ProcessRunner::limit_for(cmd)) {
+            Ok(s) => s,
+            Err(e) => return Err(e),
+        };
+        if let Some(handle) = writer {
+            let _ = handle.join();
+        }
+        let collect = |handle: Option<thread::JoinHandle<Vec<u8>>>| match handle {
+            Some(h) => h.join().unwrap_or_default(),
+            None => Vec::new(),
+        };
+        Ok(GitOutput {
+            status: GitStatus {
+                code: status.code(),
+            },
+            stdout: collect(stdout),
+            stderr: collect(stderr),
+        })
+    }
+
+    fn run_lines(
+        &self,
+        cmd: &GitCommand,
+        on_line: &mut dyn FnMut(&[u8]),
+    ) -> Result<GitOutput, String> {
+        let mut command = cmd.to_command();
+        if cmd.stdin.is_none() {
+            command.stdin(Stdio::null());
+        }
+        command.stdout(Stdio::piped());
+        if cmd.stderr.is_none() {
+            command.stderr(Stdio::piped());
+        }
+        let mut child = match command.spawn() {
+            Ok(c) => c,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        let stderr = drain(child.stderr.take());
+
+        // One line is buffered at a time. The `--timeout` only starts once
+        // stdout is closed: a command still printing is not hung.
+        if let Some(stdout) = child.stdout.take() {
+            let mut reader = BufReader::new(stdout);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match reader.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => on_line(trim_line_ending(&line)),
+                    Err(e) => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(format!("{}", e));
+                    }
+                }
+            }
+        }
+        let status = match wait_limited(&mut child, cmd, ProcessRunner::limit_for(cmd)) {
+            Ok(s) => s,
+            Err(e) => return Err(e),
+        };
+        Ok(GitOutput {
+            status: GitStatus {
+                code: status.code(),
+            },
+            stdout: Vec::new(),
+            stderr: match stderr {
+                Some(h) => h.join().unwrap_or_default(),
+                None => Vec::new(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        wait_limited, GitCommand, GitOutput, GitRunner, GitStatus, Io, ProcessRunner, POLL_INTERVAL,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn arg_line_joins_the_arguments() {
+        let mut cmd = GitCommand::new();
+        cmd.arg("log").args(["-1", "--format=%an <%ae>"]);
+        assert_eq!(cmd.arg_line(), "log -1 --format=%an <%ae>");
+        assert_eq!(cmd.program, "git");
+    }
+
+    #[test]
+    fn status_display_names_the_exit_code() {
+        assert_eq!(
+            GitStatus { code: Some(128) }.to_string(),
+            "exit status: 128"
+        );
+        assert!(!GitStatus { code: None }.success());
+    }
+
+    #[test]
+    fn error_text_keeps_both_streams() {
+        let out = GitOutput {
+            status: GitStatus { code: Some(1) },
+            stdout: b"nothing added to commit\n".to_vec(),
+            stderr: b"hint: use git add\n".to_vec(),
+        };
+        assert_eq!(
+            out.error_text(),
+            "hint: use git add\nnothing added to commit"
+        );
+        assert_eq!(out.stderr_text(), "hint: use git add");
+
+        let silent = GitOutput {
+            status: GitStatus { code: Some(128) },
+            stdout: Vec::new(),
+            stderr: b"  \n".to_vec(),
+        };
+        assert_eq!(silent.error_text(), "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_runner_feeds_input_and_captures_output() {
+        let runner = ProcessRunner;
+        let mut cmd = GitCommand::program("cat");
+        cmd.input(b"hello".to_vec());
+        let out = runner.run_output(&cmd).unwrap();
+        assert!(out.status.success());
+        assert_eq!(out.stdout, b"hello");
+
+        let mut cmd = GitCommand::program("sh");
+        cmd.args(["-c", "exit 3"]).stdout(Io::Null);
+        assert_eq!(runner.run_status(&cmd).unwrap().code(), Some(3));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_runner_streams_lines() {
+        let runner = ProcessRunner;
+        let mut cmd = GitCommand::program("sh");
+        cmd.args([
+            "-c",
+            "printf 'one\\ntwo\\r\\n\\nlast'; echo oops >&2; exit 2",
+        ]);
+        let mut lines = Vec::new();
+        let out = runner
+            .run_lines(&cmd, &mut |line| lines.push(line.to_vec()))
+            .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                b"one".to_vec(),
+                b"two".to_vec(),
+                Vec::new(),
+                b"last".to_vec()
+            ]
+        );
+        assert_eq!(out.status.code(), Some(2));
+        assert_eq!(out.stderr_text(), "oops");
+        assert!(out.stdout.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hung_commands_are_stopped_at_the_limit() {
+        let mut cmd = GitCommand::program("sleep");
+        cmd.arg("30");
+        let mut child = cmd.to_command().spawn().unwrap();
+        let err = wait_limited(&mut child, &cmd, Some(Duration::from_secs(1))).unwrap_err();
+        assert_eq!(
+            err,
+            "`sleep 30` appears hung: it did not finish within 1s (--timeout), so it was stopped"
+        );
+
+        let cmd = GitCommand::program("true");
+        let mut child = cmd.to_command().spawn().unwrap();
+        assert!(wait_limited(&mut child, &cmd, Some(Duration::from_secs(5)))
+            .unwrap()
+            .success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quick_commands_do_not_wait_for_a_poll() {
+        // The quickest of a few runs, so a busy machine does not fail it.
+        for limit in [None, Some(Duration::from_secs(5))] {
+            let cmd = GitCommand::program("true");
+            let fastest = (0..5)
+                .map(|_| {
+                    let mut child = cmd.to_command().spawn().unwrap();
+                    let started = Instant::now();
+                    assert!(wait_limited(&mut child, &cmd, limit).unwrap().success());
+                    started.elapsed()
+                })
+                .min()
+                .unwrap();
+            assert!(
+                fastest < POLL_INTERVAL / 2,
+                "took {:?} with limit {:?}",
+                fastest,
+                limit
+            );
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{
+    git::Repo,
+    identity::{CommitIdentities, Identity, Mapping},
+    preview::SignedCommit,
+    report::Histogram,
+    selection::Selection,
+};
+
+use std::collections::HashSet;
+
+/// The `git log` format of a scan, `{sig}` standing for the signature
+/// status: SHA, parents, author, committer, author date, signature, subject.
+const SCAN_FORMAT: &str = "%H%x00%P%x00%an%x00%ae%x00%cn%x00%ce%x00%at%x00{sig}%x00%s";
+
+/// What a [`scan`] works out besides the authors.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions<'a> {
+    /// The commits to be rewritten, as resolved from the command line.
+    pub selection: &'a Selection,
+    /// Narrow the selection to the commits this mapping has to visit: every
+    /// selected commit up to and including the oldest one whose identities
+    /// it changes. The rebase re-creates everything after that commit anyway,
+    /// so those stay selected. A fixed mapping changes every commit and
+    /// narrows nothing.
+    pub restrict: Option<&'a Mapping>,
+    /// Compare the selection with the remote-tracking branches.
+    pub remotes: bool,
+    /// Read signatures (`%G?`), which makes Git verify each signed commit.
+    pub signatures: bool,
+}
+
+/// What the checks before a rewrite need to know about the history, read in
+/// one `git log` pass instead of one walk per check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryScan {
+    /// Commits reachable from `HEAD` per author, the "before" of the report.
+    pub authors: Histogram,
+    /// The selection, narrowed if [`ScanOptions::restrict`] asked for it.
+    pub selection: Selection,
+    /// Commits in the selection.
+    pub total: usize,
+    /// Commits in the selection that a remote-tracking branch also reaches,
+    /// if [`ScanOptions::remotes`] asked for them.
+    pub pushed: Option<usize>,
+    /// Signed commits in the selection, newest first; always empty unless
+    /// [`ScanOptions::signatures`] asked for them.
+    pub signed: Vec<SignedCommit>,
+}
+
+/// A selected commit, held until the oldest one the mapping changes is known.
+struct Entry {
+    sha: String,
+    affected: bool,
+    pushed: bool,
+    signed: Option<SignedCommit>,
+}
+
+/// Folds `git log --topo-order` lines in [`SCAN_FORMAT`] into a [`HistoryScan`].
+///
+/// Topological order lists every commit after all of its children, so
+/// reachability is carried from child to parent: only the parents still
+/// waiting to be listed are held, never the whole history. Without a
+/// mapping to narrow by, nothing per commit is kept at all.
+pub(crate) struct Scanner<'a> {
+    options: ScanOptions<'a>,
+    /// Parents of commits reachable from `HEAD`, not listed yet.
+    from_head: HashSet<String>,
+    /// Parents of commits reachable from a remote-tracking branch, not
+    /// listed yet.
+    from_remotes: HashSet<String>,
+    authors: Histogram,
+    entries: Vec<Entry>,
+    total: usize,
+    pushed: usize,
+    signed: Vec<SignedCommit>,
+}
+
+impl<'a> Scanner<'a> {
+    /// Starts a scan of the commits reachable from `head`, with `remote_tips`
+    /// the commits the remote-tracking branches point to.
+    pub(crate) fn new(options: ScanOptions<'a>, head: &str, remote_tips: &[String]) -> Scanner<'a> {
+        let mut options = options;
+        if let Some(Mapping::Fixed(_)) = options.restrict {
+            options.restrict = None;
+        }
+        Scanner {
+            options,
+            from_head: HashSet::from([head.to_string()]),
+            from_remotes: remote_tips.iter().cloned().collect(),
+            authors: Histogram::new(),
+            entries: Vec::new(),
+            total: 0,
+            pushed: 0,
+            signed: Vec::new(),
+        }
+    }
+
+    /// Takes in one line of `git log` output; malformed lines are skipped.
+    pub(crate) fn add(&mut self, line: &str) {
+        let fields: Vec<&str> = line.splitn(9, '\0').collect();
+        if fields.len() < 9 || fields[0].is_empty() {
+            return;
+        }
+        let sha = fields[0];
+        let on_head = self.from_head.remove(sha);
+        let on_remote = self.from_remotes.remove(sha);
+        for parent in fields[1].split_whitespace() {
+            if on_head {
+                self.from_head.insert(parent.to_string());
+            }
+            if on_remote {
+                self.from_remotes.insert(parent.to_string());
+            }
+        }
+        // `--remotes` also lists commits that only the remotes have.
+        if !on_head {
+            return;
+        }
+
+        let author = Identity::new(fields[2], fields[3]);
+        *self.authors.entry(author.to_string()).or_insert(0) += 1;
+        if !self.options.selection.contains(sha) {
+            return;
+        }
+
+        let signed = match fields[7].chars().next() {
+            Some(status) if status != 'N' => Some(SignedCommit {
+                sha: sha.to_string(),
+                status,
+                subject: fields[8].to_string(),
+            }),
+            _ => None,
+        };
+        let entry = Entry {
+            sha: sha.to_string(),
+            affected: false,
+            pushed: on_remote,
+            signed,
+        };
+        match self.options.restrict {
+            Some(mapping) => {
+                let ids = CommitIdentities {
+                    author,
+                    committer: Identity::new(fields[4], fields[5]),
+                    author_time: fields[6].trim().parse().ok(),
+                    sha: Some(sha.to_string()),
+                };
+                self.entries.push(Entry {
+                    affected: mapping.plan(&ids).is_some(),
+                    ..entry
+                });
+            }
+            None => self.count(entry),
+        }
+    }
+
+    /// Counts a commit that stays selected.
+    fn count(&mut self, entry: Entry) {
+        self.total += 1;
+        if entry.pushed {
+            self.pushed += 1;
+        }
+        if let Some(signed) = entry.signed {
+            self.signed.push(signed);
+        }
+    }
+
+    /// Finishes the scan once Git has listed every commit.
+    pub(crate) fn finish(mut self) -> HistoryScan {
+        let selection = if self.options.restrict.is_some() {
+            let mut entries = std::mem::take(&mut self.entries);
+            let keep = match entries.iter().rposition(|e| e.affected) {
+                Some(oldest) => oldest + 1,
+                None => 0,
+            };
+            entries.truncate(keep);
+            let shas = entries.iter().map(|e| e.sha.clone()).collect();
+            for entry in entries {
+                self.count(entry);
+            }
+            Selection::from_shas(shas)
+        } else {
+            self.options.selection.clone()
+        };
+        HistoryScan {
+            authors: self.authors,
+            selection,
+            total: self.total,
+            pushed: if self.options.remotes {
+                Some(self.pushed)
+            } else {
+                None
+            },
+            signed: self.signed,
+        }
+    }
+}
+
+/// Reads everything the checks before a rewrite need from the commits
+/// reachable from `HEAD` (and the remote-tracking branches, if asked), in a
+/// single streamed `git log --topo-order`.
+///
+/// # Returns
+///
+/// * `Ok(HistoryScan)` on success.
+/// * `Err(String)` if `HEAD` or the history could not be read.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, scan::{self, ScanOptions}, selection::Selection};
+///
+/// let repo = Repo::discover().unwrap();
+/// let found = scan::scan(
+///     &repo,
+///     ScanOptions {
+///         selection: &Selection::All,
+///         restrict: None,
+///         remotes: true,
+///         signatures: false,
+///     },
+/// )
+/// .unwrap();
+/// println!("{} of {} commits are on a remote", found.pushed.unwrap(), found.total);
+/// ```
+pub fn scan(repo: &Repo, options: ScanOptions) -> Result<HistoryScan, String> {
+    let head = match repo.rev_parse("HEAD") {
+        Ok(sha) => sha,
+        Err(e) => return Err(e),
+    };
+    let remote_tips: Vec<String> = if options.remotes {
+        match repo.for_each_ref("%(objectname)", "refs/remotes") {
+            Ok(out) => out.lines().map(|l| l.trim().to_string()).collect(),
+            Err(e) => return Err(e),
+        }
+    } else {
+        Vec::new()
+    };
+
+    let sig = if options.signatures { "%G?" } else { "N" };
+    let format = SCAN_FORMAT.replace("{sig}", sig);
+    let mut args = vec!["--topo-order", "HEAD"];
+    if options.remotes {
+        args.push("--remotes");
+    }
+    let mut scanner = Scanner::new(options, &head, &remote_tips);
+    match repo.each_log_line(&format, &args, |line| scanner.add(line)) {
+        Ok(()) => Ok(scanner.finish()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScanOptions, Scanner};
+    use crate::{identity::parse_domain_swap, selection::Selection};
+
+    fn options(selection: &Selection) -> ScanOptions<'_> {
+        ScanOptions {
+            selection,
+            restrict: None,
+            remotes: true,
+            signatures: true,
+        }
+    }
+
+    fn line(sha: &str, parents: &str, email: &str, sig: &str) -> String {
+        format!(
+            "{}\0{}\0{}\0{}\0{}\0{}\0100\0{}\0subject {}",
+            sha, parents, sha, email, sha, email, sig, sha
+        )
+    }
+
+    #[test]
+    fn reachability_follows_parents_in_one_pass() {
+        // d (HEAD) merges c; r is only on the remote, which has b.
+        let selection = Selection::All;
+        let mut scanner = Scanner::new(options(&selection), "d", &[String::from("r")]);
+        for l in [
+            line("d", "b c", "d@x.com", "N"),
+            line("r", "b", "r@x.com", "N"),
+            line("c", "a", "c@x.com", "G"),
+            line("b", "a", "b@x.com", "N"),
+            line("a", "", "a@x.com", "E"),
+        ] {
+            scanner.add(&l);
+        }
+        let scan = scanner.finish();
+        assert_eq!(scan.total, 4);
+        assert_eq!(scan.pushed, Some(2));
+        assert_eq!(scan.authors.len(), 4);
+        assert_eq!(scan.authors["c <c@x.com>"], 1);
+        assert!(!scan.authors.contains_key("r <r@x.com>"));
+        let signed: Vec<(&str, char)> = scan
+            .signed
+            .iter()
+            .map(|c| (c.sha.as_str(), c.status))
+            .collect();
+        assert_eq!(signed, vec![("c", 'G'), ("a", 'E')]);
+        assert_eq!(scan.selection, Selection::All);
+    }
+
+    #[test]
+    fn restrict_stops_at_the_oldest_affected_commit() {
+        let mapping = parse_domain_swap("old.com=new.com").expect("parse failed");
+        let selection = Selection::from_shas(vec![
+            String::from("d"),
+            String::from("c"),
+            String::from("a"),
+        ]);
+        let mut options = options(&selection);
+        options.restrict = Some(&mapping);
+        options.remotes = false;
+        let mut scanner = Scanner::new(options, "d", &[]);
+        for l in [
+            line("d", "c", "d@x.com", "N"),
+            line("c", "b", "c@old.com", "G"),
+            line("b", "a", "b@old.com", "N"),
+            line("a", "", "a@x.com", "G"),
+        ] {
+            scanner.add(&l);
+        }
+        let scan = scanner.finish();
+        assert_eq!(
+            scan.selection,
+            Selection::Commits(vec![String::from("c"), String::from("d")])
+        );
+        assert_eq!(scan.total, 2);
+        assert_eq!(scan.pushed, None);
+        assert_eq!(scan.signed.len(), 1);
+        assert_eq!(scan.authors.values().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn restrict_empties_the_selection_when_nothing_changes() {
+        let mapping = parse_domain_swap("old.com=new.com").expect("parse failed");
+        let selection = Selection::All;
+        let mut options = options(&selection);
+        options.restrict = Some(&mapping);
+        let mut scanner = Scanner::new(options, "a", &[]);
+        scanner.add(&line("a", "", "a@x.com", "N"));
+        scanner.add("garbage");
+        let scan = scanner.finish();
+        assert_eq!(scan.selection, Selection::Commits(Vec::new()));
+        assert_eq!((scan.total, scan.pushed), (0, Some(0)));
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{
+    git::Repo,
+    identity::Mapping,
+    scan::{self, ScanOptions},
+};
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Name of the file, inside the `.git` directory, that hands the selected
+/// commits over to the sequence editor.
+const SELECTION_FILE: &str = "author-rewrite-selection";
+
+/// The set of commits chosen for rewriting.
+///
+/// Every way of choosing commits (the whole branch, a revision range, an
+/// explicit list) resolves to one of these variants before the rebase starts,
+/// so the rest of the workflow does not care how the commits were chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Every commit reachable from `HEAD`.
+    All,
+    /// An explicit set of full commit SHAs, sorted ascending.
+    Commits(Vec<String>),
+}
+
+impl Selection {
+    /// Builds a selection from full SHAs, sorting and de-duplicating them.
+    pub fn from_shas(mut shas: Vec<String>) -> Selection {
+        shas.sort();
+        shas.dedup();
+        Selection::Commits(shas)
+    }
+
+    /// Reports whether the commit named by `sha` is selected.
+    ///
+    /// `sha` may be abbreviated, as it is in rebase todo lists; it matches any
+    /// selected SHA it is a prefix of.
+    pub fn contains(&self, sha: &str) -> bool {
+        match self {
+            Selection::All => true,
+            Selection::Commits(shas) => {
+                let idx = shas.partition_point(|s| s.as_str() < sha);
+                match shas.get(idx) {
+                    Some(s) => !sha.is_empty() && s.starts_with(sha),
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+/// Splits a `--revs` expression into individual `git rev-list` arguments.
+///
+/// Arguments are separated by whitespace; quoting is not supported because
+/// revision names cannot contain spaces.
+pub(crate) fn split_revs(expr: &str) -> Vec<String> {
+    expr.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+/// Parses a list of commits, one per line.
+///
+/// Only the first whitespace-separated word of each line is used, so output
+/// such as `git log --oneline` can be fed in directly. Blank lines and lines
+/// starting with `#` are ignored.
+pub(crate) fn parse_sha_list(body: &str) -> Vec<String> {
+    body.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_whitespace().next())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// Reads a commit list from `source`, which is a file path or `-` for stdin.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` with the commits as written (possibly abbreviated).
+/// * `Err(String)` if the source could not be read.
+pub fn read_sha_list(source: &str) -> Result<Vec<String>, String> {
+    if source == "-" {
+        let mut body = String::new();
+        return match io::stdin().read_to_string(&mut body) {
+            Ok(_) => Ok(parse_sha_list(&body)),
+            Err(e) => Err(format!("cannot read commit list from stdin: {}", e)),
+        };
+    }
+
+    match fs::read_to_string(source) {
+        Ok(body) => Ok(parse_sha_list(&body)),
+        Err(e) => Err(format!("cannot read commit list {}: {}", source, e)),
+    }
+}
+
+/// Restricts `listed` to commits reachable from `HEAD`.
+///
+/// Commits that are not part of `HEAD` cannot be rewritten by rebasing the
+/// current branch; they are dropped and counted so the caller can warn.
+fn keep_reachable(repo: &Repo, listed: Vec<String>) -> Result<(Selection, usize), String> {
+    let reachable: HashSet<String> = match repo.rev_list(&["HEAD"]) {
+        Ok(v) => v.into_iter().collect(),
+        Err(e) => return Err(e),
+    };
+
+    let total = listed.len();
+    let kept: Vec<String> = listed
+        .into_iter()
+        .filter(|sha| reachable.contains(sha))
+        .collect();
+    let ignored = total - kept.len();
+
+    Ok((Selection::from_shas(kept), ignored))
+}
+
+/// Resolves a `--revs` expression into a [`Selection`].
+///
+/// # Parameters
+///
+/// * `repo` – The repository to resolve them in.
+/// * `expr` – Arguments for `git rev-list`, separated by whitespace.
+///
+/// # Returns
+///
+/// * `Ok((Selection, usize))` – the selection and the number of listed
+///   commits ignored because they are not part of `HEAD`.
+/// * `Err(String)` if `git rev-list` rejected the expression.
+pub fn resolve_revs(repo: &Repo, expr: &str) -> Result<(Selection, usize), String> {
+    let args = split_revs(expr);
+    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let listed = match repo.rev_list(&arg_refs) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("invalid --revs expression `{}`: {}", expr, e)),
+    };
+
+    keep_reachable(repo, listed)
+}
+
+/// Resolves an explicit list of (possibly abbreviated) commits into a [`Selection`].
+///
+/// # Parameters
+///
+/// * `repo` – The repository to resolve them in.
+/// * `commits` – Commit names as read by [`read_sha_list`].
+///
+/// # Returns
+///
+/// * `Ok((Selection, usize))` – the selection and the number of commits
+///   ignored because they are not part of `HEAD`.
+/// * `Err(String)` if any entry does not name a commit.
+pub fn resolve_list(repo: &Repo, commits: &[String]) -> Result<(Selection, usize), String> {
+    if commits.is_empty() {
+        return Ok((Selection::Commits(Vec::new()), 0));
+    }
+
+    let mut input = commits.join("\n");
+    input.push('\n');
+    let listed = match repo.rev_list_stdin(&["--no-walk"], &input) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("invalid commit list: {}", e)),
+    };
+
+    keep_reachable(repo, listed)
+}
+
+/// Removes the `excluded` commits from `selection`, so they are left as
+/// `pick` whatever else matches them.
+///
+/// [`Selection::All`] is expanded to the commits reachable from `HEAD` first.
+/// Excluded commits that are not selected anyway are ignored.
+///
+/// # Parameters
+///
+/// * `repo` – The repository to resolve them in.
+/// * `selection` – The commits selected so far.
+/// * `excluded` – Commit names as given to `--exclude` or read by [`read_sha_list`].
+///
+/// # Returns
+///
+/// * `Ok(Selection)` – the selection without the excluded commits.
+/// * `Err(String)` if any entry does not name a commit or the history could
+///   not be read.
+pub fn exclude(
+    repo: &Repo,
+    selection: Selection,
+    excluded: &[String],
+) -> Result<Selection, String> {
+    if excluded.is_empty() {
+        return Ok(selection);
+    }
+
+    let mut input = excluded.join("\n");
+    input.push('\n');
+    let excluded: HashSet<String> = match repo.rev_list_stdin(&["--no-walk"], &input) {
+        Ok(v) => v.into_iter().collect(),
+        Err(e) => return Err(format!("invalid excluded commit: {}", e)),
+    };
+
+    let selected = match expand(repo, selection) {
+        Ok(v) => v,
+        Err(e) => return Err(e),
+    };
+
+    Ok(without(selected, &excluded))
+}
+
+/// Removes merge commits from `selection` (`--skip-merges`), so their
+/// identities are never changed.
+///
+/// The rebase engine flattens merges away in any case; with the engines that
+/// keep them, skipped merges are only re-parented onto the rewritten commits.
+///
+/// # Returns
+///
+/// * `Ok(Selection)` – the selection without merge commits.
+/// * `Err(String)` if the history could not be read.
+pub fn skip_merges(repo: &Repo, selection: Selection) -> Result<Selection, String> {
+    let merges: HashSet<String> = match repo.rev_list(&["--merges", "HEAD"]) {
+        Ok(v) => v.into_iter().collect(),
+        Err(e) => return Err(e),
+    };
+    if merges.is_empty() {
+        return Ok(selection);
+    }
+
+    let selected = match expand(repo, selection) {
+        Ok(v) => v,
+        Err(e) => return Err(e),
+    };
+
+    Ok(without(selected, &merges))
+}
+
+/// Lists the full SHAs of `selection`, reading [`Selection::All`] from `HEAD`.
+fn expand(repo: &Repo, selection: Selection) -> Result<Vec<String>, String> {
+    match selection {
+        Selection::All => repo.rev_list(&["HEAD"]),
+        Selection::Commits(shas) => Ok(shas),
+    }
+}
+
+/// Drops the full SHAs in `excluded` from `selected`.
+fn without(selected: Vec<String>, excluded: &HashSet<String>) -> Selection {
+    Selection::from_shas(
+        selected
+            .into_iter()
+            .filter(|sha| !excluded.contains(sha))
+            .collect(),
+    )
+}
+
+/// Narrows `selection` to the commits a per-identity `mapping` needs to visit.
+///
+/// A fixed mapping changes every commit, so the selection is returned as is.
+/// Other mappings may leave some identities untouched. Commits older than the oldest
+/// affected commit are left alone entirely; everything from there on is kept,
+/// because the rebase re-creates those commits anyway and the amend step has
+/// to restore their original committer. The rewrite itself gets this from
+/// [`scan::scan`] along with the rest of its checks.
+///
+/// # Returns
+///
+/// * `Ok(Selection)` – always [`Selection::Commits`] for non-fixed mappings.
+/// * `Err(String)` if the history could not be read.
+pub fn restrict_to_mapped(
+    repo: &Repo,
+    selection: &Selection,
+    mapping: &Mapping,
+) -> Result<Selection, String> {
+    if let Mapping::Fixed(_) = mapping {
+        return Ok(selection.clone());
+    }
+
+    let options = ScanOptions {
+        selection,
+        restrict: Some(mapping),
+        remotes: false,
+        signatures: false,
+    };
+    match scan::scan(repo, options) {
+        Ok(found) => Ok(found.selection),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the selected SHAs, one per line, to a file in `git_dir`.
+///
+/// # Parameters
+///
+/// * `git_dir` – Path to the repository's `.git` directory.
+/// * `shas` – The full SHAs to write.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` with the path of the written file.
+/// * `Err(String)` if the file could not be written.
+pub fn write_selection_file(git_dir: &Path, shas: &[String]) -> Result<PathBuf, String> {
+    let path = git_dir.join(SELECTION_FILE);
+    let mut body = shas.join("\n");
+    body.push('\n');
+
+    match fs::write(&path, body) {
+        Ok(_) => Ok(path),
+        Err(e) => Err(format!("cannot write {}: {}", path.display(), e)),
+    }
+}
+
+/// Removes the selection file from `git_dir`, if present.
+///
+/// Failures are ignored: a leftover file is harmless and is overwritten by
+/// the next run.
+pub fn remove_selection_file(git_dir: &Path) {
+    let _ = fs::remove_file(git_dir.join(SELECTION_FILE));
+}
+
+/// Reads a selection file written by [`write_selection_file`].
+///
+/// Blank lines are ignored.
+///
+/// # Returns
+///
+/// * `Ok(Selection)` on success.
+/// * `Err(String)` if the file could not be read.
+pub fn read_selection_file(path: &Path) -> Result<Selection, String> {
+    match fs::read_to_string(path) {
+        Ok(body) => Ok(Selection::from_shas(
+            body.lines()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_string())
+                .collect(),
+        )),
+        Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_sha_list, read_selection_file, split_revs, without, write_selection_file, Selection,
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn all_contains_everything() {
+        assert!(Selection::All.contains("abc123"));
+    }
+
+    #[test]
+    fn commits_match_abbreviated_prefix() {
+        let sel = Selection::from_shas(vec![String::from("bbbb2222"), String::from("aaaa1111")]);
+        assert!(sel.contains("aaaa"));
+        assert!(sel.contains("bbbb2222"));
+        assert!(!sel.contains("aaab"));
+        assert!(!sel.contains("cccc"));
+        assert!(!sel.contains(""));
+    }
+
+    #[test]
+    fn from_shas_sorts_and_dedups() {
+        let sel = Selection::from_shas(vec![
+            String::from("b"),
+            String::from("a"),
+            String::from("b"),
+        ]);
+        assert_eq!(
+            sel,
+            Selection::Commits(vec![String::from("a"), String::from("b")])
+        );
+    }
+
+    #[test]
+    fn split_revs_handles_multiple_arguments() {
+        assert_eq!(
+            split_revs("  v1.0..HEAD   --first-parent "),
+            vec!["v1.0..HEAD", "--first-parent"]
+        );
+    }
+
+    #[test]
+    fn parse_sha_list_takes_first_word_and_skips_comments() {
+        let body = "# audit output\nabc123 Fix typo\n\n  def456\n";
+        assert_eq!(parse_sha_list(body), vec!["abc123", "def456"]);
+    }
+
+    #[test]
+    fn without_drops_only_excluded_commits() {
+        let selected = vec![
+            String::from("cccc"),
+            String::from("aaaa"),
+            String::from("bbbb"),
+        ];
+        let excluded: HashSet<String> = [String::from("bbbb"), String::from("dddd")]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            without(selected, &excluded),
+            Selection::Commits(vec![String::from("aaaa"), String::from("cccc")])
+        );
+    }
+
+    #[test]
+    fn selection_file_round_trips() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let shas = vec![String::from("ffff"), String::from("0000")];
+        let path = write_selection_file(dir.path(), &shas).expect("write failed");
+        let sel = read_selection_file(&path).expect("read failed");
+        assert_eq!(sel, Selection::from_shas(shas));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    git, git_version,
+    preflight::{Check, Report, Status},
+    verify::{self, Expectation},
+};
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    time::Instant,
+};
+
+/// The identity the scratch commits are made with.
+const OLD_NAME: &str = "Selftest Old";
+const OLD_EMAIL: &str = "old@selftest.invalid";
+
+/// The identity the rewrite gives them.
+const NEW_NAME: &str = "Selftest New";
+const NEW_EMAIL: &str = "new@selftest.invalid";
+
+/// The files committed to the scratch repository, one commit each.
+const FIXTURE: [(&str, &str); 3] = [
+    ("README", "selftest\n"),
+    ("src.txt", "one\n"),
+    ("src.txt", "one\ntwo\n"),
+];
+
+/// Returns the scratch repository path for this run.
+pub fn workdir() -> PathBuf {
+    env::temp_dir().join(format!("git-author-rewrite-selftest-{}", process::id()))
+}
+
+/// Checks that a supported `git` is installed.
+fn git_check() -> Check {
+    let version = match git_version::detect() {
+        Ok(v) => v,
+        Err(e) => return Check::new("git", Status::Failed, e),
+    };
+    let required = git_version::required_features(&Default::default());
+    match git_version::unsupported(version, &required).first() {
+        Some(problem) => Check::new("git", Status::Failed, problem.clone()),
+        None => Check::new("git", Status::Passed, format!("git {}", version)),
+    }
+}
+
+/// Creates the scratch repository in `dir`: one commit per [`FIXTURE`]
+/// entry, all by the old identity.
+fn fixture_check(dir: &Path) -> Check {
+    let name = "scratch repository";
+    let _ = fs::remove_dir_all(dir);
+    if let Err(e) = fs::create_dir_all(dir) {
+        return Check::new(
+            name,
+            Status::Failed,
+            format!("cannot create {}: {}", dir.display(), e),
+        );
+    }
+    if let Err(e) = git::run_in(dir, &["init", "--quiet"], &[]) {
+        return Check::new(name, Status::Failed, format!("git init failed: {}", e));
+    }
+
+    let identity = [
+        ("GIT_AUTHOR_NAME", OLD_NAME),
+        ("GIT_AUTHOR_EMAIL", OLD_EMAIL),
+        ("GIT_COMMITTER_NAME", OLD_NAME),
+        ("GIT_COMMITTER_EMAIL", OLD_EMAIL),
+    ];
+    for (i, (file, contents)) in FIXTURE.iter().enumerate() {
+        if let Err(e) = fs::write(dir.join(file), contents) {
+            return Check::new(
+                name,
+                Status::Failed,
+                format!("cannot write {}: {}", file, e),
+            );
+        }
+        let message = format!("Selftest commit {}", i + 1);
+        let steps: [&[&str]; 2] = [
+            &["add", file],
+            // The scratch commits are not what is being tested, so they skip
+            // hooks and signing.
+            &[
+                "-c",
+                "commit.gpgsign=false",
+                "commit",
+                "--quiet",
+                "--no-verify",
+                "-m",
+                &message,
+            ],
+        ];
+        for args in steps {
+            if let Err(e) = git::run_in(dir, args, &identity) {
+                return Check::new(
+                    name,
+                    Status::Failed,
+                    format!("git {} failed: {}", args.join(" "), e),
+                );
+            }
+        }
+    }
+    Check::new(
+        name,
+        Status::Passed,
+        format!("{} commits by {} <{}>", FIXTURE.len(), OLD_NAME, OLD_EMAIL),
+    )
+}
+
+/// Describes the hooks and signing settings the rewrite will run with; they
+/// come from the global git config, so the scratch repository sees them too.
+pub(crate) fn settings_check(hooks_path: Option<&str>, gpgsign: Option<&str>) -> Check {
+    let hooks = match hooks_path {
+        Some(p) => format!("hooks from {}", p),
+        None => String::from("no hooks path configured"),
+    };
+    let signing = match gpgsign {
+        Some(v) if v.eq_ignore_ascii_case("true") => "commits are signed",
+        _ => "commits are not signed",
+    };
+    Check::new(
+        "hooks & signing",
+        Status::Passed,
+        format!("{}; {}", hooks, signing),
+    )
+}
+
+/// Runs the full non-interactive rewrite (`exe`) in `dir`.
+fn rewrite_check(exe: &Path, dir: &Path) -> Check {
+    let name = "rewrite";
+    let mut cmd = Command::new(exe);
+    cmd.args(["--name", NEW_NAME, "--email", NEW_EMAIL, "--yes"])
+        .current_dir(dir)
+        // A signing key may need its passphrase.
+        .stdin(Stdio::inherit());
+    let started = Instant::now();
+    let out = match cmd.output() {
+        Ok(o) => o,
+        Err(e) => return Check::new(name, Status::Failed, format!("cannot start: {}", e)),
+    };
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let reason = stderr
+            .lines()
+            .map(|l| l.trim())
+            .rfind(|l| !l.is_empty())
+            .unwrap_or("no error output")
+            .to_string();
+        let status = match out.status.code() {
+            Some(code) => format!("exited with status {}", code),
+            None => String::from("terminated by a signal"),
+        };
+        return Check::new(name, Status::Failed, format!("{}: {}", status, reason));
+    }
+    Check::new(
+        name,
+        Status::Passed,
+        format!("completed in {:.1} s", started.elapsed().as_secs_f64()),
+    )
+}
+
+/// Checks that every scratch commit is still there and carries the new
+/// identity, as author and committer.
+fn result_check(dir: &Path) -> Check {
+    let name = "result";
+    let format = format!("--format={}", git::COMMIT_FORMAT);
+    let log = match git::run_in(dir, &["log", &format], &[]) {
+        Ok(l) => l,
+        Err(e) => return Check::new(name, Status::Failed, format!("git log failed: {}", e)),
+    };
+    let expect = Expectation {
+        name: Some(String::from(NEW_NAME)),
+        email: Some(String::from(NEW_EMAIL)),
+    };
+    let report = verify::check_commits(git::parse_log(&log), &expect);
+    if report.checked != FIXTURE.len() {
+        return Check::new(
+            name,
+            Status::Failed,
+            format!(
+                "expected {} commits, found {}",
+                FIXTURE.len(),
+                report.checked
+            ),
+        );
+    }
+    match report.violations.first() {
+        Some(v) => Check::new(
+            name,
+            Status::Failed,
+            format!(
+                "commit {} still has {} {}",
+                &v.sha[..v.sha.len().min(7)],
+                v.role,
+                v.found
+            ),
+        ),
+        None => Check::new(
+            name,
+            Status::Passed,
+            format!(
+                "all {} commits now by {} <{}>",
+                report.checked, NEW_NAME, NEW_EMAIL
+            ),
+        ),
+    }
+}
+
+/// Rewrites a throwaway repository end to end, to confirm the environment
+/// (git version, hooks, signing) works before touching a real one.
+///
+/// A scratch repository with a few commits by a fake identity is created in
+/// `dir`, then `exe` rewrites it non-interactively, and the result is
+/// checked. The checks stop at the first failure. `dir` is deleted if every
+/// check passed and kept for inspection otherwise.
+///
+/// # Parameters
+///
+/// * `exe` – The `git-author-rewrite` binary to test.
+/// * `dir` – Where to create the scratch repository, e.g. [`workdir`].
+///
+/// # Returns
+///
+/// The checks that ran, in order.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it runs git-author-rewrite on a scratch repository.
+/// use git_author_rewrite::selftest;
+/// use std::env;
+///
+/// let exe = env::current_exe().unwrap();
+/// let report = selftest::run(&exe, &selftest::workdir());
+/// assert!(report.passed());
+/// ```
+pub fn run(exe: &Path, dir: &Path) -> Report {
+    let settings = || {
+        let hooks_path = git::run_in(dir, &["config", "--get", "core.hooksPath"], &[]).ok();
+        let gpgsign = git::run_in(dir, &["config", "--get", "commit.gpgsign"], &[]).ok();
+        settings_check(hooks_path.as_deref(), gpgsign.as_deref())
+    };
+    let steps: [&dyn Fn() -> Check; 5] = [
+        &git_check,
+        &|| fixture_check(dir),
+        &settings,
+        &|| rewrite_check(exe, dir),
+        &|| result_check(dir),
+    ];
+
+    let mut checks = Vec::new();
+    for step in steps {
+        let check = step();
+        let failed = check.status == Status::Failed;
+        checks.push(check);
+        if failed {
+            break;
+        }
+    }
+
+    let report = Report { checks, repo: None };
+    if report.passed() {
+        let _ = fs::remove_dir_all(dir);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::settings_check;
+    use crate::preflight::Status;
+
+    #[test]
+    fn settings_check_describes_hooks_and_signing() {
+        let check = settings_check(Some("~/.githooks"), Some("true"));
+        assert_eq!(check.status, Status::Passed);
+        assert_eq!(check.detail, "hooks from ~/.githooks; commits are signed");
+        let check = settings_check(None, None);
+        assert_eq!(
+            check.detail,
+            "no hooks path configured; commits are not signed"
+        );
+    }
+}
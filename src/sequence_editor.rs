@@ -1,7 +1,16 @@
+use crate::{
+    git::Repo,
+    selection::{self, Selection},
+    identity::Identity,
+    todo::{self, Entry, TodoCommand},
+};
+
 use std::{
-    fs::{File, read_to_string},
-    io::Write,
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, Write},
     path::Path,
+    process::Command,
 };
 
 /// Entry point to rewrite a todo file by replacing every leading `pick` with `edit`.
@@ -15,84 +24,340 @@ use std::{
 /// * `Ok(())` on success.
 /// * `Err(String)` if the file path is missing or an I/O operation fails.
 pub fn run(todo_path: Option<&str>) -> Result<(), String> {
+    run_selected(todo_path, &Selection::All)
+}
+
+/// Rewrites a todo file, replacing `pick` with `edit` only for selected commits.
+///
+/// # Arguments
+///
+/// * `todo_path` - Optional path to the todo file.
+/// * `selection` - The commits to mark for editing.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if the file path is missing or an I/O operation fails.
+pub fn run_selected(todo_path: Option<&str>, selection: &Selection) -> Result<(), String> {
     match todo_path {
-        Some(p) => rewrite(Path::new(p)),
+        Some(p) => rewrite_selected(Path::new(p), selection),
         None => Err(String::from("missing todo file path")),
     }
 }
 
-/// Reads the file at `path`, replaces every line that starts with `pick`
-/// (ignoring leading whitespace and non-comment lines) with `edit`,
-/// and writes the updated content back to the file.
+/// What the `--sequence-editor` invocation does to a todo list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TodoEdits {
+    /// The commits to mark for editing.
+    selection: Selection,
+    /// Whether to mark commits at all; off when the user picks them (`--open`).
+    mark: bool,
+    /// Authors whose selected commits are dropped, by email.
+    drop_authors: Vec<String>,
+    /// Whether to append each commit's author to its line.
+    show_authors: bool,
+    /// Shell commands to run after each commit marked for editing.
+    exec: Vec<String>,
+}
+
+/// Entry point for the `--sequence-editor` invocation.
+///
+/// Parses the arguments that follow `--sequence-editor`, then the todo file
+/// path that Git appends:
+///
+/// * `--select <file>` – Only mark the commits in a selection file written by
+///   [`selection::write_selection_file`].
+/// * `--drop-author <email>` – Turn the `pick` lines of that author's
+///   selected commits into `drop`. May be repeated.
+/// * `--show-authors` – Append each commit's author to its line.
+/// * `--exec <command>` – Add an `exec <command>` line after every commit
+///   marked for editing. May be repeated; the commands run in order.
+/// * `--open <editor>` – Open the todo list in `editor` instead of marking
+///   any commits, for `--manual`.
+/// * `--dry-run` – Print the transformed todo list to stdout and leave the
+///   file as it is, to inspect what a rewrite would do to it.
 ///
 /// # Arguments
 ///
-/// * `path` - Path to the todo file.
+/// * `args` - The arguments after `--sequence-editor`.
 ///
 /// # Returns
 ///
-/// * `Ok(())` on successful rewrite.
-/// * `Err(String)` if an I/O error occurs during reading or writing.
-pub fn rewrite(path: &Path) -> Result<(), String> {
-    let body = match read_to_string(path) {
-        Ok(content) => content,
-        Err(e) => return Err(format!("read failed: {}", e)),
+/// * `Ok(())` on success.
+/// * `Err(String)` on malformed arguments or I/O failure.
+pub fn run_args(args: &[String]) -> Result<(), String> {
+    let mut edits = TodoEdits {
+        selection: Selection::All,
+        mark: true,
+        drop_authors: Vec::new(),
+        show_authors: false,
+        exec: Vec::new(),
     };
+    let mut todo_path = None;
+    let mut open = None;
+    let mut dry_run = false;
+    let mut iter = args.iter();
 
-    let transformed = body
-        .lines()
-        .map(transform_line)
-        .collect::<Vec<String>>()
-        .join("\n")
-        + "\n";
+    while let Some(arg) = iter.next() {
+        if arg == "--dry-run" {
+            dry_run = true;
+        } else if arg == "--show-authors" {
+            edits.show_authors = true;
+        } else if arg == "--drop-author" {
+            match iter.next() {
+                Some(email) => edits.drop_authors.push(email.clone()),
+                None => return Err(String::from("`--drop-author` requires an email")),
+            }
+        } else if arg == "--exec" {
+            match iter.next() {
+                Some(command) => edits.exec.push(command.clone()),
+                None => return Err(String::from("`--exec` requires a command")),
+            }
+        } else if arg == "--open" {
+            open = match iter.next() {
+                Some(editor) => Some(editor),
+                None => return Err(String::from("`--open` requires an editor command")),
+            };
+            edits.mark = false;
+        } else if arg == "--select" {
+            let file = match iter.next() {
+                Some(f) => f,
+                None => return Err(String::from("`--select` requires a file path")),
+            };
+            edits.selection = match selection::read_selection_file(Path::new(file)) {
+                Ok(s) => s,
+                Err(e) => return Err(e),
+            };
+        } else {
+            todo_path = Some(arg.as_str());
+        }
+    }
 
-    let mut file = match File::create(path) {
-        Ok(f) => f,
-        Err(e) => return Err(format!("create failed: {}", e)),
+    let path = match todo_path {
+        Some(p) => Path::new(p),
+        None => return Err(String::from("missing todo file path")),
+    };
+    if dry_run && open.is_some() {
+        return Err(String::from("`--dry-run` cannot be combined with `--open`"));
+    }
+    let editor = match LineEditor::new(path, &edits) {
+        Ok(e) => e,
+        Err(e) => return Err(e),
     };
+    if dry_run {
+        return write_edited(path, &editor, io::stdout().lock());
+    }
+    if let Err(e) = todo::rewrite_file(path, |entry| editor.edit(entry)) {
+        return Err(e);
+    }
+    match open {
+        Some(editor) => open_in_editor(editor, path),
+        None => Ok(()),
+    }
+}
+
+/// Applies [`TodoEdits`] to the todo list one line at a time, as it is
+/// streamed through [`todo::transform`].
+struct LineEditor<'a> {
+    edits: &'a TodoEdits,
+    /// The author of every commit in the list, when `edits` needs them.
+    authors: HashMap<String, Identity>,
+}
+
+impl<'a> LineEditor<'a> {
+    /// Prepares `edits` for the todo file at `path`. Showing or dropping
+    /// authors reads the file once more beforehand, to look up the authors
+    /// of its commits in one `git log`.
+    fn new(path: &Path, edits: &'a TodoEdits) -> Result<LineEditor<'a>, String> {
+        let mut editor = LineEditor {
+            edits,
+            authors: HashMap::new(),
+        };
+        if !edits.show_authors && edits.drop_authors.is_empty() {
+            return Ok(editor);
+        }
+        let shas = match todo::read_commits(path) {
+            Ok(s) => s,
+            Err(e) => return Err(e),
+        };
+        // Git runs the sequence editor from the top of the working tree.
+        let repo = match Repo::discover() {
+            Ok(r) => r,
+            Err(e) => return Err(e),
+        };
+        let refs: Vec<&str> = shas.iter().map(String::as_str).collect();
+        let authors = match repo.commit_authors(&refs) {
+            Ok(a) => a,
+            Err(e) => return Err(e),
+        };
+        editor.authors = shas.into_iter().zip(authors).collect();
+        Ok(editor)
+    }
 
-    match file.write_all(transformed.as_bytes()) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(format!("write failed: {}", e)),
+    /// Edits one line and returns the `exec` lines to add after it.
+    fn edit(&self, entry: &mut Entry) -> Vec<String> {
+        let author = entry.commit().and_then(|sha| self.authors.get(sha));
+        if edit_entry(entry, author, self.edits) {
+            self.edits
+                .exec
+                .iter()
+                .map(|command| format!("exec {}", command))
+                .collect()
+        } else {
+            Vec::new()
+        }
     }
 }
 
-/// Converts a single line from a Git rebase todo file.
+/// Streams the todo file at `path` through `editor` into `output`, for
+/// `--dry-run`.
+fn write_edited(path: &Path, editor: &LineEditor, output: impl Write) -> Result<(), String> {
+    let input = match File::open(path) {
+        Ok(f) => BufReader::new(f),
+        Err(e) => return Err(format!("read failed: {}", e)),
+    };
+    todo::transform(input, output, |entry| editor.edit(entry))
+}
+
+/// Runs `editor` on the todo file at `path`, through the shell as Git does.
+fn open_in_editor(editor: &str, path: &Path) -> Result<(), String> {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(path);
+    match cmd.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err(format!("editor `{}` exited with non-zero status", editor)),
+        Err(e) => Err(format!("cannot run editor `{}`: {}", editor, e)),
+    }
+}
+
+/// Streams the file at `path`, replaces every line that starts with `pick`
+/// (ignoring leading whitespace and non-comment lines) with `edit`,
+/// and swaps the result in for the file (see [`todo::rewrite_file`]). Only
+/// the command words change; line endings (`\n` or `\r\n`), whitespace, a
+/// missing final newline and subjects that are not valid UTF-8 are kept
+/// byte for byte.
 ///
-/// - Comment lines (starting with `#`) are returned unchanged.
-/// - Lines starting with `pick` (ignoring leading whitespace) are
-///   replaced with `edit`, preserving original indentation.
-/// - All other lines are returned as-is.
+/// # Arguments
+///
+/// * `path` - Path to the todo file.
+///
+/// # Returns
+///
+/// * `Ok(())` on successful rewrite.
+/// * `Err(String)` if an I/O error occurs during reading or writing.
+pub fn rewrite(path: &Path) -> Result<(), String> {
+    rewrite_selected(path, &Selection::All)
+}
+
+/// Like [`rewrite`], but only `pick` lines whose commit is in `selection`
+/// are changed to `edit`.
 ///
 /// # Arguments
 ///
-/// * `line` - A single line from the input file.
+/// * `path` - Path to the todo file.
+/// * `selection` - The commits to mark for editing.
 ///
 /// # Returns
 ///
-/// * A transformed version of the line, possibly modified.
-fn transform_line(line: &str) -> String {
-    let trimmed = line.trim_start();
+/// * `Ok(())` on successful rewrite.
+/// * `Err(String)` if an I/O error occurs during reading or writing.
+pub fn rewrite_selected(path: &Path, selection: &Selection) -> Result<(), String> {
+    todo::rewrite_file(path, |entry| {
+        mark_entry(entry, selection);
+        Vec::new()
+    })
+}
 
-    if trimmed.starts_with('#') {
-        return line.to_string();
-    }
+/// Marks a `pick` line (or `p`, with `rebase.abbreviateCommands`) for
+/// editing if its commit is in `selection`, keeping its indentation and
+/// abbreviation. Comments and other commands are left alone.
+///
+/// # Arguments
+///
+/// * `entry` - A line of the todo file.
+/// * `selection` - The commits to mark for editing.
+///
+/// # Returns
+///
+/// * `true` if the line became `edit`.
+fn mark_entry(entry: &mut Entry, selection: &Selection) -> bool {
+    let selected = match entry.line() {
+        Some(l) if l.command == TodoCommand::Pick => {
+            l.commit().is_some_and(|sha| selection.contains(sha))
+        }
+        _ => false,
+    };
+    selected && entry.set_command(TodoCommand::Edit)
+}
 
-    if trimmed.starts_with("pick ") {
-        let indent_len = line.len() - trimmed.len();
-        let indent = &line[..indent_len];
+/// Applies `edits` to a single todo line whose commit (if any) was written
+/// by `author`.
+///
+/// - A `pick` of a selected commit by one of `edits.drop_authors` becomes
+///   `drop`; otherwise it is marked as in [`mark_entry`], if `edits.mark`
+///   is set.
+/// - With `edits.show_authors`, the author is appended as a trailing
+///   comment, which Git ignores along with the subject.
+///
+/// # Arguments
+///
+/// * `entry` - A line of the todo file.
+/// * `author` - The author of the line's commit, if looked up.
+/// * `edits` - What to change.
+///
+/// # Returns
+///
+/// * `true` if the line was marked for editing.
+fn edit_entry(entry: &mut Entry, author: Option<&Identity>, edits: &TodoEdits) -> bool {
+    let dropped = match (entry.line(), author) {
+        (Some(l), Some(a)) if l.command == TodoCommand::Pick => {
+            let selected = l.commit().is_some_and(|sha| edits.selection.contains(sha));
+            let by_dropped_author = edits
+                .drop_authors
+                .iter()
+                .any(|email| email.eq_ignore_ascii_case(&a.email));
+            selected && by_dropped_author
+        }
+        _ => false,
+    };
 
-        return format!("{}edit {}", indent, &trimmed[5..]);
+    let marked = if dropped {
+        entry.set_command(TodoCommand::Drop);
+        false
+    } else {
+        edits.mark && mark_entry(entry, &edits.selection)
+    };
+    if let Some(a) = author.filter(|_| edits.show_authors) {
+        entry.append(&format!(" # Author: {}", a));
     }
-
-    line.to_string()
+    marked
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{run, transform_line};
+    use super::{edit_entry, mark_entry, run, run_args, write_edited, LineEditor, TodoEdits};
+    use crate::identity::Identity;
+    use crate::selection::Selection;
+    use crate::todo::TodoFile;
     use std::io::{Read, Write};
 
+    /// Marks a one-line todo list and returns the line.
+    fn marked(line: &str, selection: &Selection) -> String {
+        let mut todo = TodoFile::parse(line.as_bytes());
+        mark_entry(&mut todo.entries[0], selection);
+        String::from_utf8(todo.to_bytes()).expect("not UTF-8")
+    }
+
+    /// Applies `edits` to a one-line todo list and returns the line.
+    fn edited(line: &[u8], author: Option<&Identity>, edits: &TodoEdits) -> Vec<u8> {
+        let mut todo = TodoFile::parse(line);
+        edit_entry(&mut todo.entries[0], author, edits);
+        todo.to_bytes()
+    }
+
     #[test]
     fn no_picks_is_noop() {
         let mut file = tempfile::NamedTempFile::new().expect("failed to create temp file");
@@ -111,38 +376,45 @@ mod tests {
     }
 
     #[test]
-    fn transform_line_converts_pick_to_edit() {
-        let result = transform_line("pick abc123 Commit message");
+    fn mark_entry_converts_pick_to_edit() {
+        let result = marked("pick abc123 Commit message", &Selection::All);
         assert_eq!(result, "edit abc123 Commit message");
     }
 
     #[test]
-    fn transform_line_preserves_space_indent() {
-        let result = transform_line("  pick abc123 Commit message");
+    fn mark_entry_preserves_space_indent() {
+        let result = marked("  pick abc123 Commit message", &Selection::All);
         assert_eq!(result, "  edit abc123 Commit message");
     }
 
     #[test]
-    fn transform_line_preserves_tab_indent() {
-        let result = transform_line("\tpick abc123 Commit message");
+    fn mark_entry_preserves_tab_indent() {
+        let result = marked("\tpick abc123 Commit message", &Selection::All);
         assert_eq!(result, "\tedit abc123 Commit message");
     }
 
     #[test]
-    fn transform_line_preserves_mixed_indent() {
-        let result = transform_line("\t  pick abc123 Commit message");
+    fn mark_entry_preserves_mixed_indent() {
+        let result = marked("\t  pick abc123 Commit message", &Selection::All);
         assert_eq!(result, "\t  edit abc123 Commit message");
     }
 
     #[test]
-    fn transform_line_leaves_comments_unchanged() {
-        let result = transform_line("# pick abc123 Commit message");
+    fn mark_entry_leaves_comments_unchanged() {
+        let result = marked("# pick abc123 Commit message", &Selection::All);
         assert_eq!(result, "# pick abc123 Commit message");
     }
 
     #[test]
-    fn transform_line_leaves_other_commands_unchanged() {
-        let result = transform_line("squash abc123 Commit message");
+    fn mark_entry_handles_abbreviated_commands() {
+        assert_eq!(marked("p abc123 Message", &Selection::All), "e abc123 Message");
+        assert_eq!(marked("f abc123 Message", &Selection::All), "f abc123 Message");
+        assert_eq!(marked("x make test", &Selection::All), "x make test");
+    }
+
+    #[test]
+    fn mark_entry_leaves_other_commands_unchanged() {
+        let result = marked("squash abc123 Commit message", &Selection::All);
         assert_eq!(result, "squash abc123 Commit message");
     }
 
@@ -154,7 +426,7 @@ mod tests {
     }
 
     #[test]
-    fn empty_file_produces_single_newline() {
+    fn empty_file_stays_empty() {
         let file = tempfile::NamedTempFile::new().expect("failed to create temp file");
         let path = file.path().to_path_buf();
 
@@ -164,6 +436,149 @@ mod tests {
         let mut f = std::fs::File::open(&path).expect("failed to open file");
         f.read_to_string(&mut s).expect("failed to read file");
 
-        assert_eq!(s, "\n");
+        assert_eq!(s, "");
+    }
+
+    #[test]
+    fn non_utf8_subjects_survive_a_rewrite() {
+        let file = tempfile::NamedTempFile::new().expect("failed to create temp file");
+        let body = b"pick aaa Caf\xe9 cr\xe8me\r\npick bbb \xff\xfe\n";
+        std::fs::write(file.path(), body).expect("failed to write todo");
+
+        run(file.path().to_str()).expect("sequence_editor run failed");
+
+        let out = std::fs::read(file.path()).expect("failed to read todo");
+        assert_eq!(out, b"edit aaa Caf\xe9 cr\xe8me\r\nedit bbb \xff\xfe\n");
+    }
+
+    #[test]
+    fn mark_entry_skips_unselected_commits() {
+        let sel = Selection::from_shas(vec![String::from("abc123def")]);
+        assert_eq!(
+            marked("pick abc123 Selected", &sel),
+            "edit abc123 Selected"
+        );
+        assert_eq!(
+            marked("pick 999999 Other", &sel),
+            "pick 999999 Other"
+        );
+    }
+
+    fn edits(drop_authors: &[&str], show_authors: bool) -> TodoEdits {
+        TodoEdits {
+            selection: Selection::All,
+            mark: true,
+            drop_authors: drop_authors.iter().map(|e| e.to_string()).collect(),
+            show_authors,
+            exec: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn edit_entry_appends_the_author() {
+        let old = Identity::new("Old", "old@x.com");
+        let show = edits(&[], true);
+        assert_eq!(
+            edited(b"pick abc123 Fix \xe9t\xe9\r\n", Some(&old), &show),
+            b"edit abc123 Fix \xe9t\xe9 # Author: Old <old@x.com>\r\n"
+        );
+        assert_eq!(
+            edited(b"# pick abc123 Fix bug", None, &show),
+            b"# pick abc123 Fix bug"
+        );
+        assert_eq!(edited(b"exec make", None, &show), b"exec make");
+    }
+
+    #[test]
+    fn edit_entry_drops_commits_by_dropped_authors() {
+        let bot = Identity::new("Bot", "Bot@CI.example");
+        let dev = Identity::new("Dev", "dev@x.com");
+        let purge = edits(&["bot@ci.example"], false);
+        assert_eq!(edited(b"pick abc Bump", Some(&bot), &purge), b"drop abc Bump");
+        assert_eq!(edited(b"p abc Bump", Some(&bot), &purge), b"d abc Bump");
+        assert_eq!(edited(b"pick def Fix", Some(&dev), &purge), b"edit def Fix");
+
+        let manual = TodoEdits {
+            mark: false,
+            ..purge
+        };
+        assert_eq!(edited(b"pick def Fix", Some(&dev), &manual), b"pick def Fix");
+    }
+
+    #[test]
+    fn run_args_reads_selection_file() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let sel_path = dir.path().join("sel");
+        std::fs::write(&sel_path, "aaaa1111\n").expect("failed to write selection");
+        let todo_path = dir.path().join("todo");
+        std::fs::write(&todo_path, "pick aaaa One\npick bbbb Two\n").expect("failed to write todo");
+
+        let args = vec![
+            String::from("--select"),
+            sel_path.to_string_lossy().to_string(),
+            todo_path.to_string_lossy().to_string(),
+        ];
+        run_args(&args).expect("run_args failed");
+
+        let s = std::fs::read_to_string(&todo_path).expect("failed to read todo");
+        assert_eq!(s, "edit aaaa One\npick bbbb Two\n");
+    }
+
+    #[test]
+    fn dry_run_leaves_the_todo_file_alone() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let sel_path = dir.path().join("sel");
+        std::fs::write(&sel_path, "aaaa1111\n").expect("failed to write selection");
+        let todo_path = dir.path().join("todo");
+        std::fs::write(&todo_path, "pick aaaa One\npick bbbb Two\n").expect("failed to write todo");
+
+        let edits = TodoEdits {
+            selection: Selection::from_shas(vec![String::from("aaaa1111")]),
+            mark: true,
+            drop_authors: Vec::new(),
+            show_authors: false,
+            exec: Vec::new(),
+        };
+        let editor = LineEditor::new(&todo_path, &edits).expect("LineEditor::new failed");
+        let mut out = Vec::new();
+        write_edited(&todo_path, &editor, &mut out).expect("write_edited failed");
+        assert_eq!(out, b"edit aaaa One\npick bbbb Two\n");
+
+        let args = vec![
+            String::from("--dry-run"),
+            String::from("--select"),
+            sel_path.to_string_lossy().to_string(),
+            todo_path.to_string_lossy().to_string(),
+        ];
+        run_args(&args).expect("run_args failed");
+        let s = std::fs::read_to_string(&todo_path).expect("failed to read todo");
+        assert_eq!(s, "pick aaaa One\npick bbbb Two\n");
+    }
+
+    #[test]
+    fn run_args_injects_exec_lines_after_marked_commits() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let sel_path = dir.path().join("sel");
+        std::fs::write(&sel_path, "aaaa1111\n").expect("failed to write selection");
+        let todo_path = dir.path().join("todo");
+        std::fs::write(&todo_path, "pick aaaa One\r\npick bbbb Two\r\n# Rebase\r\n")
+            .expect("failed to write todo");
+
+        let args = vec![
+            String::from("--select"),
+            sel_path.to_string_lossy().to_string(),
+            String::from("--exec"),
+            String::from("cargo fmt --check"),
+            String::from("--exec"),
+            String::from("make lint"),
+            todo_path.to_string_lossy().to_string(),
+        ];
+        run_args(&args).expect("run_args failed");
+
+        let s = std::fs::read_to_string(&todo_path).expect("failed to read todo");
+        assert_eq!(
+            s,
+            "edit aaaa One\r\nexec cargo fmt --check\r\nexec make lint\r\npick bbbb Two\r\n# Rebase\r\n"
+        );
     }
 }
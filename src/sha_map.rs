@@ -0,0 +1,195 @@
+use crate::identity::Identity;
+
+use std::{collections::HashMap, fs};
+
+/// Splits one CSV line into its fields.
+///
+/// Fields may be quoted with `"`, with `""` for a quote inside, so names
+/// containing commas (`"Doe, Jane"`) survive.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` with the fields, unquoted.
+/// * `Err(String)` if a quoted field is not closed.
+fn split_csv(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(String::from("unterminated quoted field"));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Reports whether `sha` is a full SHA-1 or SHA-256 object name.
+fn is_full_sha(sha: &str) -> bool {
+    matches!(sha.len(), 40 | 64) && sha.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Parses one row, `sha,new_name,new_email`.
+fn parse_row(line: &str) -> Result<(String, Identity), String> {
+    let fields = match split_csv(line) {
+        Ok(f) => f,
+        Err(e) => return Err(e),
+    };
+    let (sha, name, email) = match fields.as_slice() {
+        [sha, name, email] => (sha.trim(), name.trim(), email.trim()),
+        _ => {
+            return Err(format!(
+                "expected 3 fields (sha,new_name,new_email), got {}",
+                fields.len()
+            ))
+        }
+    };
+    if !is_full_sha(sha) {
+        return Err(format!(
+            "`{}` is not a full commit SHA (40 or 64 hex digits)",
+            sha
+        ));
+    }
+    if name.is_empty() || email.is_empty() {
+        return Err(String::from("the new name and email cannot be empty"));
+    }
+    Ok((sha.to_ascii_lowercase(), Identity::new(name, email)))
+}
+
+/// Parses a `--sha-map` CSV: one `sha,new_name,new_email` row per commit.
+///
+/// SHAs must be written out in full, so a row can never match a commit it
+/// was not meant for. A header row (`sha,name,email` or similar), blank lines
+/// and lines starting with `#` are ignored.
+///
+/// # Returns
+///
+/// * `Ok(HashMap)` from lowercase SHA to new author.
+/// * `Err(String)` naming the first invalid line, or a SHA listed twice
+///   with different identities.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::sha_map::parse;
+///
+/// let map = parse(
+///     "sha,name,email\n\
+///      1f0c3d6e6f0d3b1b7b2c6bd1c9a4f2e8d7c6b5a4,\"Doe, Jane\",jane@corp.com\n",
+/// )
+/// .unwrap();
+/// assert_eq!(map.len(), 1);
+/// ```
+pub fn parse(text: &str) -> Result<HashMap<String, Identity>, String> {
+    let mut map = HashMap::new();
+    let mut first = true;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Exports usually start with a header naming the columns.
+        let starts_with_hex = line.split(',').next().is_some_and(|f| {
+            !f.trim().is_empty() && f.trim().bytes().all(|b| b.is_ascii_hexdigit())
+        });
+        if std::mem::take(&mut first) && !starts_with_hex {
+            continue;
+        }
+        let (sha, identity) = match parse_row(line) {
+            Ok(row) => row,
+            Err(e) => return Err(format!("sha-map line {}: {}", i + 1, e)),
+        };
+        match map.get(&sha) {
+            Some(existing) if existing != &identity => {
+                return Err(format!(
+                    "sha-map line {}: {} is already mapped to {}",
+                    i + 1,
+                    sha,
+                    existing
+                ))
+            }
+            _ => {
+                map.insert(sha, identity);
+            }
+        }
+    }
+    if map.is_empty() {
+        return Err(String::from("the sha-map file lists no commits"));
+    }
+    Ok(map)
+}
+
+/// Reads and parses the `--sha-map` CSV at `path`.
+///
+/// # Returns
+///
+/// * `Ok(HashMap)` on success.
+/// * `Err(String)` if the file could not be read or parsed.
+pub fn read(path: &str) -> Result<HashMap<String, Identity>, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) => Err(format!("cannot read sha-map {}: {}", path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, split_csv};
+    use crate::identity::Identity;
+
+    const A: &str = "1f0c3d6e6f0d3b1b7b2c6bd1c9a4f2e8d7c6b5a4";
+    const B: &str = "2e1d4c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d";
+
+    #[test]
+    fn split_csv_handles_quotes() {
+        assert_eq!(
+            split_csv("a, \"Doe, Jane\",\"say \"\"hi\"\"\"").unwrap(),
+            vec!["a", "Doe, Jane", "say \"hi\""]
+        );
+        assert!(split_csv("a,\"open").is_err());
+    }
+
+    #[test]
+    fn rows_map_shas_to_identities() {
+        let text = format!(
+            "commit,new_name,new_email\n# audit 2024-05\n{},Jane Doe,jane@corp.com\n\n{},\"Bo, Chen\",bo@corp.com\n",
+            A.to_uppercase(),
+            B
+        );
+        let map = parse(&text).expect("parse failed");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[A], Identity::new("Jane Doe", "jane@corp.com"));
+        assert_eq!(map[B], Identity::new("Bo, Chen", "bo@corp.com"));
+    }
+
+    #[test]
+    fn parse_reports_the_offending_line() {
+        let err = parse(&format!("{},Jane,j@x\n1f0c3d6,Bo,b@x\n", A)).unwrap_err();
+        assert!(
+            err.starts_with("sha-map line 2: `1f0c3d6` is not a full commit SHA"),
+            "{}",
+            err
+        );
+        let err = parse(&format!("{},Jane,j@x\n{},Bo,b@x\n", A, A)).unwrap_err();
+        assert!(err.contains("already mapped to Jane <j@x>"), "{}", err);
+        assert!(parse(&format!("{},Jane\n", A)).is_err());
+        assert!(parse("sha,name,email\n").is_err());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::git::Repo;
+
+use std::fmt;
+
+/// What to do about commit signing during a rebase rewrite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningPlan {
+    /// `commit.gpgsign` is off; commits are made as usual.
+    Unsigned,
+    /// Sign every rewritten commit, unlocking the key once up front.
+    Sign,
+    /// Turn `commit.gpgsign` off for this run only.
+    Off,
+    /// `commit.gpgsign` is on; ask the user whether to keep signing.
+    Ask,
+}
+
+impl SigningPlan {
+    /// The `commit.gpgsign` override to run the rebase with.
+    ///
+    /// [`SigningPlan::Ask`] must be resolved first; it keeps the configuration.
+    pub fn sign_override(self) -> Option<bool> {
+        match self {
+            SigningPlan::Sign => Some(true),
+            SigningPlan::Off => Some(false),
+            SigningPlan::Unsigned | SigningPlan::Ask => None,
+        }
+    }
+}
+
+/// Decides how to handle commit signing.
+///
+/// # Parameters
+///
+/// * `configured` – Whether `commit.gpgsign` is on.
+/// * `gpg_sign` – `--gpg-sign` was given.
+/// * `no_gpg_sign` – `--no-gpg-sign` was given.
+/// * `assume_yes` – `--yes` was given, so the user cannot be asked.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::signing::{plan, SigningPlan};
+///
+/// assert_eq!(plan(true, false, true, false), SigningPlan::Off);
+/// assert_eq!(plan(false, false, false, false), SigningPlan::Unsigned);
+/// ```
+pub fn plan(configured: bool, gpg_sign: bool, no_gpg_sign: bool, assume_yes: bool) -> SigningPlan {
+    if no_gpg_sign {
+        SigningPlan::Off
+    } else if gpg_sign {
+        SigningPlan::Sign
+    } else if !configured {
+        SigningPlan::Unsigned
+    } else if assume_yes {
+        // Respect the configuration when nobody can be asked.
+        SigningPlan::Sign
+    } else {
+        SigningPlan::Ask
+    }
+}
+
+/// Reads `commit.gpgsign`.
+///
+/// # Returns
+///
+/// * `Ok(bool)` – Whether commits are signed by default.
+/// * `Err(String)` if the value is not a boolean.
+pub fn configured(repo: &Repo) -> Result<bool, String> {
+    match repo.config_get_bool("commit.gpgsign") {
+        Ok(v) => Ok(v.unwrap_or(false)),
+        Err(e) => Err(format!("invalid commit.gpgsign: {}", e)),
+    }
+}
+
+/// Signs a throwaway payload with the commit signing key, so that
+/// `gpg-agent` asks for the passphrase once instead of on every commit.
+///
+/// The key is `user.signingkey`, or the configured committer as Git would
+/// use it. Nothing is done for `ssh` and `x509` signing, whose agents
+/// handle caching themselves.
+///
+/// # Returns
+///
+/// * `Ok(())` once the key has been used successfully.
+/// * `Err(String)` if signing failed (wrong passphrase, missing key, ...).
+///
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::{git::Repo, signing};
+///
+/// let repo = Repo::discover().unwrap();
+/// if let Err(e) = signing::unlock_key(&repo) {
+///     eprintln!("cannot sign: {}", e);
+/// }
+/// ```
+pub fn unlock_key(repo: &Repo) -> Result<(), String> {
+    let format = repo.config_get("gpg.format").unwrap_or_default();
+    if !format.is_empty() && format != "openpgp" {
+        return Ok(());
+    }
+    let configured = match repo.signing_key() {
+        Some(k) => Ok(k.to_string()),
+        None => repo.config_get("user.signingkey"),
+    };
+    let key = match configured {
+        Ok(k) if !k.is_empty() => k,
+        _ => format!(
+            "{} <{}>",
+            repo.config_get("user.name").unwrap_or_default(),
+            repo.config_get("user.email").unwrap_or_default()
+        ),
+    };
+    match repo.sign_buffer(b"git-author-rewrite\n".to_vec(), &key) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("cannot sign with {}: {}", key, e)),
+    }
+}
+
+/// An OpenPGP secret key that can sign, as listed by GnuPG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretKey {
+    /// The long key ID, e.g. `3AA5C34371567BD2`.
+    pub id: String,
+    /// The full fingerprint, if GnuPG listed it.
+    pub fingerprint: String,
+    /// The user IDs that are not revoked, e.g. `Jane Doe <jane@corp.com>`.
+    pub uids: Vec<String>,
+}
+
+impl SecretKey {
+    /// Reports whether one of the key's user IDs carries `email` (compared
+    /// case-insensitively), as `Name <email>` or on its own.
+    pub fn has_email(&self, email: &str) -> bool {
+        self.uids.iter().any(|uid| {
+            let address = match (uid.rfind('<'), uid.ends_with('>')) {
+                (Some(start), true) => &uid[start + 1..uid.len() - 1],
+                _ => uid.as_str(),
+            };
+            address.trim().eq_ignore_ascii_case(email.trim())
+        })
+    }
+
+    /// Reports whether `key`, a value of `user.signingkey`, names this key:
+    /// its key ID, a short ID or fingerprint ending it (with or without
+    /// `0x` and a trailing `!`), or text found in one of its user IDs.
+    pub fn matches(&self, key: &str) -> bool {
+        let hex = key.trim().trim_start_matches("0x").trim_end_matches('!');
+        let hex = hex.to_ascii_uppercase();
+        if hex.len() >= 8 && (self.id.ends_with(&hex) || self.fingerprint.ends_with(&hex)) {
+            return true;
+        }
+        !key.trim().is_empty() && self.uids.iter().any(|uid| uid.contains(key.trim()))
+    }
+}
+
+impl fmt::Display for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}  {}", self.id, self.uids.join(", "))
+    }
+}
+
+/// Undoes GnuPG's `\xNN` escapes in a `--with-colons` field.
+fn unescape_colons(field: &str) -> String {
+    let mut out = Vec::with_capacity(field.len());
+    let bytes = field.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes[i] == b'\\'
+            && bytes.get(i + 1) == Some(&b'x')
+            && i + 4 <= bytes.len()
+            && field.is_char_boundary(i + 4);
+        let byte = if escaped {
+            u8::from_str_radix(&field[i + 2..i + 4], 16).ok()
+        } else {
+            None
+        };
+        match byte {
+            Some(b) => {
+                out.push(b);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Parses `gpg --list-secret-keys --with-colons` into the keys that can
+/// sign, leaving out revoked, expired, invalid and disabled keys and
+/// revoked user IDs.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::signing::parse_secret_keys;
+///
+/// let listing = "sec:u:255:22:3AA5C34371567BD2:1700000000:::u:::scESC:::+:::ed25519::0:\n\
+///                uid:u::::1700000000::HASH::Jane Doe <jane@corp.com>::::::::::0:\n";
+/// let keys = parse_secret_keys(listing);
+/// assert_eq!(keys[0].id, "3AA5C34371567BD2");
+/// assert!(keys[0].has_email("jane@corp.com"));
+/// ```
+pub fn parse_secret_keys(listing: &str) -> Vec<SecretKey> {
+    let mut keys = Vec::new();
+    // The key being read, and whether it can sign.
+    let mut current: Option<(SecretKey, bool)> = None;
+    for line in listing.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("");
+        match field(0) {
+            "sec" => {
+                if let Some((key, true)) = current.take() {
+                    keys.push(key);
+                }
+                let usable = !matches!(field(1), "r" | "e" | "i" | "d")
+                    && (field(11).is_empty() || field(11).contains('S'))
+                    && !field(11).contains('D');
+                current = Some((
+                    SecretKey {
+                        id: field(4).to_ascii_uppercase(),
+                        fingerprint: String::new(),
+                        uids: Vec::new(),
+                    },
+                    usable,
+                ));
+            }
+            "fpr" => {
+                if let Some((key, _)) = current.as_mut() {
+                    if key.fingerprint.is_empty() {
+                        key.fingerprint = field(9).to_ascii_uppercase();
+                    }
+                }
+            }
+            "uid" if field(1) != "r" => {
+                if let Some((key, _)) = current.as_mut() {
+                    key.uids.push(unescape_colons(field(9)));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((key, true)) = current {
+        keys.push(key);
+    }
+    keys
+}
+
+/// Lists the OpenPGP secret keys that can sign commits.
+///
+/// # Returns
+///
+/// * `Ok(Vec<SecretKey>)` with the keys, possibly none.
+/// * `Err(String)` if GnuPG could not be run.
+///
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::{git::Repo, signing};
+///
+/// let repo = Repo::discover().unwrap();
+/// for key in signing::secret_keys(&repo).unwrap() {
+///     println!("{}", key);
+/// }
+/// ```
+pub fn secret_keys(repo: &Repo) -> Result<Vec<SecretKey>, String> {
+    match repo.list_secret_keys() {
+        Ok(listing) => Ok(parse_secret_keys(&listing)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Picks the key to offer first: the one `user.signingkey` names, else the
+/// first with a user ID for `email`, else the first key.
+///
+/// # Parameters
+///
+/// * `keys` – The keys to choose from.
+/// * `configured` – The value of `user.signingkey`, if set.
+/// * `email` – The new author email.
+pub fn preferred_key(keys: &[SecretKey], configured: Option<&str>, email: Option<&str>) -> usize {
+    let named = configured.and_then(|c| keys.iter().position(|k| k.matches(c)));
+    let for_email = email.and_then(|e| keys.iter().position(|k| k.has_email(e)));
+    named.or(for_email).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_secret_keys, plan, preferred_key, SigningPlan};
+
+    #[test]
+    fn flags_win_over_the_configuration() {
+        assert_eq!(plan(true, false, true, false), SigningPlan::Off);
+        assert_eq!(plan(false, true, false, false), SigningPlan::Sign);
+        assert_eq!(plan(false, true, false, false).sign_override(), Some(true));
+    }
+
+    #[test]
+    fn configured_signing_is_asked_about_unless_assume_yes() {
+        assert_eq!(plan(true, false, false, false), SigningPlan::Ask);
+        assert_eq!(plan(true, false, false, true), SigningPlan::Sign);
+        assert_eq!(plan(false, false, false, true), SigningPlan::Unsigned);
+        assert_eq!(plan(false, false, false, true).sign_override(), None);
+    }
+    const LISTING: &str = "\
+sec:u:255:22:1111AAAA2222BBBB:1700000000:::u:::scESC:::+:::ed25519::0:
+fpr:::::::::0000111122223333444455551111AAAA2222BBBB:
+uid:u::::1700000000::H1::Jane Doe <jane@home.org>::::::::::0:
+uid:r::::1700000000::H2::Jane Doe <jane@old.com>::::::::::0:
+ssb:u:255:18:9999:1700000000::::::e:::+:::cv25519::
+sec:e:255:22:3333CCCC4444DDDD:1500000000:1600000000::u:::scESC:::+:::ed25519::0:
+uid:e::::1500000000::H3::Expired <old@x.com>::::::::::0:
+sec:u:255:22:5555EEEE6666FFFF:1700000000:::u:::scESC:::+:::ed25519::0:
+fpr:::::::::00001111222233334444555555EEEE6666FFFF:
+uid:u::::1700000000::H4::Jane \\x3a Work <JANE@corp.com>::::::::::0:
+sec:u:255:22:7777AAAA8888BBBB:1700000000:::u:::eE:::+:::cv25519::0:
+uid:u::::1700000000::H5::Encrypt only <enc@x.com>::::::::::0:
+";
+
+    #[test]
+    fn parse_secret_keys_keeps_usable_signing_keys() {
+        let keys = parse_secret_keys(LISTING);
+        let ids: Vec<&str> = keys.iter().map(|k| k.id.as_str()).collect();
+        assert_eq!(ids, vec!["1111AAAA2222BBBB", "5555EEEE6666FFFF"]);
+        assert_eq!(keys[0].uids, vec!["Jane Doe <jane@home.org>"]);
+        assert_eq!(keys[1].uids, vec!["Jane : Work <JANE@corp.com>"]);
+        assert!(keys[1].has_email("jane@corp.com"));
+        assert!(!keys[0].has_email("jane@old.com"));
+        assert!(parse_secret_keys("").is_empty());
+    }
+
+    #[test]
+    fn preferred_key_follows_the_configuration_then_the_email() {
+        let keys = parse_secret_keys(LISTING);
+        assert_eq!(
+            preferred_key(&keys, Some("0x1111AAAA2222BBBB!"), Some("jane@corp.com")),
+            0
+        );
+        assert_eq!(preferred_key(&keys, Some("55EEEE6666FFFF"), None), 1);
+        assert_eq!(preferred_key(&keys, None, Some("Jane@Corp.com")), 1);
+        assert_eq!(preferred_key(&keys, Some("nobody"), Some("x@y.z")), 0);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{args::Options, engine::EngineKind};
+
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+/// A complete job description, read with `--spec <file|->`.
+///
+/// Every section and field is optional. Values fill in options that were not
+/// given on the command line, so flags can still override a shared spec.
+/// This one pins the rebase engine, so the job runs the same way whether or
+/// not the repository has commit hooks:
+///
+/// ```json
+/// {
+///   "identity": { "name": "Jane Doe", "email": "jane@corp.com" },
+///   "filters": { "revs": "main..HEAD" },
+///   "engine": "rebase",
+///   "options": { "yes": true, "force_pushed_history": false }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spec {
+    /// How commits map onto new identities.
+    pub identity: IdentitySpec,
+    /// Which commits to rewrite.
+    pub filters: FilterSpec,
+    /// The rewrite engine, as for `--engine`: `"metadata"` (the default),
+    /// `"rebase"`, `"replace"` (the same as `--replace-mode`) or `"gix"`.
+    pub engine: Option<String>,
+    /// Behavior switches.
+    pub options: OptionSpec,
+}
+
+/// The `identity` section of a [`Spec`].
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdentitySpec {
+    /// Same as `--name`.
+    pub name: Option<String>,
+    /// Same as `--email`.
+    pub email: Option<String>,
+    /// Same as `--profile`.
+    pub profile: Option<String>,
+    /// Same as `--rewrite-domain`, e.g. `"old.com=new.com"`.
+    pub rewrite_domain: Option<String>,
+    /// Same as `--rules`: a path to a mapping rules file.
+    pub rules: Option<String>,
+    /// Same as `--sha-map`: a path to a `sha,new_name,new_email` CSV file.
+    pub sha_map: Option<String>,
+    /// Same as `--name-only`.
+    pub name_only: bool,
+    /// Same as `--email-only`.
+    pub email_only: bool,
+    /// Same as `--sync-committer`.
+    pub sync_committer: bool,
+}
+
+/// The `filters` section of a [`Spec`].
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterSpec {
+    /// Same as `--revs`.
+    pub revs: Option<String>,
+    /// Same as `--commits-from`.
+    pub commits_from: Option<String>,
+    /// Same as `--exclude`: commits to leave as they are.
+    pub exclude: Vec<String>,
+    /// Same as `--exclude-file`.
+    pub exclude_file: Option<String>,
+    /// Same as `--skip-merges`.
+    pub skip_merges: bool,
+    /// Same as `--all-branches`.
+    pub all_branches: bool,
+}
+
+/// The `options` section of a [`Spec`].
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptionSpec {
+    /// Same as `--yes`.
+    pub yes: bool,
+    /// Same as `--global`.
+    pub global: bool,
+    /// Same as `--no-config-update`.
+    pub no_config_update: bool,
+    /// Same as `--include-if`: the directory the identity is saved for.
+    pub include_if: Option<String>,
+    /// Same as `--manual`.
+    pub manual: bool,
+    /// Same as `--editor`.
+    pub editor: Option<String>,
+    /// Same as `--show-authors`.
+    pub show_authors: bool,
+    /// Same as `--ask-each`.
+    pub ask_each: bool,
+    /// Same as `--pick-branch`.
+    pub pick_branch: bool,
+    /// Same as `--tui`.
+    pub tui: bool,
+    /// Same as `--show-commands`.
+    pub show_commands: bool,
+    /// Same as `--quiet`.
+    pub quiet: bool,
+    /// Same as `--force-pushed-history`.
+    pub force_pushed_history: bool,
+    /// Same as `--allow-protected`.
+    pub allow_protected: bool,
+    /// Same as `--force`.
+    pub force: bool,
+    /// Same as `--fsck`.
+    pub fsck: bool,
+    /// Same as `--check-github`.
+    pub check_github: bool,
+    /// Same as `--check-dco`.
+    pub check_dco: bool,
+    /// Same as `--cleanup`.
+    pub cleanup: bool,
+    /// Same as `--backup-refs`.
+    pub backup_refs: bool,
+    /// Same as `--backup-bundle`: where to save every ref before rewriting.
+    pub backup_bundle: Option<String>,
+    /// Same as `--audit-log`: where to append the audit record of the run.
+    pub audit_log: Option<String>,
+    /// Same as `--report-html`: where to write the HTML report of the rewrite.
+    pub report_html: Option<String>,
+    /// Same as `--rewrite-tags`.
+    pub rewrite_tags: bool,
+    /// Same as `--sign-tags`.
+    pub sign_tags: bool,
+    /// Same as `--message-replace`, one entry per substitution.
+    pub message_replace: Vec<String>,
+    /// Same as `--drop-author`, one entry per email.
+    pub drop_author: Vec<String>,
+    /// Same as `--exec`, one entry per command.
+    pub exec: Vec<String>,
+    /// Same as `--skip-on-conflict`.
+    pub skip_on_conflict: bool,
+    /// Same as `--chunk-size`.
+    pub chunk_size: Option<usize>,
+    /// Same as `--timeout`, in seconds.
+    pub timeout: Option<u64>,
+    /// Same as `--annotate`.
+    pub annotate: bool,
+    /// Same as `--record-original-sha`.
+    pub record_original_sha: bool,
+    /// Same as `--no-verify`.
+    pub no_verify: bool,
+    /// Same as `--verify`.
+    pub verify: bool,
+    /// Same as `--gpg-sign`.
+    pub gpg_sign: bool,
+    /// Same as `--no-gpg-sign`.
+    pub no_gpg_sign: bool,
+}
+
+/// Parses a spec from JSON text.
+///
+/// # Returns
+///
+/// * `Ok(Spec)` on success.
+/// * `Err(String)` describing the syntax error or unknown field.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::spec::parse;
+///
+/// let spec = parse(r#"{"identity": {"email": "jane@corp.com"}}"#).unwrap();
+/// assert_eq!(spec.identity.email.as_deref(), Some("jane@corp.com"));
+/// ```
+pub fn parse(body: &str) -> Result<Spec, String> {
+    match serde_json::from_str(body) {
+        Ok(spec) => Ok(spec),
+        Err(e) => Err(format!("invalid spec: {}", e)),
+    }
+}
+
+/// Reads and parses a spec from `source`, which is a file path or `-` for stdin.
+///
+/// # Returns
+///
+/// * `Ok(Spec)` on success.
+/// * `Err(String)` if the source could not be read or parsed.
+pub fn read(source: &str) -> Result<Spec, String> {
+    let body = if source == "-" {
+        let mut body = String::new();
+        match io::stdin().read_to_string(&mut body) {
+            Ok(_) => body,
+            Err(e) => return Err(format!("cannot read spec from stdin: {}", e)),
+        }
+    } else {
+        match fs::read_to_string(source) {
+            Ok(b) => b,
+            Err(e) => return Err(format!("cannot read spec {}: {}", source, e)),
+        }
+    };
+
+    parse(&body)
+}
+
+/// Fills in `opts` from `spec`, keeping every value set on the command line.
+///
+/// # Returns
+///
+/// * `Ok(())` on success.
+/// * `Err(String)` if the spec asks for an engine this build does not have.
+pub fn apply(spec: Spec, opts: &mut Options) -> Result<(), String> {
+    if let Some(name) = spec.engine.as_deref() {
+        if let Err(e) = EngineKind::parse(name) {
+            return Err(format!("{} in spec", e));
+        }
+    }
+    if opts.engine.is_none() && !opts.replace_mode {
+        opts.engine = spec.engine;
+    }
+
+    let Spec {
+        identity,
+        filters,
+        options,
+        ..
+    } = spec;
+
+    for (slot, value) in [
+        (&mut opts.name, identity.name),
+        (&mut opts.email, identity.email),
+        (&mut opts.profile, identity.profile),
+        (&mut opts.rewrite_domain, identity.rewrite_domain),
+        (&mut opts.rules, identity.rules),
+        (&mut opts.sha_map, identity.sha_map),
+        (&mut opts.revs, filters.revs),
+        (&mut opts.commits_from, filters.commits_from),
+        (&mut opts.exclude_file, filters.exclude_file),
+        (&mut opts.editor, options.editor),
+        (&mut opts.backup_bundle, options.backup_bundle),
+        (&mut opts.audit_log, options.audit_log),
+        (&mut opts.report_html, options.report_html),
+        (&mut opts.include_if, options.include_if),
+    ] {
+        if slot.is_none() {
+            *slot = value;
+        }
+    }
+
+    opts.name_only |= identity.name_only;
+    opts.email_only |= identity.email_only;
+    opts.sync_committer |= identity.sync_committer;
+    opts.skip_merges |= filters.skip_merges;
+    opts.all_branches |= filters.all_branches;
+    opts.yes |= options.yes;
+    opts.global |= options.global;
+    opts.no_config_update |= options.no_config_update;
+    opts.manual |= options.manual;
+    opts.show_authors |= options.show_authors;
+    opts.ask_each |= options.ask_each;
+    opts.pick_branch |= options.pick_branch;
+    opts.tui |= options.tui;
+    opts.show_commands |= options.show_commands;
+    opts.quiet |= options.quiet;
+    opts.force_pushed_history |= options.force_pushed_history;
+    opts.allow_protected |= options.allow_protected;
+    opts.force |= options.force;
+    opts.fsck |= options.fsck;
+    opts.check_github |= options.check_github;
+    opts.check_dco |= options.check_dco;
+    opts.skip_on_conflict |= options.skip_on_conflict;
+    opts.cleanup |= options.cleanup;
+    opts.backup_refs |= options.backup_refs;
+    opts.rewrite_tags |= options.rewrite_tags;
+    opts.sign_tags |= options.sign_tags;
+    opts.annotate |= options.annotate;
+    opts.record_original_sha |= options.record_original_sha;
+    opts.no_verify |= options.no_verify;
+    opts.verify |= options.verify;
+    opts.gpg_sign |= options.gpg_sign;
+    opts.no_gpg_sign |= options.no_gpg_sign;
+    if opts.message_replace.is_empty() {
+        opts.message_replace = options.message_replace;
+    }
+    if opts.drop_author.is_empty() {
+        opts.drop_author = options.drop_author;
+    }
+    if opts.exclude.is_empty() {
+        opts.exclude = filters.exclude;
+    }
+    if opts.exec.is_empty() {
+        opts.exec = options.exec;
+    }
+    if opts.chunk_size.is_none() {
+        opts.chunk_size = options.chunk_size;
+    }
+    if opts.timeout.is_none() {
+        opts.timeout = options.timeout;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, parse};
+    use crate::args::Options;
+
+    #[test]
+    fn empty_object_is_default() {
+        let spec = parse("{}").expect("parse failed");
+        assert_eq!(spec, Default::default());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = parse(r#"{"identity": {"nmae": "x"}}"#).unwrap_err();
+        assert!(err.contains("nmae"), "{}", err);
+    }
+
+    #[test]
+    fn apply_keeps_command_line_values() {
+        let spec = parse(
+            r#"{
+                "identity": {"name": "Spec Name", "email": "spec@x.com"},
+                "filters": {"revs": "main..HEAD"},
+                "options": {"yes": true, "no_verify": true, "message_replace": ["s/a/b/"]}
+            }"#,
+        )
+        .expect("parse failed");
+        let mut opts = Options {
+            name: Some(String::from("Flag Name")),
+            ..Options::default()
+        };
+        apply(spec, &mut opts).expect("apply failed");
+        assert_eq!(opts.name.as_deref(), Some("Flag Name"));
+        assert_eq!(opts.email.as_deref(), Some("spec@x.com"));
+        assert_eq!(opts.revs.as_deref(), Some("main..HEAD"));
+        assert!(opts.yes);
+        assert!(opts.no_verify);
+        assert_eq!(opts.message_replace, vec!["s/a/b/"]);
+    }
+
+    #[test]
+    fn apply_rejects_unknown_engine() {
+        let spec = parse(r#"{"engine": "filter-branch"}"#).expect("parse failed");
+        let err = apply(spec, &mut Options::default()).unwrap_err();
+        assert!(err.contains("filter-branch"));
+    }
+}
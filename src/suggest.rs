@@ -0,0 +1,310 @@
+use crate::{
+    git::{self, Commit, Repo},
+    identity::Identity,
+    profile,
+};
+
+use std::{collections::HashMap, fmt};
+
+/// How many commits of history are read for suggestions, so a huge
+/// repository does not delay the first prompt.
+const HISTORY_LIMIT: &str = "5000";
+
+/// How many of the most frequent historical identities the picker lists.
+const MENU_HISTORY: usize = 5;
+
+/// Where a [`Candidate`] identity was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// `user.name` / `user.email` in the repository's own config.
+    LocalConfig,
+    /// `user.name` / `user.email` in the global config.
+    GlobalConfig,
+    /// Recent history; the number of commits carrying the identity as
+    /// author or committer.
+    History(usize),
+    /// A saved profile, by name.
+    Profile(String),
+}
+
+/// An identity offered for the rewrite, with where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub identity: Identity,
+    pub source: Source,
+}
+
+impl fmt::Display for Candidate {
+    /// Formats the candidate as a menu label: `Name <email> (local config)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Source::LocalConfig => write!(f, "{} (local config)", self.identity),
+            Source::GlobalConfig => write!(f, "{} (global config)", self.identity),
+            Source::History(1) => write!(f, "{} (1 commit)", self.identity),
+            Source::History(n) => write!(f, "{} ({} commits)", self.identity, n),
+            Source::Profile(name) => write!(f, "{} (profile {})", self.identity, name),
+        }
+    }
+}
+
+/// Collects the identities already known for `repo`, most useful first: the
+/// configured `user.name` / `user.email` (local, then global), the authors
+/// and committers of recent history (most frequent first), then the saved
+/// profiles. An identity found in several places is listed once, under the
+/// first of them.
+///
+/// Sources that cannot be read (an unborn branch, a missing config) are
+/// skipped; the result may be empty.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, suggest};
+///
+/// let repo = Repo::discover().unwrap();
+/// for candidate in suggest::candidates(&repo) {
+///     println!("{}", candidate);
+/// }
+/// ```
+pub fn candidates(repo: &Repo) -> Vec<Candidate> {
+    let local = Identity::new(
+        &repo.config_get_local("user.name").unwrap_or_default(),
+        &repo.config_get_local("user.email").unwrap_or_default(),
+    );
+    let global = Identity::new(
+        &git::config_get_global("user.name").unwrap_or_default(),
+        &git::config_get_global("user.email").unwrap_or_default(),
+    );
+    let history: Vec<Commit> = match repo.commits(&["-n", HISTORY_LIMIT, "HEAD"]) {
+        Ok(commits) => commits.collect(),
+        Err(_) => Vec::new(),
+    };
+    let profiles = profile::list().unwrap_or_default();
+
+    let mut all = vec![
+        Candidate {
+            identity: local,
+            source: Source::LocalConfig,
+        },
+        Candidate {
+            identity: global,
+            source: Source::GlobalConfig,
+        },
+    ];
+    all.extend(history_candidates(history));
+    all.extend(profiles.into_iter().map(|p| Candidate {
+        identity: p.identity,
+        source: Source::Profile(p.name),
+    }));
+    distinct_candidates(all)
+}
+
+/// Lists the identities of `history`, most frequent first, ties broken by
+/// first appearance. A commit counts once for an identity that is both its
+/// author and its committer.
+pub(crate) fn history_candidates<I: IntoIterator<Item = Commit>>(history: I) -> Vec<Candidate> {
+    let mut counts: Vec<(Identity, usize)> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+    for commit in history {
+        let mut ids = vec![commit.author];
+        if commit.committer != ids[0] {
+            ids.push(commit.committer);
+        }
+        for id in ids {
+            let key = (id.name.clone(), id.email.clone());
+            match index.get(&key) {
+                Some(&i) => {
+                    counts[i].1 += 1;
+                }
+                None => {
+                    index.insert(key, counts.len());
+                    counts.push((id, 1));
+                }
+            }
+        }
+    }
+    // A stable sort keeps first appearance among equal counts.
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    counts
+        .into_iter()
+        .map(|(identity, n)| Candidate {
+            identity,
+            source: Source::History(n),
+        })
+        .collect()
+}
+
+/// Drops fully empty identities and every repeat of an identity.
+fn distinct_candidates(all: Vec<Candidate>) -> Vec<Candidate> {
+    let mut out: Vec<Candidate> = Vec::new();
+    for c in all {
+        let empty = c.identity.name.is_empty() && c.identity.email.is_empty();
+        if empty || out.iter().any(|o| o.identity == c.identity) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Picks the candidates the identity picker lists: every complete identity
+/// from the config and the profiles, and the most frequent ones from history.
+pub fn menu(candidates: &[Candidate]) -> Vec<Candidate> {
+    let mut from_history = 0;
+    candidates
+        .iter()
+        .filter(|c| !c.identity.name.is_empty() && !c.identity.email.is_empty())
+        .filter(|c| match c.source {
+            Source::History(_) => {
+                from_history += 1;
+                from_history <= MENU_HISTORY
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// The distinct names of `candidates`, in order.
+pub fn names(candidates: &[Candidate]) -> Vec<String> {
+    distinct(candidates.iter().map(|c| &c.identity.name))
+}
+
+/// The distinct emails of `candidates`, those used together with `name` first.
+pub fn emails(candidates: &[Candidate], name: &str) -> Vec<String> {
+    let with_name = candidates.iter().filter(|c| c.identity.name == name);
+    let others = candidates.iter().filter(|c| c.identity.name != name);
+    distinct(with_name.chain(others).map(|c| &c.identity.email))
+}
+
+fn distinct<'a, I: Iterator<Item = &'a String>>(values: I) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for v in values {
+        if !v.is_empty() && !out.contains(v) {
+            out.push(v.clone());
+        }
+    }
+    out
+}
+
+/// Completes `input` to the first of `candidates` it is a prefix of,
+/// ignoring case.
+///
+/// # Returns
+///
+/// * `Some(String)` with the completion; an empty input completes to the
+///   first candidate.
+/// * `None` if no candidate extends `input`.
+pub fn complete(candidates: &[String], input: &str) -> Option<String> {
+    let needle = input.to_lowercase();
+    candidates
+        .iter()
+        .find(|c| c.len() > input.len() && c.to_lowercase().starts_with(&needle))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        complete, distinct_candidates, emails, history_candidates, menu, names, Candidate, Source,
+    };
+    use crate::git::Commit;
+    use crate::identity::Identity;
+
+    fn commit(author: &Identity, committer: &Identity) -> Commit {
+        Commit {
+            sha: String::from("0000000"),
+            author: author.clone(),
+            committer: committer.clone(),
+            author_time: 0,
+            commit_time: 0,
+            subject: String::new(),
+        }
+    }
+
+    fn candidate(name: &str, email: &str, source: Source) -> Candidate {
+        Candidate {
+            identity: Identity::new(name, email),
+            source,
+        }
+    }
+
+    #[test]
+    fn history_candidates_are_ordered_by_frequency() {
+        let jane = Identity::new("Jane", "jane@corp.com");
+        let bot = Identity::new("Bot", "bot@ci");
+        let old = Identity::new("Jane", "jane@old.com");
+        let history = vec![commit(&jane, &bot), commit(&old, &bot), commit(&old, &old)];
+        let labels: Vec<String> = history_candidates(history)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Bot <bot@ci> (2 commits)",
+                "Jane <jane@old.com> (2 commits)",
+                "Jane <jane@corp.com> (1 commit)",
+            ]
+        );
+    }
+
+    #[test]
+    fn distinct_candidates_keep_the_first_source() {
+        let all = vec![
+            candidate("Jane", "jane@corp.com", Source::LocalConfig),
+            candidate("", "", Source::GlobalConfig),
+            candidate("Jane", "jane@corp.com", Source::History(3)),
+            candidate(
+                "Jane",
+                "jane@corp.com",
+                Source::Profile(String::from("work")),
+            ),
+        ];
+        assert_eq!(
+            distinct_candidates(all),
+            vec![candidate("Jane", "jane@corp.com", Source::LocalConfig)]
+        );
+    }
+
+    #[test]
+    fn menu_lists_complete_identities_and_caps_history() {
+        let mut all = vec![
+            candidate("Jane", "", Source::LocalConfig),
+            candidate("Work", "w@corp.com", Source::Profile(String::from("work"))),
+        ];
+        for i in 0..7 {
+            all.push(candidate(
+                "Dev",
+                &format!("dev{}@x", i),
+                Source::History(7 - i),
+            ));
+        }
+        let listed = menu(&all);
+        assert_eq!(listed.len(), 6);
+        assert_eq!(listed[0].source, Source::Profile(String::from("work")));
+        assert_eq!(listed[5].identity.email, "dev4@x");
+    }
+
+    #[test]
+    fn emails_of_the_chosen_name_come_first() {
+        let all = vec![
+            candidate("Bot", "bot@ci", Source::History(2)),
+            candidate("Jane", "jane@corp.com", Source::History(1)),
+            candidate("Jane", "", Source::GlobalConfig),
+        ];
+        assert_eq!(names(&all), vec!["Bot", "Jane"]);
+        assert_eq!(emails(&all, "Jane"), vec!["jane@corp.com", "bot@ci"]);
+    }
+
+    #[test]
+    fn complete_matches_prefixes_case_insensitively() {
+        let candidates = vec![String::from("jane@corp.com"), String::from("Jim@corp.com")];
+        assert_eq!(complete(&candidates, "ji").as_deref(), Some("Jim@corp.com"));
+        assert_eq!(complete(&candidates, "").as_deref(), Some("jane@corp.com"));
+        assert_eq!(complete(&candidates, "jane@corp.com"), None);
+        assert_eq!(complete(&candidates, "x"), None);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    commit_object::CommitObject,
+    git::Repo,
+    identity::{Identity, Mapping},
+    selection::Selection,
+};
+
+use std::collections::HashSet;
+
+/// An annotated tag and the commit it ultimately points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedTag {
+    /// The full ref name, e.g. `refs/tags/v1.0`.
+    pub refname: String,
+    /// The SHA of the tag object.
+    pub object: String,
+    /// The SHA of the object the tag peels to.
+    pub target: String,
+}
+
+/// A tag whose tagger was rewritten.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewrittenTag {
+    /// The full ref name, e.g. `refs/tags/v1.0`.
+    pub refname: String,
+    /// The SHA of the original tag object.
+    pub original: String,
+    /// The SHA of the corrected tag object.
+    pub rewritten: String,
+}
+
+/// Parses `git for-each-ref` output in `%(refname) %(objecttype) %(objectname) %(*objectname)`
+/// format, keeping only annotated tags.
+pub(crate) fn parse_tag_refs(out: &str) -> Vec<AnnotatedTag> {
+    out.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields.as_slice() {
+                [refname, "tag", object, target] if !target.is_empty() => Some(AnnotatedTag {
+                    refname: refname.to_string(),
+                    object: object.to_string(),
+                    target: target.to_string(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Lists the annotated tags pointing at commits in `selection`.
+///
+/// Must be called before the rewrite, while the selected commits are still
+/// reachable from `HEAD`.
+///
+/// # Returns
+///
+/// * `Ok(Vec<AnnotatedTag>)` with the matching tags, possibly empty.
+/// * `Err(String)` if the tags or the history could not be read.
+pub fn annotated_tags(repo: &Repo, selection: &Selection) -> Result<Vec<AnnotatedTag>, String> {
+    let out = match repo.for_each_ref(
+        "%(refname) %(objecttype) %(objectname) %(*objectname)",
+        "refs/tags/",
+    ) {
+        Ok(o) => o,
+        Err(e) => return Err(e),
+    };
+    let tags = parse_tag_refs(&out);
+
+    let reachable: Option<HashSet<String>> = match selection {
+        Selection::All => match repo.rev_list(&["HEAD"]) {
+            Ok(v) => Some(v.into_iter().collect()),
+            Err(e) => return Err(e),
+        },
+        Selection::Commits(_) => None,
+    };
+
+    Ok(tags
+        .into_iter()
+        .filter(|t| match &reachable {
+            Some(set) => set.contains(&t.target),
+            None => selection.contains(&t.target),
+        })
+        .collect())
+}
+
+/// Builds the rewritten version of the raw tag object `raw`.
+///
+/// The tag name, date and message are kept. Any inline signature is dropped,
+/// since it would no longer verify, and optionally replaced by a new one.
+///
+/// # Parameters
+///
+/// * `repo` – The repository whose signing configuration is used.
+/// * `raw` – The tag as printed by `git cat-file tag`.
+/// * `target` – The object the tag should point at now, if it moved.
+/// * `mapping` – How the tagger is rewritten, if at all.
+/// * `sign` – Re-sign the tag with the (new) tagger's key.
+///
+/// # Returns
+///
+/// * `Ok(Some(bytes))` with the new tag object.
+/// * `Ok(None)` if neither the target nor the tagger changes.
+/// * `Err(String)` if the tag could not be parsed or signed.
+pub(crate) fn rewritten_tag(
+    repo: &Repo,
+    raw: &[u8],
+    target: Option<&str>,
+    mapping: Option<&Mapping>,
+    sign: bool,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut obj = match CommitObject::parse(raw) {
+        Ok(o) => o,
+        Err(e) => return Err(e),
+    };
+    let mut changed = false;
+
+    if let Some(t) = target {
+        if obj.set_header("object", t) {
+            obj.strip_message_signature();
+            changed = true;
+        }
+    }
+
+    let tagger = obj.tagger();
+    let new_tagger = match (mapping, &tagger) {
+        (Some(m), Some(t)) => m.apply_at(t, obj.tagger_time()).filter(|n| n != t),
+        _ => None,
+    };
+    if let Some(t) = &new_tagger {
+        obj.set_tagger(t);
+        changed = true;
+    }
+
+    if !changed {
+        return Ok(None);
+    }
+    if !sign {
+        return Ok(Some(obj.to_bytes()));
+    }
+    let signer = match new_tagger.or(tagger) {
+        Some(t) => t,
+        None => return Err(String::from("tag has no tagger to sign as")),
+    };
+    match sign_tag(repo, &obj, &signer) {
+        Ok(b) => Ok(Some(b)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Appends an OpenPGP signature by `signer` to the unsigned tag `obj`,
+/// as `git tag -s` would.
+fn sign_tag(repo: &Repo, obj: &CommitObject, signer: &Identity) -> Result<Vec<u8>, String> {
+    let payload = obj.to_bytes();
+    let key = match repo.config_get("user.signingkey") {
+        Ok(k) if !k.is_empty() => k,
+        _ => signer.to_string(),
+    };
+    let signature = match repo.sign_buffer(payload.clone(), &key) {
+        Ok(s) => s,
+        Err(e) => return Err(e),
+    };
+
+    let mut out = payload;
+    out.extend_from_slice(&signature);
+    Ok(out)
+}
+
+/// Rewrites the tagger of every tag in `tags` that `mapping` affects.
+///
+/// Each corrected tag object is written to the object database, then the tag
+/// ref is moved to it (or, with `replace`, a `git replace` ref is created so
+/// the tag ref itself is left untouched). Tags keep pointing at the same
+/// object they pointed at before.
+///
+/// # Parameters
+///
+/// * `tags` – The candidates, from [`annotated_tags`].
+/// * `mapping` – How identities are rewritten.
+/// * `sign` – Re-sign the rewritten tags with the new tagger's key.
+/// * `replace` – Create replace refs instead of moving the tag refs.
+///
+/// # Returns
+///
+/// * `Ok(Vec<RewrittenTag>)` with the tags that changed.
+/// * `Err(String)` if a tag could not be read, signed or written.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, identity::{Identity, Mapping}, selection::Selection, tags};
+///
+/// let mapping = Mapping::Fixed(Identity::new("Jane Doe", "jane@corp.com"));
+/// let repo = Repo::discover().unwrap();
+/// let candidates = tags::annotated_tags(&repo, &Selection::All).unwrap();
+/// let done = tags::rewrite_tags(&repo, &candidates, &mapping, false, false).unwrap();
+/// println!("{} tags rewritten", done.len());
+/// ```
+pub fn rewrite_tags(
+    repo: &Repo,
+    tags: &[AnnotatedTag],
+    mapping: &Mapping,
+    sign: bool,
+    replace: bool,
+) -> Result<Vec<RewrittenTag>, String> {
+    let mut done = Vec::new();
+    for tag in tags {
+        let raw = match repo.cat_file("tag", &tag.object) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("cannot read tag {}: {}", tag.refname, e)),
+        };
+        let bytes = match rewritten_tag(repo, &raw, None, Some(mapping), sign) {
+            Ok(Some(b)) => b,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("cannot rewrite tag {}: {}", tag.refname, e)),
+        };
+
+        let rewritten = match repo.hash_object("tag", bytes) {
+            Ok(sha) => sha,
+            Err(e) => return Err(format!("cannot write tag {}: {}", tag.refname, e)),
+        };
+        let result = if replace {
+            repo.replace(&tag.object, &rewritten)
+        } else {
+            repo.update_ref(
+                &tag.refname,
+                &rewritten,
+                "git-author-rewrite: rewrite tagger",
+            )
+        };
+        if let Err(e) = result {
+            return Err(format!("cannot update tag {}: {}", tag.refname, e));
+        }
+
+        done.push(RewrittenTag {
+            refname: tag.refname.clone(),
+            original: tag.object.clone(),
+            rewritten,
+        });
+    }
+
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_tag_refs, rewritten_tag, AnnotatedTag};
+    use crate::{
+        commit_object::CommitObject,
+        git::Repo,
+        identity::{parse_domain_swap, Identity, Mapping},
+    };
+
+    use std::path::PathBuf;
+
+    fn repo() -> Repo {
+        Repo::new(PathBuf::from("."), PathBuf::from(".git"))
+    }
+
+    const RAW: &[u8] = b"object 1111111111111111111111111111111111111111\n\
+type commit\n\
+tag v1.0\n\
+tagger Old <old@old.com> 1700000000 +0000\n\
+\n\
+Release 1.0\n";
+
+    #[test]
+    fn parse_tag_refs_skips_lightweight_tags() {
+        let out = "refs/tags/light commit aaa \n\
+                   refs/tags/v1.0 tag bbb ccc";
+        assert_eq!(
+            parse_tag_refs(out),
+            vec![AnnotatedTag {
+                refname: String::from("refs/tags/v1.0"),
+                object: String::from("bbb"),
+                target: String::from("ccc"),
+            }]
+        );
+    }
+
+    #[test]
+    fn rewritten_tag_rewrites_matching_tagger() {
+        let mapping = parse_domain_swap("old.com=new.com").unwrap();
+        let out = rewritten_tag(&repo(), RAW, None, Some(&mapping), false)
+            .unwrap()
+            .unwrap();
+        let obj = CommitObject::parse(&out).unwrap();
+        assert_eq!(obj.tagger(), Some(Identity::new("Old", "old@new.com")));
+        assert_eq!(obj.message, b"Release 1.0\n");
+    }
+
+    #[test]
+    fn rewritten_tag_skips_unaffected_taggers() {
+        let mapping = parse_domain_swap("elsewhere.com=new.com").unwrap();
+        assert_eq!(
+            rewritten_tag(&repo(), RAW, None, Some(&mapping), false).unwrap(),
+            None
+        );
+
+        let same = Mapping::Fixed(Identity::new("Old", "old@old.com"));
+        assert_eq!(
+            rewritten_tag(&repo(), RAW, None, Some(&same), false).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rewritten_tag_retargets() {
+        let new_target = "2222222222222222222222222222222222222222";
+        let out = rewritten_tag(&repo(), RAW, Some(new_target), None, false)
+            .unwrap()
+            .unwrap();
+        let obj = CommitObject::parse(&out).unwrap();
+        assert_eq!(obj.headers[0], format!("object {}", new_target));
+        assert_eq!(obj.tagger(), Some(Identity::new("Old", "old@old.com")));
+    }
+}
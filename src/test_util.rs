@@ -0,0 +1,537 @@
+use crate::{
+    git::{self, Repo},
+    identity::Identity,
+    runner::{GitCommand, GitOutput, GitRunner, GitStatus},
+};
+
+use std::{cell::RefCell, collections::VecDeque, fs, path::Path};
+use tempfile::TempDir;
+
+/// The identity commits are made with unless [`TempRepoBuilder::author`]
+/// changes it.
+const DEFAULT_NAME: &str = "Test Author";
+const DEFAULT_EMAIL: &str = "author@test.invalid";
+
+/// The branch a new repository starts on, whatever `init.defaultBranch` says.
+pub const DEFAULT_BRANCH: &str = "main";
+
+/// The author and committer date of the first commit, as a Unix timestamp;
+/// each later commit is one minute younger.
+pub const FIRST_COMMIT_TIME: i64 = 1_600_000_000;
+
+/// One step of a [`TempRepoBuilder`], applied in order by `build`.
+#[derive(Debug, Clone)]
+enum Step {
+    Commit {
+        message: String,
+        author: Identity,
+        file: Option<(String, String)>,
+    },
+    Branch(String),
+    Checkout(String),
+    Merge {
+        branch: String,
+        message: String,
+        author: Identity,
+    },
+}
+
+/// A Git repository in a temporary directory, deleted when dropped.
+///
+/// Built with [`TempRepo::builder`]; available with the `test-util` feature.
+#[derive(Debug)]
+pub struct TempRepo {
+    dir: TempDir,
+}
+
+impl TempRepo {
+    /// Starts describing a repository; nothing is created until
+    /// [`TempRepoBuilder::build`].
+    pub fn builder() -> TempRepoBuilder {
+        TempRepoBuilder {
+            author: Identity::new(DEFAULT_NAME, DEFAULT_EMAIL),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Returns the top-level directory of the repository.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Runs `git <args>` in the repository.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` with the trimmed standard output.
+    /// * `Err(String)` with Git's error message otherwise.
+    pub fn git(&self, args: &[&str]) -> Result<String, String> {
+        git::run_in(self.path(), args, &[])
+    }
+
+    /// Returns the SHA of `HEAD`.
+    pub fn head(&self) -> Result<String, String> {
+        self.git(&["rev-parse", "HEAD"])
+    }
+
+    /// Opens the repository as a [`Repo`].
+    pub fn repo(&self) -> Result<Repo, String> {
+        Repo::open(self.path())
+    }
+
+    /// Lists the authors of the commits reachable from `rev`, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Identity>)` – One entry per commit, merges included.
+    /// * `Err(String)` if `git log` failed.
+    pub fn authors(&self, rev: &str) -> Result<Vec<Identity>, String> {
+        self.identities(rev, "%an%x00%ae")
+    }
+
+    /// Lists the committers of the commits reachable from `rev`, oldest first.
+    pub fn committers(&self, rev: &str) -> Result<Vec<Identity>, String> {
+        self.identities(rev, "%cn%x00%ce")
+    }
+
+    fn identities(&self, rev: &str, format: &str) -> Result<Vec<Identity>, String> {
+        let format = format!("--format={}", format);
+        let log = match self.git(&["log", "--reverse", &format, rev]) {
+            Ok(l) => l,
+            Err(e) => return Err(e),
+        };
+        Ok(log
+            .lines()
+            .filter_map(|l| l.split_once('\x00'))
+            .map(|(name, email)| Identity::new(name, email))
+            .collect())
+    }
+}
+
+/// Describes a [`TempRepo`]: commits, branches and merges, made in order.
+///
+/// Every commit adds or changes one file, so merges never conflict, and gets
+/// a fixed date (see [`FIRST_COMMIT_TIME`]), so SHAs are the same on every
+/// run. Commits skip hooks and signing whatever the global config says. The
+/// repository is left on the branch the last step was on, with
+/// `user.name`/`user.email` set to the last [`author`](Self::author).
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires the `test-util` feature and git.
+/// use git_author_rewrite::test_util::TempRepo;
+///
+/// let repo = TempRepo::builder()
+///     .author("Old Name", "old@example.com")
+///     .commit("Initial commit")
+///     .branch("feature")
+///     .commit_as("Add feature", "Jane Doe", "jane@example.com")
+///     .checkout("main")
+///     .commit("Fix typo")
+///     .merge("feature", "Merge feature")
+///     .build()
+///     .unwrap();
+/// assert_eq!(repo.authors("HEAD").unwrap().len(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TempRepoBuilder {
+    author: Identity,
+    steps: Vec<Step>,
+}
+
+impl TempRepoBuilder {
+    /// Sets the identity the following commits and merges are made with.
+    pub fn author(mut self, name: &str, email: &str) -> Self {
+        self.author = Identity::new(name, email);
+        self
+    }
+
+    /// Adds a commit by the current author that creates a new file.
+    pub fn commit(mut self, message: &str) -> Self {
+        self.steps.push(Step::Commit {
+            message: message.to_string(),
+            author: self.author.clone(),
+            file: None,
+        });
+        self
+    }
+
+    /// Adds a commit by `name <email>`, leaving the current author as it is.
+    pub fn commit_as(mut self, message: &str, name: &str, email: &str) -> Self {
+        self.steps.push(Step::Commit {
+            message: message.to_string(),
+            author: Identity::new(name, email),
+            file: None,
+        });
+        self
+    }
+
+    /// Adds a commit by the current author that writes `contents` to `path`.
+    pub fn commit_file(mut self, message: &str, path: &str, contents: &str) -> Self {
+        self.steps.push(Step::Commit {
+            message: message.to_string(),
+            author: self.author.clone(),
+            file: Some((path.to_string(), contents.to_string())),
+        });
+        self
+    }
+
+    /// Creates `name` at the current commit and switches to it.
+    pub fn branch(mut self, name: &str) -> Self {
+        self.steps.push(Step::Branch(name.to_string()));
+        self
+    }
+
+    /// Switches to the existing branch `name`.
+    pub fn checkout(mut self, name: &str) -> Self {
+        self.steps.push(Step::Checkout(name.to_string()));
+        self
+    }
+
+    /// Merges `branch` into the current one with a merge commit by the
+    /// current author, even when a fast-forward is possible.
+    pub fn merge(mut self, branch: &str, message: &str) -> Self {
+        self.steps.push(Step::Merge {
+            branch: branch.to_string(),
+            message: message.to_string(),
+            author: self.author.clone(),
+        });
+        self
+    }
+
+    /// Creates the repository and applies the steps in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TempRepo)` – The repository.
+    /// * `Err(String)` naming the step that failed; the directory is removed.
+    pub fn build(self) -> Result<TempRepo, String> {
+        let dir = match tempfile::Builder::new()
+            .prefix("git-author-rewrite-test-")
+            .tempdir()
+        {
+            Ok(d) => d,
+            Err(e) => return Err(format!("cannot create a temporary directory: {}", e)),
+        };
+        let repo = TempRepo { dir };
+        let path = repo.path().to_path_buf();
+        let head = format!("refs/heads/{}", DEFAULT_BRANCH);
+        let setup: [&[&str]; 4] = [
+            &["init", "--quiet"],
+            &["symbolic-ref", "HEAD", &head],
+            &["config", "user.name", &self.author.name],
+            &["config", "user.email", &self.author.email],
+        ];
+        for args in setup {
+            if let Err(e) = git::run_in(&path, args, &[]) {
+                return Err(format!("git {} failed: {}", args.join(" "), e));
+            }
+        }
+
+        let mut commits = 0;
+        for step in &self.steps {
+            let result = match step {
+                Step::Commit {
+                    message,
+                    author,
+                    file,
+                } => {
+                    commits += 1;
+                    let (file, contents) = match file {
+                        Some((f, c)) => (f.clone(), c.clone()),
+                        None => (format!("file-{}.txt", commits), format!("{}\n", message)),
+                    };
+                    commit(&path, commits, message, author, &file, &contents)
+                }
+                Step::Branch(name) => {
+                    git::run_in(&path, &["checkout", "--quiet", "-b", name], &[]).map(|_| ())
+                }
+                Step::Checkout(name) => {
+                    git::run_in(&path, &["checkout", "--quiet", name], &[]).map(|_| ())
+                }
+                Step::Merge {
+                    branch,
+                    message,
+                    author,
+                } => {
+                    commits += 1;
+                    let args = [
+                        "-c",
+                        "commit.gpgsign=false",
+                        "merge",
+                        "--quiet",
+                        "--no-ff",
+                        "--no-verify",
+                        "-m",
+                        message,
+                        branch,
+                    ];
+                    git::run_in(&path, &args, &identity_env(author, commits).as_env()).map(|_| ())
+                }
+            };
+            if let Err(e) = result {
+                return Err(format!("{:?} failed: {}", step, e));
+            }
+        }
+        Ok(repo)
+    }
+}
+
+/// The `GIT_AUTHOR_*`/`GIT_COMMITTER_*` variables of the `n`th commit.
+struct IdentityEnv {
+    name: String,
+    email: String,
+    date: String,
+}
+
+impl IdentityEnv {
+    fn as_env(&self) -> [(&str, &str); 6] {
+        [
+            ("GIT_AUTHOR_NAME", &self.name),
+            ("GIT_AUTHOR_EMAIL", &self.email),
+            ("GIT_AUTHOR_DATE", &self.date),
+            ("GIT_COMMITTER_NAME", &self.name),
+            ("GIT_COMMITTER_EMAIL", &self.email),
+            ("GIT_COMMITTER_DATE", &self.date),
+        ]
+    }
+}
+
+fn identity_env(who: &Identity, n: i64) -> IdentityEnv {
+    IdentityEnv {
+        name: who.name.clone(),
+        email: who.email.clone(),
+        date: format!("@{} +0000", FIRST_COMMIT_TIME + (n - 1) * 60),
+    }
+}
+
+/// Writes `contents` to `file` and commits it as the `n`th commit.
+fn commit(
+    dir: &Path,
+    n: i64,
+    message: &str,
+    author: &Identity,
+    file: &str,
+    contents: &str,
+) -> Result<(), String> {
+    let target = dir.join(file);
+    if let Some(parent) = target.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return Err(format!("cannot create {}: {}", parent.display(), e));
+        }
+    }
+    if let Err(e) = fs::write(&target, contents) {
+        return Err(format!("cannot write {}: {}", file, e));
+    }
+    if let Err(e) = git::run_in(dir, &["add", "--", file], &[]) {
+        return Err(e);
+    }
+    let env = identity_env(author, n);
+    let args = [
+        "-c",
+        "commit.gpgsign=false",
+        "commit",
+        "--quiet",
+        "--no-verify",
+        "-m",
+        message,
+    ];
+    git::run_in(dir, &args, &env.as_env()).map(|_| ())
+}
+
+/// A [`GitRunner`] that answers from a script instead of running anything,
+/// for tests of code that calls the git layer (hand it to a repository with
+/// [`Repo::using`], or inject it with [`git::with_runner`]).
+///
+/// Each entry pairs the arguments a command is expected to have, as given by
+/// [`GitCommand::arg_line`], with what it "prints". Commands must arrive in
+/// script order; any other command panics, naming what was expected. Every
+/// command received is recorded for later assertions.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires the `test-util` feature.
+/// use git_author_rewrite::{git::Repo, test_util::ScriptedRunner};
+/// use std::{path::PathBuf, rc::Rc};
+///
+/// let runner = Rc::new(ScriptedRunner::new(vec![
+///     ("rev-list --left-right --count HEAD...@{upstream}", ScriptedRunner::ok("2\t0")),
+/// ]));
+/// let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner.clone());
+/// let counts = repo.ahead_behind();
+/// assert_eq!(counts, Ok((2, 0)));
+/// assert!(runner.finished());
+/// ```
+#[derive(Debug, Default)]
+pub struct ScriptedRunner {
+    script: RefCell<VecDeque<(String, GitOutput)>>,
+    calls: RefCell<Vec<GitCommand>>,
+}
+
+impl ScriptedRunner {
+    /// Creates a runner that expects the commands of `script`, in order.
+    pub fn new(script: Vec<(&str, GitOutput)>) -> ScriptedRunner {
+        ScriptedRunner {
+            script: RefCell::new(
+                script
+                    .into_iter()
+                    .map(|(args, out)| (args.to_string(), out))
+                    .collect(),
+            ),
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// A successful command that printed `stdout`.
+    pub fn ok(stdout: &str) -> GitOutput {
+        GitOutput {
+            status: GitStatus { code: Some(0) },
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    /// A command that exited with `code`, printing `stderr`.
+    pub fn failed(code: i32, stderr: &str) -> GitOutput {
+        GitOutput {
+            status: GitStatus { code: Some(code) },
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    /// Returns the commands received so far, in order.
+    pub fn calls(&self) -> Vec<GitCommand> {
+        self.calls.borrow().clone()
+    }
+
+    /// Whether every scripted command was received.
+    pub fn finished(&self) -> bool {
+        self.script.borrow().is_empty()
+    }
+
+    fn answer(&self, cmd: &GitCommand) -> GitOutput {
+        self.calls.borrow_mut().push(cmd.clone());
+        let line = cmd.arg_line();
+        match self.script.borrow_mut().pop_front() {
+            Some((expected, out)) if expected == line => out,
+            Some((expected, _)) => {
+                panic!("unexpected git command `{}`; expected `{}`", line, expected)
+            }
+            None => panic!("unexpected git command `{}`; the script is done", line),
+        }
+    }
+}
+
+impl GitRunner for ScriptedRunner {
+    fn run_status(&self, cmd: &GitCommand) -> Result<GitStatus, String> {
+        Ok(self.answer(cmd).status)
+    }
+
+    fn run_output(&self, cmd: &GitCommand) -> Result<GitOutput, String> {
+        Ok(self.answer(cmd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScriptedRunner, TempRepo, DEFAULT_BRANCH};
+    use crate::{
+        git::{self, Repo},
+        identity::Identity,
+    };
+    use std::{path::PathBuf, rc::Rc};
+
+    #[test]
+    fn builds_branches_and_merges_with_the_given_authors() {
+        let repo = TempRepo::builder()
+            .author("Old", "old@example.com")
+            .commit("one")
+            .branch("feature")
+            .commit_as("two", "Jane", "jane@example.com")
+            .checkout(DEFAULT_BRANCH)
+            .commit_file("three", "docs/notes.md", "notes\n")
+            .merge("feature", "Merge feature")
+            .build()
+            .unwrap();
+
+        let old = Identity::new("Old", "old@example.com");
+        let jane = Identity::new("Jane", "jane@example.com");
+        let mut authors = repo.authors("HEAD").unwrap();
+        authors.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(authors, vec![jane, old.clone(), old.clone(), old]);
+
+        let parents = repo
+            .git(&["rev-list", "--parents", "-n", "1", "HEAD"])
+            .unwrap();
+        assert_eq!(parents.split(' ').count(), 3);
+        assert_eq!(
+            repo.git(&["symbolic-ref", "--short", "HEAD"]).unwrap(),
+            DEFAULT_BRANCH
+        );
+        assert_eq!(repo.git(&["show", "HEAD:docs/notes.md"]).unwrap(), "notes");
+    }
+
+    #[test]
+    fn commits_are_reproducible() {
+        let build = || {
+            TempRepo::builder()
+                .commit("one")
+                .commit("two")
+                .build()
+                .unwrap()
+        };
+        let (a, b) = (build(), build());
+        assert_ne!(a.path(), b.path());
+        assert_eq!(a.head().unwrap(), b.head().unwrap());
+    }
+
+    #[test]
+    fn built_repository_opens_as_a_repo() {
+        let temp = TempRepo::builder().commit("one").build().unwrap();
+        let repo = temp.repo().unwrap();
+        assert_eq!(repo.rev_parse("HEAD"), temp.head());
+        assert!(repo.git_dir().ends_with(".git"));
+        assert!(!repo.rebase_in_progress());
+    }
+
+    #[test]
+    fn a_failed_step_is_reported() {
+        let err = TempRepo::builder()
+            .commit("one")
+            .checkout("missing")
+            .build()
+            .unwrap_err();
+        assert!(err.starts_with("Checkout(\"missing\") failed:"), "{}", err);
+    }
+
+    #[test]
+    fn scripted_runner_answers_in_order_and_records_calls() {
+        let runner = Rc::new(ScriptedRunner::new(vec![
+            ("rev-parse --git-dir", ScriptedRunner::ok(".git\n")),
+            ("symbolic-ref --quiet HEAD", ScriptedRunner::failed(1, "")),
+        ]));
+        let repo = Repo::new(PathBuf::from("."), PathBuf::from(".git")).using(runner.clone());
+        let (git_dir, head) = (repo.rev_parse("--git-dir"), repo.symbolic_head());
+        assert_eq!(git_dir, Ok(String::from(".git")));
+        assert_eq!(head, None);
+        assert!(runner.finished());
+        assert_eq!(runner.calls().len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected git command `rev-parse --show-toplevel`")]
+    fn scripted_runner_rejects_unexpected_commands() {
+        let runner = Rc::new(ScriptedRunner::new(vec![(
+            "rev-parse --git-dir",
+            ScriptedRunner::ok(".git"),
+        )]));
+        // A repository described inside `with_runner` keeps using its runner.
+        let repo = git::with_runner(runner, || {
+            Repo::new(PathBuf::from("."), PathBuf::from(".git"))
+        });
+        let _ = repo.rev_parse("--show-toplevel");
+    }
+}
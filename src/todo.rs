@@ -0,0 +1,700 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// A command in a rebase todo list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoCommand {
+    /// `pick` / `p`: use the commit.
+    Pick,
+    /// `reword` / `r`: use the commit, but edit its message.
+    Reword,
+    /// `edit` / `e`: use the commit, but stop to amend it.
+    Edit,
+    /// `squash` / `s`: meld the commit into the previous one.
+    Squash,
+    /// `fixup` / `f`: like squash, keeping only one message.
+    Fixup,
+    /// `exec` / `x`: run a shell command.
+    Exec,
+    /// `break` / `b`: stop here.
+    Break,
+    /// `drop` / `d`: remove the commit.
+    Drop,
+    /// `label` / `l`: name the current `HEAD`.
+    Label,
+    /// `reset` / `t`: reset `HEAD` to a label.
+    Reset,
+    /// `merge` / `m`: create a merge commit.
+    Merge,
+    /// `update-ref` / `u`: update a ref once the rebase finishes.
+    UpdateRef,
+    /// `noop`: do nothing.
+    Noop,
+}
+
+impl TodoCommand {
+    /// Parses a command word, full or abbreviated (`rebase.abbreviateCommands`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::todo::TodoCommand;
+    ///
+    /// assert_eq!(TodoCommand::parse("p"), Some(TodoCommand::Pick));
+    /// assert_eq!(TodoCommand::parse("fixup"), Some(TodoCommand::Fixup));
+    /// assert_eq!(TodoCommand::parse("pickle"), None);
+    /// ```
+    pub fn parse(word: &str) -> Option<Self> {
+        match word {
+            "pick" | "p" => Some(TodoCommand::Pick),
+            "reword" | "r" => Some(TodoCommand::Reword),
+            "edit" | "e" => Some(TodoCommand::Edit),
+            "squash" | "s" => Some(TodoCommand::Squash),
+            "fixup" | "f" => Some(TodoCommand::Fixup),
+            "exec" | "x" => Some(TodoCommand::Exec),
+            "break" | "b" => Some(TodoCommand::Break),
+            "drop" | "d" => Some(TodoCommand::Drop),
+            "label" | "l" => Some(TodoCommand::Label),
+            "reset" | "t" => Some(TodoCommand::Reset),
+            "merge" | "m" => Some(TodoCommand::Merge),
+            "update-ref" | "u" => Some(TodoCommand::UpdateRef),
+            "noop" => Some(TodoCommand::Noop),
+            _ => None,
+        }
+    }
+
+    /// The full command word, e.g. `edit`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TodoCommand::Pick => "pick",
+            TodoCommand::Reword => "reword",
+            TodoCommand::Edit => "edit",
+            TodoCommand::Squash => "squash",
+            TodoCommand::Fixup => "fixup",
+            TodoCommand::Exec => "exec",
+            TodoCommand::Break => "break",
+            TodoCommand::Drop => "drop",
+            TodoCommand::Label => "label",
+            TodoCommand::Reset => "reset",
+            TodoCommand::Merge => "merge",
+            TodoCommand::UpdateRef => "update-ref",
+            TodoCommand::Noop => "noop",
+        }
+    }
+
+    /// The single-letter form, e.g. `e`; `noop` has none.
+    pub fn abbreviation(self) -> Option<&'static str> {
+        match self {
+            TodoCommand::Pick => Some("p"),
+            TodoCommand::Reword => Some("r"),
+            TodoCommand::Edit => Some("e"),
+            TodoCommand::Squash => Some("s"),
+            TodoCommand::Fixup => Some("f"),
+            TodoCommand::Exec => Some("x"),
+            TodoCommand::Break => Some("b"),
+            TodoCommand::Drop => Some("d"),
+            TodoCommand::Label => Some("l"),
+            TodoCommand::Reset => Some("t"),
+            TodoCommand::Merge => Some("m"),
+            TodoCommand::UpdateRef => Some("u"),
+            TodoCommand::Noop => None,
+        }
+    }
+
+    /// Reports whether the command's argument is a commit to apply (or drop).
+    pub fn takes_commit(self) -> bool {
+        matches!(
+            self,
+            TodoCommand::Pick
+                | TodoCommand::Reword
+                | TodoCommand::Edit
+                | TodoCommand::Squash
+                | TodoCommand::Fixup
+                | TodoCommand::Drop
+        )
+    }
+}
+
+/// A non-comment line of a rebase todo list, split into its parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TodoLine<'a> {
+    /// The leading whitespace.
+    pub indent: &'a str,
+    /// The command word as written, e.g. `p` or `pick`.
+    pub word: &'a str,
+    /// The parsed command.
+    pub command: TodoCommand,
+    /// The whitespace between the command word and its arguments.
+    pub separator: &'a str,
+    /// Everything after the command word, without the separating whitespace.
+    pub args: &'a str,
+}
+
+impl<'a> TodoLine<'a> {
+    /// Parses a todo line.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(TodoLine)` for a command line.
+    /// * `None` for comments, blank lines and unknown commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::todo::{TodoCommand, TodoLine};
+    ///
+    /// let line = TodoLine::parse("  p abc123 Fix bug").unwrap();
+    /// assert_eq!(line.command, TodoCommand::Pick);
+    /// assert_eq!(line.commit(), Some("abc123"));
+    /// ```
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return None;
+        }
+        let indent = &line[..line.len() - trimmed.len()];
+        let word_end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        let (word, rest) = trimmed.split_at(word_end);
+        let args = rest.trim_start();
+        let separator = &rest[..rest.len() - args.len()];
+        TodoCommand::parse(word).map(|command| TodoLine {
+            indent,
+            word,
+            command,
+            separator,
+            args,
+        })
+    }
+
+    /// The commit the line refers to: the argument of `pick`-like commands
+    /// (after `fixup -C`/`-c`), or the original merge of `merge -C`/`-c`.
+    pub fn commit(&self) -> Option<&'a str> {
+        let mut words = self.args.split_whitespace();
+        let first = words.next();
+        let has_option = matches!(first, Some("-C") | Some("-c"));
+        match self.command {
+            TodoCommand::Fixup | TodoCommand::Merge if has_option => words.next(),
+            TodoCommand::Merge => None,
+            c if c.takes_commit() => first,
+            _ => None,
+        }
+    }
+
+    /// Formats the line with `command` in place of its own, abbreviated if
+    /// the original was. Everything else is kept byte for byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use git_author_rewrite::todo::{TodoCommand, TodoLine};
+    ///
+    /// let line = TodoLine::parse("p abc123 Fix bug").unwrap();
+    /// assert_eq!(line.with_command(TodoCommand::Edit), "e abc123 Fix bug");
+    /// ```
+    pub fn with_command(&self, command: TodoCommand) -> String {
+        let abbreviated = self.command.abbreviation() == Some(self.word);
+        let word = match command.abbreviation() {
+            Some(a) if abbreviated => a,
+            _ => command.name(),
+        };
+        format!("{}{}{}{}", self.indent, word, self.separator, self.args)
+    }
+}
+
+/// Splits a line, as yielded by `split_inclusive`, into its content and its
+/// terminator (`\r\n`, `\n`, or empty for an unterminated last line).
+fn split_ending(line: &[u8]) -> (&[u8], &'static str) {
+    if let Some(l) = line.strip_suffix(b"\r\n") {
+        (l, "\r\n")
+    } else if let Some(l) = line.strip_suffix(b"\n") {
+        (l, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+/// Splits a line into its longest valid UTF-8 prefix and the bytes after it.
+///
+/// Commands and commit ids are ASCII, so only a subject in a legacy encoding
+/// reaches the tail.
+fn utf8_head(line: &[u8]) -> (&str, &[u8]) {
+    let valid = match std::str::from_utf8(line) {
+        Ok(s) => return (s, &[]),
+        Err(e) => e.valid_up_to(),
+    };
+    let (head, tail) = line.split_at(valid);
+    match std::str::from_utf8(head) {
+        Ok(h) => (h, tail),
+        Err(_) => ("", line),
+    }
+}
+
+/// One line of a [`TodoFile`] (command, comment or blank), owned so it can be
+/// changed in place. Whatever is not changed is written back byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The longest valid UTF-8 prefix of the line, which holds the command.
+    head: String,
+    /// The bytes after `head`: a subject that is not valid UTF-8.
+    tail: Vec<u8>,
+    /// Text appended after the tail, e.g. a trailing comment.
+    suffix: String,
+    /// The line terminator: `\n`, `\r\n`, or empty for an unterminated last line.
+    ending: &'static str,
+}
+
+impl Entry {
+    /// Creates a line from `text`, terminated by `\n`.
+    pub fn new(text: &str) -> Entry {
+        Entry {
+            head: text.to_string(),
+            tail: Vec::new(),
+            suffix: String::new(),
+            ending: "\n",
+        }
+    }
+
+    /// Parses one line of a todo list, with its terminator if it has one.
+    fn parse(line: &[u8]) -> Entry {
+        let (content, ending) = split_ending(line);
+        let (head, tail) = utf8_head(content);
+        Entry {
+            head: head.to_string(),
+            tail: tail.to_vec(),
+            suffix: String::new(),
+            ending,
+        }
+    }
+
+    /// Parses the line's command.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(TodoLine)` for a command line.
+    /// * `None` for comments, blank lines and unknown commands.
+    pub fn line(&self) -> Option<TodoLine<'_>> {
+        TodoLine::parse(&self.head)
+    }
+
+    /// The commit the line refers to, if any (see [`TodoLine::commit`]).
+    pub fn commit(&self) -> Option<&str> {
+        self.line().and_then(|l| l.commit())
+    }
+
+    /// Replaces the line's command, keeping its form (full or abbreviated),
+    /// indentation and arguments.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the line is not a command line, which is left unchanged.
+    pub fn set_command(&mut self, command: TodoCommand) -> bool {
+        let changed = match self.line() {
+            Some(l) => l.with_command(command),
+            None => return false,
+        };
+        self.head = changed;
+        true
+    }
+
+    /// Appends `text` to the end of the line (before its terminator).
+    pub fn append(&mut self, text: &str) {
+        self.suffix.push_str(text);
+    }
+
+    /// Writes the line, with its terminator, to `out`.
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.head.as_bytes());
+        out.extend_from_slice(&self.tail);
+        out.extend_from_slice(self.suffix.as_bytes());
+        out.extend_from_slice(self.ending.as_bytes());
+    }
+}
+
+/// A rebase todo list as typed lines: parse it, change the entries, and
+/// serialize it again.
+///
+/// Line endings (`\n` or `\r\n`), whitespace, a missing final newline and
+/// subjects that are not valid UTF-8 survive a round trip unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::todo::{TodoCommand, TodoFile};
+///
+/// let mut todo = TodoFile::parse(b"pick abc One\r\n# comment\r\n");
+/// for entry in todo.entries.iter_mut() {
+///     entry.set_command(TodoCommand::Edit);
+/// }
+/// todo.insert_line(1, "exec make test");
+/// assert_eq!(
+///     todo.to_bytes(),
+///     b"edit abc One\r\nexec make test\r\n# comment\r\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TodoFile {
+    /// The lines, in order.
+    pub entries: Vec<Entry>,
+}
+
+impl TodoFile {
+    /// Parses a todo list. Every input parses; lines that are not commands
+    /// are kept as they are.
+    pub fn parse(bytes: &[u8]) -> TodoFile {
+        let entries = bytes
+            .split_inclusive(|&b| b == b'\n')
+            .map(Entry::parse)
+            .collect();
+        TodoFile { entries }
+    }
+
+    /// Reads and parses the todo file at `path`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TodoFile)` on success.
+    /// * `Err(String)` if the file cannot be read.
+    pub fn read(path: &Path) -> Result<TodoFile, String> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(TodoFile::parse(&bytes)),
+            Err(e) => Err(format!("read failed: {}", e)),
+        }
+    }
+
+    /// Serializes the todo list.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for entry in &self.entries {
+            entry.write_to(&mut out);
+        }
+        out
+    }
+
+    /// Writes the todo list to `path`, replacing the file in one step (see
+    /// [`rewrite_file`]).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(String)` if the file cannot be created or written.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        replace_file(path, |out| match out.write_all(&self.to_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("write failed: {}", e)),
+        })
+    }
+
+    /// The commits the lines refer to, in order.
+    pub fn commits(&self) -> Vec<&str> {
+        self.entries.iter().filter_map(Entry::commit).collect()
+    }
+
+    /// Inserts a new line at `index`, terminated like the file's first line.
+    /// Appending after an unterminated last line terminates that line instead,
+    /// so the file still ends without a newline.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is greater than the number of lines.
+    pub fn insert_line(&mut self, index: usize, text: &str) {
+        let ending = match self.entries.first() {
+            Some(e) if !e.ending.is_empty() => e.ending,
+            _ => "\n",
+        };
+        let mut entry = Entry::new(text);
+        entry.ending = ending;
+        if index == self.entries.len() {
+            if let Some(last) = self.entries.last_mut() {
+                if last.ending.is_empty() {
+                    last.ending = ending;
+                    entry.ending = "";
+                }
+            }
+        }
+        self.entries.insert(index, entry);
+    }
+}
+
+/// Copies a todo list from `input` to `output` one line at a time, letting
+/// `edit` change each line and return lines to add after it. Only the line
+/// being edited is held in memory, however long the list.
+///
+/// Added lines are terminated like the first line, as with
+/// [`TodoFile::insert_line`]; lines added after an unterminated last line
+/// terminate it instead, so the output still ends without a newline.
+///
+/// # Returns
+///
+/// * `Ok(())` once every line is written.
+/// * `Err(String)` if reading or writing failed.
+///
+/// # Examples
+///
+/// ```
+/// use git_author_rewrite::todo::{transform, TodoCommand};
+///
+/// let mut out = Vec::new();
+/// transform(&b"pick abc One\r\n# comment\r\n"[..], &mut out, |entry| {
+///     if entry.set_command(TodoCommand::Edit) {
+///         vec![String::from("exec make test")]
+///     } else {
+///         Vec::new()
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!(out, b"edit abc One\r\nexec make test\r\n# comment\r\n");
+/// ```
+pub fn transform<R, W, F>(input: R, output: W, edit: F) -> Result<(), String>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(&mut Entry) -> Vec<String>,
+{
+    let (mut input, mut output, mut edit) = (input, output, edit);
+    let mut first_ending = None;
+    let mut line = Vec::new();
+    let mut out = Vec::new();
+    loop {
+        line.clear();
+        match input.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => return Err(format!("read failed: {}", e)),
+        }
+        let mut entry = Entry::parse(&line);
+        let ending = *first_ending.get_or_insert(match entry.ending {
+            "" => "\n",
+            e => e,
+        });
+
+        let added = edit(&mut entry);
+        let unterminated = entry.ending.is_empty() && !added.is_empty();
+        if unterminated {
+            entry.ending = ending;
+        }
+        out.clear();
+        entry.write_to(&mut out);
+        for (i, text) in added.iter().enumerate() {
+            let mut extra = Entry::new(text);
+            extra.ending = if unterminated && i + 1 == added.len() {
+                ""
+            } else {
+                ending
+            };
+            extra.write_to(&mut out);
+        }
+        if let Err(e) = output.write_all(&out) {
+            return Err(format!("write failed: {}", e));
+        }
+    }
+    match output.flush() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("write failed: {}", e)),
+    }
+}
+
+/// Streams the todo file at `path` through `edit` (see [`transform`]) into
+/// a temporary file next to it, then renames that over the original.
+///
+/// A crash or a failed write leaves the original file as it was, never a
+/// half-written todo list for Git to run.
+///
+/// # Returns
+///
+/// * `Ok(())` once `path` holds the edited list.
+/// * `Err(String)` if the file could not be read, written or replaced.
+pub fn rewrite_file<F>(path: &Path, edit: F) -> Result<(), String>
+where
+    F: FnMut(&mut Entry) -> Vec<String>,
+{
+    let input = match File::open(path) {
+        Ok(f) => BufReader::new(f),
+        Err(e) => return Err(format!("read failed: {}", e)),
+    };
+    replace_file(path, |out| transform(input, out, edit))
+}
+
+/// Reads the commits the lines of the todo file at `path` refer to, in
+/// order, without holding the file in memory.
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` with the commits.
+/// * `Err(String)` if the file cannot be read.
+pub fn read_commits(path: &Path) -> Result<Vec<String>, String> {
+    let input = match File::open(path) {
+        Ok(f) => BufReader::new(f),
+        Err(e) => return Err(format!("read failed: {}", e)),
+    };
+    let mut commits = Vec::new();
+    let result = transform(input, io::sink(), |entry| {
+        if let Some(sha) = entry.commit() {
+            commits.push(sha.to_string());
+        }
+        Vec::new()
+    });
+    match result {
+        Ok(()) => Ok(commits),
+        Err(e) => Err(e),
+    }
+}
+
+/// The temporary file [`replace_file`] writes before renaming it over `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = match path.file_name() {
+        Some(n) => n.to_os_string(),
+        None => OsString::from("todo"),
+    };
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Writes a new `path` with `write` into a temporary file in the same
+/// directory, flushed to disk, and renames it over `path`. The temporary
+/// file is removed if anything fails.
+fn replace_file<F>(path: &Path, write: F) -> Result<(), String>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), String>,
+{
+    let temp = temp_path(path);
+    let file = match File::create(&temp) {
+        Ok(f) => f,
+        Err(e) => return Err(format!("create failed: {}", e)),
+    };
+    let mut out = BufWriter::new(file);
+    let written = match write(&mut out) {
+        Ok(()) => match out.into_inner() {
+            Ok(f) => f.sync_all().map_err(|e| format!("write failed: {}", e)),
+            Err(e) => Err(format!("write failed: {}", e.error())),
+        },
+        Err(e) => Err(e),
+    };
+    let result = match written {
+        Ok(()) => fs::rename(&temp, path).map_err(|e| format!("rename failed: {}", e)),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_commits, rewrite_file, temp_path, transform, TodoCommand, TodoFile, TodoLine};
+    use std::fs;
+
+    #[test]
+    fn parse_accepts_full_and_abbreviated_commands() {
+        for (full, short) in [
+            ("pick", "p"),
+            ("reword", "r"),
+            ("edit", "e"),
+            ("squash", "s"),
+            ("fixup", "f"),
+            ("exec", "x"),
+            ("break", "b"),
+            ("drop", "d"),
+            ("label", "l"),
+            ("reset", "t"),
+            ("merge", "m"),
+            ("update-ref", "u"),
+        ] {
+            let command = TodoCommand::parse(full).expect(full);
+            assert_eq!(TodoCommand::parse(short), Some(command));
+            assert_eq!(command.name(), full);
+            assert_eq!(command.abbreviation(), Some(short));
+        }
+        assert_eq!(TodoCommand::parse("noop"), Some(TodoCommand::Noop));
+    }
+
+    #[test]
+    fn commit_handles_options_and_commandless_lines() {
+        let commit = |l| TodoLine::parse(l).and_then(|t| t.commit());
+        assert_eq!(commit("pick abc Subject"), Some("abc"));
+        assert_eq!(commit("f -C abc Subject"), Some("abc"));
+        assert_eq!(commit("merge -C abc topic # Merge topic"), Some("abc"));
+        assert_eq!(commit("merge topic"), None);
+        assert_eq!(commit("exec make test"), None);
+        assert_eq!(commit("label onto"), None);
+        assert_eq!(commit("# pick abc Subject"), None);
+        assert_eq!(commit("b"), None);
+    }
+
+    #[test]
+    fn with_command_keeps_indent_and_abbreviation() {
+        let line = TodoLine::parse("\t pick abc Subject").expect("parse failed");
+        assert_eq!(line.with_command(TodoCommand::Edit), "\t edit abc Subject");
+        let line = TodoLine::parse("p abc Subject").expect("parse failed");
+        assert_eq!(line.with_command(TodoCommand::Edit), "e abc Subject");
+        let line = TodoLine::parse("pick\t abc  Subject  ").expect("parse failed");
+        assert_eq!(line.separator, "\t ");
+        assert_eq!(
+            line.with_command(TodoCommand::Drop),
+            "drop\t abc  Subject  "
+        );
+    }
+
+    #[test]
+    fn todo_file_round_trips_byte_for_byte() {
+        let body = b"pick aaa Caf\xe9\r\n# comment  \r\n\n\tp bbb \xff Two \tlabel x";
+        let todo = TodoFile::parse(body);
+        assert_eq!(todo.to_bytes(), body);
+        assert_eq!(todo.commits(), vec!["aaa", "bbb"]);
+        assert!(TodoFile::parse(b"").entries.is_empty());
+    }
+
+    #[test]
+    fn transform_streams_edits_and_added_lines() {
+        let body = b"pick aaa Caf\xe9\n# pick bbb\npick ccc Two";
+        let mut out = Vec::new();
+        transform(&body[..], &mut out, |entry| {
+            if entry.set_command(TodoCommand::Edit) {
+                vec![String::from("exec make")]
+            } else {
+                Vec::new()
+            }
+        })
+        .expect("transform failed");
+        assert_eq!(
+            out,
+            b"edit aaa Caf\xe9\nexec make\n# pick bbb\nedit ccc Two\nexec make"
+        );
+    }
+
+    #[test]
+    fn rewrite_file_replaces_the_file_and_leaves_no_temp_behind() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("git-rebase-todo");
+        fs::write(&path, "pick aaa One\r\npick bbb Two\r\n").expect("failed to write todo");
+        rewrite_file(&path, |entry| {
+            entry.set_command(TodoCommand::Edit);
+            Vec::new()
+        })
+        .expect("rewrite_file failed");
+        assert_eq!(
+            fs::read(&path).expect("failed to read todo"),
+            b"edit aaa One\r\nedit bbb Two\r\n"
+        );
+        assert_eq!(read_commits(&path), Ok(vec![String::from("aaa"), String::from("bbb")]));
+        assert!(!temp_path(&path).exists());
+        assert!(rewrite_file(&dir.path().join("missing"), |_| Vec::new()).is_err());
+    }
+
+    #[test]
+    fn todo_file_entries_can_be_changed() {
+        let mut todo = TodoFile::parse(b"pick aaa Caf\xe9\n# pick bbb\npick ccc Two");
+        assert!(todo.entries[0].set_command(TodoCommand::Edit));
+        assert!(!todo.entries[1].set_command(TodoCommand::Edit));
+        todo.entries[0].append(" # note");
+        todo.insert_line(3, "exec make");
+        assert_eq!(
+            todo.to_bytes(),
+            b"edit aaa Caf\xe9 # note\n# pick bbb\npick ccc Two\nexec make"
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use crate::{
+    git::Repo,
+    progress::{self, format_duration, Progress},
+    todo::{TodoCommand, TodoLine},
+};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// How many log lines the dashboard keeps.
+const LOG_LINES: usize = 500;
+
+/// Where a commit of the rebase stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowState {
+    /// Already applied by the rebase.
+    Done,
+    /// The commit the rebase is stopped at.
+    Current,
+    /// Still in the todo list.
+    Pending,
+}
+
+/// One commit of the rebase todo list, as shown on the dashboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    /// The abbreviated SHA from the todo list.
+    pub sha: String,
+    /// The commit subject from the todo list.
+    pub subject: String,
+    /// Whether the rebase stops here to rewrite the commit (`edit`).
+    pub marked: bool,
+    /// Where the commit stands.
+    pub state: RowState,
+}
+
+/// How a dashboard run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The rebase completed.
+    Finished {
+        /// The number of commits amended.
+        amended: usize,
+        /// How long the run took.
+        elapsed: Duration,
+    },
+    /// A step failed; the rebase is left stopped where it was.
+    Failed(String),
+    /// The user aborted, and `git rebase --abort` restored the branch.
+    Aborted,
+}
+
+/// Builds the dashboard rows from the rebase's `done` and `git-rebase-todo`
+/// files. When `stopped`, the last commit in `done` is the current one.
+pub(crate) fn rows(done: &str, todo: &str, stopped: bool) -> Vec<Row> {
+    let row = |line: TodoLine, state| {
+        line.commit().map(|sha| Row {
+            sha: sha.to_string(),
+            subject: line
+                .args
+                .split_once(sha)
+                .map(|(_, s)| s.trim().to_string())
+                .unwrap_or_default(),
+            marked: line.command == TodoCommand::Edit,
+            state,
+        })
+    };
+
+    let mut out: Vec<Row> = done
+        .lines()
+        .filter_map(TodoLine::parse)
+        .filter_map(|l| row(l, RowState::Done))
+        .collect();
+    if stopped {
+        if let Some(last) = out.last_mut() {
+            last.state = RowState::Current;
+        }
+    }
+    out.extend(
+        todo.lines()
+            .filter_map(TodoLine::parse)
+            .filter_map(|l| row(l, RowState::Pending)),
+    );
+    out
+}
+
+/// The dashboard's state between redraws.
+struct Dashboard {
+    rows: Vec<Row>,
+    log: Vec<String>,
+    started: Instant,
+    progress: Progress,
+    paused: bool,
+}
+
+impl Dashboard {
+    /// Adds a line to the log pane, dropping the oldest ones past [`LOG_LINES`].
+    fn log(&mut self, line: String) {
+        self.log.push(line);
+        if self.log.len() > LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    /// Re-reads the rebase's progress from `git_dir`.
+    fn refresh(&mut self, git_dir: &Path) {
+        let dir = git_dir.join("rebase-merge");
+        if !dir.exists() {
+            return;
+        }
+        let done = progress::read_todo(&dir.join("done"));
+        let todo = progress::read_todo(&dir.join("git-rebase-todo"));
+        self.rows = rows(&done, &todo, true);
+    }
+
+    /// Draws the header, progress bar, commit list, log pane and key help.
+    fn draw(&self, frame: &mut Frame) {
+        let [header, gauge, commits, log, keys] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let total = self.rows.len();
+        let done = self
+            .rows
+            .iter()
+            .filter(|r| r.state == RowState::Done)
+            .count();
+        let elapsed = self.started.elapsed();
+        let status = if self.paused {
+            Span::styled(
+                "PAUSED",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled("running", Style::default().fg(Color::Green))
+        };
+        let summary = Line::from(vec![
+            Span::raw(format!(
+                "{}/{} in the rebase · amended {} · elapsed {} · ",
+                done,
+                total,
+                self.progress.summary(),
+                format_duration(elapsed)
+            )),
+            status,
+        ]);
+        frame.render_widget(
+            Paragraph::new(summary).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" git-author-rewrite "),
+            ),
+            header,
+        );
+
+        let ratio = if total == 0 {
+            0.0
+        } else {
+            done as f64 / total as f64
+        };
+        frame.render_widget(
+            Gauge::default()
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(ratio),
+            gauge,
+        );
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|r| {
+                let (mark, style) = match r.state {
+                    RowState::Done if r.marked => ("✔", Style::default().fg(Color::Green)),
+                    RowState::Done => ("·", Style::default().fg(Color::DarkGray)),
+                    RowState::Current => ("▶", Style::default().add_modifier(Modifier::BOLD)),
+                    RowState::Pending => (" ", Style::default()),
+                };
+                ListItem::new(format!("{} {} {}", mark, r.sha, r.subject)).style(style)
+            })
+            .collect();
+        let mut state = ListState::default();
+        state.select(self.rows.iter().position(|r| r.state != RowState::Done));
+        frame.render_stateful_widget(
+            List::new(items).block(Block::default().borders(Borders::ALL).title(" Commits ")),
+            commits,
+            &mut state,
+        );
+
+        let visible = log.height.saturating_sub(2) as usize;
+        let start = self.log.len().saturating_sub(visible);
+        let lines: Vec<Line> = self.log[start..]
+            .iter()
+            .map(|l| Line::raw(l.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Log ")),
+            log,
+        );
+
+        frame.render_widget(
+            Paragraph::new("p pause/resume · a abort the rebase")
+                .style(Style::default().fg(Color::DarkGray)),
+            keys,
+        );
+    }
+}
+
+/// A key the dashboard reacts to.
+enum Key {
+    Pause,
+    Abort,
+}
+
+/// Waits up to `timeout` for a key press.
+fn poll_key(timeout: Duration) -> Result<Option<Key>, String> {
+    match event::poll(timeout) {
+        Ok(true) => {}
+        Ok(false) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    }
+    let key = match event::read() {
+        Ok(Event::Key(k)) if k.kind == KeyEventKind::Press => k,
+        Ok(_) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    match key.code {
+        KeyCode::Char('p') | KeyCode::Char(' ') => Ok(Some(Key::Pause)),
+        KeyCode::Char('a') => Ok(Some(Key::Abort)),
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Ok(Some(Key::Abort)),
+        _ => Ok(None),
+    }
+}
+
+/// Runs the rebase loop behind a full-screen dashboard (`--tui`).
+///
+/// Each call of `step` handles one rebase stop (amend, then continue) and
+/// returns the text for the log pane, one entry per line. Between steps the
+/// dashboard redraws and reads keys: `p` pauses or resumes, `a` (or Ctrl-C)
+/// aborts the rebase.
+///
+/// # Parameters
+///
+/// * `repo` – The repository being rebased, for the rebase's progress files.
+/// * `step` – Handles the current stop; Git's own output must be captured
+///   (see [`CommitOptions::quiet`](crate::git::CommitOptions::quiet)).
+///
+/// # Returns
+///
+/// How the run ended; the terminal is restored in every case.
+///
+/// # Examples
+///
+/// ```no_run
+/// use git_author_rewrite::{git::Repo, tui::{self, Outcome}};
+///
+/// let repo = Repo::discover().unwrap();
+/// let outcome = tui::run(&repo, || Ok(String::from("amended")));
+/// if let Outcome::Failed(e) = outcome {
+///     eprintln!("{}", e);
+/// }
+/// ```
+pub fn run<F>(repo: &Repo, mut step: F) -> Outcome
+where
+    F: FnMut() -> Result<String, String>,
+{
+    let mut terminal = match ratatui::try_init() {
+        Ok(t) => t,
+        Err(e) => return Outcome::Failed(format!("cannot start the dashboard: {}", e)),
+    };
+    let mut dash = Dashboard {
+        rows: Vec::new(),
+        log: Vec::new(),
+        started: Instant::now(),
+        progress: Progress::new(progress::total_stops(repo.git_dir())),
+        paused: false,
+    };
+
+    let outcome = loop {
+        dash.refresh(repo.git_dir());
+        if let Err(e) = terminal.draw(|f| dash.draw(f)) {
+            break Outcome::Failed(format!("cannot draw the dashboard: {}", e));
+        }
+
+        let timeout = if dash.paused {
+            Duration::from_millis(250)
+        } else {
+            Duration::ZERO
+        };
+        match poll_key(timeout) {
+            Ok(Some(Key::Pause)) => {
+                dash.paused = !dash.paused;
+                let note = if dash.paused { "Paused." } else { "Resumed." };
+                dash.log(String::from(note));
+                continue;
+            }
+            Ok(Some(Key::Abort)) => match repo.rebase_abort() {
+                Ok(_) => break Outcome::Aborted,
+                Err(e) => break Outcome::Failed(e),
+            },
+            Ok(None) => {}
+            Err(e) => break Outcome::Failed(format!("cannot read the keyboard: {}", e)),
+        }
+        if dash.paused {
+            continue;
+        }
+
+        if !repo.rebase_in_progress() {
+            break Outcome::Finished {
+                amended: dash.progress.completed(),
+                elapsed: dash.started.elapsed(),
+            };
+        }
+        let began = Instant::now();
+        match step() {
+            Ok(text) => {
+                dash.progress.record(began.elapsed());
+                for line in text.lines() {
+                    dash.log(line.to_string());
+                }
+            }
+            Err(e) => break Outcome::Failed(e),
+        }
+    };
+
+    ratatui::restore();
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rows, RowState};
+
+    #[test]
+    fn rows_mark_done_current_and_pending() {
+        let done = "pick aaa First\nedit bbb Second\n";
+        let todo = "# comment\nedit ccc Third\nexec make\n";
+        let r = rows(done, todo, true);
+        let states: Vec<RowState> = r.iter().map(|r| r.state).collect();
+        assert_eq!(
+            states,
+            vec![RowState::Done, RowState::Current, RowState::Pending]
+        );
+        assert_eq!(r[1].sha, "bbb");
+        assert_eq!(r[1].subject, "Second");
+        assert!(!r[0].marked && r[2].marked);
+    }
+}
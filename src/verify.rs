@@ -0,0 +1,226 @@
+use crate::{
+    git::{Commit, Repo},
+    identity::Identity,
+    selection,
+};
+
+/// A rule the author and committer of every checked commit must follow:
+/// an [`Expectation`] for `verify`, a [`crate::policy::Policy`] for `check`.
+pub trait Rule {
+    /// Lists why `id` breaks the rule, empty if it complies.
+    fn violations(&self, id: &Identity) -> Vec<String>;
+}
+
+/// The identity every commit is expected to have after a rewrite.
+///
+/// Fields left as `None` are not checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expectation {
+    /// The expected name.
+    pub name: Option<String>,
+    /// The expected email, compared case-insensitively.
+    pub email: Option<String>,
+}
+
+impl Expectation {
+    /// Reports whether `id` satisfies the expectation.
+    pub fn matches(&self, id: &Identity) -> bool {
+        let name_ok = match &self.name {
+            Some(n) => id.name == *n,
+            None => true,
+        };
+        let email_ok = match &self.email {
+            Some(e) => id.email.eq_ignore_ascii_case(e),
+            None => true,
+        };
+        name_ok && email_ok
+    }
+}
+
+impl Rule for Expectation {
+    fn violations(&self, id: &Identity) -> Vec<String> {
+        if self.matches(id) {
+            Vec::new()
+        } else {
+            vec![String::from("not the expected identity")]
+        }
+    }
+}
+
+/// A commit whose author or committer breaks a [`Rule`], once per reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The full commit SHA.
+    pub sha: String,
+    /// `"author"` or `"committer"`.
+    pub role: &'static str,
+    /// The identity found on the commit.
+    pub found: Identity,
+    /// Why the identity breaks the rule.
+    pub reason: String,
+}
+
+/// The outcome of checking a range of history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Number of commits checked.
+    pub checked: usize,
+    /// Every mismatch found, newest commit first.
+    pub violations: Vec<Violation>,
+}
+
+/// Checks the author and committer of each of `commits` against `rule`.
+pub fn check_commits<I, R>(commits: I, rule: &R) -> Report
+where
+    I: IntoIterator<Item = Commit>,
+    R: Rule + ?Sized,
+{
+    let mut report = Report {
+        checked: 0,
+        violations: Vec::new(),
+    };
+    for commit in commits {
+        check_commit(&mut report, commit, rule);
+    }
+    report
+}
+
+/// Adds `commit` to `report`: counts it and records its violations.
+fn check_commit<R: Rule + ?Sized>(report: &mut Report, commit: Commit, rule: &R) {
+    report.checked += 1;
+    for (role, id) in [("author", commit.author), ("committer", commit.committer)] {
+        for reason in rule.violations(&id) {
+            report.violations.push(Violation {
+                sha: commit.sha.clone(),
+                role,
+                found: id.clone(),
+                reason,
+            });
+        }
+    }
+}
+
+/// Checks every commit selected by `revs` (default `HEAD`) against `rule`.
+///
+/// # Parameters
+///
+/// * `repo` – The repository to check.
+/// * `rule` – What the identities must satisfy, e.g. an [`Expectation`].
+/// * `revs` – A `git rev-list` expression, as for `--revs`.
+///
+/// # Returns
+///
+/// * `Ok(Report)` with the commits checked and any mismatches.
+/// * `Err(String)` if the history could not be read.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Ignored because it requires a Git repository.
+/// use git_author_rewrite::{git::Repo, verify::{verify, Expectation}};
+///
+/// let repo = Repo::discover().unwrap();
+/// let expect = Expectation { name: None, email: Some(String::from("jane@corp.com")) };
+/// let report = verify(&repo, &expect, None).unwrap();
+/// assert!(report.violations.is_empty());
+/// ```
+pub fn verify<R: Rule + ?Sized>(
+    repo: &Repo,
+    rule: &R,
+    revs: Option<&str>,
+) -> Result<Report, String> {
+    let args = match revs {
+        Some(expr) => selection::split_revs(expr),
+        None => vec![String::from("HEAD")],
+    };
+    let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    // Streamed, so only the violations are kept in memory.
+    let mut report = Report {
+        checked: 0,
+        violations: Vec::new(),
+    };
+    match repo.each_commit(&arg_refs, |commit| check_commit(&mut report, commit, rule)) {
+        Ok(()) => Ok(report),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_commits, Expectation, Violation};
+    use crate::git::Commit;
+    use crate::identity::Identity;
+
+    fn commit(sha: &str, author: Identity, committer: Identity) -> Commit {
+        Commit {
+            sha: sha.to_string(),
+            author,
+            committer,
+            author_time: 0,
+            commit_time: 0,
+            subject: String::new(),
+        }
+    }
+
+    fn expect_email(email: &str) -> Expectation {
+        Expectation {
+            name: None,
+            email: Some(email.to_string()),
+        }
+    }
+
+    #[test]
+    fn matches_compares_email_case_insensitively() {
+        let expect = expect_email("Jane@Corp.com");
+        assert!(expect.matches(&Identity::new("Anyone", "jane@corp.com")));
+        assert!(!expect.matches(&Identity::new("Jane", "jane@old.com")));
+    }
+
+    #[test]
+    fn matches_checks_name_exactly() {
+        let expect = Expectation {
+            name: Some(String::from("Jane Doe")),
+            email: None,
+        };
+        assert!(expect.matches(&Identity::new("Jane Doe", "x@y")));
+        assert!(!expect.matches(&Identity::new("jane doe", "x@y")));
+    }
+
+    #[test]
+    fn check_commits_reports_author_and_committer_mismatches() {
+        let jane = Identity::new("Jane", "jane@corp.com");
+        let commits = vec![
+            commit("b", jane.clone(), Identity::new("Bot", "bot@ci")),
+            commit("a", Identity::new("Old", "old@x.com"), jane),
+        ];
+        let report = check_commits(commits, &expect_email("jane@corp.com"));
+        assert_eq!(report.checked, 2);
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation {
+                    sha: String::from("b"),
+                    role: "committer",
+                    found: Identity::new("Bot", "bot@ci"),
+                    reason: String::from("not the expected identity"),
+                },
+                Violation {
+                    sha: String::from("a"),
+                    role: "author",
+                    found: Identity::new("Old", "old@x.com"),
+                    reason: String::from("not the expected identity"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn check_commits_of_clean_history_is_empty() {
+        let jane = Identity::new("Jane", "jane@corp.com");
+        let commits = vec![commit("a", jane.clone(), jane)];
+        assert!(check_commits(commits, &expect_email("jane@corp.com"))
+            .violations
+            .is_empty());
+    }
+}
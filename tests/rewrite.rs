@@ -0,0 +1,275 @@
+//! End-to-end runs of the binary on repositories built with
+//! `test_util::TempRepo`; needs the `test-util` feature.
+
+use git_author_rewrite::{exit::EXIT_NOTHING_TO_DO, identity::Identity, test_util::TempRepo};
+
+use std::process::{Command, Output, Stdio};
+
+fn rewrite(repo: &TempRepo, extra: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_git-author-rewrite"))
+        .args(["--name", "New Name", "--email", "new@example.com"])
+        .args(["--no-verify", "--no-gpg-sign", "--yes"])
+        .args(extra)
+        .current_dir(repo.path())
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn rebase_engine_rewrites_every_commit() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .commit_as("two", "Someone Else", "else@example.com")
+        .commit("three")
+        .build()
+        .unwrap();
+
+    let out = rewrite(&repo, &["--engine", "rebase"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let new = Identity::new("New Name", "new@example.com");
+    assert_eq!(repo.authors("HEAD").unwrap(), vec![new.clone(); 3]);
+    assert_eq!(repo.committers("HEAD").unwrap(), vec![new; 3]);
+}
+
+#[test]
+fn metadata_engine_keeps_every_tree() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .branch("feature")
+        .commit("two")
+        .checkout("main")
+        .commit("three")
+        .merge("feature", "Merge feature")
+        .build()
+        .unwrap();
+    let trees = repo.git(&["log", "--format=%T", "main"]).unwrap();
+
+    let out = rewrite(&repo, &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let new = Identity::new("New Name", "new@example.com");
+    assert_eq!(repo.authors("main").unwrap(), vec![new; 4]);
+    assert_eq!(repo.git(&["log", "--format=%T", "main"]).unwrap(), trees);
+    assert!(repo.git(&["status", "--porcelain"]).unwrap().is_empty());
+}
+
+#[test]
+fn gix_engine_keeps_merges() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .branch("feature")
+        .commit("two")
+        .checkout("main")
+        .commit("three")
+        .merge("feature", "Merge feature")
+        .build()
+        .unwrap();
+
+    let out = rewrite(&repo, &["--engine", "gix"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let new = Identity::new("New Name", "new@example.com");
+    assert_eq!(repo.authors("main").unwrap(), vec![new; 4]);
+    let merges = repo.git(&["rev-list", "--merges", "main"]).unwrap();
+    assert_eq!(merges.lines().count(), 1);
+}
+
+#[test]
+fn all_branches_share_the_rewritten_history() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .commit("two")
+        .branch("feature")
+        .commit("feature work")
+        .checkout("main")
+        .commit("three")
+        .build()
+        .unwrap();
+
+    let out = rewrite(&repo, &["--all-branches"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let new = Identity::new("New Name", "new@example.com");
+    assert_eq!(repo.authors("main").unwrap(), vec![new.clone(); 3]);
+    assert_eq!(repo.authors("feature").unwrap(), vec![new; 3]);
+    // The shared commits were rewritten once, for both branches.
+    let base = repo.git(&["merge-base", "main", "feature"]).unwrap();
+    assert_eq!(base, repo.git(&["rev-parse", "main~1"]).unwrap());
+    assert_eq!(base, repo.git(&["rev-parse", "feature~1"]).unwrap());
+    assert!(repo.git(&["status", "--porcelain"]).unwrap().is_empty());
+}
+
+#[test]
+fn skip_merges_keeps_merge_identities() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .branch("feature")
+        .commit("two")
+        .checkout("main")
+        .commit("three")
+        .merge("feature", "Merge feature")
+        .build()
+        .unwrap();
+
+    let out = rewrite(&repo, &["--engine", "gix", "--skip-merges"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let merge = repo.git(&["log", "-1", "--format=%an", "main"]).unwrap();
+    assert_eq!(merge.trim(), "Old Name");
+    let rest = repo
+        .git(&["log", "--no-merges", "--format=%an", "main"])
+        .unwrap();
+    assert_eq!(rest.lines().collect::<Vec<_>>(), vec!["New Name"; 3]);
+}
+
+#[test]
+fn sync_committer_alone_copies_each_author() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .commit_as("two", "Someone Else", "else@example.com")
+        .build()
+        .unwrap();
+    let bot = ["-c", "user.name=Bot", "-c", "user.email=bot@ci"];
+    repo.git(&[&bot[..], &["commit", "--amend", "--no-edit"]].concat())
+        .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_git-author-rewrite"))
+        .args(["--sync-committer", "--no-verify", "--no-gpg-sign", "--yes"])
+        .current_dir(repo.path())
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let authors = repo.authors("HEAD").unwrap();
+    assert_eq!(
+        authors[1],
+        Identity::new("Someone Else", "else@example.com")
+    );
+    assert_eq!(repo.committers("HEAD").unwrap(), authors);
+}
+
+#[test]
+fn stdout_only_carries_the_report() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .commit("two")
+        .build()
+        .unwrap();
+
+    let out = rewrite(&repo, &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(
+        stdout.trim().lines().next(),
+        Some("Authors before and after the rewrite:"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("New Name <new@example.com>"), "{}", stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Pre-flight checks:"), "{}", stderr);
+    assert!(stderr.contains("Rewrote 2 commits;"), "{}", stderr);
+}
+
+#[test]
+fn quiet_run_prints_nothing_on_success() {
+    let repo = TempRepo::builder()
+        .author("Old Name", "old@example.com")
+        .commit("one")
+        .commit("two")
+        .build()
+        .unwrap();
+
+    let out = rewrite(&repo, &["--quiet"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(
+        out.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    assert!(
+        out.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let new = Identity::new("New Name", "new@example.com");
+    assert_eq!(repo.authors("HEAD").unwrap(), vec![new; 2]);
+}
+
+#[test]
+fn repository_without_commits_has_nothing_to_rewrite() {
+    let repo = TempRepo::builder().build().unwrap();
+
+    let out = rewrite(&repo, &["--engine", "rebase"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(out.status.code(), Some(EXIT_NOTHING_TO_DO), "{}", stderr);
+    assert!(
+        stderr.contains("Nothing to rewrite: this repository has no commits yet."),
+        "{}",
+        stderr
+    );
+    assert!(out.stdout.is_empty());
+    assert!(!repo.path().join(".git/rebase-merge").exists());
+    assert!(repo.head().is_err());
+}
+
+#[test]
+fn missing_flags_without_a_terminal_fail_before_the_checks() {
+    let repo = TempRepo::builder().commit("one").build().unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_git-author-rewrite"))
+        .args(["--email", "new@example.com"])
+        .current_dir(repo.path())
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(out.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("stdin is not a terminal"), "{}", stderr);
+    assert!(stderr.contains("--name <NAME>"), "{}", stderr);
+    assert!(stderr.contains("--yes"), "{}", stderr);
+    assert!(!stderr.contains("Pre-flight checks:"), "{}", stderr);
+}